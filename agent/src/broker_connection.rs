@@ -2,36 +2,226 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
 use broker_api::{
-    AgentToBrokerMessage, AgentToBrokerMessageCompat, BrokerToAgentMessage,
-    BrokerToAgentMessageCompat,
+    AgentConnectionStatus, AgentToBrokerMessage, AgentToBrokerMessageCompat,
+    BrokerToAgentMessage, BrokerToAgentMessageCompat,
 };
+use chrono::Utc;
 use rpc::{AsyncDuplex, AsyncRequest, AsyncResponse, GenericValue};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 
+/// Backoff between attempts to connect to the broker. Delays are
+/// shortened by a random fraction of up to `jitter`, so that agents
+/// do not all reconnect at once after a broker restart.
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Between 0 (no jitter) and 1.
+    pub jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(300),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before the given attempt (starting from 1), given a
+    /// random number in [0, 1).
+    pub fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let exp = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.initial_delay.as_secs_f64()
+            * self.multiplier.max(1.0).powi(exp))
+        .min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0);
+        Duration::from_secs_f64(delay * (1.0 - jitter))
+    }
+}
+
+/// Start dialing the broker for the broker client, and return the
+/// local address the client should connect to instead.
+///
+/// `AsyncBrokerClient` reconnects on its own, without backoff. Its
+/// connections are accepted on a local listener instead; for each one,
+/// the broker is dialed until connecting succeeds, waiting between
+/// attempts as set by the policy, and the connection is forwarded.
+/// The broker thus only sees attempts paced by the policy. TLS is
+/// still negotiated end to end, with the broker's domain name.
+pub async fn dial_broker(
+    addr: String,
+    policy: ReconnectPolicy,
+    status: BrokerStatus,
+) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let local_addr = listener.local_addr()?;
+    tokio::spawn(forward_connections(listener, addr, policy, status));
+    Ok(local_addr)
+}
+
+async fn forward_connections(
+    listener: TcpListener,
+    addr: String,
+    policy: ReconnectPolicy,
+    status: BrokerStatus,
+) {
+    /* Failed attempts since the last lasting connection. A lost
+     * connection counts as one, so that agents disconnected by a
+     * broker restart do not all reconnect at once. */
+    let mut failures = 0;
+    loop {
+        let mut client = match listener.accept().await {
+            Ok((client, _)) => client,
+            Err(e) => {
+                log::warn!("failed to accept broker client connection: {e}");
+                continue;
+            }
+        };
+
+        let mut broker = loop {
+            if failures > 0 {
+                let delay = policy.delay(failures, rand::random());
+                status.reconnecting(failures, delay);
+                tokio::time::sleep(delay).await;
+            }
+            log::info!("connecting to broker at {addr}");
+            match TcpStream::connect(&addr).await {
+                Ok(broker) => break broker,
+                Err(e) => {
+                    log::info!("failed to connect to broker: {e}");
+                    failures = failures.saturating_add(1);
+                }
+            }
+        };
+
+        let connected = Instant::now();
+        if let Err(e) =
+            tokio::io::copy_bidirectional(&mut client, &mut broker).await
+        {
+            log::debug!("broker connection closed: {e}");
+        }
+        status.disconnected();
+        failures = match connected.elapsed() >= policy.max_delay {
+            true => 1,
+            false => failures.saturating_add(1),
+        };
+    }
+}
+
+/// The state of the broker connection, as far as the agent can tell.
+/// A message from the broker shows that it is connected, a failed
+/// send or a lost connection that it is not. While waiting to
+/// reconnect, the next attempt is reported. Until any of this
+/// happens, the state is unknown.
+#[derive(Clone, Default)]
+pub struct BrokerStatus(Arc<Mutex<Option<AgentConnectionStatus>>>);
+
+impl BrokerStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> Option<AgentConnectionStatus> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Record that a message was received from the broker.
+    pub fn connected(&self) {
+        let mut status = self.0.lock().unwrap();
+        if !matches!(*status, Some(AgentConnectionStatus::Connected { .. })) {
+            log::info!("connected to broker");
+            *status =
+                Some(AgentConnectionStatus::Connected { since: Utc::now() });
+        }
+    }
+
+    /// Record that a message could not be sent to the broker.
+    pub fn disconnected(&self) {
+        let mut status = self.0.lock().unwrap();
+        if !matches!(
+            *status,
+            Some(
+                AgentConnectionStatus::Disconnected { .. }
+                    | AgentConnectionStatus::Reconnecting { .. }
+            )
+        ) {
+            log::info!("broker not connected");
+            *status = Some(AgentConnectionStatus::Disconnected {
+                since: Utc::now(),
+                error: Some(String::from("not connected")),
+                next_try: None,
+            });
+        }
+    }
+
+    /// Record that the next attempt to connect to the broker is
+    /// delayed.
+    pub fn reconnecting(&self, attempt: u32, delay: Duration) {
+        log::info!(
+            "reconnecting to broker in {:.1}s (attempt {})",
+            delay.as_secs_f64(),
+            attempt
+        );
+        let mut status = self.0.lock().unwrap();
+        let now = Utc::now();
+        *status = Some(AgentConnectionStatus::Reconnecting {
+            since: match *status {
+                Some(
+                    AgentConnectionStatus::Disconnected { since, .. }
+                    | AgentConnectionStatus::Reconnecting { since, .. },
+                ) => since,
+                _ => now,
+            },
+            attempt,
+            next_try: now
+                + chrono::Duration::from_std(delay)
+                    .unwrap_or_else(|_| chrono::Duration::zero()),
+        });
+    }
+}
+
 pub fn broker_message_handler<V>(
     agent_sender: mpsc::Sender<AsyncRequest<V>>,
     db_sender: mpsc::Sender<AsyncResponse<V>>,
+    status: BrokerStatus,
 ) -> impl Fn(
     BrokerToAgentMessage<V>,
 ) -> std::result::Result<(), AgentToBrokerMessage<V>>
 where
     V: GenericValue,
 {
-    move |msg: BrokerToAgentMessage<V>| match msg {
-        BrokerToAgentMessage::Backend { message } => {
-            agent_sender.try_send(message).map_err(|e| {
-                log::warn!("failed to send agent request: {}", e);
-                AgentToBrokerMessage::Backend {
-                    message: AsyncResponse::<V>::from_send_error::<String>(e),
+    move |msg: BrokerToAgentMessage<V>| {
+        status.connected();
+        match msg {
+            BrokerToAgentMessage::Backend { message } => {
+                agent_sender.try_send(message).map_err(|e| {
+                    log::warn!("failed to send agent request: {}", e);
+                    AgentToBrokerMessage::Backend {
+                        message: AsyncResponse::<V>::from_send_error::<String>(
+                            e,
+                        ),
+                    }
+                })
+            }
+            BrokerToAgentMessage::MetricsEngine { message } => {
+                if let Err(e) = db_sender.try_send(message) {
+                    log::warn!("failed to send db response: {}", e);
                 }
-            })
-        }
-        BrokerToAgentMessage::MetricsEngine { message } => {
-            if let Err(e) = db_sender.try_send(message) {
-                log::warn!("failed to send db response: {}", e);
+                Ok(())
             }
-            Ok(())
         }
     }
 }
@@ -39,55 +229,65 @@ where
 pub fn broker_message_handler_compat<V>(
     agent_sender: mpsc::Sender<AsyncRequest<V>>,
     db_sender: mpsc::Sender<AsyncResponse<V>>,
+    status: BrokerStatus,
 ) -> impl Fn(
     BrokerToAgentMessageCompat<V>,
 ) -> std::result::Result<(), AgentToBrokerMessageCompat<V>>
 where
     V: GenericValue,
 {
-    move |msg: BrokerToAgentMessageCompat<V>| match msg {
-        BrokerToAgentMessageCompat::Backend {
-            message: AsyncDuplex::Request(message),
-        } => agent_sender.try_send(message).map_err(|e| {
-            log::warn!("failed to send agent request: {}", e);
-            AgentToBrokerMessageCompat::Backend {
-                message: AsyncDuplex::Response(
-                    AsyncResponse::<V>::from_send_error::<String>(e),
-                ),
+    move |msg: BrokerToAgentMessageCompat<V>| {
+        status.connected();
+        match msg {
+            BrokerToAgentMessageCompat::Backend {
+                message: AsyncDuplex::Request(message),
+            } => agent_sender.try_send(message).map_err(|e| {
+                log::warn!("failed to send agent request: {}", e);
+                AgentToBrokerMessageCompat::Backend {
+                    message: AsyncDuplex::Response(
+                        AsyncResponse::<V>::from_send_error::<String>(e),
+                    ),
+                }
+            }),
+            BrokerToAgentMessageCompat::Database {
+                message: AsyncDuplex::Response(message),
+            } => {
+                if let Err(e) = db_sender.try_send(message) {
+                    log::warn!("failed to send db response: {}", e);
+                }
+                Ok(())
             }
-        }),
-        BrokerToAgentMessageCompat::Database {
-            message: AsyncDuplex::Response(message),
-        } => {
-            if let Err(e) = db_sender.try_send(message) {
-                log::warn!("failed to send db response: {}", e);
+            _ => {
+                log::warn!("dropping unexpected message from broker.");
+                Ok(())
             }
-            Ok(())
-        }
-        _ => {
-            log::warn!("dropping unexpected message from broker.");
-            Ok(())
         }
     }
 }
 
 pub fn broker_unconnected_handler<V: GenericValue + Clone>(
     db_sender: mpsc::Sender<AsyncResponse<V>>,
+    status: BrokerStatus,
 ) -> impl Fn(AgentToBrokerMessage<V>) {
     let unconnected =
         V::serialize_from(Result::<(), &str>::Err("not connected!")).unwrap();
-    move |msg: AgentToBrokerMessage<V>| match msg {
-        AgentToBrokerMessage::Backend { message: _ } => {
-            log::warn!("failed to send response to backend: not connected!");
-        }
-        AgentToBrokerMessage::MetricsEngine {
-            message: AsyncRequest { req_id, request: _ },
-        } => {
-            if let Err(e) = db_sender.try_send(AsyncResponse {
-                req_id,
-                response: unconnected.clone(),
-            }) {
-                log::warn!("failed to send db response: {}", e);
+    move |msg: AgentToBrokerMessage<V>| {
+        status.disconnected();
+        match msg {
+            AgentToBrokerMessage::Backend { message: _ } => {
+                log::warn!(
+                    "failed to send response to backend: not connected!"
+                );
+            }
+            AgentToBrokerMessage::MetricsEngine {
+                message: AsyncRequest { req_id, request: _ },
+            } => {
+                if let Err(e) = db_sender.try_send(AsyncResponse {
+                    req_id,
+                    response: unconnected.clone(),
+                }) {
+                    log::warn!("failed to send db response: {}", e);
+                }
             }
         }
     }
@@ -95,25 +295,135 @@ pub fn broker_unconnected_handler<V: GenericValue + Clone>(
 
 pub fn broker_unconnected_handler_compat<V: GenericValue + Clone>(
     db_sender: mpsc::Sender<AsyncResponse<V>>,
+    status: BrokerStatus,
 ) -> impl Fn(AgentToBrokerMessageCompat<V>) {
     let unconnected =
         V::serialize_from(Result::<(), &str>::Err("not connected!")).unwrap();
-    move |msg: AgentToBrokerMessageCompat<V>| match msg {
-        AgentToBrokerMessageCompat::Backend { message: _ } => {
-            log::warn!("failed to send response to backend: not connected!");
-        }
-        AgentToBrokerMessageCompat::Database {
-            message: AsyncDuplex::Request(AsyncRequest { req_id, request: _ }),
-        } => {
-            if let Err(e) = db_sender.try_send(AsyncResponse {
-                req_id,
-                response: unconnected.clone(),
-            }) {
-                log::warn!("failed to send db response: {}", e);
+    move |msg: AgentToBrokerMessageCompat<V>| {
+        status.disconnected();
+        match msg {
+            AgentToBrokerMessageCompat::Backend { message: _ } => {
+                log::warn!(
+                    "failed to send response to backend: not connected!"
+                );
+            }
+            AgentToBrokerMessageCompat::Database {
+                message:
+                    AsyncDuplex::Request(AsyncRequest { req_id, request: _ }),
+            } => {
+                if let Err(e) = db_sender.try_send(AsyncResponse {
+                    req_id,
+                    response: unconnected.clone(),
+                }) {
+                    log::warn!("failed to send db response: {}", e);
+                }
+            }
+            _ => {
+                log::warn!("dropping unexpected message from broker");
             }
         }
-        _ => {
-            log::warn!("dropping unexpected message from broker");
-        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use broker_api::AgentConnectionStatus;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::{dial_broker, BrokerStatus, ReconnectPolicy};
+
+    #[test]
+    fn exponential_delay() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            multiplier: 3.0,
+            jitter: 0.0,
+        };
+        let delays = (1..=5)
+            .map(|attempt| policy.delay(attempt, 0.5).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, vec![1, 3, 9, 10, 10]);
+        assert_eq!(policy.delay(u32::MAX, 0.5), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn jitter() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_secs(8),
+            jitter: 0.5,
+            ..ReconnectPolicy::default()
+        };
+        assert_eq!(policy.delay(1, 0.0), Duration::from_secs(8));
+        assert_eq!(policy.delay(1, 0.5), Duration::from_secs(6));
+        assert!(policy.delay(1, 0.999) > Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn reconnect_with_backoff() {
+        let broker = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let broker_addr = broker.local_addr().unwrap();
+        let status = BrokerStatus::new();
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.0,
+        };
+        let local_addr =
+            dial_broker(broker_addr.to_string(), policy, status.clone())
+                .await
+                .unwrap();
+
+        /* Connections are forwarded to the broker. */
+        let mut client = TcpStream::connect(local_addr).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+        let (mut conn, _) = broker.accept().await.unwrap();
+        let mut buf = [0; 5];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        /* Once the broker is gone, attempts are delayed. */
+        drop(conn);
+        drop(broker);
+        assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+        drop(client);
+        let _client = TcpStream::connect(local_addr).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !matches!(
+                status.status(),
+                Some(AgentConnectionStatus::Reconnecting { attempt, .. })
+                    if attempt >= 2
+            ) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn status() {
+        let status = BrokerStatus::new();
+        assert_eq!(status.status(), None);
+
+        /* Further failures keep the time of the first one. */
+        status.disconnected();
+        let first = status.status();
+        status.disconnected();
+        assert!(matches!(
+            first,
+            Some(AgentConnectionStatus::Disconnected { .. })
+        ));
+        assert_eq!(status.status(), first);
+
+        status.connected();
+        assert!(matches!(
+            status.status(),
+            Some(AgentConnectionStatus::Connected { .. })
+        ));
     }
 }
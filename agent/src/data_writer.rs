@@ -0,0 +1,417 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Delivery of collected data to the metrics engine. Data that could
+//! not be written is queued and retried with exponential backoff, so
+//! that a restart of the metrics engine does not lose a collection
+//! cycle. The backoff is global: a failed table is moved to the back
+//! of the queue, so that a table the engine keeps rejecting does not
+//! hold up newer data, and the first success flushes the rest of the
+//! queue without delay.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+
+use agent_utils::FileLock;
+
+use crate::error::{Error, Result};
+
+/// Data for one table: management pack, table name and metrics.
+pub type Entry<T> = (String, String, T);
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, before data is dropped.
+    pub retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Timeout for a single attempt.
+    pub timeout: Duration,
+    /// Maximum number of queued tables. When full, the oldest data
+    /// is dropped.
+    pub queue_size: usize,
+}
+
+/// Optional disk backing for the queue: data still queued on
+/// shutdown is saved, and sent after the next start.
+pub struct Spool {
+    path: PathBuf,
+}
+
+struct Queue<T> {
+    entries: VecDeque<Queued<T>>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+struct Queued<T> {
+    entry: Entry<T>,
+    /// Arrival order; failed entries are requeued at the back.
+    seq: u64,
+    /// Failed attempts for this table.
+    failures: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            timeout: Duration::from_secs(10),
+            queue_size: 1000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry (starting from 1).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+impl Spool {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            path: dir.join("data_queue.json"),
+        }
+    }
+
+    /// Load and remove the saved queue, if any.
+    fn load<T: DeserializeOwned>(&self) -> Result<Vec<Entry<T>>> {
+        let _lock = FileLock::acquire(&self.path, FileLock::DEFAULT_TIMEOUT)?;
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(Error::IO(e)),
+        };
+        std::fs::remove_file(&self.path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    fn save<T: Serialize>(&self, entries: &VecDeque<Queued<T>>) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        /* The lock also covers the temporary file. */
+        let _lock = FileLock::acquire(&self.path, FileLock::DEFAULT_TIMEOUT)?;
+        let mut entries = entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|q| q.seq);
+        let entries = entries.into_iter().map(|q| &q.entry).collect::<Vec<_>>();
+        let tmp = self.path.with_extension("json.new");
+        std::fs::write(&tmp, serde_json::to_vec(&entries)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl<T> Queue<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_seq: 0,
+        }
+    }
+
+    /// Queue data, dropping the oldest entry if the queue is full.
+    fn push(&mut self, entry: Entry<T>) {
+        if self.entries.len() >= self.capacity {
+            let oldest =
+                self.entries.iter().enumerate().min_by_key(|(_, q)| q.seq);
+            if let Some((oldest, _)) = oldest {
+                let (_, table, _) = self.entries.remove(oldest).unwrap().entry;
+                eprintln!(
+                    "Warning: data queue full; dropping data for table {}",
+                    table
+                );
+            }
+        }
+        self.entries.push_back(Queued {
+            entry,
+            seq: self.next_seq,
+            failures: 0,
+        });
+        self.next_seq += 1;
+    }
+}
+
+/// Write data received on `receiver` using `send`, until termination
+/// is requested or the channel is closed.
+pub async fn run<T, F, Fut, R>(
+    mut receiver: mpsc::Receiver<Entry<T>>,
+    send: F,
+    policy: RetryPolicy,
+    spool: Option<Spool>,
+    mut term_receiver: watch::Receiver<bool>,
+) where
+    T: Clone + Serialize + DeserializeOwned,
+    F: Fn(String, String, T) -> Fut,
+    Fut: Future<Output = std::result::Result<R, String>>,
+{
+    let mut queue = Queue::new(policy.queue_size);
+    if let Some(spool) = &spool {
+        match spool.load() {
+            Ok(entries) => {
+                for entry in entries {
+                    queue.push(entry);
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: failed to load queued data: {}", e)
+            }
+        }
+    }
+
+    /* Consecutive failures, for the global backoff. */
+    let mut failures = 0;
+    let mut retry_at = None;
+    let mut closed = false;
+
+    while !*term_receiver.borrow() {
+        if closed && queue.entries.is_empty() {
+            break;
+        }
+
+        let due = retry_at.map_or(true, |t| t <= Instant::now());
+        if queue.entries.is_empty() || !due {
+            let wait = retry_at.filter(|_| !queue.entries.is_empty());
+            let wake = wait.unwrap_or_else(Instant::now);
+            tokio::select! {
+                data = receiver.recv(), if !closed => match data {
+                    Some(entry) => queue.push(entry),
+                    None => closed = true,
+                },
+                _ = term_receiver.changed() => {}
+                _ = tokio::time::sleep_until(wake), if wait.is_some() => {}
+            }
+            continue;
+        }
+
+        /* Queue everything received so far, so that a failed table
+         * is retried after the newer ones. */
+        while let Ok(entry) = receiver.try_recv() {
+            queue.push(entry);
+        }
+
+        let mut queued = queue.entries.pop_front().unwrap();
+        let (mp, table, data) = queued.entry.clone();
+        let attempt = send(mp, table.clone(), data);
+        let res = match tokio::time::timeout(policy.timeout, attempt).await {
+            Ok(res) => res,
+            Err(_) => Err(Error::Timeout.to_string()),
+        };
+
+        match res {
+            Ok(_) => {
+                failures = 0;
+                retry_at = None;
+            }
+            Err(e) => {
+                failures += 1;
+                queued.failures += 1;
+                let backoff = policy.backoff(failures);
+                retry_at = Some(Instant::now() + backoff);
+                if queued.failures > policy.retries {
+                    eprintln!(
+                        "Warning: dropping data for table {} after {} \
+                         attempts: {}",
+                        table, queued.failures, e
+                    );
+                } else {
+                    eprintln!(
+                        "Warning: failed to write data for table {} \
+                         (retry {}/{} in {:?} or later): {}",
+                        table, queued.failures, policy.retries, backoff, e
+                    );
+                    queue.entries.push_back(queued);
+                }
+            }
+        }
+    }
+
+    receiver.close();
+    while let Ok(entry) = receiver.try_recv() {
+        queue.push(entry);
+    }
+
+    if !queue.entries.is_empty() {
+        match &spool {
+            Some(spool) => match spool.save(&queue.entries) {
+                Ok(()) => {}
+                Err(e) => eprintln!(
+                    "Warning: failed to save {} queued tables: {}",
+                    queue.entries.len(),
+                    e
+                ),
+            },
+            None => eprintln!(
+                "Warning: dropping {} queued tables on shutdown",
+                queue.entries.len()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio::sync::{mpsc, watch};
+
+    use super::{run, RetryPolicy, Spool};
+
+    fn policy(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            timeout: Duration::from_secs(1),
+            queue_size: 10,
+        }
+    }
+
+    /// Run the writer on the given tables, failing the first
+    /// `failures` attempts and every attempt for `rejected`. Returns
+    /// the attempted and written tables.
+    async fn write(
+        tables: &[&str],
+        failures: usize,
+        rejected: Option<&str>,
+        policy: RetryPolicy,
+        spool: Option<Spool>,
+    ) -> (Vec<String>, Vec<String>) {
+        let (sender, receiver) = mpsc::channel(10);
+        let (_term_sender, term_receiver) = watch::channel(false);
+        for table in tables {
+            sender
+                .send((String::from("mp"), table.to_string(), 1u32))
+                .await
+                .unwrap();
+        }
+        drop(sender);
+
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let written = Arc::new(Mutex::new(Vec::new()));
+        run(
+            receiver,
+            |_mp, table: String, _data| {
+                let attempts = attempts.clone();
+                let written = written.clone();
+                async move {
+                    let mut attempts = attempts.lock().unwrap();
+                    attempts.push(table.clone());
+                    let reject = rejected == Some(table.as_str());
+                    match attempts.len() > failures && !reject {
+                        true => {
+                            written.lock().unwrap().push(table);
+                            Ok(())
+                        }
+                        false => Err(String::from("metrics engine down")),
+                    }
+                }
+            },
+            policy,
+            spool,
+            term_receiver,
+        )
+        .await;
+
+        let attempts = attempts.lock().unwrap().clone();
+        let written = written.lock().unwrap().clone();
+        (attempts, written)
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(8));
+        assert_eq!(policy.backoff(5), Duration::from_secs(10));
+        assert_eq!(policy.backoff(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn retry_transient_failure() {
+        let (attempts, written) =
+            write(&["a", "b"], 2, None, policy(3), None).await;
+        assert_eq!(attempts, vec!["a", "b", "a", "b"]);
+        assert_eq!(written, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn drop_after_retries() {
+        /* "a" fails on all three attempts; "b" is written. */
+        let (attempts, written) =
+            write(&["a", "b"], 0, Some("a"), policy(2), None).await;
+        assert_eq!(attempts, vec!["a", "b", "a", "a"]);
+        assert_eq!(written, vec!["b"]);
+    }
+
+    #[tokio::test]
+    async fn rejected_table_does_not_block() {
+        /* Newer tables are written while "a" is retried. */
+        let (attempts, written) =
+            write(&["a", "b", "c"], 0, Some("a"), policy(1), None).await;
+        assert_eq!(attempts, vec!["a", "b", "c", "a"]);
+        assert_eq!(written, vec!["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn spool_on_shutdown() {
+        let dir = std::env::temp_dir()
+            .join(format!("agent_data_writer_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        /* Terminate while the writer is backing off. */
+        let (sender, receiver) = mpsc::channel(10);
+        let (term_sender, term_receiver) = watch::channel(false);
+        sender
+            .send((String::from("mp"), String::from("a"), 1u32))
+            .await
+            .unwrap();
+        let writer = tokio::spawn(run(
+            receiver,
+            |_mp, _table, _data: u32| async {
+                Err::<(), _>(String::from("metrics engine down"))
+            },
+            RetryPolicy {
+                initial_backoff: Duration::from_secs(60),
+                max_backoff: Duration::from_secs(60),
+                ..policy(3)
+            },
+            Some(Spool::new(dir.clone())),
+            term_receiver,
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        term_sender.send(true).unwrap();
+        writer.await.unwrap();
+        assert!(dir.join("data_queue.json").exists());
+
+        /* The saved data is sent after the next start. */
+        let (attempts, written) =
+            write(&["b"], 0, None, policy(3), Some(Spool::new(dir.clone())))
+                .await;
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(written, vec!["a", "b"]);
+        assert!(!dir.join("data_queue.json").exists());
+    }
+}
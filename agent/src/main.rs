@@ -7,15 +7,20 @@ pub mod error;
 #[macro_use]
 pub mod context;
 mod broker_connection;
+mod data_writer;
+mod readiness;
+mod shutdown;
+mod status;
 
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{path::PathBuf, process};
 
 use agent_utils::KeyVault;
 use clap::{App, Arg};
 use dbschema::Timestamped;
-use futures::Future;
+use futures::{Future, FutureExt};
 use metrics_types::{Data, MetricsTable};
 use tokio::{
     signal::unix::{signal, SignalKind},
@@ -32,10 +37,12 @@ use rpc::{
 use agent_service::AgentService;
 //use backend_connector::{BackendConnector, BackendConnectorEvent};
 use etc::EtcManager;
-use protocol::PluginManager;
+use etc_base::Protocol;
+use protocol::{PluginManager, RetryPolicy, ValidationPolicy};
 use scheduler::Scheduler;
 
-use error::{Error, Result};
+use error::Result;
+use readiness::{Readiness, Stage};
 
 #[tokio::main]
 async fn main() {
@@ -66,6 +73,20 @@ async fn main() {
 			 .multiple(true)
 			 .help("Ignore log output from specific module(s)")
 		)
+        .arg(Arg::with_name("log-format")
+			 .long("log-format")
+			 .takes_value(true)
+			 .possible_values(&["plain", "json"])
+			 .default_value("plain")
+			 .help("Log output format: plain text or JSON lines")
+		)
+        .arg(Arg::with_name("log-module")
+			 .long("log-module")
+			 .takes_value(true)
+			 .multiple(true)
+			 .help("Override the log level for a module (MODULE=LEVEL). \
+					Can be changed at runtime on the status endpoint.")
+		)
         .arg(
             Arg::with_name("connect")
                 .long("connect")
@@ -108,23 +129,181 @@ async fn main() {
                 .takes_value(true)
                 .help("The private key of the agent."),
         )
+        .arg(
+            Arg::with_name("status")
+                .long("status")
+                .help("Serve a local HTTP status endpoint."),
+        )
+        .arg(
+            Arg::with_name("status-listen")
+                .long("status-listen")
+                .takes_value(true)
+                .requires("status")
+                .help("Listen address for the status endpoint (default: 127.0.0.1:9998)."),
+        )
+        .arg(
+            Arg::with_name("shutdown-timeout")
+                .long("shutdown-timeout")
+                .takes_value(true)
+                .help("Seconds to wait for open connections on shutdown before \
+                       forcing it (default: 30; 0 waits indefinitely)."),
+        )
+        .arg(
+            Arg::with_name("data-retries")
+                .long("data-retries")
+                .takes_value(true)
+                .help("Number of times to retry writing data to the metrics \
+                       engine before dropping it (default: 5)."),
+        )
+        .arg(
+            Arg::with_name("data-spool")
+                .long("data-spool")
+                .takes_value(true)
+                .help("Directory to save data that could not be written to \
+                       the metrics engine on shutdown, to be sent after the \
+                       next start."),
+        )
+        .arg(
+            Arg::with_name("reconnect-delay")
+                .long("reconnect-delay")
+                .takes_value(true)
+                .help("Seconds to wait before the first attempt to reconnect \
+                       to the broker (default: 1)."),
+        )
+        .arg(
+            Arg::with_name("reconnect-max-delay")
+                .long("reconnect-max-delay")
+                .takes_value(true)
+                .help("Maximum seconds between attempts to reconnect to the \
+                       broker (default: 300)."),
+        )
+        .arg(
+            Arg::with_name("reconnect-multiplier")
+                .long("reconnect-multiplier")
+                .takes_value(true)
+                .help("Factor by which the reconnect delay grows after each \
+                       failed attempt (default: 2)."),
+        )
+        .arg(
+            Arg::with_name("reconnect-jitter")
+                .long("reconnect-jitter")
+                .takes_value(true)
+                .help("Fraction (0-1) by which reconnect delays are randomly \
+                       shortened, to spread reconnects after a broker \
+                       restart (default: 0.5)."),
+        )
+        .arg(
+            Arg::with_name("exec-allow")
+                .long("exec-allow")
+                .takes_value(true)
+                .multiple(true)
+                .help("Allow the exec protocol to run this program (absolute \
+                       path). Can be specified multiple times."),
+        )
+        .arg(
+            Arg::with_name("table-timing")
+                .long("table-timing")
+                .help("Request each data table separately, so that the time \
+                       spent fetching it can be logged (at debug level). This \
+                       disables batching across tables; meant for \
+                       troubleshooting slow hosts."),
+        )
+        .arg(
+            Arg::with_name("retry-attempts")
+                .long("retry-attempts")
+                .takes_value(true)
+                .help("Number of times a failed protocol request is attempted, \
+                       for protocols that only read data (default: 1)."),
+        )
+        .arg(
+            Arg::with_name("validate-output")
+                .long("validate-output")
+                .takes_value(true)
+                .multiple(true)
+                .help("Check the output of a protocol's plugin against the \
+                       data table specs (PROTOCOL=off|warn|coerce|strict). \
+                       Can be specified multiple times (default: off)."),
+        )
         .get_matches();
 
+    let shutdown_timeout = match matches.value_of("shutdown-timeout") {
+        None => Some(shutdown::DEFAULT_TIMEOUT),
+        Some(val) => match val.parse::<u64>() {
+            Ok(0) => None,
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(_) => {
+                eprintln!("Error: invalid shutdown timeout: {}", val);
+                process::exit(1);
+            }
+        },
+    };
+
+    let data_retry_policy = match matches.value_of("data-retries") {
+        None => data_writer::RetryPolicy::default(),
+        Some(val) => match val.parse::<u32>() {
+            Ok(retries) => data_writer::RetryPolicy {
+                retries,
+                ..data_writer::RetryPolicy::default()
+            },
+            Err(_) => {
+                eprintln!("Error: invalid number of data retries: {}", val);
+                process::exit(1);
+            }
+        },
+    };
+    let data_spool = matches
+        .value_of("data-spool")
+        .map(|dir| data_writer::Spool::new(PathBuf::from(dir)));
+
+    let reconnect_policy = {
+        let arg = |name: &str, default: f64| match matches.value_of(name) {
+            None => default,
+            Some(val) => match val.parse::<f64>() {
+                Ok(val) if val.is_finite() && val >= 0.0 => val,
+                _ => {
+                    eprintln!("Error: invalid value for {}: {}", name, val);
+                    process::exit(1);
+                }
+            },
+        };
+        let default = broker_connection::ReconnectPolicy::default();
+        broker_connection::ReconnectPolicy {
+            initial_delay: Duration::from_secs_f64(arg(
+                "reconnect-delay",
+                default.initial_delay.as_secs_f64(),
+            )),
+            max_delay: Duration::from_secs_f64(arg(
+                "reconnect-max-delay",
+                default.max_delay.as_secs_f64(),
+            )),
+            multiplier: arg("reconnect-multiplier", default.multiplier),
+            jitter: arg("reconnect-jitter", default.jitter),
+        }
+    };
+    let broker_status = broker_connection::BrokerStatus::new();
+
+    let log_format = match matches.value_of("log-format") {
+        Some("json") => logger::LogFormat::Json,
+        _ => logger::LogFormat::Plain,
+    };
     let mut log_config = simplelog::ConfigBuilder::new();
+    let mut json_logger = logger::JsonLogger::stderr();
 
     if let Some(vals) = matches.values_of("log-allow-module") {
         for module in vals {
             log_config.add_filter_allow(module.to_string());
+            json_logger.add_filter_allow(module.to_string());
         }
     }
 
     if let Some(vals) = matches.values_of("log-ignore-module") {
         for module in vals {
             log_config.add_filter_ignore(module.to_string());
+            json_logger.add_filter_ignore(module.to_string());
         }
     }
 
-    if let Err(e) = simplelog::TermLogger::init(
+    let log_filter = Arc::new(logger::ModuleFilter::new(
         match matches.occurrences_of("verbose") {
             0 => simplelog::LevelFilter::Off,
             1 => simplelog::LevelFilter::Error,
@@ -133,10 +312,43 @@ async fn main() {
             4 => simplelog::LevelFilter::Debug,
             5.. => simplelog::LevelFilter::Trace,
         },
-        log_config.build(),
-        simplelog::TerminalMode::Stderr,
-        simplelog::ColorChoice::Auto,
-    ) {
+    ));
+
+    if let Some(vals) = matches.values_of("log-module") {
+        for val in vals {
+            match val.split_once('=').and_then(|(module, level)| {
+                Some((module, level.parse().ok()?))
+            }) {
+                Some((module, level)) => {
+                    log_filter.set_override(module, Some(level))
+                }
+                None => {
+                    eprintln!("Error: invalid log module override: {}", val);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    /* The filter decides on the level; the terminal or JSON logger
+     * only applies the allow / ignore lists. */
+    logger::set_format(log_format);
+    let log_init = match log_format {
+        logger::LogFormat::Plain => logger::FilteredLogger::new(
+            log_filter.clone(),
+            simplelog::TermLogger::new(
+                simplelog::LevelFilter::Trace,
+                log_config.build(),
+                simplelog::TerminalMode::Stderr,
+                simplelog::ColorChoice::Auto,
+            ),
+        )
+        .init(),
+        logger::LogFormat::Json => {
+            logger::FilteredLogger::new(log_filter.clone(), json_logger).init()
+        }
+    };
+    if let Err(e) = log_init {
         eprintln!("Error: failed to initialize logging: {}", e);
         process::exit(1);
     }
@@ -191,6 +403,63 @@ async fn main() {
         cache_path.clone(),
         vault.clone(),
     ));
+    plugin_manager.add_plugin(prometheus_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(exec_protocol::Plugin::new(
+        exec_protocol::Allowlist::new(
+            matches
+                .values_of("exec-allow")
+                .into_iter()
+                .flatten()
+                .map(PathBuf::from),
+        ),
+    ));
+
+    plugin_manager.set_table_timing(matches.is_present("table-timing"));
+
+    if let Some(val) = matches.value_of("retry-attempts") {
+        match val.parse::<u32>() {
+            Ok(max_attempts) if max_attempts > 0 => {
+                plugin_manager.set_retry_policy(RetryPolicy { max_attempts })
+            }
+            _ => {
+                eprintln!("Error: invalid number of retry attempts: {}", val);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(vals) = matches.values_of("validate-output") {
+        for val in vals {
+            let (proto, policy) = match val.split_once('=') {
+                Some((proto, policy)) => (Protocol(proto.to_string()), policy),
+                None => {
+                    eprintln!("Error: invalid output validation: {}", val);
+                    process::exit(1);
+                }
+            };
+            let policy = match policy {
+                "off" => ValidationPolicy::Off,
+                "warn" => ValidationPolicy::Warn,
+                "coerce" => ValidationPolicy::Coerce,
+                "strict" => ValidationPolicy::Strict,
+                _ => {
+                    eprintln!("Error: invalid validation policy: {}", policy);
+                    process::exit(1);
+                }
+            };
+            if !plugin_manager.get_protocols().contains(&proto) {
+                eprintln!("Error: unknown protocol: {}", proto);
+                process::exit(1);
+            }
+            plugin_manager.set_validation_policy(proto, policy);
+        }
+    }
+
+    let readiness = Arc::new(Readiness::new());
+    readiness.complete(Stage::Plugins);
 
     let plugin_manager = Arc::new(plugin_manager);
     let etc_manager = Arc::new(EtcManager::new());
@@ -199,8 +468,27 @@ async fn main() {
         etc_manager.spec_receiver().await,
         data_sender,
     );
+    let scheduler_stats = scheduler.stats();
+    readiness.complete(Stage::Scheduler);
+
+    /* Specs are sent by the backend; the agent is ready once the
+     * first package has been loaded. */
+    let mut spec_receiver = etc_manager.spec_receiver().await;
+    tokio::spawn({
+        let readiness = readiness.clone();
+        async move {
+            if spec_receiver.changed().await.is_ok() {
+                readiness.complete(Stage::Specs);
+            }
+        }
+    });
     let agent_service = Arc::new(
-        AgentService::new(plugin_manager, etc_manager, scheduler).unwrap(),
+        AgentService::new(
+            plugin_manager.clone(),
+            etc_manager.clone(),
+            scheduler,
+        )
+        .unwrap(),
     );
 
     let (agent_req_sender, agent_req_receiver) = mpsc::channel(1000);
@@ -209,6 +497,23 @@ async fn main() {
 
     let broker_compat = matches.is_present("broker-compat");
 
+    /* Only connect to the broker once requests can be handled. */
+    readiness.wait_for(Stage::Scheduler).await;
+
+    /* The broker client reconnects without backoff; when connecting,
+     * let it go through a local dialer that paces the attempts. */
+    let broker_addr = match listen {
+        true => addr.to_string(),
+        false => broker_connection::dial_broker(
+            addr.to_string(),
+            reconnect_policy,
+            broker_status.clone(),
+        )
+        .await
+        .expect("failed to start the broker dialer")
+        .to_string(),
+    };
+
     let (agent_res_sender, metrics_engine_req_sender, broker_shutdown): (
         Box<dyn MsgWriteStream<((), AsyncResponse<serde_cbor::Value>)>>,
         Box<dyn MsgWriteStream<((), AsyncRequest<serde_cbor::Value>)>>,
@@ -219,10 +524,12 @@ async fn main() {
                 broker_connection::broker_message_handler::<serde_cbor::Value>(
                     agent_req_sender,
                     metrics_engine_res_sender.clone(),
+                    broker_status.clone(),
                 );
             let broker_unconnected =
                 broker_connection::broker_unconnected_handler(
                     metrics_engine_res_sender,
+                    broker_status.clone(),
                 );
 
             let broker = match listen {
@@ -241,7 +548,7 @@ async fn main() {
                     .expect("failed to init broker connection")
                 }
                 false => {
-                    let connector = Connector::tcp(addr.to_string())
+                    let connector = Connector::tcp(broker_addr.clone())
                         .await
                         .tls(tls_config, broker_domain)
                         .expect("failed to initialize TLS")
@@ -271,11 +578,14 @@ async fn main() {
                 broker_connection::broker_message_handler_compat::<
                     serde_cbor::Value,
                 >(
-                    agent_req_sender, metrics_engine_res_sender.clone()
+                    agent_req_sender,
+                    metrics_engine_res_sender.clone(),
+                    broker_status.clone(),
                 );
             let broker_unconnected =
                 broker_connection::broker_unconnected_handler_compat(
                     metrics_engine_res_sender,
+                    broker_status.clone(),
                 );
 
             let broker = match listen {
@@ -294,7 +604,7 @@ async fn main() {
                     .expect("failed to init broker connection")
                 }
                 false => {
-                    let connector = Connector::tcp(addr.to_string())
+                    let connector = Connector::tcp(broker_addr.clone())
                         .await
                         .tls(tls_config, broker_domain)
                         .expect("failed to initialize TLS")
@@ -338,8 +648,29 @@ async fn main() {
 
     let (term_sender, term_receiver) = watch::channel(false);
 
-    let data_writer =
-        tokio::spawn(data_writer(data_receiver, metrics_engine, term_receiver));
+    let status_server = matches.is_present("status").then(|| {
+        let addr = matches
+            .value_of("status-listen")
+            .unwrap_or(status::DEFAULT_ADDR)
+            .to_string();
+        let server = status::StatusServer::new(
+            etc_manager,
+            scheduler_stats,
+            log_filter,
+            readiness,
+            broker_status,
+        );
+        let term_receiver = term_receiver.clone();
+        tokio::spawn(async move { server.run(&addr, term_receiver).await })
+    });
+
+    let data_writer = tokio::spawn(write_data(
+        data_receiver,
+        metrics_engine,
+        data_retry_policy,
+        data_spool,
+        term_receiver,
+    ));
 
     let mut sigint = signal(SignalKind::interrupt())
         .expect("failed to install sigint handler");
@@ -353,32 +684,78 @@ async fn main() {
 
     eprintln!("Awaiting open connections (press ctrl-c to force shutdown)...");
 
-    let mut shutdown = Box::pin(async {
-        if let Err(e) = term_sender.send(true) {
-            eprintln!("Warning: failed to send termination signal: {}", e);
+    if let Err(e) = term_sender.send(true) {
+        eprintln!("Warning: failed to send termination signal: {}", e);
+    }
+    // if let Err(e) = agent.shutdown().await {
+    //     eprintln!("Warning: failed to shut down agent service: {}", e);
+    // }
+    // if let Ok(srv) = Arc::try_unwrap(agent_service) {
+    //     let _ = srv.scheduler.shutdown().await;
+    // }
+    let mut steps = vec![(
+        "data writer",
+        async {
+            match data_writer.await {
+                Err(e) => {
+                    eprintln!("Warning: failed to join data writer: {}", e);
+                }
+                Ok(Err(e)) => {
+                    eprintln!("Warning: data writer failed: {}", e)
+                }
+                Ok(Ok(())) => {}
+            }
         }
-        // if let Err(e) = agent.shutdown().await {
-        //     eprintln!("Warning: failed to shut down agent service: {}", e);
-        // }
-        // if let Ok(srv) = Arc::try_unwrap(agent_service) {
-        //     let _ = srv.scheduler.shutdown().await;
-        // }
-        match data_writer.await {
-            Err(e) => {
-                eprintln!("Warning: failed to join data writer: {}", e);
+        .boxed_local(),
+    )];
+    if let Some(status_server) = status_server {
+        steps.push((
+            "status server",
+            async {
+                match status_server.await {
+                    Err(e) => {
+                        eprintln!(
+                            "Warning: failed to join status server: {}",
+                            e
+                        );
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("Warning: status server failed: {}", e)
+                    }
+                    Ok(Ok(())) => {}
+                }
             }
-            Ok(Err(e)) => {
-                eprintln!("Warning: data writer failed: {}", e)
+            .boxed_local(),
+        ));
+    }
+    steps.push((
+        "protocol plugins",
+        plugin_manager.shutdown().boxed_local(),
+    ));
+    steps.push((
+        "broker connection",
+        async {
+            if let Err(e) = broker_shutdown().await {
+                eprintln!(
+                    "Warning: failed to shut down broker connection: {}",
+                    e
+                );
             }
-            Ok(Ok(())) => {}
         }
-        if let Err(e) = broker_shutdown().await {
-            eprintln!("Warning: failed to shut down broker connection: {}", e);
-        }
-    });
+        .boxed_local(),
+    ));
+
+    let mut shutdown = Box::pin(shutdown::run(steps, shutdown_timeout));
 
     tokio::select! {
-        _ = &mut shutdown => {}
+        outcome = &mut shutdown => {
+            if let shutdown::ShutdownOutcome::TimedOut(in_flight) = outcome {
+                eprintln!(
+                    "Shutdown timed out; force shutdown! Still in flight: {}",
+                    in_flight.join(", ")
+                );
+            }
+        }
         _ = sigint.recv() => {
             eprintln!("Received SIGINT; force shutdown!");
         }
@@ -388,8 +765,8 @@ async fn main() {
     }
 }
 
-async fn data_writer(
-    mut receiver: mpsc::Receiver<(
+async fn write_data(
+    receiver: mpsc::Receiver<(
         String,
         String,
         Timestamped<MetricsTable<Data<serde_json::Value>>>,
@@ -398,27 +775,18 @@ async fn data_writer(
         rpc::AsyncClientConnection<AgentMetricsProto, serde_cbor::Value, ()>,
         serde_cbor::Value,
     >,
-    mut term_receiver: watch::Receiver<bool>,
+    retry_policy: data_writer::RetryPolicy,
+    spool: Option<data_writer::Spool>,
+    term_receiver: watch::Receiver<bool>,
 ) -> Result<()> {
-    while !*term_receiver.borrow() {
-        let (mp, table, data) = tokio::select! {
-            data = receiver.recv() => {
-                match data {
-                    Some(data) => data,
-                    None => break
-                }
-            },
-            _ = term_receiver.changed() => continue
-        };
-        let res = tokio::select! {
-            r = metrics_engine.create_metrics(mp, table, data) => r,
-            _ = tokio::time::sleep(std::time::Duration::from_secs(10))
-                => Err(Error::Timeout.to_string())
-        };
-        if let Err(e) = res {
-            eprintln!("Warning: failed to write data: {}", e);
-        }
-    }
+    data_writer::run(
+        receiver,
+        |mp, table, data| metrics_engine.create_metrics(mp, table, data),
+        retry_policy,
+        spool,
+        term_receiver,
+    )
+    .await;
     metrics_engine.into_inner().shutdown().await?;
     Ok(())
 }
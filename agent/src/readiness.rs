@@ -0,0 +1,183 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Startup readiness. The agent initializes in a fixed sequence of
+//! stages and only reports ready once the last one completed, so
+//! that no work is routed to it prematurely.
+
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// Startup stages, in the order in which they complete. Specs are
+/// received from the backend, so they can only be loaded once the
+/// agent accepts requests, i.e. when plugins and scheduler are up.
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Plugins,
+    Scheduler,
+    Specs,
+}
+
+#[derive(Serialize, PartialEq, Eq, Debug)]
+pub struct ReadinessState {
+    pub ready: bool,
+    /// The stage the agent is waiting for, if not ready.
+    pub waiting_for: Option<Stage>,
+}
+
+pub struct Readiness {
+    completed: watch::Sender<Option<Stage>>,
+}
+
+impl Stage {
+    const ALL: [Self; 3] = [Self::Plugins, Self::Scheduler, Self::Specs];
+
+    fn next(completed: Option<Self>) -> Option<Self> {
+        match completed {
+            None => Some(Self::ALL[0]),
+            Some(stage) => Self::ALL.into_iter().find(|s| *s > stage),
+        }
+    }
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self {
+            completed: watch::channel(None).0,
+        }
+    }
+
+    /// Mark a stage as completed. Stages completed out of order are
+    /// refused, since a later stage depends on the earlier ones.
+    /// Completing a stage again has no effect.
+    pub fn complete(&self, stage: Stage) -> bool {
+        self.completed.send_if_modified(|completed| {
+            match Stage::next(*completed) {
+                Some(next) if next == stage => {
+                    log::info!("startup: {:?} initialized", stage);
+                    *completed = Some(stage);
+                    true
+                }
+                _ => {
+                    if *completed < Some(stage) {
+                        log::warn!(
+                            "startup: {:?} completed before {:?}; ignored",
+                            stage,
+                            Stage::next(*completed)
+                        );
+                    }
+                    false
+                }
+            }
+        });
+        self.is_completed(stage)
+    }
+
+    pub fn is_completed(&self, stage: Stage) -> bool {
+        *self.completed.borrow() >= Some(stage)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state().ready
+    }
+
+    pub fn state(&self) -> ReadinessState {
+        let waiting_for = Stage::next(*self.completed.borrow());
+        ReadinessState {
+            ready: waiting_for.is_none(),
+            waiting_for,
+        }
+    }
+
+    /// Wait until a stage is completed.
+    pub async fn wait_for(&self, stage: Stage) {
+        let mut receiver = self.completed.subscribe();
+        /* Cannot fail: the sender is kept by self. */
+        let _ = receiver
+            .wait_for(|completed| *completed >= Some(stage))
+            .await;
+    }
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{Readiness, ReadinessState, Stage};
+
+    #[test]
+    fn ready_after_all_stages() {
+        let readiness = Readiness::new();
+        assert_eq!(
+            readiness.state(),
+            ReadinessState {
+                ready: false,
+                waiting_for: Some(Stage::Plugins)
+            }
+        );
+
+        assert!(readiness.complete(Stage::Plugins));
+        assert!(readiness.complete(Stage::Scheduler));
+        assert!(!readiness.is_ready());
+        assert_eq!(readiness.state().waiting_for, Some(Stage::Specs));
+
+        assert!(readiness.complete(Stage::Specs));
+        assert_eq!(
+            readiness.state(),
+            ReadinessState {
+                ready: true,
+                waiting_for: None
+            }
+        );
+    }
+
+    #[test]
+    fn out_of_order_is_refused() {
+        let readiness = Readiness::new();
+
+        /* Specs cannot be loaded before the plugins are up. */
+        assert!(!readiness.complete(Stage::Specs));
+        assert!(!readiness.complete(Stage::Scheduler));
+        assert_eq!(readiness.state().waiting_for, Some(Stage::Plugins));
+
+        assert!(readiness.complete(Stage::Plugins));
+        assert!(!readiness.complete(Stage::Specs));
+        assert!(!readiness.is_ready());
+
+        /* Repeated completion is harmless. */
+        assert!(readiness.complete(Stage::Plugins));
+        assert_eq!(readiness.state().waiting_for, Some(Stage::Scheduler));
+    }
+
+    #[tokio::test]
+    async fn wait_for_stage() {
+        let readiness = Arc::new(Readiness::new());
+        readiness.complete(Stage::Plugins);
+
+        let waiter = tokio::spawn({
+            let readiness = readiness.clone();
+            async move {
+                readiness.wait_for(Stage::Scheduler).await;
+                readiness.is_completed(Stage::Scheduler)
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        readiness.complete(Stage::Scheduler);
+        assert!(tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap());
+    }
+}
@@ -0,0 +1,87 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+
+/// Default time to wait for open connections on shutdown.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ShutdownOutcome {
+    Completed,
+    /// The timeout expired; the named steps did not finish.
+    TimedOut(Vec<&'static str>),
+}
+
+/// Run the shutdown steps in order. If a timeout is given and it
+/// expires, the remaining steps are dropped and reported.
+pub async fn run(
+    steps: Vec<(&'static str, LocalBoxFuture<'_, ()>)>,
+    timeout: Option<Duration>,
+) -> ShutdownOutcome {
+    let pending: Mutex<Vec<_>> =
+        Mutex::new(steps.iter().map(|(name, _)| *name).collect());
+    let run = async {
+        for (name, step) in steps {
+            step.await;
+            pending.lock().unwrap().retain(|n| *n != name);
+        }
+    };
+
+    match timeout {
+        None => {
+            run.await;
+            ShutdownOutcome::Completed
+        }
+        Some(timeout) => match tokio::time::timeout(timeout, run).await {
+            Ok(()) => ShutdownOutcome::Completed,
+            Err(_) => ShutdownOutcome::TimedOut(pending.into_inner().unwrap()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future::{pending, FutureExt};
+
+    use super::{run, ShutdownOutcome};
+
+    #[tokio::test]
+    async fn completed() {
+        let outcome = run(
+            vec![
+                ("data writer", async {}.boxed_local()),
+                ("broker connection", async {}.boxed_local()),
+            ],
+            Some(Duration::from_secs(10)),
+        )
+        .await;
+        assert_eq!(outcome, ShutdownOutcome::Completed);
+    }
+
+    #[tokio::test]
+    async fn forced_after_timeout() {
+        let outcome = run(
+            vec![
+                ("data writer", async {}.boxed_local()),
+                ("status server", pending().boxed_local()),
+                ("broker connection", async {}.boxed_local()),
+            ],
+            Some(Duration::from_millis(50)),
+        )
+        .await;
+        assert_eq!(
+            outcome,
+            ShutdownOutcome::TimedOut(vec![
+                "status server",
+                "broker connection"
+            ])
+        );
+    }
+}
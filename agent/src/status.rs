@@ -0,0 +1,424 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Local HTTP endpoint to monitor the agent itself, independent of
+//! the broker connection. Serves:
+//!
+//! - `/health`: readiness check; fails until plugins, scheduler and
+//!   specs are initialized
+//! - `/status`: loaded packages, scheduler and broker connection
+//!   status
+//! - `/metrics`: scheduler counters
+//! - `/log`: log levels; `POST /log/<level>` sets the default level,
+//!   `POST /log/<module>/<level>` overrides the level for a module
+//!   and `DELETE /log/<module>` removes the override
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use broker_api::AgentConnectionStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use etc::EtcManager;
+use etc_base::{PackageName, PackageVersion};
+use log::LevelFilter;
+use logger::ModuleFilter;
+use scheduler::{Stats, StatsSnapshot};
+
+use crate::broker_connection::BrokerStatus;
+use crate::error::{Error, Result};
+use crate::readiness::{Readiness, ReadinessState};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:9998";
+/// Time a client gets to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause after a failed accept, eg. when out of file descriptors.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+pub struct StatusServer {
+    etc_manager: Arc<EtcManager>,
+    stats: Arc<Stats>,
+    log_filter: Arc<ModuleFilter>,
+    readiness: Arc<Readiness>,
+    broker: BrokerStatus,
+    started: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+struct Status {
+    version: &'static str,
+    started: DateTime<Utc>,
+    uptime: i64,
+    readiness: ReadinessState,
+    packages: HashMap<PackageName, PackageVersion>,
+    data_tables: usize,
+    tables: usize,
+    scheduler: StatsSnapshot,
+    broker: Option<AgentConnectionStatus>,
+}
+
+#[derive(Serialize, Debug)]
+struct LogLevels {
+    default: String,
+    modules: BTreeMap<String, String>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl StatusServer {
+    pub fn new(
+        etc_manager: Arc<EtcManager>,
+        stats: Arc<Stats>,
+        log_filter: Arc<ModuleFilter>,
+        readiness: Arc<Readiness>,
+        broker: BrokerStatus,
+    ) -> Self {
+        Self {
+            etc_manager,
+            stats,
+            log_filter,
+            readiness,
+            broker,
+            started: Utc::now(),
+        }
+    }
+
+    pub async fn run(
+        self,
+        addr: &str,
+        mut term_receiver: watch::Receiver<bool>,
+    ) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        log::info!("status endpoint listening on {}", addr);
+        let server = Arc::new(self);
+
+        while !*term_receiver.borrow() {
+            let (stream, peer) = tokio::select! {
+                r = listener.accept() => match r {
+                    Ok(r) => r,
+                    Err(e) => {
+                        log::warn!("status endpoint: accept failed: {}", e);
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                },
+                r = term_receiver.changed() => match r {
+                    Ok(()) => continue,
+                    /* The sender was dropped: treat as termination. */
+                    Err(_) => break,
+                },
+            };
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve(stream, READ_TIMEOUT).await {
+                    log::debug!("status request from {} failed: {}", peer, e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn serve(
+        &self,
+        stream: TcpStream,
+        read_timeout: Duration,
+    ) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let mut request = String::new();
+        tokio::time::timeout(read_timeout, async {
+            reader.read_line(&mut request).await?;
+
+            /* Skip request headers. */
+            let mut line = String::new();
+            while reader.read_line(&mut line).await? > 2 {
+                line.clear();
+            }
+            Ok::<_, Error>(())
+        })
+        .await
+        .map_err(|_| Error::Timeout)??;
+
+        let res = match request.split_whitespace().collect::<Vec<_>>()[..] {
+            ["GET", path, _] => self.handle(path).await,
+            [method @ ("POST" | "DELETE"), path, _] => {
+                self.handle_log(method, path)
+            }
+            _ => Response::text(405, "method not allowed"),
+        };
+
+        writer
+            .write_all(
+                format!(
+                    "HTTP/1.1 {} {}\r\nContent-Type: {}\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    res.status,
+                    res.reason(),
+                    res.content_type,
+                    res.body.len(),
+                    res.body
+                )
+                .as_bytes(),
+            )
+            .await?;
+        writer.shutdown().await?;
+        Ok(())
+    }
+
+    async fn handle(&self, path: &str) -> Response {
+        match path {
+            "/health" => match self.readiness.state().waiting_for {
+                None => Response::text(200, "ok"),
+                Some(stage) => Response::text(
+                    503,
+                    &format!("starting: waiting for {:?}", stage),
+                ),
+            },
+            "/status" => match self.status().await {
+                Ok(status) => Response::json(&status),
+                Err(e) => Response::text(500, &e.to_string()),
+            },
+            "/metrics" => Response::json(&self.stats.snapshot()),
+            "/log" => Response::json(&self.log_levels()),
+            _ => Response::text(404, "not found"),
+        }
+    }
+
+    fn handle_log(&self, method: &str, path: &str) -> Response {
+        let args = match path.strip_prefix("/log/") {
+            Some(args) => args.split('/').collect::<Vec<_>>(),
+            None => return Response::text(404, "not found"),
+        };
+        let level = match (method, args.as_slice()) {
+            ("POST", [level] | [_, level]) => {
+                match level.parse::<LevelFilter>() {
+                    Ok(level) => Some(level),
+                    Err(_) => return Response::text(400, "invalid log level"),
+                }
+            }
+            _ => None,
+        };
+        match (args.as_slice(), level) {
+            ([_], Some(level)) => self.log_filter.set_default(level),
+            ([module, _], Some(level)) => {
+                self.log_filter.set_override(module, Some(level))
+            }
+            ([module], None) if method == "DELETE" => {
+                self.log_filter.set_override(module, None)
+            }
+            _ => return Response::text(404, "not found"),
+        }
+        log::info!("log levels changed: {} {}", method, path);
+        Response::json(&self.log_levels())
+    }
+
+    fn log_levels(&self) -> LogLevels {
+        LogLevels {
+            default: self.log_filter.default_level().to_string(),
+            modules: self
+                .log_filter
+                .overrides()
+                .into_iter()
+                .map(|(module, level)| (module, level.to_string()))
+                .collect(),
+        }
+    }
+
+    async fn status(&self) -> Result<Status> {
+        let spec = self.etc_manager.spec().await;
+        let data_tables = spec.input.values().map(|i| i.data_tables.len());
+        Ok(Status {
+            version: env!("CARGO_PKG_VERSION"),
+            started: self.started,
+            uptime: (Utc::now() - self.started).num_seconds(),
+            readiness: self.readiness.state(),
+            packages: self
+                .etc_manager
+                .loaded_pkgs()
+                .await
+                .map_err(|e| crate::error::Error::Custom(e.to_string()))?,
+            data_tables: data_tables.sum(),
+            tables: spec.etc.tables.len(),
+            scheduler: self.stats.snapshot(),
+            broker: self.broker.status(),
+        })
+    }
+}
+
+impl Response {
+    fn text(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: body.to_string(),
+        }
+    }
+
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self {
+                status: 200,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Self::text(500, &e.to_string()),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use etc::EtcManager;
+    use log::LevelFilter;
+    use logger::ModuleFilter;
+    use scheduler::Stats;
+
+    use super::StatusServer;
+    use crate::broker_connection::BrokerStatus;
+    use crate::error::Error;
+    use crate::readiness::{Readiness, Stage};
+
+    fn server() -> StatusServer {
+        let readiness = Readiness::new();
+        readiness.complete(Stage::Plugins);
+        readiness.complete(Stage::Scheduler);
+        readiness.complete(Stage::Specs);
+        server_with(readiness)
+    }
+
+    fn server_with(readiness: Readiness) -> StatusServer {
+        StatusServer::new(
+            Arc::new(EtcManager::new()),
+            Arc::new(Stats::new()),
+            Arc::new(ModuleFilter::new(LevelFilter::Warn)),
+            Arc::new(readiness),
+            BrokerStatus::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn health() {
+        let res = server().handle("/health").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, "ok");
+    }
+
+    #[tokio::test]
+    async fn unhealthy_until_ready() {
+        let server = server_with(Readiness::new());
+        assert_eq!(server.handle("/health").await.status, 503);
+
+        server.readiness.complete(Stage::Plugins);
+        server.readiness.complete(Stage::Scheduler);
+        let res = server.handle("/health").await;
+        assert_eq!(res.status, 503);
+        assert_eq!(res.body, "starting: waiting for Specs");
+
+        let res = server.handle("/status").await;
+        let status: serde_json::Value =
+            serde_json::from_str(&res.body).unwrap();
+        assert_eq!(status["readiness"]["ready"], false);
+        assert_eq!(status["readiness"]["waiting_for"], "specs");
+
+        server.readiness.complete(Stage::Specs);
+        assert_eq!(server.handle("/health").await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn status() {
+        let res = server().handle("/status").await;
+        assert_eq!(res.status, 200);
+        assert_eq!(res.content_type, "application/json");
+        let status: serde_json::Value =
+            serde_json::from_str(&res.body).unwrap();
+        assert_eq!(status["packages"], serde_json::json!({}));
+        assert_eq!(status["readiness"]["ready"], true);
+        assert_eq!(status["tables"], 0);
+        assert_eq!(status["scheduler"]["runs"], 0);
+        assert!(status["broker"].is_null());
+    }
+
+    #[tokio::test]
+    async fn metrics() {
+        let res = server().handle("/metrics").await;
+        assert_eq!(res.status, 200);
+        let metrics: serde_json::Value =
+            serde_json::from_str(&res.body).unwrap();
+        assert_eq!(metrics["tasks"], 0);
+        assert_eq!(metrics["failures"], 0);
+    }
+
+    #[tokio::test]
+    async fn log_levels() {
+        let server = server();
+        let res = server.handle_log("POST", "/log/snmp_protocol/debug");
+        assert_eq!(res.status, 200);
+        assert_eq!(
+            server.log_filter.level_for("snmp_protocol::walk"),
+            LevelFilter::Debug
+        );
+
+        let res = server.handle("/log").await;
+        let levels: serde_json::Value =
+            serde_json::from_str(&res.body).unwrap();
+        assert_eq!(levels["default"], "WARN");
+        assert_eq!(levels["modules"]["snmp_protocol"], "DEBUG");
+
+        assert_eq!(server.handle_log("POST", "/log/info").status, 200);
+        assert_eq!(
+            server.handle_log("DELETE", "/log/snmp_protocol").status,
+            200
+        );
+        assert_eq!(
+            server.log_filter.level_for("snmp_protocol"),
+            LevelFilter::Info
+        );
+        assert_eq!(server.handle_log("POST", "/log/loud").status, 400);
+        assert_eq!(server.handle_log("DELETE", "/status").status, 404);
+    }
+
+    #[tokio::test]
+    async fn stalled_client() {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _client =
+            tokio::net::TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let res = server().serve(stream, Duration::from_millis(50)).await;
+        assert!(matches!(res, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        assert_eq!(server().handle("/nope").await.status, 404);
+    }
+}
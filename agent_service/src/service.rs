@@ -320,14 +320,16 @@ impl agent_api::AgentService for AgentService {
         &self,
         client_info: azure_protocol::ClientInfo,
     ) -> Result<Vec<azure_protocol::Tenant>> {
-        let client = client_info.login(None).await.map_err(|e| {
-            protocol::Error::Plugin(
-                Protocol(String::from("Azure")),
-                Box::new(e),
-            )
-        })?;
+        let session = azure_protocol::Session::from(
+            client_info.login(None).await.map_err(|e| {
+                protocol::Error::Plugin(
+                    Protocol(String::from("Azure")),
+                    Box::new(e),
+                )
+            })?,
+        );
         Ok(
-            azure_protocol::request_resource(&client, "tenants", "2020-01-01")
+            azure_protocol::request_resource(&session, "tenants", "2020-01-01")
                 .await
                 .map_err(|e| {
                     protocol::Error::Plugin(
@@ -341,14 +343,16 @@ impl agent_api::AgentService for AgentService {
         &self,
         client_info: azure_protocol::ClientInfo,
     ) -> Result<Vec<azure_protocol::Subscription>> {
-        let client = client_info.login(None).await.map_err(|e| {
-            protocol::Error::Plugin(
-                Protocol(String::from("Azure")),
-                Box::new(e),
-            )
-        })?;
+        let session = azure_protocol::Session::from(
+            client_info.login(None).await.map_err(|e| {
+                protocol::Error::Plugin(
+                    Protocol(String::from("Azure")),
+                    Box::new(e),
+                )
+            })?,
+        );
         Ok(azure_protocol::request_resource(
-            &client,
+            &session,
             "subscriptions",
             "2020-01-01",
         )
@@ -370,17 +374,19 @@ impl agent_api::AgentService for AgentService {
             std::result::Result<Vec<azure_protocol::ResourceGroup>, String>,
         >,
     > {
-        let client = client_info.login(None).await.map_err(|e| {
-            protocol::Error::Plugin(
-                Protocol(String::from("Azure")),
-                Box::new(e),
-            )
-        })?;
+        let session = azure_protocol::Session::from(
+            client_info.login(None).await.map_err(|e| {
+                protocol::Error::Plugin(
+                    Protocol(String::from("Azure")),
+                    Box::new(e),
+                )
+            })?,
+        );
         let requests = subscriptions
             .iter()
             .map(|s| {
                 azure_protocol::request_resource_from_subscription(
-                    &client,
+                    &session,
                     s,
                     "resourcegroups",
                     "2021-04-01",
@@ -412,18 +418,20 @@ impl agent_api::AgentService for AgentService {
             >,
         >,
     > {
-        let client = client_info.login(None).await.map_err(|e| {
-            protocol::Error::Plugin(
-                Protocol(String::from("Azure")),
-                Box::new(e),
-            )
-        })?;
+        let session = azure_protocol::Session::from(
+            client_info.login(None).await.map_err(|e| {
+                protocol::Error::Plugin(
+                    Protocol(String::from("Azure")),
+                    Box::new(e),
+                )
+            })?,
+        );
 
         let requests = subscriptions
             .iter()
             .map(|s| {
                 azure_protocol::request_resource_from_subscription(
-                    &client,
+                    &session,
                     s,
                     "resources",
                     "2021-04-01",
@@ -516,7 +524,7 @@ impl agent_api::AgentService for AgentService {
                     table_id.clone(),
                     table_id
                         .try_get_from(&spec.etc.tables)?
-                        .calculate(query_mode, &spec.etc, &data)?,
+                        .calculate_summary(query_mode, &spec.etc, &data)?,
                 ))
             })
             .collect::<Result<_>>()?;
@@ -544,34 +552,32 @@ impl agent_api::AgentService for AgentService {
                                 .into_iter()
                                 .map(|row| {
                                     row.into_iter()
-                                        .filter_map(|(k, v)| {
+                                        .flat_map(|(k, v)| {
                                             let field = match k
                                                 .try_get_from(&spec.etc.fields)
                                             {
                                                 Ok(v) => v,
-                                                Err(e) => return Some(Err(e.into())),
+                                                Err(e) => return vec![Err(e.into())],
                                             };
-                                            if !field.elastic_data {
-                                                return None;
+                                            if !field.elastic_data || field.is_excluded() {
+                                                return Vec::new();
                                             }
                                             let elastic_field: String = match &field
                                                 .elastic_field {
 													Some(v) => v.to_string(),
-													None => return Some(Err(Error::MissingElasticField(
+													None => return vec![Err(Error::MissingElasticField(
                                                         k.to_string(),
-                                                    )))
+                                                    ))]
 												};
-                                            Some(Ok((
-                                                elastic_field,
-                                                v.map_err(|e| e.to_string())
-                                                    .and_then(|v| {
-                                                        v.to_json_value()
-                                                            .ok_or_else(|| {
-                                                                "unserializable"
-                                                                    .to_string()
-                                                            })
-                                                    }),
-                                            )))
+                                            field
+                                                .output_json_fields(
+                                                    elastic_field,
+                                                    v.map_err(|e| e.to_string()),
+                                                    None,
+                                                )
+                                                .into_iter()
+                                                .map(Ok)
+                                                .collect()
                                         })
                                         .collect()
                                 })
@@ -2,6 +2,9 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -12,6 +15,18 @@ pub enum Error {
     IncompatibleDefinitions(String),
     #[error("Missing {0}")]
     MissingObject(String),
+    #[error(
+        "Template variables do not match: missing {missing:?}, unused {unused:?}"
+    )]
+    TemplateMismatch {
+        missing: Vec<String>,
+        unused: Vec<String>,
+    },
+
+    #[error("failed to lock {0:?}: {1}")]
+    Lock(PathBuf, std::io::Error),
+    #[error("timeout waiting {1:?} for lock on {0:?}")]
+    LockTimeout(PathBuf, Duration),
 
     #[cfg(feature = "trust-dns-resolver")]
     #[error("failed to resolve hostname: {0}")]
@@ -0,0 +1,159 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Advisory exclusive lock on a (cache) file, held until dropped.
+/// The lock is taken on a separate "<file>.lock" file, so that the
+/// locked file itself can be truncated or replaced. Locks held by a
+/// process are released by the OS when it exits, so a crashed run
+/// cannot block later ones.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Try to take the lock for `path` without waiting.
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let lock_path = Self::lock_path(path);
+        if let Some(dir) = lock_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::Lock(lock_path.clone(), e))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| Error::Lock(lock_path.clone(), e))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self {
+                file,
+                path: lock_path,
+            })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(Error::Lock(lock_path, e)),
+        }
+    }
+
+    /// Take the lock for `path`, blocking the current thread until
+    /// it is available or `timeout` expires.
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if start.elapsed() >= timeout {
+                return Err(Error::LockTimeout(Self::lock_path(path), timeout));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Take the lock for `path`, waiting asynchronously until it is
+    /// available or `timeout` expires.
+    #[cfg(feature = "tokio")]
+    pub async fn acquire_async(path: &Path, timeout: Duration) -> Result<Self> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if start.elapsed() >= timeout {
+                return Err(Error::LockTimeout(Self::lock_path(path), timeout));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn lock_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        path.with_file_name(name)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.file.unlock() {
+            log::warn!("failed to unlock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::FileLock;
+    use crate::Error;
+
+    fn cache_file(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("agent_utils_lock_{}", std::process::id()))
+            .join(name)
+    }
+
+    /* Every lock opens its own file description, so two locks in one
+     * process contend exactly like two processes would. */
+
+    #[test]
+    fn exclusive() {
+        let path = cache_file("exclusive.json");
+        let lock = FileLock::try_acquire(&path).unwrap().unwrap();
+        assert!(FileLock::try_acquire(&path).unwrap().is_none());
+        drop(lock);
+        assert!(FileLock::try_acquire(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn timeout() {
+        let path = cache_file("timeout.json");
+        let _lock = FileLock::acquire(&path, Duration::ZERO).unwrap();
+        assert!(matches!(
+            FileLock::acquire(&path, Duration::from_millis(100)),
+            Err(Error::LockTimeout(_, _))
+        ));
+    }
+
+    #[test]
+    fn contention() {
+        let path = cache_file("contention.json");
+        let lock = FileLock::acquire(&path, Duration::ZERO).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let waiter = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                let lock = FileLock::acquire(&path, Duration::from_secs(10));
+                sender.send(()).unwrap();
+                lock.map(|_| ())
+            }
+        });
+
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(lock);
+        assert!(receiver.recv_timeout(Duration::from_secs(10)).is_ok());
+        waiter.join().unwrap().unwrap();
+    }
+}
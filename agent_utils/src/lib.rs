@@ -4,6 +4,7 @@
 
 pub mod database;
 mod error;
+mod file_lock;
 pub mod pyrepr;
 mod template;
 mod utils;
@@ -12,6 +13,7 @@ pub mod vault;
 
 pub use database::{DBId, DBObj};
 pub use error::{Error, Result};
+pub use file_lock::FileLock;
 pub use template::Template;
 #[cfg(feature = "trust-dns-resolver")]
 pub use utils::{ip_lookup, ip_lookup_one, ip_lookup_one_sync, ip_lookup_sync};
@@ -2,7 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Write;
 
 use nom::{
@@ -13,6 +13,8 @@ use nom::{
     Finish, IResult,
 };
 
+use crate::{Error, Result};
+
 /// Simple template used for auto-generating API definitions
 /// for other programming languages. If this were to be used
 /// for production code, it should be updated to use errors
@@ -54,6 +56,40 @@ impl<'a> Template<'a> {
         }
         output
     }
+
+    /// Fill in the template, failing if any variable in the template
+    /// is missing from `vars`, or if `vars` contains variables not
+    /// used in the template.
+    pub fn fill_strict(&self, vars: HashMap<&str, &str>) -> Result<String> {
+        let used = self.variables();
+        let missing = used
+            .iter()
+            .filter(|var| !vars.contains_key(*var))
+            .map(|var| var.to_string())
+            .collect::<Vec<_>>();
+        let mut unused = vars
+            .keys()
+            .filter(|var| !used.contains(*var))
+            .map(|var| var.to_string())
+            .collect::<Vec<_>>();
+        unused.sort();
+
+        match missing.is_empty() && unused.is_empty() {
+            true => Ok(self.fill(vars)),
+            false => Err(Error::TemplateMismatch { missing, unused }),
+        }
+    }
+
+    /// The variables used in the template.
+    pub fn variables(&self) -> BTreeSet<&'a str> {
+        self.0
+            .iter()
+            .filter_map(|elem| match elem {
+                Elem::Text(_) => None,
+                Elem::Variable(var) | Elem::Indented(_, var, _) => Some(*var),
+            })
+            .collect()
+    }
 }
 
 fn parse_template(mut input: &str) -> IResult<&str, Template> {
@@ -114,3 +150,55 @@ fn variable(input: &str) -> IResult<&str, &str> {
     )(input)?;
     Ok((input, var_name))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Template;
+    use crate::Error;
+
+    const TEMPLATE: &str = "host={{Host}}\n    {{Body}}\nport={{Port}}\n";
+
+    #[test]
+    fn fill_strict() {
+        let vars = HashMap::from([
+            ("Host", "localhost"),
+            ("Port", "9999"),
+            ("Body", "a\nb"),
+        ]);
+        assert_eq!(
+            Template::parse(TEMPLATE).fill_strict(vars).unwrap(),
+            "host=localhost\n    a\n    b\nport=9999\n"
+        );
+    }
+
+    #[test]
+    fn missing_keys() {
+        let vars = HashMap::from([("Host", "localhost")]);
+        match Template::parse(TEMPLATE).fill_strict(vars) {
+            Err(Error::TemplateMismatch { missing, unused }) => {
+                assert_eq!(missing, vec!["Body", "Port"]);
+                assert!(unused.is_empty());
+            }
+            r => panic!("expected template mismatch, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn extra_keys() {
+        let vars = HashMap::from([
+            ("Host", "localhost"),
+            ("Port", "9999"),
+            ("Body", ""),
+            ("Hostname", "localhost"),
+        ]);
+        match Template::parse(TEMPLATE).fill_strict(vars) {
+            Err(Error::TemplateMismatch { missing, unused }) => {
+                assert!(missing.is_empty());
+                assert_eq!(unused, vec!["Hostname"]);
+            }
+            r => panic!("expected template mismatch, got {:?}", r),
+        }
+    }
+}
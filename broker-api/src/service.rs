@@ -60,6 +60,12 @@ pub enum AgentConnectionStatus {
         next_try: Option<DateTime<Utc>>,
     },
     Retrying,
+    /// The connection was lost; waiting to reconnect.
+    Reconnecting {
+        since: DateTime<Utc>,
+        attempt: u32,
+        next_try: DateTime<Utc>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
@@ -5,6 +5,7 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
     marker::PhantomData,
+    sync::Arc,
 };
 
 use broker_api::{
@@ -23,13 +24,20 @@ use tokio::{
 };
 use tokio_rustls::server::TlsStream;
 
+use crate::failover::InFlight;
 use crate::node::Node;
 
-pub struct AgentHandler<V>(PhantomData<V>);
+pub struct AgentHandler<V> {
+    in_flight: Arc<InFlight>,
+    _marker: PhantomData<V>,
+}
 
 impl<V> AgentHandler<V> {
-    pub fn new() -> Self {
-        Self(PhantomData)
+    pub fn new(in_flight: Arc<InFlight>) -> Self {
+        Self {
+            in_flight,
+            _marker: PhantomData,
+        }
     }
 }
 impl<S> BrokerHandler<TlsStream<S>> for AgentHandler<Value>
@@ -68,7 +76,7 @@ where
             AgentConnectionStatus::Connected { since: Utc::now() },
         );
 
-        if let Some(backend) = &node.backend {
+        if let Some(backend) = node.backend() {
             if let Err(e) =
                 backend.try_send(BrokerToBackendMessage::BrokerEvent {
                     event: BrokerEvent::AgentConnected {
@@ -110,7 +118,26 @@ where
                 },
             );
 
-            if let Some(backend) = &node.backend {
+            /* Fail requests the agent will not respond to. */
+            for (req_id, backend_id) in self.in_flight.remove_agent(org, agent)
+            {
+                if let Some(backend) = node.backend_by_id(backend_id) {
+                    let _ = backend.try_send(BrokerToBackendMessage::Agent {
+                        agent_id: agent.clone(),
+                        message: AsyncResponse {
+                            req_id,
+                            response: serde_cbor::value::to_value::<
+                                std::result::Result<(), &str>,
+                            >(Err(
+                                "agent disconnected",
+                            ))
+                            .unwrap(),
+                        },
+                    });
+                }
+            }
+
+            if let Some(backend) = node.backend() {
                 if let Err(e) =
                     backend.try_send(BrokerToBackendMessage::BrokerEvent {
                         event: BrokerEvent::AgentDisconnected {
@@ -151,22 +178,32 @@ where
     fn handle_message(
         &self,
         node: &Self::Node,
-        (_, agent_id): &Self::Key,
+        (org, agent_id): &Self::Key,
         msg: Self::ReadMsg,
     ) -> std::result::Result<(), Self::WriteMsg> {
         match msg {
-            AgentToBrokerMessage::Backend { message } => match &node.backend {
-                Some(backend) => {
-                    match backend.try_send(BrokerToBackendMessage::Agent {
-                        agent_id: agent_id.clone(),
-                        message,
-                    }) {
-                        Ok(()) => Ok(()),
-                        Err(_) => Ok(()), // ignore msg when queue is full
+            AgentToBrokerMessage::Backend { message } => {
+                /* Return responses to the backend that issued the
+                 * request, or took it over; drop them if it has
+                 * disconnected or the request was failed. A backend
+                 * that reconnected may be reusing the request id. */
+                let backend = self
+                    .in_flight
+                    .take(org, agent_id, message.req_id)
+                    .and_then(|id| node.backend_by_id(id));
+                match backend {
+                    Some(backend) => {
+                        match backend.try_send(BrokerToBackendMessage::Agent {
+                            agent_id: agent_id.clone(),
+                            message,
+                        }) {
+                            Ok(()) => Ok(()),
+                            Err(_) => Ok(()), // ignore msg when queue is full
+                        }
                     }
+                    None => Ok(()), // ignore msg when backend is not connected
                 }
-                None => Ok(()), // ignore msg when backend is not connected
-            },
+            }
             AgentToBrokerMessage::MetricsEngine { message } => {
                 let req_id = message.req_id;
                 match &node.database {
@@ -12,18 +12,22 @@ use tokio::{
 use tokio_rustls::server::TlsStream;
 
 use broker_api::{
-    BackendToBrokerMessage, BrokerEvent, BrokerProto, BrokerToAgentMessage,
-    BrokerToBackendMessage, OrgId,
+    AgentId, BackendToBrokerMessage, BrokerEvent, BrokerProto,
+    BrokerToAgentMessage, BrokerToBackendMessage, OrgId,
 };
 use rpc::{
     AsyncRequest, AsyncResponse, BrokerHandler, CborReadStream, CborStream,
     CborWriteStream, GenericValue, MsgStream, RequestHandler, TlsStreamExt,
 };
 
+use crate::failover::{
+    self, agent_error, is_idempotent, BackendConnId, InFlight,
+};
 use crate::node::Node;
 
 pub struct BackendHandler<H, V> {
     broker_handler: Arc<H>,
+    in_flight: Arc<InFlight>,
     _marker: PhantomData<V>,
 }
 
@@ -34,9 +38,10 @@ where
     V::Error: Send,
     H::Error: Send,
 {
-    pub fn new(broker_handler: Arc<H>) -> Self {
+    pub fn new(broker_handler: Arc<H>, in_flight: Arc<InFlight>) -> Self {
         Self {
             broker_handler,
+            in_flight,
             _marker: PhantomData,
         }
     }
@@ -51,7 +56,7 @@ where
     S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
     H::Error: Send,
 {
-    type Key = OrgId;
+    type Key = (OrgId, BackendConnId);
     type Node = Node<Value>;
     type ReadStream = CborReadStream<TlsStream<S>, Self::ReadMsg>;
     type WriteStream = CborWriteStream<TlsStream<S>, Self::WriteMsg>;
@@ -62,61 +67,56 @@ where
         let org = stream
             .peer_organization()
             .ok_or(rpc::Error::Authentication)?;
-        Ok(OrgId(org))
+        Ok((OrgId(org), self.in_flight.new_backend_id()))
     }
 
     fn add_node(
         &self,
         nodes: &mut HashMap<OrgId, Self::Node>,
-        org: &Self::Key,
+        (org, id): &Self::Key,
         sender: mpsc::Sender<Self::WriteMsg>,
     ) -> rpc::Result<()> {
         let node = nodes.entry(org.clone()).or_insert_with(Self::Node::default);
-        match node.backend.is_some() {
+        /* Additional connections for an organization used to be refused
+         * as duplicates. They are now kept as standby; since standby
+         * backends cannot send requests to agents, a second connection
+         * can no longer take over an organization's agents while the
+         * first one is connected. */
+        match node.add_backend(*id, sender.clone()) {
             true => {
-                log::warn!(
-                    "Refusing duplicate backend connection for {}",
-                    &org.0
-                );
-                Err(rpc::Error::AuthenticationFailed)
+                announce_agents(&node.agents, &sender);
+                log::info!("Connection from backend {}", &org.0);
             }
             false => {
-                for agent_id in node.agents.keys() {
-                    if let Err(e) =
-                        sender.try_send(BrokerToBackendMessage::BrokerEvent {
-                            event: BrokerEvent::AgentConnected {
-                                agent_id: agent_id.clone(),
-                            },
-                        })
-                    {
-                        log::warn!(
-                            "failed to send agent connected event to backend: {}",
-                            e
-                        );
-                    }
-                }
-                let _ = node.backend.insert(sender);
-                log::info!("Connection from backend {}", &org.0);
-                Ok(())
+                log::info!("Connection from standby backend {}", &org.0);
             }
         }
+        Ok(())
     }
 
     fn remove_node(
         &self,
         nodes: &mut HashMap<OrgId, Self::Node>,
-        org: &Self::Key,
+        (org, id): &Self::Key,
     ) {
         if let Some(node) = nodes.get_mut(org) {
-            node.backend.take();
-            log::info!("Disconnect from backend {}", &org.0);
+            match failover::remove_backend(&self.in_flight, node, org, *id) {
+                Some(standby) => {
+                    announce_agents(&node.agents, &standby);
+                    log::info!(
+                        "Disconnect from backend {}; standby backend took over",
+                        &org.0
+                    );
+                }
+                None => log::info!("Disconnect from backend {}", &org.0),
+            }
         }
     }
 
     fn get_node<'a>(
         &self,
         nodes: &'a HashMap<OrgId, Self::Node>,
-        org: &Self::Key,
+        (org, _id): &Self::Key,
     ) -> Option<&'a Self::Node> {
         nodes.get(org)
     }
@@ -131,50 +131,58 @@ where
     fn handle_message(
         &self,
         node: &Self::Node,
-        org: &Self::Key,
+        (org, id): &Self::Key,
         msg: Self::ReadMsg,
     ) -> std::result::Result<(), Self::WriteMsg> {
         match msg {
             BackendToBrokerMessage::Agent { agent_id, message } => {
                 let req_id = message.req_id;
-                match node.agents.get(&agent_id) {
-                    Some(agent) => {
-                        match agent
-                            .try_send(BrokerToAgentMessage::Backend { message })
-                        {
-                            Ok(()) => Ok(()),
-                            Err(_) => Err(BrokerToBackendMessage::Agent {
-                                agent_id,
-                                message: AsyncResponse {
-                                    req_id,
-                                    response: serde_cbor::value::to_value::<
-                                        std::result::Result<(), &str>,
-                                    >(
-                                        Err("agent queue full")
-                                    )
-                                    .unwrap(),
-                                },
-                            }),
-                        }
-                    }
-                    None => Err(BrokerToBackendMessage::Agent {
+                /* Only the active backend may send requests: agents
+                 * respond by request id only, so requests from several
+                 * backends could not be told apart. */
+                if !node.is_active(*id) {
+                    return Err(agent_error(
                         agent_id,
-                        message: AsyncResponse {
+                        req_id,
+                        "backend is on standby",
+                    ));
+                }
+                let agent = match node.agents.get(&agent_id) {
+                    Some(agent) => agent,
+                    None => {
+                        return Err(agent_error(
+                            agent_id,
                             req_id,
-                            response: serde_cbor::value::to_value::<
-                                std::result::Result<(), &str>,
-                            >(Err(
-                                "agent not connected",
-                            ))
-                            .unwrap(),
-                        },
-                    }),
+                            "agent not connected",
+                        ))
+                    }
+                };
+                /* A backend that took over may reuse the id of a
+                 * request its predecessor is still waiting for. */
+                let idempotent = is_idempotent(&message.request);
+                if !self
+                    .in_flight
+                    .insert(org, &agent_id, req_id, *id, idempotent)
+                {
+                    return Err(agent_error(
+                        agent_id,
+                        req_id,
+                        "request id already in flight",
+                    ));
+                }
+                match agent.try_send(BrokerToAgentMessage::Backend { message })
+                {
+                    Ok(()) => Ok(()),
+                    Err(_) => {
+                        self.in_flight.take(org, &agent_id, req_id);
+                        Err(agent_error(agent_id, req_id, "agent queue full"))
+                    }
                 }
             }
             BackendToBrokerMessage::Broker {
                 message: AsyncRequest { req_id, request },
             } => {
-                let res_sender = match node.backend.as_ref() {
+                let res_sender = match node.backend_by_id(*id) {
                     Some(s) => s.clone(),
                     None => return Ok(()),
                 };
@@ -202,3 +210,22 @@ where
         }
     }
 }
+
+/// Notify a backend of the agents that are already connected.
+fn announce_agents<V>(
+    agents: &HashMap<AgentId, mpsc::Sender<BrokerToAgentMessage<V>>>,
+    backend: &mpsc::Sender<BrokerToBackendMessage<V>>,
+) {
+    for agent_id in agents.keys() {
+        if let Err(e) = backend.try_send(BrokerToBackendMessage::BrokerEvent {
+            event: BrokerEvent::AgentConnected {
+                agent_id: agent_id.clone(),
+            },
+        }) {
+            log::warn!(
+                "failed to send agent connected event to backend: {}",
+                e
+            );
+        }
+    }
+}
@@ -49,10 +49,6 @@ pub enum Error {
     SshHostArg(String, ssh::Error),
     #[error("Ssh connection for {0} failed: {1}")]
     SshConnect(String, ssh::Error),
-    #[error("Ssh authentication failed for {0}: {1}")]
-    SshAuthenticate(String, thrussh::Error),
-    #[error("Access for {0} was denied by the ssh server")]
-    SshAuthentication(String),
     #[error("Failed to open TCP channel for {0}: {1}")]
     SshChannel(String, thrussh::Error),
     #[error("Failed to join SSH connector: {0}")]
@@ -84,7 +80,7 @@ impl From<Error> for broker_api::BrokerError {
             retry: match err {
                 Error::KeyDecode(_)
                 | Error::SshHostArg(_, _)
-                | Error::SshAuthentication(_) => false,
+                | Error::SshConnect(_, ssh::Error::AccessDenied(_)) => false,
                 _ => true,
             },
             message: err.to_string(),
@@ -0,0 +1,313 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Backend failover.
+//!
+//! Requests to agents are issued by the active backend. When it
+//! disconnects, its outstanding requests are handed over to the
+//! standby backend that takes over: idempotent requests stay in
+//! flight and the agent's responses are re-dispatched to the new
+//! backend, while requests that change the agent's state are failed
+//! back to it, since the broker cannot tell whether they were
+//! executed and must not duplicate them. When no backend remains,
+//! the requests are forgotten and their responses discarded.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use broker_api::{AgentId, BrokerToBackendMessage, OrgId};
+use rpc::AsyncResponse;
+use serde_cbor::Value;
+use tokio::sync::mpsc;
+
+use crate::node::Node;
+
+/// Identifies a single backend connection. An organization can have
+/// several backends connected: the first one is active, the others
+/// are standby and take over when it disconnects.
+pub type BackendConnId = u64;
+
+/// Agent requests that change the agent's state, by method name
+/// with underscores removed, compared case-insensitively.
+const NON_IDEMPOTENT: [&str; 6] = [
+    "shutdown",
+    "config",
+    "install",
+    "uninstall",
+    "loadpkg",
+    "unloadpkg",
+];
+
+/// Whether an agent request can safely be answered to another
+/// backend than the one that issued it. Requests that cannot be
+/// recognized are considered non-idempotent.
+pub fn is_idempotent(request: &Value) -> bool {
+    let method = match request {
+        Value::Text(method) => method,
+        Value::Map(map) if map.len() == 1 => match map.keys().next() {
+            Some(Value::Text(method)) => method,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    let method = method.replace('_', "").to_lowercase();
+    !NON_IDEMPOTENT.contains(&method.as_str())
+}
+
+/// Backend requests forwarded to agents, by the backend connection
+/// that issued them, so that responses are returned to the issuing
+/// backend only.
+#[derive(Default, Debug)]
+pub struct InFlight {
+    next_backend: AtomicU64,
+    requests: Mutex<HashMap<(OrgId, AgentId, u64), Request>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Request {
+    backend: BackendConnId,
+    idempotent: bool,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_backend_id(&self) -> BackendConnId {
+        self.next_backend.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Record a request. Returns false, without recording it, if a
+    /// request with the same id is already in flight to the agent.
+    pub fn insert(
+        &self,
+        org: &OrgId,
+        agent: &AgentId,
+        req_id: u64,
+        backend: BackendConnId,
+        idempotent: bool,
+    ) -> bool {
+        match self.requests.lock().unwrap().entry((
+            org.clone(),
+            agent.clone(),
+            req_id,
+        )) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(ent) => {
+                ent.insert(Request {
+                    backend,
+                    idempotent,
+                });
+                true
+            }
+        }
+    }
+
+    pub fn take(
+        &self,
+        org: &OrgId,
+        agent: &AgentId,
+        req_id: u64,
+    ) -> Option<BackendConnId> {
+        self.requests
+            .lock()
+            .unwrap()
+            .remove(&(org.clone(), agent.clone(), req_id))
+            .map(|req| req.backend)
+    }
+
+    /// Hand the requests of a disconnected backend over to the
+    /// backend that took over. Idempotent requests are reassigned to
+    /// it; the others, or all of them if no backend took over, are
+    /// forgotten. Returns the agent and request id of the forgotten
+    /// requests.
+    pub fn remove_backend(
+        &self,
+        org: &OrgId,
+        backend: BackendConnId,
+        successor: Option<BackendConnId>,
+    ) -> Vec<(AgentId, u64)> {
+        let mut removed = Vec::new();
+        self.requests.lock().unwrap().retain(|(o, a, req_id), req| {
+            if o != org || req.backend != backend {
+                return true;
+            }
+            match successor {
+                Some(successor) if req.idempotent => {
+                    req.backend = successor;
+                    true
+                }
+                _ => {
+                    removed.push((a.clone(), *req_id));
+                    false
+                }
+            }
+        });
+        removed
+    }
+
+    /// Forget the requests forwarded to a disconnected agent. Returns
+    /// the request ids and the backends that issued them.
+    pub fn remove_agent(
+        &self,
+        org: &OrgId,
+        agent: &AgentId,
+    ) -> Vec<(u64, BackendConnId)> {
+        let mut removed = Vec::new();
+        self.requests.lock().unwrap().retain(|(o, a, req_id), req| {
+            match o == org && a == agent {
+                true => {
+                    removed.push((*req_id, req.backend));
+                    false
+                }
+                false => true,
+            }
+        });
+        removed
+    }
+}
+
+/// Remove a disconnected backend from the organization's node and
+/// fail over its requests to the standby backend that takes over, if
+/// any. Returns the backend that took over.
+pub fn remove_backend(
+    in_flight: &InFlight,
+    node: &mut Node<Value>,
+    org: &OrgId,
+    backend: BackendConnId,
+) -> Option<mpsc::Sender<BrokerToBackendMessage<Value>>> {
+    let successor = node.remove_backend(backend).cloned();
+    let successor_id = successor
+        .as_ref()
+        .and_then(|_| node.backends.first().map(|(id, _)| *id));
+    let failed = in_flight.remove_backend(org, backend, successor_id);
+    match &successor {
+        Some(successor) => {
+            for (agent_id, req_id) in failed {
+                if let Err(e) = successor.try_send(agent_error(
+                    agent_id,
+                    req_id,
+                    "backend disconnected before the agent responded",
+                )) {
+                    log::warn!(
+                        "failed to fail request over to backend {}: {}",
+                        &org.0,
+                        e
+                    );
+                }
+            }
+        }
+        None if !failed.is_empty() => log::warn!(
+            "Backend {} disconnected with {} request(s) in flight",
+            &org.0,
+            failed.len()
+        ),
+        None => {}
+    }
+    successor
+}
+
+/// An error response to a request for an agent.
+pub fn agent_error(
+    agent_id: AgentId,
+    req_id: u64,
+    error: &str,
+) -> BrokerToBackendMessage<Value> {
+    BrokerToBackendMessage::Agent {
+        agent_id,
+        message: AsyncResponse {
+            req_id,
+            response: serde_cbor::value::to_value::<
+                std::result::Result<(), &str>,
+            >(Err(error))
+            .unwrap(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_return_to_issuing_backend() {
+        let in_flight = InFlight::new();
+        let org = OrgId("org".to_string());
+        let agent = AgentId("agent".to_string());
+        let (a, b) = (in_flight.new_backend_id(), in_flight.new_backend_id());
+        assert_ne!(a, b);
+
+        assert!(in_flight.insert(&org, &agent, 1, a, true));
+        assert!(in_flight.insert(&org, &agent, 2, b, true));
+        /* Ids in use are not taken over by another backend. */
+        assert!(!in_flight.insert(&org, &agent, 1, b, true));
+        assert_eq!(in_flight.take(&org, &agent, 2), Some(b));
+        assert_eq!(in_flight.take(&org, &agent, 2), None);
+
+        assert_eq!(in_flight.remove_agent(&org, &agent), vec![(1, a)]);
+        assert_eq!(in_flight.take(&org, &agent, 1), None);
+    }
+
+    #[test]
+    fn idempotency_by_method() {
+        let method = |name: &str| Value::Text(name.to_string());
+        let call = |name: &str| {
+            Value::Map([(method(name), Value::Null)].into_iter().collect())
+        };
+        assert!(is_idempotent(&method("ping")));
+        assert!(is_idempotent(&call("get_etc_tables")));
+        assert!(!is_idempotent(&method("shutdown")));
+        assert!(!is_idempotent(&call("LoadPkg")));
+        assert!(!is_idempotent(&Value::Null));
+    }
+
+    #[test]
+    fn backend_drop_fails_over() {
+        let in_flight = InFlight::new();
+        let org = OrgId("org".to_string());
+        let agent = AgentId("agent".to_string());
+        let mut node = Node::default();
+        let (active, mut active_rx) = mpsc::channel(4);
+        let (standby, mut standby_rx) = mpsc::channel(4);
+        let (a, b) = (in_flight.new_backend_id(), in_flight.new_backend_id());
+        assert!(node.add_backend(a, active));
+        assert!(!node.add_backend(b, standby.clone()));
+
+        assert!(in_flight.insert(&org, &agent, 1, a, true));
+        assert!(in_flight.insert(&org, &agent, 2, a, false));
+
+        let successor = remove_backend(&in_flight, &mut node, &org, a);
+        assert!(successor.unwrap().same_channel(&standby));
+        assert!(node.is_active(b));
+        assert!(active_rx.try_recv().is_err());
+
+        /* The idempotent request's response goes to the new backend;
+         * the other request is failed back to it. */
+        assert_eq!(in_flight.take(&org, &agent, 1), Some(b));
+        match standby_rx.try_recv().unwrap() {
+            BrokerToBackendMessage::Agent { agent_id, message } => {
+                assert_eq!(agent_id, agent);
+                assert_eq!(message.req_id, 2);
+            }
+            _ => panic!("expected an agent response"),
+        }
+        assert!(standby_rx.try_recv().is_err());
+        assert_eq!(in_flight.take(&org, &agent, 2), None);
+
+        /* Without a backend left, requests are forgotten, so that a
+         * reconnecting backend can reuse their ids. */
+        assert!(in_flight.insert(&org, &agent, 3, b, true));
+        assert!(remove_backend(&in_flight, &mut node, &org, b).is_none());
+        assert!(node.backend().is_none());
+        let c = in_flight.new_backend_id();
+        assert!(node.add_backend(c, standby));
+        assert!(in_flight.insert(&org, &agent, 3, c, true));
+    }
+}
@@ -7,6 +7,7 @@ mod backend_handler;
 mod broker_service;
 mod database_handler;
 mod error;
+mod failover;
 mod node;
 mod ssh_connector;
 
@@ -23,6 +24,7 @@ use agent_handler::AgentHandler;
 use backend_handler::BackendHandler;
 use database_handler::DatabaseHandler;
 use error::{Error, Result};
+use failover::InFlight;
 use node::Node;
 
 use crate::broker_service::BrokerService;
@@ -136,6 +138,7 @@ async fn run(
         rpc::tls_server_config(&ca_path, &cert_path, &key_path).await?;
 
     let node_map = Arc::new(RwLock::new(HashMap::new()));
+    let in_flight = Arc::new(InFlight::new());
 
     let broker_handler =
         Arc::new(broker_api::BrokerHandler::new(BrokerService::new(
@@ -152,14 +155,14 @@ async fn run(
                 .tcp(agent_addr)
                 .await?
                 .tls(tls_config.clone())
-                .handler(AgentHandler::<Value>::new()),
+                .handler(AgentHandler::<Value>::new(in_flight.clone())),
         )
         .handler(
             rpc::AsyncBrokerHandlerBuilder::<Node<Value>, _>::new()
                 .tcp(backend_addr)
                 .await?
                 .tls(tls_config.clone())
-                .handler(BackendHandler::new(broker_handler)),
+                .handler(BackendHandler::new(broker_handler, in_flight)),
         )
         .handler(
             rpc::AsyncBrokerHandlerBuilder::<Node<Value>, _>::new()
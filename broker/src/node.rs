@@ -10,9 +10,13 @@ use broker_api::{
 };
 use tokio::sync::mpsc;
 
+use crate::failover::BackendConnId;
+
 #[derive(Clone, Debug)]
 pub struct Node<V> {
-    pub backend: Option<mpsc::Sender<BrokerToBackendMessage<V>>>,
+    /// Connected backends. The first one is active; the others are
+    /// standby.
+    pub backends: Vec<(BackendConnId, mpsc::Sender<BrokerToBackendMessage<V>>)>,
     pub database: Option<mpsc::Sender<BrokerToMetricsEngineMessage<V>>>,
     pub agents: HashMap<AgentId, mpsc::Sender<BrokerToAgentMessage<V>>>,
     pub agent_connection_info: HashMap<AgentId, AgentConnectionStatus>,
@@ -21,7 +25,7 @@ pub struct Node<V> {
 impl<V> Default for Node<V> {
     fn default() -> Self {
         Self {
-            backend: None,
+            backends: Vec::new(),
             database: None,
             agents: HashMap::new(),
             agent_connection_info: HashMap::new(),
@@ -29,6 +33,83 @@ impl<V> Default for Node<V> {
     }
 }
 
+impl<V> Node<V> {
+    /// The active backend.
+    pub fn backend(&self) -> Option<&mpsc::Sender<BrokerToBackendMessage<V>>> {
+        self.backends.first().map(|(_, sender)| sender)
+    }
+
+    pub fn backend_by_id(
+        &self,
+        id: BackendConnId,
+    ) -> Option<&mpsc::Sender<BrokerToBackendMessage<V>>> {
+        self.backends
+            .iter()
+            .find(|(backend, _)| *backend == id)
+            .map(|(_, sender)| sender)
+    }
+
+    pub fn is_active(&self, id: BackendConnId) -> bool {
+        self.backends.first().map(|(backend, _)| *backend) == Some(id)
+    }
+
+    /// Add a backend connection. Returns whether it became active.
+    pub fn add_backend(
+        &mut self,
+        id: BackendConnId,
+        sender: mpsc::Sender<BrokerToBackendMessage<V>>,
+    ) -> bool {
+        self.backends.push((id, sender));
+        self.backends.len() == 1
+    }
+
+    /// Remove a backend connection. Returns the standby backend that
+    /// took over, if the removed backend was active.
+    pub fn remove_backend(
+        &mut self,
+        id: BackendConnId,
+    ) -> Option<&mpsc::Sender<BrokerToBackendMessage<V>>> {
+        let pos = self
+            .backends
+            .iter()
+            .position(|(backend, _)| *backend == id)?;
+        self.backends.remove(pos);
+        match pos {
+            0 => self.backend(),
+            _ => None,
+        }
+    }
+}
+
 impl<V> rpc::BrokerNode for Node<V> {
     type Key = OrgId;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standby_backend_takes_over() {
+        let mut node = Node::<()>::default();
+        let (active, _active_rx) = mpsc::channel(1);
+        let (standby, _standby_rx) = mpsc::channel(1);
+
+        assert!(node.add_backend(1, active));
+        assert!(!node.add_backend(2, standby.clone()));
+        assert!(node.is_active(1));
+        assert!(!node.is_active(2));
+
+        assert!(node.remove_backend(2).is_none());
+        assert!(!node.add_backend(2, standby.clone()));
+
+        let promoted = node.remove_backend(1).unwrap();
+        assert!(promoted.same_channel(&standby));
+        assert!(node.backend_by_id(1).is_none());
+        assert!(node.backend().unwrap().same_channel(&standby));
+        assert!(node.is_active(2));
+
+        assert!(node.remove_backend(2).is_none());
+        assert!(node.backend().is_none());
+    }
+}
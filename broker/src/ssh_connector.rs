@@ -168,61 +168,21 @@ async fn ssh_connect(
         thrussh_keys::decode_secret_key(ssh_config.private_key.as_str(), None)
             .map_err(Error::KeyDecode)?,
     );
-    let mut session: Option<thrussh::client::Handle<ssh::Client>> = None;
-
-    for host_arg in ssh_config
+    let credentials = ssh::Credentials::PublicKey(key);
+    let jump_hosts = ssh_config
         .jump_hosts
         .iter()
-        .chain(std::iter::once(&ssh_config.host))
-    {
-        let host = ssh::Host::parse(host_arg)
-            .map_err(|e| Error::SshHostArg(host_arg.to_string(), e))?;
-        let conn_string = host.conn_string();
-        log::debug!("{}: Connecting to {}", &log_prefix, &conn_string);
-
-        let mut sess = match session {
-            None => thrussh::client::connect(
-                config.clone(),
-                &conn_string,
-                ssh::Client::new(),
-            )
-            .await
-            .map_err(|e| Error::SshConnect(host_arg.to_string(), e))?,
-            Some(mut sess) => {
-                let chan = sess
-                    .channel_open_direct_tcpip(
-                        host.host_name(),
-                        host.port(),
-                        server_name,
-                        server_port,
-                    )
-                    .await
-                    .map_err(|e| Error::SshChannel(host_arg.to_string(), e))?;
+        .map(|host_arg| hop(host_arg, &credentials))
+        .collect::<Result<Vec<_>>>()?;
+    let target = hop(&ssh_config.host, &credentials)?;
 
-                thrussh::client::connect_stream(
-                    config.clone(),
-                    ssh::Forward::new(chan),
-                    ssh::Client::new(),
-                )
-                .await
-                .map_err(|e| Error::SshConnect(host_arg.to_string(), e))?
-            }
-        };
-
-        if !sess
-            .authenticate_publickey(host.user(), key.clone())
-            .await
-            .map_err(|e| Error::SshAuthenticate(host_arg.to_string(), e))?
-        {
-            return Err(Error::SshAuthentication(host_arg.to_string()));
-        }
-
-        session = Some(sess);
-    }
+    log::debug!("{}: Connecting to {}", &log_prefix, &target.host);
+    let mut session = ssh::Client::connect(config, &jump_hosts, &target)
+        .await
+        .map_err(|e| Error::SshConnect(ssh_config.host.to_string(), e))?;
 
     log::debug!("{}: forwarding agent socket", &log_prefix);
 
-    let mut session = session.unwrap();
     let channel = session
         .channel_open_direct_tcpip(
             "localhost",
@@ -257,3 +217,14 @@ async fn ssh_connect(
     )
     .await?)
 }
+
+fn hop<'a>(
+    host_arg: &'a str,
+    credentials: &ssh::Credentials,
+) -> Result<ssh::Hop<'a>> {
+    Ok(ssh::Hop::new(
+        ssh::Host::parse(host_arg)
+            .map_err(|e| Error::SshHostArg(host_arg.to_string(), e))?,
+        credentials.clone(),
+    ))
+}
@@ -0,0 +1,96 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use flate2::write::GzEncoder;
+use flate2::GzBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Compression of the bulk files. Compressed files get an extra
+/// extension, from which the uploader derives the Content-Encoding
+/// header for the bulk request.
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+}
+
+/// A bulk file being written. The body is compressed while it is
+/// serialized, so that it is never held in memory as a whole.
+pub(crate) enum BulkWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Compression {
+    /// The extension added to the bulk file name.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+        }
+    }
+
+    /// The Content-Encoding of the bulk body.
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+        }
+    }
+
+    /// The file name for a bulk file, given its uncompressed name.
+    pub(crate) fn file_name(&self, name: &str) -> String {
+        match self.extension() {
+            Some(ext) => format!("{name}.{ext}"),
+            None => name.to_string(),
+        }
+    }
+
+    /// Start writing a bulk file. The gzip header carries the
+    /// uncompressed file name.
+    pub(crate) fn writer(&self, file: File, name: &str) -> BulkWriter {
+        let writer = BufWriter::new(file);
+        match self {
+            Self::None => BulkWriter::Plain(writer),
+            Self::Gzip => BulkWriter::Gzip(
+                GzBuilder::new()
+                    .filename(name)
+                    .write(writer, flate2::Compression::default()),
+            ),
+        }
+    }
+}
+
+impl BulkWriter {
+    /// Write the gzip trailer, if any, and flush the file.
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for BulkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
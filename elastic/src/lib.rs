@@ -2,10 +2,12 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+mod compression;
 mod error;
 mod output;
 mod state;
 
+pub use compression::Compression;
 pub use error::{Error, Result};
 pub use output::{write_events, write_output};
 pub use output::{ElasticFieldName, ElasticTableName};
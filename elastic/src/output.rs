@@ -7,12 +7,14 @@ use serde::Serialize;
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use super::compression::{BulkWriter, Compression};
 use super::error::Result;
 use super::state::State;
 use expression::EvalError;
+use value::addr::{DisplayIpv4, DisplayIpv6, DisplayMac};
 use value::Value;
 
 /* Table and field names, to be updated if
@@ -24,19 +26,11 @@ pub struct ElasticFieldName(pub String);
 
 pub fn write_events<T: Serialize>(
     base_dir: &Path,
+    compression: Compression,
     table: String,
     events: Vec<&T>,
 ) -> Result<()> {
-    fs::create_dir_all(base_dir)?;
-    let state = State::load(base_dir)?;
-
-    let path = base_dir.join(format!("{}.json", state.last_file_id));
-    let new_path = base_dir.join(format!("{}.json.new", state.last_file_id));
-    let file = OpenOptions::new()
-        .create_new(true)
-        .write(true)
-        .open(&new_path)?;
-    let mut writer = BufWriter::new(file);
+    let (path, new_path, mut writer) = create_file(base_dir, compression)?;
 
     for event in events {
         serde_json::to_writer(
@@ -48,12 +42,14 @@ pub fn write_events<T: Serialize>(
         writer.write_all(b"\n")?;
     }
 
+    writer.finish()?;
     fs::rename(&new_path, path)?;
     Ok(())
 }
 
 pub fn write_output(
     base_dir: &Path,
+    compression: Compression,
     host: &str,
     site: &str,
     data: &HashMap<
@@ -61,16 +57,7 @@ pub fn write_output(
         Vec<HashMap<ElasticFieldName, std::result::Result<Value, EvalError>>>,
     >,
 ) -> Result<()> {
-    fs::create_dir_all(base_dir)?;
-    let state = State::load(base_dir)?;
-
-    let path = base_dir.join(format!("{}.json", state.last_file_id));
-    let new_path = base_dir.join(format!("{}.json.new", state.last_file_id));
-    let file = OpenOptions::new()
-        .create_new(true)
-        .write(true)
-        .open(&new_path)?;
-    let mut writer = BufWriter::new(file);
+    let (path, new_path, mut writer) = create_file(base_dir, compression)?;
 
     let ts = Utc::now().to_rfc3339_opts(SecondsFormat::AutoSi, true);
 
@@ -78,10 +65,32 @@ pub fn write_output(
         write_table(writer.by_ref(), &ts, host, site, table_id, table_data)?;
     }
 
+    writer.finish()?;
     fs::rename(&new_path, path)?;
     Ok(())
 }
 
+/// Create the next bulk file. It is written under a temporary name,
+/// to be renamed once complete.
+fn create_file(
+    base_dir: &Path,
+    compression: Compression,
+) -> Result<(PathBuf, PathBuf, BulkWriter)> {
+    fs::create_dir_all(base_dir)?;
+    let state = State::load(base_dir)?;
+
+    let name = format!("{}.json", state.last_file_id);
+    let file_name = compression.file_name(&name);
+    let path = base_dir.join(&file_name);
+    let new_path = base_dir.join(format!("{file_name}.new"));
+    let file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&new_path)?;
+
+    Ok((path, new_path, compression.writer(file, &name)))
+}
+
 pub fn write_table<W: Write>(
     mut file: W,
     ts: &str,
@@ -145,6 +154,17 @@ pub fn write_table<W: Write>(
                                 serde_json::value::Value::Number(v),
                             )
                         }),
+                    Ok(Value::CompositeQuantity(v)) => v
+                        .clone()
+                        .normalize()
+                        .ok()
+                        .and_then(|v| serde_json::Number::from_f64(v.0))
+                        .map(|v| {
+                            (
+                                field_name.0.as_str(),
+                                serde_json::value::Value::Number(v),
+                            )
+                        }),
                     Ok(Value::Enum(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::String(
@@ -157,10 +177,22 @@ pub fn write_table<W: Write>(
                             v.get_value_str().to_string(),
                         ),
                     )),
+                    /* As a string, to keep all places. */
+                    Ok(Value::Decimal(v)) => Some((
+                        field_name.0.as_str(),
+                        serde_json::value::Value::String(v.to_string()),
+                    )),
                     Ok(Value::Boolean(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::Bool(*v),
                     )),
+                    Ok(Value::TriState(v)) => Some((
+                        field_name.0.as_str(),
+                        match v.to_bool() {
+                            Some(v) => serde_json::value::Value::Bool(v),
+                            None => serde_json::value::Value::Null,
+                        },
+                    )),
                     Ok(Value::Time(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::String(
@@ -179,28 +211,19 @@ pub fn write_table<W: Write>(
                     Ok(Value::MacAddress(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::String(
-                            v.iter()
-                                .map(|i| format!("{:02x}", i))
-                                .collect::<Vec<_>>()
-                                .join(":"),
+                            DisplayMac(&v).to_string(),
                         ),
                     )),
                     Ok(Value::Ipv4Address(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::String(
-                            v.iter()
-                                .map(|i| format!("{}", i))
-                                .collect::<Vec<_>>()
-                                .join("."),
+                            DisplayIpv4(&v).to_string(),
                         ),
                     )),
                     Ok(Value::Ipv6Address(v)) => Some((
                         field_name.0.as_str(),
                         serde_json::value::Value::String(
-                            v.iter()
-                                .map(|i| format!("{:x}", i))
-                                .collect::<Vec<_>>()
-                                .join(":"),
+                            DisplayIpv6(&v).to_string(),
                         ),
                     )),
                     Ok(Value::Option(_)) => None, // TODO!
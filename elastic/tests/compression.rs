@@ -0,0 +1,85 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use elastic::{write_output, Compression, ElasticFieldName, ElasticTableName};
+use flate2::read::GzDecoder;
+use value::Value;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "elastic_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn write(dir: &Path, compression: Compression) {
+    let data = HashMap::from([(
+        ElasticTableName(String::from("interfaces")),
+        (0..100)
+            .map(|i| {
+                HashMap::from([(
+                    ElasticFieldName(String::from("in_octets")),
+                    Ok(Value::Integer(i)),
+                )])
+            })
+            .collect(),
+    )]);
+    write_output(dir, compression, "h", "s", &data).unwrap();
+}
+
+#[test]
+fn uncompressed() {
+    let dir = temp_dir("uncompressed");
+    write(&dir, Compression::None);
+
+    let body = std::fs::read_to_string(dir.join("0.json")).unwrap();
+    assert_eq!(body.lines().count(), 200);
+    assert!(!dir.join("0.json.gz").exists());
+    assert!(!dir.join("0.json.new").exists());
+}
+
+#[test]
+fn gzip() {
+    let dir = temp_dir("gzip");
+    write(&dir, Compression::Gzip);
+    write(&dir, Compression::Gzip);
+
+    for id in 0..2 {
+        let path = dir.join(format!("{id}.json.gz"));
+        let mut decoder = GzDecoder::new(std::fs::File::open(path).unwrap());
+        let mut body = String::new();
+        decoder.read_to_string(&mut body).unwrap();
+
+        /* The header carries the name of the uncompressed file. */
+        assert_eq!(
+            decoder.header().and_then(|h| h.filename()),
+            Some(format!("{id}.json").as_bytes())
+        );
+        assert_eq!(body.lines().count(), 200);
+        for line in body.lines() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+    }
+
+    assert!(!dir.join("0.json").exists());
+    assert!(!dir.join("1.json.gz.new").exists());
+}
+
+#[test]
+fn content_encoding() {
+    assert_eq!(Compression::Gzip.content_encoding(), Some("gzip"));
+    assert_eq!(Compression::None.content_encoding(), None);
+    assert_eq!(
+        serde_json::from_str::<Compression>("\"gzip\"").unwrap(),
+        Compression::Gzip
+    );
+    assert_eq!(Compression::default(), Compression::None);
+}
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 
 use etc_base::FieldId;
 use expression::EvalError;
+use regex::Regex;
 use rule_engine::selector::ValueSelector;
 use serde::{Deserialize, Serialize};
 use value::Value;
@@ -15,9 +16,94 @@ use value::Value;
 pub struct ConfigRule {
     pub selectors: Vec<FieldSelector>,
     pub value: Value,
+    /// Restrict the rule to matching hosts. A rule with a host
+    /// condition and without selectors applies to all rows of the
+    /// matching hosts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<HostMatch>,
+}
+
+/// Host name condition of a config rule. Regexes are compiled when
+/// the package is loaded.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum HostMatch {
+    Exact(String),
+    Regex(#[serde(with = "agent_serde::regex")] Regex),
+}
+
+impl PartialEq for HostMatch {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (HostMatch::Exact(a), HostMatch::Exact(b)) => a == b,
+            (HostMatch::Regex(a), HostMatch::Regex(b)) => {
+                agent_serde::regex::compare(a, b)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl HostMatch {
+    pub fn matches(&self, host: &str) -> bool {
+        match self {
+            HostMatch::Exact(name) => name == host,
+            HostMatch::Regex(regex) => regex.is_match(host),
+        }
+    }
+}
+
+/// Find the value of the first matching rule for a host. Rules for an
+/// exact host name take precedence over rules matching the host by
+/// regex, which take precedence over rules without host condition.
+pub fn lookup_config_value<'a, I>(
+    rules: I,
+    host: &str,
+    row: &HashMap<FieldId, Result<Value, EvalError>>,
+) -> Option<Result<Value, EvalError>>
+where
+    I: IntoIterator<Item = &'a ConfigRule>,
+{
+    let mut rules = rules
+        .into_iter()
+        .filter(|rule| rule.matches_host(host))
+        .collect::<Vec<_>>();
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.specificity()));
+    rules
+        .into_iter()
+        .filter_map(|rule| rule.evaled_matches_host(host, row).transpose())
+        .next()
 }
 
 impl ConfigRule {
+    /// Whether the host condition, if any, matches.
+    pub fn matches_host(&self, host: &str) -> bool {
+        self.host.as_ref().map_or(true, |m| m.matches(host))
+    }
+
+    /// Rules with a more specific host condition take precedence.
+    fn specificity(&self) -> u8 {
+        match &self.host {
+            Some(HostMatch::Exact(_)) => 2,
+            Some(HostMatch::Regex(_)) => 1,
+            None => 0,
+        }
+    }
+
+    /// Like `evaled_matches`, taking the host condition into account.
+    pub fn evaled_matches_host(
+        &self,
+        host: &str,
+        row: &HashMap<FieldId, Result<value::Value, EvalError>>,
+    ) -> Result<Option<Value>, EvalError> {
+        match (self.matches_host(host), &self.host) {
+            (false, _) => Ok(None),
+            (true, Some(_)) if self.selectors.is_empty() => {
+                Ok(Some(self.value.clone()))
+            }
+            (true, _) => self.evaled_matches(row),
+        }
+    }
+
     pub fn evaled_matches(
         &self,
         row: &HashMap<FieldId, Result<value::Value, EvalError>>,
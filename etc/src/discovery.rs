@@ -0,0 +1,195 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use agent_utils::{DBObj, TryGetFrom};
+use etc_base::{FieldId, TableId};
+use expression::{EvalCell, EvalError, EvalResult, Expr};
+use value::{Data, Value};
+
+use super::error::{Error, Result};
+use super::etc::Etc;
+use super::table::TableSpec;
+
+/// Link from a child table (e.g. VM disks) to the parent table its
+/// items are discovered from (e.g. VMs).
+#[derive(Serialize, Deserialize, Clone, DBObj, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ParentSpec {
+    pub table: TableId,
+    /// Evaluates to the item id of the parent, in the child row.
+    pub item_id: Expr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveredItem {
+    pub id: String,
+    pub name: Option<String>,
+    pub parent: Option<String>,
+}
+
+impl TableSpec {
+    /// Calculate the items for the discovered rows of this table. For
+    /// child tables, only rows belonging to one of the discovered
+    /// `parents` are kept.
+    pub fn discover_items(
+        &self,
+        etc: &Etc,
+        rows: &[HashMap<FieldId, EvalResult>],
+        parents: Option<&[DiscoveredItem]>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Vec<DiscoveredItem>> {
+        let parent_ids = parents.map(|parents| {
+            parents
+                .iter()
+                .map(|item| item.id.as_str())
+                .collect::<HashSet<_>>()
+        });
+
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(field_id, val)| {
+                        let field = field_id.try_get_from(&etc.fields)?;
+                        Ok((
+                            field.name.as_str(),
+                            EvalCell::new_evaluated(val.clone()),
+                        ))
+                    })
+                    .collect::<Result<HashMap<_, _>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(rows
+            .iter()
+            .enumerate()
+            .filter_map(|(i, row)| {
+                self.row_item(i, row, parent_ids.as_ref(), warnings)
+            })
+            .collect())
+    }
+
+    /// Calculate the item for row `i`, given by field name. The item
+    /// id of a child table is prefixed with its parent's item id. If
+    /// `parent_ids` is given, rows whose parent is not in it are left
+    /// out.
+    pub fn row_item<'a>(
+        &self,
+        i: usize,
+        row: &'a HashMap<&'a str, EvalCell<'a, Data, Value>>,
+        parent_ids: Option<&HashSet<&str>>,
+        warnings: &mut Vec<String>,
+    ) -> Option<DiscoveredItem> {
+        let id = match &self.item_id {
+            None => self
+                .item_type
+                .as_ref()
+                .map_or("unknown", |(item_type, _)| item_type.as_str())
+                .to_string(),
+            Some(expr) => match eval_string(expr, row) {
+                Ok(id) => id,
+                Err(e) => {
+                    warnings.push(format!(
+                        "Warning: failed to calculate item_id for row {}: {}",
+                        i, e
+                    ));
+                    return None;
+                }
+            },
+        };
+
+        let name = self
+            .item_name
+            .as_ref()
+            .and_then(|expr| eval_string(expr, row).ok());
+
+        let parent = match &self.parent {
+            None => {
+                return Some(DiscoveredItem {
+                    id,
+                    name,
+                    parent: None,
+                })
+            }
+            Some(parent) => parent,
+        };
+
+        let parent_id = match eval_string(&parent.item_id, row) {
+            Ok(id) => id,
+            Err(e) => {
+                warnings.push(format!(
+                    "Warning: failed to calculate parent item_id for row {}: {}",
+                    i, e
+                ));
+                return None;
+            }
+        };
+
+        match parent_ids {
+            Some(ids) if !ids.contains(parent_id.as_str()) => None,
+            _ => Some(DiscoveredItem {
+                id: format!("{}/{}", parent_id, id),
+                name,
+                parent: Some(parent_id),
+            }),
+        }
+    }
+}
+
+impl Etc {
+    /// Order the given discovery tables so that every parent comes
+    /// before its children. Parent tables not in `tables` are added.
+    pub fn discovery_order<'a>(
+        &self,
+        tables: impl IntoIterator<Item = &'a TableId>,
+    ) -> Result<Vec<TableId>> {
+        let mut order = Vec::new();
+        let mut done = HashSet::new();
+
+        for table_id in tables {
+            let mut path = Vec::new();
+            let mut current = table_id;
+            while !done.contains(current) {
+                if path.contains(&current) {
+                    return Err(Error::ParentCycle(current.clone()));
+                }
+                path.push(current);
+                let table = current.try_get_from(&self.tables)?;
+                match &table.parent {
+                    Some(parent) => {
+                        let parent_table =
+                            parent.table.try_get_from(&self.tables)?;
+                        if !parent_table.discovery {
+                            return Err(Error::ParentNotDiscovered(
+                                current.clone(),
+                                parent.table.clone(),
+                            ));
+                        }
+                        current = &parent.table;
+                    }
+                    None => break,
+                }
+            }
+            for table_id in path.into_iter().rev() {
+                if done.insert(table_id) {
+                    order.push(table_id.clone());
+                }
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+fn eval_string<'a>(
+    expr: &Expr,
+    row: &'a HashMap<&'a str, EvalCell<'a, Data, Value>>,
+) -> std::result::Result<String, EvalError> {
+    expr.eval_in_row(Some(row), None)
+        .and_then(|id| Ok(id.into_string()?))
+}
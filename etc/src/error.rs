@@ -9,7 +9,7 @@ use thiserror::Error;
 
 #[cfg(feature = "tokio")]
 use super::spec::Spec;
-use etc_base::PackageName;
+use etc_base::{PackageName, TableId};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -17,6 +17,17 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error("Invalid package data in {0}: {1}")]
     PackageData(PackageName, serde_json::Error),
+    #[error(
+        "Unsupported package format version {1} in {0} (supported: up to {})",
+        crate::package::FORMAT_VERSION
+    )]
+    PackageFormatVersion(PackageName, u64),
+    #[error("Failed to migrate package {0} from format version {1}: {2}")]
+    PackageMigration(PackageName, u64, String),
+    #[error("{1} (parent of {0}) is not a discovery table")]
+    ParentNotDiscovered(TableId, TableId),
+    #[error("Cyclic parent relationship for {0}")]
+    ParentCycle(TableId),
     #[error("{0}")]
     Utils(#[from] agent_utils::Error),
     #[error("Protocol error: {0}")]
@@ -2,16 +2,16 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use tokio::sync::{watch, RwLock};
 
 use agent_utils::TryAppend;
-use etc_base::{PackageName, PackageVersion};
+use etc_base::{PackageName, PackageVersion, Protocol};
 use protocol::PluginManager;
 
-use super::error::{Error, Result};
+use super::error::Result;
 use super::etc::Etc;
 use super::package::Package;
 use super::spec::Spec;
@@ -41,8 +41,7 @@ impl EtcManager {
         spec: String,
         plugins: &PluginManager,
     ) -> Result<()> {
-        let spec = serde_json::from_str(&spec)
-            .map_err(|e| Error::PackageData(name.clone(), e))?;
+        let spec = Package::from_json(&name, &spec)?;
         let mut packages = self.packages.read().await.clone();
         packages.insert(name.clone(), (version, spec));
         self.reload_pkgs(packages, plugins).await
@@ -58,6 +57,40 @@ impl EtcManager {
         self.reload_pkgs(packages, plugins).await
     }
 
+    /// Add or replace a single package. Unlike `load_pkg`, plugin
+    /// inputs are only reloaded for the protocols the old or new
+    /// version of the package provides input for; the inputs of other
+    /// protocols are shared with the current spec. Queries running
+    /// against the current spec keep using it until they finish.
+    pub async fn replace_pkg(
+        &self,
+        name: PackageName,
+        version: PackageVersion,
+        spec: String,
+        plugins: &PluginManager,
+    ) -> Result<()> {
+        let pkg = Package::from_json(&name, &spec)?;
+        let mut packages = self.packages.write().await;
+        let mut new_packages = packages.clone();
+        new_packages.insert(name.clone(), (version, pkg));
+        self.reload_pkg(&mut packages, new_packages, &name, plugins)
+            .await
+    }
+
+    /// Remove a single package, reloading only the inputs of the
+    /// protocols it provided input for.
+    pub async fn remove_pkg(
+        &self,
+        name: PackageName,
+        plugins: &PluginManager,
+    ) -> Result<()> {
+        let mut packages = self.packages.write().await;
+        let mut new_packages = packages.clone();
+        new_packages.remove(&name);
+        self.reload_pkg(&mut packages, new_packages, &name, plugins)
+            .await
+    }
+
     pub async fn loaded_pkgs(
         &self,
     ) -> Result<HashMap<PackageName, PackageVersion>> {
@@ -91,10 +124,7 @@ impl EtcManager {
             etc.try_append(spec.etc.clone())?;
         }
 
-        let spec = Spec {
-            etc,
-            input: plugins.load_inputs(inputs).await?,
-        };
+        let spec = Spec::new(etc, plugins.load_inputs(inputs).await?);
 
         let mut packages_write = self.packages.write().await;
 
@@ -103,4 +133,51 @@ impl EtcManager {
 
         Ok(())
     }
+
+    /// Build the spec for `new_packages`, which differ from `packages`
+    /// only in package `name`. The etc objects are merged again, since
+    /// definitions may be shared between packages.
+    async fn reload_pkg(
+        &self,
+        packages: &mut HashMap<PackageName, (PackageVersion, Package)>,
+        new_packages: HashMap<PackageName, (PackageVersion, Package)>,
+        name: &PackageName,
+        plugins: &PluginManager,
+    ) -> Result<()> {
+        let affected: HashSet<Protocol> = packages
+            .get(name)
+            .into_iter()
+            .chain(new_packages.get(name))
+            .flat_map(|(_, pkg)| pkg.input.keys().cloned())
+            .collect();
+
+        let mut inputs = Vec::new();
+        let mut etc = Etc::default();
+
+        for (_, pkg) in new_packages.values() {
+            inputs.push(
+                pkg.input
+                    .iter()
+                    .filter(|(proto, _)| affected.contains(*proto))
+                    .map(|(proto, input)| (proto.clone(), input.clone()))
+                    .collect(),
+            );
+            etc.try_append(pkg.etc.clone())?;
+        }
+
+        let mut input: HashMap<_, _> = self
+            .spec()
+            .await
+            .input
+            .iter()
+            .filter(|(proto, _)| !affected.contains(*proto))
+            .map(|(proto, input)| (proto.clone(), input.clone()))
+            .collect();
+        input.extend(plugins.load_inputs(inputs).await?);
+
+        self.spec_sender.send(Arc::new(Spec::new(etc, input)))?;
+        *packages = new_packages;
+
+        Ok(())
+    }
 }
@@ -7,12 +7,14 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use agent_utils::DBObj;
-use etc_base::Row;
-use expression::{EvalCell, Expr};
+use etc_base::{DataFieldId, Row};
+use expression::{EvalCell, EvalError, Expr};
+use protocol::REDACTED;
 use unit::{DecPrefix, DimensionlessUnit, Unit};
-use value::{Data, DataError, Type, Value};
+use value::{Data, DataError, EnumRepr, Type, Value};
 
 use crate::event_category::EventCategory;
+use crate::query_mode::QueryMode;
 use crate::source::Source2;
 
 use super::source::Source;
@@ -61,6 +63,36 @@ pub struct FieldSpec {
     pub units: Option<Vec<Unit>>,
     #[serde(default)]
     pub expose_configrules: ExposeConfigRules,
+    /// Sensitive values are masked or left out when writing output.
+    /// They remain available to thresholds and formulas. Fields taking
+    /// their value from a sensitive data field are marked sensitive
+    /// when the spec is loaded (see `Spec::new`).
+    #[serde(default = "default_false")]
+    pub sensitive: bool,
+    #[serde(default)]
+    pub redaction: Redaction,
+    /// Representation of integer enum values in output.
+    #[serde(default)]
+    pub enum_repr: EnumRepr,
+    /// Conditions that must all hold for the field to be part of the
+    /// table; otherwise it is left out of the computed table entirely.
+    #[serde(default)]
+    pub conditions: Vec<FieldCondition>,
+}
+
+/// A condition for including a field in a table.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldCondition {
+    /// Include in these query modes only.
+    Modes(Vec<QueryMode>),
+    /// Include if the device provides the data field, ie. if it has a
+    /// value in at least one row of the query result.
+    Capability(DataFieldId),
+    /// Include if the expression is true for at least one row of the
+    /// query result. Data fields are referenced as in computed query
+    /// fields, e.g. `${SNMP.ifHighSpeed}`.
+    Expr(Expr),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -96,8 +128,68 @@ pub enum ExposeConfigRules {
     Both,
 }
 
+/// How a sensitive field is written to output targets.
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    smart_default::SmartDefault,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Redaction {
+    /// Write a placeholder instead of the value.
+    #[default]
+    Mask,
+    /// Leave the field out.
+    Exclude,
+}
+
 impl FieldSpec {
-    const DEFAULT_EXPR: Expr = Expr::Data;
+    pub(crate) const DEFAULT_EXPR: Expr = Expr::Data;
+
+    /// Whether the field is left out of output targets. Only string
+    /// fields can be masked: a placeholder would not match the type
+    /// of other fields, so these are always left out.
+    pub fn is_excluded(&self) -> bool {
+        self.sensitive
+            && (self.redaction == Redaction::Exclude
+                || !matches!(
+                    self.input_type,
+                    Type::UnicodeString | Type::BinaryString
+                ))
+    }
+
+    /// The value to write to output targets for this field.
+    pub fn output_value(&self, value: Value) -> Value {
+        match self.sensitive {
+            true => Value::UnicodeString(REDACTED.to_string()),
+            false => value,
+        }
+    }
+
+    /// The output fields for a value of this field, with integer enums
+    /// represented according to `enum_repr`.
+    pub fn output_json_fields(
+        &self,
+        name: String,
+        value: Result<Value, String>,
+        display_unit: Option<Unit>,
+    ) -> Vec<(String, Result<serde_json::Value, String>)> {
+        match value.and_then(|v| {
+            self.output_value(v).to_json_fields(
+                &name,
+                display_unit,
+                self.enum_repr,
+            )
+        }) {
+            Ok(fields) => fields.into_iter().map(|(n, v)| (n, Ok(v))).collect(),
+            Err(e) => vec![(name, Err(e))],
+        }
+    }
 
     pub fn field_expr<'a>(&'a self, row: &Row) -> EvalCell<'a, Data, Value> {
         match &self.source2 {
@@ -106,19 +198,76 @@ impl FieldSpec {
         }
     }
 
+    /// Whether the field's conditions allow it in this query mode.
+    pub fn included_in_mode(&self, query_mode: QueryMode) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds_for_mode(query_mode))
+    }
+
+    /// Whether the field's conditions allow it for the query result.
+    pub fn included_for_data(&self, rows: &[Row]) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds_for_data(rows))
+    }
+
     pub fn event_category(&self) -> EventCategory {
         match self.event_category {
             Some(cat) => cat,
             None => match &self.input_type {
-                Type::Integer | Type::Float | Type::Quantity(_) => {
-                    EventCategory::Performance
-                }
+                Type::Integer
+                | Type::Float
+                | Type::Decimal
+                | Type::Quantity(_) => EventCategory::Performance,
                 _ => EventCategory::Availability,
             },
         }
     }
 }
 
+impl FieldCondition {
+    /// Whether the condition holds in this query mode. Conditions on
+    /// data hold until the data is known.
+    pub fn holds_for_mode(&self, query_mode: QueryMode) -> bool {
+        match self {
+            Self::Modes(modes) => modes.contains(&query_mode),
+            Self::Capability(_) | Self::Expr(_) => true,
+        }
+    }
+
+    /// Whether the condition holds for the query result.
+    pub fn holds_for_data(&self, rows: &[Row]) -> bool {
+        match self {
+            Self::Modes(_) => true,
+            Self::Capability(field_id) => rows
+                .iter()
+                .any(|row| matches!(row.get(field_id), Some(Ok(_)))),
+            Self::Expr(expr) => rows.iter().any(|row| {
+                let names = row
+                    .keys()
+                    .map(|field_id| (field_id, query::variable_name(field_id)))
+                    .collect::<HashMap<_, _>>();
+                let vars = row
+                    .iter()
+                    .map(|(field_id, value)| {
+                        (
+                            names[field_id].as_str(),
+                            EvalCell::new_evaluated(
+                                value.clone().map_err(EvalError::DataError),
+                            ),
+                        )
+                    })
+                    .collect();
+                matches!(
+                    expr.eval_in_row(Some(&vars), None),
+                    Ok(Value::Boolean(true))
+                )
+            }),
+        }
+    }
+}
+
 impl RelativeDisplayType {
     pub fn display_unit(&self) -> Unit {
         match self {
@@ -148,6 +297,11 @@ impl Source2 {
                 expr.as_ref().unwrap_or(&FieldSpec::DEFAULT_EXPR),
                 Some(Err(DataError::Missing)),
             ),
+            /* Resolved by the table; see `Lookups`. */
+            Source2::Lookup(_) => EvalCell::new(
+                &FieldSpec::DEFAULT_EXPR,
+                Some(Err(DataError::Missing)),
+            ),
         }
     }
 }
@@ -0,0 +1,101 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use agent_utils::DBObj;
+use etc_base::FieldId;
+use expression::{Aggregate, EvalError, EvalResult};
+use value::{DataError, Value};
+
+/// Summarize a table into one row per distinct combination of the
+/// group-by fields. Without group-by fields, the whole table is
+/// summarized into a single row.
+#[derive(Serialize, Deserialize, Clone, DBObj, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct GroupBySpec {
+    #[serde(default)]
+    pub fields: Vec<FieldId>,
+    pub aggregates: Vec<AggregateSpec>,
+}
+
+#[derive(Serialize, Deserialize, Clone, DBObj, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct AggregateSpec {
+    /// The field in the summary row.
+    pub field: FieldId,
+    pub function: Aggregate,
+    /// The field to aggregate. When unset, `count` counts rows.
+    pub source: Option<FieldId>,
+}
+
+type TableRow = HashMap<FieldId, EvalResult>;
+
+impl GroupBySpec {
+    /// Calculate the summary rows. Groups are returned in order of
+    /// first appearance. Rows where a group-by field fails to evaluate
+    /// are grouped under a missing value for that field.
+    pub fn summarize(&self, rows: &[TableRow]) -> Vec<TableRow> {
+        let mut groups: Vec<(Vec<EvalResult>, Vec<&TableRow>)> = Vec::new();
+
+        if self.fields.is_empty() {
+            groups.push((Vec::new(), Vec::new()));
+        }
+
+        for row in rows {
+            let key: Vec<EvalResult> = self
+                .fields
+                .iter()
+                .map(|field_id| match row.get(field_id) {
+                    Some(Ok(value)) => Ok(value.clone()),
+                    _ => Err(EvalError::DataError(DataError::Missing)),
+                })
+                .collect();
+            match groups.iter_mut().find(|(k, _)| key_eq(k, &key)) {
+                Some((_, members)) => members.push(row),
+                None => groups.push((key, vec![row])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(key, members)| {
+                let mut summary: TableRow =
+                    self.fields.iter().cloned().zip(key).collect();
+                summary.extend(self.aggregates.iter().map(|aggregate| {
+                    (aggregate.field.clone(), aggregate.eval(&members))
+                }));
+                summary
+            })
+            .collect()
+    }
+}
+
+impl AggregateSpec {
+    fn eval(&self, rows: &[&TableRow]) -> EvalResult {
+        match &self.source {
+            Some(source) => self.function.eval(rows.iter().map(|row| {
+                row.get(source)
+                    .cloned()
+                    .unwrap_or(Err(EvalError::DataError(DataError::Missing)))
+            })),
+            None => match self.function {
+                Aggregate::Count => Ok(Value::Integer(rows.len() as i64)),
+                _ => Err(EvalError::TypeError(
+                    "aggregate requires a source field (except for 'count')",
+                )),
+            },
+        }
+    }
+}
+
+fn key_eq(a: &[EvalResult], b: &[EvalResult]) -> bool {
+    a.iter().zip(b).all(|(a, b)| match (a, b) {
+        (Ok(a), Ok(b)) => a.literal_eq(b),
+        (Err(_), Err(_)) => true,
+        _ => false,
+    })
+}
@@ -9,35 +9,50 @@ mod spec;
 
 mod check;
 mod config_rule;
+mod discovery;
 mod etc;
 mod field;
+mod group_by;
 mod mp;
+mod simulate;
 mod source;
 mod table;
 mod threshold;
 //mod selector;
 mod query_mode;
+mod samples;
 
 mod error;
 mod event_category;
 mod layer;
+mod lookup;
 
 #[cfg(feature = "tokio")]
 pub use etc_manager::EtcManager;
-pub use package::Package;
-pub use spec::Spec;
+pub use package::{Package, FORMAT_VERSION};
+pub use spec::{DataUsage, Spec};
 
 pub use crate::etc::Etc;
 pub use check::CheckSpec;
+pub use discovery::{DiscoveredItem, ParentSpec};
 pub use event_category::EventCategory;
-pub use field::{FieldSpec, RelativeDisplayType, TimeDisplayType};
+pub use field::{
+    FieldCondition, FieldSpec, Redaction, RelativeDisplayType,
+    TimeDisplayType,
+};
+pub use group_by::{AggregateSpec, GroupBySpec};
 pub use layer::Layer;
+pub use lookup::LookupSpec;
 pub use mp::MPSpec;
 pub use query_mode::QueryMode;
+pub use samples::{percentile, SampleDb};
+pub use simulate::{simulate, ItemState, Sample, Simulation};
 pub use source::{Source, Source2};
 pub use table::TableSpec;
-pub use threshold::{ThresholdLevel, ThresholdSpec};
+pub use threshold::{
+    ThresholdBound, ThresholdError, ThresholdLevel, ThresholdOp, ThresholdSpec,
+};
 
 pub use error::{Error, Result};
 
-pub use config_rule::ConfigRule;
+pub use config_rule::{lookup_config_value, ConfigRule, HostMatch};
@@ -0,0 +1,135 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use agent_utils::TryGetFrom;
+use etc_base::{FieldId, Row, TableId};
+use expression::{EvalCell, EvalError, EvalResult, Expr};
+use protocol::DataMap;
+use value::{Data, DataError, Value};
+
+use super::error::Result;
+use super::etc::Etc;
+use super::field::FieldSpec;
+use super::source::Source2;
+
+/// A value taken from a related row in another table, e.g. the name
+/// of an interface's parent device. The related table is calculated
+/// from the same data, so it must be queried by the same check.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct LookupSpec {
+    /// The related table.
+    pub table: TableId,
+    /// The key of the related row, evaluated on the query row. Data
+    /// fields are referenced as in computed query fields, e.g.
+    /// `${SNMP.ifIndex}`.
+    pub key: Expr,
+    /// The field of the related table to match the key against.
+    pub key_field: FieldId,
+    /// The field of the related table to take the value from.
+    pub field: FieldId,
+}
+
+/// The related rows for the lookup fields of a table, as pairs of
+/// key and value.
+pub(crate) struct Lookups<'a> {
+    fields: HashMap<&'a FieldId, Vec<(Value, EvalResult)>>,
+}
+
+impl<'a> Lookups<'a> {
+    /// No lookups; lookup fields evaluate to missing.
+    pub(crate) fn none() -> Self {
+        Self {
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Calculate the related tables for the lookup fields. Lookups
+    /// are not resolved in the related tables themselves, so that
+    /// tables can refer to each other.
+    pub(crate) fn new(
+        fields: &[(&'a FieldId, &'a FieldSpec)],
+        etc: &Etc,
+        data: &DataMap,
+    ) -> Result<Self> {
+        let mut tables = HashMap::new();
+        let mut lookups = HashMap::new();
+
+        for (field_id, field) in fields {
+            let spec = match &field.source2 {
+                Some(Source2::Lookup(spec)) => spec,
+                _ => continue,
+            };
+
+            if !tables.contains_key(&spec.table) {
+                let table = spec.table.try_get_from(&etc.tables)?;
+                tables.insert(&spec.table, table.lookup_rows(etc, data)?);
+            }
+            let rows = &tables[&spec.table];
+
+            lookups.insert(
+                *field_id,
+                rows.iter()
+                    .filter_map(|row| {
+                        let key = row.get(&spec.key_field)?.as_ref().ok()?;
+                        let value = row.get(&spec.field).cloned().unwrap_or(
+                            Err(EvalError::DataError(DataError::Missing)),
+                        );
+                        Some((key.clone(), value))
+                    })
+                    .collect(),
+            );
+        }
+
+        Ok(Self { fields: lookups })
+    }
+
+    /// The value for a lookup field in a query row; missing if the
+    /// related row does not exist.
+    pub(crate) fn resolve(
+        &self,
+        field_id: &FieldId,
+        spec: &LookupSpec,
+        row: &Row,
+    ) -> Data {
+        let rows = self.fields.get(field_id).ok_or(DataError::Missing)?;
+        let key = eval_key(&spec.key, row).map_err(into_data_error)?;
+        rows.iter()
+            .find(|(k, _)| *k == key)
+            .ok_or(DataError::Missing)?
+            .1
+            .clone()
+            .map_err(into_data_error)
+    }
+}
+
+fn eval_key(expr: &Expr, row: &Row) -> EvalResult {
+    let names = row
+        .keys()
+        .map(|field_id| (field_id, query::variable_name(field_id)))
+        .collect::<HashMap<_, _>>();
+    let vars = row
+        .iter()
+        .map(|(field_id, value)| {
+            (
+                names[field_id].as_str(),
+                EvalCell::new_evaluated(
+                    value.clone().map_err(EvalError::DataError),
+                ),
+            )
+        })
+        .collect();
+    expr.eval_in_row(Some(&vars), None)
+}
+
+fn into_data_error(err: EvalError) -> DataError {
+    match err {
+        EvalError::DataError(e) => e,
+        e => DataError::External(e.to_string()),
+    }
+}
@@ -10,10 +10,23 @@ use serde::{
 };
 use serde_json::value::RawValue;
 
-use etc_base::Protocol;
+use etc_base::{PackageName, Protocol};
 
+use super::error::{Error as EtcError, Result as EtcResult};
 use super::etc::Etc;
 
+/// The current on-disk package format version. Packages without a
+/// "FormatVersion" key predate versioning and are treated as version 1.
+pub const FORMAT_VERSION: u64 = 2;
+
+/// Migration from on-disk format version `n` to `n + 1`, operating on
+/// the untyped JSON representation.
+type Migration =
+    fn(serde_json::Value) -> std::result::Result<serde_json::Value, String>;
+
+/// Registered migrations, indexed by the version they upgrade from.
+const MIGRATIONS: &[(u64, Migration)] = &[(1, migrate_v1_v2)];
+
 /// On-disk representation of EventTypeCatalog definitions.
 /// It contains the subset needed to run the agent for one
 /// or more Monitoring Packs.
@@ -32,6 +45,86 @@ pub struct Package {
     /// Etc Objects.
     #[serde(flatten)]
     pub etc: Etc,
+    /// Always written as the current version.
+    #[serde(rename = "FormatVersion")]
+    format_version: FormatVersion,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+struct FormatVersion;
+
+impl Serialize for FormatVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(FORMAT_VERSION)
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(rename = "FormatVersion", default = "legacy_format_version")]
+    format_version: u64,
+}
+
+const fn legacy_format_version() -> u64 {
+    1
+}
+
+impl Package {
+    /// Load a package from its on-disk JSON representation, migrating
+    /// older format versions to the current one.
+    pub fn from_json(name: &PackageName, data: &str) -> EtcResult<Self> {
+        let version = serde_json::from_str::<VersionProbe>(data)
+            .map_err(|e| EtcError::PackageData(name.clone(), e))?
+            .format_version;
+
+        if version == FORMAT_VERSION {
+            return serde_json::from_str(data)
+                .map_err(|e| EtcError::PackageData(name.clone(), e));
+        }
+
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(EtcError::PackageFormatVersion(name.clone(), version));
+        }
+
+        let mut value = serde_json::from_str(data)
+            .map_err(|e| EtcError::PackageData(name.clone(), e))?;
+        for (from, migrate) in MIGRATIONS.iter().filter(|(v, _)| *v >= version)
+        {
+            log::debug!(
+                "migrating package {} from format version {} to {}",
+                name,
+                from,
+                from + 1
+            );
+            value = migrate(value).map_err(|e| {
+                EtcError::PackageMigration(name.clone(), *from, e)
+            })?;
+        }
+
+        serde_json::from_value(value)
+            .map_err(|e| EtcError::PackageData(name.clone(), e))
+    }
+}
+
+/// Version 1 packages still carried the data tables and fields. These
+/// duplicate what the protocol plugins derive from their own "Input"
+/// section (which is kept as-is), and were already ignored on load
+/// before format versions existed, so dropping them loses nothing
+/// the agent uses.
+fn migrate_v1_v2(
+    mut value: serde_json::Value,
+) -> std::result::Result<serde_json::Value, String> {
+    let pkg = value
+        .as_object_mut()
+        .ok_or_else(|| "expected a package object".to_string())?;
+    pkg.remove("DataTables");
+    pkg.remove("DataFields");
+    pkg.remove("DataTableFields");
+    pkg.insert("FormatVersion".to_string(), serde_json::json!(2));
+    Ok(value)
 }
 
 /* Manual Deserialize implementation to get correct linenumbers on
@@ -41,6 +134,7 @@ pub struct Package {
 struct PackageVisitor;
 
 const FIELDS: &[&str] = &[
+    "FormatVersion",
     "Input",
     "MPs",
     "Checks",
@@ -83,6 +177,17 @@ impl<'de> Visitor<'de> for PackageVisitor {
 
         while let Some(key) = map.next_key::<&str>()? {
             match key {
+                /* Unversioned packages are accepted as-is; use
+                 * Package::from_json to migrate older formats. */
+                "FormatVersion" => match map.next_value::<u64>()? {
+                    FORMAT_VERSION => {}
+                    v => {
+                        return Err(A::Error::custom(format!(
+                            "unsupported package format version {v} \
+                             (expected {FORMAT_VERSION})"
+                        )))
+                    }
+                },
                 "Input" => match input.is_some() {
                     true => return Err(A::Error::duplicate_field("Input")),
                     false => {
@@ -146,13 +251,7 @@ impl<'de> Visitor<'de> for PackageVisitor {
                     .ok_or_else(|| A::Error::missing_field("Fields"))?,
                 config_rules: config_rules.unwrap_or_default(),
             },
+            format_version: FormatVersion,
         })
     }
 }
-
-/*impl Package {
-    /// Load from JSON file
-    pub fn from_file<R: Read>(file: R) -> io::Result<Self> {
-        Ok(serde_json::from_reader(file)?)
-    }
-}*/
@@ -0,0 +1,130 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::io::{ErrorKind, Result};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use agent_utils::FileLock;
+
+use super::threshold::ThresholdLevel;
+
+/// Recent samples and threshold levels per field, kept across runs to
+/// evaluate percentile and hysteresis thresholds. Like the counter
+/// database, state is read from the previous run and the state to
+/// keep is written on save.
+pub struct SampleDb {
+    sample_file: PathBuf,
+    old_state: State,
+    new_state: Mutex<State>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    #[serde(default)]
+    samples: HashMap<String, Vec<(SystemTime, f64)>>,
+    #[serde(default)]
+    levels: HashMap<String, LevelState>,
+}
+
+/// The reported level of a hysteresis threshold, and the number of
+/// consecutive evaluations that resulted in the last evaluated level.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct LevelState {
+    pub level: Option<ThresholdLevel>,
+    pub last: Option<ThresholdLevel>,
+    pub count: u32,
+}
+
+impl SampleDb {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            sample_file: path,
+            old_state: State::default(),
+            new_state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut samples = Self::new(path);
+        let _lock = samples.lock()?;
+        samples.old_state = match std::fs::read(&samples.sample_file) {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                log::warn!("Unable to deserialize samples: {e}");
+                State::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => State::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(samples)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.sample_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let _lock = self.lock()?;
+        std::fs::write(
+            &self.sample_file,
+            serde_json::to_vec(&*self.new_state.lock().unwrap())?,
+        )
+    }
+
+    /// Add a sample and return the samples within `window` of `now`,
+    /// including the new one. Older samples are dropped.
+    pub fn add(
+        &self,
+        key: String,
+        value: f64,
+        now: SystemTime,
+        window: Duration,
+    ) -> Vec<f64> {
+        let mut samples: Vec<(SystemTime, f64)> = self
+            .old_state
+            .samples
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .filter(|(t, _)| {
+                matches!(now.duration_since(*t), Ok(age) if age <= window)
+            })
+            .cloned()
+            .collect();
+        samples.push((now, value));
+        let values = samples.iter().map(|(_, v)| *v).collect();
+        self.new_state.lock().unwrap().samples.insert(key, samples);
+        values
+    }
+
+    /// The level state from the previous run.
+    pub(crate) fn level(&self, key: &str) -> LevelState {
+        self.old_state.levels.get(key).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn set_level(&self, key: String, state: LevelState) {
+        self.new_state.lock().unwrap().levels.insert(key, state);
+    }
+
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.sample_file, FileLock::DEFAULT_TIMEOUT)
+            .map_err(std::io::Error::other)
+    }
+}
+
+/// The nearest-rank percentile (0-100) of a set of samples.
+pub fn percentile(samples: &[f64], percentile: u8) -> Option<f64> {
+    let mut sorted: Vec<f64> =
+        samples.iter().copied().filter(|v| !v.is_nan()).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let rank = (percentile.min(100) as f64 / 100.0 * sorted.len() as f64).ceil()
+        as usize;
+    Some(sorted[rank.max(1) - 1])
+}
@@ -0,0 +1,91 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use value::Data;
+
+use super::threshold::{ThresholdError, ThresholdLevel, ThresholdSpec};
+
+/// A recent observation of a thresholded field, as retrieved from the
+/// state store, together with the other fields of its row.
+#[derive(Clone, Debug)]
+pub struct Sample<'a> {
+    pub item: String,
+    pub value: Data,
+    pub row: HashMap<&'a str, Data>,
+}
+
+/// The outcome of evaluating a proposed threshold against recent data.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Simulation {
+    /// Level per item under the proposed threshold.
+    pub states: Vec<ItemState>,
+    /// Items for which the proposed threshold yields a different
+    /// level than the current one.
+    pub changes: Vec<ItemState>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ItemState {
+    pub item: String,
+    pub current: Option<ThresholdLevel>,
+    pub proposed: Option<ThresholdLevel>,
+}
+
+impl ItemState {
+    /// Whether the item would move to a more severe level.
+    pub fn is_escalation(&self) -> bool {
+        severity(&self.proposed) > severity(&self.current)
+    }
+}
+
+/// Evaluate `proposed` against recent samples and compare the result
+/// with the levels produced by the `current` threshold (if any).
+/// Samples without a value do not trigger either threshold.
+pub fn simulate(
+    current: Option<&ThresholdSpec>,
+    proposed: &ThresholdSpec,
+    samples: &[Sample],
+) -> Result<Simulation, ThresholdError> {
+    let states = samples
+        .iter()
+        .map(|sample| {
+            Ok(ItemState {
+                item: sample.item.clone(),
+                current: match current {
+                    Some(spec) => level(spec, sample)?,
+                    None => None,
+                },
+                proposed: level(proposed, sample)?,
+            })
+        })
+        .collect::<Result<Vec<_>, ThresholdError>>()?;
+    let changes = states
+        .iter()
+        .filter(|state| state.current != state.proposed)
+        .cloned()
+        .collect();
+    Ok(Simulation { states, changes })
+}
+
+fn level(
+    spec: &ThresholdSpec,
+    sample: &Sample,
+) -> Result<Option<ThresholdLevel>, ThresholdError> {
+    match &sample.value {
+        Ok(value) => spec.eval(value, &sample.row),
+        Err(_) => Ok(None),
+    }
+}
+
+fn severity(level: &Option<ThresholdLevel>) -> u8 {
+    match level {
+        None => 0,
+        Some(ThresholdLevel::Warning) => 1,
+        Some(ThresholdLevel::Critical) => 2,
+    }
+}
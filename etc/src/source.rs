@@ -7,6 +7,8 @@ use serde::{Deserialize, Deserializer, Serialize};
 use etc_base::{DataFieldId, DataTableId, ProtoDataFieldId};
 use expression::Expr;
 
+use super::lookup::LookupSpec;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Source {
     #[serde(deserialize_with = "deserialize_data")]
@@ -22,6 +24,8 @@ pub enum Source2 {
     Data(DataTableId, DataFieldId, Option<Expr>),
     Formula(Expr),
     Config(Option<Expr>),
+    /// Value from a related row in another table.
+    Lookup(LookupSpec),
 }
 
 #[derive(Serialize, Deserialize)]
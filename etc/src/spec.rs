@@ -2,17 +2,43 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use agent_utils::TryGetFrom;
 use etc_base::{DataFieldId, DataTableId, Protocol, QueryMap, TableId};
-use protocol::Input;
+use protocol::{DataTableSpec, Input};
 use query::{KeySet, QueryType};
 
 use super::error::Result;
 use super::etc::Etc;
+use super::field::FieldSpec;
 use super::query_mode::QueryMode;
-use super::source::Source;
+use super::source::{Source, Source2};
+
+/// Data that is collected but never used, and data that is used but
+/// never collected.
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct DataUsage {
+    /// Data tables no table query is based on.
+    pub unused_data_tables: BTreeSet<DataTableId>,
+    /// Non-key data fields of used data tables that are neither the
+    /// source of a field nor used by a query.
+    pub unused_data_fields: BTreeSet<DataFieldId>,
+    /// Data tables queried but not provided by the protocol input.
+    pub missing_data_tables: BTreeSet<DataTableId>,
+    /// Data fields a field takes its value from, that are not
+    /// provided by the data table nor computed by the query.
+    pub missing_data_fields: BTreeSet<DataFieldId>,
+}
+
+impl DataUsage {
+    pub fn is_empty(&self) -> bool {
+        self.unused_data_tables.is_empty()
+            && self.unused_data_fields.is_empty()
+            && self.missing_data_tables.is_empty()
+            && self.missing_data_fields.is_empty()
+    }
+}
 
 /// In-memory representation of EventTypeCatalog definitions.
 #[derive(Default, Debug)]
@@ -22,6 +48,38 @@ pub struct Spec {
 }
 
 impl Spec {
+    /// Build the spec, keeping the sensitive flags of fields and the
+    /// data fields they take their value from in sync: a sensitive
+    /// field marks its data fields, so that the plugin output is
+    /// redacted in logs, and a sensitive data field marks the fields
+    /// based on it, so that their value is redacted in output.
+    pub fn new(mut etc: Etc, mut input: HashMap<Protocol, Input>) -> Self {
+        for field in etc.fields.values().filter(|field| field.sensitive) {
+            for DataFieldId(proto, field_id) in source_data_fields(field) {
+                if let Some(data_field) = input
+                    .get_mut(proto)
+                    .and_then(|input| input.data_fields.get_mut(field_id))
+                {
+                    data_field.sensitive = true;
+                }
+            }
+        }
+
+        for field in etc.fields.values_mut() {
+            let sensitive = source_data_fields(field).any(
+                |DataFieldId(proto, field_id)| {
+                    input
+                        .get(proto)
+                        .and_then(|input| input.data_fields.get(field_id))
+                        .is_some_and(|data_field| data_field.sensitive)
+                },
+            );
+            field.sensitive |= sensitive;
+        }
+
+        Self { input, etc }
+    }
+
     pub fn queries_for(
         &self,
         table_ids: &HashSet<TableId>,
@@ -96,6 +154,81 @@ impl Spec {
         Ok(prot_queries)
     }
 
+    /// Find data tables and fields that are collected but never used
+    /// by a table, and fields that are used but never collected.
+    pub fn data_usage(&self) -> Result<DataUsage> {
+        let mut usage = DataUsage::default();
+        let mut queries = Vec::new();
+        let mut used_tables = HashSet::new();
+        let mut used_fields = HashSet::new();
+
+        for table in self.etc.tables.values() {
+            let query = table.query.try_get_from(&self.etc.queries)?;
+            for data_table_id in query.required_data_tables() {
+                if self.data_table(&data_table_id).is_none() {
+                    usage.missing_data_tables.insert(data_table_id.clone());
+                }
+                used_tables.insert(data_table_id);
+            }
+
+            for field_id in &table.fields {
+                let field = field_id.try_get_from(&self.etc.fields)?;
+                if let Source::Data(data_table_id, data_field_id, _) =
+                    &field.source
+                {
+                    used_fields.insert(data_field_id.clone());
+                    if query.computes_data_field(data_field_id) {
+                        continue;
+                    }
+                    match self.data_table(data_table_id) {
+                        Some(data_table) => {
+                            if !data_table.fields.contains(&data_field_id.1) {
+                                usage
+                                    .missing_data_fields
+                                    .insert(data_field_id.clone());
+                            }
+                        }
+                        None => {
+                            usage
+                                .missing_data_tables
+                                .insert(data_table_id.clone());
+                        }
+                    }
+                }
+            }
+
+            queries.push(query);
+        }
+
+        for (proto, input) in &self.input {
+            for (table_id, data_table) in &input.data_tables {
+                let data_table_id =
+                    DataTableId(proto.clone(), table_id.clone());
+                if !used_tables.contains(&data_table_id) {
+                    usage.unused_data_tables.insert(data_table_id);
+                    continue;
+                }
+                for field_id in data_table.fields.difference(&data_table.keys) {
+                    let data_field_id =
+                        DataFieldId(proto.clone(), field_id.clone());
+                    if !used_fields.contains(&data_field_id)
+                        && !queries
+                            .iter()
+                            .any(|query| query.uses_data_field(&data_field_id))
+                    {
+                        usage.unused_data_fields.insert(data_field_id);
+                    }
+                }
+            }
+        }
+
+        Ok(usage)
+    }
+
+    fn data_table(&self, table_id: &DataTableId) -> Option<&DataTableSpec> {
+        self.input.get(&table_id.0)?.data_tables.get(&table_id.1)
+    }
+
     /// Find the type of the table.
     pub fn get_data_table_type(
         &self,
@@ -132,3 +265,16 @@ impl Spec {
         })
     }
 }
+
+/// The data fields a field takes its value from.
+fn source_data_fields(field: &FieldSpec) -> impl Iterator<Item = &DataFieldId> {
+    let source = match &field.source {
+        Source::Data(_, data_field_id, _) => Some(data_field_id),
+        _ => None,
+    };
+    let source2 = match &field.source2 {
+        Some(Source2::Data(_, data_field_id, _)) => Some(data_field_id),
+        _ => None,
+    };
+    source.into_iter().chain(source2)
+}
@@ -8,16 +8,21 @@ use serde::{Deserialize, Serialize};
 
 use agent_utils::{DBObj, TryGetFrom};
 use etc_base::{Annotated, FieldId, QueryId, Row};
-use expression::{EvalError, EvalResult, Expr};
+use expression::{EvalCell, EvalError, EvalResult, Expr};
 use protocol::DataMap;
 use query::AnnotatedQueryResult;
-use value::{DataError, Value};
+use value::{Data, DataError, Value};
 
+use super::discovery::ParentSpec;
 use super::error::Result;
 use super::etc::Etc;
 use super::field::FieldSpec;
+use super::group_by::GroupBySpec;
 use super::layer::Layer;
+use super::lookup::Lookups;
 use super::query_mode::QueryMode;
+use super::source::Source2;
+use super::threshold::ThresholdLevel;
 
 #[derive(Serialize, Deserialize, Clone, DBObj, Debug, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
@@ -40,8 +45,12 @@ pub struct TableSpec {
     pub item_type: Option<(String, String)>,
     pub item_id: Option<Expr>,
     pub item_name: Option<Expr>,
+    /// Discover items from the items of a parent table.
+    pub parent: Option<ParentSpec>,
     pub sub_tables: Option<Vec<SubTableSpec>>,
     pub fields: Vec<FieldId>,
+    /// Summarize the table into grouped rows.
+    pub group_by: Option<GroupBySpec>,
 }
 
 #[derive(Serialize, Deserialize, Clone, DBObj, Debug, PartialEq, Eq)]
@@ -64,23 +73,70 @@ impl TableSpec {
                 value: rows,
                 warnings,
             }) => Ok(Annotated {
-                value: self.eval_exprs(query_mode, etc, rows)?,
+                value: self.eval_exprs(query_mode, etc, data, rows)?,
                 warnings,
             }),
             Err(e) => Err(e),
         })
     }
 
+    /// Calculate the table and, if a group-by is configured, its
+    /// summary rows.
+    pub fn calculate_summary(
+        &self,
+        query_mode: QueryMode,
+        etc: &Etc,
+        data: &DataMap,
+    ) -> Result<AnnotatedQueryResult<Vec<HashMap<FieldId, EvalResult>>>> {
+        let group_by = match &self.group_by {
+            Some(group_by) => group_by,
+            None => return self.calculate(query_mode, etc, data),
+        };
+        Ok(self
+            .calculate(query_mode, etc, data)?
+            .map(|result| Annotated {
+                value: group_by.summarize(&result.value),
+                warnings: result.warnings,
+            }))
+    }
+
     fn eval_exprs(
         &self,
         query_mode: QueryMode,
         etc: &Etc,
-        data: Vec<Row>,
+        data: &DataMap,
+        rows: Vec<Row>,
+    ) -> Result<Vec<HashMap<FieldId, EvalResult>>> {
+        let fields = self
+            .fields_for_mode(query_mode, etc)?
+            .into_iter()
+            .filter(|(_field_id, field)| field.included_for_data(&rows))
+            .collect::<Vec<_>>();
+        let lookups = Lookups::new(&fields, etc, data)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| calculate_row(&fields, &lookups, row))
+            .collect())
+    }
+
+    /// All fields of the table, to be looked up from another table.
+    /// Lookup fields of this table are left unresolved. If the query
+    /// fails, there are no rows to look up.
+    pub(crate) fn lookup_rows(
+        &self,
+        etc: &Etc,
+        data: &DataMap,
     ) -> Result<Vec<HashMap<FieldId, EvalResult>>> {
-        let fields = self.fields_for_mode(query_mode, etc)?;
-        Ok(data
+        let query = self.query.try_get_from(&etc.queries)?;
+        let rows = match query.run(data) {
+            Ok(Annotated { value: rows, .. }) => rows,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let fields = self.get_fields(etc)?;
+        let lookups = Lookups::none();
+        Ok(rows
             .into_iter()
-            .map(|row| calculate_row(&fields, row))
+            .map(|row| calculate_row(&fields, &lookups, row))
             .collect())
     }
 
@@ -113,7 +169,9 @@ impl TableSpec {
         Ok(self
             .get_fields(etc)?
             .into_iter()
-            .filter(|(_field_id, field)| field.discovery)
+            .filter(|(_field_id, field)| {
+                field.discovery && field.included_in_mode(QueryMode::Discovery)
+            })
             .collect())
     }
 
@@ -124,10 +182,63 @@ impl TableSpec {
         Ok(self
             .get_fields(etc)?
             .into_iter()
-            .filter(|(_field_id, field)| field.monitoring)
+            .filter(|(_field_id, field)| {
+                field.monitoring
+                    && field.included_in_mode(QueryMode::Monitoring)
+            })
             .collect())
     }
 
+    /// Monitoring fields with a threshold.
+    pub fn threshold_fields<'a>(
+        &'a self,
+        etc: &'a Etc,
+    ) -> Result<Vec<(&'a FieldId, &'a FieldSpec)>> {
+        Ok(self
+            .monitoring_fields(etc)?
+            .into_iter()
+            .filter(|(_field_id, field)| field.threshold.is_some())
+            .collect())
+    }
+
+    /// The most severe threshold level exceeded by the monitoring
+    /// fields of a calculated row. Thresholds are evaluated against
+    /// the current value only (see `ThresholdSpec::eval`); fields
+    /// without a value, or whose threshold cannot be evaluated, do
+    /// not trigger.
+    pub fn row_threshold_level(
+        &self,
+        etc: &Etc,
+        row: &HashMap<FieldId, EvalResult>,
+    ) -> Result<Option<ThresholdLevel>> {
+        let fields = self.threshold_fields(etc)?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let values = row
+            .iter()
+            .map(|(field_id, value)| {
+                Ok((
+                    field_id.try_get_from(&etc.fields)?.name.as_str(),
+                    value.clone().map_err(|_| DataError::Missing),
+                ))
+            })
+            .collect::<Result<HashMap<&str, Data>>>()?;
+
+        let mut level = None;
+        for (field_id, field) in fields {
+            if let (Some(threshold), Some(Ok(value))) =
+                (&field.threshold, row.get(field_id))
+            {
+                if let Ok(field_level) = threshold.eval(value, &values) {
+                    level = level.max(field_level);
+                }
+            }
+        }
+        Ok(level)
+    }
+
     pub fn checkmk_fields<'a>(
         &'a self,
         etc: &'a Etc,
@@ -137,18 +248,29 @@ impl TableSpec {
             .into_iter()
             .filter(|(_field_id, field)| {
                 field.check_mk.unwrap_or(field.monitoring)
+                    && field.included_in_mode(QueryMode::CheckMk)
             })
             .collect())
     }
 }
 
 fn calculate_row(
-    fields: &Vec<(&FieldId, &FieldSpec)>,
+    fields: &[(&FieldId, &FieldSpec)],
+    lookups: &Lookups,
     row: Row,
 ) -> HashMap<FieldId, std::result::Result<Value, EvalError>> {
     let expr_row: HashMap<_, _> = fields
         .iter()
-        .map(|(_, field)| (field.name.as_str(), field.field_expr(&row)))
+        .map(|(field_id, field)| {
+            let cell = match &field.source2 {
+                Some(Source2::Lookup(spec)) => EvalCell::new(
+                    &FieldSpec::DEFAULT_EXPR,
+                    Some(lookups.resolve(field_id, spec, &row)),
+                ),
+                _ => field.field_expr(&row),
+            };
+            (field.name.as_str(), cell)
+        })
         .collect();
 
     let mut eval_row: HashMap<_, _> = expr_row
@@ -2,17 +2,25 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
+use std::time::{Duration, SystemTime};
 
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error;
 
-use expression::Expr;
-use value::Value;
+use expression::{EvalCell, EvalError, Expr};
+use unit::{Dimension, Quantity, UnitError};
+use value::{Data, TriState, Type, Value};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+use super::samples::{percentile, SampleDb};
+
+#[derive(
+    Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
 pub enum ThresholdLevel {
     Warning,
     Critical,
@@ -25,6 +33,373 @@ pub enum ThresholdSpec {
         warning: Option<serde_json::Value>,
         critical: Option<serde_json::Value>,
     },
+    /// Bounds calculated per row from the other fields of the table.
+    Dynamic {
+        warning: Option<ThresholdBound>,
+        critical: Option<ThresholdBound>,
+    },
+    /// Levels per state of a boolean or tri-state field. The unknown
+    /// state has to be mapped explicitly; it never inherits the
+    /// level of false.
+    State {
+        #[serde(rename = "false")]
+        on_false: Option<ThresholdLevel>,
+        #[serde(rename = "true")]
+        on_true: Option<ThresholdLevel>,
+        unknown: Option<ThresholdLevel>,
+    },
+    /// Bounds on a percentile (0-100) of the samples in the last
+    /// `window` seconds, rather than on the instantaneous value, so
+    /// single spikes do not trigger.
+    Percentile {
+        percentile: u8,
+        window: u64,
+        warning: Option<ThresholdBound>,
+        critical: Option<ThresholdBound>,
+    },
+    /// Damp flapping of another threshold. An active level is kept
+    /// as long as the value exceeds its `leave_*` bound, which is
+    /// typically less strict than the bound to enter the level, and
+    /// the level only changes after `consecutive` evaluations
+    /// resulting in the same new level.
+    Hysteresis {
+        threshold: Box<ThresholdSpec>,
+        #[serde(default)]
+        leave_warning: Option<ThresholdBound>,
+        #[serde(default)]
+        leave_critical: Option<ThresholdBound>,
+        #[serde(default = "default_consecutive")]
+        consecutive: u32,
+    },
+}
+
+fn default_consecutive() -> u32 {
+    1
+}
+
+/// A threshold bound calculated per row. The expression can refer
+/// to other fields of the table by name (`${capacity}`) and to the
+/// checked field itself as `@`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ThresholdBound {
+    pub op: ThresholdOp,
+    pub value: Expr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Error, Debug)]
+pub enum ThresholdError {
+    #[error("threshold evaluation failed: {0}")]
+    Eval(#[from] EvalError),
+    #[error("threshold is not numeric: {0}")]
+    NotNumeric(Type),
+    #[error("threshold of type {1} cannot be compared to field of type {0}")]
+    Incompatible(Type, Type),
+    #[error("unit error: {0}")]
+    Unit(#[from] UnitError),
+    #[error("state threshold on non-boolean field of type {0}")]
+    NotBoolean(Type),
+    #[error("invalid percentile: {0} (expected: 0-100)")]
+    InvalidPercentile(u8),
+}
+
+impl ThresholdSpec {
+    /// Check that dynamic threshold bounds are numeric and of the
+    /// same dimension as the checked field, and that state thresholds
+    /// are set on boolean or tri-state fields. `row` holds the types
+    /// of the other fields in the table. Selector thresholds are
+    /// checked by the rule engine.
+    pub fn check(
+        &self,
+        field_type: &Type,
+        row: &HashMap<&str, Type>,
+    ) -> Result<(), ThresholdError> {
+        match self {
+            ThresholdSpec::Selector { .. } => Ok(()),
+            ThresholdSpec::Dynamic { warning, critical } => warning
+                .iter()
+                .chain(critical)
+                .try_for_each(|bound| bound.check(field_type, row)),
+            ThresholdSpec::Percentile {
+                percentile,
+                warning,
+                critical,
+                ..
+            } => match *percentile <= 100 {
+                true => warning
+                    .iter()
+                    .chain(critical)
+                    .try_for_each(|bound| bound.check(field_type, row)),
+                false => Err(ThresholdError::InvalidPercentile(*percentile)),
+            },
+            ThresholdSpec::State { .. } => match field_type {
+                Type::Boolean | Type::TriState => Ok(()),
+                t => Err(ThresholdError::NotBoolean(t.clone())),
+            },
+            ThresholdSpec::Hysteresis {
+                threshold,
+                leave_warning,
+                leave_critical,
+                ..
+            } => {
+                threshold.check(field_type, row)?;
+                leave_warning
+                    .iter()
+                    .chain(leave_critical)
+                    .try_for_each(|bound| bound.check(field_type, row))
+            }
+        }
+    }
+
+    /// Evaluate dynamic and state thresholds for a field value, given
+    /// the values of the other fields in the row. Returns the most severe level
+    /// triggered. Selector thresholds are evaluated by the rule
+    /// engine and never trigger here. Percentile thresholds are
+    /// evaluated against the value alone; use `eval_with_samples`
+    /// to take recent samples into account. Hysteresis thresholds
+    /// evaluate to the level of the wrapped threshold.
+    pub fn eval(
+        &self,
+        value: &Value,
+        row: &HashMap<&str, Data>,
+    ) -> Result<Option<ThresholdLevel>, ThresholdError> {
+        match self {
+            ThresholdSpec::Selector { .. } => Ok(None),
+            ThresholdSpec::Dynamic { warning, critical }
+            | ThresholdSpec::Percentile {
+                warning, critical, ..
+            } => eval_bounds(warning, critical, value, row),
+            ThresholdSpec::State {
+                on_false,
+                on_true,
+                unknown,
+            } => {
+                let state = match value {
+                    Value::Boolean(v) => TriState::from(*v),
+                    Value::TriState(v) => *v,
+                    v => return Err(ThresholdError::NotBoolean(v.get_type())),
+                };
+                Ok(match state {
+                    TriState::False => on_false.clone(),
+                    TriState::True => on_true.clone(),
+                    TriState::Unknown => unknown.clone(),
+                })
+            }
+            ThresholdSpec::Hysteresis { threshold, .. } => {
+                threshold.eval(value, row)
+            }
+        }
+    }
+
+    /// Like `eval`, but percentile thresholds are evaluated against
+    /// the percentile of the samples stored under `key` in the
+    /// window, after adding the value to them, and hysteresis
+    /// thresholds take the level of the previous run into account.
+    pub fn eval_with_samples(
+        &self,
+        value: &Value,
+        row: &HashMap<&str, Data>,
+        samples: &SampleDb,
+        key: String,
+        now: SystemTime,
+    ) -> Result<Option<ThresholdLevel>, ThresholdError> {
+        match self {
+            ThresholdSpec::Percentile {
+                percentile: p,
+                window,
+                warning,
+                critical,
+            } => {
+                let (sample, unit) = match value {
+                    Value::Quantity(q) => {
+                        let Quantity(n, u) = q.normalize()?;
+                        (n, Some(u))
+                    }
+                    v => (number(v)?, None),
+                };
+                let samples =
+                    samples.add(key, sample, now, Duration::from_secs(*window));
+                let n = percentile(&samples, *p).unwrap_or(sample);
+                let value = match unit {
+                    Some(u) => Value::Quantity(Quantity(n, u)),
+                    None => Value::Float(n),
+                };
+                eval_bounds(warning, critical, &value, row)
+            }
+            ThresholdSpec::Hysteresis {
+                threshold,
+                leave_warning,
+                leave_critical,
+                consecutive,
+            } => {
+                let mut state = samples.level(&key);
+                let current = threshold.eval_with_samples(
+                    value,
+                    row,
+                    samples,
+                    key.clone(),
+                    now,
+                )?;
+                let held = match state.level {
+                    Some(ThresholdLevel::Critical)
+                        if exceeds(leave_critical, value, row)? =>
+                    {
+                        Some(ThresholdLevel::Critical)
+                    }
+                    Some(_) if exceeds(leave_warning, value, row)? => {
+                        Some(ThresholdLevel::Warning)
+                    }
+                    _ => None,
+                };
+                let target = current.max(held);
+                state.count = match state.last == target {
+                    true => state.count.saturating_add(1),
+                    false => 1,
+                };
+                if state.count >= *consecutive {
+                    state.level = target.clone();
+                }
+                state.last = target;
+                let level = state.level.clone();
+                samples.set_level(key, state);
+                Ok(level)
+            }
+            _ => self.eval(value, row),
+        }
+    }
+}
+
+/// Whether the value exceeds an optional bound.
+fn exceeds(
+    bound: &Option<ThresholdBound>,
+    value: &Value,
+    row: &HashMap<&str, Data>,
+) -> Result<bool, ThresholdError> {
+    match bound {
+        Some(bound) => bound.eval(value, row),
+        None => Ok(false),
+    }
+}
+
+/// The most severe bound exceeded by the value.
+fn eval_bounds(
+    warning: &Option<ThresholdBound>,
+    critical: &Option<ThresholdBound>,
+    value: &Value,
+    row: &HashMap<&str, Data>,
+) -> Result<Option<ThresholdLevel>, ThresholdError> {
+    if let Some(bound) = critical {
+        if bound.eval(value, row)? {
+            return Ok(Some(ThresholdLevel::Critical));
+        }
+    }
+    if let Some(bound) = warning {
+        if bound.eval(value, row)? {
+            return Ok(Some(ThresholdLevel::Warning));
+        }
+    }
+    Ok(None)
+}
+
+impl ThresholdBound {
+    pub fn check(
+        &self,
+        field_type: &Type,
+        row: &HashMap<&str, Type>,
+    ) -> Result<(), ThresholdError> {
+        let vars = row
+            .iter()
+            .map(|(name, typ)| {
+                (*name, EvalCell::new_evaluated(Ok(typ.clone())))
+            })
+            .collect();
+        let bound_type =
+            self.value.check_in_row(Some(&vars), Some(field_type))?;
+        match (
+            numeric_dimension(field_type),
+            numeric_dimension(&bound_type),
+        ) {
+            (_, None) => Err(ThresholdError::NotNumeric(bound_type)),
+            (None, _) => Err(ThresholdError::NotNumeric(field_type.clone())),
+            (Some(a), Some(b)) if a == b => Ok(()),
+            _ => Err(ThresholdError::Incompatible(
+                field_type.clone(),
+                bound_type,
+            )),
+        }
+    }
+
+    /// Whether the value exceeds the bound.
+    pub fn eval(
+        &self,
+        value: &Value,
+        row: &HashMap<&str, Data>,
+    ) -> Result<bool, ThresholdError> {
+        let vars = row
+            .iter()
+            .map(|(name, data)| {
+                (
+                    *name,
+                    EvalCell::new_evaluated(
+                        data.clone().map_err(EvalError::DataError),
+                    ),
+                )
+            })
+            .collect();
+        let bound = self
+            .value
+            .eval_in_row(Some(&vars), Some(&Ok(value.clone())))?;
+        Ok(match compare(value, &bound)? {
+            None => false,
+            Some(ord) => match self.op {
+                ThresholdOp::Gt => ord == Ordering::Greater,
+                ThresholdOp::Ge => ord != Ordering::Less,
+                ThresholdOp::Lt => ord == Ordering::Less,
+                ThresholdOp::Le => ord != Ordering::Greater,
+            },
+        })
+    }
+}
+
+/// Dimension of a numeric type; plain numbers are dimensionless.
+fn numeric_dimension(typ: &Type) -> Option<Dimension> {
+    match typ {
+        Type::Integer | Type::Float | Type::Decimal => {
+            Some(Dimension::Dimensionless)
+        }
+        Type::Quantity(dim) => Some(*dim),
+        _ => None,
+    }
+}
+
+fn compare(
+    value: &Value,
+    bound: &Value,
+) -> Result<Option<Ordering>, ThresholdError> {
+    match (value, bound) {
+        (Value::Quantity(a), Value::Quantity(b)) => Ok(a.partial_cmp(b)?),
+        (a, b) => Ok(number(a)?.partial_cmp(&number(b)?)),
+    }
+}
+
+fn number(value: &Value) -> Result<f64, ThresholdError> {
+    match value {
+        Value::Integer(n) => Ok(*n as f64),
+        Value::Float(n) => Ok(*n),
+        Value::Decimal(n) => Ok(n.to_f64()),
+        Value::Quantity(q) if q.dimension() == Dimension::Dimensionless => {
+            Ok(q.normalize()?.0)
+        }
+        v => Err(ThresholdError::NotNumeric(v.get_type())),
+    }
 }
 
 /* Backward compatibility. */
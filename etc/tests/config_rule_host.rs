@@ -0,0 +1,111 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::json;
+
+use etc::{lookup_config_value, ConfigRule, Error, HostMatch, Package};
+use etc_base::PackageName;
+use value::Value;
+
+const PACKAGE_V1: &str = include_str!("fixtures/package_v1.json");
+
+fn rule(host: Option<HostMatch>, value: i64) -> ConfigRule {
+    ConfigRule {
+        selectors: Vec::new(),
+        value: Value::Integer(value),
+        host,
+    }
+}
+
+fn regex(pattern: &str) -> Option<HostMatch> {
+    Some(HostMatch::Regex(Regex::new(pattern).unwrap()))
+}
+
+fn exact(host: &str) -> Option<HostMatch> {
+    Some(HostMatch::Exact(host.to_string()))
+}
+
+fn lookup(rules: &[ConfigRule], host: &str) -> Option<Value> {
+    lookup_config_value(rules, host, &HashMap::new())
+        .transpose()
+        .unwrap()
+}
+
+#[test]
+fn regex_host_match() {
+    let rules = [rule(regex("^db-.*"), 1)];
+    assert_eq!(lookup(&rules, "db-01"), Some(Value::Integer(1)));
+    assert_eq!(lookup(&rules, "web-01"), None);
+}
+
+#[test]
+fn more_specific_rules_win() {
+    let rules = [
+        rule(regex("^db-.*"), 1),
+        rule(exact("db-01"), 2),
+        rule(regex("^db-0.*"), 3),
+    ];
+    assert_eq!(lookup(&rules, "db-01"), Some(Value::Integer(2)));
+    /* Among regex rules, the first matching rule wins. */
+    assert_eq!(lookup(&rules, "db-02"), Some(Value::Integer(1)));
+    assert_eq!(lookup(&rules, "web-01"), None);
+}
+
+#[test]
+fn rules_without_host_condition() {
+    let rules = [rule(None, 0), rule(regex("^db-.*"), 1)];
+    assert_eq!(lookup(&rules, "db-01"), Some(Value::Integer(1)));
+    /* Without selectors, a rule without host condition never
+     * matches, as before. */
+    assert_eq!(lookup(&rules, "web-01"), None);
+}
+
+#[test]
+fn host_match_serialization() {
+    let rule: ConfigRule = serde_json::from_value(json!({
+        "Selectors": [],
+        "Value": Value::Integer(1),
+        "Host": {"Regex": "^db-.*"}
+    }))
+    .unwrap();
+    assert_eq!(rule.host, regex("^db-.*"));
+    let json = serde_json::to_value(&rule).unwrap();
+    assert_eq!(json["Host"], json!({"Regex": "^db-.*"}));
+
+    let rule: ConfigRule = serde_json::from_value(json!({
+        "Selectors": [],
+        "Value": Value::Integer(1)
+    }))
+    .unwrap();
+    assert_eq!(rule.host, None);
+}
+
+#[test]
+fn invalid_pattern_fails_at_load() {
+    let mut json: serde_json::Value = serde_json::from_str(PACKAGE_V1).unwrap();
+    let pkg = json.as_object_mut().unwrap();
+    pkg.remove("DataTables");
+    pkg.remove("DataFields");
+    pkg.remove("DataTableFields");
+    json["FormatVersion"] = json!(2);
+    json["ConfigRules"] = json!({
+        "threshold": {
+            "mp": [{
+                "Selectors": [],
+                "Value": Value::Integer(1),
+                "Host": {"Regex": "^db-(.*"}
+            }]
+        }
+    });
+    let data = serde_json::to_string(&json).unwrap();
+    match Package::from_json(&PackageName(String::from("test")), &data) {
+        Err(Error::PackageData(_, e)) => {
+            assert!(e.to_string().contains("regular expression"))
+        }
+        r => panic!("expected a package data error, got {r:?}"),
+    }
+}
@@ -0,0 +1,143 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use etc::{
+    Etc, FieldSpec, TableSpec, ThresholdBound, ThresholdError, ThresholdLevel,
+    ThresholdOp, ThresholdSpec,
+};
+use etc_base::{FieldId, TableId};
+use expression::{EvalError, Expr};
+use unit::{Dimension, Quantity};
+use value::{Type, Value};
+
+/// Warn at 80% and go critical at 90% of the queue capacity.
+fn capacity_threshold() -> ThresholdSpec {
+    ThresholdSpec::Dynamic {
+        warning: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse("{${capacity} * 0.8}").unwrap(),
+        }),
+        critical: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse("{${capacity} * 0.9}").unwrap(),
+        }),
+    }
+}
+
+fn level(length: i64, capacity: i64) -> Option<ThresholdLevel> {
+    let row = HashMap::from([
+        ("length", Ok(Value::Integer(length))),
+        ("capacity", Ok(Value::Integer(capacity))),
+    ]);
+    capacity_threshold()
+        .eval(&Value::Integer(length), &row)
+        .unwrap()
+}
+
+#[test]
+fn percentage_of_capacity() {
+    assert_eq!(level(10, 100), None);
+    assert_eq!(level(80, 100), Some(ThresholdLevel::Warning));
+    assert_eq!(level(95, 100), Some(ThresholdLevel::Critical));
+    assert_eq!(level(95, 1000), None);
+}
+
+#[test]
+fn row_level() {
+    let mut etc = Etc::default();
+    let table: TableSpec = serde_json::from_value(serde_json::json!({
+        "Query": "queues",
+        "Name": "queues",
+        "Fields": ["length", "capacity"]
+    }))
+    .unwrap();
+    etc.tables.insert(TableId::from("queues"), table);
+    for name in ["length", "capacity"] {
+        let mut field: FieldSpec = serde_json::from_value(serde_json::json!({
+            "Name": name,
+            "Source": "Config",
+            "InputType": "integer"
+        }))
+        .unwrap();
+        if name == "length" {
+            field.threshold = Some(capacity_threshold());
+        }
+        etc.fields.insert(FieldId::from(name), field);
+    }
+
+    let table = &etc.tables[&TableId::from("queues")];
+    let fields = table.threshold_fields(&etc).unwrap();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].0, &FieldId::from("length"));
+    let level = |length, capacity| {
+        let row = HashMap::from([
+            (FieldId::from("length"), length),
+            (FieldId::from("capacity"), capacity),
+        ]);
+        table.row_threshold_level(&etc, &row).unwrap()
+    };
+    let int = |n| Ok(Value::Integer(n));
+    assert_eq!(level(int(10), int(100)), None);
+    assert_eq!(level(int(95), int(100)), Some(ThresholdLevel::Critical));
+    assert_eq!(level(int(95), Err(EvalError::ZeroDivision)), None);
+}
+
+#[test]
+fn missing_reference() {
+    let row = HashMap::from([("length", Ok(Value::Integer(10)))]);
+    assert!(matches!(
+        capacity_threshold().eval(&Value::Integer(10), &row),
+        Err(ThresholdError::Eval(_))
+    ));
+}
+
+#[test]
+fn check_numeric() {
+    let row =
+        HashMap::from([("length", Type::Integer), ("capacity", Type::Integer)]);
+    capacity_threshold().check(&Type::Integer, &row).unwrap();
+
+    let row = HashMap::from([
+        ("length", Type::Integer),
+        ("capacity", Type::UnicodeString),
+    ]);
+    assert!(capacity_threshold().check(&Type::Integer, &row).is_err());
+}
+
+#[test]
+fn check_dimension() {
+    let threshold = ThresholdSpec::Dynamic {
+        warning: Some(ThresholdBound {
+            op: ThresholdOp::Gt,
+            value: Expr::parse("{${size} * 0.8}").unwrap(),
+        }),
+        critical: None,
+    };
+
+    let row = HashMap::from([("size", Type::Quantity(Dimension::Information))]);
+    threshold
+        .check(&Type::Quantity(Dimension::Information), &row)
+        .unwrap();
+    assert!(matches!(
+        threshold.check(&Type::Quantity(Dimension::Time), &row),
+        Err(ThresholdError::Incompatible(_, _))
+    ));
+    assert!(matches!(
+        threshold.check(&Type::Integer, &row),
+        Err(ThresholdError::Incompatible(_, _))
+    ));
+
+    let row = HashMap::from([(
+        "size",
+        Ok(Value::Quantity(Quantity::parse("1 GB").unwrap())),
+    )]);
+    assert_eq!(
+        threshold
+            .eval(&Value::Quantity(Quantity::parse("900 MB").unwrap()), &row)
+            .unwrap(),
+        Some(ThresholdLevel::Warning)
+    );
+}
@@ -0,0 +1,150 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use etc::{Etc, FieldCondition, FieldSpec, QueryMode, TableSpec};
+use etc_base::{
+    DataFieldId, FieldId, ProtoDataFieldId, Protocol, Row, TableId,
+};
+use expression::Expr;
+use value::{DataError, Value};
+
+fn field(name: &str, conditions: Vec<FieldCondition>) -> FieldSpec {
+    let mut field: FieldSpec = serde_json::from_value(serde_json::json!({
+        "Name": name,
+        "Monitoring": true,
+        "Discovery": true,
+        "Source": "Config",
+        "InputType": "integer"
+    }))
+    .unwrap();
+    field.conditions = conditions;
+    field
+}
+
+fn etc(fields: Vec<FieldSpec>) -> Etc {
+    let mut etc = Etc::default();
+    let table: TableSpec = serde_json::from_value(serde_json::json!({
+        "Query": "interfaces",
+        "Name": "interfaces",
+        "Discovery": true,
+        "Fields": fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>()
+    }))
+    .unwrap();
+    etc.tables.insert(TableId::from("interfaces"), table);
+    for field in fields {
+        etc.fields.insert(FieldId::from(field.name.as_str()), field);
+    }
+    etc
+}
+
+fn field_names(etc: &Etc, query_mode: QueryMode) -> Vec<&str> {
+    etc.tables[&TableId::from("interfaces")]
+        .fields_for_mode(query_mode, etc)
+        .unwrap()
+        .into_iter()
+        .map(|(_, field)| field.name.as_str())
+        .collect()
+}
+
+fn data_field(name: &str) -> DataFieldId {
+    DataFieldId(
+        Protocol(String::from("SNMP")),
+        ProtoDataFieldId(String::from(name)),
+    )
+}
+
+fn row(if_type: i64, high_speed: Option<i64>) -> Row {
+    Row::from([
+        (data_field("ifType"), Ok(Value::Integer(if_type))),
+        (
+            data_field("ifHighSpeed"),
+            high_speed.map(Value::Integer).ok_or(DataError::Missing),
+        ),
+    ])
+}
+
+#[test]
+fn mode_condition() {
+    let etc = etc(vec![
+        field("index", vec![]),
+        field(
+            "speed",
+            vec![FieldCondition::Modes(vec![QueryMode::Discovery])],
+        ),
+    ]);
+    assert_eq!(field_names(&etc, QueryMode::Monitoring), vec!["index"]);
+    assert_eq!(field_names(&etc, QueryMode::CheckMk), vec!["index"]);
+    assert_eq!(
+        field_names(&etc, QueryMode::Discovery),
+        vec!["index", "speed"]
+    );
+}
+
+#[test]
+fn capability_condition() {
+    let speed = field(
+        "speed",
+        vec![FieldCondition::Capability(data_field("ifHighSpeed"))],
+    );
+    assert!(speed.included_in_mode(QueryMode::Monitoring));
+    assert!(speed.included_for_data(&[row(6, None), row(6, Some(1000))]));
+    assert!(!speed.included_for_data(&[row(6, None), row(24, None)]));
+    assert!(!speed.included_for_data(&[]));
+}
+
+#[test]
+fn expression_condition() {
+    let ethernet = field(
+        "ethernet",
+        vec![FieldCondition::Expr(
+            Expr::parse("{${SNMP.ifType} == 6}").unwrap(),
+        )],
+    );
+    assert!(ethernet.included_for_data(&[row(24, None), row(6, None)]));
+    assert!(!ethernet.included_for_data(&[row(24, None)]));
+
+    /* Errors, e.g. on missing data, exclude the field. */
+    let missing = field(
+        "missing",
+        vec![FieldCondition::Expr(
+            Expr::parse("{${SNMP.ifHighSpeed} > 0}").unwrap(),
+        )],
+    );
+    assert!(!missing.included_for_data(&[row(6, None)]));
+}
+
+#[test]
+fn conditions_must_all_hold() {
+    let field = field(
+        "speed",
+        vec![
+            FieldCondition::Modes(vec![QueryMode::Monitoring]),
+            FieldCondition::Capability(data_field("ifHighSpeed")),
+        ],
+    );
+    assert!(!field.included_in_mode(QueryMode::Discovery));
+    assert!(field.included_in_mode(QueryMode::Monitoring));
+    assert!(!field.included_for_data(&[row(6, None)]));
+}
+
+#[test]
+fn deserialize_conditions() {
+    let field: FieldSpec = serde_json::from_value(serde_json::json!({
+        "Name": "speed",
+        "Source": "Config",
+        "InputType": "integer",
+        "Conditions": [
+            {"modes": ["monitoring"]},
+            {"capability": "SNMP_ifHighSpeed"}
+        ]
+    }))
+    .unwrap();
+    assert_eq!(
+        field.conditions,
+        vec![
+            FieldCondition::Modes(vec![QueryMode::Monitoring]),
+            FieldCondition::Capability(data_field("ifHighSpeed")),
+        ]
+    );
+}
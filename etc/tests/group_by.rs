@@ -0,0 +1,118 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use etc::{AggregateSpec, GroupBySpec};
+use etc_base::FieldId;
+use expression::{Aggregate, EvalError, EvalResult};
+use value::{DataError, Value};
+
+fn row(host: &str, size: Option<i64>) -> HashMap<FieldId, EvalResult> {
+    HashMap::from_iter([
+        (
+            FieldId::from("host"),
+            Ok(Value::UnicodeString(host.to_string())),
+        ),
+        (
+            FieldId::from("size"),
+            size.map(Value::Integer)
+                .ok_or(EvalError::DataError(DataError::Missing)),
+        ),
+    ])
+}
+
+fn aggregate(field: &str, function: Aggregate) -> AggregateSpec {
+    AggregateSpec {
+        field: FieldId::from(field),
+        function,
+        source: Some(FieldId::from("size")),
+    }
+}
+
+fn spec(fields: &[&str]) -> GroupBySpec {
+    GroupBySpec {
+        fields: fields.iter().map(|f| FieldId::from(*f)).collect(),
+        aggregates: vec![
+            AggregateSpec {
+                field: FieldId::from("disks"),
+                function: Aggregate::Count,
+                source: None,
+            },
+            aggregate("total", Aggregate::Sum),
+            aggregate("average", Aggregate::Avg),
+            aggregate("largest", Aggregate::Max),
+        ],
+    }
+}
+
+fn get(row: &HashMap<FieldId, EvalResult>, field: &str) -> Option<Value> {
+    row.get(&FieldId::from(field))
+        .unwrap()
+        .as_ref()
+        .ok()
+        .cloned()
+}
+
+#[test]
+fn groups_detail_rows_into_summary_rows() {
+    let rows = vec![
+        row("a", Some(10)),
+        row("b", Some(5)),
+        row("a", Some(30)),
+        row("a", None),
+    ];
+    let summary = spec(&["host"]).summarize(&rows);
+
+    assert_eq!(summary.len(), 2);
+    assert_eq!(
+        get(&summary[0], "host"),
+        Some(Value::UnicodeString("a".to_string()))
+    );
+    assert_eq!(get(&summary[0], "disks"), Some(Value::Integer(3)));
+    assert_eq!(get(&summary[0], "total"), Some(Value::Integer(40)));
+    assert_eq!(get(&summary[0], "average"), Some(Value::Float(20.0)));
+    assert_eq!(get(&summary[0], "largest"), Some(Value::Integer(30)));
+
+    assert_eq!(
+        get(&summary[1], "host"),
+        Some(Value::UnicodeString("b".to_string()))
+    );
+    assert_eq!(get(&summary[1], "disks"), Some(Value::Integer(1)));
+    assert_eq!(get(&summary[1], "average"), Some(Value::Float(5.0)));
+}
+
+#[test]
+fn summarizes_the_whole_table_without_group_fields() {
+    let rows = vec![row("a", Some(10)), row("b", Some(5))];
+    let summary = spec(&[]).summarize(&rows);
+
+    assert_eq!(summary.len(), 1);
+    assert_eq!(get(&summary[0], "disks"), Some(Value::Integer(2)));
+    assert_eq!(get(&summary[0], "total"), Some(Value::Integer(15)));
+    assert_eq!(get(&summary[0], "largest"), Some(Value::Integer(10)));
+}
+
+#[test]
+fn empty_table_yields_zero_summary_or_no_groups() {
+    let summary = spec(&[]).summarize(&[]);
+    assert_eq!(summary.len(), 1);
+    assert_eq!(get(&summary[0], "disks"), Some(Value::Integer(0)));
+    assert_eq!(get(&summary[0], "total"), Some(Value::Integer(0)));
+    assert!(matches!(
+        summary[0].get(&FieldId::from("average")),
+        Some(Err(EvalError::DataError(DataError::Missing)))
+    ));
+
+    assert!(spec(&["host"]).summarize(&[]).is_empty());
+}
+
+#[test]
+fn group_with_only_missing_values() {
+    let summary = spec(&["host"]).summarize(&[row("a", None)]);
+    assert_eq!(summary.len(), 1);
+    assert_eq!(get(&summary[0], "disks"), Some(Value::Integer(1)));
+    assert_eq!(get(&summary[0], "total"), Some(Value::Integer(0)));
+    assert_eq!(get(&summary[0], "largest"), None);
+}
@@ -0,0 +1,190 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use agent_utils::TryAppend;
+use etc::EtcManager;
+use etc_base::{
+    AnnotatedResult, PackageName, PackageVersion, ProtoDataFieldId,
+    ProtoDataTableId, ProtoQueryMap, ProtoRow, Protocol,
+};
+use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin, PluginManager};
+
+#[derive(thiserror::Error, Debug)]
+#[error("test error")]
+struct TestError;
+
+#[derive(Deserialize, Default, Clone)]
+struct TestInput;
+
+impl TryAppend for TestInput {
+    fn try_append(&mut self, _other: Self) -> agent_utils::Result<()> {
+        Ok(())
+    }
+}
+
+/// Counts how often its input is loaded.
+struct TestPlugin<const N: usize> {
+    loads: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl<const N: usize> LocalPlugin for TestPlugin<N> {
+    type Error = TestError;
+    type TypeError = TestError;
+    type DTError = TestError;
+    type DTWarning = TestError;
+    type Input = TestInput;
+    type Config = ();
+
+    const PROTOCOL: &'static str = ["a", "b"][N];
+    const VERSION: &'static str = "0.0.0";
+
+    fn show_queries(
+        &self,
+        _input: &TestInput,
+        _query: &ProtoQueryMap,
+    ) -> Result<String, TestError> {
+        Ok(String::new())
+    }
+
+    async fn run_queries(
+        &self,
+        _input: &TestInput,
+        _config: &(),
+        _query: &ProtoQueryMap,
+    ) -> Result<
+        HashMap<
+            ProtoDataTableId,
+            AnnotatedResult<Vec<ProtoRow>, TestError, TestError>,
+        >,
+        TestError,
+    > {
+        Ok(HashMap::new())
+    }
+
+    fn get_tables(
+        &self,
+        _input: &TestInput,
+    ) -> Result<HashMap<ProtoDataTableId, DataTableSpec>, TestError> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        Ok(HashMap::new())
+    }
+
+    fn get_fields(
+        &self,
+        _input: &TestInput,
+    ) -> Result<HashMap<ProtoDataFieldId, DataFieldSpec>, TestError> {
+        Ok(HashMap::new())
+    }
+}
+
+fn plugins() -> (PluginManager, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let a = Arc::new(AtomicUsize::new(0));
+    let b = Arc::new(AtomicUsize::new(0));
+    let mut plugins = PluginManager::new();
+    plugins.add_plugin(TestPlugin::<0> { loads: a.clone() });
+    plugins.add_plugin(TestPlugin::<1> { loads: b.clone() });
+    (plugins, a, b)
+}
+
+fn package(proto: &str) -> String {
+    serde_json::json!({
+        "Input": { proto: null },
+        "MPs": {},
+        "Checks": {},
+        "Queries": {},
+        "Tables": {},
+        "Fields": {},
+        "ConfigRules": {},
+    })
+    .to_string()
+}
+
+fn name(name: &str) -> PackageName {
+    PackageName(String::from(name))
+}
+
+fn version() -> PackageVersion {
+    PackageVersion(String::from("1.0.0"))
+}
+
+fn proto(name: &str) -> Protocol {
+    Protocol(String::from(name))
+}
+
+#[tokio::test]
+async fn replace_reloads_affected_protocols() {
+    let (plugins, a, b) = plugins();
+    let manager = EtcManager::new();
+
+    manager
+        .load_pkg(name("pa"), version(), package("a"), &plugins)
+        .await
+        .unwrap();
+    manager
+        .load_pkg(name("pb"), version(), package("b"), &plugins)
+        .await
+        .unwrap();
+    let old = manager.spec().await;
+    let (loads_a, loads_b) =
+        (a.load(Ordering::SeqCst), b.load(Ordering::SeqCst));
+
+    manager
+        .replace_pkg(name("pb"), version(), package("b"), &plugins)
+        .await
+        .unwrap();
+    assert_eq!(a.load(Ordering::SeqCst), loads_a);
+    assert_eq!(b.load(Ordering::SeqCst), loads_b + 1);
+
+    let new = manager.spec().await;
+    assert!(Arc::ptr_eq(
+        &old.input[&proto("a")].handle,
+        &new.input[&proto("a")].handle
+    ));
+    assert!(!Arc::ptr_eq(
+        &old.input[&proto("b")].handle,
+        &new.input[&proto("b")].handle
+    ));
+}
+
+#[tokio::test]
+async fn remove_keeps_old_snapshot() {
+    let (plugins, a, _) = plugins();
+    let manager = EtcManager::new();
+
+    manager
+        .replace_pkg(name("pa"), version(), package("a"), &plugins)
+        .await
+        .unwrap();
+    manager
+        .replace_pkg(name("pb"), version(), package("b"), &plugins)
+        .await
+        .unwrap();
+    let old = manager.spec().await;
+    let loads_a = a.load(Ordering::SeqCst);
+
+    manager.remove_pkg(name("pb"), &plugins).await.unwrap();
+    assert_eq!(a.load(Ordering::SeqCst), loads_a);
+
+    let new = manager.spec().await;
+    assert!(new.input.contains_key(&proto("a")));
+    assert!(!new.input.contains_key(&proto("b")));
+    assert!(old.input.contains_key(&proto("b")));
+    assert_eq!(
+        manager
+            .loaded_pkgs()
+            .await
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec![&name("pa")]
+    );
+}
@@ -0,0 +1,178 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use etc::{
+    SampleDb, ThresholdBound, ThresholdLevel, ThresholdOp, ThresholdSpec,
+};
+use expression::Expr;
+use value::{Type, Value};
+
+fn bound(expr: &str) -> Option<ThresholdBound> {
+    Some(ThresholdBound {
+        op: ThresholdOp::Ge,
+        value: Expr::parse(expr).unwrap(),
+    })
+}
+
+/// Warn at 80 and go critical at 90.
+fn temperature() -> ThresholdSpec {
+    ThresholdSpec::Dynamic {
+        warning: bound("{80}"),
+        critical: bound("{90}"),
+    }
+}
+
+fn hysteresis(consecutive: u32) -> ThresholdSpec {
+    ThresholdSpec::Hysteresis {
+        threshold: Box::new(temperature()),
+        leave_warning: bound("{75}"),
+        leave_critical: bound("{85}"),
+        consecutive,
+    }
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("etc_hysteresis_{}_{}", name, std::process::id()))
+        .join("samples.json")
+}
+
+/// Evaluate a series of values, persisting the state between runs.
+fn run(
+    threshold: &ThresholdSpec,
+    name: &str,
+    values: &[i64],
+) -> Vec<Option<ThresholdLevel>> {
+    let path = temp_path(name);
+    let row = HashMap::new();
+    let now = SystemTime::now();
+    let levels = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let samples = SampleDb::load(path.clone()).unwrap();
+            let level = threshold
+                .eval_with_samples(
+                    &Value::Integer(*v),
+                    &row,
+                    &samples,
+                    "temperature".to_string(),
+                    now + Duration::from_secs(60 * i as u64),
+                )
+                .unwrap();
+            samples.save().unwrap();
+            level
+        })
+        .collect();
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    levels
+}
+
+#[test]
+fn without_hysteresis() {
+    let levels = run(&temperature(), "plain", &[79, 80, 79, 80, 79]);
+    assert_eq!(
+        levels,
+        [
+            None,
+            Some(ThresholdLevel::Warning),
+            None,
+            Some(ThresholdLevel::Warning),
+            None
+        ]
+    );
+}
+
+#[test]
+fn leave_bounds() {
+    let levels = run(
+        &hysteresis(1),
+        "leave",
+        &[79, 80, 79, 76, 74, 79, 91, 86, 84],
+    );
+    assert_eq!(
+        levels,
+        [
+            None,
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            None,
+            None,
+            Some(ThresholdLevel::Critical),
+            Some(ThresholdLevel::Critical),
+            Some(ThresholdLevel::Warning),
+        ]
+    );
+}
+
+#[test]
+fn consecutive_evaluations() {
+    let threshold = ThresholdSpec::Hysteresis {
+        threshold: Box::new(temperature()),
+        leave_warning: None,
+        leave_critical: None,
+        consecutive: 3,
+    };
+    let levels = run(
+        &threshold,
+        "consecutive",
+        &[80, 79, 80, 80, 80, 79, 80, 79, 79, 79],
+    );
+    assert_eq!(
+        levels,
+        [
+            None,
+            None,
+            None,
+            None,
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            Some(ThresholdLevel::Warning),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn stateless_eval() {
+    let row = HashMap::new();
+    assert_eq!(
+        hysteresis(3).eval(&Value::Integer(80), &row).unwrap(),
+        Some(ThresholdLevel::Warning)
+    );
+}
+
+#[test]
+fn deserialize_defaults() {
+    let threshold: ThresholdSpec = serde_json::from_value(serde_json::json!({
+        "hysteresis": {
+            "threshold": {
+                "dynamic": {
+                    "warning": {
+                        "op": "ge",
+                        "value": Expr::parse("{80}").unwrap()
+                    },
+                    "critical": null
+                }
+            }
+        }
+    }))
+    .unwrap();
+    assert!(matches!(
+        threshold,
+        ThresholdSpec::Hysteresis {
+            leave_warning: None,
+            leave_critical: None,
+            consecutive: 1,
+            ..
+        }
+    ));
+    threshold.check(&Type::Integer, &HashMap::new()).unwrap();
+}
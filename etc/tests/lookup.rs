@@ -0,0 +1,175 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use etc::{Etc, FieldSpec, LookupSpec, QueryMode, Source2, TableSpec};
+use etc_base::{
+    Annotated, DataFieldId, DataTableId, FieldId, ProtoDataFieldId,
+    ProtoDataTableId, Protocol, QueryId, Row, TableId,
+};
+use expression::{EvalError, Expr};
+use protocol::DataMap;
+use query::{ErrorAction, Query};
+use value::{DataError, Value};
+
+fn data_table(name: &str) -> DataTableId {
+    DataTableId(
+        Protocol(String::from("SNMP")),
+        ProtoDataTableId(String::from(name)),
+    )
+}
+
+fn data_field(name: &str) -> DataFieldId {
+    DataFieldId(
+        Protocol(String::from("SNMP")),
+        ProtoDataFieldId(String::from(name)),
+    )
+}
+
+fn field(name: &str, source: Source2) -> FieldSpec {
+    let mut field: FieldSpec = serde_json::from_value(serde_json::json!({
+        "Name": name,
+        "Source": "Config",
+        "InputType": "string"
+    }))
+    .unwrap();
+    field.source2 = Some(source);
+    field
+}
+
+fn data(table: &str, name: &str) -> Source2 {
+    Source2::Data(data_table(table), data_field(name), None)
+}
+
+fn table(etc: &mut Etc, name: &str, fields: Vec<FieldSpec>) {
+    let table: TableSpec = serde_json::from_value(serde_json::json!({
+        "Query": name,
+        "Name": name,
+        "Fields": fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>()
+    }))
+    .unwrap();
+    etc.queries.insert(
+        QueryId::from(name),
+        Query::Data(data_table(name), ErrorAction::Fail, false),
+    );
+    etc.tables.insert(TableId::from(name), table);
+    for field in fields {
+        etc.fields.insert(FieldId::from(field.name.as_str()), field);
+    }
+}
+
+/// Interfaces, with the name of their device looked up by device id.
+fn etc() -> Etc {
+    let mut etc = Etc::default();
+    table(
+        &mut etc,
+        "devices",
+        vec![
+            field("device_id", data("devices", "devId")),
+            field("device_name", data("devices", "devName")),
+        ],
+    );
+    table(
+        &mut etc,
+        "interfaces",
+        vec![
+            field("if_name", data("interfaces", "ifName")),
+            field(
+                "if_device",
+                Source2::Lookup(LookupSpec {
+                    table: TableId::from("devices"),
+                    key: Expr::parse("{${SNMP.ifDevice}}").unwrap(),
+                    key_field: FieldId::from("device_id"),
+                    field: FieldId::from("device_name"),
+                }),
+            ),
+        ],
+    );
+    etc
+}
+
+fn row(cells: &[(&str, &str)]) -> Row {
+    cells
+        .iter()
+        .map(|(field, value)| {
+            (
+                data_field(field),
+                Ok(Value::UnicodeString(value.to_string())),
+            )
+        })
+        .collect()
+}
+
+fn data_map() -> DataMap {
+    DataMap::from([
+        (
+            data_table("devices"),
+            Ok(Annotated {
+                value: vec![row(&[("devId", "1"), ("devName", "switch-1")])],
+                warnings: Vec::new(),
+            }),
+        ),
+        (
+            data_table("interfaces"),
+            Ok(Annotated {
+                value: vec![
+                    row(&[("ifName", "eth0"), ("ifDevice", "1")]),
+                    row(&[("ifName", "eth1"), ("ifDevice", "2")]),
+                ],
+                warnings: Vec::new(),
+            }),
+        ),
+    ])
+}
+
+#[test]
+fn lookup_field() {
+    let etc = etc();
+    let rows = etc.tables[&TableId::from("interfaces")]
+        .calculate(QueryMode::Monitoring, &etc, &data_map())
+        .unwrap()
+        .unwrap()
+        .value;
+    assert_eq!(rows.len(), 2);
+
+    let device = |name: &str| {
+        let name = Value::UnicodeString(name.to_string());
+        let row = rows
+            .iter()
+            .find(|row| {
+                row[&FieldId::from("if_name")].as_ref().ok() == Some(&name)
+            })
+            .unwrap();
+        row[&FieldId::from("if_device")].clone()
+    };
+
+    /* Resolved from the related row. */
+    assert_eq!(
+        device("eth0").ok(),
+        Some(Value::UnicodeString(String::from("switch-1")))
+    );
+
+    /* No related row: missing. */
+    assert!(matches!(
+        device("eth1"),
+        Err(EvalError::DataError(DataError::Missing))
+    ));
+}
+
+#[test]
+fn deserialize_lookup() {
+    let source: Source2 = serde_json::from_value(serde_json::json!({
+        "Lookup": {
+            "Table": "devices",
+            "Key": Expr::parse("{${SNMP.ifDevice}}").unwrap(),
+            "KeyField": "device_id",
+            "Field": "device_name"
+        }
+    }))
+    .unwrap();
+    assert!(matches!(
+        source,
+        Source2::Lookup(LookupSpec { table, .. })
+            if table == TableId::from("devices")
+    ));
+}
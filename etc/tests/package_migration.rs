@@ -0,0 +1,67 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use etc::{Error, Package, FORMAT_VERSION};
+use etc_base::{PackageName, Protocol};
+
+const PACKAGE_V1: &str = include_str!("fixtures/package_v1.json");
+
+fn name() -> PackageName {
+    PackageName(String::from("test"))
+}
+
+#[test]
+fn migrate_v1_package() {
+    let pkg = Package::from_json(&name(), PACKAGE_V1).unwrap();
+    assert!(pkg.input.contains_key(&Protocol(String::from("SNMP"))));
+    assert!(pkg.etc.tables.is_empty());
+
+    let json = serde_json::to_value(&pkg).unwrap();
+    assert_eq!(json["FormatVersion"], FORMAT_VERSION);
+    assert!(json.get("DataTables").is_none());
+    assert!(json.get("DataFields").is_none());
+    assert!(json.get("DataTableFields").is_none());
+}
+
+#[test]
+fn migration_keeps_loaded_data() {
+    /* The dropped sections were never part of the loaded package: a
+     * plain load, which ignores them, gives the same result. */
+    let migrated = Package::from_json(&name(), PACKAGE_V1).unwrap();
+    let plain = serde_json::from_str::<Package>(PACKAGE_V1).unwrap();
+    assert_eq!(
+        serde_json::to_value(&migrated).unwrap(),
+        serde_json::to_value(&plain).unwrap()
+    );
+
+    let v1: serde_json::Value = serde_json::from_str(PACKAGE_V1).unwrap();
+    let json = serde_json::to_value(&migrated).unwrap();
+    assert_eq!(json["Input"], v1["Input"]);
+}
+
+#[test]
+fn current_package_roundtrip() {
+    let pkg = Package::from_json(&name(), PACKAGE_V1).unwrap();
+    let data = serde_json::to_string(&pkg).unwrap();
+    let reloaded = Package::from_json(&name(), &data).unwrap();
+    assert_eq!(
+        serde_json::to_value(&pkg).unwrap(),
+        serde_json::to_value(&reloaded).unwrap()
+    );
+}
+
+#[test]
+fn reject_future_package_version() {
+    let mut json: serde_json::Value = serde_json::from_str(PACKAGE_V1).unwrap();
+    json["FormatVersion"] = serde_json::json!(FORMAT_VERSION + 1);
+    let data = serde_json::to_string(&json).unwrap();
+
+    match Package::from_json(&name(), &data) {
+        Err(Error::PackageFormatVersion(_, v)) => {
+            assert_eq!(v, FORMAT_VERSION + 1)
+        }
+        r => panic!("expected a format version error, got {r:?}"),
+    }
+    assert!(serde_json::from_str::<Package>(&data).is_err());
+}
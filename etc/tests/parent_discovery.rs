@@ -0,0 +1,207 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use etc::{DiscoveredItem, Error, Etc, FieldSpec, ParentSpec, TableSpec};
+use etc_base::{FieldId, QueryId, TableId};
+use expression::{EvalResult, Expr};
+use value::Value;
+
+fn field(name: &str) -> FieldSpec {
+    serde_json::from_value(serde_json::json!({
+        "Name": name,
+        "Discovery": true,
+        "Source": "Config",
+        "InputType": "string"
+    }))
+    .unwrap()
+}
+
+fn table(
+    item_id: &str,
+    parent: Option<ParentSpec>,
+    fields: &[&str],
+) -> TableSpec {
+    TableSpec {
+        query: QueryId::from("query"),
+        name: None,
+        title: None,
+        monitoring: true,
+        discovery: true,
+        layer: None,
+        check_mk: None,
+        elastic_index: None,
+        description: None,
+        singleton: false,
+        item_type: None,
+        item_id: Some(Expr::parse(item_id).unwrap()),
+        item_name: None,
+        parent,
+        sub_tables: None,
+        fields: fields.iter().map(|f| FieldId::from(*f)).collect(),
+        group_by: None,
+    }
+}
+
+/// VMs, with disks discovered from the VMs they belong to.
+fn vm_etc() -> Etc {
+    let mut etc = Etc::default();
+    for name in ["vm_name", "disk_vm", "disk_name"] {
+        etc.fields.insert(FieldId::from(name), field(name));
+    }
+    etc.tables.insert(
+        TableId::from("vms"),
+        table("{${vm_name}}", None, &["vm_name"]),
+    );
+    etc.tables.insert(
+        TableId::from("disks"),
+        table(
+            "{${disk_name}}",
+            Some(ParentSpec {
+                table: TableId::from("vms"),
+                item_id: Expr::parse("{${disk_vm}}").unwrap(),
+            }),
+            &["disk_vm", "disk_name"],
+        ),
+    );
+    etc
+}
+
+fn row(cells: &[(&str, &str)]) -> HashMap<FieldId, EvalResult> {
+    cells
+        .iter()
+        .map(|(field, value)| {
+            (
+                FieldId::from(*field),
+                Ok(Value::UnicodeString(value.to_string())),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn discover_children_from_parents() {
+    let etc = vm_etc();
+    let mut warnings = Vec::new();
+
+    assert_eq!(
+        etc.discovery_order([&TableId::from("disks")]).unwrap(),
+        vec![TableId::from("vms"), TableId::from("disks")]
+    );
+
+    let vms = etc.tables[&TableId::from("vms")]
+        .discover_items(
+            &etc,
+            &[row(&[("vm_name", "web")]), row(&[("vm_name", "db")])],
+            None,
+            &mut warnings,
+        )
+        .unwrap();
+    assert_eq!(vms.len(), 2);
+    assert!(vms.iter().all(|vm| vm.parent.is_none()));
+
+    let disks = etc.tables[&TableId::from("disks")]
+        .discover_items(
+            &etc,
+            &[
+                row(&[("disk_vm", "web"), ("disk_name", "sda")]),
+                row(&[("disk_vm", "db"), ("disk_name", "sda")]),
+                row(&[("disk_vm", "db"), ("disk_name", "sdb")]),
+                row(&[("disk_vm", "removed"), ("disk_name", "sda")]),
+            ],
+            Some(&vms),
+            &mut warnings,
+        )
+        .unwrap();
+
+    assert_eq!(
+        disks,
+        vec![
+            DiscoveredItem {
+                id: String::from("web/sda"),
+                name: None,
+                parent: Some(String::from("web")),
+            },
+            DiscoveredItem {
+                id: String::from("db/sda"),
+                name: None,
+                parent: Some(String::from("db")),
+            },
+            DiscoveredItem {
+                id: String::from("db/sdb"),
+                name: None,
+                parent: Some(String::from("db")),
+            },
+        ]
+    );
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn children_keyed_without_parents() {
+    let etc = vm_etc();
+    let mut warnings = Vec::new();
+
+    let disks = etc.tables[&TableId::from("disks")]
+        .discover_items(
+            &etc,
+            &[row(&[("disk_vm", "web"), ("disk_name", "sda")])],
+            None,
+            &mut warnings,
+        )
+        .unwrap();
+    assert_eq!(
+        disks,
+        vec![DiscoveredItem {
+            id: String::from("web/sda"),
+            name: None,
+            parent: Some(String::from("web")),
+        }]
+    );
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn missing_parent_id() {
+    let etc = vm_etc();
+    let mut warnings = Vec::new();
+    let parents = [DiscoveredItem {
+        id: String::from("web"),
+        name: None,
+        parent: None,
+    }];
+
+    let disks = etc.tables[&TableId::from("disks")]
+        .discover_items(
+            &etc,
+            &[row(&[("disk_name", "sda")])],
+            Some(&parents),
+            &mut warnings,
+        )
+        .unwrap();
+    assert!(disks.is_empty());
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn reject_invalid_parents() {
+    let mut etc = vm_etc();
+    etc.tables.get_mut(&TableId::from("vms")).unwrap().discovery = false;
+    assert!(matches!(
+        etc.discovery_order([&TableId::from("disks")]),
+        Err(Error::ParentNotDiscovered(_, _))
+    ));
+
+    let mut etc = vm_etc();
+    etc.tables.get_mut(&TableId::from("vms")).unwrap().parent =
+        Some(ParentSpec {
+            table: TableId::from("disks"),
+            item_id: Expr::parse("{${vm_name}}").unwrap(),
+        });
+    assert!(matches!(
+        etc.discovery_order([&TableId::from("disks")]),
+        Err(Error::ParentCycle(_))
+    ));
+}
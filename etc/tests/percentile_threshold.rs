@@ -0,0 +1,178 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use etc::{
+    percentile, SampleDb, ThresholdBound, ThresholdError, ThresholdLevel,
+    ThresholdOp, ThresholdSpec,
+};
+use expression::Expr;
+use unit::Quantity;
+use value::{Type, Value};
+
+/// `p95 warn 200ms crit 500ms` over the last ten minutes.
+fn latency_threshold() -> ThresholdSpec {
+    ThresholdSpec::Percentile {
+        percentile: 95,
+        window: 600,
+        warning: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse("{200 ms}").unwrap(),
+        }),
+        critical: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse("{500 ms}").unwrap(),
+        }),
+    }
+}
+
+fn latency(ms: u32) -> Value {
+    Value::Quantity(Quantity::parse(&format!("{ms} ms")).unwrap())
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("etc_samples_{}_{}", name, std::process::id()))
+        .join("samples.json")
+}
+
+#[test]
+fn nearest_rank_percentile() {
+    let samples: Vec<f64> = (1..=20).map(f64::from).collect();
+    assert_eq!(percentile(&samples, 95), Some(19.0));
+    assert_eq!(percentile(&samples, 50), Some(10.0));
+    assert_eq!(percentile(&samples, 100), Some(20.0));
+    assert_eq!(percentile(&samples, 0), Some(1.0));
+    assert_eq!(percentile(&[], 95), None);
+}
+
+#[test]
+fn single_spike_does_not_trigger() {
+    let threshold = latency_threshold();
+    let samples = SampleDb::new(temp_path("spike"));
+    let row = HashMap::new();
+    let start = SystemTime::now();
+
+    let mut levels = Vec::new();
+    for i in 0..20 {
+        let value = match i {
+            10 => latency(900),
+            _ => latency(100),
+        };
+        levels.push(
+            threshold
+                .eval_with_samples(
+                    &value,
+                    &row,
+                    &samples,
+                    "latency".to_string(),
+                    start + Duration::from_secs(i * 10),
+                )
+                .unwrap(),
+        );
+    }
+
+    /* Until enough samples are collected, the spike dominates. */
+    assert_eq!(levels[10], Some(ThresholdLevel::Critical));
+    assert_eq!(levels[19], None);
+    /* The instantaneous value alone would have triggered. */
+    assert_eq!(
+        threshold.eval(&latency(900), &row).unwrap(),
+        Some(ThresholdLevel::Critical)
+    );
+}
+
+#[test]
+fn sustained_latency_triggers() {
+    let threshold = latency_threshold();
+    let samples = SampleDb::new(temp_path("sustained"));
+    let row = HashMap::new();
+    let now = SystemTime::now();
+    for (i, ms) in [250, 300, 280].into_iter().enumerate() {
+        let level = threshold
+            .eval_with_samples(
+                &latency(ms),
+                &row,
+                &samples,
+                "latency".to_string(),
+                now + Duration::from_secs(i as u64),
+            )
+            .unwrap();
+        assert_eq!(level, Some(ThresholdLevel::Warning));
+    }
+}
+
+#[test]
+fn samples_persist_across_runs() {
+    let path = temp_path("persist");
+    let threshold = latency_threshold();
+    let row = HashMap::new();
+    let now = SystemTime::now();
+
+    let samples = SampleDb::load(path.clone()).unwrap();
+    for i in 0..19 {
+        threshold
+            .eval_with_samples(
+                &latency(100),
+                &row,
+                &samples,
+                "latency".to_string(),
+                now + Duration::from_secs(i),
+            )
+            .unwrap();
+    }
+    samples.save().unwrap();
+
+    /* With the history of the previous run, the spike is absorbed. */
+    let samples = SampleDb::load(path.clone()).unwrap();
+    assert_eq!(
+        threshold
+            .eval_with_samples(
+                &latency(900),
+                &row,
+                &samples,
+                "latency".to_string(),
+                now + Duration::from_secs(20),
+            )
+            .unwrap(),
+        None
+    );
+
+    /* Samples outside the window are discarded. */
+    let samples = SampleDb::load(path.clone()).unwrap();
+    assert_eq!(
+        threshold
+            .eval_with_samples(
+                &latency(900),
+                &row,
+                &samples,
+                "latency".to_string(),
+                now + Duration::from_secs(3600),
+            )
+            .unwrap(),
+        Some(ThresholdLevel::Critical)
+    );
+
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn check_percentile() {
+    let row = HashMap::new();
+    latency_threshold()
+        .check(&Type::Quantity(unit::Dimension::Time), &row)
+        .unwrap();
+    let invalid = ThresholdSpec::Percentile {
+        percentile: 120,
+        window: 600,
+        warning: None,
+        critical: None,
+    };
+    assert!(matches!(
+        invalid.check(&Type::Float, &row),
+        Err(ThresholdError::InvalidPercentile(120))
+    ));
+}
@@ -0,0 +1,141 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use etc::{Etc, FieldSpec, Redaction, Source, Spec};
+use etc_base::{
+    DataFieldId, DataTableId, FieldId, ProtoDataFieldId, ProtoDataTableId,
+    Protocol,
+};
+use expression::{EvalCell, Expr};
+use protocol::{DataFieldSpec, Input, REDACTED};
+use value::{Type, Value};
+
+fn field(sensitive: bool, redaction: Option<&str>) -> FieldSpec {
+    let mut spec = serde_json::json!({
+        "Name": "serial",
+        "Source": "Config",
+        "InputType": "string",
+        "Sensitive": sensitive,
+    });
+    if let Some(redaction) = redaction {
+        spec["Redaction"] = serde_json::json!(redaction);
+    }
+    serde_json::from_value(spec).unwrap()
+}
+
+fn serial() -> Value {
+    Value::UnicodeString("A1B2C3".to_string())
+}
+
+#[test]
+fn not_sensitive_by_default() {
+    let field: FieldSpec = serde_json::from_value(serde_json::json!({
+        "Name": "serial",
+        "Source": "Config",
+        "InputType": "string",
+    }))
+    .unwrap();
+    assert!(!field.sensitive);
+    assert!(!field.is_excluded());
+    assert!(matches!(
+        field.output_value(serial()),
+        Value::UnicodeString(v) if v == "A1B2C3"
+    ));
+}
+
+#[test]
+fn sensitive_value_is_masked() {
+    let field = field(true, None);
+    assert_eq!(field.redaction, Redaction::Mask);
+    assert!(!field.is_excluded());
+    assert!(matches!(
+        field.output_value(serial()),
+        Value::UnicodeString(v) if v == REDACTED
+    ));
+}
+
+#[test]
+fn sensitive_value_can_be_excluded() {
+    assert!(field(true, Some("exclude")).is_excluded());
+    assert!(!field(false, Some("exclude")).is_excluded());
+}
+
+#[test]
+fn sensitive_non_string_is_excluded() {
+    let mut field = field(true, None);
+    field.input_type = Type::Integer;
+    assert!(field.is_excluded());
+}
+
+#[test]
+fn sensitive_flag_follows_data_field() {
+    let proto = || Protocol("test".to_string());
+    let data_field = |name: &str, sensitive| {
+        (
+            ProtoDataFieldId(name.to_string()),
+            DataFieldSpec {
+                name: name.to_string(),
+                input_type: Type::UnicodeString,
+                sensitive,
+            },
+        )
+    };
+    let field = |name: &str, sensitive| {
+        let mut field = field(sensitive, None);
+        field.source = Source::Data(
+            DataTableId(proto(), ProtoDataTableId("devices".to_string())),
+            DataFieldId(proto(), ProtoDataFieldId(name.to_string())),
+            None,
+        );
+        (FieldId(name.to_string()), field)
+    };
+
+    let mut etc = Etc::default();
+    etc.fields = HashMap::from_iter([
+        field("serial", false),
+        field("owner", true),
+        field("model", false),
+    ]);
+    let input = Input {
+        handle: Arc::new(()),
+        data_tables: HashMap::new(),
+        data_fields: HashMap::from_iter([
+            data_field("serial", true),
+            data_field("owner", false),
+            data_field("model", false),
+        ]),
+    };
+    let spec = Spec::new(etc, HashMap::from_iter([(proto(), input)]));
+
+    let sensitive = |name: &str| {
+        let field = &spec.etc.fields[&FieldId(name.to_string())];
+        let data_field = &spec.input[&proto()].data_fields
+            [&ProtoDataFieldId(name.to_string())];
+        (field.sensitive, data_field.sensitive)
+    };
+    assert_eq!(sensitive("serial"), (true, true));
+    assert_eq!(sensitive("owner"), (true, true));
+    assert_eq!(sensitive("model"), (false, false));
+}
+
+#[test]
+fn sensitive_value_is_usable_in_formula() {
+    let field = field(true, None);
+    let row = HashMap::from_iter([(
+        field.name.as_str(),
+        EvalCell::new_evaluated(Ok(serial())),
+    )]);
+    let expr = Expr::parse("{starts_with($serial, 'A1')}").unwrap();
+    assert!(matches!(
+        expr.eval_in_row(Some(&row), None).unwrap(),
+        Value::Boolean(true)
+    ));
+    assert!(matches!(
+        field.output_value(serial()),
+        Value::UnicodeString(v) if v == REDACTED
+    ));
+}
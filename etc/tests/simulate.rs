@@ -0,0 +1,102 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use etc::{
+    simulate, Sample, ThresholdBound, ThresholdLevel, ThresholdOp,
+    ThresholdSpec,
+};
+use expression::Expr;
+use value::{DataError, Value};
+
+fn capacity_threshold(warning: &str, critical: &str) -> ThresholdSpec {
+    ThresholdSpec::Dynamic {
+        warning: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse(&format!("{{${{capacity}} * {warning}}}"))
+                .unwrap(),
+        }),
+        critical: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse(&format!("{{${{capacity}} * {critical}}}"))
+                .unwrap(),
+        }),
+    }
+}
+
+fn sample(item: &str, length: i64) -> Sample<'static> {
+    Sample {
+        item: item.to_string(),
+        value: Ok(Value::Integer(length)),
+        row: HashMap::from([
+            ("length", Ok(Value::Integer(length))),
+            ("capacity", Ok(Value::Integer(100))),
+        ]),
+    }
+}
+
+fn samples() -> Vec<Sample<'static>> {
+    vec![
+        sample("q1", 50),
+        sample("q2", 75),
+        sample("q3", 86),
+        sample("q4", 92),
+        sample("q5", 97),
+        Sample {
+            item: "q6".to_string(),
+            value: Err(DataError::Missing),
+            row: HashMap::new(),
+        },
+    ]
+}
+
+fn changes(sim: &etc::Simulation) -> Vec<(&str, Option<ThresholdLevel>)> {
+    sim.changes
+        .iter()
+        .map(|c| (c.item.as_str(), c.proposed.clone()))
+        .collect()
+}
+
+#[test]
+fn stricter_threshold() {
+    let current = capacity_threshold("0.8", "0.9");
+    let proposed = capacity_threshold("0.7", "0.85");
+    let sim = simulate(Some(&current), &proposed, &samples()).unwrap();
+    assert_eq!(sim.states.len(), 6);
+    assert_eq!(
+        changes(&sim),
+        vec![
+            ("q2", Some(ThresholdLevel::Warning)),
+            ("q3", Some(ThresholdLevel::Critical)),
+        ]
+    );
+    assert!(sim.changes.iter().all(|c| c.is_escalation()));
+}
+
+#[test]
+fn looser_threshold() {
+    let current = capacity_threshold("0.8", "0.9");
+    let proposed = capacity_threshold("0.9", "0.95");
+    let sim = simulate(Some(&current), &proposed, &samples()).unwrap();
+    assert_eq!(
+        changes(&sim),
+        vec![("q3", None), ("q4", Some(ThresholdLevel::Warning))]
+    );
+    assert!(sim.changes.iter().all(|c| !c.is_escalation()));
+}
+
+#[test]
+fn no_current_threshold() {
+    let proposed = capacity_threshold("0.8", "0.9");
+    let sim = simulate(None, &proposed, &samples()).unwrap();
+    assert_eq!(
+        changes(&sim),
+        vec![
+            ("q3", Some(ThresholdLevel::Warning)),
+            ("q4", Some(ThresholdLevel::Critical)),
+            ("q5", Some(ThresholdLevel::Critical)),
+        ]
+    );
+}
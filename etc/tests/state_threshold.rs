@@ -0,0 +1,57 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use etc::{ThresholdError, ThresholdLevel, ThresholdSpec};
+use value::{TriState, Type, Value};
+
+fn link_threshold() -> ThresholdSpec {
+    serde_json::from_value(serde_json::json!({
+        "state": {
+            "false": "Critical",
+            "unknown": "Warning"
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn unknown_state() {
+    let row = HashMap::new();
+    let threshold = link_threshold();
+    assert_eq!(
+        threshold
+            .eval(&Value::TriState(TriState::False), &row)
+            .unwrap(),
+        Some(ThresholdLevel::Critical)
+    );
+    assert_eq!(
+        threshold
+            .eval(&Value::TriState(TriState::Unknown), &row)
+            .unwrap(),
+        Some(ThresholdLevel::Warning)
+    );
+    assert_eq!(
+        threshold
+            .eval(&Value::TriState(TriState::True), &row)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        threshold.eval(&Value::Boolean(false), &row).unwrap(),
+        Some(ThresholdLevel::Critical)
+    );
+}
+
+#[test]
+fn check_state_type() {
+    let row = HashMap::new();
+    link_threshold().check(&Type::TriState, &row).unwrap();
+    link_threshold().check(&Type::Boolean, &row).unwrap();
+    assert!(matches!(
+        link_threshold().check(&Type::Integer, &row),
+        Err(ThresholdError::NotBoolean(_))
+    ));
+}
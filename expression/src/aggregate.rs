@@ -0,0 +1,187 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use value::{DataError, NumericValuePair, Type, Value};
+
+use super::error::{EvalError, EvalResult};
+
+/// Functions summarizing a column of values.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// How aggregates treat missing values.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingValues {
+    /// Aggregate over the values that are present.
+    #[default]
+    Skip,
+    /// Any missing value makes the result missing.
+    Propagate,
+}
+
+impl Aggregate {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        }
+    }
+
+    /// Aggregate a column of values, skipping missing values.
+    pub fn eval<I>(self, values: I) -> EvalResult
+    where
+        I: IntoIterator<Item = EvalResult>,
+    {
+        self.eval_opts(values, MissingValues::Skip)
+    }
+
+    /// Aggregate a column of values. Errors other than missing values
+    /// are always returned. Without values, the count and sum are
+    /// zero, while the average, minimum and maximum are missing.
+    pub fn eval_opts<I>(self, values: I, missing: MissingValues) -> EvalResult
+    where
+        I: IntoIterator<Item = EvalResult>,
+    {
+        let mut acc: Option<Value> = None;
+        let mut count = 0;
+        for value in values {
+            let value = match value {
+                Ok(value) => value,
+                Err(e) if e.is_missing() && missing == MissingValues::Skip => {
+                    continue
+                }
+                Err(e) => return Err(e),
+            };
+            count += 1;
+            acc = Some(match acc {
+                None => value,
+                Some(acc) => match self {
+                    Aggregate::Count => acc,
+                    Aggregate::Sum | Aggregate::Avg => add(acc, value)?,
+                    Aggregate::Min => match compare(&value, &acc)? {
+                        Ordering::Less => value,
+                        _ => acc,
+                    },
+                    Aggregate::Max => match compare(&value, &acc)? {
+                        Ordering::Greater => value,
+                        _ => acc,
+                    },
+                },
+            });
+        }
+
+        match (self, acc) {
+            (Aggregate::Count, _) => Ok(Value::Integer(count)),
+            (Aggregate::Sum, None) => Ok(Value::Integer(0)),
+            (_, None) => Err(EvalError::DataError(DataError::Missing)),
+            (Aggregate::Avg, Some(sum)) => match sum {
+                Value::Integer(n) => Ok(Value::Float(n as f64 / count as f64)),
+                Value::Float(n) => Ok(Value::Float(n / count as f64)),
+                Value::Decimal(n) => {
+                    Ok(Value::Float(n.to_f64() / count as f64))
+                }
+                Value::Quantity(q) => Ok(Value::Quantity(q / count as f64)),
+                _ => Err(EvalError::TypeError(
+                    "invalid type for 'avg' (expected: numeric)",
+                )),
+            },
+            (Aggregate::Sum | Aggregate::Min | Aggregate::Max, Some(v)) => {
+                check_value(self, &v)?;
+                Ok(v)
+            }
+        }
+    }
+
+    /// The result type of the aggregate over values of type `typ`.
+    pub fn check(self, typ: &Type) -> Result<Type, EvalError> {
+        match (self, typ) {
+            (Aggregate::Count, _) => Ok(Type::Integer),
+            (
+                Aggregate::Sum,
+                Type::Integer | Type::Float | Type::Decimal | Type::Quantity(_),
+            ) => Ok(typ.clone()),
+            (Aggregate::Avg, Type::Integer | Type::Float | Type::Decimal) => {
+                Ok(Type::Float)
+            }
+            (Aggregate::Avg, Type::Quantity(_)) => Ok(typ.clone()),
+            (
+                Aggregate::Min | Aggregate::Max,
+                Type::Integer
+                | Type::Float
+                | Type::Decimal
+                | Type::Quantity(_)
+                | Type::UnicodeString
+                | Type::Time
+                | Type::Age,
+            ) => Ok(typ.clone()),
+            _ => Err(type_error(self)),
+        }
+    }
+}
+
+fn add(a: Value, b: Value) -> EvalResult {
+    match NumericValuePair::from(a, b) {
+        Some(NumericValuePair::Integer(a, b)) => Ok(Value::Integer(
+            a.checked_add(b).ok_or(EvalError::IntegerOverflow)?,
+        )),
+        Some(NumericValuePair::Float(a, b)) => Ok(Value::Float(a + b)),
+        Some(NumericValuePair::Decimal(a, b)) => Ok(Value::Decimal((a + b)?)),
+        Some(NumericValuePair::Quantity(a, b)) => Ok(Value::Quantity((a + b)?)),
+        None => Err(EvalError::TypeError(
+            "invalid type for 'sum' or 'avg' (expected: numeric)",
+        )),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Result<Ordering, EvalError> {
+    let ord = match (a, b) {
+        (Value::UnicodeString(a), Value::UnicodeString(b)) => Some(a.cmp(b)),
+        (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::Age(a), Value::Age(b)) => Some(a.cmp(b)),
+        (a, b) => match NumericValuePair::from(a.clone(), b.clone()) {
+            Some(NumericValuePair::Integer(a, b)) => Some(a.cmp(&b)),
+            Some(NumericValuePair::Float(a, b)) => a.partial_cmp(&b),
+            Some(NumericValuePair::Decimal(a, b)) => Some(a.cmp(&b)),
+            Some(NumericValuePair::Quantity(a, b)) => a.partial_cmp(&b)?,
+            None => {
+                return Err(EvalError::TypeError(
+                    "invalid type for 'min' or 'max'",
+                ))
+            }
+        },
+    };
+    /* NaN neither replaces nor is replaced. */
+    Ok(ord.unwrap_or(Ordering::Equal))
+}
+
+fn check_value(aggregate: Aggregate, value: &Value) -> Result<(), EvalError> {
+    aggregate.check(&value.get_type()).map(|_| ())
+}
+
+fn type_error(aggregate: Aggregate) -> EvalError {
+    EvalError::TypeError(match aggregate {
+        Aggregate::Count => "invalid type for 'count'",
+        Aggregate::Sum => "invalid type for 'sum' (expected: numeric)",
+        Aggregate::Avg => "invalid type for 'avg' (expected: numeric)",
+        Aggregate::Min => "invalid type for 'min'",
+        Aggregate::Max => "invalid type for 'max'",
+    })
+}
@@ -52,8 +52,14 @@ pub enum EvalError {
     // FromUtf16(String),
     #[error("Overflow in time / age calculation")]
     TimeOverflow,
+    #[error("Failed to parse time: {0}")]
+    TimeParseError(String),
+    #[error("Ambiguous time without timezone: {0}")]
+    NaiveTime(String),
     #[error("Error while using a selector: {0}")]
     Selector(String),
+    #[error("Invalid regex: {0}")]
+    Regex(String),
 }
 
 impl From<UnitError> for EvalError {
@@ -75,6 +81,16 @@ impl<'f> From<dynfmt::Error<'f>> for EvalError {
 }
 
 impl EvalError {
+    /// Whether the error is due to missing data only (as opposed to
+    /// an error value or an evaluation error).
+    pub fn is_missing(&self) -> bool {
+        match self {
+            Self::DataError(DataError::Missing) => true,
+            Self::VariableError(_, e) => e.is_missing(),
+            _ => false,
+        }
+    }
+
     pub fn is_missing_data(&self) -> bool {
         match self {
             Self::DataError(DataError::Missing)
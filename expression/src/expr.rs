@@ -2,24 +2,32 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt::{self, Display};
 use std::ops::RangeInclusive;
+use std::sync::Arc;
 
 use agent_utils::pyrepr::PyUnicode;
-use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use derivative::Derivative;
 use dynfmt::{python::PythonFormat, Format};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use unit::{Dimension, FracPrefix, Quantity, TimeUnit, Unit};
-use value::{Data, DataError, NumericTypePair, NumericValuePair, Type, Value};
+use unit::{
+    Dimension, DimensionlessUnit, FracPrefix, Quantity, TimeUnit, Unit,
+};
+use value::{
+    Data, DataError, Decimal, ListValue, NumericTypePair, NumericValuePair,
+    TriState, Type, Value,
+};
 
 use crate::options::EvalOpts;
 
 use super::error::EvalError;
+use super::aggregate::{Aggregate, MissingValues};
 use super::eval::EvalCell;
 use super::parser::parse_expr;
 
@@ -42,6 +50,8 @@ pub enum Expr {
     Lt(Box<Expr>, Box<Expr>),
     Eq(Box<Expr>, Box<Expr>),
     Ne(Box<Expr>, Box<Expr>),
+    /// Numeric equality within the epsilon from the evaluation options.
+    ApproxEq(Box<Expr>, Box<Expr>),
     Gt(Box<Expr>, Box<Expr>),
     Ge(Box<Expr>, Box<Expr>),
 
@@ -59,6 +69,20 @@ pub enum Expr {
 
     // General functions
     Fallback(Box<Expr>, Box<Expr>),
+    /// The first argument that is neither missing nor an empty option.
+    Coalesce(Vec<Expr>),
+    /// The second argument if the first is missing.
+    Default(Box<Expr>, Box<Expr>),
+    /// Conditional; only the selected branch is evaluated.
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+
+    // List functions (`@` refers to the element in the second argument)
+    Map(Box<Expr>, Box<Expr>),
+    Filter(Box<Expr>, Box<Expr>),
+    /// Sum, average, minimum, maximum or count of a list.
+    Aggregate(Aggregate, Box<Expr>),
+    /// List element by index; negative indices count from the end.
+    Index(Box<Expr>, Box<Expr>),
 
     // Type conversions
     FromUtf8(Box<Expr>),
@@ -91,18 +115,59 @@ pub enum Expr {
     SHA1(Box<Expr>),
     MD5(Box<Expr>),
 
+    // Unicode string functions (substring indexes characters, not bytes)
+    Substring(Box<Expr>, Box<Expr>, Box<Expr>),
+    Split(Box<Expr>, Box<Expr>),
+    Replace(Box<Expr>, Box<Expr>, Box<Expr>),
+    Lower(Box<Expr>),
+    Upper(Box<Expr>),
+    Trim(Box<Expr>),
+
+    // String comparisons (eq_ci compares case-folded strings)
+    EqCi(Box<Expr>, Box<Expr>),
+    Contains(Box<Expr>, Box<Expr>),
+    StartsWith(Box<Expr>, Box<Expr>),
+    Matches(Box<Expr>, Box<Expr>),
+
     // Validation
     NotEmpty(Box<Expr>),
 
+    // Tri-state functions
+    IsUnknown(Box<Expr>),
+    UnknownAs(Box<Expr>, Box<Expr>),
+
     // Numeric Functions
     Log(Box<Expr>, Box<Expr>),
     Sign(Box<Expr>),
     Abs(Box<Expr>),
+    // percent(a, b) is a / b as a percentage; missing if b is zero
+    Percent(Box<Expr>, Box<Expr>),
+    /// Smaller and larger of two numbers; clamp limits a number to
+    /// [min, max]. Missing operands other than the clamped value are
+    /// ignored unless missing values are propagated.
+    Min(Box<Expr>, Box<Expr>),
+    Max(Box<Expr>, Box<Expr>),
+    Clamp(Box<Expr>, Box<Expr>, Box<Expr>),
+
+    // Rounding: round and round_to round half to even
+    Round(Box<Expr>),
+    Floor(Box<Expr>),
+    Ceil(Box<Expr>),
+    RoundTo(Box<Expr>, Box<Expr>),
 
     // Bit field
     BitsLE(Box<Expr>, Box<Expr>, Box<Expr>),
     BitsBE(Box<Expr>, Box<Expr>, Box<Expr>),
 
+    // Date / time functions
+    Now,
+    /// Parse a timestamp that includes a timezone offset.
+    ParseTime(Box<Expr>, String),
+    /// Parse a timestamp, interpreting it as UTC if it has no offset.
+    ParseTimeUtc(Box<Expr>, String),
+    /// An age as a quantity in seconds.
+    Duration(Box<Expr>),
+
     // MP-specific
     UnpackTime(Box<Expr>),
 }
@@ -177,9 +242,12 @@ impl Expr {
                     (Value::Boolean(v1), Value::Boolean(v2)) => {
                         Ok(Value::Boolean(v1 || v2))
                     }
-                    _ => Err(EvalError::TypeError(
-                        "invalid types for boolean or",
-                    )),
+                    (v1, v2) => match (tristate(&v1), tristate(&v2)) {
+                        (Some(v1), Some(v2)) => Ok(Value::TriState(v1.or(v2))),
+                        _ => Err(EvalError::TypeError(
+                            "invalid types for boolean or",
+                        )),
+                    },
                 }
             }
 
@@ -191,14 +259,20 @@ impl Expr {
                     (Value::Boolean(v1), Value::Boolean(v2)) => {
                         Ok(Value::Boolean(v1 && v2))
                     }
-                    _ => Err(EvalError::TypeError(
-                        "invalid types for boolean and",
-                    )),
+                    (v1, v2) => match (tristate(&v1), tristate(&v2)) {
+                        (Some(v1), Some(v2)) => {
+                            Ok(Value::TriState(v1.and(v2)))
+                        }
+                        _ => Err(EvalError::TypeError(
+                            "invalid types for boolean and",
+                        )),
+                    },
                 }
             }
 
             Self::Not(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::Boolean(v) => Ok(Value::Boolean(!v)),
+                Value::TriState(v) => Ok(Value::TriState(v.not())),
                 _ => Err(EvalError::TypeError("invalid types for boolean not")),
             },
 
@@ -219,6 +293,9 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 > v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 > v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 > t2))
                     }
@@ -248,6 +325,9 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 >= v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 >= v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 >= t2))
                     }
@@ -277,12 +357,18 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 == v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 == v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 == t2))
                     }
                     (Value::Age(d1), Value::Age(d2)) => {
                         Ok(Value::Boolean(d1 == d2))
                     }
+                    (Value::TriState(v1), Value::TriState(v2)) => {
+                        Ok(Value::Boolean(v1 == v2))
+                    }
                     _ => Err(EvalError::TypeError(
                         "invalid types for comparison operator",
                     )),
@@ -306,18 +392,45 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 != v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 != v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 != t2))
                     }
                     (Value::Age(d1), Value::Age(d2)) => {
                         Ok(Value::Boolean(d1 != d2))
                     }
+                    (Value::TriState(v1), Value::TriState(v2)) => {
+                        Ok(Value::Boolean(v1 != v2))
+                    }
                     _ => Err(EvalError::TypeError(
                         "invalid types for comparison operator",
                     )),
                 }
             }
 
+            Self::ApproxEq(e1, e2) => {
+                match (
+                    e1.eval_in_row_opts(vars, data, opts)?,
+                    e2.eval_in_row_opts(vars, data, opts)?,
+                ) {
+                    (
+                        v1 @ (Value::Integer(_)
+                        | Value::Float(_)
+                        | Value::Decimal(_)
+                        | Value::Quantity(_)),
+                        v2 @ (Value::Integer(_)
+                        | Value::Float(_)
+                        | Value::Decimal(_)
+                        | Value::Quantity(_)),
+                    ) => Ok(Value::Boolean(v1.approx_eq(&v2, opts.epsilon))),
+                    _ => Err(EvalError::TypeError(
+                        "invalid types for approximate comparison",
+                    )),
+                }
+            }
+
             Self::Le(e1, e2) => {
                 match (
                     e1.eval_in_row_opts(vars, data, opts)?,
@@ -335,6 +448,9 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 <= v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 <= v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 <= t2))
                     }
@@ -364,6 +480,9 @@ impl Expr {
                     (Value::Float(v1), Value::Float(v2)) => {
                         Ok(Value::Boolean(v1 < v2))
                     }
+                    (Value::Decimal(v1), Value::Decimal(v2)) => {
+                        Ok(Value::Boolean(v1 < v2))
+                    }
                     (Value::Time(t1), Value::Time(t2)) => {
                         Ok(Value::Boolean(t1 < t2))
                     }
@@ -397,6 +516,9 @@ impl Expr {
                     Some(NumericValuePair::Float(v1, v2)) => {
                         Ok(Value::Float(v1 + v2))
                     }
+                    Some(NumericValuePair::Decimal(v1, v2)) => {
+                        Ok(Value::Decimal((v1 + v2)?))
+                    }
                     Some(NumericValuePair::Quantity(v1, v2)) => {
                         Ok(Value::Quantity((v1 + v2)?))
                     }
@@ -429,6 +551,9 @@ impl Expr {
                     Some(NumericValuePair::Float(v1, v2)) => {
                         Ok(Value::Float(v1 - v2))
                     }
+                    Some(NumericValuePair::Decimal(v1, v2)) => {
+                        Ok(Value::Decimal((v1 - v2)?))
+                    }
                     Some(NumericValuePair::Quantity(v1, v2)) => {
                         Ok(Value::Quantity((v1 - v2)?))
                     }
@@ -451,6 +576,9 @@ impl Expr {
                 Some(NumericValuePair::Float(v1, v2)) => {
                     Ok(Value::Float(v1 * v2))
                 }
+                Some(NumericValuePair::Decimal(v1, v2)) => {
+                    Ok(Value::Decimal((v1 * v2)?))
+                }
                 Some(NumericValuePair::Quantity(v1, v2)) => {
                     Ok(Value::Quantity((v1 * v2)?))
                 }
@@ -469,6 +597,10 @@ impl Expr {
                 Some(NumericValuePair::Float(v1, v2)) => {
                     Ok(Value::Float(v1 / v2))
                 }
+                /* Division is not exact in decimal. */
+                Some(NumericValuePair::Decimal(v1, v2)) => {
+                    Ok(Value::Float(v1.to_f64() / v2.to_f64()))
+                }
                 Some(NumericValuePair::Quantity(v1, v2)) => {
                     Ok(Value::Quantity((v1 / v2)?))
                 }
@@ -502,6 +634,9 @@ impl Expr {
             Self::Neg(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::Integer(v) => Ok(Value::Integer(-v)),
                 Value::Float(v) => Ok(Value::Float(-v)),
+                Value::Decimal(v) => Ok(Value::Decimal(
+                    Decimal::new(-v.mantissa(), v.scale()),
+                )),
                 Value::Quantity(Quantity(v, u)) => {
                     Ok(Value::Quantity(Quantity(-v, u)))
                 }
@@ -519,18 +654,97 @@ impl Expr {
                 Some(NumericValuePair::Float(b, v)) => {
                     Ok(Value::Float(v.log(b)))
                 }
+                Some(NumericValuePair::Decimal(b, v)) => {
+                    Ok(Value::Float(v.to_f64().log(b.to_f64())))
+                }
                 _ => Err(EvalError::TypeError("invalid types for logarithm")),
             },
 
             Self::Abs(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::Integer(v) => Ok(Value::Integer(v.abs())),
                 Value::Float(v) => Ok(Value::Float(v.abs())),
+                Value::Decimal(v) => Ok(Value::Decimal(
+                    Decimal::new(v.mantissa().abs(), v.scale()),
+                )),
                 Value::Quantity(Quantity(v, u)) => {
                     Ok(Value::Quantity(Quantity(v.abs(), u)))
                 }
                 _ => Err(EvalError::TypeError("invalid type for abs")),
             },
 
+            Self::Percent(e1, e2) => match NumericValuePair::from(
+                e1.eval_in_row_opts(vars, data, opts)?,
+                e2.eval_in_row_opts(vars, data, opts)?,
+            ) {
+                Some(NumericValuePair::Integer(v1, v2)) => {
+                    percent(Quantity::from_value(v1 as f64), v2 as f64)
+                }
+                Some(NumericValuePair::Float(v1, v2)) => {
+                    percent(Quantity::from_value(v1), v2)
+                }
+                Some(NumericValuePair::Decimal(v1, v2)) => {
+                    percent(Quantity::from_value(v1.to_f64()), v2.to_f64())
+                }
+                Some(NumericValuePair::Quantity(v1, v2)) => {
+                    percent((v1 / Quantity(1.0, v2.1))?, v2.0)
+                }
+                None => Err(EvalError::TypeError("invalid types for percent")),
+            },
+            Self::Min(e1, e2) | Self::Max(e1, e2) => {
+                let max = matches!(self, Self::Max(_, _));
+                match (
+                    operand(e1.eval_in_row_opts(vars, data, opts), opts)?,
+                    operand(e2.eval_in_row_opts(vars, data, opts), opts)?,
+                ) {
+                    (Some(v1), Some(v2)) => min_max(v1, v2, max),
+                    (Some(v), None) | (None, Some(v)) => {
+                        min_max(v.clone(), v, max)
+                    }
+                    (None, None) => {
+                        Err(EvalError::DataError(DataError::Missing))
+                    }
+                }
+            }
+            Self::Clamp(e1, e2, e3) => {
+                let v = e1.eval_in_row_opts(vars, data, opts)?;
+                let min = e2.eval_in_row_opts(vars, data, opts);
+                let max = e3.eval_in_row_opts(vars, data, opts);
+                let v = match operand(min, opts)? {
+                    Some(min) => min_max(v, min, true)?,
+                    None => v,
+                };
+                match operand(max, opts)? {
+                    Some(max) => min_max(v, max, false),
+                    None => Ok(v),
+                }
+            }
+
+            Self::Round(e) => round_value(
+                e.eval_in_row_opts(vars, data, opts)?,
+                f64::round_ties_even,
+            ),
+            Self::Floor(e) => {
+                round_value(e.eval_in_row_opts(vars, data, opts)?, f64::floor)
+            }
+            Self::Ceil(e) => {
+                round_value(e.eval_in_row_opts(vars, data, opts)?, f64::ceil)
+            }
+            Self::RoundTo(e1, e2) => match (
+                e1.eval_in_row_opts(vars, data, opts)?,
+                e2.eval_in_row_opts(vars, data, opts)?,
+            ) {
+                (Value::Integer(v), Value::Integer(d)) => {
+                    Ok(Value::Integer(round_integer_to(v, d)?))
+                }
+                (Value::Float(v), Value::Integer(d)) => {
+                    Ok(Value::Float(round_float_to(v, d)))
+                }
+                (Value::Quantity(Quantity(v, u)), Value::Integer(d)) => {
+                    Ok(Value::Quantity(Quantity(round_float_to(v, d), u)))
+                }
+                _ => Err(EvalError::TypeError("invalid types for round_to")),
+            },
+
             Self::Sign(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::Integer(v) => {
                     Ok(Value::Integer(if v >= 0 { 1 } else { -1 }))
@@ -632,6 +846,96 @@ impl Expr {
                 }
             }
 
+            Self::Coalesce(es) => {
+                for expr in es {
+                    match expr.eval_in_row_opts(vars, data, opts) {
+                        Ok(Value::Option(v)) => match v.get_value() {
+                            Some(v) => return Ok(v.clone()),
+                            None => continue,
+                        },
+                        Ok(v) => return Ok(v),
+                        Err(e) if e.is_missing() => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(EvalError::DataError(DataError::Missing))
+            }
+
+            Self::Default(e1, e2) => {
+                match e1.eval_in_row_opts(vars, data, opts) {
+                    Err(e) if e.is_missing() => {
+                        e2.eval_in_row_opts(vars, data, opts)
+                    }
+                    res => res,
+                }
+            }
+
+            Self::If(e1, e2, e3) => match e1.eval_in_row_opts(vars, data, opts)? {
+                Value::Boolean(true) => e2.eval_in_row_opts(vars, data, opts),
+                Value::Boolean(false) => e3.eval_in_row_opts(vars, data, opts),
+                _ => Err(EvalError::TypeError(
+                    "invalid condition type for 'if' (expected: boolean)",
+                )),
+            },
+
+            Self::Map(e1, e2) => match e1.eval_in_row_opts(vars, data, opts)? {
+                Value::List(l) => {
+                    let vs = l
+                        .get_values()
+                        .iter()
+                        .map(|v| {
+                            e2.eval_in_row_opts(
+                                vars,
+                                Some(&Ok(v.clone())),
+                                opts,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let t = match vs.first() {
+                        Some(v) => v.get_type(),
+                        /* Row variables are not available here, so
+                         * the element type of an empty result can
+                         * only be derived from self-contained
+                         * expressions. */
+                        None => {
+                            e2.check_opts(Some(l.get_element_type()), opts)?
+                        }
+                    };
+                    Ok(Value::List(ListValue::new(Arc::new(t), vs)?))
+                }
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for 'map' (expected: list)",
+                )),
+            },
+
+            Self::Filter(e1, e2) => {
+                match e1.eval_in_row_opts(vars, data, opts)? {
+                    Value::List(l) => {
+                        let mut vs = Vec::new();
+                        for v in l.get_values() {
+                            let d = Ok(v.clone());
+                            match e2.eval_in_row_opts(vars, Some(&d), opts)? {
+                                Value::Boolean(true) => vs.push(v.clone()),
+                                Value::Boolean(false) => {}
+                                _ => {
+                                    return Err(EvalError::TypeError(
+                                        "invalid predicate type for 'filter' \
+                                         (expected: boolean)",
+                                    ))
+                                }
+                            }
+                        }
+                        Ok(Value::List(ListValue::new(
+                            Arc::new(l.get_element_type().clone()),
+                            vs,
+                        )?))
+                    }
+                    _ => Err(EvalError::TypeError(
+                        "invalid argument type for 'filter' (expected: list)",
+                    )),
+                }
+            }
+
             Self::FromUtf8(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::BinaryString(bs) => Ok(Value::UnicodeString(
                     String::from_utf8(bs)
@@ -854,6 +1158,49 @@ impl Expr {
                 }
             }
 
+            Self::Aggregate(a, e) => match e.eval_in_row_opts(vars, data, opts)? {
+                Value::List(l) => {
+                    let v = a.eval_opts(
+                        l.get_values().iter().cloned().map(Ok),
+                        opts.missing,
+                    )?;
+                    match (a, &v, l.get_element_type()) {
+                        (Aggregate::Sum, Value::Integer(0), Type::Float) => {
+                            Ok(Value::Float(0.0))
+                        }
+                        _ => Ok(v),
+                    }
+                }
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for aggregate function \
+                     (expected: list)",
+                )),
+            },
+
+            Self::Index(e1, e2) => match (
+                e1.eval_in_row_opts(vars, data, opts)?,
+                e2.eval_in_row_opts(vars, data, opts)?,
+            ) {
+                (Value::List(l), Value::Integer(i)) => {
+                    let vs = l.get_values();
+                    let i = match i < 0 {
+                        true => vs.len() as i64 + i,
+                        false => i,
+                    };
+                    match i >= 0 {
+                        true => vs
+                            .get(i as usize)
+                            .cloned()
+                            .ok_or(EvalError::OutOfBounds),
+                        false => Err(EvalError::OutOfBounds),
+                    }
+                }
+                _ => Err(EvalError::TypeError(
+                    "invalid argument types for 'index' function \
+                     (expected: list, int)",
+                )),
+            },
+
             Self::SubStr(e1, e2, e3) => match (
                 e1.eval_in_row_opts(vars, data, opts)?,
                 e2.eval_in_row_opts(vars, data, opts)?,
@@ -942,6 +1289,107 @@ impl Expr {
                 }
             }
 
+            Self::EqCi(e1, e2)
+            | Self::Contains(e1, e2)
+            | Self::StartsWith(e1, e2)
+            | Self::Matches(e1, e2) => {
+                let v1 = e1.eval_in_row_opts(vars, data, opts)?;
+                let v2 = e2.eval_in_row_opts(vars, data, opts)?;
+                let v1 = eval_string(v1, opts)?;
+                let v2 = eval_string(v2, opts)?;
+                Ok(Value::Boolean(match self {
+                    Self::EqCi(_, _) => casefold(&v1) == casefold(&v2),
+                    Self::Contains(_, _) => v1.contains(v2.as_str()),
+                    Self::StartsWith(_, _) => v1.starts_with(v2.as_str()),
+                    _ => Regex::new(&v2)
+                        .map_err(|e| EvalError::Regex(e.to_string()))?
+                        .is_match(&v1),
+                }))
+            }
+
+            Self::Substring(e1, e2, e3) => {
+                let v = eval_unicode(
+                    e1.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                match (
+                    e2.eval_in_row_opts(vars, data, opts)?,
+                    e3.eval_in_row_opts(vars, data, opts)?,
+                ) {
+                    (Value::Integer(start), Value::Integer(len))
+                        if start >= 0 && len >= 0 =>
+                    {
+                        let (start, len) = (start as usize, len as usize);
+                        match start + len <= v.chars().count() {
+                            true => Ok(Value::UnicodeString(
+                                v.chars().skip(start).take(len).collect(),
+                            )),
+                            false => Err(EvalError::OutOfBounds),
+                        }
+                    }
+                    (Value::Integer(_), Value::Integer(_)) => {
+                        Err(EvalError::OutOfBounds)
+                    }
+                    _ => Err(EvalError::TypeError(
+                        "invalid argument types for 'substring' function \
+                         (expected: unicode string, int, int)",
+                    )),
+                }
+            }
+
+            Self::Split(e1, e2) => {
+                let v = eval_unicode(
+                    e1.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                let sep = eval_unicode(
+                    e2.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                if sep.is_empty() {
+                    return Err(EvalError::TypeError(
+                        "empty separator in 'split' function",
+                    ));
+                }
+                Ok(Value::List(ListValue::new(
+                    Arc::new(Type::UnicodeString),
+                    v.split(sep.as_str())
+                        .map(|s| Value::UnicodeString(s.to_string()))
+                        .collect(),
+                )?))
+            }
+
+            Self::Replace(e1, e2, e3) => {
+                let v = eval_unicode(
+                    e1.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                let from = eval_unicode(
+                    e2.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                let to = eval_unicode(
+                    e3.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                match from.is_empty() {
+                    true => Ok(Value::UnicodeString(v)),
+                    false => Ok(Value::UnicodeString(v.replace(&from, &to))),
+                }
+            }
+
+            Self::Lower(e) | Self::Upper(e) | Self::Trim(e) => {
+                let v = eval_unicode(
+                    e.eval_in_row_opts(vars, data, opts)?,
+                    opts,
+                )?;
+                Ok(Value::UnicodeString(match self {
+                    Self::Lower(_) => v.to_lowercase(),
+                    Self::Upper(_) => v.to_uppercase(),
+                    _ => v.trim().to_string(),
+                }))
+            }
+
             Self::HexStr(e) => match e.eval_in_row_opts(vars, data, opts)? {
                 Value::BinaryString(v) => Ok(Value::UnicodeString(
                     v.iter()
@@ -988,6 +1436,56 @@ impl Expr {
                 _ => Err(EvalError::TypeError("invalid type for not_empty")),
             },
 
+            Self::IsUnknown(e) => match e.eval_in_row_opts(vars, data, opts)? {
+                Value::TriState(v) => Ok(Value::Boolean(v.is_unknown())),
+                Value::Boolean(_) => Ok(Value::Boolean(false)),
+                _ => Err(EvalError::TypeError("invalid type for is_unknown")),
+            },
+
+            Self::UnknownAs(e1, e2) => {
+                match (
+                    e1.eval_in_row_opts(vars, data, opts)?,
+                    e2.eval_in_row_opts(vars, data, opts)?,
+                ) {
+                    (Value::TriState(v), Value::Boolean(d)) => {
+                        Ok(Value::Boolean(v.to_bool().unwrap_or(d)))
+                    }
+                    (Value::Boolean(v), Value::Boolean(_)) => {
+                        Ok(Value::Boolean(v))
+                    }
+                    _ => Err(EvalError::TypeError(
+                        "invalid types for unknown_as",
+                    )),
+                }
+            }
+
+            Self::Now => Ok(Value::Time(Utc::now())),
+            Self::ParseTime(e, fmt) | Self::ParseTimeUtc(e, fmt) => {
+                match e.eval_in_row_opts(vars, data, opts)? {
+                    Value::UnicodeString(s) => parse_time(
+                        &s,
+                        fmt,
+                        matches!(self, Self::ParseTimeUtc(..)),
+                    ),
+                    _ => Err(EvalError::TypeError(
+                        "invalid argument type for 'parse_time' \
+                         (expected: unicode string)",
+                    )),
+                }
+            }
+            Self::Duration(e) => match e.eval_in_row_opts(vars, data, opts)? {
+                Value::Age(d) => Ok(Value::Quantity(Quantity(
+                    match d.num_nanoseconds() {
+                        Some(ns) => ns as f64 / 1e9,
+                        None => d.num_milliseconds() as f64 / 1e3,
+                    },
+                    Unit::Time(TimeUnit::Second(FracPrefix::Unit)),
+                ))),
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for 'duration' (expected: age)",
+                )),
+            },
+
             Self::UnpackTime(e) => {
                 match e.eval_in_row_opts(vars, data, opts)? {
                     Value::BinaryString(v) => match v.as_slice() {
@@ -1102,6 +1600,7 @@ impl Expr {
             Self::Or(e1, e2) => {
                 match (e1.check_in_row_opts(vars, data, opts)?, e2.check_in_row_opts(vars, data, opts)?) {
                     (Type::Boolean, Type::Boolean) => Ok(Type::Boolean),
+                    (Type::Boolean | Type::TriState, Type::Boolean | Type::TriState) => Ok(Type::TriState),
                     _ => Err(EvalError::TypeError("invalid types for boolean or")),
                 }
             }
@@ -1109,11 +1608,13 @@ impl Expr {
             Self::And(e1, e2) => match (e1.check_in_row_opts(vars, data, opts)?, e2.check_in_row_opts(vars, data, opts)?)
             {
                 (Type::Boolean, Type::Boolean) => Ok(Type::Boolean),
+                (Type::Boolean | Type::TriState, Type::Boolean | Type::TriState) => Ok(Type::TriState),
                 _ => Err(EvalError::TypeError("invalid types for boolean and")),
             },
 
             Self::Not(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::Boolean => Ok(Type::Boolean),
+                Type::TriState => Ok(Type::TriState),
                 _ => Err(EvalError::TypeError("invalid types for boolean not")),
             },
 
@@ -1126,6 +1627,7 @@ impl Expr {
                     (Type::UnicodeString, Type::UnicodeString) => Ok(Type::Boolean),
                     (Type::Integer, Type::Integer) => Ok(Type::Boolean),
                     (Type::Float, Type::Float) => Ok(Type::Boolean),
+                    (Type::Decimal, Type::Decimal) => Ok(Type::Boolean),
 					(Type::Time, Type::Time) => Ok(Type::Boolean),
 					(Type::Age, Type::Age) => Ok(Type::Boolean),
                     _ => Err(EvalError::TypeError(
@@ -1143,12 +1645,31 @@ impl Expr {
 					(Type::Age, Type::Age) => Ok(Type::Boolean),
                     (Type::Integer, Type::Integer) => Ok(Type::Boolean),
                     (Type::Float, Type::Float) => Ok(Type::Boolean),
+                    (Type::Decimal, Type::Decimal) => Ok(Type::Boolean),
+                    (Type::TriState, Type::TriState) => Ok(Type::Boolean),
                     _ => Err(EvalError::TypeError(
                         "invalid types for comparison operator",
                     )),
                 }
             }
 
+            Self::ApproxEq(e1, e2) => match NumericTypePair::from(
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+            ) {
+                Some(
+                    NumericTypePair::Integer
+                    | NumericTypePair::Float
+                    | NumericTypePair::Decimal,
+                ) => Ok(Type::Boolean),
+                Some(NumericTypePair::Quantity(d1, d2)) if d1 == d2 => {
+                    Ok(Type::Boolean)
+                }
+                _ => Err(EvalError::TypeError(
+                    "invalid types for approximate comparison",
+                )),
+            },
+
             Self::Add(e1, e2) => match (
 				e1.check_in_row_opts(vars, data, opts)?,
                 e2.check_in_row_opts(vars, data, opts)?,
@@ -1158,6 +1679,7 @@ impl Expr {
 				(t1,t2) => match NumericTypePair::from(t1,t2) {
 					Some(NumericTypePair::Integer) => Ok(Type::Integer),
 					Some(NumericTypePair::Float) => Ok(Type::Float),
+					Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
 					Some(NumericTypePair::Quantity(d1, d2)) => Ok(Type::Quantity((d1 + d2)?)),
 					None => Err(EvalError::TypeError("invalid types for addition")),
 				}
@@ -1173,6 +1695,7 @@ impl Expr {
 				(t1,t2) => match NumericTypePair::from(t1,t2) {
 					Some(NumericTypePair::Integer) => Ok(Type::Integer),
 					Some(NumericTypePair::Float) => Ok(Type::Float),
+					Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
 					Some(NumericTypePair::Quantity(d1, d2)) => Ok(Type::Quantity((d1 - d2)?)),
 					None => Err(EvalError::TypeError("invalid types for subtraction")),
 				}
@@ -1184,6 +1707,7 @@ impl Expr {
             ) {
                 Some(NumericTypePair::Integer) => Ok(Type::Integer),
                 Some(NumericTypePair::Float) => Ok(Type::Float),
+                Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
                 Some(NumericTypePair::Quantity(d1, d2)) => Ok(Type::Quantity((d1 * d2)?)),
                 None => Err(EvalError::TypeError("invalid types for multiplication")),
             },
@@ -1194,6 +1718,7 @@ impl Expr {
             ) {
                 Some(NumericTypePair::Integer) => Ok(Type::Float),
                 Some(NumericTypePair::Float) => Ok(Type::Float),
+                Some(NumericTypePair::Decimal) => Ok(Type::Float),
                 Some(NumericTypePair::Quantity(d1, d2)) => Ok(Type::Quantity((d1 / d2)?)),
                 None => Err(EvalError::TypeError("invalid types for division")),
             },
@@ -1215,6 +1740,7 @@ impl Expr {
             Self::Neg(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::Integer => Ok(Type::Integer),
                 Type::Float => Ok(Type::Float),
+                Type::Decimal => Ok(Type::Decimal),
                 Type::Quantity(d) => Ok(Type::Quantity(d)),
 				Type::Age => Ok(Type::Age),
                 _ => Err(EvalError::TypeError("invalid types for negation")),
@@ -1226,16 +1752,67 @@ impl Expr {
             ) {
                 Some(NumericTypePair::Integer) => Ok(Type::Float),
                 Some(NumericTypePair::Float) => Ok(Type::Float),
+                Some(NumericTypePair::Decimal) => Ok(Type::Float),
                 _ => Err(EvalError::TypeError("invalid types for log")),
             },
 
             Self::Abs(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::Integer => Ok(Type::Integer),
                 Type::Float => Ok(Type::Float),
+                Type::Decimal => Ok(Type::Decimal),
                 Type::Quantity(d) => Ok(Type::Quantity(d)),
                 _ => Err(EvalError::TypeError("invalid types for abs")),
             },
 
+            Self::Percent(e1, e2) => match NumericTypePair::from(
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+            ) {
+                Some(
+                    NumericTypePair::Integer
+                    | NumericTypePair::Float
+                    | NumericTypePair::Decimal,
+                ) => Ok(Type::Quantity(Dimension::Dimensionless)),
+                Some(NumericTypePair::Quantity(d1, d2)) => match (d1 / d2)? {
+                    Dimension::Dimensionless => {
+                        Ok(Type::Quantity(Dimension::Dimensionless))
+                    }
+                    _ => Err(EvalError::TypeError(
+                        "percent of quantities with different dimensions",
+                    )),
+                },
+                None => Err(EvalError::TypeError("invalid types for percent")),
+            },
+            Self::Min(e1, e2) | Self::Max(e1, e2) => min_max_type(
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+            ),
+            Self::Clamp(e1, e2, e3) => min_max_type(
+                min_max_type(
+                    e1.check_in_row_opts(vars, data, opts)?,
+                    e2.check_in_row_opts(vars, data, opts)?,
+                )?,
+                e3.check_in_row_opts(vars, data, opts)?,
+            ),
+
+            Self::Round(e) | Self::Floor(e) | Self::Ceil(e) => {
+                match e.check_in_row_opts(vars, data, opts)? {
+                    Type::Integer => Ok(Type::Integer),
+                    Type::Float => Ok(Type::Float),
+                    Type::Quantity(d) => Ok(Type::Quantity(d)),
+                    _ => Err(EvalError::TypeError("invalid types for rounding")),
+                }
+            }
+            Self::RoundTo(e1, e2) => match (
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+            ) {
+                (Type::Integer, Type::Integer) => Ok(Type::Integer),
+                (Type::Float, Type::Integer) => Ok(Type::Float),
+                (Type::Quantity(d), Type::Integer) => Ok(Type::Quantity(d)),
+                _ => Err(EvalError::TypeError("invalid types for round_to")),
+            },
+
             Self::Sign(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::Integer => Ok(Type::Integer),
                 Type::Float => Ok(Type::Integer),
@@ -1281,6 +1858,7 @@ impl Expr {
 						(t1,t2) => match NumericTypePair::from(t1, t2) {
 							Some(NumericTypePair::Integer) => Ok(Type::Integer),
 							Some(NumericTypePair::Float) => Ok(Type::Float),
+							Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
 							Some(NumericTypePair::Quantity(d1, d2)) => Ok(Type::Quantity((d1 + d2)?)),
 							None => Err(EvalError::TypeError("incompatible types for fallback")),
 						}
@@ -1288,6 +1866,81 @@ impl Expr {
                 }
             }
 
+            Self::Coalesce(es) => {
+                let mut types = es.iter().map(|e| {
+                    match e.check_in_row_opts(vars, data, opts)? {
+                        Type::Option(t) => Ok(t.as_ref().clone()),
+                        t => Ok(t),
+                    }
+                });
+                let first = types.next().ok_or(EvalError::TypeError(
+                    "coalesce requires at least one argument",
+                ))??;
+                types.try_fold(first, |t1, t2| {
+                    let err = "incompatible types for coalesce";
+                    common_type(t1, t2?, opts, err)
+                })
+            }
+
+            Self::Default(e1, e2) => common_type(
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+                opts,
+                "incompatible types for default",
+            ),
+
+            Self::If(e1, e2, e3) => match e1.check_in_row_opts(vars, data, opts)? {
+                Type::Boolean => common_type(
+                    e2.check_in_row_opts(vars, data, opts)?,
+                    e3.check_in_row_opts(vars, data, opts)?,
+                    opts,
+                    "incompatible branch types for 'if'",
+                ),
+                _ => Err(EvalError::TypeError(
+                    "invalid condition type for 'if' (expected: boolean)",
+                )),
+            },
+
+            Self::Map(e1, e2) => match e1.check_in_row_opts(vars, data, opts)? {
+                Type::List(t) => Ok(Type::List(Arc::new(
+                    e2.check_in_row_opts(vars, Some(t.as_ref()), opts)?,
+                ))),
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for 'map' (expected: list)",
+                )),
+            },
+
+            Self::Filter(e1, e2) => match e1.check_in_row_opts(vars, data, opts)? {
+                Type::List(t) => match e2.check_in_row_opts(vars, Some(t.as_ref()), opts)? {
+                    Type::Boolean => Ok(Type::List(t)),
+                    _ => Err(EvalError::TypeError(
+                        "invalid predicate type for 'filter' (expected: boolean)",
+                    )),
+                },
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for 'filter' (expected: list)",
+                )),
+            },
+
+            Self::Aggregate(a, e) => match e.check_in_row_opts(vars, data, opts)? {
+                Type::List(t) => a.check(&t),
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for aggregate function \
+                     (expected: list)",
+                )),
+            },
+
+            Self::Index(e1, e2) => match (
+                e1.check_in_row_opts(vars, data, opts)?,
+                e2.check_in_row_opts(vars, data, opts)?,
+            ) {
+                (Type::List(t), Type::Integer) => Ok(t.as_ref().clone()),
+                _ => Err(EvalError::TypeError(
+                    "invalid argument types for 'index' function \
+                     (expected: list, int)",
+                )),
+            },
+
             Self::SubStr(e1, e2, e3) => match (
                 e1.check_in_row_opts(vars, data, opts)?,
                 e2.check_in_row_opts(vars, data, opts)?,
@@ -1452,10 +2105,13 @@ impl Expr {
 				Type::BinaryString |
 				Type::Integer |
 				Type::Float |
+				Type::Decimal |
 				Type::Quantity(_) |
+				Type::CompositeQuantity(_) |
 				Type::Enum(_) |
 				Type::IntEnum(_) |
 				Type::Boolean |
+				Type::TriState |
 				Type::Time |
 				Type::Age |
 				Type::MacAddress |
@@ -1487,6 +2143,55 @@ impl Expr {
                 )),
             },
 
+            Self::EqCi(e1, e2)
+            | Self::Contains(e1, e2)
+            | Self::StartsWith(e1, e2) => {
+                check_string(e1.check_in_row_opts(vars, data, opts)?, opts)?;
+                check_string(e2.check_in_row_opts(vars, data, opts)?, opts)?;
+                Ok(Type::Boolean)
+            }
+
+            Self::Matches(e1, e2) => {
+                check_string(e1.check_in_row_opts(vars, data, opts)?, opts)?;
+                check_string(e2.check_in_row_opts(vars, data, opts)?, opts)?;
+                if let Self::Literal(Value::UnicodeString(r)) = e2.as_ref() {
+                    Regex::new(r).map_err(|e| EvalError::Regex(e.to_string()))?;
+                }
+                Ok(Type::Boolean)
+            }
+
+            Self::Substring(e1, e2, e3) => {
+                check_unicode(e1.check_in_row_opts(vars, data, opts)?, opts)?;
+                match (
+                    e2.check_in_row_opts(vars, data, opts)?,
+                    e3.check_in_row_opts(vars, data, opts)?,
+                ) {
+                    (Type::Integer, Type::Integer) => Ok(Type::UnicodeString),
+                    _ => Err(EvalError::TypeError(
+                        "invalid argument types for 'substring' function \
+                         (expected: unicode string, int, int)",
+                    )),
+                }
+            }
+
+            Self::Split(e1, e2) => {
+                check_unicode(e1.check_in_row_opts(vars, data, opts)?, opts)?;
+                check_unicode(e2.check_in_row_opts(vars, data, opts)?, opts)?;
+                Ok(Type::List(Arc::new(Type::UnicodeString)))
+            }
+
+            Self::Replace(e1, e2, e3) => {
+                check_unicode(e1.check_in_row_opts(vars, data, opts)?, opts)?;
+                check_unicode(e2.check_in_row_opts(vars, data, opts)?, opts)?;
+                check_unicode(e3.check_in_row_opts(vars, data, opts)?, opts)?;
+                Ok(Type::UnicodeString)
+            }
+
+            Self::Lower(e) | Self::Upper(e) | Self::Trim(e) => {
+                check_unicode(e.check_in_row_opts(vars, data, opts)?, opts)?;
+                Ok(Type::UnicodeString)
+            }
+
             Self::HexStr(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::BinaryString => Ok(Type::UnicodeString),
                 _ => Err(EvalError::TypeError("invalid type for hex_string")),
@@ -1498,6 +2203,16 @@ impl Expr {
                 _ => Err(EvalError::TypeError("invalid type for not_empty")),
             },
 
+            Self::IsUnknown(e) => match e.check_in_row_opts(vars, data, opts)? {
+                Type::TriState | Type::Boolean => Ok(Type::Boolean),
+                _ => Err(EvalError::TypeError("invalid type for is_unknown")),
+            },
+
+            Self::UnknownAs(e1, e2) => match (e1.check_in_row_opts(vars, data, opts)?, e2.check_in_row_opts(vars, data, opts)?) {
+                (Type::TriState | Type::Boolean, Type::Boolean) => Ok(Type::Boolean),
+                _ => Err(EvalError::TypeError("invalid types for unknown_as")),
+            },
+
             Self::MD5(e) => match e.check_in_row_opts(vars, data, opts)? {
                 // Type::BinaryString => Ok(Type::UnicodeString),
                 // Type::UnicodeString => Ok(Type::UnicodeString),
@@ -1510,6 +2225,23 @@ impl Expr {
                 _ => Err(EvalError::TypeError("the sha1 function is not yet implemented")),
             },
 
+            Self::Now => Ok(Type::Time),
+            Self::ParseTime(e, _) | Self::ParseTimeUtc(e, _) => {
+                match e.check_in_row_opts(vars, data, opts)? {
+                    Type::UnicodeString => Ok(Type::Time),
+                    _ => Err(EvalError::TypeError(
+                        "invalid argument type for 'parse_time' \
+                         (expected: unicode string)",
+                    )),
+                }
+            }
+            Self::Duration(e) => match e.check_in_row_opts(vars, data, opts)? {
+                Type::Age => Ok(Type::Quantity(Dimension::Time)),
+                _ => Err(EvalError::TypeError(
+                    "invalid argument type for 'duration' (expected: age)",
+                )),
+            },
+
             Self::UnpackTime(e) => match e.check_in_row_opts(vars, data, opts)? {
                 Type::BinaryString => Ok(Type::Time),
                 _ => Err(EvalError::TypeError("invalid type for unpack_time")),
@@ -1558,6 +2290,7 @@ impl Display for Expr {
             Expr::Le(e1, e2) => write!(f, "({}) <= ({})", e1, e2),
             Expr::Eq(e1, e2) => write!(f, "({}) == ({})", e1, e2),
             Expr::Ne(e1, e2) => write!(f, "({}) != ({})", e1, e2),
+            Expr::ApproxEq(e1, e2) => write!(f, "({}) ~= ({})", e1, e2),
             Expr::Ge(e1, e2) => write!(f, "({}) >= ({})", e1, e2),
             Expr::Gt(e1, e2) => write!(f, "({}) > ({})", e1, e2),
             Expr::Add(e1, e2) => write!(f, "({}) + ({})", e1, e2),
@@ -1568,6 +2301,16 @@ impl Display for Expr {
             Expr::Neg(e) => write!(f, "-({})", e),
             Expr::Log(b, e) => write!(f, "log({},{})", b, e),
             Expr::Abs(e) => write!(f, "abs({})", e),
+            Expr::Percent(e1, e2) => write!(f, "percent({}, {})", e1, e2),
+            Expr::Min(e1, e2) => write!(f, "min({}, {})", e1, e2),
+            Expr::Max(e1, e2) => write!(f, "max({}, {})", e1, e2),
+            Expr::Clamp(e1, e2, e3) => {
+                write!(f, "clamp({}, {}, {})", e1, e2, e3)
+            }
+            Expr::Round(e) => write!(f, "round({})", e),
+            Expr::Floor(e) => write!(f, "floor({})", e),
+            Expr::Ceil(e) => write!(f, "ceil({})", e),
+            Expr::RoundTo(e1, e2) => write!(f, "round_to({}, {})", e1, e2),
             Expr::Sign(e) => write!(f, "sign({})", e),
             Expr::BitsLE(e1, e2, e3) => {
                 write!(f, "bits_le({}, {}, {})", e1, e2, e3)
@@ -1576,6 +2319,22 @@ impl Display for Expr {
                 write!(f, "bits_be({}, {}, {})", e1, e2, e3)
             }
             Expr::Fallback(e1, e2) => write!(f, "fallback({}, {})", e1, e2),
+            Expr::Coalesce(es) => {
+                write!(f, "coalesce(")?;
+                for (i, e) in es.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Default(e1, e2) => write!(f, "default({}, {})", e1, e2),
+            Expr::If(e1, e2, e3) => write!(f, "if({}, {}, {})", e1, e2, e3),
+            Expr::Map(e1, e2) => write!(f, "map({}, {})", e1, e2),
+            Expr::Aggregate(a, e) => write!(f, "{}({})", a.name(), e),
+            Expr::Index(e1, e2) => write!(f, "index({}, {})", e1, e2),
+            Expr::Filter(e1, e2) => write!(f, "filter({}, {})", e1, e2),
             Expr::FromUtf8(e) => write!(f, "from_utf8({})", e),
             Expr::FromUtf8Lossy(e) => write!(f, "from_utf8_lossy({})", e),
             // Expr::FromUtf16(e) => write!(f, "from_utf16({})", e),
@@ -1597,9 +2356,35 @@ impl Display for Expr {
             Expr::ToString(e) => write!(f, "to_string({})", e),
             Expr::RegSubst(e, r, s) => write!(f, "({})~s/{}/{}/", e, r, s),
             Expr::SHA1(e) => write!(f, "sha1({})", e),
+            Expr::Substring(e1, e2, e3) => {
+                write!(f, "substring({}, {}, {})", e1, e2, e3)
+            }
+            Expr::Split(e1, e2) => write!(f, "split({}, {})", e1, e2),
+            Expr::Replace(e1, e2, e3) => {
+                write!(f, "replace({}, {}, {})", e1, e2, e3)
+            }
+            Expr::Lower(e) => write!(f, "lower({})", e),
+            Expr::Upper(e) => write!(f, "upper({})", e),
+            Expr::Trim(e) => write!(f, "trim({})", e),
             Expr::MD5(e) => write!(f, "md5({})", e),
+            Expr::EqCi(e1, e2) => write!(f, "eq_ci({}, {})", e1, e2),
+            Expr::Contains(e1, e2) => write!(f, "contains({}, {})", e1, e2),
+            Expr::StartsWith(e1, e2) => {
+                write!(f, "starts_with({}, {})", e1, e2)
+            }
+            Expr::Matches(e1, e2) => write!(f, "matches({}, {})", e1, e2),
             Expr::NotEmpty(e) => write!(f, "not_empty({})", e),
+            Expr::IsUnknown(e) => write!(f, "is_unknown({})", e),
+            Expr::UnknownAs(e1, e2) => write!(f, "unknown_as({}, {})", e1, e2),
             Expr::HexStr(e) => write!(f, "hex_string({})", e),
+            Expr::Now => write!(f, "now()"),
+            Expr::ParseTime(e, fmt) => {
+                write!(f, "parse_time({}, \"{}\")", e, fmt)
+            }
+            Expr::ParseTimeUtc(e, fmt) => {
+                write!(f, "parse_time_utc({}, \"{}\")", e, fmt)
+            }
+            Expr::Duration(e) => write!(f, "duration({})", e),
             Expr::UnpackTime(e) => write!(f, "unpack_time({})", e),
             Expr::Quantity(e, u) => write!(f, "({}) {}", e, u),
             Expr::Convert(e, u) => write!(f, "convert({},{})", e, u),
@@ -1636,6 +2421,9 @@ impl Display for PyRepr<'_> {
             Expr::Ne(e1, e2) => {
                 write!(f, "Ne({},{})", PyRepr(e1), PyRepr(e2))
             }
+            Expr::ApproxEq(e1, e2) => {
+                write!(f, "ApproxEq({},{})", PyRepr(e1), PyRepr(e2))
+            }
             Expr::Gt(e1, e2) => {
                 write!(f, "Gt({},{})", PyRepr(e1), PyRepr(e2))
             }
@@ -1679,6 +2467,38 @@ impl Display for PyRepr<'_> {
             Expr::Fallback(e1, e2) => {
                 write!(f, "Fallback({},{})", PyRepr(e1), PyRepr(e2))
             }
+            Expr::Coalesce(es) => {
+                write!(f, "Coalesce([")?;
+                for (i, e) in es.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", PyRepr(e))?;
+                }
+                write!(f, "])")
+            }
+            Expr::Default(e1, e2) => {
+                write!(f, "Default({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::If(e1, e2, e3) => write!(
+                f,
+                "If({},{},{})",
+                PyRepr(e1),
+                PyRepr(e2),
+                PyRepr(e3)
+            ),
+            Expr::Map(e1, e2) => {
+                write!(f, "Map({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Aggregate(a, e) => {
+                write!(f, "Aggregate({},{})", PyUnicode(a.name()), PyRepr(e))
+            }
+            Expr::Index(e1, e2) => {
+                write!(f, "Index({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Filter(e1, e2) => {
+                write!(f, "Filter({},{})", PyRepr(e1), PyRepr(e2))
+            }
             Expr::FromUtf8(expr) => {
                 write!(f, "FromUtf8({})", PyRepr(expr))
             }
@@ -1736,12 +2556,70 @@ impl Display for PyRepr<'_> {
             Expr::HexStr(expr) => write!(f, "HexStr({})", PyRepr(expr)),
             Expr::SHA1(expr) => write!(f, "SHA1({})", PyRepr(expr)),
             Expr::MD5(expr) => write!(f, "MD5({})", PyRepr(expr)),
+            Expr::Substring(e1, e2, e3) => write!(
+                f,
+                "Substring({},{},{})",
+                PyRepr(e1),
+                PyRepr(e2),
+                PyRepr(e3)
+            ),
+            Expr::Split(e1, e2) => {
+                write!(f, "Split({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Replace(e1, e2, e3) => write!(
+                f,
+                "Replace({},{},{})",
+                PyRepr(e1),
+                PyRepr(e2),
+                PyRepr(e3)
+            ),
+            Expr::Lower(expr) => write!(f, "Lower({})", PyRepr(expr)),
+            Expr::Upper(expr) => write!(f, "Upper({})", PyRepr(expr)),
+            Expr::Trim(expr) => write!(f, "Trim({})", PyRepr(expr)),
+            Expr::EqCi(e1, e2) => {
+                write!(f, "EqCi({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Contains(e1, e2) => {
+                write!(f, "Contains({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::StartsWith(e1, e2) => {
+                write!(f, "StartsWith({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Matches(e1, e2) => {
+                write!(f, "Matches({},{})", PyRepr(e1), PyRepr(e2))
+            }
             Expr::NotEmpty(expr) => write!(f, "NotEmpty({})", PyRepr(expr)),
+            Expr::IsUnknown(expr) => write!(f, "IsUnknown({})", PyRepr(expr)),
+            Expr::UnknownAs(e1, e2) => {
+                write!(f, "UnknownAs({},{})", PyRepr(e1), PyRepr(e2))
+            }
             Expr::Log(e1, e2) => {
                 write!(f, "Log({},{})", PyRepr(e1), PyRepr(e2))
             }
             Expr::Sign(expr) => write!(f, "Sign({})", PyRepr(expr)),
             Expr::Abs(expr) => write!(f, "Abs({})", PyRepr(expr)),
+            Expr::Percent(e1, e2) => {
+                write!(f, "Percent({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Min(e1, e2) => {
+                write!(f, "Min({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Max(e1, e2) => {
+                write!(f, "Max({},{})", PyRepr(e1), PyRepr(e2))
+            }
+            Expr::Clamp(e1, e2, e3) => write!(
+                f,
+                "Clamp({},{},{})",
+                PyRepr(e1),
+                PyRepr(e2),
+                PyRepr(e3)
+            ),
+            Expr::Round(expr) => write!(f, "Round({})", PyRepr(expr)),
+            Expr::Floor(expr) => write!(f, "Floor({})", PyRepr(expr)),
+            Expr::Ceil(expr) => write!(f, "Ceil({})", PyRepr(expr)),
+            Expr::RoundTo(e1, e2) => {
+                write!(f, "RoundTo({},{})", PyRepr(e1), PyRepr(e2))
+            }
             Expr::BitsLE(e1, e2, e3) => write!(
                 f,
                 "BitsLE({},{},{})",
@@ -1756,6 +2634,14 @@ impl Display for PyRepr<'_> {
                 PyRepr(e2),
                 PyRepr(e3)
             ),
+            Expr::Now => write!(f, "Now()"),
+            Expr::ParseTime(expr, fmt) => {
+                write!(f, "ParseTime({},{})", PyRepr(expr), PyUnicode(fmt))
+            }
+            Expr::ParseTimeUtc(expr, fmt) => {
+                write!(f, "ParseTimeUtc({},{})", PyRepr(expr), PyUnicode(fmt))
+            }
+            Expr::Duration(expr) => write!(f, "Duration({})", PyRepr(expr)),
             Expr::UnpackTime(expr) => {
                 write!(f, "UnpackTime({})", PyRepr(expr))
             }
@@ -1772,6 +2658,25 @@ const MICROS_RANGE: RangeInclusive<f64> =
 const NANOS_RANGE: RangeInclusive<f64> =
     i64::MIN as f64 / 1e9..=i64::MAX as f64 / 1e9;
 
+/// Parse a timestamp in the given (strftime-like) format. Timestamps
+/// without timezone offset are rejected as ambiguous, unless `utc` is
+/// set. Date-only formats are parsed as midnight.
+fn parse_time(s: &str, fmt: &str, utc: bool) -> Result<Value, EvalError> {
+    match DateTime::parse_from_str(s, fmt) {
+        Ok(t) => Ok(Value::Time(t.with_timezone(&Utc))),
+        Err(e) => match NaiveDateTime::parse_from_str(s, fmt).or_else(|_| {
+            NaiveDate::parse_from_str(s, fmt)
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        }) {
+            Ok(t) => match utc {
+                true => Ok(Value::Time(Utc.from_utc_datetime(&t))),
+                false => Err(EvalError::NaiveTime(s.to_string())),
+            },
+            Err(_) => Err(EvalError::TimeParseError(format!("{}: {}", s, e))),
+        },
+    }
+}
+
 fn i64_to_duration(v: i64) -> Result<Duration, EvalError> {
     match SECONDS_RANGE.contains(&v) {
         true => Ok(Duration::seconds(v)),
@@ -1795,3 +2700,240 @@ fn f64_to_duration(v: f64) -> Result<Duration, EvalError> {
         },
     }
 }
+
+/// Express the ratio of a value to a divisor as a percentage. The
+/// value must be dimensionless: the unit of the divisor has already
+/// been divided out. Division by zero yields missing data rather than
+/// an infinite percentage.
+fn percent(v: Quantity, divisor: f64) -> Result<Value, EvalError> {
+    match divisor == 0.0 || divisor.is_nan() {
+        true => Err(EvalError::DataError(DataError::Missing)),
+        false => {
+            let unit = Unit::Dimensionless(DimensionlessUnit::Percent);
+            Ok(Value::Quantity((v / divisor).convert(&unit)?))
+        }
+    }
+}
+
+/// An optional operand: missing values are skipped (`None`), unless
+/// they are propagated.
+fn operand(
+    res: Result<Value, EvalError>,
+    opts: &EvalOpts,
+) -> Result<Option<Value>, EvalError> {
+    match res {
+        Ok(v) => Ok(Some(v)),
+        Err(e) if e.is_missing() && opts.missing == MissingValues::Skip => {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// The smaller, or larger if `max` is set, of two numbers. Integers
+/// compared to floats are converted to floats.
+fn min_max(v1: Value, v2: Value, max: bool) -> Result<Value, EvalError> {
+    let (ord, v1, v2) = match NumericValuePair::from(v1, v2) {
+        Some(NumericValuePair::Integer(a, b)) => {
+            (a.partial_cmp(&b), Value::Integer(a), Value::Integer(b))
+        }
+        Some(NumericValuePair::Float(a, b)) => {
+            (a.partial_cmp(&b), Value::Float(a), Value::Float(b))
+        }
+        Some(NumericValuePair::Decimal(a, b)) => {
+            (a.partial_cmp(&b), Value::Decimal(a), Value::Decimal(b))
+        }
+        Some(NumericValuePair::Quantity(a, b)) => {
+            (a.partial_cmp(&b)?, Value::Quantity(a), Value::Quantity(b))
+        }
+        None => {
+            return Err(EvalError::TypeError(
+                "invalid types for min, max or clamp (expected: numeric)",
+            ))
+        }
+    };
+    Ok(match (ord, max) {
+        (Some(Ordering::Greater), false) | (Some(Ordering::Less), true) => v2,
+        _ => v1,
+    })
+}
+
+fn min_max_type(t1: Type, t2: Type) -> Result<Type, EvalError> {
+    match NumericTypePair::from(t1, t2) {
+        Some(NumericTypePair::Integer) => Ok(Type::Integer),
+        Some(NumericTypePair::Float) => Ok(Type::Float),
+        Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
+        Some(NumericTypePair::Quantity(d1, d2)) if d1 == d2 => {
+            Ok(Type::Quantity(d1))
+        }
+        Some(NumericTypePair::Quantity(_, _)) => Err(EvalError::TypeError(
+            "min, max or clamp of quantities with different dimensions",
+        )),
+        None => Err(EvalError::TypeError(
+            "invalid types for min, max or clamp (expected: numeric)",
+        )),
+    }
+}
+
+/// The result type of a fallback between expressions of type `t1`
+/// and `t2`.
+fn common_type(
+    t1: Type,
+    t2: Type,
+    opts: &EvalOpts,
+    err: &'static str,
+) -> Result<Type, EvalError> {
+    if t1 == t2 {
+        return Ok(t1);
+    }
+    match (t1, t2) {
+        (
+            Type::BinaryString | Type::UnicodeString,
+            Type::BinaryString | Type::UnicodeString,
+        ) => match &opts.types.strict_strings {
+            false => Ok(Type::UnicodeString),
+            true => Err(EvalError::TypeError(
+                "fallback between binary and unicode string \
+                 while implicit casting is disabled",
+            )),
+        },
+        (t1, t2) => match NumericTypePair::from(t1, t2) {
+            Some(NumericTypePair::Integer) => Ok(Type::Integer),
+            Some(NumericTypePair::Float) => Ok(Type::Float),
+            Some(NumericTypePair::Decimal) => Ok(Type::Decimal),
+            Some(NumericTypePair::Quantity(d1, d2)) => {
+                Ok(Type::Quantity((d1 + d2)?))
+            }
+            None => Err(EvalError::TypeError(err)),
+        },
+    }
+}
+
+/// Get a string argument for a string comparison. Binary strings are
+/// converted lossily, unless implicit string conversion is disabled.
+fn eval_string(v: Value, opts: &EvalOpts) -> Result<String, EvalError> {
+    match v {
+        Value::UnicodeString(v) => Ok(v),
+        Value::BinaryString(v) => match &opts.types.strict_strings {
+            false => Ok(String::from_utf8_lossy(&v).into_owned()),
+            true => Err(EvalError::TypeError(
+                "string comparison on binary string while implicit \
+                 string conversion is disabled",
+            )),
+        },
+        _ => Err(EvalError::TypeError(
+            "invalid type for string comparison (expected: unicode string)",
+        )),
+    }
+}
+
+fn check_string(t: Type, opts: &EvalOpts) -> Result<(), EvalError> {
+    match t {
+        Type::UnicodeString => Ok(()),
+        Type::BinaryString => match &opts.types.strict_strings {
+            false => Ok(()),
+            true => Err(EvalError::TypeError(
+                "string comparison on binary string while implicit \
+                 string conversion is disabled",
+            )),
+        },
+        _ => Err(EvalError::TypeError(
+            "invalid type for string comparison (expected: unicode string)",
+        )),
+    }
+}
+
+fn eval_unicode(v: Value, opts: &EvalOpts) -> Result<String, EvalError> {
+    match v {
+        Value::UnicodeString(v) => Ok(v),
+        Value::BinaryString(v) => match &opts.types.strict_strings {
+            false => Ok(String::from_utf8_lossy(&v).into_owned()),
+            true => Err(EvalError::TypeError(
+                "string function on binary string while implicit \
+                 string conversion is disabled",
+            )),
+        },
+        _ => Err(EvalError::TypeError(
+            "invalid argument type for string function \
+             (expected: unicode string)",
+        )),
+    }
+}
+
+fn check_unicode(t: Type, opts: &EvalOpts) -> Result<(), EvalError> {
+    match t {
+        Type::UnicodeString => Ok(()),
+        Type::BinaryString => match &opts.types.strict_strings {
+            false => Ok(()),
+            true => Err(EvalError::TypeError(
+                "string function on binary string while implicit \
+                 string conversion is disabled",
+            )),
+        },
+        _ => Err(EvalError::TypeError(
+            "invalid argument type for string function \
+             (expected: unicode string)",
+        )),
+    }
+}
+
+/// Unicode case folding, approximated by a round trip through upper
+/// case, so that e.g. "ß" and "SS" compare equal.
+fn casefold(v: &str) -> String {
+    v.to_uppercase().to_lowercase()
+}
+
+/// Round a numeric value to an integral value. Integers are returned
+/// unchanged.
+fn round_value(v: Value, round: fn(f64) -> f64) -> Result<Value, EvalError> {
+    match v {
+        Value::Integer(v) => Ok(Value::Integer(v)),
+        Value::Float(v) => Ok(Value::Float(round(v))),
+        Value::Quantity(Quantity(v, u)) => {
+            Ok(Value::Quantity(Quantity(round(v), u)))
+        }
+        _ => Err(EvalError::TypeError("invalid type for rounding")),
+    }
+}
+
+/// Round to the given number of decimals, half to even. Negative
+/// decimals round to tens, hundreds, etc. Note that the decimal
+/// value may not be exactly representable: 2.675 is stored as
+/// 2.67499..., and thus rounds to 2.67.
+fn round_float_to(v: f64, decimals: i64) -> f64 {
+    let scale = 10f64.powi(decimals.clamp(-308, 308) as i32);
+    match v * scale {
+        scaled if scaled.is_finite() => scaled.round_ties_even() / scale,
+        _ => v,
+    }
+}
+
+/// Round an integer to a (negative) number of decimals, half to even.
+fn round_integer_to(v: i64, decimals: i64) -> Result<i64, EvalError> {
+    if decimals >= 0 {
+        return Ok(v);
+    }
+    let m = match u32::try_from(-decimals)
+        .ok()
+        .and_then(|e| 10i64.checked_pow(e))
+    {
+        Some(m) => m,
+        None => return Ok(0),
+    };
+    let (q, r) = (v.div_euclid(m), v.rem_euclid(m));
+    let q = match (2 * r).cmp(&m) {
+        Ordering::Less => q,
+        Ordering::Greater => q + 1,
+        Ordering::Equal => q + q.rem_euclid(2),
+    };
+    q.checked_mul(m).ok_or(EvalError::IntegerOverflow)
+}
+
+/// Operand of a three-valued logic operator; booleans are promoted.
+fn tristate(v: &Value) -> Option<TriState> {
+    match v {
+        Value::Boolean(v) => Some(TriState::from(*v)),
+        Value::TriState(v) => Some(*v),
+        _ => None,
+    }
+}
@@ -2,6 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+pub mod aggregate;
 pub mod error;
 pub mod eval;
 pub mod expr;
@@ -9,8 +10,9 @@ mod options;
 pub mod parser;
 pub mod row;
 
+pub use aggregate::{Aggregate, MissingValues};
 pub use error::{EvalError, EvalResult};
 pub use eval::EvalCell;
 pub use expr::Expr;
 pub use options::EvalOpts;
-pub use row::{ExprRow, TypeRow, ValueRow};
+pub use row::{aggregate_column, ExprRow, TypeRow, ValueRow};
@@ -2,9 +2,15 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use value::TypeOpts;
+use value::{Epsilon, TypeOpts};
+
+use super::aggregate::MissingValues;
 
 #[derive(Default, Debug)]
 pub struct EvalOpts {
     pub types: TypeOpts,
+    /// Tolerance for the approximate equality operator (`~=`).
+    pub epsilon: Epsilon,
+    /// Whether aggregates skip or propagate missing values.
+    pub missing: MissingValues,
 }
@@ -10,16 +10,17 @@ use nom::{
     character::complete::{anychar, char, digit1, space0},
     combinator::{map, recognize, value},
     error::ErrorKind,
-    multi::many1,
+    multi::{many1, separated_list1},
     sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 use regex::Regex;
 
+use super::aggregate::Aggregate;
 use super::error::EvalError;
 use super::expr::Expr;
 use unit::parser::valid_composite_unit;
-use value::Value;
+use value::{TriState, Value};
 
 pub fn parse_expr(input: &str) -> Result<Expr, EvalError> {
     match string_expr(input) {
@@ -250,6 +251,7 @@ operator_table! { alg_expr {
     alg_expr_not,   unary,         { char('!') => Expr::Not },
     alg_expr_cmp,   binary,        { tag("<=") => Expr::Le, char('<') => Expr::Lt,
                      tag("==") => Expr::Eq, tag("!=") => Expr::Ne,
+                     tag("~=") => Expr::ApproxEq,
                      tag(">=") => Expr::Ge, char('>') => Expr::Gt },
     /* bitwise operators? */
     alg_expr_sum,  binary_lassoc,  { char('+') => Expr::Add, char('-') => Expr::Sub },
@@ -257,7 +259,9 @@ operator_table! { alg_expr {
     alg_expr_pow,  binary_rassoc,  { char('^') => Expr::Pow, tag("**") => Expr::Pow },
     alg_expr_neg,  unary,          { char('-') => Expr::Neg },
     alg_expr_term, { opt_unit(variable_reference), opt_unit(data_reference),
-             opt_unit(function), opt_unit(float_literal),
+             opt_unit(function), opt_unit(coalesce_fun),
+             opt_unit(aggregate_fun), now_fun,
+             opt_unit(float_literal),
              opt_unit(integer_literal), opt_unit(brackets),
              bool_literal, string_literal }
 }}
@@ -265,11 +269,27 @@ operator_table! { alg_expr {
 function_table! { function {
     convert_fun,     "convert",     Expr::Convert,    (expr:expr , unit:unit ),
     fallback_fun,    "fallback",    Expr::Fallback,   (expr1:expr, expr2:expr),
+    default_fun,     "default",     Expr::Default,    (expr:expr, default:expr),
+    if_fun,          "if",          Expr::If,         (cond:expr, then:expr, other:expr),
+    map_fun,         "map",         Expr::Map,        (list:expr, expr:expr),
+    filter_fun,      "filter",      Expr::Filter,     (list:expr, pred:expr),
+    index_fun,       "index",       Expr::Index,      (list:expr, index:expr),
     format_fun,      "format",      Expr::Format,     (fmt:string, expr:expr ),
     tostring_fun,    "to_string",   Expr::ToString,   (expr:expr),
     regsubst_fun,    "substitute",  Expr::RegSubst,   (expr:expr, regex:regex, subst:string),
     substr_fun,      "substr",      Expr::SubStr,     (e:expr, f:expr, t:expr),
     concat_fun,      "concat",      Expr::Concat,     (expr1:expr, expr2:expr),
+    substring_fun,   "substring",   Expr::Substring,  (e:expr, start:expr, len:expr),
+    split_fun,       "split",       Expr::Split,      (expr:expr, sep:expr),
+    replace_fun,     "replace",     Expr::Replace,    (expr:expr, from:expr, to:expr),
+    lower_fun,       "lower",       Expr::Lower,      (expr:expr),
+    upper_fun,       "upper",       Expr::Upper,      (expr:expr),
+    trim_fun,        "trim",        Expr::Trim,       (expr:expr),
+
+    eq_ci_fun,       "eq_ci",       Expr::EqCi,       (expr1:expr, expr2:expr),
+    contains_fun,    "contains",    Expr::Contains,   (expr:expr, substr:expr),
+    starts_with_fun, "starts_with", Expr::StartsWith, (expr:expr, prefix:expr),
+    matches_fun,     "matches",     Expr::Matches,    (expr:expr, regex:expr),
 
     from_utf8_fun,        "from_utf8",        Expr::FromUtf8,       (expr:expr),
     from_utf8_lossy_fun,  "from_utf8_lossy",  Expr::FromUtf8Lossy,  (expr:expr),
@@ -290,13 +310,27 @@ function_table! { function {
     sha1_fun,        "sha1",        Expr::SHA1,       (expr:expr),
 
     not_empty_fun,   "not_empty",   Expr::NotEmpty,   (expr:expr),
+    is_unknown_fun,  "is_unknown",  Expr::IsUnknown,  (expr:expr),
+    unknown_as_fun,  "unknown_as",  Expr::UnknownAs,  (expr:expr, default:expr),
 
     log_fun,         "log",         Expr::Log,        (base:expr, expr:expr),
     abs_fun,         "abs",         Expr::Abs,        (expr:expr),
+    percent_fun,     "percent",     Expr::Percent,    (expr:expr, divisor:expr),
+    min_fun,         "min",         Expr::Min,        (expr1:expr, expr2:expr),
+    max_fun,         "max",         Expr::Max,        (expr1:expr, expr2:expr),
+    clamp_fun,       "clamp",       Expr::Clamp,      (expr:expr, min:expr, max:expr),
     sign_fun,        "sign",        Expr::Sign,       (expr:expr),
+    round_fun,       "round",       Expr::Round,      (expr:expr),
+    floor_fun,       "floor",       Expr::Floor,      (expr:expr),
+    ceil_fun,        "ceil",        Expr::Ceil,       (expr:expr),
+    round_to_fun,    "round_to",    Expr::RoundTo,    (expr:expr, decimals:expr),
     bits_le_fun,     "bits_le",     Expr::BitsLE,     (n:expr,f:expr,l:expr),
     bits_be_fun,     "bits_be",     Expr::BitsBE,     (n:expr,f:expr,l:expr),
 
+    parse_time_utc_fun, "parse_time_utc", Expr::ParseTimeUtc, (expr:expr, format:string),
+    parse_time_fun,  "parse_time",  Expr::ParseTime,  (expr:expr, format:string),
+    duration_fun,    "duration",    Expr::Duration,   (expr:expr),
+
     hex_string_fun,  "hex_string",  Expr::HexStr,     (expr:expr),
     unpack_time_fun, "unpack_time", Expr::UnpackTime, (expr:expr)
 
@@ -323,6 +357,39 @@ where
     }
 }
 
+fn coalesce_fun(input: &str) -> IResult<&str, Expr> {
+    let (input, _) = tag("coalesce")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, args) = separated_list1(char(','), alg_expr)(input)?;
+    let (input, _) = char(')')(input)?;
+    Ok((input, Expr::Coalesce(args)))
+}
+
+fn aggregate_fun(input: &str) -> IResult<&str, Expr> {
+    let (input, aggregate) = alt((
+        value(Aggregate::Count, tag("count")),
+        value(Aggregate::Sum, tag("sum")),
+        value(Aggregate::Avg, tag("avg")),
+        value(Aggregate::Min, tag("min")),
+        value(Aggregate::Max, tag("max")),
+    ))(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, list) = alg_expr(input)?;
+    let (input, _) = char(')')(input)?;
+    Ok((input, Expr::Aggregate(aggregate, Box::new(list))))
+}
+
+fn now_fun(input: &str) -> IResult<&str, Expr> {
+    let (input, _) = tag("now")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, _) = space0(input)?;
+    let (input, _) = char(')')(input)?;
+    Ok((input, Expr::Now))
+}
+
 fn brackets(input: &str) -> IResult<&str, Expr> {
     let (input, _) = char('(')(input)?;
     let (input, res) = alg_expr(input)?;
@@ -375,6 +442,10 @@ fn bool_literal(input: &str) -> IResult<&str, Expr> {
     alt((
         value(Expr::Literal(Value::Boolean(false)), tag("false")),
         value(Expr::Literal(Value::Boolean(true)), tag("true")),
+        value(
+            Expr::Literal(Value::TriState(TriState::Unknown)),
+            tag("unknown"),
+        ),
     ))(input)
 }
 
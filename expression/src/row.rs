@@ -5,9 +5,10 @@
 use linked_hash_map::LinkedHashMap;
 use std::collections::HashMap;
 
-use value::{Data, Type, Value};
+use value::{Data, DataError, Type, Value};
 
-use super::error::EvalError;
+use super::aggregate::Aggregate;
+use super::error::{EvalError, EvalResult};
 use super::eval::EvalCell;
 use super::expr::Expr;
 use super::options::EvalOpts;
@@ -82,3 +83,38 @@ impl<'a> ExprRow<'a> {
         )
     }
 }
+
+impl<'a> TypeRow<'a> {
+    /// The type of an aggregate over a column of rows of this type.
+    pub fn check_aggregate(
+        &self,
+        column: &str,
+        aggregate: Aggregate,
+    ) -> Result<Type, EvalError> {
+        match self.0.get(column) {
+            Some(Ok(t)) => aggregate.check(t),
+            Some(Err(e)) => Err(e.clone()),
+            None => Err(EvalError::DataError(DataError::Missing)),
+        }
+    }
+}
+
+/// Aggregate a column over the rows of a query result into a single
+/// value, e.g. for use in a singleton table. Missing values are
+/// skipped or propagated according to `opts`.
+pub fn aggregate_column(
+    rows: &[ValueRow],
+    column: &str,
+    aggregate: Aggregate,
+    opts: &EvalOpts,
+) -> EvalResult {
+    aggregate.eval_opts(
+        rows.iter().map(|row| {
+            row.0
+                .get(column)
+                .cloned()
+                .unwrap_or(Err(EvalError::DataError(DataError::Missing)))
+        }),
+        opts.missing,
+    )
+}
@@ -0,0 +1,128 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use linked_hash_map::LinkedHashMap;
+
+use expression::{
+    aggregate_column, Aggregate, EvalError, EvalOpts, Expr, MissingValues,
+    ValueRow,
+};
+use value::{DataError, ListValue, Type, Value};
+
+fn float_list(vs: &[f64]) -> Value {
+    Value::List(
+        ListValue::new(
+            Arc::new(Type::Float),
+            vs.iter().copied().map(Value::Float).collect(),
+        )
+        .unwrap(),
+    )
+}
+
+fn eval(expr: &str, list: Value) -> Result<Value, EvalError> {
+    Expr::parse(expr).unwrap().eval(Some(&Ok(list)))
+}
+
+fn rows(values: &[Option<i64>]) -> Vec<ValueRow<'static>> {
+    values
+        .iter()
+        .map(|v| {
+            let mut row = LinkedHashMap::new();
+            row.insert(
+                "throughput",
+                v.map(Value::Integer)
+                    .ok_or(EvalError::DataError(DataError::Missing)),
+            );
+            ValueRow(row)
+        })
+        .collect()
+}
+
+#[test]
+fn aggregate_list() {
+    let temps = float_list(&[41.5, 38.0, 45.5]);
+    assert_eq!(
+        eval("{sum(@)}", temps.clone()).unwrap(),
+        Value::Float(125.0)
+    );
+    assert_eq!(
+        eval("{avg(@)}", temps.clone()).unwrap(),
+        Value::Float(125.0 / 3.0)
+    );
+    assert_eq!(eval("{min(@)}", temps.clone()).unwrap(), Value::Float(38.0));
+    assert_eq!(eval("{max(@)}", temps.clone()).unwrap(), Value::Float(45.5));
+    assert_eq!(eval("{count(@)}", temps).unwrap(), Value::Integer(3));
+}
+
+#[test]
+fn aggregate_empty_list() {
+    assert_eq!(
+        eval("{sum(@)}", float_list(&[])).unwrap(),
+        Value::Float(0.0)
+    );
+    assert_eq!(
+        eval("{count(@)}", float_list(&[])).unwrap(),
+        Value::Integer(0)
+    );
+    assert!(eval("{avg(@)}", float_list(&[])).unwrap_err().is_missing());
+    assert!(eval("{max(@)}", float_list(&[])).unwrap_err().is_missing());
+}
+
+#[test]
+fn check_aggregate() {
+    let list = Type::List(Arc::new(Type::Integer));
+    let check = |expr: &str| Expr::parse(expr).unwrap().check(Some(&list));
+    assert_eq!(check("{sum(@)}").unwrap(), Type::Integer);
+    assert_eq!(check("{avg(@)}").unwrap(), Type::Float);
+    assert_eq!(check("{max(@)}").unwrap(), Type::Integer);
+    assert_eq!(check("{count(@)}").unwrap(), Type::Integer);
+    assert!(Expr::parse("{sum(@)}")
+        .unwrap()
+        .check(Some(&Type::Integer))
+        .is_err());
+}
+
+#[test]
+fn aggregate_column_skips_missing() {
+    let rows = rows(&[Some(10), None, Some(32)]);
+    let opts = EvalOpts::default();
+    assert_eq!(
+        aggregate_column(&rows, "throughput", Aggregate::Sum, &opts).unwrap(),
+        Value::Integer(42)
+    );
+    assert_eq!(
+        aggregate_column(&rows, "throughput", Aggregate::Avg, &opts).unwrap(),
+        Value::Float(21.0)
+    );
+    assert_eq!(
+        aggregate_column(&rows, "throughput", Aggregate::Count, &opts).unwrap(),
+        Value::Integer(2)
+    );
+}
+
+#[test]
+fn aggregate_column_propagates_missing() {
+    let rows = rows(&[Some(10), None, Some(32)]);
+    let opts = EvalOpts {
+        missing: MissingValues::Propagate,
+        ..EvalOpts::default()
+    };
+    assert!(aggregate_column(&rows, "throughput", Aggregate::Sum, &opts)
+        .unwrap_err()
+        .is_missing());
+}
+
+#[test]
+fn aggregate_empty_column() {
+    let opts = EvalOpts::default();
+    assert_eq!(
+        aggregate_column(&[], "throughput", Aggregate::Sum, &opts).unwrap(),
+        Value::Integer(0)
+    );
+    assert!(aggregate_column(&[], "throughput", Aggregate::Avg, &opts)
+        .unwrap_err()
+        .is_missing());
+}
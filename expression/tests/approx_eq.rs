@@ -0,0 +1,41 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalOpts, Expr};
+use value::{Epsilon, Type, Value};
+
+fn eval(expr: &str, epsilon: Epsilon) -> bool {
+    let opts = EvalOpts {
+        epsilon,
+        ..EvalOpts::default()
+    };
+    match Expr::parse(expr).unwrap().eval_opts(None, &opts).unwrap() {
+        Value::Boolean(v) => v,
+        v => panic!("expected a boolean, got {v:?}"),
+    }
+}
+
+#[test]
+fn approx_eq() {
+    let eps = Epsilon::default();
+    assert!(eval("{0.1 + 0.2 ~= 0.3}", eps));
+    assert!(!eval("{0.1 + 0.2 == 0.3}", eps));
+    assert!(eval("{3 ~= 3.0}", eps));
+    assert!(!eval("{0.3 ~= 0.31}", eps));
+}
+
+#[test]
+fn epsilon_modes() {
+    assert!(eval("{100.0 ~= 100.5}", Epsilon::Absolute(1.0)));
+    assert!(!eval("{100.0 ~= 101.5}", Epsilon::Absolute(1.0)));
+    assert!(eval("{1000000.0 ~= 1000500.0}", Epsilon::Relative(1e-3)));
+    assert!(!eval("{1.0 ~= 1.5}", Epsilon::Relative(1e-3)));
+}
+
+#[test]
+fn types() {
+    let check = |expr: &str| Expr::parse(expr).unwrap().check(None);
+    assert_eq!(check("{1.5 ~= 1}").unwrap(), Type::Boolean);
+    assert!(check("{'a' ~= 'a'}").is_err());
+}
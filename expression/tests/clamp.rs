@@ -0,0 +1,122 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalError, EvalOpts, Expr, MissingValues};
+use unit::{Dimension, Quantity};
+use value::{Data, DataError, Type, Value};
+
+fn eval(expr: &str, data: Data) -> Result<Value, EvalError> {
+    Expr::parse(expr).unwrap().eval(Some(&data))
+}
+
+fn eval_propagate(expr: &str, data: Data) -> Result<Value, EvalError> {
+    let opts = EvalOpts {
+        missing: MissingValues::Propagate,
+        ..EvalOpts::default()
+    };
+    Expr::parse(expr).unwrap().eval_opts(Some(&data), &opts)
+}
+
+fn check(expr: &str) -> Result<Type, EvalError> {
+    Expr::parse(expr).unwrap().check(Some(&Type::Integer))
+}
+
+#[test]
+fn clamp_to_range() {
+    for (value, expected) in [(-5, 0), (0, 0), (42, 42), (100, 100), (120, 100)]
+    {
+        assert_eq!(
+            eval("{clamp(@, 0, 100)}", Ok(Value::Integer(value))).unwrap(),
+            Value::Integer(expected)
+        );
+    }
+    assert_eq!(
+        eval("{clamp(@, 0.0, 1.0)}", Ok(Value::Float(1.5))).unwrap(),
+        Value::Float(1.0)
+    );
+    assert_eq!(
+        eval("{clamp(@, 0, 100)}", Ok(Value::Float(-0.5))).unwrap(),
+        Value::Float(0.0)
+    );
+}
+
+#[test]
+fn clamp_quantities() {
+    let value = Value::Quantity(Quantity::parse("30 s").unwrap());
+    assert_eq!(
+        eval("{clamp(@, 1 s, 10 s)}", Ok(value)).unwrap(),
+        Value::Quantity(Quantity::parse("10 s").unwrap())
+    );
+    let value = Value::Quantity(Quantity::parse("200 ms").unwrap());
+    assert_eq!(
+        eval("{clamp(@, 1 s, 10 s)}", Ok(value)).unwrap(),
+        Value::Quantity(Quantity::parse("1 s").unwrap())
+    );
+}
+
+#[test]
+fn min_and_max() {
+    let v = |n| Ok(Value::Integer(n));
+    assert_eq!(eval("{min(@, 10)}", v(3)).unwrap(), Value::Integer(3));
+    assert_eq!(eval("{min(@, 10)}", v(30)).unwrap(), Value::Integer(10));
+    assert_eq!(eval("{max(@, 10)}", v(3)).unwrap(), Value::Integer(10));
+    assert_eq!(eval("{max(@, 10)}", v(30)).unwrap(), Value::Integer(30));
+    assert_eq!(eval("{max(@, 2.5)}", v(2)).unwrap(), Value::Float(2.5));
+}
+
+#[test]
+fn missing_bounds() {
+    let missing = || Err(DataError::Missing);
+    assert_eq!(
+        eval("{clamp(150, @, 100)}", missing()).unwrap(),
+        Value::Integer(100)
+    );
+    assert_eq!(
+        eval("{clamp(-5, 0, @)}", missing()).unwrap(),
+        Value::Integer(0)
+    );
+    assert_eq!(
+        eval("{clamp(-5, @, @)}", missing()).unwrap(),
+        Value::Integer(-5)
+    );
+    assert_eq!(eval("{min(@, 10)}", missing()).unwrap(), Value::Integer(10));
+    assert!(eval("{clamp(@, 0, 100)}", missing())
+        .unwrap_err()
+        .is_missing());
+
+    assert!(eval_propagate("{clamp(150, @, 100)}", missing())
+        .unwrap_err()
+        .is_missing());
+    assert!(eval_propagate("{max(@, 10)}", missing())
+        .unwrap_err()
+        .is_missing());
+}
+
+#[test]
+fn clamp_types() {
+    assert_eq!(check("{clamp(@, 0, 100)}").unwrap(), Type::Integer);
+    assert_eq!(check("{clamp(@, 0, 1.5)}").unwrap(), Type::Float);
+    assert_eq!(check("{min(@, 1.5)}").unwrap(), Type::Float);
+    assert_eq!(
+        check("{max(1 s, 500 ms)}").unwrap(),
+        Type::Quantity(Dimension::Time)
+    );
+    assert!(check("{clamp(@, 0 s, 100)}").is_err());
+    assert!(check("{max(@, \"10\")}").is_err());
+}
+
+#[test]
+fn display_and_repr() {
+    let expr = Expr::parse("{clamp(@, 0, 100)}").unwrap();
+    assert_eq!(Expr::parse(&format!("{{{expr}}}")).unwrap(), expr);
+    assert_eq!(
+        expr.py_repr().to_string(),
+        format!(
+            "Clamp({},{},{})",
+            Expr::Data.py_repr(),
+            Expr::parse("{0}").unwrap().py_repr(),
+            Expr::parse("{100}").unwrap().py_repr()
+        )
+    );
+}
@@ -0,0 +1,95 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use expression::{EvalError, Expr};
+use value::{Data, DataError, OptionValue, Type, Value};
+
+fn eval(expr: &str, data: Data) -> Result<Value, EvalError> {
+    Expr::parse(expr).unwrap().eval(Some(&data))
+}
+
+fn check(expr: &str, data: Type) -> Result<Type, EvalError> {
+    Expr::parse(expr).unwrap().check(Some(&data))
+}
+
+fn none() -> Data {
+    Ok(Value::Option(
+        OptionValue::new(Arc::new(Type::Integer), None).unwrap(),
+    ))
+}
+
+#[test]
+fn default_on_missing() {
+    assert_eq!(
+        eval("{default(@, 0)}", Err(DataError::Missing)).unwrap(),
+        Value::Integer(0)
+    );
+    assert_eq!(
+        eval("{default(@, 0)}", Ok(Value::Integer(5))).unwrap(),
+        Value::Integer(5)
+    );
+    assert_eq!(
+        eval("{default(@ + 1, 0)}", Err(DataError::Missing)).unwrap(),
+        Value::Integer(0)
+    );
+}
+
+#[test]
+fn default_propagates_errors() {
+    assert!(matches!(
+        eval("{default(@, 0)}", Err(DataError::CounterOverflow)),
+        Err(EvalError::DataError(DataError::CounterOverflow))
+    ));
+}
+
+#[test]
+fn coalesce() {
+    assert_eq!(
+        eval("{coalesce(@, 1, 2)}", Err(DataError::Missing)).unwrap(),
+        Value::Integer(1)
+    );
+    assert_eq!(
+        eval("{coalesce(@, 1)}", Ok(Value::Integer(7))).unwrap(),
+        Value::Integer(7)
+    );
+    assert_eq!(eval("{coalesce(@, 3)}", none()).unwrap(), Value::Integer(3));
+    assert_eq!(
+        eval("{coalesce( @ , @ , 4 )}", none()).unwrap(),
+        Value::Integer(4)
+    );
+}
+
+#[test]
+fn coalesce_all_missing() {
+    assert!(matches!(
+        eval("{coalesce(@, @)}", Err(DataError::Missing)),
+        Err(EvalError::DataError(DataError::Missing))
+    ));
+}
+
+#[test]
+fn coalesce_propagates_errors() {
+    assert!(matches!(
+        eval("{coalesce(@, 1)}", Err(DataError::CounterOverflow)),
+        Err(EvalError::DataError(DataError::CounterOverflow))
+    ));
+}
+
+#[test]
+fn types() {
+    assert_eq!(
+        check("{default(@, 0)}", Type::Integer).unwrap(),
+        Type::Integer
+    );
+    assert_eq!(check("{coalesce(@, 0)}", Type::Float).unwrap(), Type::Float);
+    assert_eq!(
+        check("{coalesce(@, 0)}", Type::Option(Arc::new(Type::Integer)))
+            .unwrap(),
+        Type::Integer
+    );
+    assert!(check("{coalesce(@, 'none')}", Type::Integer).is_err());
+    assert!(check("{default(@, 'none')}", Type::Integer).is_err());
+}
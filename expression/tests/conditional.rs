@@ -0,0 +1,65 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalError, Expr};
+use value::{Type, Value};
+
+fn eval(expr: &str, data: Value) -> Result<Value, EvalError> {
+    Expr::parse(expr).unwrap().eval(Some(&Ok(data)))
+}
+
+fn check(expr: &str, data: Type) -> Result<Type, EvalError> {
+    Expr::parse(expr).unwrap().check(Some(&data))
+}
+
+#[test]
+fn select_branch() {
+    let expr = "{if(@ == 1, 'up', if(@ == 2, 'down', 'unknown'))}";
+    assert_eq!(
+        eval(expr, Value::Integer(1)).unwrap(),
+        Value::UnicodeString("up".to_string())
+    );
+    assert_eq!(
+        eval(expr, Value::Integer(2)).unwrap(),
+        Value::UnicodeString("down".to_string())
+    );
+    assert_eq!(
+        eval(expr, Value::Integer(7)).unwrap(),
+        Value::UnicodeString("unknown".to_string())
+    );
+}
+
+#[test]
+fn short_circuit() {
+    /* No variables are available, so evaluating ${capacity} fails. */
+    let expr = "{if(@ == 0, 0, @ / ${capacity})}";
+    assert_eq!(eval(expr, Value::Integer(0)).unwrap(), Value::Integer(0));
+    assert!(eval(expr, Value::Integer(4)).is_err());
+    let expr = "{if(@ != 0, @ / ${capacity}, 0)}";
+    assert_eq!(eval(expr, Value::Integer(0)).unwrap(), Value::Integer(0));
+    assert_eq!(
+        eval("{if(@ == 0, 0, 100 / @)}", Value::Integer(4)).unwrap(),
+        Value::Float(25.0)
+    );
+}
+
+#[test]
+fn types() {
+    assert_eq!(
+        check("{if(@ > 1, 'high', 'low')}", Type::Integer).unwrap(),
+        Type::UnicodeString
+    );
+    assert_eq!(
+        check("{if(@ > 1, @, 1.5)}", Type::Integer).unwrap(),
+        Type::Float
+    );
+    assert!(matches!(
+        check("{if(@, 1, 2)}", Type::Integer),
+        Err(EvalError::TypeError(_))
+    ));
+    assert!(matches!(
+        check("{if(@ > 1, 'high', 0)}", Type::Integer),
+        Err(EvalError::TypeError(_))
+    ));
+}
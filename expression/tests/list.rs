@@ -0,0 +1,86 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use expression::Expr;
+use value::{ListValue, Type, Value};
+
+fn int_list(vs: &[i64]) -> Value {
+    Value::List(
+        ListValue::new(
+            Arc::new(Type::Integer),
+            vs.iter().copied().map(Value::Integer).collect(),
+        )
+        .unwrap(),
+    )
+}
+
+fn list_type(t: Type) -> Type {
+    Type::List(Arc::new(t))
+}
+
+#[test]
+fn map_list() {
+    let expr = Expr::parse("{map(@, @ * 2)}").unwrap();
+    assert_eq!(
+        expr.check(Some(&list_type(Type::Integer))).unwrap(),
+        list_type(Type::Integer)
+    );
+    assert_eq!(
+        expr.eval(Some(&Ok(int_list(&[1, 2, 3])))).unwrap(),
+        int_list(&[2, 4, 6])
+    );
+
+    let expr = Expr::parse("{map(@, to_string(@))}").unwrap();
+    assert_eq!(
+        expr.check(Some(&list_type(Type::Integer))).unwrap(),
+        list_type(Type::UnicodeString)
+    );
+}
+
+#[test]
+fn map_empty_list() {
+    let expr = Expr::parse("{map(@, @ > 1)}").unwrap();
+    assert_eq!(
+        expr.eval(Some(&Ok(int_list(&[])))).unwrap(),
+        Value::List(
+            ListValue::new(Arc::new(Type::Boolean), Vec::new()).unwrap()
+        )
+    );
+}
+
+#[test]
+fn filter_list() {
+    let expr = Expr::parse("{filter(@, @ > 1)}").unwrap();
+    assert_eq!(
+        expr.check(Some(&list_type(Type::Integer))).unwrap(),
+        list_type(Type::Integer)
+    );
+    assert_eq!(
+        expr.eval(Some(&Ok(int_list(&[1, 2, 3])))).unwrap(),
+        int_list(&[2, 3])
+    );
+    assert_eq!(expr.eval(Some(&Ok(int_list(&[])))).unwrap(), int_list(&[]));
+}
+
+#[test]
+fn filter_then_map() {
+    let expr = Expr::parse("{map(filter(@, @ != 2), @ + 1)}").unwrap();
+    assert_eq!(
+        expr.eval(Some(&Ok(int_list(&[1, 2, 3])))).unwrap(),
+        int_list(&[2, 4])
+    );
+}
+
+#[test]
+fn invalid_arguments() {
+    let expr = Expr::parse("{filter(@, @ + 1)}").unwrap();
+    assert!(expr.check(Some(&list_type(Type::Integer))).is_err());
+    assert!(expr.eval(Some(&Ok(int_list(&[1])))).is_err());
+
+    let expr = Expr::parse("{map(@, @)}").unwrap();
+    assert!(expr.check(Some(&Type::Integer)).is_err());
+    assert!(expr.eval(Some(&Ok(Value::Integer(1)))).is_err());
+}
@@ -0,0 +1,64 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalError, Expr};
+use unit::{Dimension, DimensionlessUnit, Quantity, Unit};
+use value::{DataError, Type, Value};
+
+fn eval(expr: &str) -> Result<Value, EvalError> {
+    Expr::parse(expr).unwrap().eval(None)
+}
+
+fn percent(expr: &str) -> f64 {
+    match eval(expr).unwrap() {
+        Value::Quantity(Quantity(
+            v,
+            Unit::Dimensionless(DimensionlessUnit::Percent),
+        )) => v,
+        v => panic!("expected a percentage, got {v:?}"),
+    }
+}
+
+#[test]
+fn ratio_as_percentage() {
+    assert!((percent("{percent(1, 4)}") - 25.0).abs() < 1e-9);
+    assert!((percent("{percent(3.0, 2.0)}") - 150.0).abs() < 1e-9);
+    assert!((percent("{percent(250 ms, 1 s)}") - 25.0).abs() < 1e-9);
+}
+
+#[test]
+fn zero_denominator_is_missing() {
+    for expr in [
+        "{percent(1, 0)}",
+        "{percent(0.0, 0.0)}",
+        "{percent(1 s, 0 s)}",
+    ] {
+        assert!(matches!(
+            eval(expr),
+            Err(EvalError::DataError(DataError::Missing))
+        ));
+    }
+}
+
+#[test]
+fn negative_values() {
+    assert!((percent("{percent(-1, 4)}") + 25.0).abs() < 1e-9);
+    assert!((percent("{percent(1, -4)}") + 25.0).abs() < 1e-9);
+    assert!((percent("{percent(-1.0, -4.0)}") - 25.0).abs() < 1e-9);
+}
+
+#[test]
+fn percent_types() {
+    let check = |expr: &str| Expr::parse(expr).unwrap().check(None);
+    assert_eq!(
+        check("{percent(1, 4)}").unwrap(),
+        Type::Quantity(Dimension::Dimensionless)
+    );
+    assert!(check("{percent(1 s, 4)}").is_err());
+    assert!(check("{percent(\"1\", 4)}").is_err());
+    assert_eq!(
+        Expr::parse("{percent(@, 4)}").unwrap().to_string(),
+        "percent(@, 4)"
+    );
+}
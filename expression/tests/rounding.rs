@@ -0,0 +1,56 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::Expr;
+use value::{Type, Value};
+
+fn eval(expr: &str) -> Value {
+    Expr::parse(expr).unwrap().eval(None).unwrap()
+}
+
+#[test]
+fn round_half_to_even() {
+    assert_eq!(eval("{round(2.5)}"), Value::Float(2.0));
+    assert_eq!(eval("{round(3.5)}"), Value::Float(4.0));
+    assert_eq!(eval("{round(-2.5)}"), Value::Float(-2.0));
+    assert_eq!(eval("{round(-2.6)}"), Value::Float(-3.0));
+    assert_eq!(eval("{round(7)}"), Value::Integer(7));
+}
+
+#[test]
+fn floor_and_ceil() {
+    assert_eq!(eval("{floor(2.7)}"), Value::Float(2.0));
+    assert_eq!(eval("{floor(-2.2)}"), Value::Float(-3.0));
+    assert_eq!(eval("{ceil(2.2)}"), Value::Float(3.0));
+    assert_eq!(eval("{ceil(-2.7)}"), Value::Float(-2.0));
+}
+
+#[test]
+fn round_to_decimals() {
+    assert_eq!(eval("{round_to(0.125, 2)}"), Value::Float(0.12));
+    assert_eq!(eval("{round_to(0.375, 2)}"), Value::Float(0.38));
+    assert_eq!(eval("{round_to(-0.125, 2)}"), Value::Float(-0.12));
+    assert_eq!(eval("{round_to(1234.5, -2)}"), Value::Float(1200.0));
+    assert_eq!(eval("{round_to(1250, -2)}"), Value::Integer(1200));
+    assert_eq!(eval("{round_to(1350, -2)}"), Value::Integer(1400));
+    assert_eq!(eval("{round_to(-1250, -2)}"), Value::Integer(-1200));
+    assert_eq!(eval("{round_to(-1251, -2)}"), Value::Integer(-1300));
+    assert_eq!(eval("{round_to(42, 3)}"), Value::Integer(42));
+}
+
+#[test]
+fn rounding_types() {
+    let check = |expr: &str| Expr::parse(expr).unwrap().check(None);
+    assert_eq!(check("{round(1.5)}").unwrap(), Type::Float);
+    assert_eq!(check("{ceil(1)}").unwrap(), Type::Integer);
+    assert_eq!(check("{round_to(1.5, 1)}").unwrap(), Type::Float);
+    assert!(check("{round(\"1.5\")}").is_err());
+    assert!(check("{round_to(1.5, 1.0)}").is_err());
+}
+
+#[test]
+fn rounding_repr() {
+    let expr = Expr::parse("{round_to(floor(@), 2)}").unwrap();
+    assert_eq!(expr.to_string(), "round_to(floor(@), 2)");
+}
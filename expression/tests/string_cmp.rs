@@ -0,0 +1,79 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalError, Expr};
+use value::{Type, Value};
+
+fn eval(expr: &str) -> bool {
+    match Expr::parse(expr).unwrap().eval(None).unwrap() {
+        Value::Boolean(v) => v,
+        v => panic!("expected a boolean, got {v:?}"),
+    }
+}
+
+fn check(expr: &str) -> Result<Type, EvalError> {
+    Expr::parse(expr).unwrap().check(None)
+}
+
+#[test]
+fn eq_ci() {
+    assert!(eval("{eq_ci('Running', 'RUNNING')}"));
+    assert!(!eval("{eq_ci('Running', 'Stopped')}"));
+}
+
+#[test]
+fn eq_ci_unicode() {
+    assert!(eval("{eq_ci('Ärger', 'äRGER')}"));
+    assert!(eval("{eq_ci('Straße', 'STRASSE')}"));
+    assert!(eval("{eq_ci('ΟΔΟΣ', 'οδος')}"));
+    assert!(!eval("{eq_ci('Straße', 'Strase')}"));
+}
+
+#[test]
+fn contains() {
+    assert!(eval("{contains('link is up', 'up')}"));
+    assert!(!eval("{contains('link is up', 'Up')}"));
+    assert!(eval("{contains('größe', 'öß')}"));
+}
+
+#[test]
+fn starts_with() {
+    assert!(eval("{starts_with('ERROR: disk full', 'ERROR')}"));
+    assert!(!eval("{starts_with('no ERROR', 'ERROR')}"));
+}
+
+#[test]
+fn matches() {
+    assert!(eval("{matches('eth0', '^eth[0-9]+$')}"));
+    assert!(!eval("{matches('lo', '^eth[0-9]+$')}"));
+    assert!(eval("{matches('Ünïcödé', '(?i)^ü')}"));
+}
+
+#[test]
+fn typed_as_boolean() {
+    for expr in [
+        "{eq_ci('a', 'b')}",
+        "{contains('a', 'b')}",
+        "{starts_with('a', 'b')}",
+        "{matches('a', 'b')}",
+    ] {
+        assert_eq!(check(expr).unwrap(), Type::Boolean);
+    }
+}
+
+#[test]
+fn invalid_regex_fails_check() {
+    assert!(matches!(
+        check("{matches('a', '(unclosed')}"),
+        Err(EvalError::Regex(_))
+    ));
+}
+
+#[test]
+fn non_string_argument() {
+    assert!(matches!(
+        check("{contains(1, 'a')}"),
+        Err(EvalError::TypeError(_))
+    ));
+}
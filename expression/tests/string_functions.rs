@@ -0,0 +1,102 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::{EvalError, EvalOpts, Expr};
+use value::{Type, TypeOpts, Value};
+
+fn eval(expr: &str) -> Value {
+    Expr::parse(expr).unwrap().eval(None).unwrap()
+}
+
+fn string(expr: &str) -> String {
+    match eval(expr) {
+        Value::UnicodeString(v) => v,
+        v => panic!("expected a unicode string, got {v:?}"),
+    }
+}
+
+fn check(expr: &str) -> Result<Type, EvalError> {
+    Expr::parse(expr).unwrap().check(None)
+}
+
+#[test]
+fn substring() {
+    assert_eq!(
+        string("{substring('GigabitEthernet0/1', 7, 8)}"),
+        "Ethernet"
+    );
+    assert_eq!(string("{substring('Ärger', 1, 3)}"), "rge");
+    assert!(matches!(
+        Expr::parse("{substring('abc', 2, 5)}").unwrap().eval(None),
+        Err(EvalError::OutOfBounds)
+    ));
+}
+
+#[test]
+fn split_and_index() {
+    assert_eq!(
+        string("{index(split('Cisco IOS;15.2;c2960', ';'), 1)}"),
+        "15.2"
+    );
+    assert_eq!(
+        string("{index(split('Cisco IOS;15.2;c2960', ';'), -1)}"),
+        "c2960"
+    );
+    assert!(matches!(
+        Expr::parse("{index(split('a;b', ';'), 2)}")
+            .unwrap()
+            .eval(None),
+        Err(EvalError::OutOfBounds)
+    ));
+}
+
+#[test]
+fn replace() {
+    assert_eq!(string("{replace('eth0.100', '.', ':')}"), "eth0:100");
+    assert_eq!(string("{replace('abc', '', 'x')}"), "abc");
+}
+
+#[test]
+fn case_and_trim() {
+    assert_eq!(string("{lower('UP')}"), "up");
+    assert_eq!(string("{upper('straße')}"), "STRASSE");
+    assert_eq!(string("{trim('  eth0 ')}"), "eth0");
+}
+
+#[test]
+fn types() {
+    assert_eq!(
+        check("{split('a,b', ',')}").unwrap(),
+        Type::List(std::sync::Arc::new(Type::UnicodeString))
+    );
+    assert_eq!(
+        check("{index(split('a,b', ','), 0)}").unwrap(),
+        Type::UnicodeString
+    );
+    assert_eq!(check("{trim('a')}").unwrap(), Type::UnicodeString);
+    assert!(check("{lower(1)}").is_err());
+    assert!(check("{substring('abc', '1', 2)}").is_err());
+    assert!(check("{index('abc', 0)}").is_err());
+}
+
+#[test]
+fn binary_strings() {
+    let expr = Expr::parse("{upper(@)}").unwrap();
+    let data = Ok(Value::BinaryString(b"up".to_vec()));
+    assert_eq!(
+        expr.eval(Some(&data)).unwrap(),
+        Value::UnicodeString("UP".to_string())
+    );
+
+    let opts = EvalOpts {
+        types: TypeOpts {
+            strict_strings: true,
+        },
+        ..EvalOpts::default()
+    };
+    assert!(matches!(
+        expr.check_opts(Some(&Type::BinaryString), &opts),
+        Err(EvalError::TypeError(_))
+    ));
+}
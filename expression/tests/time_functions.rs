@@ -0,0 +1,93 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::{TimeZone, Utc};
+
+use expression::{EvalError, Expr};
+use unit::{Dimension, FracPrefix, Quantity, TimeUnit, Unit};
+use value::{Type, Value};
+
+fn eval(expr: &str, data: &str) -> Result<Value, EvalError> {
+    Expr::parse(expr)
+        .unwrap()
+        .eval(Some(&Ok(Value::UnicodeString(data.to_string()))))
+}
+
+#[test]
+fn parse_time_with_offset() {
+    assert_eq!(
+        eval(
+            "{parse_time(@, '%Y-%m-%d %H:%M:%S %z')}",
+            "2024-03-01 12:00:00 +0200"
+        )
+        .unwrap(),
+        Value::Time(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap())
+    );
+}
+
+#[test]
+fn parse_time_rejects_naive_timestamps() {
+    assert!(matches!(
+        eval(
+            "{parse_time(@, '%Y-%m-%d %H:%M:%S')}",
+            "2024-03-01 12:00:00"
+        ),
+        Err(EvalError::NaiveTime(_))
+    ));
+    assert_eq!(
+        eval(
+            "{parse_time_utc(@, '%Y-%m-%d %H:%M:%S')}",
+            "2024-03-01 12:00:00"
+        )
+        .unwrap(),
+        Value::Time(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap())
+    );
+    assert_eq!(
+        eval("{parse_time_utc(@, '%Y-%m-%d')}", "2024-03-01").unwrap(),
+        Value::Time(Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap())
+    );
+}
+
+#[test]
+fn parse_time_invalid() {
+    assert!(matches!(
+        eval("{parse_time_utc(@, '%Y-%m-%d')}", "yesterday"),
+        Err(EvalError::TimeParseError(_))
+    ));
+}
+
+#[test]
+fn duration_since_timestamp() {
+    let expr = "{duration(parse_time_utc(@, '%Y-%m-%d %H:%M:%S') \
+                - parse_time_utc('2024-03-01 10:30:00', \
+                '%Y-%m-%d %H:%M:%S'))}";
+    assert_eq!(
+        eval(expr, "2024-03-01 12:00:00").unwrap(),
+        Value::Quantity(Quantity(
+            5400.0,
+            Unit::Time(TimeUnit::Second(FracPrefix::Unit))
+        ))
+    );
+    assert_eq!(
+        Expr::parse(expr)
+            .unwrap()
+            .check(Some(&Type::UnicodeString))
+            .unwrap(),
+        Type::Quantity(Dimension::Time)
+    );
+}
+
+#[test]
+fn age_since_now() {
+    let age = Expr::parse("{duration(now() - @)}")
+        .unwrap()
+        .eval(Some(&Ok(Value::Time(
+            Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap(),
+        ))))
+        .unwrap();
+    match age {
+        Value::Quantity(q) => assert!(q.0 > 0.0),
+        _ => panic!("expected a quantity"),
+    }
+}
@@ -0,0 +1,84 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use expression::Expr;
+use value::{TriState, Type, Value};
+
+fn eval(expr: &str, data: TriState) -> Value {
+    Expr::parse(expr)
+        .unwrap()
+        .eval(Some(&Ok(Value::TriState(data))))
+        .unwrap()
+}
+
+#[test]
+fn logic() {
+    assert_eq!(
+        eval("{@ && false}", TriState::Unknown),
+        Value::TriState(TriState::False)
+    );
+    assert_eq!(
+        eval("{@ && true}", TriState::Unknown),
+        Value::TriState(TriState::Unknown)
+    );
+    assert_eq!(
+        eval("{@ || true}", TriState::Unknown),
+        Value::TriState(TriState::True)
+    );
+    assert_eq!(
+        eval("{!@}", TriState::False),
+        Value::TriState(TriState::True)
+    );
+    assert_eq!(
+        Expr::parse("{@ || false}")
+            .unwrap()
+            .check(Some(&Type::TriState))
+            .unwrap(),
+        Type::TriState
+    );
+}
+
+#[test]
+fn compare() {
+    assert_eq!(
+        eval("{@ == unknown}", TriState::Unknown),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        eval("{@ == unknown}", TriState::False),
+        Value::Boolean(false)
+    );
+    assert_eq!(eval("{@ != unknown}", TriState::True), Value::Boolean(true));
+    assert!(Expr::parse("{@ == 1}")
+        .unwrap()
+        .check(Some(&Type::TriState))
+        .is_err());
+}
+
+#[test]
+fn explicit_unknown() {
+    assert_eq!(
+        eval("{is_unknown(@)}", TriState::Unknown),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        eval("{is_unknown(@)}", TriState::False),
+        Value::Boolean(false)
+    );
+    assert_eq!(
+        eval("{unknown_as(@, true)}", TriState::Unknown),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        eval("{unknown_as(@, true)}", TriState::False),
+        Value::Boolean(false)
+    );
+    assert_eq!(
+        Expr::parse("{unknown_as(@, false)}")
+            .unwrap()
+            .check(Some(&Type::TriState))
+            .unwrap(),
+        Type::Boolean
+    );
+}
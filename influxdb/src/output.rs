@@ -0,0 +1,230 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::error::Result;
+use expression::EvalError;
+use value::Value;
+
+/* Table and field names, as used in measurement and field keys. */
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct InfluxTableName(pub String);
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct InfluxFieldName(pub String);
+
+/// A row of a table: the tags identifying the item (item id and
+/// name, index columns) and the field values.
+#[derive(Default, Debug)]
+pub struct InfluxRow {
+    pub tags: BTreeMap<String, String>,
+    pub fields: HashMap<InfluxFieldName, std::result::Result<Value, EvalError>>,
+}
+
+/// Timestamp precision, which should match the precision the
+/// consumer is configured to write with.
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    #[default]
+    Ns,
+    Ms,
+    S,
+}
+
+enum FieldValue {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+}
+
+/// Write the tables in InfluxDB line protocol. Every run produces a
+/// new file in `base_dir`, which is renamed into place when complete,
+/// so that a shipper can pick up and remove finished files.
+pub fn write_output(
+    base_dir: &Path,
+    host: &str,
+    site: &str,
+    precision: Precision,
+    data: &HashMap<InfluxTableName, Vec<InfluxRow>>,
+) -> Result<()> {
+    fs::create_dir_all(base_dir)?;
+
+    let now = Utc::now();
+    let name = format!("{}", now.timestamp_millis());
+    let path = base_dir.join(format!("{name}.lp"));
+    let new_path = base_dir.join(format!("{name}.lp.new"));
+    let file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(&new_path)?;
+    let mut writer = BufWriter::new(file);
+
+    let mut tables = data.iter().collect::<Vec<_>>();
+    tables.sort_by_key(|(table_name, _)| *table_name);
+    for (table_name, rows) in tables {
+        write_table(
+            writer.by_ref(),
+            now,
+            precision,
+            host,
+            site,
+            table_name,
+            rows,
+        )?;
+    }
+
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&new_path, path)?;
+    Ok(())
+}
+
+/// Write one line per row of the table, with the table name as
+/// measurement. Fields without a usable value (errors, lists,
+/// non-finite floats...) are left out; rows without any field are
+/// skipped, since a line needs at least one.
+pub fn write_table<W: Write>(
+    mut file: W,
+    timestamp: DateTime<Utc>,
+    precision: Precision,
+    host: &str,
+    site: &str,
+    table_name: &InfluxTableName,
+    rows: &[InfluxRow],
+) -> Result<()> {
+    let measurement = escape_measurement(&table_name.0);
+    let timestamp = format_timestamp(timestamp, precision);
+
+    for row in rows {
+        let mut tags = row
+            .tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        tags.insert("host", host);
+        tags.insert("site", site);
+
+        let mut fields = row
+            .fields
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.0.as_str(), field_value(value.as_ref().ok()?)?))
+            })
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            continue;
+        }
+        fields.sort_by_key(|(name, _)| *name);
+
+        write!(file, "{measurement}")?;
+        for (key, value) in tags {
+            /* Empty tag values are not allowed. */
+            if !value.is_empty() {
+                write!(file, ",{}={}", escape_key(key), escape_key(value))?;
+            }
+        }
+        for (i, (key, value)) in fields.into_iter().enumerate() {
+            let sep = if i == 0 { ' ' } else { ',' };
+            write!(file, "{sep}{}={}", escape_key(key), format_field(value))?;
+        }
+        writeln!(file, " {timestamp}")?;
+    }
+
+    Ok(())
+}
+
+fn field_value(value: &Value) -> Option<FieldValue> {
+    match value {
+        Value::Integer(v) => Some(FieldValue::Integer(*v)),
+        Value::Float(v) => float_value(*v),
+        Value::Decimal(v) => float_value(v.to_f64()),
+        Value::Quantity(v) => v.normalize().ok().and_then(|v| float_value(v.0)),
+        Value::CompositeQuantity(v) => {
+            v.clone().normalize().ok().and_then(|v| float_value(v.0))
+        }
+        Value::Boolean(v) => Some(FieldValue::Boolean(*v)),
+        Value::TriState(v) => v.to_bool().map(FieldValue::Boolean),
+        Value::Age(v) => float_value(v.num_milliseconds() as f64 / 1000.0),
+        Value::Time(v) => float_value(v.timestamp_millis() as f64 / 1000.0),
+        Value::UnicodeString(v) => Some(FieldValue::String(v.clone())),
+        Value::Enum(v) => Some(FieldValue::String(v.get_value().to_string())),
+        Value::IntEnum(v) => {
+            Some(FieldValue::String(v.get_value_str().to_string()))
+        }
+        Value::MacAddress(_)
+        | Value::Ipv4Address(_)
+        | Value::Ipv6Address(_) => Some(FieldValue::String(value.to_string())),
+        Value::Option(v) => v.get_value().and_then(field_value),
+        Value::Result(v) => v.get_value().ok().and_then(field_value),
+        Value::BinaryString(_)
+        | Value::Tuple(_)
+        | Value::List(_)
+        | Value::Set(_)
+        | Value::Map(_)
+        | Value::Json(_) => None,
+    }
+}
+
+/// Line protocol has no representation for NaN and infinity.
+fn float_value(value: f64) -> Option<FieldValue> {
+    value.is_finite().then_some(FieldValue::Float(value))
+}
+
+fn format_field(value: FieldValue) -> String {
+    match value {
+        FieldValue::Integer(v) => format!("{v}i"),
+        FieldValue::Float(v) => v.to_string(),
+        FieldValue::Boolean(v) => v.to_string(),
+        FieldValue::String(v) => format!("\"{}\"", escape_string(&v)),
+    }
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>, precision: Precision) -> i64 {
+    match precision {
+        Precision::Ns => timestamp.timestamp_nanos_opt().unwrap_or_default(),
+        Precision::Ms => timestamp.timestamp_millis(),
+        Precision::S => timestamp.timestamp(),
+    }
+}
+
+/// Escape a measurement name: commas and spaces.
+fn escape_measurement(name: &str) -> String {
+    escape(name, &[',', ' '])
+}
+
+/// Escape a tag key, tag value or field key: commas, equal signs and
+/// spaces.
+fn escape_key(key: &str) -> String {
+    escape(key, &[',', '=', ' '])
+}
+
+/// Escape a string field value: double quotes and backslashes.
+fn escape_string(value: &str) -> String {
+    escape(value, &['"', '\\'])
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            c if special.contains(&c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,122 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, TimeZone, Utc};
+use expression::EvalError;
+use influxdb::{
+    write_table, InfluxFieldName, InfluxRow, InfluxTableName, Precision,
+};
+use value::{DataError, Value};
+
+fn row(
+    tags: &[(&str, &str)],
+    fields: Vec<(&str, Result<Value, EvalError>)>,
+) -> InfluxRow {
+    InfluxRow {
+        tags: tags
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        fields: fields
+            .into_iter()
+            .map(|(k, v)| (InfluxFieldName(k.to_string()), v))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+fn timestamp() -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(1_600_000_000_123).unwrap()
+}
+
+fn output(table: &str, precision: Precision, rows: &[InfluxRow]) -> String {
+    let mut buf = Vec::new();
+    write_table(
+        &mut buf,
+        timestamp(),
+        precision,
+        "h",
+        "s",
+        &InfluxTableName(table.to_string()),
+        rows,
+    )
+    .unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn field_types() {
+    let rows = [row(
+        &[("item_id", "1")],
+        vec![
+            ("octets", Ok(Value::Integer(100))),
+            ("load", Ok(Value::Float(0.5))),
+            ("up", Ok(Value::Boolean(true))),
+            ("descr", Ok(Value::UnicodeString(String::from("eth0")))),
+            ("addr", Ok(Value::Ipv4Address([10, 0, 0, 1]))),
+        ],
+    )];
+    assert_eq!(
+        output("interfaces", Precision::S, &rows),
+        "interfaces,host=h,item_id=1,site=s addr=\"10.0.0.1\",\
+         descr=\"eth0\",load=0.5,octets=100i,up=true 1600000000\n"
+    );
+}
+
+#[test]
+fn precision() {
+    let rows = [row(&[], vec![("v", Ok(Value::Integer(1)))])];
+    assert_eq!(
+        output("t", Precision::Ms, &rows),
+        "t,host=h,site=s v=1i 1600000000123\n"
+    );
+    assert_eq!(
+        output("t", Precision::Ns, &rows),
+        "t,host=h,site=s v=1i 1600000000123000000\n"
+    );
+    assert_eq!(
+        serde_json::from_str::<Precision>("\"ms\"").unwrap(),
+        Precision::Ms
+    );
+}
+
+#[test]
+fn escaping() {
+    let rows = [row(
+        &[("if name", "a,b=c d"), ("empty", "")],
+        vec![(
+            "in=out, total",
+            Ok(Value::UnicodeString(String::from("say \"hi\" \\o/"))),
+        )],
+    )];
+    assert_eq!(
+        output("my table,1", Precision::S, &rows),
+        "my\\ table\\,1,host=h,if\\ name=a\\,b\\=c\\ d,site=s \
+         in\\=out\\,\\ total=\"say \\\"hi\\\" \\\\o/\" 1600000000\n"
+    );
+}
+
+#[test]
+fn skip_unusable_values() {
+    let rows = [
+        row(
+            &[],
+            vec![
+                ("error", Err(EvalError::DataError(DataError::Missing))),
+                ("nan", Ok(Value::Float(f64::NAN))),
+                ("v", Ok(Value::Integer(1))),
+            ],
+        ),
+        /* Rows without usable fields are left out. */
+        row(
+            &[],
+            vec![("error", Err(EvalError::DataError(DataError::Missing)))],
+        ),
+    ];
+    assert_eq!(
+        output("t", Precision::S, &rows),
+        "t,host=h,site=s v=1i 1600000000\n"
+    );
+}
@@ -105,7 +105,7 @@ fn main() {
                         Template::parse(std::include_str!(
                             "../scripts/centos7-embedded-rpms.sh"
                         ))
-                        .fill(
+                        .fill_strict(
                             [
                                 ("RpmVersion", version),
                                 ("SmartAgentRpmBase64", &agent_rpm),
@@ -121,6 +121,7 @@ fn main() {
                             .cloned()
                             .collect()
                         )
+                        .unwrap()
                     );
                 }
                 false => {
@@ -133,7 +134,7 @@ fn main() {
                         Template::parse(std::include_str!(
                             "../scripts/centos7-via-repo.sh"
                         ))
-                        .fill(
+                        .fill_strict(
                             [
                                 ("RepoUrl", repo_url.as_str()),
                                 ("CaCert", ca_cert.as_str()),
@@ -147,6 +148,7 @@ fn main() {
                             .cloned()
                             .collect()
                         )
+                        .unwrap()
                     );
                 }
             }
@@ -12,7 +12,8 @@ use serde_json::Value;
 use etc::FieldSpec;
 use metrics_types::{Metric, Status, Thresholded};
 use rule_engine::selector::ValueSelector;
-use unit::{DecPrefix, Dimension, DimensionlessUnit, Unit};
+use unit::parser::parse_composite_unit;
+use unit::{DecPrefix, Dimension, DimensionlessUnit, Quantity, Unit};
 use value::{FormatOpts, Type};
 
 // abs (rel) [ warn / crit ]    { thresholds are configurable }
@@ -246,6 +247,18 @@ impl FormattedThresholdValue {
     }
 }
 
+/// Convert a value between two units, given as strings.
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    let parse = |unit: &str| {
+        parse_composite_unit(unit)
+            .map_err(|e| format!("invalid unit '{unit}': {e}"))
+    };
+    Quantity(value, parse(from)?)
+        .convert(&parse(to)?)
+        .map(|Quantity(value, _)| value)
+        .map_err(|e| format!("cannot convert {from} to {to}: {e}"))
+}
+
 fn parse_format(input: &str) -> Result<u8, String> {
     match format_string(input) {
         Ok(("", precision)) => Ok(precision),
@@ -296,6 +309,7 @@ fn format_opts_abs(spec: &FieldSpec) -> Result<FormatOpts, String> {
             None => None,
         },
         unit: spec.display_unit,
+        enum_repr: spec.enum_repr,
     })
 }
 
@@ -320,6 +334,7 @@ fn format_opts_rel(spec: &FieldSpec) -> Result<Option<FormatOpts>, String> {
             None => None,
         },
         unit: Some(unit),
+        ..FormatOpts::default()
     }))
 }
 
@@ -618,10 +633,6 @@ fn format_opts_rel(spec: &FieldSpec) -> Result<Option<FormatOpts>, String> {
 //     }
 // }
 
-// pub fn convert(value: f64, unit: Unit) -> Result<f64, UnitError> {
-//     Ok(Quantity(value, unit.normalize()).convert(&unit)?.0)
-// }
-
 // struct FloatFmt {
 //     precision: usize,
 //     val: f64,
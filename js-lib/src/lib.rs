@@ -42,16 +42,13 @@ pub fn format(
     })
 }
 
-// #[wasm_bindgen]
-// pub fn convert(value: f64, unit: &JsValue) -> f64 {
-//     throw_errors(|| {
-//         format::convert(
-//             value,
-//             JsValue::into_serde(unit).map_err(|e| e.to_string())?,
-//         )
-//         .map_err(|e| e.to_string())
-//     })
-// }
+/// Convert a value from one unit to another, eg.
+/// `convert(1024, "kB", "MB")`. Throws if either unit cannot be
+/// parsed or the dimensions differ.
+#[wasm_bindgen]
+pub fn convert(value: f64, from: &str, to: &str) -> f64 {
+    throw_errors(|| format::convert(value, from, to))
+}
 
 #[wasm_bindgen]
 pub fn metric_schemas(pkg: JsValue) -> JsValue {
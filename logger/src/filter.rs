@@ -0,0 +1,123 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// Log level filter with a default level and per-module overrides,
+/// which can be changed at runtime. The override for the longest
+/// matching module path applies, so "snmp_protocol::query" takes
+/// precedence over "snmp_protocol".
+#[derive(Debug)]
+pub struct ModuleFilter {
+    default: RwLock<LevelFilter>,
+    overrides: RwLock<BTreeMap<String, LevelFilter>>,
+}
+
+impl ModuleFilter {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default: RwLock::new(default),
+            overrides: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// The level applying to log records with the given target.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.overrides
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(module, _)| is_in_module(target, module))
+            .max_by_key(|(module, _)| module.len())
+            .map_or_else(|| self.default_level(), |(_, level)| *level)
+    }
+
+    pub fn default_level(&self) -> LevelFilter {
+        *self.default.read().unwrap()
+    }
+
+    pub fn set_default(&self, level: LevelFilter) {
+        *self.default.write().unwrap() = level;
+        self.update_max_level();
+    }
+
+    /// Set the level for a module, or remove its override if `level`
+    /// is `None`.
+    pub fn set_override(&self, module: &str, level: Option<LevelFilter>) {
+        {
+            let mut overrides = self.overrides.write().unwrap();
+            match level {
+                Some(level) => overrides.insert(module.to_string(), level),
+                None => overrides.remove(module),
+            };
+        }
+        self.update_max_level();
+    }
+
+    pub fn overrides(&self) -> BTreeMap<String, LevelFilter> {
+        self.overrides.read().unwrap().clone()
+    }
+
+    /// The most verbose level in use.
+    pub fn max_level(&self) -> LevelFilter {
+        self.overrides
+            .read()
+            .unwrap()
+            .values()
+            .fold(self.default_level(), |max, level| max.max(*level))
+    }
+
+    /* The log macros check the global max level before calling the
+     * logger, so it has to follow the most verbose override. */
+    fn update_max_level(&self) {
+        log::set_max_level(self.max_level());
+    }
+}
+
+fn is_in_module(target: &str, module: &str) -> bool {
+    target
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Logger passing records allowed by a `ModuleFilter` on to an inner
+/// logger, which should itself accept all levels.
+pub struct FilteredLogger<L> {
+    filter: Arc<ModuleFilter>,
+    inner: L,
+}
+
+impl<L: Log> FilteredLogger<L> {
+    pub fn new(filter: Arc<ModuleFilter>, inner: L) -> Self {
+        Self { filter, inner }
+    }
+}
+
+impl<L: Log + 'static> FilteredLogger<L> {
+    /// Install as the global logger.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        log::set_max_level(self.filter.max_level());
+        log::set_boxed_logger(Box::new(self))
+    }
+}
+
+impl<L: Log> Log for FilteredLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+            && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record)
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,80 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use log::{Log, Metadata, Record};
+
+/// Logger writing each record as a single-line JSON object with
+/// level, timestamp, module, target and message fields. Module
+/// allow / ignore filters work as in simplelog: a record passes if
+/// its target starts with one of the allowed modules (or none are
+/// given) and with none of the ignored ones. Levels are left to the
+/// `FilteredLogger` wrapping it.
+pub struct JsonLogger<W> {
+    writer: Mutex<W>,
+    allow: Vec<String>,
+    ignore: Vec<String>,
+}
+
+impl JsonLogger<io::Stderr> {
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+}
+
+impl<W: Write + Send> JsonLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            allow: Vec::new(),
+            ignore: Vec::new(),
+        }
+    }
+
+    pub fn add_filter_allow(&mut self, module: String) -> &mut Self {
+        self.allow.push(module);
+        self
+    }
+
+    pub fn add_filter_ignore(&mut self, module: String) -> &mut Self {
+        self.ignore.push(module);
+        self
+    }
+
+    fn skip(&self, target: &str) -> bool {
+        (!self.allow.is_empty()
+            && !self.allow.iter().any(|module| target.starts_with(module)))
+            || self.ignore.iter().any(|module| target.starts_with(module))
+    }
+}
+
+/// The JSON representation of a log record.
+pub fn record_to_json(record: &Record) -> serde_json::Value {
+    serde_json::json!({
+        "level": record.level().as_str().to_lowercase(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "module": record.module_path().unwrap_or_else(|| record.target()),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+}
+
+impl<W: Write + Send> Log for JsonLogger<W> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        !self.skip(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let mut writer = self.writer.lock().unwrap();
+            let _ = writeln!(writer, "{}", record_to_json(record));
+        }
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
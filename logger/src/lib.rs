@@ -3,9 +3,18 @@
  ******************************************************************************/
 
 use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use serde::{Deserialize, Serialize};
 
+mod filter;
+mod json;
+
+pub use filter::{FilteredLogger, ModuleFilter};
+pub use json::{record_to_json, JsonLogger};
+
+static FORMAT: AtomicU8 = AtomicU8::new(LogFormat::Plain as u8);
+
 #[derive(
     Serialize,
     Deserialize,
@@ -35,13 +44,77 @@ impl fmt::Display for Verbosity {
     }
 }
 
+/// Output format for the log macros.
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// "Warning: message" lines.
+    #[default]
+    Plain = 0,
+    /// Single-line JSON objects with level, message, timestamp,
+    /// module and target fields.
+    Json = 1,
+}
+
+/// Set the output format of the log macros for the whole process.
+pub fn set_format(format: LogFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn format() -> LogFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        1 => LogFormat::Json,
+        _ => LogFormat::Plain,
+    }
+}
+
+/// Write a log line to stderr in the configured format. Verbosity
+/// filtering is done by the macros.
+#[doc(hidden)]
+pub fn emit(
+    level: Verbosity,
+    target: Option<&str>,
+    module: &str,
+    args: fmt::Arguments,
+) {
+    match format() {
+        LogFormat::Plain => match target {
+            Some(target) => eprintln!("{level} [{target}]: {args}"),
+            None => eprintln!("{level}: {args}"),
+        },
+        LogFormat::Json => eprintln!(
+            "{}",
+            serde_json::json!({
+                "level": level,
+                "message": args.to_string(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "module": module,
+                "target": target,
+            })
+        ),
+    }
+}
+
+/* The macros take an optional `target: "name"` first argument, as
+ * in the log crate, to identify the emitting plugin. */
+
 #[macro_export]
 macro_rules! log_warning {
+    (target: $target:expr, $verbosity:expr, $($arg:tt)+) => {
+	if let Some(v) = $verbosity {
+	    if v >= logger::Verbosity::Warning {
+		logger::emit(logger::Verbosity::Warning, Some($target),
+			     module_path!(), format_args!($($arg)*))
+	    }
+	}
+    };
     ($verbosity:expr,$($arg:tt)+) => {
 	if let Some(v) = $verbosity {
 	    if v >= logger::Verbosity::Warning {
-		eprint!("Warning: ");
-		eprintln!($($arg)*)
+		logger::emit(logger::Verbosity::Warning, None, module_path!(),
+			     format_args!($($arg)*))
 	    }
 	}
     }
@@ -49,11 +122,19 @@ macro_rules! log_warning {
 
 #[macro_export]
 macro_rules! log_info {
+    (target: $target:expr, $verbosity:expr, $($arg:tt)+) => {
+	if let Some(v) = $verbosity {
+	    if v >= logger::Verbosity::Info {
+		logger::emit(logger::Verbosity::Info, Some($target),
+			     module_path!(), format_args!($($arg)*))
+	    }
+	}
+    };
     ($verbosity:expr,$($arg:tt)+) => {
 	if let Some(v) = $verbosity {
 	    if v >= logger::Verbosity::Info {
-		eprint!("Info: ");
-		eprintln!($($arg)*)
+		logger::emit(logger::Verbosity::Info, None, module_path!(),
+			     format_args!($($arg)*))
 	    }
 	}
     }
@@ -61,11 +142,19 @@ macro_rules! log_info {
 
 #[macro_export]
 macro_rules! log_debug {
+    (target: $target:expr, $verbosity:expr, $($arg:tt)+) => {
+	if let Some(v) = $verbosity {
+	    if v >= logger::Verbosity::Debug {
+		logger::emit(logger::Verbosity::Debug, Some($target),
+			     module_path!(), format_args!($($arg)*))
+	    }
+	}
+    };
     ($verbosity:expr,$($arg:tt)+) => {
 	if let Some(v) = $verbosity {
 	    if v >= logger::Verbosity::Debug {
-		eprint!("Debug: ");
-		eprintln!($($arg)*)
+		logger::emit(logger::Verbosity::Debug, None, module_path!(),
+			     format_args!($($arg)*))
 	    }
 	}
     }
@@ -0,0 +1,97 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use log::{Level, Log, Record};
+use logger::JsonLogger;
+
+/// Writer collecting the output in a shared buffer.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn lines(&self) -> Vec<serde_json::Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn log(logger: &impl Log, level: Level, target: &str, message: &str) {
+    logger.log(
+        &Record::builder()
+            .level(level)
+            .target(target)
+            .module_path(Some(target))
+            .args(format_args!("{}", message))
+            .build(),
+    );
+}
+
+#[test]
+fn record_structure() {
+    let buffer = Buffer::default();
+    let logger = JsonLogger::new(buffer.clone());
+    log(
+        &logger,
+        Level::Warn,
+        "snmp_protocol::query",
+        "walk \"timed\" out",
+    );
+
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 1);
+    let record = lines[0].as_object().unwrap();
+    assert_eq!(record["level"], "warn");
+    assert_eq!(record["module"], "snmp_protocol::query");
+    assert_eq!(record["target"], "snmp_protocol::query");
+    assert_eq!(record["message"], "walk \"timed\" out");
+    assert!(chrono::DateTime::parse_from_rfc3339(
+        record["timestamp"].as_str().unwrap()
+    )
+    .is_ok());
+}
+
+#[test]
+fn one_line_per_record() {
+    let buffer = Buffer::default();
+    let logger = JsonLogger::new(buffer.clone());
+    log(&logger, Level::Info, "agent", "first\nsecond");
+    log(&logger, Level::Debug, "agent", "third");
+
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["message"], "first\nsecond");
+    assert_eq!(lines[1]["level"], "debug");
+}
+
+#[test]
+fn module_filters() {
+    let buffer = Buffer::default();
+    let mut logger = JsonLogger::new(buffer.clone());
+    logger
+        .add_filter_allow(String::from("snmp_protocol"))
+        .add_filter_ignore(String::from("snmp_protocol::walk"));
+    log(&logger, Level::Info, "snmp_protocol::query", "allowed");
+    log(&logger, Level::Info, "snmp_protocol::walk", "ignored");
+    log(&logger, Level::Info, "hyper", "not allowed");
+
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["message"], "allowed");
+}
@@ -0,0 +1,86 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::{Arc, Mutex};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use logger::{FilteredLogger, ModuleFilter};
+
+/// Inner logger collecting the targets of the records it receives.
+#[derive(Default)]
+struct Collect(Mutex<Vec<String>>);
+
+impl Log for &Collect {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.lock().unwrap().push(record.target().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+fn log(logger: &impl Log, level: Level, target: &str) {
+    logger.log(
+        &Record::builder()
+            .level(level)
+            .target(target)
+            .args(format_args!("test"))
+            .build(),
+    )
+}
+
+#[test]
+fn module_overrides() {
+    let filter = ModuleFilter::new(LevelFilter::Warn);
+    filter.set_override("snmp_protocol", Some(LevelFilter::Debug));
+    filter.set_override("snmp_protocol::walk", Some(LevelFilter::Trace));
+    filter.set_override("api_protocol", Some(LevelFilter::Off));
+
+    assert_eq!(filter.level_for("agent"), LevelFilter::Warn);
+    assert_eq!(filter.level_for("snmp_protocol"), LevelFilter::Debug);
+    assert_eq!(filter.level_for("snmp_protocol::query"), LevelFilter::Debug);
+    assert_eq!(filter.level_for("snmp_protocol::walk"), LevelFilter::Trace);
+    assert_eq!(filter.level_for("snmp_protocols"), LevelFilter::Warn);
+    assert_eq!(filter.level_for("api_protocol::vmware"), LevelFilter::Off);
+    assert_eq!(filter.max_level(), LevelFilter::Trace);
+}
+
+#[test]
+fn filter_records() {
+    let collect = Collect::default();
+    let filter = Arc::new(ModuleFilter::new(LevelFilter::Info));
+    filter.set_override("snmp_protocol", Some(LevelFilter::Trace));
+    let logger = FilteredLogger::new(filter, &collect);
+
+    log(&logger, Level::Debug, "agent");
+    log(&logger, Level::Info, "agent");
+    log(&logger, Level::Trace, "snmp_protocol::query");
+    log(&logger, Level::Trace, "wmi_protocol");
+
+    assert_eq!(
+        *collect.0.lock().unwrap(),
+        vec!["agent", "snmp_protocol::query"]
+    );
+}
+
+#[test]
+fn runtime_change() {
+    let collect = Collect::default();
+    let filter = Arc::new(ModuleFilter::new(LevelFilter::Warn));
+    let logger = FilteredLogger::new(filter.clone(), &collect);
+
+    log(&logger, Level::Debug, "ssh_protocol");
+    filter.set_override("ssh_protocol", Some(LevelFilter::Debug));
+    log(&logger, Level::Debug, "ssh_protocol");
+    filter.set_override("ssh_protocol", None);
+    log(&logger, Level::Debug, "ssh_protocol");
+    filter.set_default(LevelFilter::Debug);
+    log(&logger, Level::Debug, "ssh_protocol");
+
+    assert_eq!(collect.0.lock().unwrap().len(), 2);
+    assert!(filter.overrides().is_empty());
+}
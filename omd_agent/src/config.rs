@@ -5,6 +5,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 use etc_base::{CheckId, Protocol, Tag};
 
@@ -89,6 +90,8 @@ impl<'de> Deserialize<'de> for HostConfig {
 #[serde(into = "AgentConfigVx")]
 pub struct AgentConfig {
     pub write_smartm_data: Option<AgentDataConfig>,
+    pub write_metrics: Option<AgentMetricsConfig>,
+    pub write_influx_data: Option<AgentInfluxConfig>,
     pub use_password_vault: Option<PasswordVault>,
     #[serde(default)]
     pub error_reporting: ErrorReporting,
@@ -103,6 +106,8 @@ pub struct AgentConfig {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct AgentConfigV2 {
     pub write_smartm_data: Option<AgentDataConfig>,
+    pub write_metrics: Option<AgentMetricsConfig>,
+    pub write_influx_data: Option<AgentInfluxConfig>,
     pub use_password_vault: Option<PasswordVault>,
     #[serde(default)]
     pub error_reporting: ErrorReporting,
@@ -145,6 +150,25 @@ enum AgentConfigVx {
 #[serde(default)]
 pub struct AgentDataConfig {
     pub instances: Vec<String>,
+    /// Compression of the bulk files written for the instances.
+    pub compression: elastic::Compression,
+}
+
+/// Prometheus text format output, written to one file per host.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct AgentMetricsConfig {
+    /// Output directory; defaults to `var/mnow/metrics` in the site.
+    pub path: Option<PathBuf>,
+}
+
+/// InfluxDB line protocol output, written to a new file for every run.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct AgentInfluxConfig {
+    /// Output directory; defaults to `var/mnow/influx` in the site.
+    pub path: Option<PathBuf>,
+    pub precision: influxdb::Precision,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -165,6 +189,7 @@ impl Default for AgentDataConfig {
     fn default() -> Self {
         Self {
             instances: vec![String::from("main")],
+            compression: elastic::Compression::None,
         }
     }
 }
@@ -197,6 +222,8 @@ impl From<AgentConfig> for AgentConfigV2 {
         Self {
             show_field_errors: val.show_field_errors,
             write_smartm_data: val.write_smartm_data,
+            write_metrics: val.write_metrics,
+            write_influx_data: val.write_influx_data,
             run_noninventorized_checks: val.run_noninventorized_checks,
             error_reporting: val.error_reporting,
             use_password_vault: val.use_password_vault,
@@ -210,6 +237,8 @@ impl From<AgentConfigV2> for AgentConfig {
         Self {
             show_field_errors: val.show_field_errors,
             write_smartm_data: val.write_smartm_data,
+            write_metrics: val.write_metrics,
+            write_influx_data: val.write_influx_data,
             run_noninventorized_checks: val.run_noninventorized_checks,
             error_reporting: val.error_reporting,
             use_password_vault: val.use_password_vault,
@@ -225,6 +254,8 @@ impl From<AgentConfigV1> for AgentConfigV2 {
             show_field_errors: val.show_field_errors,
             run_noninventorized_checks: false,
             error_reporting: ErrorReporting::default(),
+            write_metrics: None,
+            write_influx_data: None,
             write_smartm_data: match val.write_smartm_data {
                 true => Some(AgentDataConfig::default()),
                 false => None,
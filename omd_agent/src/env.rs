@@ -20,6 +20,8 @@ const AGENT_PATH: &str = "local/share/mnow/agent/mps";
 const PARSERS_PATH: &str = "local/share/mnow/agent/parsers";
 const CONFIG_PATH: &str = "var/mnow/config";
 const DATA_PATH: &str = "var/mnow/data";
+const METRICS_PATH: &str = "var/mnow/metrics";
+const INFLUX_PATH: &str = "var/mnow/influx";
 const CACHE_PATH: &str = "var/mnow/state";
 const PACKAGE_META_PATH: &str = "var/mnow/packages";
 pub(super) const ERRORS_PATH: &str = "tmp/mnow/cache";
@@ -46,6 +48,14 @@ pub fn get_data_path() -> Result<PathBuf> {
     Ok(omd_root()?.join(DATA_PATH))
 }
 
+pub fn get_metrics_path() -> Result<PathBuf> {
+    Ok(omd_root()?.join(METRICS_PATH))
+}
+
+pub fn get_influx_path() -> Result<PathBuf> {
+    Ok(omd_root()?.join(INFLUX_PATH))
+}
+
 pub fn get_specs_path() -> Result<PathBuf> {
     Ok(omd_root()?.join(AGENT_PATH))
 }
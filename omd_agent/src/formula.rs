@@ -7,13 +7,15 @@ use std::collections::HashMap;
 use expression::{EvalCell, EvalError, EvalResult, Expr};
 use value::{Data, DataError, Value};
 
-use etc::{FieldSpec, Source, Source2, TableSpec};
+use etc::{lookup_config_value, FieldSpec, Source, Source2, TableSpec};
 use etc_base::{FieldId, Row};
 
 use crate::context::Context;
 use crate::error::Result;
 use agent_utils::TryGetFrom;
 
+/// Calculate the rows of a table and, if a group-by is configured,
+/// its summary rows.
 pub fn calculate_table(
     ctx: &Context,
     table: &TableSpec,
@@ -26,10 +28,14 @@ pub fn calculate_table(
             Ok((field_id, field_id.try_get_from(&ctx.spec.etc.fields)?))
         })
         .collect::<Result<Vec<_>>>()?;
-    Ok(data
+    let rows = data
         .into_iter()
         .map(|row| calculate_row(&fields, row, ctx))
-        .collect())
+        .collect::<Vec<_>>();
+    Ok(match &table.group_by {
+        Some(group_by) => group_by.summarize(&rows),
+        None => rows,
+    })
 }
 
 type EvaledRow = HashMap<FieldId, std::result::Result<value::Value, EvalError>>;
@@ -107,16 +113,16 @@ fn lookup_conf(
 ) -> std::result::Result<Value, EvalError> {
     let mps = ctx.get_mps();
 
-    ctx.spec
+    let rules = ctx
+        .spec
         .etc
         .config_rules
         .get(field_id)
         .ok_or(EvalError::MissingVariable(field_id.to_string()))?
         .iter()
         .filter_map(|(mpid, confrules)| mps.contains(mpid).then_some(confrules))
-        .flatten()
-        .filter_map(|conf_rule| conf_rule.evaled_matches(row).transpose())
-        .next()
+        .flatten();
+    lookup_config_value(rules, &ctx.options.host_name, row)
         .ok_or_else(|| EvalError::MissingVariable(field_id.to_string()))
         .and_then(std::convert::identity)
 }
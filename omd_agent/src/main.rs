@@ -10,6 +10,7 @@ use std::os::unix::io::RawFd;
 use std::os::unix::process::CommandExt;
 
 use std::process::{self, Command};
+use std::sync::Arc;
 use std::time::Instant;
 
 use clap::{App, Arg};
@@ -22,15 +23,30 @@ use etc_base::{
     Annotated, CheckId, MPId, PackageName, PackageVersion, TableId, Tag,
 };
 use expression::EvalCell;
-use protocol::PluginManager;
+use protocol::{PluginManager, ProbeResult};
 
 use omd_agent::config::PasswordVault;
 use omd_agent::context::{Context, Mode, Options};
 use omd_agent::error::{Error, Result};
 use omd_agent::formula::calculate_table;
-use omd_agent::output::TableData;
+use omd_agent::output::{metrics_name, metrics_rows, TableData};
 use omd_agent::{env, omd_root};
 
+/// Dependencies whose log output is too noisy to be useful.
+const LOG_IGNORE_MODULES: &[&str] = &[
+    "serde_xml_rs",
+    "handlebars",
+    "want",
+    "mio",
+    "odbc_api",
+    "hyper_util",
+    "cookie_store",
+    "reqwest",
+    "hyper",
+    "tracing",
+    "rustls",
+];
+
 #[tokio::main]
 async fn main() {
     // console_subscriber::init();
@@ -68,8 +84,14 @@ async fn agent() -> Result<()> {
 			.help("Increase verbosity. This option can be specified multiple times. \
 				The maximum verbosity level is 3. Note that this option is NOT \
 				compatible with WATO inventory!"))
+		.arg(Arg::with_name("log-format").long("log-format").takes_value(true)
+			.possible_values(&["plain", "json"]).default_value("plain")
+			.help("Log output format: plain text or JSON lines."))
 		.arg(Arg::with_name("show-queries").long("show-queries").short("q")
 			.help("Output a list of queries instead of running them."))
+		.arg(Arg::with_name("validate").long("validate").conflicts_with("show-queries")
+			.help("Check connectivity and authentication for each protocol \
+				instead of running the queries."))
 			.get_matches();
 
     let log_level = match matches.occurrences_of("verbose") {
@@ -82,24 +104,33 @@ async fn agent() -> Result<()> {
     };
 
     // enable logging
-    if let Err(e) = simplelog::TermLogger::init(
-        log_level,
-        simplelog::ConfigBuilder::new()
-            .add_filter_ignore_str("serde_xml_rs")
-            .add_filter_ignore_str("handlebars")
-            .add_filter_ignore_str("want")
-            .add_filter_ignore_str("mio")
-            .add_filter_ignore_str("odbc_api")
-            .add_filter_ignore_str("hyper_util")
-            .add_filter_ignore_str("cookie_store")
-            .add_filter_ignore_str("reqwest")
-            .add_filter_ignore_str("hyper")
-            .add_filter_ignore_str("tracing")
-            .add_filter_ignore_str("rustls")
-            .build(),
-        simplelog::TerminalMode::Stderr,
-        simplelog::ColorChoice::Auto,
-    ) {
+    let log_init = match matches.value_of("log-format") {
+        Some("json") => {
+            logger::set_format(logger::LogFormat::Json);
+            let mut json_logger = logger::JsonLogger::stderr();
+            for module in LOG_IGNORE_MODULES {
+                json_logger.add_filter_ignore(module.to_string());
+            }
+            logger::FilteredLogger::new(
+                Arc::new(logger::ModuleFilter::new(log_level)),
+                json_logger,
+            )
+            .init()
+        }
+        _ => {
+            let mut log_config = simplelog::ConfigBuilder::new();
+            for module in LOG_IGNORE_MODULES {
+                log_config.add_filter_ignore_str(*module);
+            }
+            simplelog::TermLogger::init(
+                log_level,
+                log_config.build(),
+                simplelog::TerminalMode::Stderr,
+                simplelog::ColorChoice::Auto,
+            )
+        }
+    };
+    if let Err(e) = log_init {
         eprintln!("Error: failed to initialize logging: {}", e);
         process::exit(1);
     }
@@ -215,6 +246,10 @@ async fn agent() -> Result<()> {
         cache_path.clone(),
         vault.clone(),
     ));
+    plugin_manager.add_plugin(prometheus_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
 
     info!(
         "loaded plugins: {:?}",
@@ -366,6 +401,34 @@ async fn agent() -> Result<()> {
 
     if matches.is_present("show-queries") {
         plugin_manager.show_queries(&ctx.spec.input, &prot_queries);
+    } else if matches.is_present("validate") {
+        let results = plugin_manager
+            .validate_queries(
+                &ctx.spec.input,
+                ctx.config.protocols.clone(),
+                &prot_queries,
+            )
+            .await?;
+
+        let mut results = results.into_iter().collect::<Vec<_>>();
+        results.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut failed = false;
+        for (proto, result) in results {
+            match result {
+                ProbeResult::Ok => println!("{}: OK", proto),
+                ProbeResult::Failed(msg) => {
+                    failed = true;
+                    println!("{}: FAILED: {}", proto, msg)
+                }
+                ProbeResult::Unsupported => {
+                    println!("{}: no check available", proto)
+                }
+            }
+        }
+        if failed {
+            std::mem::drop(plugin_manager);
+            std::process::exit(2);
+        }
     } else {
         /* Run queries. */
 
@@ -531,6 +594,7 @@ async fn agent() -> Result<()> {
             for instance in &smartm_data_config.instances {
                 if let Err(e) = elastic::write_output(
                     &env::get_data_path()?.join(quote_filename(instance)),
+                    smartm_data_config.compression,
                     &ctx.options.host_name,
                     &ctx.site_name,
                     &elastic_data,
@@ -549,6 +613,119 @@ async fn agent() -> Result<()> {
             );
         }
 
+        /* Write metrics output. */
+
+        if let Some(metrics_config) = &ctx.config.agent.write_metrics {
+            let start = Instant::now();
+
+            let mut metrics_data = HashMap::new();
+
+            for (table_id, table_data) in check_data.iter() {
+                let metrics_table = match table_data {
+                    Ok((table_data, _)) => {
+                        metrics_rows(&ctx, table_id, table_data)?
+                    }
+                    Err(_) => Vec::new(),
+                };
+                metrics_data.insert(
+                    openmetrics::MetricTableName(metrics_name(&table_id.0)),
+                    metrics_table
+                        .into_iter()
+                        .map(|row| openmetrics::MetricRow {
+                            labels: row.labels,
+                            fields: row
+                                .fields
+                                .into_iter()
+                                .map(|(name, value)| {
+                                    (openmetrics::MetricFieldName(name), value)
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                );
+            }
+
+            let path = match &metrics_config.path {
+                Some(path) => path.clone(),
+                None => env::get_metrics_path()?,
+            }
+            .join(format!("{}.prom", quote_filename(&ctx.options.host_name)));
+
+            if let Err(e) = openmetrics::write_output(
+                &path,
+                &ctx.options.host_name,
+                &ctx.site_name,
+                &metrics_data,
+            ) {
+                debug!("failed to write metrics to {}: {}", path.display(), e);
+            }
+
+            let duration = Instant::now().duration_since(start);
+            info!(
+                "Benchmark: writing metrics output took {:.03}s",
+                duration.as_secs_f64()
+            );
+        }
+
+        /* Write InfluxDB output. */
+
+        if let Some(influx_config) = &ctx.config.agent.write_influx_data {
+            let start = Instant::now();
+
+            let mut influx_data = HashMap::new();
+
+            for (table_id, table_data) in check_data.iter() {
+                let influx_table = match table_data {
+                    Ok((table_data, _)) => {
+                        metrics_rows(&ctx, table_id, table_data)?
+                    }
+                    Err(_) => Vec::new(),
+                };
+                influx_data.insert(
+                    influxdb::InfluxTableName(metrics_name(&table_id.0)),
+                    influx_table
+                        .into_iter()
+                        .map(|row| influxdb::InfluxRow {
+                            tags: row.labels,
+                            fields: row
+                                .fields
+                                .into_iter()
+                                .map(|(name, value)| {
+                                    (influxdb::InfluxFieldName(name), value)
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                );
+            }
+
+            let path = match &influx_config.path {
+                Some(path) => path.clone(),
+                None => env::get_influx_path()?,
+            }
+            .join(quote_filename(&ctx.options.host_name));
+
+            if let Err(e) = influxdb::write_output(
+                &path,
+                &ctx.options.host_name,
+                &ctx.site_name,
+                influx_config.precision,
+                &influx_data,
+            ) {
+                debug!(
+                    "failed to write influx data to {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+
+            let duration = Instant::now().duration_since(start);
+            info!(
+                "Benchmark: writing influx output took {:.03}s",
+                duration.as_secs_f64()
+            );
+        }
+
         /* Write OMD output. */
 
         let start = Instant::now();
@@ -3,7 +3,7 @@
  ******************************************************************************/
 
 use chrono::SecondsFormat;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{BufWriter, Write};
 use std::path::PathBuf;
 use std::{fs, io};
@@ -16,14 +16,102 @@ use crate::problems::{get_problems, load_problems};
 
 use agent_utils::TryGetFrom;
 use etc_base::{CheckId, FieldId, TableId};
-use expression::EvalError;
+use expression::{EvalCell, EvalError};
 use query::AnnotatedQueryResult;
+use value::addr::{DisplayIpv4, DisplayIpv6, DisplayMac};
 use value::{DataError, HashableValue, Value};
 
-type EvalResult = std::result::Result<Value, EvalError>;
-type EvaluatedRow = HashMap<FieldId, EvalResult>;
+pub type EvalResult = std::result::Result<Value, EvalError>;
+pub type EvaluatedRow = HashMap<FieldId, EvalResult>;
 pub type TableData = AnnotatedQueryResult<Vec<EvaluatedRow>>;
 
+/// A row for the metrics output targets (.prom, InfluxDB).
+#[derive(Default, Debug)]
+pub struct MetricsRow {
+    /// Labels identifying the item: its id and name, and the values
+    /// of the selector fields.
+    pub labels: BTreeMap<String, String>,
+    pub fields: HashMap<String, EvalResult>,
+}
+
+/// The name of a table or field in the metrics output targets.
+pub fn metrics_name(id: &str) -> String {
+    id.to_lowercase().replace(' ', "_")
+}
+
+/// Prepare the rows of a table for the metrics output targets.
+/// Sensitive fields are masked or left out, and are never used as
+/// a label.
+pub fn metrics_rows(
+    ctx: &Context,
+    table_id: &TableId,
+    rows: &[EvaluatedRow],
+) -> Result<Vec<MetricsRow>> {
+    let table_spec = table_id.try_get_from(&ctx.spec.etc.tables)?;
+    rows.iter()
+        .map(|row| {
+            let row_vars = row
+                .iter()
+                .map(|(field_id, field_data)| {
+                    Ok((
+                        field_id
+                            .try_get_from(&ctx.spec.etc.fields)?
+                            .name
+                            .as_str(),
+                        EvalCell::new_evaluated(field_data.clone()),
+                    ))
+                })
+                .collect::<Result<_>>()?;
+
+            let mut metrics_row = MetricsRow::default();
+
+            if !table_spec.singleton {
+                let item_labels = [
+                    ("item_id", &table_spec.item_id),
+                    ("item_name", &table_spec.item_name),
+                ];
+                for (label, expr) in item_labels {
+                    let value = expr
+                        .as_ref()
+                        .map(|expr| expr.eval_in_row(Some(&row_vars), None));
+                    if let Some(Ok(value)) = value {
+                        metrics_row
+                            .labels
+                            .insert(label.to_string(), value.to_string());
+                    }
+                }
+            }
+
+            for (field_id, field_data) in row {
+                let field_spec = field_id.try_get_from(&ctx.spec.etc.fields)?;
+                if field_spec.is_excluded() {
+                    continue;
+                }
+                let name = metrics_name(&field_id.0);
+
+                /* Index columns identify the item. */
+
+                if field_spec.selector && !field_spec.sensitive {
+                    if let Ok(value) = field_data {
+                        metrics_row
+                            .labels
+                            .insert(name.clone(), value.to_string());
+                    }
+                }
+
+                metrics_row.fields.insert(
+                    name,
+                    field_data
+                        .clone()
+                        .map(|value| field_spec.output_value(value)),
+                );
+            }
+
+            Ok(metrics_row)
+        })
+        .collect()
+}
+
 pub fn write_output(
     checks: &HashMap<CheckId, HashSet<TableId>>,
     data: &HashMap<TableId, TableData>,
@@ -198,6 +286,7 @@ fn write_value<T: Write>(out: &mut T, val: &Value) -> Result<()> {
             true => write!(out, "{v}")?,
             false => write!(out, "None")?,
         },
+        Value::Decimal(v) => write!(out, "{v}")?,
         Value::Quantity(v) => match v.normalize() {
             Ok(v) => match v.0.is_finite() {
                 true => write!(out, "{}", v.0)?,
@@ -205,12 +294,24 @@ fn write_value<T: Write>(out: &mut T, val: &Value) -> Result<()> {
             },
             Err(_) => write!(out, "None")?,
         },
+        Value::CompositeQuantity(v) => match v.clone().normalize() {
+            Ok(v) => match v.0.is_finite() {
+                true => write!(out, "{}", v.0)?,
+                false => write!(out, "None")?,
+            },
+            Err(_) => write!(out, "None")?,
+        },
         Value::Enum(v) => write_str(out, v.get_value())?,
         Value::IntEnum(v) => write_str(out, v.get_value_str())?,
         Value::Boolean(v) => match v {
             true => write!(out, "True")?,
             false => write!(out, "False")?,
         },
+        Value::TriState(v) => match v.to_bool() {
+            Some(true) => write!(out, "True")?,
+            Some(false) => write!(out, "False")?,
+            None => write!(out, "None")?,
+        },
         Value::Time(v) => write_str(
             out,
             &v.to_rfc3339_opts(SecondsFormat::Micros, true).to_string(),
@@ -225,19 +326,9 @@ fn write_value<T: Write>(out: &mut T, val: &Value) -> Result<()> {
             };
             write!(out, "{}", seconds)?
         }
-        Value::MacAddress(v) => write!(
-            out,
-            "'{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}'",
-            v[0], v[1], v[2], v[3], v[4], v[5],
-        )?,
-        Value::Ipv4Address(v) => {
-            write!(out, "'{}.{}.{}.{}'", v[0], v[1], v[2], v[3])?
-        }
-        Value::Ipv6Address(v) => write!(
-            out,
-            "'{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}'",
-            v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-        )?,
+        Value::MacAddress(v) => write!(out, "'{}'", DisplayMac(v))?,
+        Value::Ipv4Address(v) => write!(out, "'{}'", DisplayIpv4(v))?,
+        Value::Ipv6Address(v) => write!(out, "'{}'", DisplayIpv6(v))?,
         Value::Option(v) => match v.get_value() {
             Some(v) => write_value(out, v)?,
             None => write!(out, "None")?,
@@ -311,19 +402,9 @@ fn write_hashable_value<T: Write>(
             true => write!(out, "True")?,
             false => write!(out, "False")?,
         },
-        HashableValue::MacAddress(v) => write!(
-            out,
-            "'{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}'",
-            v[0], v[1], v[2], v[3], v[4], v[5],
-        )?,
-        HashableValue::Ipv4Address(v) => {
-            write!(out, "'{}.{}.{}.{}'", v[0], v[1], v[2], v[3])?
-        }
-        HashableValue::Ipv6Address(v) => write!(
-            out,
-            "'{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}'",
-            v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-        )?,
+        HashableValue::MacAddress(v) => write!(out, "'{}'", DisplayMac(v))?,
+        HashableValue::Ipv4Address(v) => write!(out, "'{}'", DisplayIpv4(v))?,
+        HashableValue::Ipv6Address(v) => write!(out, "'{}'", DisplayIpv6(v))?,
         HashableValue::Option(v) => match v.get_value() {
             Some(v) => write_hashable_value(out, v)?,
             None => write!(out, "None")?,
@@ -0,0 +1,10 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+mod error;
+mod output;
+
+pub use error::{Error, Result};
+pub use output::{write_output, write_table};
+pub use output::{MetricFieldName, MetricRow, MetricTableName};
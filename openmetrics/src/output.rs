@@ -0,0 +1,200 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::error::Result;
+use expression::EvalError;
+use value::Value;
+
+/* Table and field names, as used in metric names. */
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct MetricTableName(pub String);
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub struct MetricFieldName(pub String);
+
+/// A row of a table: the labels identifying the item (item id and
+/// name, index columns) and the field values.
+#[derive(Default, Debug)]
+pub struct MetricRow {
+    pub labels: BTreeMap<String, String>,
+    pub fields: HashMap<MetricFieldName, std::result::Result<Value, EvalError>>,
+}
+
+/// A sample value: numeric values become gauges, other scalar values
+/// info metrics carrying the value in a label.
+enum Sample {
+    Gauge(f64),
+    Info(String),
+}
+
+/// Write the tables in the Prometheus text exposition format. The
+/// file is replaced atomically, so that a scraper never reads a
+/// partially written file.
+pub fn write_output(
+    path: &Path,
+    host: &str,
+    site: &str,
+    data: &HashMap<MetricTableName, Vec<MetricRow>>,
+) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let new_path = path.with_extension("prom.new");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(&new_path)?;
+    let mut writer = BufWriter::new(file);
+
+    let mut tables = data.iter().collect::<Vec<_>>();
+    tables.sort_by_key(|(table_name, _)| *table_name);
+    for (table_name, rows) in tables {
+        write_table(writer.by_ref(), host, site, table_name, rows)?;
+    }
+
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&new_path, path)?;
+    Ok(())
+}
+
+/// Write the metric families of a table. Each field becomes a family
+/// named after the table and field, with one sample per row. Fields
+/// without a usable value (errors, lists, maps...) are skipped.
+pub fn write_table<W: Write>(
+    mut file: W,
+    host: &str,
+    site: &str,
+    table_name: &MetricTableName,
+    rows: &[MetricRow],
+) -> Result<()> {
+    let mut families: BTreeMap<String, Vec<(String, f64)>> = BTreeMap::new();
+
+    for row in rows {
+        let mut labels = vec![
+            (String::from("host"), host.to_string()),
+            (String::from("site"), site.to_string()),
+        ];
+        labels.extend(
+            row.labels
+                .iter()
+                .map(|(name, value)| (label_name(name), value.clone())),
+        );
+
+        for (field_name, value) in &row.fields {
+            let name =
+                metric_name(&format!("{}_{}", table_name.0, field_name.0));
+            match value.as_ref().ok().and_then(sample) {
+                Some(Sample::Gauge(value)) => families
+                    .entry(name)
+                    .or_default()
+                    .push((format_labels(&labels, None), value)),
+                Some(Sample::Info(value)) => families
+                    .entry(format!("{name}_info"))
+                    .or_default()
+                    .push((format_labels(&labels, Some(&value)), 1.0)),
+                None => {}
+            }
+        }
+    }
+
+    for (name, samples) in families {
+        writeln!(file, "# TYPE {name} gauge")?;
+        for (labels, value) in samples {
+            writeln!(file, "{name}{{{labels}}} {}", format_value(value))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn sample(value: &Value) -> Option<Sample> {
+    match value {
+        Value::Integer(v) => Some(Sample::Gauge(*v as f64)),
+        Value::Float(v) => Some(Sample::Gauge(*v)),
+        Value::Decimal(v) => Some(Sample::Gauge(v.to_f64())),
+        Value::Quantity(v) => v.normalize().ok().map(|v| Sample::Gauge(v.0)),
+        Value::CompositeQuantity(v) => {
+            v.clone().normalize().ok().map(|v| Sample::Gauge(v.0))
+        }
+        Value::Boolean(v) => Some(Sample::Gauge(f64::from(u8::from(*v)))),
+        Value::TriState(v) => {
+            v.to_bool().map(|v| Sample::Gauge(f64::from(u8::from(v))))
+        }
+        Value::Age(v) => {
+            Some(Sample::Gauge(v.num_milliseconds() as f64 / 1000.0))
+        }
+        Value::Time(v) => {
+            Some(Sample::Gauge(v.timestamp_millis() as f64 / 1000.0))
+        }
+        Value::UnicodeString(v) => Some(Sample::Info(v.clone())),
+        Value::Enum(v) => Some(Sample::Info(v.get_value().to_string())),
+        Value::IntEnum(v) => Some(Sample::Info(v.get_value_str().to_string())),
+        Value::MacAddress(_)
+        | Value::Ipv4Address(_)
+        | Value::Ipv6Address(_) => Some(Sample::Info(value.to_string())),
+        Value::Option(v) => v.get_value().and_then(sample),
+        Value::Result(v) => v.get_value().ok().and_then(sample),
+        Value::BinaryString(_)
+        | Value::Tuple(_)
+        | Value::List(_)
+        | Value::Set(_)
+        | Value::Map(_)
+        | Value::Json(_) => None,
+    }
+}
+
+/// Metric names may only contain `[a-zA-Z0-9_:]` and may not start
+/// with a digit.
+fn metric_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == ':' {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect::<String>();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{name}"),
+        false => name,
+    }
+}
+
+/// Label names may only contain `[a-zA-Z0-9_]` and may not start
+/// with a digit.
+fn label_name(name: &str) -> String {
+    metric_name(name).replace(':', "_")
+}
+
+fn format_labels(labels: &[(String, String)], value: Option<&str>) -> String {
+    labels
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(value.map(|value| ("value", value)))
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    match value {
+        v if v.is_nan() => String::from("NaN"),
+        v if v == f64::INFINITY => String::from("+Inf"),
+        v if v == f64::NEG_INFINITY => String::from("-Inf"),
+        v => v.to_string(),
+    }
+}
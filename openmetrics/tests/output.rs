@@ -0,0 +1,130 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+
+use expression::EvalError;
+use openmetrics::{write_table, MetricFieldName, MetricRow, MetricTableName};
+use value::{DataError, Value};
+
+fn row(
+    labels: &[(&str, &str)],
+    fields: Vec<(&str, Result<Value, EvalError>)>,
+) -> MetricRow {
+    MetricRow {
+        labels: labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+        fields: fields
+            .into_iter()
+            .map(|(k, v)| (MetricFieldName(k.to_string()), v))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+fn output(table: &str, rows: &[MetricRow]) -> String {
+    let mut buf = Vec::new();
+    write_table(
+        &mut buf,
+        "h",
+        "s",
+        &MetricTableName(table.to_string()),
+        rows,
+    )
+    .unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn gauges() {
+    let rows = [
+        row(
+            &[("item_id", "1")],
+            vec![
+                ("in_octets", Ok(Value::Integer(100))),
+                ("up", Ok(Value::Boolean(true))),
+            ],
+        ),
+        row(
+            &[("item_id", "2")],
+            vec![
+                ("in_octets", Ok(Value::Float(2.5))),
+                ("up", Ok(Value::Boolean(false))),
+            ],
+        ),
+    ];
+    assert_eq!(
+        output("interfaces", &rows),
+        "# TYPE interfaces_in_octets gauge\n\
+         interfaces_in_octets{host=\"h\",site=\"s\",item_id=\"1\"} 100\n\
+         interfaces_in_octets{host=\"h\",site=\"s\",item_id=\"2\"} 2.5\n\
+         # TYPE interfaces_up gauge\n\
+         interfaces_up{host=\"h\",site=\"s\",item_id=\"1\"} 1\n\
+         interfaces_up{host=\"h\",site=\"s\",item_id=\"2\"} 0\n"
+    );
+}
+
+#[test]
+fn info_metrics() {
+    let rows = [row(
+        &[],
+        vec![
+            ("descr", Ok(Value::UnicodeString(String::from("eth0")))),
+            ("address", Ok(Value::Ipv4Address([10, 0, 0, 1]))),
+        ],
+    )];
+    assert_eq!(
+        output("interfaces", &rows),
+        "# TYPE interfaces_address_info gauge\n\
+         interfaces_address_info{host=\"h\",site=\"s\",value=\"10.0.0.1\"} 1\n\
+         # TYPE interfaces_descr_info gauge\n\
+         interfaces_descr_info{host=\"h\",site=\"s\",value=\"eth0\"} 1\n"
+    );
+}
+
+#[test]
+fn escape_label_values() {
+    let rows = [row(
+        &[("item_name", "a \"quoted\" \\ name\nwith newline")],
+        vec![("value", Ok(Value::Integer(1)))],
+    )];
+    assert_eq!(
+        output("t", &rows),
+        "# TYPE t_value gauge\n\
+         t_value{host=\"h\",site=\"s\",\
+         item_name=\"a \\\"quoted\\\" \\\\ name\\nwith newline\"} 1\n"
+    );
+}
+
+#[test]
+fn sanitize_names() {
+    let rows = [row(
+        &[("Index Column", "x")],
+        vec![("In Octets/s", Ok(Value::Integer(1)))],
+    )];
+    assert_eq!(
+        output("1 Interfaces", &rows),
+        "# TYPE _1_interfaces_in_octets_s gauge\n\
+         _1_interfaces_in_octets_s{host=\"h\",site=\"s\",\
+         index_column=\"x\"} 1\n"
+    );
+}
+
+#[test]
+fn skip_unusable_values() {
+    let rows = [row(
+        &[],
+        vec![
+            ("error", Err(EvalError::DataError(DataError::Missing))),
+            ("tuple", Ok(Value::Tuple(vec![Value::Integer(1)]))),
+            ("none", Ok(Value::Float(f64::INFINITY))),
+        ],
+    )];
+    assert_eq!(
+        output("t", &rows),
+        "# TYPE t_none gauge\n\
+         t_none{host=\"h\",site=\"s\"} +Inf\n"
+    );
+}
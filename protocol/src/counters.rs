@@ -4,18 +4,33 @@
 
 use std::io::ErrorKind;
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, sync::Mutex};
 
+use agent_utils::FileLock;
 use log::{debug, info, trace, warn};
+use serde::{Deserialize, Serialize};
 use tap::TapFallible;
 use value::{DataError, Value};
 
 type Result<T> = std::result::Result<T, std::io::Error>;
 
+/// How consecutive samples of a counter are reported.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterMode {
+    /// The increase since the previous sample.
+    Difference,
+    /// The increase per second since the previous sample.
+    Rate,
+}
+
+/// Counters stored in the counter file persist across agent runs, so
+/// that a rate can be computed on the first poll of a run.
 #[derive(Debug)]
 pub struct CounterDb {
     counter_file: PathBuf,
+    ttl: Option<Duration>,
     old_state: HashMap<String, (SystemTime, u64)>,
     new_state: Mutex<HashMap<String, (SystemTime, u64)>>,
 }
@@ -24,12 +39,27 @@ impl CounterDb {
     pub fn new(path: PathBuf) -> Self {
         Self {
             counter_file: path,
+            ttl: None,
             old_state: HashMap::new(),
             new_state: Mutex::new(HashMap::new()),
         }
     }
+    /// Set the age after which stored counters are discarded on
+    /// load; a rate over such an interval would hide everything that
+    /// happened in it. By default, counters never expire. Call before
+    /// loading.
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
     pub async fn load(path: PathBuf) -> Result<Self> {
-        let mut counters = Self::new(path);
+        Self::load_with_ttl(path, None).await
+    }
+    pub async fn load_with_ttl(
+        path: PathBuf,
+        ttl: Option<Duration>,
+    ) -> Result<Self> {
+        let mut counters = Self::new(path).with_ttl(ttl);
         counters.try_load().await?;
         Ok(counters)
     }
@@ -37,13 +67,15 @@ impl CounterDb {
         use tokio::fs;
 
         debug!("loading counterdb: {:?}", self.counter_file.display());
+        let _lock = self.lock().await?;
         self.old_state = match fs::read(&self.counter_file).await {
-            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(),            
+            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
             Err(e) => return Err(e),
             Ok(data) => serde_json::from_slice(&data)
                 .tap_err(|e| warn!("Unable to deserialize counters: {e}"))
                 .unwrap_or_default(),
         };
+        self.expire(SystemTime::now());
 
         Ok(())
     }
@@ -59,17 +91,35 @@ impl CounterDb {
         use std::{fs::File, io::BufReader};
 
         debug!("loading counterdb: {:?}", self.counter_file.display());
+        let _lock = self.blocking_lock()?;
         self.old_state = match File::open(&self.counter_file) {
             Ok(f) => serde_json::from_reader(BufReader::new(f))
                 .tap_err(|e| warn!("Unable to deserialize counters: {e}"))
                 .unwrap_or_default(),
-            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(), 
+            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
             Err(e) => return Err(e),
         };
+        self.expire(SystemTime::now());
 
         Ok(())
     }
 
+    fn expire(&mut self, now: SystemTime) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let before = self.old_state.len();
+        self.old_state.retain(|_, (then, _)| {
+            !matches!(now.duration_since(*then), Ok(age) if age > ttl)
+        });
+        if self.old_state.len() < before {
+            debug!(
+                "discarded {} expired counters",
+                before - self.old_state.len()
+            );
+        }
+    }
+
     pub fn get(&self, k: &String) -> Option<&(SystemTime, u64)> {
         self.old_state.get(k)
     }
@@ -86,11 +136,7 @@ impl CounterDb {
         let number = match self.get(&key) {
             None => Err(DataError::CounterPending),
             Some((_, old)) => {
-                if &new < old {
-                    Err(DataError::CounterOverflow)
-                } else {
-                    Ok(new - *old)
-                }
+                increase(*old, new).ok_or(DataError::CounterOverflow)
             }
         }
         .map(|num| Value::Integer(num as i64));
@@ -107,19 +153,50 @@ impl CounterDb {
         number
     }
 
+    /// Report a counter sample in the given mode.
+    pub fn sample(
+        &self,
+        key: String,
+        new: u64,
+        now: SystemTime,
+        mode: CounterMode,
+    ) -> std::result::Result<Value, DataError> {
+        match mode {
+            CounterMode::Difference => self.difference(key, new, now),
+            CounterMode::Rate => self.rate_per_second(key, new, now),
+        }
+    }
+
+    /// The per-second rate of a counter. Equivalent to
+    /// `rate_per_second`.
     pub fn counter(
         &self,
         key: String,
         new: u64,
         now: SystemTime,
+    ) -> std::result::Result<Value, DataError> {
+        self.rate_per_second(key, new, now)
+    }
+
+    /// The increase of a counter since the previous sample, divided
+    /// by the wall-clock time elapsed between the samples. If no time
+    /// has elapsed, the previous sample is kept and the rate is
+    /// pending.
+    pub fn rate_per_second(
+        &self,
+        key: String,
+        new: u64,
+        now: SystemTime,
     ) -> std::result::Result<Value, DataError> {
         let number = match self.get(&key) {
             None => Err(DataError::CounterPending),
             Some((then, old)) => {
-                match (now.duration_since(*then), &new < old) {
-                    (Ok(dur), false) => {
-                        Ok((new - old) as f64 / dur.as_secs_f64())
+                match (now.duration_since(*then), increase(*old, new)) {
+                    (Ok(dur), _) if dur.is_zero() => {
+                        self.insert(key, (*then, *old));
+                        return Err(DataError::CounterPending);
                     }
+                    (Ok(dur), Some(inc)) => Ok(inc as f64 / dur.as_secs_f64()),
                     _ => Err(DataError::CounterOverflow),
                 }
             }
@@ -133,11 +210,14 @@ impl CounterDb {
             self.get(&key),
             &number
         );
-        
+
         self.insert(key, (now, new));
         number
     }
 
+    /// Merge the new samples into the counter file. The file is
+    /// re-read under the same lock, so that samples saved by other
+    /// runs since this database was loaded are not lost.
     pub async fn save(&self) -> Result<()> {
         use tokio::{fs, io::AsyncWriteExt};
 
@@ -146,6 +226,16 @@ impl CounterDb {
             fs::create_dir_all(dir).await?;
         }
 
+        let _lock = self.lock().await?;
+        let state = match fs::read(&self.counter_file).await {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+            Ok(data) => serde_json::from_slice(&data)
+                .tap_err(|e| warn!("Unable to deserialize counters: {e}"))
+                .unwrap_or_default(),
+        };
+        let state = self.merge(state, SystemTime::now());
+
         let mut f = fs::OpenOptions::new()
             .create(true)
             .truncate(true)
@@ -153,8 +243,7 @@ impl CounterDb {
             .open(&self.counter_file)
             .await?;
 
-        f.write_all(&serde_json::to_vec(&self.new_state).unwrap())
-            .await
+        f.write_all(&serde_json::to_vec(&state).unwrap()).await
     }
 
     #[cfg(feature = "blocking")]
@@ -167,12 +256,205 @@ impl CounterDb {
             fs::create_dir_all(dir)?;
         }
 
+        let _lock = self.blocking_lock()?;
+        let state = match fs::read(&self.counter_file) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+            Ok(data) => serde_json::from_slice(&data)
+                .tap_err(|e| warn!("Unable to deserialize counters: {e}"))
+                .unwrap_or_default(),
+        };
+        let state = self.merge(state, SystemTime::now());
+
         let mut f = fs::OpenOptions::new()
             .create(true)
             .truncate(true)
             .write(true)
             .open(&self.counter_file)?;
 
-        f.write_all(&serde_json::to_vec(&self.new_state).unwrap())
+        f.write_all(&serde_json::to_vec(&state).unwrap())
+    }
+
+    /// Add the new samples to the stored state. Where both have a
+    /// sample for a counter, the most recent one is kept.
+    fn merge(
+        &self,
+        mut state: HashMap<String, (SystemTime, u64)>,
+        now: SystemTime,
+    ) -> HashMap<String, (SystemTime, u64)> {
+        if let Some(ttl) = self.ttl {
+            state.retain(|_, (then, _)| {
+                !matches!(now.duration_since(*then), Ok(age) if age > ttl)
+            });
+        }
+        for (key, (then, value)) in self.new_state.lock().unwrap().iter() {
+            match state.get(key) {
+                Some((stored, _)) if stored > then => {}
+                _ => {
+                    state.insert(key.clone(), (*then, *value));
+                }
+            }
+        }
+        state
+    }
+
+    /// Serialize access to the counter file between concurrent
+    /// agent runs sharing the cache directory.
+    async fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire_async(&self.counter_file, FileLock::DEFAULT_TIMEOUT)
+            .await
+            .map_err(std::io::Error::other)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn blocking_lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.counter_file, FileLock::DEFAULT_TIMEOUT)
+            .map_err(std::io::Error::other)
+    }
+}
+
+/// The increase of a counter between two samples. A decrease is taken
+/// to be a wrap of a 32-bit or 64-bit counter if the previous sample
+/// was in the upper half of its range. Otherwise the counter was reset
+/// (eg. by a reboot) and the increase is unknown.
+fn increase(old: u64, new: u64) -> Option<u64> {
+    if new >= old {
+        return Some(new - old);
+    }
+
+    let max = match old <= u32::MAX as u64 {
+        true => u32::MAX as u64,
+        false => u64::MAX,
+    };
+    (old > max / 2).then(|| max - old + new + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    use value::{DataError, Value};
+
+    use super::{increase, CounterDb, CounterMode};
+
+    #[test]
+    fn counter_wraps() {
+        assert_eq!(increase(10, 15), Some(5));
+        assert_eq!(increase(u32::MAX as u64 - 4, 5), Some(10));
+        assert_eq!(increase(u64::MAX - 4, 5), Some(10));
+        /* Resets are not mistaken for wraps. */
+        assert_eq!(increase(1000, 5), None);
+        assert_eq!(increase(u32::MAX as u64 + 1000, 5), None);
+    }
+
+    #[test]
+    fn expire_on_load() {
+        let now = SystemTime::now();
+        let path = PathBuf::from("/nonexistent/counters.json");
+        let mut db =
+            CounterDb::new(path).with_ttl(Some(Duration::from_secs(600)));
+        db.old_state
+            .insert("fresh".to_string(), (now - Duration::from_secs(60), 100));
+        db.old_state.insert(
+            "stale".to_string(),
+            (now - Duration::from_secs(3600), 100),
+        );
+        db.expire(now);
+
+        assert_eq!(
+            db.counter("fresh".to_string(), 160, now),
+            Ok(Value::Float(1.0))
+        );
+        assert_eq!(
+            db.counter("stale".to_string(), 160, now),
+            Err(DataError::CounterPending)
+        );
+    }
+
+    #[test]
+    fn no_expiry_by_default() {
+        let now = SystemTime::now();
+        let path = PathBuf::from("/nonexistent/counters.json");
+        let mut db = CounterDb::new(path);
+        db.old_state.insert(
+            "old".to_string(),
+            (now - Duration::from_secs(7 * 24 * 3600), 100),
+        );
+        db.expire(now);
+
+        assert!(db.get(&"old".to_string()).is_some());
+    }
+
+    #[tokio::test]
+    async fn concurrent_saves() {
+        let dir = std::env::temp_dir()
+            .join(format!("counters_concurrent_{}", std::process::id()));
+        let path = dir.join("counters.json");
+        let then = SystemTime::now();
+        let now = then + Duration::from_secs(10);
+
+        let db = CounterDb::new(path.clone());
+        db.insert("a".to_string(), (then, 100));
+        db.insert("b".to_string(), (then, 100));
+        db.save().await.unwrap();
+
+        /* Two runs load the same state and each sample one counter. */
+        let db1 = CounterDb::load(path.clone()).await.unwrap();
+        let db2 = CounterDb::load(path.clone()).await.unwrap();
+        db1.insert("a".to_string(), (now, 150));
+        db2.insert("b".to_string(), (now, 200));
+        db1.save().await.unwrap();
+        db2.save().await.unwrap();
+
+        let db = CounterDb::load(path).await.unwrap();
+        assert_eq!(db.get(&"a".to_string()), Some(&(now, 150)));
+        assert_eq!(db.get(&"b".to_string()), Some(&(now, 200)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn merge_keeps_latest() {
+        let then = SystemTime::now();
+        let now = then + Duration::from_secs(10);
+        let path = PathBuf::from("/nonexistent/counters.json");
+        let db = CounterDb::new(path).with_ttl(Some(Duration::from_secs(600)));
+        db.insert("old".to_string(), (then, 100));
+        db.insert("new".to_string(), (now, 100));
+
+        let stored = [
+            ("old".to_string(), (now, 200)),
+            ("new".to_string(), (then, 200)),
+            ("stale".to_string(), (now - Duration::from_secs(3600), 200)),
+        ];
+        let state = db.merge(stored.into_iter().collect(), now);
+        assert_eq!(state.get("old"), Some(&(now, 200)));
+        assert_eq!(state.get("new"), Some(&(now, 100)));
+        assert_eq!(state.get("stale"), None);
+    }
+
+    #[test]
+    fn rate_without_elapsed_time() {
+        let then = SystemTime::now();
+        let path = PathBuf::from("/nonexistent/counters.json");
+        let mut db = CounterDb::new(path);
+        db.old_state.insert("key".to_string(), (then, 100));
+
+        assert_eq!(
+            db.sample("key".to_string(), 150, then, CounterMode::Rate),
+            Err(DataError::CounterPending)
+        );
+        /* The previous sample is kept for the next run. */
+        assert_eq!(db.new_state.lock().unwrap().get("key"), Some(&(then, 100)));
+
+        let now = then + Duration::from_secs(10);
+        assert_eq!(
+            db.sample("key".to_string(), 150, now, CounterMode::Rate),
+            Ok(Value::Float(5.0))
+        );
+        assert_eq!(
+            db.sample("key".to_string(), 150, now, CounterMode::Difference),
+            Ok(Value::Integer(50))
+        );
     }
 }
@@ -14,4 +14,8 @@ pub struct DataFieldSpec {
     // Compat: this is now part of query
     //pub join_key: Option<JoinKey>,
     pub input_type: Type,
+    /// Sensitive values are hidden in logs. They are still available
+    /// to queries and formulas.
+    #[serde(default)]
+    pub sensitive: bool,
 }
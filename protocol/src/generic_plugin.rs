@@ -17,6 +17,7 @@ use serde_json::value::RawValue;
 use super::error::{DataTableError, Error, ErrorOrigin, Result};
 use super::input::Input;
 use super::local_plugin::LocalPlugin;
+use super::retry::Idempotency;
 
 pub type DataMap = HashMap<
     DataTableId,
@@ -38,6 +39,54 @@ pub trait GenericPlugin {
 
     async fn load_inputs(&self, input: Vec<Box<RawValue>>) -> Result<Input>;
 
+    /// See `LocalPlugin::warmup`.
+    async fn warmup(
+        &self,
+        _input: &(dyn Any + Send + Sync),
+        _config: &RawValue,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// See `LocalPlugin::probe`.
+    async fn probe(
+        &self,
+        _input: &(dyn Any + Send + Sync),
+        _config: &RawValue,
+    ) -> Option<Result<()>> {
+        None
+    }
+
+    /// See `LocalPlugin::shutdown`.
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// See `LocalPlugin::idempotency`. Plugins that cannot tell are
+    /// never retried.
+    fn idempotency(
+        &self,
+        _input: &(dyn Any + Send + Sync),
+        _query: &ProtoQueryMap,
+    ) -> Idempotency {
+        Idempotency::NonIdempotent
+    }
+
+    /// See `LocalPlugin::supports_collect_all`.
+    fn supports_collect_all(&self, _input: &(dyn Any + Send + Sync)) -> bool {
+        false
+    }
+
+    /// See `LocalPlugin::collect_all`.
+    async fn collect_all(
+        &self,
+        input: &(dyn Any + Send + Sync),
+        config: &RawValue,
+        query: &ProtoQueryMap,
+    ) -> Result<ProtoDataMap> {
+        self.run_queries(input, config, query).await
+    }
+
     fn show_queries(
         &self,
         input: &(dyn Any + Send + Sync),
@@ -86,10 +135,88 @@ impl<T: LocalPlugin + 'static> GenericPlugin for T {
             data_fields: self
                 .get_fields(&input)
                 .map_err(|e| Error::Plugin(self.protocol(), Box::new(e)))?,
-            handle: Box::new(input),
+            handle: Arc::new(input),
+        })
+    }
+
+    async fn warmup(
+        &self,
+        input: &(dyn Any + Send + Sync),
+        config: &RawValue,
+    ) -> Result<()> {
+        let input = input
+            .downcast_ref()
+            .ok_or_else(|| Error::WrongInput(self.protocol()))?;
+        let config = serde_json::from_str(config.get())
+            .map_err(|e| Error::ConfigFormat(self.protocol(), e))?;
+        self.warmup(input, &config)
+            .await
+            .map_err(|e| Error::Plugin(self.protocol(), Box::new(e)))
+    }
+
+    async fn probe(
+        &self,
+        input: &(dyn Any + Send + Sync),
+        config: &RawValue,
+    ) -> Option<Result<()>> {
+        let input = match input.downcast_ref() {
+            Some(input) => input,
+            None => return Some(Err(Error::WrongInput(self.protocol()))),
+        };
+        let config = match serde_json::from_str(config.get()) {
+            Ok(config) => config,
+            Err(e) => {
+                return Some(Err(Error::ConfigFormat(self.protocol(), e)))
+            }
+        };
+        self.probe(input, &config).await.map(|res| {
+            res.map_err(|e| Error::Plugin(self.protocol(), Box::new(e)))
         })
     }
 
+    async fn shutdown(&self) -> Result<()> {
+        self.shutdown()
+            .await
+            .map_err(|e| Error::Plugin(self.protocol(), Box::new(e)))
+    }
+
+    fn idempotency(
+        &self,
+        input: &(dyn Any + Send + Sync),
+        query: &ProtoQueryMap,
+    ) -> Idempotency {
+        match input.downcast_ref() {
+            Some(input) => self.idempotency(input, query),
+            None => Idempotency::NonIdempotent,
+        }
+    }
+
+    fn supports_collect_all(&self, input: &(dyn Any + Send + Sync)) -> bool {
+        match input.downcast_ref() {
+            Some(input) => self.supports_collect_all(input),
+            None => false,
+        }
+    }
+
+    async fn collect_all(
+        &self,
+        input: &(dyn Any + Send + Sync),
+        config: &RawValue,
+        query: &ProtoQueryMap,
+    ) -> Result<ProtoDataMap> {
+        let input = input
+            .downcast_ref()
+            .ok_or_else(|| Error::WrongInput(self.protocol()))?;
+        let config = serde_json::from_str(config.get())
+            .map_err(|e| Error::ConfigFormat(self.protocol(), e))?;
+
+        let result = self
+            .collect_all(input, &config, query)
+            .await
+            .map_err(|e| Error::Plugin(self.protocol(), Box::new(e)))?;
+        Ok(make_data_map::<T>(result))
+    }
+
     fn show_queries(
         &self,
         input: &(dyn Any + Send + Sync),
@@ -3,18 +3,25 @@
  ******************************************************************************/
 
 use std::{
+    collections::{BTreeMap, HashMap},
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use agent_utils::ip_lookup_one;
+use etc_base::{ProtoDataFieldId, ProtoDataTableId, ProtoRow};
 use reqwest::{
     cookie::Jar,
-    header::{HeaderName, HeaderValue},
+    header::{HeaderMap, HeaderName, HeaderValue},
+    RequestBuilder, StatusCode,
 };
 use reqwest::{Certificate, Client};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use value::{Type, Value};
+
+use crate::{DataFieldSpec, DataTableSpec};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -166,3 +173,301 @@ impl Config {
         })
     }
 }
+
+/// The protocol data table in which HTTP-based plugins expose their
+/// request metrics.
+pub const REQUEST_STATS_TABLE: &str = "http_request_stats";
+
+const REQUEST_STATS_FIELDS: [(&str, Type); 7] = [
+    ("endpoint", Type::UnicodeString),
+    ("requests", Type::Integer),
+    ("failures", Type::Integer),
+    ("avg_latency", Type::Float),
+    ("max_latency", Type::Float),
+    ("bytes", Type::Integer),
+    ("last_status", Type::Integer),
+];
+
+/// Per-endpoint request metrics, shared between the requests of a
+/// plugin run. Endpoints are identified by method and url path.
+#[derive(Default, Debug)]
+pub struct RequestStats(Mutex<HashMap<String, EndpointStats>>);
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Clone, Debug)]
+pub struct EndpointStats {
+    pub requests: u64,
+    /// Requests that failed to complete or returned a non-success
+    /// status code.
+    pub failures: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+    pub bytes: u64,
+    pub last_status: Option<u16>,
+    pub statuses: BTreeMap<u16, u64>,
+}
+
+/// A response read in full by `RequestStats::send`.
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl RequestStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send a request and read the full response body, recording
+    /// latency, status and response size for the endpoint.
+    pub async fn send(
+        &self,
+        request: RequestBuilder,
+    ) -> reqwest::Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let endpoint = format!("{} {}", request.method(), request.url().path());
+
+        let start = Instant::now();
+        let result = match client.execute(request).await {
+            Ok(response) => {
+                let status = response.status();
+                let headers = response.headers().clone();
+                response.bytes().await.map(|body| Response {
+                    status,
+                    headers,
+                    body: Vec::from(body),
+                })
+            }
+            Err(e) => Err(e),
+        };
+
+        match &result {
+            Ok(response) => self.record(
+                &endpoint,
+                Some(response.status),
+                start.elapsed(),
+                response.body.len() as u64,
+            ),
+            Err(e) => self.record(&endpoint, e.status(), start.elapsed(), 0),
+        }
+
+        result
+    }
+
+    pub fn record(
+        &self,
+        endpoint: &str,
+        status: Option<StatusCode>,
+        latency: Duration,
+        bytes: u64,
+    ) {
+        let mut stats = self.0.lock().unwrap();
+        let stats = stats.entry(endpoint.to_string()).or_default();
+        stats.requests += 1;
+        if !status.is_some_and(|s| s.is_success()) {
+            stats.failures += 1;
+        }
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+        stats.bytes += bytes;
+        stats.last_status = status.map(|s| s.as_u16());
+        if let Some(status) = status {
+            *stats.statuses.entry(status.as_u16()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn get(&self, endpoint: &str) -> Option<EndpointStats> {
+        self.0.lock().unwrap().get(endpoint).cloned()
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, EndpointStats> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn table_id() -> ProtoDataTableId {
+        ProtoDataTableId(REQUEST_STATS_TABLE.to_string())
+    }
+
+    /// Field ids are prefixed with the table name, to avoid clashes
+    /// with the plugin's own fields.
+    fn field_id(name: &str) -> ProtoDataFieldId {
+        ProtoDataFieldId(format!("{REQUEST_STATS_TABLE}.{name}"))
+    }
+
+    /// The spec of the request stats table, keyed by endpoint.
+    pub fn table_spec() -> (ProtoDataTableId, DataTableSpec) {
+        (
+            Self::table_id(),
+            DataTableSpec {
+                name: REQUEST_STATS_TABLE.to_string(),
+                singleton: false,
+                keys: [Self::field_id("endpoint")].into_iter().collect(),
+                fields: REQUEST_STATS_FIELDS
+                    .iter()
+                    .map(|(name, _)| Self::field_id(name))
+                    .collect(),
+            },
+        )
+    }
+
+    /// The specs of the request stats table's fields.
+    pub fn field_specs() -> HashMap<ProtoDataFieldId, DataFieldSpec> {
+        REQUEST_STATS_FIELDS
+            .iter()
+            .map(|(name, input_type)| {
+                (
+                    Self::field_id(name),
+                    DataFieldSpec {
+                        name: name.to_string(),
+                        input_type: input_type.clone(),
+                        sensitive: false,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Metrics as rows of the request stats table.
+    pub fn rows(&self) -> Vec<ProtoRow> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(endpoint, stats)| {
+                [
+                    ("endpoint", Value::UnicodeString(endpoint.clone())),
+                    ("requests", Value::Integer(stats.requests as i64)),
+                    ("failures", Value::Integer(stats.failures as i64)),
+                    ("avg_latency", Value::Float(stats.avg_latency())),
+                    (
+                        "max_latency",
+                        Value::Float(stats.max_latency.as_secs_f64()),
+                    ),
+                    ("bytes", Value::Integer(stats.bytes as i64)),
+                ]
+                .into_iter()
+                .map(|(k, v)| (Self::field_id(k), Ok(v)))
+                .chain(stats.last_status.map(|s| {
+                    (
+                        Self::field_id("last_status"),
+                        Ok(Value::Integer(s as i64)),
+                    )
+                }))
+                .collect()
+            })
+            .collect()
+    }
+}
+
+impl EndpointStats {
+    /// Average latency in seconds.
+    pub fn avg_latency(&self) -> f64 {
+        match self.requests {
+            0 => 0.0,
+            n => self.total_latency.as_secs_f64() / n as f64,
+        }
+    }
+}
+
+impl Response {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{RequestStats, REQUEST_STATS_TABLE};
+
+    /// Serve a single canned response on a local port.
+    async fn serve_once(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn successful_request() {
+        let url = serve_once("200 OK", "hello").await;
+        let stats = RequestStats::new();
+        let client = reqwest::Client::new();
+        let response = stats
+            .send(client.get(format!("{url}/api/v1")))
+            .await
+            .unwrap();
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(response.text(), "hello");
+
+        let endpoint = stats.get("GET /api/v1").unwrap();
+        assert_eq!(endpoint.requests, 1);
+        assert_eq!(endpoint.failures, 0);
+        assert_eq!(endpoint.bytes, 5);
+        assert_eq!(endpoint.last_status, Some(200));
+        assert_eq!(endpoint.statuses.get(&200), Some(&1));
+        assert!(endpoint.max_latency > std::time::Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn failed_request() {
+        let url = serve_once("500 Internal Server Error", "oops").await;
+        let stats = RequestStats::new();
+        let client = reqwest::Client::new();
+        let response =
+            stats.send(client.post(format!("{url}/api"))).await.unwrap();
+        assert_eq!(response.status.as_u16(), 500);
+
+        /* Nothing listens on the port anymore. */
+        assert!(stats.send(client.post(format!("{url}/api"))).await.is_err());
+
+        let endpoint = stats.get("POST /api").unwrap();
+        assert_eq!(endpoint.requests, 2);
+        assert_eq!(endpoint.failures, 2);
+        assert_eq!(endpoint.bytes, 4);
+        assert_eq!(endpoint.last_status, None);
+        assert_eq!(endpoint.statuses.get(&500), Some(&1));
+    }
+
+    #[test]
+    fn stats_table() {
+        let stats = RequestStats::new();
+        stats.record("GET /a", None, std::time::Duration::ZERO, 0);
+        let (table_id, table) = RequestStats::table_spec();
+        assert_eq!(table_id.0, REQUEST_STATS_TABLE);
+
+        let fields = RequestStats::field_specs();
+        assert!(table.keys.is_subset(&table.fields));
+        assert!(table.fields.iter().all(|field| fields.contains_key(field)));
+
+        /* Rows only hold the table's fields. */
+        let rows = stats.rows();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].keys().all(|field| table.fields.contains(field)));
+    }
+}
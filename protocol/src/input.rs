@@ -4,15 +4,17 @@
 
 use std::any::Any;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use etc_base::{ProtoDataFieldId, ProtoDataTableId};
 
 use super::data_field::DataFieldSpec;
 use super::data_table::DataTableSpec;
 
-#[derive(Debug)]
+/// The loaded input of a protocol. Clones share the plugin handle.
+#[derive(Clone, Debug)]
 pub struct Input {
-    pub handle: Box<dyn Any + Send + Sync>,
+    pub handle: Arc<dyn Any + Send + Sync>,
     pub data_tables: HashMap<ProtoDataTableId, DataTableSpec>,
     pub data_fields: HashMap<ProtoDataFieldId, DataFieldSpec>,
 }
@@ -19,13 +19,17 @@ pub mod auth;
 pub mod http;
 
 mod input;
+mod probe;
+mod redact;
+mod retry;
+mod validation;
 #[cfg(feature = "rpc")]
 mod remote_plugin;
 #[cfg(feature = "rpc")]
 pub mod service;
 
 #[cfg(feature = "tokio")]
-pub use counters::CounterDb;
+pub use counters::{CounterDb, CounterMode};
 
 pub use data_field::DataFieldSpec;
 pub use data_table::DataTableSpec;
@@ -33,7 +37,10 @@ pub use error::{DataTableError, Error, ErrorCategory, ErrorOrigin, Result};
 pub use generic_plugin::{DataMap, GenericPlugin, ProtoDataMap};
 pub use input::Input;
 pub use local_plugin::LocalPlugin;
-pub use plugin_manager::PluginManager;
+pub use plugin_manager::{PluginManager, Timings};
+pub use probe::ProbeResult;
+pub use redact::{RedactedRow, REDACTED};
+pub use retry::{Idempotency, RetryPolicy};
 #[cfg(feature = "rpc")]
 pub use remote_plugin::RemotePlugin;
 #[cfg(feature = "rpc")]
@@ -41,5 +48,6 @@ pub use service::{
     ConfigRef, InputRef, ProtoJsonDataMap, ProtocolHandler, ProtocolProto,
     ProtocolRequest, ProtocolService, ProtocolServiceStub,
 };
+pub use validation::{ValidationError, ValidationPolicy};
 // mod config
 //pub use config::HostConfig;
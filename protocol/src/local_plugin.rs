@@ -15,6 +15,7 @@ use etc_base::{ProtoQueryMap, ProtoRow};
 
 use super::data_field::DataFieldSpec;
 use super::data_table::DataTableSpec;
+use super::retry::Idempotency;
 
 /* Plugin interface */
 
@@ -47,6 +48,73 @@ pub trait LocalPlugin: Send + Sync {
 
     /* Query API. */
 
+    /// Called once per run, before `run_queries`, to prefetch data
+    /// shared by all queries for the host (eg. a session or an index
+    /// of resource ids). Plugins must not depend on it: if warmup
+    /// fails, the error is logged and queries are run regardless,
+    /// fetching what they need themselves.
+    async fn warmup(
+        &self,
+        _input: &Self::Input,
+        _config: &Self::Config,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Check that the host is reachable and accepts the configured
+    /// credentials, with the cheapest request the protocol allows
+    /// (eg. a single get or a login), without collecting data.
+    /// Returns `None` if the plugin has no such check.
+    async fn probe(
+        &self,
+        _input: &Self::Input,
+        _config: &Self::Config,
+    ) -> Option<Result<(), Self::Error>> {
+        None
+    }
+
+    /// Called once when the agent shuts down, to close cached sessions
+    /// and persist state deterministically rather than relying on
+    /// `Drop`.
+    async fn shutdown(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Whether running the query has no side effects, so that it may
+    /// be retried after a failure. Plugins that only read (eg. SNMP
+    /// gets) should override this; others are never retried.
+    fn idempotency(
+        &self,
+        _input: &Self::Input,
+        _query: &ProtoQueryMap,
+    ) -> Idempotency {
+        Idempotency::NonIdempotent
+    }
+
+    /// Whether the plugin implements `collect_all`.
+    fn supports_collect_all(&self, _input: &Self::Input) -> bool {
+        false
+    }
+
+    /// Collect all tables requested for a host in one call, setting up
+    /// the connection and authenticating once and fetching the tables
+    /// concurrently. When supported, this is called instead of
+    /// `warmup` and `run_queries`.
+    async fn collect_all(
+        &self,
+        input: &Self::Input,
+        config: &Self::Config,
+        query: &ProtoQueryMap,
+    ) -> Result<
+        HashMap<
+            ProtoDataTableId,
+            AnnotatedResult<Vec<ProtoRow>, Self::DTWarning, Self::DTError>,
+        >,
+        Self::Error,
+    > {
+        self.run_queries(input, config, query).await
+    }
+
     fn show_queries(
         &self,
         input: &Self::Input,
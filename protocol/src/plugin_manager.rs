@@ -3,28 +3,72 @@
  ******************************************************************************/
 
 // use log::debug;
+use log::{log_enabled, trace, warn, Level};
 use serde_json::value::RawValue;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use etc_base::{DataFieldId, DataTableId, Protocol, QueryMap};
+use etc_base::{DataFieldId, DataTableId, ProtoQueryMap, Protocol, QueryMap};
 
 use super::error::{DataTableError, Error, ErrorOrigin, Result};
 use super::generic_plugin::{DataMap, GenericPlugin};
 use super::input::Input;
 use super::local_plugin::LocalPlugin;
+use super::probe::ProbeResult;
+use super::redact::RedactedRow;
+use super::retry::RetryPolicy;
+use super::validation::{validate_table, ValidationPolicy};
+
+/// Time spent fetching each data table, from the start of the
+/// protocol request until the plugin returned. This excludes plugin
+/// warmup and formula evaluation. Tables requested in one batch
+/// share the batch's time, unless table timing is enabled.
+pub type Timings = HashMap<DataTableId, Duration>;
 
 pub struct PluginManager {
     plugins: HashMap<Protocol, Box<dyn GenericPlugin + Send + Sync>>,
+    validation: HashMap<Protocol, ValidationPolicy>,
+    retry: RetryPolicy,
+    table_timing: bool,
 }
 
 impl PluginManager {
     pub fn new() -> Self {
         Self {
             plugins: HashMap::new(),
+            validation: HashMap::new(),
+            retry: RetryPolicy::default(),
+            table_timing: false,
         }
     }
 
+    /// Set how output of a protocol's plugin is checked against the
+    /// data table specs. Protocols without a policy are not checked.
+    pub fn set_validation_policy(
+        &mut self,
+        protocol: Protocol,
+        policy: ValidationPolicy,
+    ) {
+        self.validation.insert(protocol, policy);
+    }
+
+    /// Set how often failing plugin runs are retried. Queries the
+    /// plugin declares non-idempotent are never retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry = policy;
+    }
+
+    /// Request each data table separately, so that timings are
+    /// attributed to individual tables instead of being shared by all
+    /// tables of a protocol. Meant for troubleshooting slow hosts, as
+    /// it gives up any batching a plugin does across tables, including
+    /// bulk collection.
+    pub fn set_table_timing(&mut self, enabled: bool) {
+        self.table_timing = enabled;
+    }
+
     pub fn add_plugin<T: GenericPlugin + Send + Sync + 'static>(
         &mut self,
         plugin: T,
@@ -36,6 +80,19 @@ impl PluginManager {
         self.plugins.remove(proto);
     }
 
+    /// Give all plugins a chance to close sessions and persist state
+    /// before the agent exits. Failures are logged and do not keep
+    /// the other plugins from shutting down.
+    pub async fn shutdown(&self) {
+        let mut protos: Vec<_> = self.plugins.keys().collect();
+        protos.sort();
+        for proto in protos {
+            if let Err(e) = self.plugins[proto].shutdown().await {
+                warn!("{proto}: shutdown failed: {e}");
+            }
+        }
+    }
+
     pub fn get_protocols(&self) -> HashSet<Protocol> {
         self.plugins.keys().cloned().collect()
     }
@@ -100,13 +157,64 @@ impl PluginManager {
         }
     }
 
-    pub async fn run_queries(
+    /// Check connectivity and authentication for each protocol in
+    /// the query, without collecting data. Intended as a pre-flight
+    /// check of a host configuration.
+    pub async fn validate_queries(
         &self,
         input: &HashMap<Protocol, Input>,
         mut config: HashMap<Protocol, Box<RawValue>>,
         query: &QueryMap,
+    ) -> Result<HashMap<Protocol, ProbeResult>> {
+        let mut results = HashMap::new();
+
+        for proto in query.keys() {
+            let plugin = self
+                .plugins
+                .get(proto)
+                .ok_or_else(|| Error::MissingPlugin(proto.clone()))?;
+            let proto_input = input
+                .get(proto)
+                .ok_or_else(|| Error::MissingInput(proto.clone()))?;
+            let proto_config = config
+                .remove(proto)
+                .ok_or_else(|| Error::MissingConfig(proto.clone()))?;
+
+            let result = match plugin
+                .probe(proto_input.handle.as_ref(), &proto_config)
+                .await
+            {
+                Some(Ok(())) => ProbeResult::Ok,
+                Some(Err(e)) => ProbeResult::Failed(e.to_string()),
+                None => ProbeResult::Unsupported,
+            };
+            results.insert(proto.clone(), result);
+        }
+
+        Ok(results)
+    }
+
+    pub async fn run_queries(
+        &self,
+        input: &HashMap<Protocol, Input>,
+        config: HashMap<Protocol, Box<RawValue>>,
+        query: &QueryMap,
     ) -> Result<DataMap> {
+        Ok(self.run_queries_timed(input, config, query).await?.0)
+    }
+
+    /// Run the queries, returning the time spent fetching each data
+    /// table along with the data. Plugins supporting bulk collection
+    /// get all tables for the host in a single `collect_all` call;
+    /// other plugins are warmed up before running the queries.
+    pub async fn run_queries_timed(
+        &self,
+        input: &HashMap<Protocol, Input>,
+        mut config: HashMap<Protocol, Box<RawValue>>,
+        query: &QueryMap,
+    ) -> Result<(DataMap, Timings)> {
         let mut data_map = HashMap::new();
+        let mut timings = HashMap::new();
 
         for (proto, proto_query) in query {
             let plugin = self
@@ -115,23 +223,75 @@ impl PluginManager {
                 .ok_or_else(|| Error::MissingPlugin(proto.clone()))?;
             let proto_input = input
                 .get(proto)
-                .ok_or_else(|| Error::MissingInput(proto.clone()))?
-                .handle
-                .as_ref();
+                .ok_or_else(|| Error::MissingInput(proto.clone()))?;
             let proto_config = config
                 .remove(proto)
                 .ok_or_else(|| Error::MissingConfig(proto.clone()))?;
 
-            let proto_res = plugin
-                .run_queries(proto_input, &proto_config, proto_query)
-                .await;
+            let bulk = !self.table_timing
+                && plugin.supports_collect_all(proto_input.handle.as_ref());
+
+            if !bulk {
+                if let Err(e) = plugin
+                    .warmup(proto_input.handle.as_ref(), &proto_config)
+                    .await
+                {
+                    warn!(
+                        "{proto}: warmup failed; running queries without: {e}"
+                    );
+                }
+            }
+
+            let batches: Vec<Cow<ProtoQueryMap>> = match self.table_timing {
+                true => proto_query
+                    .iter()
+                    .map(|(table_id, fields)| {
+                        Cow::Owned(HashMap::from([(
+                            table_id.clone(),
+                            fields.clone(),
+                        )]))
+                    })
+                    .collect(),
+                false => vec![Cow::Borrowed(proto_query)],
+            };
+
+            for proto_query in batches.iter().map(|batch| batch.as_ref()) {
+                let idempotency = plugin
+                    .idempotency(proto_input.handle.as_ref(), proto_query);
+                let started = Instant::now();
+                let proto_res = self
+                    .retry
+                    .run(&proto.0, idempotency, || match bulk {
+                        true => plugin.collect_all(
+                            proto_input.handle.as_ref(),
+                            &proto_config,
+                            proto_query,
+                        ),
+                        false => plugin.run_queries(
+                            proto_input.handle.as_ref(),
+                            &proto_config,
+                            proto_query,
+                        ),
+                    })
+                    .await;
+                let elapsed = started.elapsed();
+
+                for data_table_id in proto_query.keys() {
+                    timings.insert(
+                        DataTableId(proto.clone(), data_table_id.clone()),
+                        elapsed,
+                    );
+                }
 
-            match proto_res {
-                Ok(mut data) => {
-                    for data_table_id in proto_query.keys() {
-                        data_map.insert(
-                            DataTableId(proto.clone(), data_table_id.clone()),
-                            data.remove(data_table_id)
+                match proto_res {
+                    Ok(mut data) => {
+                        for (data_table_id, fields) in proto_query {
+                            let table_id = DataTableId(
+                                proto.clone(),
+                                data_table_id.clone(),
+                            );
+                            let table_res = data
+                                .remove(data_table_id)
                                 .unwrap_or_else(|| {
                                     Err(Arc::new(DataTableError {
                                         origin: ErrorOrigin::Protocol(
@@ -141,40 +301,84 @@ impl PluginManager {
                                             MgrError::MissingDataTable,
                                         ),
                                     }))
-                                })
-                                .map(|table_res| {
+                                });
+                            let table_res = match proto_input
+                                .data_tables
+                                .get(data_table_id)
+                            {
+                                Some(spec) => validate_table(
+                                    self.validation
+                                        .get(proto)
+                                        .copied()
+                                        .unwrap_or_default(),
+                                    &table_id,
+                                    spec,
+                                    &proto_input.data_fields,
+                                    fields,
+                                    table_res,
+                                ),
+                                None => table_res,
+                            };
+                            if let (true, Ok(data)) =
+                                (log_enabled!(Level::Trace), &table_res)
+                            {
+                                for row in &data.value {
+                                    trace!(
+                                        "{}: {:?}",
+                                        table_id,
+                                        RedactedRow::new(
+                                            row,
+                                            &proto_input.data_fields
+                                        )
+                                    );
+                                }
+                            }
+                            data_map.insert(
+                                table_id,
+                                table_res.map(|table_res| {
                                     table_res.map(|rows| {
                                         rows.into_iter()
                                             .map(|row| {
-                                                row
-															 .into_iter()
-															 .map(|(field_id,field_res)|{
-																 (DataFieldId(proto.clone(),field_id),field_res)
-															 })
-															 .collect()
+                                                row.into_iter()
+                                                    .map(|(field_id, field_res)| {
+                                                        (
+                                                            DataFieldId(
+                                                                proto.clone(),
+                                                                field_id,
+                                                            ),
+                                                            field_res,
+                                                        )
+                                                    })
+                                                    .collect()
                                             })
                                             .collect()
                                     })
                                 }),
-                        );
+                            );
+                        }
                     }
-                }
-                Err(err) => {
-                    let error = Arc::new(DataTableError {
-                        origin: ErrorOrigin::Protocol(proto.clone()),
-                        error: Box::new(MgrError::PluginFailed(Box::new(err))),
-                    });
-                    for data_table_id in proto_query.keys() {
-                        data_map.insert(
-                            DataTableId(proto.clone(), data_table_id.clone()),
-                            Err(error.clone()),
-                        );
+                    Err(err) => {
+                        let error = Arc::new(DataTableError {
+                            origin: ErrorOrigin::Protocol(proto.clone()),
+                            error: Box::new(MgrError::PluginFailed(Box::new(
+                                err,
+                            ))),
+                        });
+                        for data_table_id in proto_query.keys() {
+                            data_map.insert(
+                                DataTableId(
+                                    proto.clone(),
+                                    data_table_id.clone(),
+                                ),
+                                Err(error.clone()),
+                            );
+                        }
                     }
                 }
             }
         }
 
-        Ok(data_map)
+        Ok((data_map, timings))
     }
 }
 
@@ -185,3 +389,384 @@ enum MgrError {
     #[error("Protocol plugin failure: {0}")]
     PluginFailed(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use agent_utils::TryAppend;
+    use async_trait::async_trait;
+    use etc_base::{
+        Annotated, AnnotatedResult, DataFieldId, DataTableId, ProtoDataFieldId,
+        ProtoDataTableId, ProtoQueryMap, ProtoRow, Protocol,
+    };
+    use serde::Deserialize;
+    use value::Value;
+
+    use super::PluginManager;
+    use crate::{DataFieldSpec, DataTableSpec, LocalPlugin};
+    use crate::{Idempotency, ProbeResult, RetryPolicy};
+
+    #[derive(thiserror::Error, Debug)]
+    #[error("test error")]
+    struct TestError;
+
+    #[derive(Deserialize, Default, Clone)]
+    struct TestInput;
+
+    impl TryAppend for TestInput {
+        fn try_append(&mut self, _other: Self) -> agent_utils::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Serves a value that is expensive to fetch, from the warmup
+    /// cache if available. Warmup, every run and every bulk
+    /// collection set up a connection.
+    #[derive(Default)]
+    struct TestPlugin {
+        fail_warmup: bool,
+        bulk: bool,
+        connections: AtomicUsize,
+        warmups: AtomicUsize,
+        fetches: AtomicUsize,
+        shutdowns: AtomicUsize,
+        runs: AtomicUsize,
+        fail_probe: bool,
+        /// Number of runs that fail before one succeeds.
+        fail_runs: usize,
+        idempotent: bool,
+        shared: Mutex<Option<i64>>,
+    }
+
+    impl TestPlugin {
+        fn fetch(&self) -> i64 {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            42
+        }
+    }
+
+    #[async_trait]
+    impl LocalPlugin for TestPlugin {
+        type Error = TestError;
+        type TypeError = TestError;
+        type DTError = TestError;
+        type DTWarning = TestError;
+        type Input = TestInput;
+        type Config = ();
+
+        const PROTOCOL: &'static str = "test";
+        const VERSION: &'static str = "0.0.0";
+
+        async fn warmup(
+            &self,
+            _input: &TestInput,
+            _config: &(),
+        ) -> Result<(), TestError> {
+            self.warmups.fetch_add(1, Ordering::SeqCst);
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            match self.fail_warmup {
+                true => Err(TestError),
+                false => {
+                    *self.shared.lock().unwrap() = Some(self.fetch());
+                    Ok(())
+                }
+            }
+        }
+
+        async fn probe(
+            &self,
+            _input: &TestInput,
+            _config: &(),
+        ) -> Option<Result<(), TestError>> {
+            match self.fail_probe {
+                true => Some(Err(TestError)),
+                false => Some(Ok(())),
+            }
+        }
+
+        async fn shutdown(&self) -> Result<(), TestError> {
+            self.shutdowns.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn idempotency(
+            &self,
+            _input: &TestInput,
+            _query: &ProtoQueryMap,
+        ) -> Idempotency {
+            match self.idempotent {
+                true => Idempotency::Idempotent,
+                false => Idempotency::NonIdempotent,
+            }
+        }
+
+        fn supports_collect_all(&self, _input: &TestInput) -> bool {
+            self.bulk
+        }
+
+        async fn collect_all(
+            &self,
+            _input: &TestInput,
+            _config: &(),
+            query: &ProtoQueryMap,
+        ) -> Result<
+            HashMap<
+                ProtoDataTableId,
+                AnnotatedResult<Vec<ProtoRow>, TestError, TestError>,
+            >,
+            TestError,
+        > {
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            let value = self.fetch();
+            Ok(query
+                .keys()
+                .map(|table| (table.clone(), Ok(table_data(value))))
+                .collect())
+        }
+
+        fn show_queries(
+            &self,
+            _input: &TestInput,
+            _query: &ProtoQueryMap,
+        ) -> Result<String, TestError> {
+            Ok(String::new())
+        }
+
+        async fn run_queries(
+            &self,
+            _input: &TestInput,
+            _config: &(),
+            query: &ProtoQueryMap,
+        ) -> Result<
+            HashMap<
+                ProtoDataTableId,
+                AnnotatedResult<Vec<ProtoRow>, TestError, TestError>,
+            >,
+            TestError,
+        > {
+            let runs = self.runs.fetch_add(1, Ordering::SeqCst) + 1;
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            if runs <= self.fail_runs {
+                return Err(TestError);
+            }
+            Ok(query
+                .keys()
+                .map(|table| {
+                    let shared = *self.shared.lock().unwrap();
+                    let value = shared.unwrap_or_else(|| self.fetch());
+                    (table.clone(), Ok(table_data(value)))
+                })
+                .collect())
+        }
+
+        fn get_tables(
+            &self,
+            _input: &TestInput,
+        ) -> Result<HashMap<ProtoDataTableId, DataTableSpec>, TestError>
+        {
+            Ok(HashMap::new())
+        }
+
+        fn get_fields(
+            &self,
+            _input: &TestInput,
+        ) -> Result<HashMap<ProtoDataFieldId, DataFieldSpec>, TestError>
+        {
+            Ok(HashMap::new())
+        }
+    }
+
+    fn table_data(value: i64) -> Annotated<Vec<ProtoRow>, TestError> {
+        let row = HashMap::from([(
+            ProtoDataFieldId(String::from("value")),
+            Ok(Value::Integer(value)),
+        )]);
+        Annotated {
+            value: vec![row],
+            warnings: Vec::new(),
+        }
+    }
+
+    type Setup = (
+        PluginManager,
+        HashMap<Protocol, crate::Input>,
+        HashMap<Protocol, Box<serde_json::value::RawValue>>,
+        etc_base::QueryMap,
+    );
+
+    async fn setup(plugin: TestPlugin) -> Setup {
+        let proto = Protocol(String::from("test"));
+        let mut manager = PluginManager::new();
+        manager.add_plugin(plugin);
+
+        let input = manager
+            .load_inputs(vec![HashMap::from([(
+                proto.clone(),
+                serde_json::value::to_raw_value(&()).unwrap(),
+            )])])
+            .await
+            .unwrap();
+        let config = HashMap::from([(
+            proto.clone(),
+            serde_json::value::to_raw_value(&()).unwrap(),
+        )]);
+        let query = HashMap::from([(
+            proto.clone(),
+            ["a", "b"]
+                .into_iter()
+                .map(|t| (ProtoDataTableId(t.to_string()), HashSet::new()))
+                .collect(),
+        )]);
+        (manager, input, config, query)
+    }
+
+    async fn run(plugin: TestPlugin) -> PluginManager {
+        let proto = Protocol(String::from("test"));
+        let (manager, input, config, query) = setup(plugin).await;
+        let data = manager.run_queries(&input, config, &query).await.unwrap();
+        for table in ["a", "b"] {
+            let rows = &data[&DataTableId(
+                proto.clone(),
+                ProtoDataTableId(table.to_string()),
+            )]
+                .as_ref()
+                .unwrap()
+                .value;
+            assert_eq!(
+                rows[0][&DataFieldId(
+                    proto.clone(),
+                    ProtoDataFieldId(String::from("value"))
+                )],
+                Ok(Value::Integer(42))
+            );
+        }
+        manager
+    }
+
+    #[tokio::test]
+    async fn warmup_data_is_reused() {
+        let manager = run(TestPlugin::default()).await;
+        let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+        assert_eq!(plugin.warmups.load(Ordering::SeqCst), 1);
+        assert_eq!(plugin.fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_warmup_falls_back() {
+        let manager = run(TestPlugin {
+            fail_warmup: true,
+            ..TestPlugin::default()
+        })
+        .await;
+        let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+        assert_eq!(plugin.warmups.load(Ordering::SeqCst), 1);
+        assert_eq!(plugin.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn plugins_are_shut_down() {
+        let manager = run(TestPlugin::default()).await;
+        manager.shutdown().await;
+        let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+        assert_eq!(plugin.shutdowns.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn validate_queries() {
+        let proto = Protocol(String::from("test"));
+        for (fail_probe, expected) in [
+            (false, ProbeResult::Ok),
+            (
+                true,
+                ProbeResult::Failed(String::from(
+                    "Protocol test error: test error",
+                )),
+            ),
+        ] {
+            let (manager, input, config, query) = setup(TestPlugin {
+                fail_probe,
+                ..TestPlugin::default()
+            })
+            .await;
+            let results = manager
+                .validate_queries(&input, config, &query)
+                .await
+                .unwrap();
+            let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+            assert_eq!(plugin.fetches.load(Ordering::SeqCst), 0);
+            assert_eq!(results[&proto], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn only_idempotent_plugins_are_retried() {
+        for (idempotent, runs) in [(false, 1), (true, 2)] {
+            let (mut manager, input, config, query) = setup(TestPlugin {
+                fail_runs: 1,
+                idempotent,
+                ..TestPlugin::default()
+            })
+            .await;
+            manager.set_retry_policy(RetryPolicy { max_attempts: 3 });
+            let data =
+                manager.run_queries(&input, config, &query).await.unwrap();
+            assert_eq!(data.len(), 2);
+            assert!(data.values().all(|res| res.is_ok() == idempotent));
+            let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+            assert_eq!(plugin.runs.load(Ordering::SeqCst), runs);
+        }
+    }
+
+    #[tokio::test]
+    async fn table_timings() {
+        let proto = Protocol(String::from("test"));
+        for (table_timing, runs) in [(false, 1), (true, 2)] {
+            let (mut manager, input, config, query) =
+                setup(TestPlugin::default()).await;
+            manager.set_table_timing(table_timing);
+            let (data, timings) = manager
+                .run_queries_timed(&input, config, &query)
+                .await
+                .unwrap();
+            assert_eq!(data.len(), 2);
+            assert_eq!(
+                timings.keys().collect::<HashSet<_>>(),
+                data.keys().collect::<HashSet<_>>()
+            );
+            assert!(timings.keys().all(|table_id| table_id.0 == proto));
+            let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+            assert_eq!(plugin.runs.load(Ordering::SeqCst), runs);
+        }
+    }
+    #[tokio::test]
+    async fn bulk_collection() {
+        let mut connections = Vec::new();
+        for (bulk, table_timing) in
+            [(false, false), (false, true), (true, false), (true, true)]
+        {
+            let (mut manager, input, config, query) = setup(TestPlugin {
+                bulk,
+                ..TestPlugin::default()
+            })
+            .await;
+            manager.set_table_timing(table_timing);
+            let data =
+                manager.run_queries(&input, config, &query).await.unwrap();
+            assert_eq!(data.len(), 2);
+            for res in data.values() {
+                let rows = &res.as_ref().unwrap().value;
+                assert_eq!(rows.len(), 1);
+                assert!(rows[0].values().all(|v| v == &Ok(Value::Integer(42))));
+            }
+            let plugin = manager.get_local_plugin::<TestPlugin>().unwrap();
+            connections.push(plugin.connections.load(Ordering::SeqCst));
+        }
+        /* Warmup and one run, warmup and a run per table, a single
+         * bulk collection, and the per-table path again since table
+         * timing disables bulk collection. */
+        assert_eq!(connections, [2, 3, 1, 3]);
+    }
+}
@@ -0,0 +1,23 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a connectivity and authentication check against a
+/// host, made without collecting data.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(tag = "status", content = "message", rename_all = "snake_case")]
+pub enum ProbeResult {
+    Ok,
+    Failed(String),
+    /// The plugin has no way to check the host short of running the
+    /// queries.
+    Unsupported,
+}
+
+impl ProbeResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ProbeResult::Ok)
+    }
+}
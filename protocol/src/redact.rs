@@ -0,0 +1,85 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::fmt;
+
+use etc_base::{ProtoDataFieldId, ProtoRow};
+
+use super::data_field::DataFieldSpec;
+
+/// Placeholder written instead of the value of a sensitive field.
+pub const REDACTED: &str = "<redacted>";
+
+/// Formats a row of plugin output for logging, hiding the values of
+/// sensitive fields.
+pub struct RedactedRow<'a> {
+    row: &'a ProtoRow,
+    fields: &'a HashMap<ProtoDataFieldId, DataFieldSpec>,
+}
+
+impl<'a> RedactedRow<'a> {
+    pub fn new(
+        row: &'a ProtoRow,
+        fields: &'a HashMap<ProtoDataFieldId, DataFieldSpec>,
+    ) -> Self {
+        Self { row, fields }
+    }
+}
+
+impl<'a> fmt::Debug for RedactedRow<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (field_id, value) in self.row {
+            match self.fields.get(field_id) {
+                Some(field) if field.sensitive => {
+                    map.entry(field_id, &format_args!("{}", REDACTED))
+                }
+                _ => map.entry(field_id, value),
+            };
+        }
+        map.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use etc_base::{ProtoDataFieldId, ProtoRow};
+    use value::{Type, Value};
+
+    use super::{RedactedRow, REDACTED};
+    use crate::DataFieldSpec;
+
+    #[test]
+    fn sensitive_values_are_hidden() {
+        let field = |name: &str, sensitive| {
+            (
+                ProtoDataFieldId(name.to_string()),
+                DataFieldSpec {
+                    name: name.to_string(),
+                    input_type: Type::UnicodeString,
+                    sensitive,
+                },
+            )
+        };
+        let fields =
+            HashMap::from_iter([field("model", false), field("serial", true)]);
+        let row: ProtoRow = HashMap::from_iter([
+            (
+                ProtoDataFieldId("model".to_string()),
+                Ok(Value::UnicodeString("SX-200".to_string())),
+            ),
+            (
+                ProtoDataFieldId("serial".to_string()),
+                Ok(Value::UnicodeString("A1B2C3".to_string())),
+            ),
+        ]);
+        let output = format!("{:?}", RedactedRow::new(&row, &fields));
+        assert!(output.contains("SX-200"));
+        assert!(output.contains(REDACTED));
+        assert!(!output.contains("A1B2C3"));
+    }
+}
@@ -90,7 +90,7 @@ where
             .await
             .map_err(|e| Error::RemotePlugin(self.protocol.clone(), e))?;
         Ok(crate::Input {
-            handle: Box::new(Input {
+            handle: Arc::new(Input {
                 remote: input,
                 types: data_fields
                     .iter()
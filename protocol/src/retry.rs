@@ -0,0 +1,116 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fmt::Display;
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether repeating an operation is free of side effects. Plugins
+/// performing write-like operations (eg. acknowledging alerts) should
+/// declare them non-idempotent, so that they are never retried
+/// automatically.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Idempotency {
+    Idempotent,
+    NonIdempotent,
+}
+
+impl Idempotency {
+    pub fn is_idempotent(self) -> bool {
+        self == Idempotency::Idempotent
+    }
+}
+
+/// How often a failed operation is attempted. The default makes a
+/// single attempt, ie. failures are not retried.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 1 }
+    }
+}
+
+impl RetryPolicy {
+    /// Run `op`, retrying on failure as long as attempts are left.
+    /// Non-idempotent operations are run exactly once.
+    pub async fn run<T, E, F, Fut>(
+        &self,
+        name: &str,
+        idempotency: Idempotency,
+        mut op: F,
+    ) -> Result<T, E>
+    where
+        E: Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let attempts = match idempotency {
+            Idempotency::Idempotent => self.max_attempts.max(1),
+            Idempotency::NonIdempotent => 1,
+        };
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if attempt < attempts => {
+                    log::warn!(
+                        "{name}: attempt {attempt}/{attempts} failed; \
+                         retrying: {e}"
+                    );
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::{Idempotency, RetryPolicy};
+
+    /// Operation failing on the first `failures` calls.
+    async fn op(calls: &AtomicU32, failures: u32) -> Result<u32, String> {
+        let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        match n > failures {
+            true => Ok(n),
+            false => Err(format!("failure {n}")),
+        }
+    }
+
+    #[tokio::test]
+    async fn idempotent_is_retried() {
+        let policy = RetryPolicy { max_attempts: 3 };
+        let calls = AtomicU32::new(0);
+        let res = policy
+            .run("test", Idempotency::Idempotent, || op(&calls, 2))
+            .await;
+        assert_eq!(res, Ok(3));
+
+        let calls = AtomicU32::new(0);
+        let res = policy
+            .run("test", Idempotency::Idempotent, || op(&calls, 5))
+            .await;
+        assert_eq!(res, Err(String::from("failure 3")));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn non_idempotent_is_never_retried() {
+        let policy = RetryPolicy { max_attempts: 3 };
+        let calls = AtomicU32::new(0);
+        let res = policy
+            .run("test", Idempotency::NonIdempotent, || op(&calls, 1))
+            .await;
+        assert_eq!(res, Err(String::from("failure 1")));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,295 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use etc_base::{
+    Annotated, AnnotatedResult, DataTableId, ProtoDataFieldId, ProtoRow,
+    Warning,
+};
+use value::{DataError, Type};
+
+use super::data_field::DataFieldSpec;
+use super::data_table::DataTableSpec;
+use super::error::{DataTableError, ErrorOrigin};
+
+type TableResult =
+    AnnotatedResult<Vec<ProtoRow>, Arc<DataTableError>, Arc<DataTableError>>;
+
+/// What to do when a plugin produces rows that do not match the
+/// declared data table spec.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationPolicy {
+    /// Pass plugin output through unchecked.
+    Off,
+    /// Report mismatches as warnings, but pass rows through as-is.
+    Warn,
+    /// Drop unknown columns, mark missing columns and cast values
+    /// to the declared type, reporting mismatches as warnings.
+    Coerce,
+    /// Fail the whole table on the first mismatch.
+    Strict,
+}
+
+/// Plugins that predate validation may not match their specs
+/// exactly, so checking is opt-in per protocol.
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ValidationError {
+    #[error("column {0} is not declared in the data table spec")]
+    UnknownColumn(ProtoDataFieldId),
+    #[error("requested column {0} is missing from the plugin output")]
+    MissingColumn(ProtoDataFieldId),
+    #[error("column {0} has type {1}, expected {2}")]
+    WrongType(ProtoDataFieldId, Type, Type),
+}
+
+/// Check plugin output for one data table against its spec. Only
+/// requested fields are expected to be present.
+pub(crate) fn validate_table(
+    policy: ValidationPolicy,
+    table_id: &DataTableId,
+    spec: &DataTableSpec,
+    fields: &HashMap<ProtoDataFieldId, DataFieldSpec>,
+    requested: &HashSet<ProtoDataFieldId>,
+    result: TableResult,
+) -> TableResult {
+    let Annotated {
+        value: rows,
+        mut warnings,
+    } = match (policy, result) {
+        (ValidationPolicy::Off, result) | (_, result @ Err(_)) => {
+            return result
+        }
+        (_, Ok(data)) => data,
+    };
+
+    let error = |e: ValidationError| {
+        Arc::new(DataTableError {
+            origin: ErrorOrigin::DataTable(table_id.clone()),
+            error: Box::new(e),
+        })
+    };
+
+    /* Report each problem once per table, not once per row. */
+    let mut reported = HashSet::new();
+    let mut report = |e: ValidationError| -> Result<(), Arc<DataTableError>> {
+        match policy {
+            ValidationPolicy::Strict => Err(error(e)),
+            _ => {
+                if reported.insert(e.to_string()) {
+                    warnings.push(Warning::warn(error(e)));
+                }
+                Ok(())
+            }
+        }
+    };
+
+    let coerce = policy == ValidationPolicy::Coerce;
+    let mut valid_rows = Vec::with_capacity(rows.len());
+    for mut row in rows {
+        for field_id in row.keys().cloned().collect::<Vec<_>>() {
+            if !spec.fields.contains(&field_id) {
+                if coerce {
+                    row.remove(&field_id);
+                }
+                report(ValidationError::UnknownColumn(field_id))?;
+            }
+        }
+
+        for field_id in requested.intersection(&spec.fields) {
+            let field = match fields.get(field_id) {
+                Some(field) => field,
+                None => continue,
+            };
+            match row.remove(field_id) {
+                None => {
+                    report(ValidationError::MissingColumn(field_id.clone()))?;
+                    if coerce {
+                        row.insert(field_id.clone(), Err(DataError::Missing));
+                    }
+                }
+                Some(Ok(value)) if value.get_type() != field.input_type => {
+                    let typ = value.get_type();
+                    report(ValidationError::WrongType(
+                        field_id.clone(),
+                        typ.clone(),
+                        field.input_type.clone(),
+                    ))?;
+                    if !coerce {
+                        row.insert(field_id.clone(), Ok(value));
+                        continue;
+                    }
+                    row.insert(
+                        field_id.clone(),
+                        value.cast_to(&field.input_type).map_err(|_| {
+                            DataError::TypeError(format!(
+                                "expected {}, got {}",
+                                field.input_type, typ
+                            ))
+                        }),
+                    );
+                }
+                Some(data) => {
+                    row.insert(field_id.clone(), data);
+                }
+            }
+        }
+
+        valid_rows.push(row);
+    }
+
+    Ok(Annotated {
+        value: valid_rows,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use etc_base::{
+        Annotated, DataTableId, ProtoDataFieldId, ProtoDataTableId, ProtoRow,
+        Protocol,
+    };
+    use value::{DataError, Type, Value};
+
+    use super::{validate_table, TableResult, ValidationPolicy};
+    use crate::{DataFieldSpec, DataTableSpec};
+
+    fn id(name: &str) -> ProtoDataFieldId {
+        ProtoDataFieldId(name.to_string())
+    }
+
+    fn spec() -> (DataTableSpec, HashMap<ProtoDataFieldId, DataFieldSpec>) {
+        let fields = [("name", Type::UnicodeString), ("load", Type::Float)]
+            .into_iter()
+            .map(|(name, input_type)| {
+                (
+                    id(name),
+                    DataFieldSpec {
+                        name: name.to_string(),
+                        input_type,
+                        sensitive: false,
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let table = DataTableSpec {
+            name: String::from("system"),
+            singleton: false,
+            keys: HashSet::from([id("name")]),
+            fields: fields.keys().cloned().collect(),
+        };
+        (table, fields)
+    }
+
+    fn validate(policy: ValidationPolicy, row: ProtoRow) -> TableResult {
+        let (table, fields) = spec();
+        validate_table(
+            policy,
+            &DataTableId(
+                Protocol(String::from("test")),
+                ProtoDataTableId(String::from("system")),
+            ),
+            &table,
+            &fields,
+            &table.fields,
+            Ok(Annotated {
+                value: vec![row],
+                warnings: Vec::new(),
+            }),
+        )
+    }
+
+    fn row(fields: Vec<(&str, Value)>) -> ProtoRow {
+        fields.into_iter().map(|(k, v)| (id(k), Ok(v))).collect()
+    }
+
+    #[test]
+    fn matching_table() {
+        let input = row(vec![
+            ("name", Value::UnicodeString(String::from("host"))),
+            ("load", Value::Float(0.5)),
+        ]);
+        let data = validate(ValidationPolicy::Strict, input.clone()).unwrap();
+        assert!(data.warnings.is_empty());
+        assert_eq!(data.value, vec![input]);
+    }
+
+    #[test]
+    fn extra_column() {
+        let input = row(vec![
+            ("name", Value::UnicodeString(String::from("host"))),
+            ("load", Value::Float(0.5)),
+            ("uptime", Value::Integer(10)),
+        ]);
+        assert!(validate(ValidationPolicy::Strict, input.clone()).is_err());
+        let data = validate(ValidationPolicy::Coerce, input).unwrap();
+        assert_eq!(data.warnings.len(), 1);
+        assert!(!data.value[0].contains_key(&id("uptime")));
+    }
+
+    #[test]
+    fn missing_column() {
+        let input =
+            row(vec![("name", Value::UnicodeString(String::from("host")))]);
+        assert!(validate(ValidationPolicy::Strict, input.clone()).is_err());
+        let data = validate(ValidationPolicy::Coerce, input).unwrap();
+        assert_eq!(data.warnings.len(), 1);
+        assert_eq!(data.value[0][&id("load")], Err(DataError::Missing));
+    }
+
+    #[test]
+    fn mistyped_column() {
+        let input = row(vec![
+            ("name", Value::UnicodeString(String::from("host"))),
+            ("load", Value::Integer(1)),
+        ]);
+        assert!(validate(ValidationPolicy::Strict, input.clone()).is_err());
+        let data = validate(ValidationPolicy::Coerce, input).unwrap();
+        assert_eq!(data.warnings.len(), 1);
+        assert_eq!(data.value[0][&id("load")], Ok(Value::Float(1.0)));
+
+        let input = row(vec![
+            ("name", Value::Integer(1)),
+            ("load", Value::Float(1.0)),
+        ]);
+        let data = validate(ValidationPolicy::Coerce, input).unwrap();
+        assert!(matches!(
+            data.value[0][&id("name")],
+            Err(DataError::TypeError(_))
+        ));
+    }
+
+    #[test]
+    fn validation_warn() {
+        let input = row(vec![
+            ("load", Value::Integer(1)),
+            ("uptime", Value::Integer(10)),
+        ]);
+        let data = validate(ValidationPolicy::Warn, input.clone()).unwrap();
+        assert_eq!(data.warnings.len(), 3);
+        assert_eq!(data.value, vec![input]);
+    }
+
+    #[test]
+    fn validation_off() {
+        let input = row(vec![("uptime", Value::Integer(10))]);
+        let data = validate(ValidationPolicy::Off, input.clone()).unwrap();
+        assert!(data.warnings.is_empty());
+        assert_eq!(data.value, vec![input]);
+        assert_eq!(ValidationPolicy::default(), ValidationPolicy::Off);
+    }
+}
@@ -90,12 +90,15 @@ async fn main() {
     let pkgs = futures::stream::iter(&args.package)
         .then(|path| {
             let path = path.clone();
-            async {
-                let data = tokio::fs::read_to_string(path)
+            async move {
+                let data = tokio::fs::read_to_string(&path)
                     .await
                     .expect("Failed to read package");
-                serde_json::from_str::<etc::Package>(&data)
-                    .expect("Failed to decode package.")
+                etc::Package::from_json(
+                    &etc_base::PackageName(path.display().to_string()),
+                    &data,
+                )
+                .expect("Failed to decode package.")
             }
         })
         .collect::<Vec<_>>()
@@ -0,0 +1,54 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+
+use etc_base::{ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap};
+use serde::{Deserialize, Serialize};
+
+use crate::plugin::DataMap;
+
+/// Per-table allowlist of response fields to retain. Tables that are
+/// not listed keep all requested fields. Fields outside the list are
+/// not requested from the API plugins, so they are not parsed, and
+/// are dropped from any rows that still carry them. Dropped fields
+/// are reported missing to formulas using them.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(transparent)]
+pub struct FieldAllowlist(
+    pub HashMap<ProtoDataTableId, HashSet<ProtoDataFieldId>>,
+);
+
+impl FieldAllowlist {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Remove fields outside the allowlist from the query.
+    pub fn restrict_query(&self, query: &ProtoQueryMap) -> ProtoQueryMap {
+        query
+            .iter()
+            .map(|(table_id, field_ids)| {
+                let field_ids = match self.0.get(table_id) {
+                    Some(allowed) => {
+                        field_ids.intersection(allowed).cloned().collect()
+                    }
+                    None => field_ids.clone(),
+                };
+                (table_id.clone(), field_ids)
+            })
+            .collect()
+    }
+
+    /// Drop fields outside the allowlist from the rows.
+    pub fn apply(&self, data: &mut DataMap) {
+        for (table_id, table) in data.iter_mut() {
+            if let (Some(allowed), Ok(table)) = (self.0.get(table_id), table) {
+                for row in &mut table.value {
+                    row.retain(|field_id, _| allowed.contains(field_id));
+                }
+            }
+        }
+    }
+}
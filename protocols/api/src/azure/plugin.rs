@@ -3,19 +3,20 @@
  ******************************************************************************/
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures::{stream, StreamExt};
 use jsonpath::Selector;
 use log::{debug, info};
-use reqwest::Client;
 use serde_json::Value;
 
 use agent_utils::{KeyVault, TryGetFrom};
 use azure_protocol::Config;
 use etc_base::{Annotated, ProtoQueryMap};
 use etc_base::{ProtoDataFieldId, ProtoDataTableId, ProtoRow};
-use rest_protocol::{http::HTTPMethod, input::RESTRequest, Template};
+use protocol::http::RequestStats;
+use rest_protocol::{http::HTTPMethod, input::RESTRequest, Session, Template};
 use uritemplate::UriTemplate;
 
 use crate::azure::{DTEResult, DTError, Response, Result};
@@ -27,11 +28,20 @@ use crate::{APIPlugin, Input, Plugin as ProtPlugin};
 pub struct Plugin {
     key_vault: KeyVault,
     pub config: Config,
+    stats: Arc<RequestStats>,
 }
 
 impl Plugin {
-    pub fn new(key_vault: KeyVault, config: Config) -> Result<Self> {
-        Ok(Self { key_vault, config })
+    pub fn new(
+        key_vault: KeyVault,
+        config: Config,
+        stats: Arc<RequestStats>,
+    ) -> Result<Self> {
+        Ok(Self {
+            key_vault,
+            config,
+            stats,
+        })
     }
 
     fn create_request(&self) -> DTEResult<RESTRequest> {
@@ -53,7 +63,7 @@ impl Plugin {
 
     async fn exec_request(
         &self,
-        client: &Client,
+        session: &Session,
         // dt_id: ProtoDataTableId,
         command: TableSpec,
         fields: HashMap<ProtoDataFieldId, FieldSpec>,
@@ -90,7 +100,7 @@ impl Plugin {
             requests.push(self.request_subscription(
                 input.clone(),
                 &selectors,
-                client,
+                session,
             ));
         }
 
@@ -116,12 +126,12 @@ impl Plugin {
         &self,
         input: HashMap<String, String>,
         selectors: &HashMap<ProtoDataFieldId, (FieldSpec, Selector)>,
-        client: &Client,
+        session: &Session,
     ) -> DTEResult<Vec<ProtoRow>> {
         let mut request = self.create_request()?;
         let response: Response = serde_json::from_str(
             &request
-                .execute(client, &input)
+                .execute_in(session, &input)
                 .await
                 .map_err(DTError::RESTError)?,
         )
@@ -131,14 +141,14 @@ impl Plugin {
 
     async fn exec_query(
         &self,
-        client: &Client,
+        session: &Session,
         dt_id: ProtoDataTableId,
         command: TableSpec,
         fields: HashMap<ProtoDataFieldId, FieldSpec>,
     ) -> (ProtoDataTableId, TableData) {
         (
             dt_id.clone(),
-            self.exec_request(client, command, fields).await,
+            self.exec_request(session, command, fields).await,
         )
     }
 }
@@ -152,11 +162,13 @@ impl APIPlugin for Plugin {
     ) -> APIResult<DataMap> {
         info!("Using Azure API plugin");
 
-        let client = self
-            .config
-            .login(Some(&self.key_vault))
-            .await
-            .map_err(super::error::Error::Azure)?;
+        let session = Session::from(
+            self.config
+                .login(Some(&self.key_vault))
+                .await
+                .map_err(super::error::Error::Azure)?,
+        )
+        .with_stats(self.stats.clone());
         debug!("Logged in successfully");
 
         let mut requests = Vec::new();
@@ -183,7 +195,7 @@ impl APIPlugin for Plugin {
                 fields.len()
             );
             requests.push(self.exec_query(
-                &client,
+                &session,
                 dt_id.clone(),
                 command.clone(),
                 fields,
@@ -26,7 +26,12 @@ pub struct Config {
     #[serde(default)]
     pub proxmox: Option<super::proxmox::Config>,
     #[serde(default)]
-    pub elastic: Option<super::elastic::Config>, //external: HashMap<PluginId,Value>,
+    pub elastic: Option<super::elastic::Config>,
+    #[serde(default)]
+    pub graphql: Option<super::graphql::Config>, //external: HashMap<PluginId,Value>,
+    /// Response fields to retain per table.
+    #[serde(default)]
+    pub field_allowlist: super::FieldAllowlist,
 }
 
 pub type KeyvaultResult<T> = std::result::Result<T, KeyvaultError>;
@@ -8,6 +8,8 @@ use protocol::{auth, http};
 
 use serde_json::Value as JsonValue;
 
+use super::mapping::MappingConflict;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, thiserror::Error)]
@@ -63,4 +65,9 @@ pub enum DTError {
 pub type DTWResult<T> = std::result::Result<T, DTWarning>;
 
 #[derive(Debug, thiserror::Error)]
-pub enum DTWarning {}
+pub enum DTWarning {
+    #[error("{0}")]
+    MappingConflict(MappingConflict),
+    #[error("could not retrieve the mapping for {0}: {1}")]
+    Mapping(String, String),
+}
@@ -0,0 +1,225 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde_json::{Map, Value as JsonValue};
+
+/// Suffix of the endpoints searching an index pattern, e.g.
+/// `logs-*/_search`.
+const SEARCH_SUFFIX: &str = "/_search";
+
+/// The index pattern searched by an endpoint, if it is a search.
+pub fn search_index_pattern(endpoint: &str) -> Option<&str> {
+    let path = endpoint.split_once('?').map_or(endpoint, |(path, _)| path);
+    path.strip_suffix(SEARCH_SUFFIX)
+        .filter(|pattern| !pattern.is_empty())
+}
+
+pub fn mapping_endpoint(index_pattern: &str) -> String {
+    format!("{index_pattern}/_mapping")
+}
+
+/// The mapped field name for a field path in a search hit.
+pub fn field_name(path: &str) -> &str {
+    path.strip_prefix("_source.").unwrap_or(path)
+}
+
+/// Type of a field, as far as relevant for value conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Ip,
+    Other,
+}
+
+impl FieldKind {
+    pub fn from_elastic(typ: &str) -> Self {
+        match typ {
+            "keyword" | "constant_keyword" | "wildcard" | "text"
+            | "match_only_text" => Self::String,
+            "long" | "integer" | "short" | "byte" | "unsigned_long" => {
+                Self::Integer
+            }
+            "double" | "float" | "half_float" | "scaled_float" => Self::Float,
+            "boolean" => Self::Boolean,
+            "date" | "date_nanos" => Self::Date,
+            "ip" => Self::Ip,
+            _ => Self::Other,
+        }
+    }
+
+    /// The type both kinds can be coerced to. Everything can be
+    /// represented as a string.
+    fn common(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => {
+                Self::Float
+            }
+            _ => Self::String,
+        }
+    }
+}
+
+/// Field mappings per index, as returned by the `_mapping` endpoint.
+/// Object fields are flattened to dotted names, multi-fields are
+/// included as `<field>.<subfield>`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexMappings(pub BTreeMap<String, BTreeMap<String, String>>);
+
+/// A field mapped with different types in different indices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MappingConflict {
+    pub field: String,
+    /// Elastic type per index.
+    pub types: BTreeMap<String, String>,
+    pub resolved: FieldKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReconciledMapping {
+    pub fields: HashMap<String, FieldKind>,
+    pub conflicts: Vec<MappingConflict>,
+}
+
+impl IndexMappings {
+    pub fn from_response(response: &JsonValue) -> Self {
+        Self(
+            response
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(index, mapping)| {
+                    let mut fields = BTreeMap::new();
+                    if let Some(properties) = mapping_properties(mapping) {
+                        add_properties(&mut fields, "", properties);
+                    }
+                    (index.clone(), fields)
+                })
+                .collect(),
+        )
+    }
+
+    /// Determine a common type for every field. Fields with different
+    /// types in different indices are reported as conflicts.
+    pub fn reconcile(&self) -> ReconciledMapping {
+        let mut types: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+        for (index, fields) in &self.0 {
+            for (field, typ) in fields {
+                types
+                    .entry(field.as_str())
+                    .or_default()
+                    .insert(index.as_str(), typ.as_str());
+            }
+        }
+
+        let mut result = ReconciledMapping::default();
+        for (field, types) in types {
+            let resolved = types
+                .values()
+                .map(|typ| FieldKind::from_elastic(typ))
+                .reduce(FieldKind::common)
+                .unwrap_or(FieldKind::Other);
+            let mut distinct = types.values().collect::<Vec<_>>();
+            distinct.sort();
+            distinct.dedup();
+            if distinct.len() > 1 {
+                result.conflicts.push(MappingConflict {
+                    field: field.to_string(),
+                    types: types
+                        .iter()
+                        .map(|(index, typ)| {
+                            (index.to_string(), typ.to_string())
+                        })
+                        .collect(),
+                    resolved,
+                });
+            }
+            result.fields.insert(field.to_string(), resolved);
+        }
+        result
+    }
+}
+
+/* Before Elasticsearch 7, mappings are nested in a document type. */
+fn mapping_properties(mapping: &JsonValue) -> Option<&Map<String, JsonValue>> {
+    let mappings = mapping.get("mappings")?;
+    match mappings.get("properties") {
+        Some(properties) => properties.as_object(),
+        None => mappings
+            .as_object()?
+            .values()
+            .find_map(|doc| doc.get("properties")?.as_object()),
+    }
+}
+
+fn add_properties(
+    fields: &mut BTreeMap<String, String>,
+    prefix: &str,
+    properties: &Map<String, JsonValue>,
+) {
+    for (name, def) in properties {
+        let name = format!("{prefix}{name}");
+        if let Some(properties) =
+            def.get("properties").and_then(|p| p.as_object())
+        {
+            add_properties(fields, &format!("{name}."), properties);
+            continue;
+        }
+        if let Some(typ) = def.get("type").and_then(|t| t.as_str()) {
+            fields.insert(name.clone(), typ.to_string());
+        }
+        if let Some(subfields) = def.get("fields").and_then(|f| f.as_object()) {
+            add_properties(fields, &format!("{name}."), subfields);
+        }
+    }
+}
+
+/// Convert a value from an index with a different mapping to the
+/// common type, if needed.
+pub fn coerce(value: &JsonValue, kind: FieldKind) -> Option<JsonValue> {
+    match (kind, value) {
+        (FieldKind::String, JsonValue::Number(n)) => {
+            Some(JsonValue::String(n.to_string()))
+        }
+        (FieldKind::String, JsonValue::Bool(b)) => {
+            Some(JsonValue::String(b.to_string()))
+        }
+        (FieldKind::Integer, JsonValue::String(s)) => {
+            s.parse::<i64>().ok().map(JsonValue::from)
+        }
+        (FieldKind::Float, JsonValue::String(s)) => {
+            s.parse::<f64>().ok().map(JsonValue::from)
+        }
+        (FieldKind::Float, JsonValue::Number(n)) if !n.is_f64() => {
+            n.as_f64().map(JsonValue::from)
+        }
+        (FieldKind::Boolean, JsonValue::String(s)) => {
+            s.parse::<bool>().ok().map(JsonValue::Bool)
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for MappingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field {} has conflicting mappings ({}); using {:?}",
+            self.field,
+            self.types
+                .iter()
+                .map(|(index, typ)| format!("{index}: {typ}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.resolved
+        )
+    }
+}
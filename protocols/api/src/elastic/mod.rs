@@ -5,8 +5,12 @@
 mod api;
 mod config;
 mod error;
+mod mapping;
 mod plugin;
 
 pub use config::Config;
 pub use error::{DTEResult, DTError, DTWResult, DTWarning, Error, Result};
+pub use mapping::{
+    FieldKind, IndexMappings, MappingConflict, ReconciledMapping,
+};
 pub use plugin::Plugin;
@@ -4,7 +4,6 @@
 
 use std::collections::HashMap;
 use std::convert::identity;
-use std::net::Ipv4Addr;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -12,14 +11,18 @@ use std::time::SystemTime;
 
 use agent_utils::{KeyVault, TryGetFrom};
 use chrono::{DateTime, Duration, Utc};
-use etc_base::{Annotated, ProtoDataFieldId, ProtoQueryMap};
+use etc_base::{Annotated, ProtoDataFieldId, ProtoQueryMap, Warning};
 use futures::{stream, StreamExt};
 use itertools::Itertools;
 use log::{debug, info, trace, warn};
+use logger::Verbosity;
 use protocol::CounterDb;
 use serde_json::Value as JsonValue;
 use tap::{Pipe, Tap, TapFallible};
-use value::{Data, DataError, EnumValue, IntEnumValue, Value};
+use unit::Quantity;
+use value::{
+    parse_ipv4_address, Data, DataError, EnumValue, IntEnumValue, Value,
+};
 
 use crate::elastic::api::DataTable;
 use crate::error::Result as APIResult;
@@ -31,7 +34,8 @@ use crate::{
 
 use super::api::Request;
 use super::error::{PathError, PathResult};
-use super::{Config, DTEResult, DTError};
+use super::mapping::{self, FieldKind, IndexMappings, ReconciledMapping};
+use super::{Config, DTEResult, DTError, DTWarning};
 
 type TableKey = Rc<Option<String>>;
 
@@ -177,6 +181,7 @@ fn collect_field(
     table: &JsonValue,
     rowkey: &str,
     counterdb: &CounterDb,
+    kind: Option<FieldKind>,
 ) -> Data {
     let value = follow_path([table], &datafield.parameter_header)
         .map_err(|e| DataError::External(e.to_string()))?
@@ -192,6 +197,8 @@ fn collect_field(
         .tap_err(|e| {
             warn!("failed to find path for {}: {e}", datafield.parameter_name)
         })?;
+    let coerced = kind.and_then(|kind| mapping::coerce(value, kind));
+    let value = coerced.as_ref().unwrap_or(value);
     let parse_err = || {
         DataError::Parse(
             serde_json::to_string(value).unwrap(),
@@ -264,31 +271,35 @@ fn collect_field(
             .as_str()
             .map(|s| {
                 Ok(Value::Ipv4Address(
-                    s.parse::<Ipv4Addr>().map_err(|_| parse_err())?.octets(),
+                    parse_ipv4_address(s).map_err(|_| parse_err())?,
                 ))
             })
             .ok_or_else(parse_err)?,
 
-        ParameterType::Counter => value
+        ParameterType::Counter
+        | ParameterType::Difference
+        | ParameterType::Rate => value
             .as_i64()
             .map(|i| {
-                counterdb.counter(
+                counterdb.sample(
                     format!("{}.{}", rowkey, datafield.parameter_name),
                     i as u64,
                     SystemTime::now(),
+                    datafield.parameter_type.counter_mode().unwrap(),
                 )
             })
             .ok_or_else(parse_err)?,
-        ParameterType::Difference => value
-            .as_i64()
-            .map(|i| {
-                counterdb.difference(
-                    format!("{}.{}", rowkey, datafield.parameter_name),
-                    i as u64,
-                    SystemTime::now(),
-                )
+
+        ParameterType::Information
+        | ParameterType::Bandwidth
+        | ParameterType::IoPerformance
+        | ParameterType::IoLatency => value
+            .as_f64()
+            .map(|v| {
+                let dimension = datafield.parameter_type.dimension().unwrap();
+                Value::Quantity(Quantity(v, dimension.reference_unit()))
             })
-            .ok_or_else(parse_err)?,
+            .ok_or_else(parse_err),
     }
 }
 
@@ -334,12 +345,54 @@ fn get_clustertables<'a>(
     Ok(vec![(Rc::new(clustername), table)])
 }
 
+/// Reconcile the mappings of the indices matched by the index
+/// pattern of a search table.
+fn get_mapping(
+    datatable: &DataTable<'_>,
+    data: &HashMap<&str, DTEResult<JsonValue>>,
+    warnings: &mut Vec<Warning<crate::DTWarning>>,
+) -> Option<ReconciledMapping> {
+    let pattern = mapping::search_index_pattern(&datatable.spec.command_name)?;
+    let mapping = match data.get(mapping::mapping_endpoint(pattern).as_str()) {
+        Some(Ok(mapping)) => IndexMappings::from_response(mapping).reconcile(),
+        Some(Err(e)) => {
+            warnings.push(Warning {
+                verbosity: Verbosity::Warning,
+                message: DTWarning::Mapping(pattern.to_string(), e.to_string())
+                    .into(),
+            });
+            return None;
+        }
+        None => return None,
+    };
+
+    warnings.extend(
+        mapping
+            .conflicts
+            .iter()
+            .filter(|conflict| {
+                datatable.fields.values().any(|df| {
+                    mapping::field_name(&df.parameter_header) == conflict.field
+                })
+            })
+            .map(|conflict| Warning {
+                verbosity: Verbosity::Warning,
+                message: DTWarning::MappingConflict(conflict.clone()).into(),
+            }),
+    );
+    Some(mapping)
+}
+
 // TODO: refactor to make it more readable?
 fn collect_table(
     datatable: DataTable<'_>,
     data: &mut HashMap<&str, DTEResult<JsonValue>>,
     counterdb: &CounterDb,
 ) -> TableData {
+    let mut warnings = Vec::new();
+    let mapping = get_mapping(&datatable, data, &mut warnings);
+    let kinds = mapping.as_ref().map(|mapping| &mapping.fields);
+
     let table = data
         .get_mut(datatable.spec.command_name.as_str())
         .unwrap()
@@ -381,7 +434,16 @@ fn collect_table(
                                         .map(Value::UnicodeString)
                                         .ok_or(DataError::Missing)
                                 } else {
-                                    collect_field(df, table, &rowkey, counterdb)
+                                    let kind = kinds.and_then(|kinds| {
+                                        kinds
+                                            .get(mapping::field_name(
+                                                &df.parameter_header,
+                                            ))
+                                            .copied()
+                                    });
+                                    collect_field(
+                                        df, table, &rowkey, counterdb, kind,
+                                    )
                                 };
 
                                 // trace!("data for {dfid:?}: {field:?}");
@@ -396,7 +458,7 @@ fn collect_table(
 
     Ok(Annotated {
         value: rows,
-        warnings: Vec::new(),
+        warnings,
     })
 }
 
@@ -469,7 +531,18 @@ impl APIPlugin for Plugin {
             })
             .collect::<APIResult<Vec<_>>>()?;
 
-        let apicalls = datatables.iter().fold(
+        // Searches over an index pattern need the mappings of the
+        // matched indices to reconcile field types.
+        let mapping_endpoints = datatables
+            .iter()
+            .filter_map(|dt| {
+                mapping::search_index_pattern(&dt.spec.command_name)
+            })
+            .map(mapping::mapping_endpoint)
+            .unique()
+            .collect::<Vec<_>>();
+
+        let mut apicalls = datatables.iter().fold(
             HashMap::<&str, Request>::new(),
             |mut accum: HashMap<&str, Request>, elem: &DataTable| {
                 accum
@@ -502,6 +575,14 @@ impl APIPlugin for Plugin {
                 accum
             },
         );
+        for endpoint in &mapping_endpoints {
+            apicalls.entry(endpoint.as_str()).or_insert(Request {
+                auth: &auth,
+                client: client.clone(),
+                base_url: &base_url,
+                endpoint: endpoint.as_str(),
+            });
+        }
         info!("scheduled {} api calls", apicalls.len());
 
         // cannot use a closure due to lifetime issues
@@ -45,6 +45,8 @@ pub enum Error {
     Proxmox(#[from] super::proxmox::Error),
     #[error("Elastic: {0}")]
     Elastic(#[from] super::elastic::Error),
+    #[error("GraphQL: {0}")]
+    GraphQL(#[from] super::graphql::Error),
 
     #[error("{0}")]
     Custom(String),
@@ -83,6 +85,8 @@ pub enum DTError {
     Proxmox(#[from] super::proxmox::DTError),
     #[error("Elastic: {0}")]
     Elastic(#[from] super::elastic::DTError),
+    #[error("GraphQL: {0}")]
+    GraphQL(#[from] super::graphql::DTError),
     //External(String, String)
 }
 
@@ -108,6 +112,8 @@ pub enum DTWarning {
     Proxmox(#[from] super::proxmox::DTWarning),
     #[error("Elastic: {0}")]
     Elastic(#[from] super::elastic::DTWarning),
+    #[error("GraphQL: {0}")]
+    GraphQL(#[from] super::graphql::DTWarning),
     //External(String, String)
 }
 
@@ -0,0 +1,61 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use agent_utils::KeyVault;
+use protocol::{
+    auth::{self, LookupKeyvault},
+    http,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use super::{Error, Result};
+
+const DEFAULT_ENDPOINT: &str = "/graphql";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub http: http::Config,
+    #[serde(default)]
+    pub auth: Option<auth::BasicAuth>,
+    /// The path of the GraphQL endpoint (default: "/graphql").
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Variables sent along with every query.
+    #[serde(default)]
+    pub variables: Map<String, JsonValue>,
+}
+
+impl Config {
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT)
+    }
+
+    pub async fn get_url(&self) -> Result<String> {
+        Ok(format!(
+            "{}{}",
+            self.http.base_url(None).await?,
+            self.endpoint()
+        ))
+    }
+
+    pub async fn get_client(&self) -> Result<Client> {
+        Ok(self.http.create_client(Vec::new()).await?.0)
+    }
+
+    pub async fn get_credentials(
+        &self,
+        keyvault: KeyVault,
+    ) -> Result<Option<auth::BasicAuth>> {
+        match &self.auth {
+            Some(auth) => Ok(Some(
+                auth.lookup_keyvault(keyvault)
+                    .await
+                    .map_err(Error::CredentialLookup)?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use protocol::{auth, http};
+use reqwest::StatusCode;
+
+use super::GraphQLError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unable to create http client: {0}")]
+    CreateClient(#[from] http::Error),
+    #[error("unable to find credentials: {0}")]
+    CredentialLookup(#[source] auth::Error),
+}
+
+pub type DTEResult<T> = std::result::Result<T, DTError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DTError {
+    #[error("error sending request: {0}")]
+    SendRequest(#[source] reqwest::Error),
+    #[error("recieved an invalid response ({0}): {1}")]
+    InvalidResponse(StatusCode, String),
+    #[error("unable to deserialize response: {0}")]
+    DeserializeResponse(#[source] serde_json::Error),
+    #[error("query failed: {}", format_errors(.0))]
+    Query(Vec<GraphQLError>),
+    #[error("the response did not contain any data")]
+    NoData,
+    #[error("invalid JSONPath {0}: {1}")]
+    JsonPath(String, String),
+    #[error("{0}")]
+    Custom(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DTWarning {
+    #[error("{0}")]
+    Query(GraphQLError),
+}
+
+fn format_errors(errors: &[GraphQLError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -0,0 +1,13 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+mod config;
+mod error;
+mod plugin;
+mod response;
+
+pub use config::Config;
+pub use error::{DTEResult, DTError, DTWarning, Error, Result};
+pub use plugin::Plugin;
+pub use response::{GraphQLError, Response, Rows};
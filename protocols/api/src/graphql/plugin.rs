@@ -0,0 +1,209 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use agent_utils::{KeyVault, TryGetFrom};
+use etc_base::{
+    Annotated, ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap, Warning,
+};
+use futures::{stream, StreamExt};
+use jsonpath::Selector;
+use log::{debug, info, trace, warn};
+use logger::Verbosity;
+use protocol::auth;
+use reqwest::Client;
+use serde_json::{json, Value as JsonValue};
+use value::DataError;
+
+use crate::error::Result as APIResult;
+use crate::input::FieldSpec;
+use crate::ms_graph::parsers::parse_jsonval;
+use crate::plugin::{DataMap, TableData};
+use crate::{APIPlugin, Input, Plugin as ProtPlugin};
+
+use super::{Config, DTEResult, DTError, Response};
+
+pub struct Plugin {
+    key_vault: KeyVault,
+    config: Config,
+}
+
+struct Request<'a> {
+    client: &'a Client,
+    url: &'a str,
+    auth: Option<&'a auth::BasicAuth>,
+}
+
+impl Plugin {
+    pub fn new(key_vault: KeyVault, config: Config) -> Self {
+        Self { key_vault, config }
+    }
+}
+
+impl Request<'_> {
+    async fn query(&self, query: &str, config: &Config) -> DTEResult<Response> {
+        let mut request = self.client.post(self.url).json(&json!({
+            "query": query,
+            "variables": config.variables,
+        }));
+        if let Some(auth) = self.auth {
+            request =
+                request.basic_auth(&auth.username, auth.password.as_deref());
+        }
+
+        let response = request.send().await.map_err(DTError::SendRequest)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(DTError::SendRequest)?;
+        trace!(
+            "response from {}: {}",
+            self.url,
+            String::from_utf8_lossy(&body)
+        );
+
+        /* Servers may report query errors with a non-success status,
+         * so try to read the envelope first. */
+        match Response::from_slice(&body) {
+            Ok(response)
+                if status.is_success() || !response.errors.is_empty() =>
+            {
+                Ok(response)
+            }
+            Ok(_) | Err(_) if !status.is_success() => {
+                Err(DTError::InvalidResponse(
+                    status,
+                    String::from_utf8_lossy(&body).into_owned(),
+                ))
+            }
+            result => result,
+        }
+    }
+}
+
+fn collect_table(
+    response: &DTEResult<Response>,
+    path: &str,
+    fields: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+) -> TableData {
+    let response = response
+        .as_ref()
+        .map_err(|e| DTError::Custom(e.to_string()))?;
+    let rows = response.rows(path)?;
+
+    let selectors = fields
+        .iter()
+        .map(|(df_id, field)| {
+            Ok((
+                *df_id,
+                Selector::new(&field.parameter_header).map_err(|e| {
+                    DTError::JsonPath(
+                        field.parameter_header.clone(),
+                        e.to_string(),
+                    )
+                })?,
+            ))
+        })
+        .collect::<DTEResult<HashMap<_, _>>>()?;
+
+    let value = rows
+        .rows
+        .iter()
+        .map(|row| {
+            fields
+                .iter()
+                .map(|(df_id, field)| {
+                    let value = match selectors[df_id].find(row).last() {
+                        Some(value) => parse_jsonval(field, value.clone())
+                            .map_err(|e| DataError::TypeError(e.to_string())),
+                        None => Err(DataError::Missing),
+                    };
+                    ((*df_id).clone(), value)
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(Annotated {
+        value,
+        warnings: rows
+            .warnings
+            .into_iter()
+            .map(|w| Warning {
+                verbosity: Verbosity::Warning,
+                message: w.into(),
+            })
+            .collect(),
+    })
+}
+
+#[async_trait::async_trait]
+impl APIPlugin for Plugin {
+    async fn run_queries(
+        &self,
+        input: &Input,
+        query: &ProtoQueryMap,
+    ) -> APIResult<DataMap> {
+        info!("Using GraphQL plugin");
+
+        let client = self.config.get_client().await?;
+        let auth = self.config.get_credentials(self.key_vault.clone()).await?;
+        let url = self.config.get_url().await?;
+        debug!("connecting to {url}");
+
+        let request = &Request {
+            client: &client,
+            url: &url,
+            auth: auth.as_ref(),
+        };
+
+        /* Tables sharing a query document share a request. */
+        let mut tables: HashMap<&str, Vec<&ProtoDataTableId>> = HashMap::new();
+        for dt_id in query.keys() {
+            let table = ProtPlugin::get_datatable_id(dt_id)
+                .try_get_from(&input.data_tables)?;
+            tables
+                .entry(table.command_line.as_str())
+                .or_default()
+                .push(dt_id);
+        }
+        info!("scheduled {} queries", tables.len());
+
+        let responses = stream::iter(tables.keys())
+            .map(|doc| async move {
+                let response = request.query(doc, &self.config).await;
+                if let Err(e) = &response {
+                    warn!("query failed: {e}");
+                }
+                (*doc, response)
+            })
+            .buffer_unordered(tables.len().max(1))
+            .collect::<HashMap<_, _>>()
+            .await;
+
+        let mut data = HashMap::with_capacity(query.len());
+        for (doc, dt_ids) in &tables {
+            let response = &responses[doc];
+            for dt_id in dt_ids {
+                let table = ProtPlugin::get_datatable_id(dt_id)
+                    .try_get_from(&input.data_tables)?;
+                let fields = query[*dt_id]
+                    .iter()
+                    .map(|df_id| {
+                        Ok((
+                            df_id,
+                            ProtPlugin::get_datafield_id(df_id)
+                                .try_get_from(&input.data_fields)?,
+                        ))
+                    })
+                    .collect::<APIResult<HashMap<_, _>>>()?;
+                data.insert(
+                    (*dt_id).clone(),
+                    collect_table(response, &table.command_name, &fields),
+                );
+            }
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,89 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fmt;
+
+use jsonpath::Selector;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use super::{DTEResult, DTError, DTWarning};
+
+/// The GraphQL response envelope.
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    #[serde(default)]
+    pub data: Option<JsonValue>,
+    #[serde(default)]
+    pub errors: Vec<GraphQLError>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GraphQLError {
+    pub message: String,
+    /// The path of the response field that caused the error.
+    #[serde(default)]
+    pub path: Vec<JsonValue>,
+}
+
+/// The rows selected from a response, with the errors reported for
+/// a partially successful query.
+#[derive(Debug)]
+pub struct Rows {
+    pub rows: Vec<JsonValue>,
+    pub warnings: Vec<DTWarning>,
+}
+
+impl Response {
+    pub fn from_slice(body: &[u8]) -> DTEResult<Self> {
+        serde_json::from_slice(body).map_err(DTError::DeserializeResponse)
+    }
+
+    /// Select the rows of a table from the response data. Errors
+    /// fail the table when no data was returned; otherwise the data
+    /// is assumed to be partial and the errors become warnings.
+    pub fn rows(&self, path: &str) -> DTEResult<Rows> {
+        let data = match &self.data {
+            Some(JsonValue::Null) | None => {
+                return Err(match self.errors.is_empty() {
+                    true => DTError::NoData,
+                    false => DTError::Query(self.errors.clone()),
+                })
+            }
+            Some(data) => data,
+        };
+
+        let selector = Selector::new(path)
+            .map_err(|e| DTError::JsonPath(path.to_string(), e.to_string()))?;
+
+        Ok(Rows {
+            rows: selector.find(data).cloned().collect(),
+            warnings: self
+                .errors
+                .iter()
+                .cloned()
+                .map(DTWarning::Query)
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.path.is_empty() {
+            let path = self
+                .path
+                .iter()
+                .map(|step| match step {
+                    JsonValue::String(s) => s.clone(),
+                    step => step.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(".");
+            write!(f, " (at {path})")?;
+        }
+        Ok(())
+    }
+}
@@ -9,7 +9,9 @@ use std::sync::Arc;
 
 use agent_utils::TryAppend;
 use etc_base::{DataFieldId, DataTableId};
+use protocol::CounterMode;
 use serde::{Deserialize, Serialize};
+use unit::Dimension;
 use value::Type;
 
 use crate::error::{TypeError, TypeResult};
@@ -84,8 +86,17 @@ pub enum ParameterType {
     Enum,
     Counter,
     Difference,
+    /// Per-second rate of a counter.
+    Rate,
     #[serde(alias = "ipaddr")]
     IpAddress,
+    /// Quantities, in the reference unit of their dimension: bytes,
+    /// bytes per second, operations per second and seconds per
+    /// operation respectively.
+    Information,
+    Bandwidth,
+    IoPerformance,
+    IoLatency,
 }
 
 impl FieldSpec {
@@ -111,7 +122,39 @@ impl FieldSpec {
             ),
             ParameterType::Counter => Ok(Type::Float),
             ParameterType::Difference => Ok(Type::Integer),
+            ParameterType::Rate => Ok(Type::Float),
             ParameterType::IpAddress => Ok(Type::Ipv4Address),
+            ParameterType::Information
+            | ParameterType::Bandwidth
+            | ParameterType::IoPerformance
+            | ParameterType::IoLatency => {
+                Ok(Type::Quantity(self.parameter_type.dimension().unwrap()))
+            }
+        }
+    }
+}
+
+impl ParameterType {
+    /// How the counter db reports samples of this type, if the type
+    /// is a counter.
+    pub fn counter_mode(self) -> Option<CounterMode> {
+        match self {
+            ParameterType::Counter | ParameterType::Rate => {
+                Some(CounterMode::Rate)
+            }
+            ParameterType::Difference => Some(CounterMode::Difference),
+            _ => None,
+        }
+    }
+
+    /// The dimension of quantity types.
+    pub fn dimension(self) -> Option<Dimension> {
+        match self {
+            ParameterType::Information => Some(Dimension::Information),
+            ParameterType::Bandwidth => Some(Dimension::Bandwidth),
+            ParameterType::IoPerformance => Some(Dimension::IOPerformance),
+            ParameterType::IoLatency => Some(Dimension::IOLatency),
+            _ => None,
         }
     }
 }
@@ -147,7 +190,12 @@ impl Display for ParameterType {
                 ParameterType::Enum => "Enum",
                 ParameterType::Counter => "Counter",
                 ParameterType::Difference => "Difference",
+                ParameterType::Rate => "Rate",
                 ParameterType::IpAddress => "Ip Address",
+                ParameterType::Information => "Information",
+                ParameterType::Bandwidth => "Bandwidth",
+                ParameterType::IoPerformance => "IO Performance",
+                ParameterType::IoLatency => "IO Latency",
             }
         )
     }
@@ -2,6 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+mod allowlist;
 mod config;
 pub mod error;
 mod input;
@@ -12,6 +13,7 @@ pub mod soap;
 pub mod azure;
 pub mod cache;
 pub mod elastic;
+pub mod graphql;
 pub mod ldap;
 pub mod mirth;
 pub mod ms_graph;
@@ -20,6 +22,7 @@ pub mod unity;
 pub mod vmware;
 pub mod xenapp_director;
 
+pub use allowlist::FieldAllowlist;
 pub use config::Config;
 pub use error::{DTWarning, Error, Result};
 pub use input::Input;
@@ -17,8 +17,8 @@ use uuid::Uuid;
 use value::{DataError, Value};
 
 use super::responses::{
-    ChannelConnector, ChannelGroups, ChannelStatistics, ChannelStatuss,
-    ConnectorType,
+    ChannelConnector, ChannelGroups, ChannelStatistic, ChannelStatistics,
+    ChannelStatuss, ConnectorStatistic, ConnectorType,
 };
 use super::{api, smb, Config, DTEResult, DTWResult, Error, Result};
 use crate::error::Result as APIResult;
@@ -91,6 +91,7 @@ impl Plugin {
     async fn request_datatable<'a>(&self, req: Request<'a>) -> TableData {
         // channel_status: gname, cid, name, state
         // channel_stats: gname, cid, rx, tx, err, filt, queue
+        // connector_stats: cid, name, mdid, type, rx, tx, err, filt, queue
         // connectors: gname, cid, path, scheme, count, type
         // sys_info: jvm, os, db
 
@@ -98,6 +99,7 @@ impl Plugin {
             "channel_groups" => self.request_channel_groups(req).await,
             "channel_status" => self.request_channel_status(req).await,
             "channel_stats" => self.request_channel_stats(req).await,
+            "connector_stats" => self.request_connector_stats(req).await,
             "connectors" => self.request_connectors(req).await,
             "sys_info" => self.request_system_info(req).await,
             _ => Err(crate::error::DTError::CommandNotFound(
@@ -175,6 +177,117 @@ impl Plugin {
         })
     }
 
+    async fn request_connector_statistic(
+        &self,
+        id: Uuid,
+        api: Arc<api::Client>,
+        connector: ChannelConnector,
+        connector_type: ConnectorType,
+    ) -> DTWResult<ConnectorStatistic> {
+        let meta_data_id = connector.meta_data_id.data;
+        let statistics: ChannelStatistic = api
+            .get_endpoint(format!(
+                "channels/{id}/connectorStatistics/{meta_data_id}"
+            ))
+            .await
+            .map_err(DTError::Api)
+            .tap_err(|e| {
+                error!("failed to retrieve connector statistics: {e}")
+            })?;
+        trace!("retrieved connector statistics: {statistics:#?}");
+
+        Ok(ConnectorStatistic {
+            name: connector.name.data,
+            meta_data_id,
+            connector_type,
+            statistics,
+        })
+    }
+
+    async fn request_connector_stats_for_channel(
+        &self,
+        id: Uuid,
+        api: Arc<api::Client>,
+    ) -> DTWResult<Vec<DTWResult<ConnectorStatistic>>> {
+        let channel: ChannelSpecific = api
+            .get_endpoint(format!("channels/{id}"))
+            .await
+            .map_err(DTError::Api)
+            .tap_err(|e| error!("failed to retrieve channel info: {e}"))?;
+        trace!("retrieved channel info: {channel:#?}");
+
+        let requests = [(channel.source_connector, ConnectorType::Source)]
+            .into_iter()
+            .chain(
+                channel
+                    .destination_connectors
+                    .data
+                    .into_iter()
+                    .map(|conn| (conn, ConnectorType::Destination)),
+            )
+            .map(|(conn, ctype)| {
+                self.request_connector_statistic(id, api.clone(), conn, ctype)
+            })
+            .collect::<Vec<_>>();
+
+        let num_requests = requests.len();
+        debug!("scheduled {num_requests} requests for connector statistics");
+
+        Ok(stream::iter(requests)
+            .buffer_unordered(num_requests)
+            .collect()
+            .await)
+    }
+
+    async fn request_connector_stats<'a>(&self, req: Request<'a>) -> TableData {
+        let channelgroups = self.get_channelgroups(req.api.clone()).await?;
+
+        let requests = channelgroups.get_channels().into_iter().map(|id| {
+            self.request_connector_stats_for_channel(id, req.api.clone())
+        });
+
+        let num_requests = requests.len();
+        debug!("scheduled {num_requests} requests for channel connectors");
+        let results: Vec<_> = stream::iter(requests)
+            .buffer_unordered(num_requests)
+            .collect()
+            .await;
+
+        let mut rows = Vec::with_capacity(results.len());
+        let mut warns = Vec::new();
+
+        for result in results.into_iter().flat_map(|r| match r {
+            Ok(r) => r,
+            Err(e) => vec![Err(e)],
+        }) {
+            match result {
+                Err(e) => warns.push(e),
+                Ok(conn) => rows.push(
+                    req.datafields
+                        .iter()
+                        .map(|(id, field)| {
+                            (
+                                (*id).clone(),
+                                conn.get_data(field, req.counter_db.clone()),
+                            )
+                        })
+                        .collect(),
+                ),
+            }
+        }
+
+        Ok(Annotated {
+            value: rows,
+            warnings: warns
+                .into_iter()
+                .map(|w| Warning {
+                    message: crate::error::DTWarning::Mirth(w),
+                    verbosity: Verbosity::Warning,
+                })
+                .collect(),
+        })
+    }
+
     async fn request_connector<'a>(
         &self,
         id: Uuid,
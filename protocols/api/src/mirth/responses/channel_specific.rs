@@ -57,14 +57,13 @@ pub struct ChannelConnector {
     pub transport_name: Value<String>,
     pub mode: Value<String>, // TODO: make enum
     pub enabled: Value<bool>,
+    pub meta_data_id: Value<u64>,
 
     #[cfg(feature = "mirth-full")]
     pub transformer: Transformer,
     #[cfg(feature = "mirth-full")]
     pub filter: Filter,
     #[cfg(feature = "mirth-full")]
-    pub meta_data_id: Value<u64>,
-    #[cfg(feature = "mirth-full")]
     pub wait_for_previous: Value<bool>,
 }
 
@@ -478,7 +477,7 @@ pub struct PruningSettings {
     pub archive_enabled: Value<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub enum ConnectorType {
     Source,
     Destination,
@@ -11,7 +11,7 @@ use value::{Data, DataError};
 
 use crate::input::FieldSpec;
 
-use super::Value;
+use super::{ConnectorType, Value};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChannelStatistics {
@@ -38,34 +38,178 @@ impl ChannelStatistic {
         field: &FieldSpec,
         counterdb: Arc<CounterDb>,
     ) -> Data {
+        let key = format!("{}.{}", self.channel_id, field.parameter_name);
         match field.parameter_header.as_str() {
             "channel_id" => self.channel_id.to_smartm_value(),
-            "received" => self.received.to_smartm_counter(
-                self.channel_id.data,
-                field,
-                counterdb,
-            ),
-            "sent" => self.sent.to_smartm_counter(
-                self.channel_id.data,
+            _ => self.get_counter(key, field, counterdb),
+        }
+    }
+
+    fn get_counter(
+        &self,
+        key: String,
+        field: &FieldSpec,
+        counterdb: Arc<CounterDb>,
+    ) -> Data {
+        match field.parameter_header.as_str() {
+            "received" => {
+                self.received.to_smartm_counter(key, field, counterdb)
+            }
+            "sent" => self.sent.to_smartm_counter(key, field, counterdb),
+            "error" => self.error.to_smartm_counter(key, field, counterdb),
+            "filtered" => {
+                self.filtered.to_smartm_counter(key, field, counterdb)
+            }
+            "queued" => self.queued.to_smartm_counter(key, field, counterdb),
+            _ => Err(DataError::Missing),
+        }
+    }
+}
+
+/// Statistics of a single connector within a channel, as returned by
+/// `channels/{id}/connectorStatistics/{metaDataId}`.
+#[derive(Debug)]
+pub struct ConnectorStatistic {
+    pub name: String,
+    pub meta_data_id: u64,
+    pub connector_type: ConnectorType,
+    pub statistics: ChannelStatistic,
+}
+
+impl ConnectorStatistic {
+    /// Counters are tracked per connector; the meta data id is the only
+    /// stable identifier of a connector within its channel.
+    pub fn counter_key(&self, field: &FieldSpec) -> String {
+        format!(
+            "{}.{}.{}",
+            self.statistics.channel_id, self.meta_data_id, field.parameter_name
+        )
+    }
+
+    pub fn get_data(
+        &self,
+        field: &FieldSpec,
+        counterdb: Arc<CounterDb>,
+    ) -> Data {
+        match field.parameter_header.as_str() {
+            "channel_id" => self.statistics.channel_id.to_smartm_value(),
+            "connector_name" => {
+                Ok(value::Value::UnicodeString(self.name.clone()))
+            }
+            "meta_data_id" => {
+                Ok(value::Value::Integer(self.meta_data_id as i64))
+            }
+            "type" => self.connector_type.to_smartm_value(field),
+            _ => self.statistics.get_counter(
+                self.counter_key(field),
                 field,
                 counterdb,
             ),
-            "error" => self.error.to_smartm_counter(
-                self.channel_id.data,
-                field,
-                counterdb,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use protocol::CounterDb;
+    use value::{DataError, Value};
+
+    use super::{ChannelStatistic, ConnectorStatistic};
+    use crate::input::{FieldSpec, ParameterType};
+    use crate::mirth::responses::ConnectorType;
+
+    const CONNECTOR_STATISTICS: &str = r#"<channelStatistics>
+  <serverId>c8f1fb4c-7c61-4b3b-a2b1-3a3e62c4d0a7</serverId>
+  <channelId>0b2f4a4e-4c1e-4a8b-9d1a-2a1c9b3e5f60</channelId>
+  <received>120</received>
+  <sent>117</sent>
+  <error>2</error>
+  <filtered>1</filtered>
+  <queued>0</queued>
+</channelStatistics>"#;
+
+    fn field(header: &str, parameter_type: ParameterType) -> FieldSpec {
+        FieldSpec {
+            parameter_name: header.to_string(),
+            parameter_header: header.to_string(),
+            parameter_type,
+            values: None,
+            is_key: false,
+        }
+    }
+
+    fn connector(meta_data_id: u64) -> ConnectorStatistic {
+        ConnectorStatistic {
+            name: format!("Destination {meta_data_id}"),
+            meta_data_id,
+            connector_type: ConnectorType::Destination,
+            statistics: serde_xml_rs::from_str::<ChannelStatistic>(
+                CONNECTOR_STATISTICS,
+            )
+            .unwrap(),
+        }
+    }
+
+    fn counter_db() -> Arc<CounterDb> {
+        Arc::new(CounterDb::new(PathBuf::from("/nonexistent/counters.json")))
+    }
+
+    #[test]
+    fn parse_connector_statistics() {
+        let conn = connector(1);
+        let db = counter_db();
+        assert_eq!(
+            conn.get_data(
+                &field("received", ParameterType::Integer),
+                db.clone()
             ),
-            "filtered" => self.filtered.to_smartm_counter(
-                self.channel_id.data,
-                field,
-                counterdb,
+            Ok(Value::Integer(120))
+        );
+        assert_eq!(
+            conn.get_data(&field("error", ParameterType::Integer), db.clone()),
+            Ok(Value::Integer(2))
+        );
+        assert_eq!(
+            conn.get_data(
+                &field("meta_data_id", ParameterType::Integer),
+                db.clone()
             ),
-            "queued" => self.queued.to_smartm_counter(
-                self.channel_id.data,
-                field,
-                counterdb,
+            Ok(Value::Integer(1))
+        );
+        assert_eq!(
+            conn.get_data(
+                &field("connector_name", ParameterType::String),
+                db.clone()
             ),
-            _ => Err(DataError::Missing),
-        }
+            Ok(Value::UnicodeString("Destination 1".to_string()))
+        );
+        assert_eq!(
+            conn.get_data(&field("channel_id", ParameterType::String), db),
+            Ok(Value::UnicodeString(
+                "0b2f4a4e-4c1e-4a8b-9d1a-2a1c9b3e5f60".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn connector_counters_are_keyed_per_connector() {
+        let field = field("sent", ParameterType::Counter);
+        let source = connector(0);
+        let dest = connector(1);
+        assert_eq!(
+            source.counter_key(&field),
+            "0b2f4a4e-4c1e-4a8b-9d1a-2a1c9b3e5f60.0.sent"
+        );
+        assert_eq!(
+            dest.counter_key(&field),
+            "0b2f4a4e-4c1e-4a8b-9d1a-2a1c9b3e5f60.1.sent"
+        );
+        assert_eq!(
+            dest.get_data(&field, counter_db()),
+            Err(DataError::CounterPending)
+        );
     }
 }
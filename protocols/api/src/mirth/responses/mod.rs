@@ -24,7 +24,9 @@ pub use system_stats::SystemStats;
 
 pub use channel_group::ChannelGroups;
 pub use channel_specific::{ChannelConnector, ChannelSpecific, ConnectorType};
-pub use channel_statistics::ChannelStatistics;
+pub use channel_statistics::{
+    ChannelStatistic, ChannelStatistics, ConnectorStatistic,
+};
 pub use channel_status::ChannelStatuss;
 pub use system_info::SystemInfo;
 
@@ -33,7 +35,7 @@ pub use system_stats::SystemStats;
 use uuid::Uuid;
 use value::{Data, EnumValue};
 
-use crate::input::{FieldSpec, ParameterType, ValueTypes};
+use crate::input::{FieldSpec, ValueTypes};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Value<T> {
@@ -89,18 +91,15 @@ impl Value<u64> {
     }
     pub fn to_smartm_counter(
         &self,
-        channel_id: Uuid,
+        key: String,
         field: &FieldSpec,
         counter_db: Arc<CounterDb>,
     ) -> Data {
-        let key = format!("{}.{}", channel_id, field.parameter_name);
-        let now = SystemTime::now();
-        match field.parameter_type {
-            ParameterType::Counter => counter_db.counter(key, self.data, now),
-            ParameterType::Difference => {
-                counter_db.difference(key, self.data, now)
+        match field.parameter_type.counter_mode() {
+            Some(mode) => {
+                counter_db.sample(key, self.data, SystemTime::now(), mode)
             }
-            _ => self.to_smartm_value(),
+            None => self.to_smartm_value(),
         }
     }
 }
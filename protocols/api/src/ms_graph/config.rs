@@ -2,27 +2,30 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
 
 use reqwest::Client;
 
 use agent_utils::KeyVault;
 use log::warn;
-use rest_protocol::{
-    http::{AuthType, BodyType, ContentType, HTTPMethod},
-    Application, Template,
-};
 use serde::{Deserialize, Serialize};
-use uritemplate::UriTemplate;
 
 use crate::ms_graph::filters::{OnedriveUsage, OutlookUsage, SharepointUsage};
+use crate::ms_graph::token::{Scopes, Token, TokenError, TokenResponse};
 use crate::ms_graph::{Error, Result};
 
+static LOGIN_ENDPOINT: &str = "https://login.microsoftonline.com";
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct Config {
     pub credentials: Option<Credentials>,
     #[serde(default)]
     pub rapports: Option<RapportConfig>,
+    /// Scopes to request a token for, by endpoint prefix. Endpoints
+    /// without a matching prefix use the default Graph scope.
+    #[serde(default)]
+    pub scopes: BTreeMap<String, Scopes>,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
@@ -42,69 +45,85 @@ impl Config {
             Err(Error::NoPassword)
         }
     }
+
+    pub async fn acquire_token(
+        &self,
+        vault: &KeyVault,
+        scopes: &Scopes,
+    ) -> Result<Token> {
+        if let Some(cl) = &self.credentials {
+            cl.acquire_token(Some(vault), scopes).await
+        } else {
+            Err(Error::NoPassword)
+        }
+    }
+
+    /// The scopes required for an endpoint. The longest matching
+    /// prefix wins.
+    pub fn scopes_for(&self, endpoint: &str) -> Scopes {
+        self.scopes
+            .iter()
+            .filter(|(prefix, _)| endpoint.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, scopes)| scopes.clone())
+            .unwrap_or_default()
+    }
 }
 
 impl Credentials {
+    /// Log in with the default Graph scope.
     pub async fn login(&self, vault: Option<&KeyVault>) -> Result<Client> {
-        let mut data_template: HashMap<String, Template> = HashMap::new();
-        data_template.insert(
-            String::from("grant_type"),
-            Template::parse("client_credentials")?,
-        );
-        data_template.insert(
-            String::from("client_secret"),
-            Template::parse("{{client_secret}}")?,
-        );
-        data_template.insert(
-            String::from("client_id"),
-            Template::parse("{{client_id}}")?,
-        );
-        data_template.insert(
-            String::from("scope"),
-            Template::parse("https://graph.microsoft.com/.default")?,
-        );
-
-        let mut credentials: HashMap<String, String> = HashMap::new();
-        if let Some(tenant_id) = self.tenant_id.as_ref() {
-            credentials.insert(String::from("tenant_id"), tenant_id.clone());
-        } else {
+        self.acquire_token(vault, &Scopes::default())
+            .await?
+            .client()
+    }
+
+    /// Request an application token for the given scopes.
+    pub async fn acquire_token(
+        &self,
+        vault: Option<&KeyVault>,
+        scopes: &Scopes,
+    ) -> Result<Token> {
+        let tenant_id = self.tenant_id.as_deref().unwrap_or_else(|| {
             warn!("No tenant given!");
-        }
-        credentials.insert(String::from("client_id"), self.client_id.clone());
-        credentials.insert(
-            String::from("client_secret"),
-            match vault.unwrap_or(&KeyVault::Identity) {
-                KeyVault::Identity => {
-                    self.client_secret.as_ref().cloned().unwrap_or_default()
-                }
-                KeyVault::KeyReader(vault) => {
-                    vault
-                        .retrieve_password(
-                            self.client_name
-                                .as_ref()
-                                .cloned()
-                                .unwrap_or_default(),
-                        )
-                        .await?
-                }
-            },
-        );
-
-        let mut rest_application = Application {
-            content_type: ContentType::JSON,
-            auth_type: AuthType::Token(Template::parse(
-                "Bearer {{access_token}}"
-            )?),
-            login_url: UriTemplate::new("https://login.microsoftonline.com/{tenant_id}/oauth2/v2.0/token"),
-            login_method: HTTPMethod::POST,
-            login_body_type: BodyType::FormUrlEncoded,
-            login_data: data_template
+            ""
+        });
+        let client_secret = match vault.unwrap_or(&KeyVault::Identity) {
+            KeyVault::Identity => {
+                self.client_secret.as_ref().cloned().unwrap_or_default()
+            }
+            KeyVault::KeyReader(vault) => {
+                vault
+                    .retrieve_password(
+                        self.client_name.as_ref().cloned().unwrap_or_default(),
+                    )
+                    .await?
+            }
         };
 
-        rest_application
-            .login(&credentials)
-            .await
-            .map_err(Error::RESTError)
+        let now = SystemTime::now();
+        let response = Client::new()
+            .post(format!("{LOGIN_ENDPOINT}/{tenant_id}/oauth2/v2.0/token"))
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", client_secret.as_str()),
+                ("scope", scopes.to_param().as_str()),
+            ])
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let token: TokenResponse = response.json().await?;
+            Ok(Token::new(
+                token.access_token,
+                Duration::from_secs(token.expires_in),
+                now,
+            ))
+        } else {
+            let error: TokenError = response.json().await?;
+            Err(error.into_error(scopes.clone()))
+        }
     }
 }
 
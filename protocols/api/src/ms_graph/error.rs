@@ -8,6 +8,7 @@ use thiserror::Error;
 use rest_protocol::{RESTError, TemplateError};
 use value::Type;
 
+use super::token::Scopes;
 use crate::error::TypeError;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -34,6 +35,12 @@ pub enum Error {
     ReqwestError(#[from] reqwest::Error),
     #[error("No ClientSecret or ClientName given")]
     NoPassword,
+    #[error("Permission denied for scopes '{0}': {1}. Grant the required application permissions and admin consent in the app registration")]
+    ConsentRequired(Scopes, String),
+    #[error("Token request for scopes '{0}' failed: {1}")]
+    TokenRequest(Scopes, String),
+    #[error("Received an invalid access token")]
+    InvalidToken,
 }
 
 impl Error {
@@ -62,8 +69,10 @@ pub enum DTError {
     ToManyRetries(String),
     #[error("Time went backwards")]
     SystemTimeError,
-    #[error("Cannot request data from url: {0}. Check if the permissions of the user are correct")]
-    Forbidden(String),
+    #[error("Cannot request data from url: {0} with a token for scopes '{1}'. Check if the application has the required permissions and admin consent")]
+    Forbidden(String, Scopes),
+    #[error("No token for scopes '{0}': {1}")]
+    NoToken(Scopes, String),
     #[error("{0}")]
     EtcSyntaxError(String),
     #[error("cannot parse json to an {1}: {0}")]
@@ -8,10 +8,12 @@ mod error;
 pub(super) mod filters;
 pub(super) mod parsers;
 mod plugin;
+mod token;
 
 pub use config::{Config, Credentials};
 pub use error::{DTError, DTWarning, Error, Result};
-pub use plugin::Plugin;
+pub use plugin::{request_with_retry, Plugin};
+pub use token::{Scopes, Token, TokenCache, TokenError, GRAPH_DEFAULT_SCOPE};
 
 pub use definitions::{Organization, ResourceResponse};
 pub mod requests;
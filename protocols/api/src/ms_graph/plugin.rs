@@ -3,22 +3,26 @@
  ******************************************************************************/
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use futures::{stream, StreamExt};
 use jsonpath::Selector;
 use log::{info, trace, warn};
-use reqwest::{Client, Response, StatusCode};
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use tokio::time::{sleep, Duration};
 
 use agent_utils::{KeyVault, TryGetFrom};
 use etc_base::{Annotated, ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap};
+use protocol::http::{RequestStats, Response};
 use value::DataError;
 
-use super::error::{DTEResult, DTError, Result};
+use super::error::{DTEResult, DTError, Error, Result};
+use super::token::{Scopes, TokenCache};
 use crate::error::Result as APIResult;
 use crate::input::{FieldSpec, PluginId, TableSpec};
 use crate::ms_graph::definitions::LicenseSku;
@@ -33,11 +37,14 @@ pub static MSGRAPH_ENDPOINT: &str = "https://graph.microsoft.com/v1.0";
 pub struct Plugin {
     key_vault: KeyVault,
     pub config: Config,
+    tokens: TokenCache,
+    stats: Arc<RequestStats>,
 }
 
 #[async_recursion]
 pub async fn request_with_retry(
     client: &Client,
+    stats: &RequestStats,
     url: &str,
     retries: u16,
 ) -> DTEResult<Response> {
@@ -46,13 +53,13 @@ pub async fn request_with_retry(
         warn!("no retries left for {}", &url);
         Err(DTError::ToManyRetries(url.to_string()))
     } else {
-        match client.get(url).send().await {
+        match stats.send(client.get(url)).await {
             Err(e) => Err(DTError::ReqwestError(e)),
             Ok(r) => {
-                if r.status() == StatusCode::TOO_MANY_REQUESTS {
+                if r.status == StatusCode::TOO_MANY_REQUESTS {
                     info!("request for '{}' failed due to throtteling. {} retries left", url, retries);
                     sleep(Duration::from_secs(1)).await;
-                    request_with_retry(client, url, retries - 1).await
+                    request_with_retry(client, stats, url, retries - 1).await
                 } else {
                     Ok(r)
                 }
@@ -85,13 +92,40 @@ struct Group {
 }
 
 impl Plugin {
-    pub fn new(key_vault: KeyVault, config: Config) -> Result<Self> {
-        Ok(Self { key_vault, config })
+    pub fn new(
+        key_vault: KeyVault,
+        config: Config,
+        stats: Arc<RequestStats>,
+    ) -> Result<Self> {
+        Ok(Self {
+            key_vault,
+            config,
+            tokens: TokenCache::new(),
+            stats,
+        })
+    }
+
+    /// A client authenticated for the given scopes, reusing a cached
+    /// token if possible.
+    async fn client(&self, scopes: &Scopes) -> Result<Client> {
+        let now = SystemTime::now();
+        let token = match self.tokens.get(scopes, now) {
+            Some(token) => token,
+            None => {
+                info!("requesting token for scopes '{scopes}'");
+                let token =
+                    self.config.acquire_token(&self.key_vault, scopes).await?;
+                self.tokens.insert(scopes.clone(), token.clone());
+                token
+            }
+        };
+        token.client()
     }
 
     async fn exec_query(
         &self,
         client: &Client,
+        scopes: &Scopes,
         dt_id: ProtoDataTableId,
         command: TableSpec,
         fields: HashMap<ProtoDataFieldId, FieldSpec>,
@@ -103,52 +137,44 @@ impl Plugin {
         };
         let url = format!("{}/{}", MSGRAPH_ENDPOINT, endpoint);
         info!("retrieving datatable: {:?} ({})", &dt_id, &url);
-        let response = match request_with_retry(client, &url, 3).await {
-            Err(e) => return (dt_id, Err(e.to_api())),
-            Ok(r) => r,
-        };
-        let status = response.status();
+        let response =
+            match request_with_retry(client, &self.stats, &url, 3).await {
+                Err(e) => return (dt_id, Err(e.to_api())),
+                Ok(r) => r,
+            };
+        let status = response.status;
         info!("{:?} returned status {}", &dt_id, &status);
+        let response = response.text();
 
         (
             dt_id.clone(),
-            match response.text().await {
-                Err(e) => Err(DTError::ReqwestError(e).to_api()),
-                Ok(response) => {
-                    if status == StatusCode::FORBIDDEN {
-                        warn!("{:?} forbidden request", &dt_id);
-                        Err(DTError::Forbidden(url).to_api())
-                    } else {
-                        match command.command_name.as_str() {
-                            "get_state" => self.get_state(
-                                &command.command_line,
-                                response,
-                                fields,
-                            ),
-                            "get_rapport" => self.get_rapport(
-                                &command.command_line,
-                                response,
-                                fields,
-                            ),
-                            "get_internal_table_with_root" => self
-                                .get_internal_table_with_rootid(
-                                    &command.command_line,
-                                    table_args,
-                                    response,
-                                    fields,
-                                ),
-                            "get_channels" => {
-                                self.get_channels(client, response, fields)
-                                    .await
-                            }
-                            "get_licenceskus" => {
-                                self.get_license_skus(client, response, fields)
-                                    .await
-                            }
-                            s => Err(DTError::CommandNotFound(s.to_string())
-                                .to_api()),
-                        }
+            if status == StatusCode::FORBIDDEN {
+                warn!("{:?} forbidden request", &dt_id);
+                Err(DTError::Forbidden(url, scopes.clone()).to_api())
+            } else {
+                match command.command_name.as_str() {
+                    "get_state" => {
+                        self.get_state(&command.command_line, response, fields)
                     }
+                    "get_rapport" => self.get_rapport(
+                        &command.command_line,
+                        response,
+                        fields,
+                    ),
+                    "get_internal_table_with_root" => self
+                        .get_internal_table_with_rootid(
+                            &command.command_line,
+                            table_args,
+                            response,
+                            fields,
+                        ),
+                    "get_channels" => {
+                        self.get_channels(client, response, fields).await
+                    }
+                    "get_licenceskus" => {
+                        self.get_license_skus(client, response, fields).await
+                    }
+                    s => Err(DTError::CommandNotFound(s.to_string()).to_api()),
                 }
             },
         )
@@ -175,13 +201,12 @@ impl Plugin {
         for team in teams {
             let response = request_with_retry(
                 client,
+                &self.stats,
                 &format!("{}/teams/{}/channels", MSGRAPH_ENDPOINT, team.id),
                 10,
             )
             .await?
-            .text()
-            .await
-            .map_err(|e| DTError::ReqwestError(e).to_api())?;
+            .text();
             let data: MappedServiceResponse =
                 serde_json::from_str(&response)
                     .map_err(DTError::SerdeJsonError)?;
@@ -217,10 +242,14 @@ impl Plugin {
 
         let reverence = {
             const LICENSE_PLAN_REFERENCE: &str = "https://download.microsoft.com/download/e/3/e/e3e9faf2-f28b-490a-9ada-c6089a1fc5b0/Product%20names%20and%20service%20plan%20identifiers%20for%20licensing.csv";
-            let response =
-                request_with_retry(client, LICENSE_PLAN_REFERENCE, 3).await?;
-            let response =
-                response.text().await.map_err(DTError::ReqwestError)?;
+            let response = request_with_retry(
+                client,
+                &self.stats,
+                LICENSE_PLAN_REFERENCE,
+                3,
+            )
+            .await?;
+            let response = response.text();
             deserialize_csv(response)?
         };
 
@@ -439,8 +468,9 @@ impl APIPlugin for Plugin {
     ) -> APIResult<DataMap> {
         info!("Using MS Graph plugin");
 
-        let client = self.config.login(&self.key_vault).await?;
-
+        let mut clients: HashMap<Scopes, std::result::Result<Client, String>> =
+            HashMap::new();
+        let mut failed = Vec::new();
         let mut requests = Vec::new();
         for (dt_id, df_ids) in query {
             let command = ProtPlugin::get_datatable_id(dt_id)
@@ -459,21 +489,55 @@ impl APIPlugin for Plugin {
                     ))
                 })
                 .collect::<Result<HashMap<ProtoDataFieldId, FieldSpec>>>()?;
+            let endpoint = match command.command_line.split_once('|') {
+                Some((endpoint, _)) => endpoint,
+                None => command.command_line.as_str(),
+            };
+            let scopes = self.config.scopes_for(endpoint);
+            if !clients.contains_key(&scopes) {
+                let client = match self.client(&scopes).await {
+                    Ok(client) => Ok(client),
+                    Err(Error::NoPassword) => {
+                        return Err(Error::NoPassword.to_api())
+                    }
+                    Err(e) => {
+                        warn!("{e}");
+                        Err(e.to_string())
+                    }
+                };
+                clients.insert(scopes.clone(), client);
+            }
+            let client = match clients.get(&scopes).unwrap() {
+                Ok(client) => client.clone(),
+                Err(e) => {
+                    failed.push((
+                        dt_id.clone(),
+                        Err(DTError::NoToken(scopes, e.clone()).to_api()),
+                    ));
+                    continue;
+                }
+            };
             info!(
                 "planning to request {:?} with {} fields",
                 &dt_id,
                 fields.len()
             );
-            requests.push(self.exec_query(
-                &client,
-                dt_id.clone(),
-                command.clone(),
-                fields,
-            ));
+            requests.push(async move {
+                self.exec_query(
+                    &client,
+                    &scopes,
+                    dt_id.clone(),
+                    command.clone(),
+                    fields,
+                )
+                .await
+            });
         }
 
         info!("planned {} requests", requests.len());
-        let data = stream::iter(requests).buffer_unordered(8).collect().await;
+        let mut data: DataMap =
+            stream::iter(requests).buffer_unordered(8).collect().await;
+        data.extend(failed);
         info!("all requests completed");
 
         Ok(data)
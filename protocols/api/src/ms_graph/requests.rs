@@ -2,6 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use protocol::http::RequestStats;
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 
@@ -14,8 +15,8 @@ pub async fn get_object<T: DeserializeOwned>(
     endpoint: &str,
 ) -> Result<Vec<T>> {
     let url = format!("{}/{}", MSGRAPH_ENDPOINT, endpoint);
-    let response = request_with_retry(client, &url, 3)
+    let response = request_with_retry(client, &RequestStats::new(), &url, 3)
         .await
         .map_err(|e| e.to_err())?;
-    Ok(response.json::<ResourceResponse<T>>().await?.value)
+    Ok(response.json::<ResourceResponse<T>>()?.value)
 }
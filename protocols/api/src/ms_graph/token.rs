@@ -0,0 +1,172 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::ms_graph::{Error, Result};
+
+pub const GRAPH_DEFAULT_SCOPE: &str = "https://graph.microsoft.com/.default";
+
+/// Tokens are renewed this long before they expire, to avoid
+/// expiry in the middle of a run.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Azure AD error codes indicating the application lacks (consent
+/// for) the requested permissions.
+const CONSENT_ERROR_CODES: &[u32] = &[
+    65001,   // consent required
+    70011,   // invalid scope
+    500011,  // resource principal not found in tenant
+    7000112, // application disabled
+];
+
+/// The set of scopes a token is requested for.
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug,
+)]
+#[serde(transparent)]
+pub struct Scopes(BTreeSet<String>);
+
+impl Scopes {
+    pub fn new<I, S>(scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self(scopes.into_iter().map(Into::into).collect())
+    }
+
+    /// The scope parameter for the token request.
+    pub fn to_param(&self) -> String {
+        self.0
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Default for Scopes {
+    fn default() -> Self {
+        Self::new([GRAPH_DEFAULT_SCOPE])
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_param())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    access_token: String,
+    expires_at: SystemTime,
+}
+
+impl Token {
+    pub fn new(
+        access_token: String,
+        expires_in: Duration,
+        now: SystemTime,
+    ) -> Self {
+        Self {
+            access_token,
+            expires_at: now + expires_in,
+        }
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn is_valid(&self, now: SystemTime) -> bool {
+        now + EXPIRY_MARGIN < self.expires_at
+    }
+
+    /// Build a client authenticating its requests with this token.
+    pub fn client(&self) -> Result<Client> {
+        let mut value =
+            HeaderValue::from_str(&format!("Bearer {}", self.access_token))
+                .map_err(|_| Error::InvalidToken)?;
+        value.set_sensitive(true);
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value);
+        Ok(Client::builder()
+            .cookie_store(true)
+            .default_headers(headers)
+            .build()?)
+    }
+}
+
+/// Successful response of the token endpoint.
+#[derive(Deserialize, Debug)]
+pub(super) struct TokenResponse {
+    pub access_token: String,
+    pub expires_in: u64,
+}
+
+/// Error response of the token endpoint.
+#[derive(Deserialize, Debug)]
+pub struct TokenError {
+    pub error: String,
+    #[serde(default)]
+    pub error_description: Option<String>,
+    #[serde(default)]
+    pub error_codes: Vec<u32>,
+}
+
+impl TokenError {
+    /// Permission and consent problems are reported separately, since
+    /// they must be solved in the app registration.
+    pub fn into_error(self, scopes: Scopes) -> Error {
+        let description = self.error_description.unwrap_or(self.error.clone());
+        if matches!(
+            self.error.as_str(),
+            "invalid_scope" | "unauthorized_client" | "consent_required"
+        ) || self
+            .error_codes
+            .iter()
+            .any(|code| CONSENT_ERROR_CODES.contains(code))
+        {
+            Error::ConsentRequired(scopes, description)
+        } else {
+            Error::TokenRequest(scopes, description)
+        }
+    }
+}
+
+/// Tokens by the set of scopes they were requested for.
+#[derive(Default, Debug)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<Scopes, Token>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a token for exactly these scopes, if one is cached and
+    /// not about to expire.
+    pub fn get(&self, scopes: &Scopes, now: SystemTime) -> Option<Token> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .get(scopes)
+            .filter(|token| token.is_valid(now))
+            .cloned()
+    }
+
+    pub fn insert(&self, scopes: Scopes, token: Token) {
+        self.tokens.lock().unwrap().insert(scopes, token);
+    }
+}
@@ -5,11 +5,12 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use agent_utils::{KeyVault, TryGetFrom};
 use async_trait::async_trait;
 use etc_base::{
-    AnnotatedResult, DataFieldId, DataTableId, ProtoDataFieldId,
+    Annotated, AnnotatedResult, DataFieldId, DataTableId, ProtoDataFieldId,
     ProtoDataTableId, ProtoQueryMap, ProtoRow, Protocol,
 };
 use futures::{stream, StreamExt};
@@ -18,9 +19,10 @@ use log::{debug, info, warn};
 use crate::error::{DTError, DTWarning, Error, Result, TypeError, TypeResult};
 use crate::input::PluginId;
 use crate::{
-    cache, elastic, ldap, mirth, ms_graph, proxmox, unity, vmware,
+    cache, elastic, graphql, ldap, mirth, ms_graph, proxmox, unity, vmware,
     xenapp_director, Config, Input,
 };
+use protocol::http::RequestStats;
 use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin};
 
 pub type TableData = AnnotatedResult<Vec<ProtoRow>, DTWarning, DTError>;
@@ -75,6 +77,10 @@ impl LocalPlugin for Plugin {
     ) -> Result<String> {
         let mut out = String::new();
         for (resource_id, field_ids) in query {
+            if *resource_id == RequestStats::table_id() {
+                writeln!(out, "API: request stats")?;
+                continue;
+            }
             let command = Self::get_datatable_id(resource_id)
                 .try_get_from(&input.data_tables)?;
             writeln!(
@@ -107,7 +113,7 @@ impl LocalPlugin for Plugin {
         &self,
         input: &Self::Input,
     ) -> TypeResult<HashMap<ProtoDataTableId, DataTableSpec>> {
-        Ok(input
+        let mut tables: HashMap<_, _> = input
             .data_tables
             .keys()
             .map(|dt_id| {
@@ -140,14 +146,16 @@ impl LocalPlugin for Plugin {
                     },
                 )
             })
-            .collect())
+            .collect();
+        tables.extend([RequestStats::table_spec()]);
+        Ok(tables)
     }
 
     fn get_fields(
         &self,
         input: &Self::Input,
     ) -> TypeResult<HashMap<ProtoDataFieldId, DataFieldSpec>> {
-        input
+        let mut fields: HashMap<_, _> = input
             .data_fields
             .iter()
             .map(|(df_id, field_spec)| {
@@ -156,10 +164,13 @@ impl LocalPlugin for Plugin {
                     DataFieldSpec {
                         name: field_spec.parameter_name.clone(),
                         input_type: field_spec.get_type()?,
+                        sensitive: false,
                     },
                 ))
             })
-            .collect::<TypeResult<HashMap<ProtoDataFieldId, DataFieldSpec>>>()
+            .collect::<TypeResult<_>>()?;
+        fields.extend(RequestStats::field_specs());
+        Ok(fields)
     }
 
     async fn run_queries(
@@ -170,6 +181,8 @@ impl LocalPlugin for Plugin {
     ) -> Result<DataMap> {
         let mut plugins: HashMap<PluginId, Box<dyn APIPlugin + Send>> =
             HashMap::new();
+        /* Metrics of the requests made by the HTTP-based plugins. */
+        let stats = Arc::new(RequestStats::new());
 
         if let Some(conf) = &config.vmware {
             plugins.insert(
@@ -187,6 +200,7 @@ impl LocalPlugin for Plugin {
                 Box::new(ms_graph::Plugin::new(
                     self.key_vault.clone(),
                     conf.clone(),
+                    stats.clone(),
                 )?),
             );
         }
@@ -196,6 +210,7 @@ impl LocalPlugin for Plugin {
                 Box::new(crate::azure::Plugin::new(
                     self.key_vault.clone(),
                     conf.clone(),
+                    stats.clone(),
                 )?),
             );
         }
@@ -275,14 +290,29 @@ impl LocalPlugin for Plugin {
                 )),
             );
         }
+
+        if let Some(conf) = &config.graphql {
+            plugins.insert(
+                PluginId(String::from("graphql")),
+                Box::new(graphql::Plugin::new(
+                    self.key_vault.clone(),
+                    conf.clone(),
+                )),
+            );
+        }
         debug!(
             "registerd plugins: {:?}",
             plugins.keys().collect::<HashSet<_>>()
         );
 
+        let query = config.field_allowlist.restrict_query(query);
+        let stats_table = RequestStats::table_id();
         let mut plugin_requests: HashMap<PluginId, ProtoQueryMap> =
             HashMap::with_capacity(plugins.len());
-        for (dt_id, df_ids) in query {
+        for (dt_id, df_ids) in &query {
+            if *dt_id == stats_table {
+                continue;
+            }
             let cmd = Self::get_datatable_id(dt_id)
                 .try_get_from(&input.data_tables)?;
             plugin_requests
@@ -317,7 +347,7 @@ impl LocalPlugin for Plugin {
             .await;
         info!("API requests done");
 
-        let results =
+        let mut results =
             data.into_iter()
                 .fold(HashMap::new(), |mut accum, (plugin, dm)| {
                     match dm {
@@ -333,6 +363,16 @@ impl LocalPlugin for Plugin {
                     }
                     accum
                 });
+        if query.contains_key(&stats_table) {
+            results.insert(
+                stats_table,
+                Ok(Annotated {
+                    value: stats.rows(),
+                    warnings: Vec::new(),
+                }),
+            );
+        }
+        config.field_allowlist.apply(&mut results);
 
         Ok(results)
     }
@@ -94,7 +94,7 @@ impl<'a> Client<'a> {
         Ok(())
     }
 
-    async fn request<T: DeserializeOwned, U: Display + IntoUrl>(
+    pub(crate) async fn request<T: DeserializeOwned, U: Display + IntoUrl>(
         &self,
         url: U,
     ) -> DTEResult<T> {
@@ -135,6 +135,14 @@ impl<'a> Client<'a> {
     pub(crate) fn node_resource(&self, resource: &str) -> String {
         format!("{}/nodes/{}/{}", self.base_url, self.node, resource)
     }
+    pub(crate) fn guest_resource(
+        &self,
+        guest_type: VmType,
+        vmid: u64,
+        resource: &str,
+    ) -> String {
+        self.node_resource(&format!("{guest_type}/{vmid}/{resource}"))
+    }
     pub async fn request_noderesource<T: Resource>(&self) -> DTEResult<T> {
         self.request(self.node_resource(T::ENDPOINT)).await
     }
@@ -170,7 +178,7 @@ impl<'a> Client<'a> {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) enum VmType {
     Qemu,
     Lxc,
@@ -196,7 +204,7 @@ impl Display for VmType {
             f,
             "{}",
             match self {
-                Self::Lxc => "lcx",
+                Self::Lxc => "lxc",
                 Self::Qemu => "qemu",
             }
         )
@@ -0,0 +1,560 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::{
+    collections::HashMap, fmt::Display, mem, sync::Arc, time::SystemTime,
+};
+
+use chrono::{DateTime, Duration};
+use etc_base::{ProtoDataFieldId, ProtoRow};
+use futures::{stream, StreamExt};
+use protocol::CounterDb;
+use serde::{Deserialize, Serialize};
+use tap::Pipe;
+use value::{Data, DataError, EnumValue, Value};
+
+use crate::input::{FieldSpec, ParameterType, ValueTypes};
+
+use super::client::VmType;
+use super::resource::Resource;
+use super::{Client, DTEResult};
+
+/// Current status of a QEMU VM or LXC container. Stopped guests
+/// omit most usage fields, so these are all optional.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestStatus {
+    #[serde(skip_deserializing)]
+    vmid: u64,
+    #[serde(skip_deserializing, default = "default_guest_type")]
+    guest_type: VmType,
+    #[serde(default)]
+    name: String,
+    status: GuestState,
+    #[serde(default)]
+    uptime: u64,
+    cpu: Option<f64>,
+    cpus: Option<f64>,
+    mem: Option<i64>,
+    maxmem: Option<i64>,
+    disk: Option<i64>,
+    maxdisk: Option<i64>,
+    diskread: Option<u64>,
+    diskwrite: Option<u64>,
+    netin: Option<u64>,
+    netout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuestState {
+    Running,
+    Stopped,
+    Paused,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Result of the most recent backup (vzdump) task for a guest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestBackup {
+    vmid: u64,
+    guest_type: VmType,
+    task: Option<BackupTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupTask {
+    upid: String,
+    starttime: i64,
+    endtime: Option<i64>,
+    /// "OK", "WARNINGS: <n>" or an error message; absent while
+    /// the task is running.
+    status: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupState {
+    Ok,
+    Warning,
+    Failed,
+    Running,
+    Never,
+}
+
+fn default_guest_type() -> VmType {
+    VmType::Qemu
+}
+
+async fn list_guests(client: &Client<'_>) -> DTEResult<Vec<(VmType, u64)>> {
+    Ok(client
+        .get_qemuids()
+        .await?
+        .into_iter()
+        .map(|vmid| (VmType::Qemu, vmid))
+        .chain(
+            client
+                .get_lxcids()
+                .await?
+                .into_iter()
+                .map(|vmid| (VmType::Lxc, vmid)),
+        )
+        .collect())
+}
+
+#[async_trait::async_trait]
+impl Resource for GuestStatus {
+    const ENDPOINT: &'static str = "{guest}/{vmid}/status/current";
+    const NODERESOURCE: bool = true;
+
+    async fn from_client<'a>(client: Arc<Client<'a>>) -> DTEResult<Vec<Self>> {
+        let guests = list_guests(&client).await?;
+        let n = guests.len();
+        guests
+            .into_iter()
+            .map(|(guest_type, vmid)| {
+                let client = client.clone();
+                async move {
+                    let mut status: GuestStatus = client
+                        .request(client.guest_resource(
+                            guest_type,
+                            vmid,
+                            "status/current",
+                        ))
+                        .await?;
+                    status.vmid = vmid;
+                    status.guest_type = guest_type;
+                    Ok(status)
+                }
+            })
+            .pipe(stream::iter)
+            .buffered(n.max(1))
+            .collect::<Vec<DTEResult<_>>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    fn into_data(
+        mut self,
+        datafields: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+        counterdb: Arc<CounterDb>,
+    ) -> ProtoRow {
+        let running = self.status == GuestState::Running;
+        let key = format!("{}.{}", self.guest_type, self.vmid);
+
+        /* Counters of stopped guests restart from zero; do not
+         * update the counter db until the guest is running. */
+        let counter = |df: &FieldSpec, value: Option<u64>| -> Data {
+            let value = match (running, value) {
+                (true, Some(value)) => value,
+                _ => return Err(DataError::Missing),
+            };
+            let key = format!("{key}.{}", df.parameter_name);
+            match df.parameter_type {
+                ParameterType::Counter => {
+                    counterdb.counter(key, value, SystemTime::now())
+                }
+                ParameterType::Difference => {
+                    counterdb.difference(key, value, SystemTime::now())
+                }
+                _ => Ok(Value::Integer(value as i64)),
+            }
+        };
+
+        /* Usage of a stopped guest is zero rather than missing. */
+        let usage = |value: Option<i64>| -> Data {
+            match running {
+                true => value.map(Value::Integer).ok_or(DataError::Missing),
+                false => Ok(Value::Integer(0)),
+            }
+        };
+
+        datafields
+            .iter()
+            .map(|(&dfid, &df)| {
+                (
+                    dfid.clone(),
+                    match df.parameter_header.as_str() {
+                        "vmid" => Ok(Value::Integer(self.vmid as i64)),
+                        "type" => Ok(Value::UnicodeString(
+                            self.guest_type.to_string(),
+                        )),
+                        "name" => {
+                            Ok(Value::UnicodeString(mem::take(&mut self.name)))
+                        }
+                        "status" => {
+                            into_enumvalue(self.status, df.values.as_ref())
+                        }
+                        "running" => Ok(Value::Boolean(running)),
+                        "uptime" => Ok(Value::Age(Duration::seconds(
+                            self.uptime as i64,
+                        ))),
+                        "cpu" => match running {
+                            true => self
+                                .cpu
+                                .map(Value::Float)
+                                .ok_or(DataError::Missing),
+                            false => Ok(Value::Float(0.0)),
+                        },
+                        "cpus" => self
+                            .cpus
+                            .map(|n| Value::Integer(n as i64))
+                            .ok_or(DataError::Missing),
+                        "mem" => usage(self.mem),
+                        "maxmem" => self
+                            .maxmem
+                            .map(Value::Integer)
+                            .ok_or(DataError::Missing),
+                        "disk" => usage(self.disk),
+                        "maxdisk" => self
+                            .maxdisk
+                            .map(Value::Integer)
+                            .ok_or(DataError::Missing),
+                        "diskread" => counter(df, self.diskread),
+                        "diskwrite" => counter(df, self.diskwrite),
+                        "netin" => counter(df, self.netin),
+                        "netout" => counter(df, self.netout),
+                        _ => Err(DataError::Missing),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Resource for GuestBackup {
+    const ENDPOINT: &'static str = "tasks?typefilter=vzdump&vmid={vmid}";
+    const NODERESOURCE: bool = true;
+
+    async fn from_client<'a>(client: Arc<Client<'a>>) -> DTEResult<Vec<Self>> {
+        let guests = list_guests(&client).await?;
+        let n = guests.len();
+        guests
+            .into_iter()
+            .map(|(guest_type, vmid)| {
+                let client = client.clone();
+                async move {
+                    let tasks: Vec<BackupTask> = client
+                        .request_list(format!(
+                            "{}?typefilter=vzdump&vmid={vmid}&limit=1",
+                            client.node_resource("tasks")
+                        ))
+                        .await?;
+                    Ok(GuestBackup::from_tasks(vmid, guest_type, tasks))
+                }
+            })
+            .pipe(stream::iter)
+            .buffered(n.max(1))
+            .collect::<Vec<DTEResult<_>>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    fn into_data(
+        self,
+        datafields: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+        _counterdb: Arc<CounterDb>,
+    ) -> ProtoRow {
+        let state = self.state();
+        let task = self.task.as_ref();
+        let time = |t: Option<i64>| -> Data {
+            t.and_then(|t| DateTime::from_timestamp(t, 0))
+                .map(Value::Time)
+                .ok_or(DataError::Missing)
+        };
+
+        datafields
+            .iter()
+            .map(|(&dfid, &df)| {
+                (
+                    dfid.clone(),
+                    match df.parameter_header.as_str() {
+                        "vmid" => Ok(Value::Integer(self.vmid as i64)),
+                        "type" => Ok(Value::UnicodeString(
+                            self.guest_type.to_string(),
+                        )),
+                        "state" => into_enumvalue(state, df.values.as_ref()),
+                        "upid" => task
+                            .map(|t| Value::UnicodeString(t.upid.clone()))
+                            .ok_or(DataError::Missing),
+                        "message" => task
+                            .and_then(|t| t.status.clone())
+                            .map(Value::UnicodeString)
+                            .ok_or(DataError::Missing),
+                        "starttime" => time(task.map(|t| t.starttime)),
+                        "endtime" => time(task.and_then(|t| t.endtime)),
+                        "age" => task
+                            .and_then(|t| t.endtime)
+                            .and_then(|t| DateTime::from_timestamp(t, 0))
+                            .map(|t| Value::Age(chrono::Utc::now() - t))
+                            .ok_or(DataError::Missing),
+                        _ => Err(DataError::Missing),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl GuestBackup {
+    fn from_tasks(
+        vmid: u64,
+        guest_type: VmType,
+        tasks: Vec<BackupTask>,
+    ) -> Self {
+        Self {
+            vmid,
+            guest_type,
+            task: tasks.into_iter().max_by_key(|t| t.starttime),
+        }
+    }
+
+    pub fn state(&self) -> BackupState {
+        match &self.task {
+            None => BackupState::Never,
+            Some(BackupTask { status: None, .. }) => BackupState::Running,
+            Some(BackupTask {
+                status: Some(status),
+                ..
+            }) => match status.as_str() {
+                "OK" => BackupState::Ok,
+                s if s.starts_with("WARNINGS") => BackupState::Warning,
+                _ => BackupState::Failed,
+            },
+        }
+    }
+}
+
+fn into_enumvalue(value: impl Display, values: Option<&ValueTypes>) -> Data {
+    match values
+        .ok_or(DataError::TypeError("expected valuestypes".to_string()))?
+    {
+        ValueTypes::Integer(_) => {
+            Err(DataError::TypeError("expected stringenum".to_string()))
+        }
+        ValueTypes::String(s) => {
+            EnumValue::new(s.clone(), value.to_string()).map(Value::Enum)
+        }
+    }
+}
+
+impl Display for GuestState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Running => "running",
+                Self::Stopped => "stopped",
+                Self::Paused => "paused",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl Display for BackupState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Ok => "ok",
+                Self::Warning => "warning",
+                Self::Failed => "failed",
+                Self::Running => "running",
+                Self::Never => "never",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use etc_base::ProtoDataFieldId;
+    use protocol::CounterDb;
+    use value::{DataError, EnumValue, Value};
+
+    use super::{
+        BackupState, BackupTask, GuestBackup, GuestState, GuestStatus,
+    };
+    use crate::input::{FieldSpec, ParameterType, ValueTypes};
+    use crate::proxmox::client::VmType;
+    use crate::proxmox::resource::Resource;
+
+    const RUNNING: &str = r#"{
+        "status": "running", "qmpstatus": "running", "name": "web01",
+        "vmid": 100, "uptime": 3600, "cpu": 0.25, "cpus": 2,
+        "mem": 1073741824, "maxmem": 2147483648,
+        "disk": 0, "maxdisk": 34359738368,
+        "diskread": 1000, "diskwrite": 2000, "netin": 300, "netout": 400,
+        "ha": {"managed": 0}
+    }"#;
+
+    const STOPPED: &str = r#"{
+        "status": "stopped", "name": "ct01", "vmid": "101",
+        "uptime": 0, "cpus": 1, "maxmem": 536870912, "maxdisk": 8589934592
+    }"#;
+
+    fn field(header: &str, parameter_type: ParameterType) -> FieldSpec {
+        let values = match parameter_type {
+            ParameterType::Enum => Some(ValueTypes::String(Arc::new(
+                ["running", "stopped", "paused", "unknown"]
+                    .into_iter()
+                    .chain(["ok", "warning", "failed", "never"])
+                    .map(String::from)
+                    .collect(),
+            ))),
+            _ => None,
+        };
+        FieldSpec {
+            parameter_name: header.to_string(),
+            parameter_header: header.to_string(),
+            parameter_type,
+            values,
+            is_key: false,
+        }
+    }
+
+    fn enum_value(value: &str) -> value::Data {
+        let ValueTypes::String(values) =
+            field("", ParameterType::Enum).values.unwrap()
+        else {
+            unreachable!()
+        };
+        Ok(Value::Enum(
+            EnumValue::new(values, value.to_string()).unwrap(),
+        ))
+    }
+
+    fn row(
+        resource: impl Resource,
+        fields: &[(&str, ParameterType)],
+    ) -> HashMap<String, value::Data> {
+        let fields = fields
+            .iter()
+            .map(|(name, typ)| {
+                (ProtoDataFieldId(name.to_string()), field(name, *typ))
+            })
+            .collect::<Vec<_>>();
+        let datafields = fields.iter().map(|(id, f)| (id, f)).collect();
+        resource
+            .into_data(
+                &datafields,
+                Arc::new(CounterDb::new(PathBuf::from(
+                    "/nonexistent/counters.json",
+                ))),
+            )
+            .into_iter()
+            .map(|(k, v)| (k.0, v))
+            .collect()
+    }
+
+    #[test]
+    fn parse_running_guest() {
+        let mut status: GuestStatus = serde_json::from_str(RUNNING).unwrap();
+        status.vmid = 100;
+        assert_eq!(status.status, GuestState::Running);
+        let row = row(
+            status,
+            &[
+                ("status", ParameterType::Enum),
+                ("cpu", ParameterType::Float),
+                ("mem", ParameterType::Integer),
+                ("netin", ParameterType::Counter),
+            ],
+        );
+        assert_eq!(row["status"], enum_value("running"));
+        assert_eq!(row["cpu"], Ok(Value::Float(0.25)));
+        assert_eq!(row["mem"], Ok(Value::Integer(1073741824)));
+        assert_eq!(row["netin"], Err(DataError::CounterPending));
+    }
+
+    #[test]
+    fn parse_stopped_guest() {
+        let mut status: GuestStatus = serde_json::from_str(STOPPED).unwrap();
+        status.vmid = 101;
+        status.guest_type = VmType::Lxc;
+        assert_eq!(status.status, GuestState::Stopped);
+        let row = row(
+            status,
+            &[
+                ("type", ParameterType::String),
+                ("status", ParameterType::Enum),
+                ("running", ParameterType::Boolean),
+                ("cpu", ParameterType::Float),
+                ("mem", ParameterType::Integer),
+                ("maxmem", ParameterType::Integer),
+                ("netin", ParameterType::Counter),
+            ],
+        );
+        assert_eq!(row["type"], Ok(Value::UnicodeString("lxc".into())));
+        assert_eq!(row["status"], enum_value("stopped"));
+        assert_eq!(row["running"], Ok(Value::Boolean(false)));
+        assert_eq!(row["cpu"], Ok(Value::Float(0.0)));
+        assert_eq!(row["mem"], Ok(Value::Integer(0)));
+        assert_eq!(row["maxmem"], Ok(Value::Integer(536870912)));
+        assert_eq!(row["netin"], Err(DataError::Missing));
+    }
+
+    #[test]
+    fn parse_backup_results() {
+        let tasks: Vec<BackupTask> = serde_json::from_str(
+            r#"[
+                {"upid": "UPID:pve1:00001:vzdump:100:root@pam:",
+                 "type": "vzdump", "id": "100", "user": "root@pam",
+                 "starttime": 1700000000, "endtime": 1700000300,
+                 "status": "OK"},
+                {"upid": "UPID:pve1:00002:vzdump:100:root@pam:",
+                 "type": "vzdump", "id": "100", "user": "root@pam",
+                 "starttime": 1700086400, "endtime": 1700086500,
+                 "status": "ERROR: job failed with err -5"}
+            ]"#,
+        )
+        .unwrap();
+        let backup = GuestBackup::from_tasks(100, VmType::Qemu, tasks);
+        assert_eq!(backup.state(), BackupState::Failed);
+        let row = row(
+            backup,
+            &[
+                ("state", ParameterType::Enum),
+                ("message", ParameterType::String),
+            ],
+        );
+        assert_eq!(row["state"], enum_value("failed"));
+        assert_eq!(
+            row["message"],
+            Ok(Value::UnicodeString("ERROR: job failed with err -5".into()))
+        );
+
+        let running: Vec<BackupTask> = serde_json::from_str(
+            r#"[{"upid": "UPID:pve1:00003:vzdump:101:root@pam:",
+                 "starttime": 1700172800}]"#,
+        )
+        .unwrap();
+        let backup = GuestBackup::from_tasks(101, VmType::Lxc, running);
+        assert_eq!(backup.state(), BackupState::Running);
+
+        let warnings: Vec<BackupTask> = serde_json::from_str(
+            r#"[{"upid": "UPID:pve1:00004:vzdump:101:root@pam:",
+                 "starttime": 1700172800, "endtime": 1700172900,
+                 "status": "WARNINGS: 1"}]"#,
+        )
+        .unwrap();
+        let backup = GuestBackup::from_tasks(101, VmType::Lxc, warnings);
+        assert_eq!(backup.state(), BackupState::Warning);
+
+        let backup = GuestBackup::from_tasks(102, VmType::Qemu, Vec::new());
+        assert_eq!(backup.state(), BackupState::Never);
+        let row = row(backup, &[("endtime", ParameterType::Time)]);
+        assert_eq!(row["endtime"], Err(DataError::Missing));
+    }
+}
@@ -5,6 +5,7 @@
 mod client;
 mod config;
 mod error;
+mod guest;
 mod plugin;
 mod resource;
 
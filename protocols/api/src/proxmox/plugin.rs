@@ -21,6 +21,7 @@ use crate::input::{FieldSpec, TableSpec};
 use crate::plugin::TableData;
 use crate::{plugin::DataMap, APIPlugin, Input, Plugin as ProtPlugin};
 
+use super::guest::{GuestBackup, GuestStatus};
 use super::resource::{
     ClusterStatus, LxcStatus, Resource, Storage, Task, Version, VmSnapshot,
     VmStatus,
@@ -112,6 +113,12 @@ impl Plugin {
             "lxc_status" => self.request_resource::<LxcStatus>(request).await,
             "task" => self.request_resource::<Task>(request).await,
             "storage" => self.request_resource::<Storage>(request).await,
+            "guest_status" => {
+                self.request_resource::<GuestStatus>(request).await
+            }
+            "guest_backup" => {
+                self.request_resource::<GuestBackup>(request).await
+            }
 
             // "replication" => self.request_resource::<Replication>(request).await,
             // "ceph_status" => self.request_resource::<CephStatus>(request).await,
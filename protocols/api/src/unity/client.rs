@@ -23,6 +23,7 @@ use value::{Data, DataError, IntEnumValue, Value};
 
 use crate::input::{FieldSpec, ParameterType, ValueTypes};
 
+use super::storage::{Lun, Pool};
 use super::{Config, DTEResult, DTError, Error, Result};
 
 #[derive(Debug)]
@@ -286,6 +287,37 @@ impl Client {
             .tap(|pts| trace!("systemCapacity tiers requested: {pts:#?}"))
             .pipe(Ok)
     }
+
+    pub async fn request_pools(&self) -> DTEResult<Vec<Pool>> {
+        const FIELDS: [&str; 7] = [
+            "id",
+            "name",
+            "health",
+            "sizeTotal",
+            "sizeUsed",
+            "sizeFree",
+            "sizeSubscribed",
+        ];
+        self.request_resource("pool", &FIELDS, "")
+            .await
+            .tap_ok(|pools| trace!("pools requested: {pools:#?}"))
+    }
+
+    pub async fn request_luns(&self) -> DTEResult<Vec<Lun>> {
+        const FIELDS: [&str; 8] = [
+            "id",
+            "name",
+            "health",
+            "pool",
+            "sizeTotal",
+            "sizeAllocated",
+            "isThinEnabled",
+            "wwn",
+        ];
+        self.request_resource("lun", &FIELDS, "")
+            .await
+            .tap_ok(|luns| trace!("luns requested: {luns:#?}"))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,6 +6,7 @@ mod client;
 mod config;
 mod error;
 mod plugin;
+mod storage;
 
 pub use client::{AsValue, Client};
 pub use config::Config;
@@ -19,7 +19,8 @@ use tokio::sync::OnceCell;
 use value::{Data, DataError, EnumValue, IntEnumValue, Value};
 
 use super::client::{Metric, MetricValue};
-use super::{AsValue, Client, Config, Error, Result};
+use super::storage::{LunPerformance, LUN_METRICS};
+use super::{AsValue, Client, Config, DTEResult, Error, Result};
 use crate::error::{DTError as APIDTError, Result as APIResult};
 use crate::input::{FieldSpec, ParameterType, TableSpec, ValueTypes};
 use crate::plugin::TableData;
@@ -588,6 +589,73 @@ impl Plugin {
         })
     }
 
+    async fn get_pools(
+        &self,
+        dfs: HashMap<&ProtoDataFieldId, &FieldSpec>,
+        client: Arc<Client>,
+    ) -> TableData {
+        let data = client
+            .request_pools()
+            .await?
+            .into_iter()
+            .map(|pool| pool.into_data(&dfs))
+            .collect();
+
+        Ok(Annotated {
+            value: data,
+            warnings: Vec::new(),
+        })
+    }
+
+    async fn get_luns(
+        &self,
+        dfs: HashMap<&ProtoDataFieldId, &FieldSpec>,
+        client: Arc<Client>,
+    ) -> TableData {
+        let (luns, performance) = tokio::join!(
+            client.request_luns(),
+            self.get_lun_performance(&client)
+        );
+        let mut performance = performance?;
+
+        let data = luns?
+            .into_iter()
+            .map(|mut lun| {
+                lun.performance = performance.remove(&lun.id);
+                lun.into_data(&dfs)
+            })
+            .collect();
+
+        Ok(Annotated {
+            value: data,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Performance of the LUNs since the previous run, by LUN id.
+    async fn get_lun_performance(
+        &self,
+        client: &Client,
+    ) -> DTEResult<HashMap<String, LunPerformance>> {
+        let since = self.get_metric_timestamp(LUN_METRICS[0]).await;
+        debug!(
+            "retrieving lun performance since {} ({} min ago)",
+            since,
+            (Utc::now() - since).num_minutes()
+        );
+
+        let metrics = client
+            .request_historical_metric(&LUN_METRICS, since)
+            .await?;
+        for path in LUN_METRICS {
+            if let Some(metric) = metrics.iter().find(|m| m.path == path) {
+                self.set_metric_timestamp(path.to_string(), metric.timestamp)
+            }
+        }
+
+        Ok(LunPerformance::from_metrics(&metrics))
+    }
+
     async fn request_datatable(
         &self,
         request: DtRequest<'_>,
@@ -605,6 +673,8 @@ impl Plugin {
             "get_systemcapacitytiers" => {
                 self.get_systemcapacitytiers(request.dfs, client).await
             }
+            "get_pools" => self.get_pools(request.dfs, client).await,
+            "get_luns" => self.get_luns(request.dfs, client).await,
             _ => Err(crate::error::DTError::CommandNotFound(
                 request.dt.command_name.clone(),
             )),
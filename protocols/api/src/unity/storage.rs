@@ -0,0 +1,572 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::mem;
+
+use etc_base::{ProtoDataFieldId, ProtoRow};
+use serde::{Deserialize, Serialize};
+use unit::{
+    BinPrefix, DecPrefix, FracPrefix, InformationUnit, OperationUnit, Quantity,
+    TimeUnit, Unit,
+};
+use value::{Data, DataError, EnumValue, Value};
+
+use crate::input::{FieldSpec, ValueTypes};
+
+use super::client::{IdOnly, MetricValue};
+use super::AsValue;
+
+const BYTES: Unit = Unit::Information(InformationUnit::Byte(BinPrefix::Unit));
+const BYTES_PER_SECOND: Unit = Unit::Bandwidth(
+    InformationUnit::Byte(BinPrefix::Unit),
+    TimeUnit::Second(FracPrefix::Unit),
+);
+const OPERATIONS_PER_SECOND: Unit = Unit::IOPerformance(
+    OperationUnit::Operation(DecPrefix::Unit),
+    TimeUnit::Second(FracPrefix::Unit),
+);
+const MICROSECONDS_PER_OPERATION: Unit = Unit::IOLatency(
+    TimeUnit::Second(FracPrefix::Micro),
+    OperationUnit::Operation(DecPrefix::Unit),
+);
+
+/// Historical per-LUN metrics, keyed by storage processor and LUN id.
+pub const LUN_READS: &str = "sp.*.storage.lun.*.readsRate";
+pub const LUN_WRITES: &str = "sp.*.storage.lun.*.writesRate";
+pub const LUN_READ_BYTES: &str = "sp.*.storage.lun.*.readBytesRate";
+pub const LUN_WRITE_BYTES: &str = "sp.*.storage.lun.*.writeBytesRate";
+pub const LUN_RESPONSE_TIME: &str = "sp.*.storage.lun.*.responseTime";
+pub const LUN_METRICS: [&str; 5] = [
+    LUN_READS,
+    LUN_WRITES,
+    LUN_READ_BYTES,
+    LUN_WRITE_BYTES,
+    LUN_RESPONSE_TIME,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+    pub value: HealthInt,
+    #[serde(default)]
+    pub description_ids: Vec<String>,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize,
+)]
+pub struct HealthInt(u8);
+
+/// Status derived from the health of a pool or LUN.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectStatus {
+    Ok,
+    Degraded,
+    Faulted,
+    Offline,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pool {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub health: Health,
+    pub size_total: u64,
+    pub size_used: u64,
+    pub size_free: u64,
+    #[serde(default)]
+    pub size_subscribed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lun {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub health: Health,
+    pub pool: Option<IdOnly>,
+    pub size_total: u64,
+    #[serde(default)]
+    pub size_allocated: u64,
+    #[serde(default)]
+    pub is_thin_enabled: bool,
+    pub wwn: Option<String>,
+    #[serde(skip)]
+    pub performance: Option<LunPerformance>,
+}
+
+/// Performance of a LUN, averaged over the samples since the last
+/// run and summed over the storage processors.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LunPerformance {
+    pub reads: f64,
+    pub writes: f64,
+    pub read_bytes: f64,
+    pub write_bytes: f64,
+    /// Response time in µs, weighted by the operations on each
+    /// storage processor; missing when the LUN was idle.
+    pub response_time: Option<f64>,
+}
+
+#[derive(Default)]
+struct SpSample {
+    reads: f64,
+    writes: f64,
+    read_bytes: f64,
+    write_bytes: f64,
+    response_time: Option<f64>,
+}
+
+impl Health {
+    /// Offline objects are reported by their description ids, since
+    /// their health value only reflects the severity.
+    pub fn status(&self) -> ObjectStatus {
+        if self.description_ids.iter().any(|id| id.contains("OFFLINE")) {
+            return ObjectStatus::Offline;
+        }
+        match self.value.0 {
+            5 | 7 => ObjectStatus::Ok,
+            10 | 15 => ObjectStatus::Degraded,
+            20 | 25 | 30 => ObjectStatus::Faulted,
+            _ => ObjectStatus::Unknown,
+        }
+    }
+}
+
+impl AsValue for HealthInt {
+    fn as_value(&self, choices: Option<&ValueTypes>) -> Data {
+        self.parse_enum(self.0 as i64, choices)
+    }
+}
+
+impl Pool {
+    pub fn into_data(
+        mut self,
+        datafields: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+    ) -> ProtoRow {
+        let status = self.health.status();
+        let percentage = |value: u64| -> Data {
+            match self.size_total {
+                0 => Err(DataError::Missing),
+                total => Ok(Value::Float(value as f64 / total as f64 * 100.0)),
+            }
+        };
+
+        datafields
+            .iter()
+            .map(|(&dfid, &df)| {
+                (
+                    dfid.clone(),
+                    match df.parameter_header.as_str() {
+                        "id" => Ok(Value::UnicodeString(self.id.clone())),
+                        "name" => {
+                            Ok(Value::UnicodeString(mem::take(&mut self.name)))
+                        }
+                        "health" => {
+                            self.health.value.as_value(df.values.as_ref())
+                        }
+                        "status" => into_enumvalue(status, df.values.as_ref()),
+                        "size_total" => information(self.size_total),
+                        "size_used" => information(self.size_used),
+                        "size_free" => information(self.size_free),
+                        "size_subscribed" => information(self.size_subscribed),
+                        "utilization" => percentage(self.size_used),
+                        "subscription" => percentage(self.size_subscribed),
+                        _ => Err(DataError::Missing),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl Lun {
+    pub fn into_data(
+        mut self,
+        datafields: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+    ) -> ProtoRow {
+        let status = self.health.status();
+        let perf = self.performance.as_ref();
+        let rate = |unit, value: fn(&LunPerformance) -> f64| -> Data {
+            perf.map(|p| Value::Quantity(Quantity(value(p), unit)))
+                .ok_or(DataError::Missing)
+        };
+
+        datafields
+            .iter()
+            .map(|(&dfid, &df)| {
+                (
+                    dfid.clone(),
+                    match df.parameter_header.as_str() {
+                        "id" => Ok(Value::UnicodeString(self.id.clone())),
+                        "name" => {
+                            Ok(Value::UnicodeString(mem::take(&mut self.name)))
+                        }
+                        "pool_id" => self
+                            .pool
+                            .as_ref()
+                            .map(|p| Value::UnicodeString(p.id.clone()))
+                            .ok_or(DataError::Missing),
+                        "wwn" => self
+                            .wwn
+                            .clone()
+                            .map(Value::UnicodeString)
+                            .ok_or(DataError::Missing),
+                        "health" => {
+                            self.health.value.as_value(df.values.as_ref())
+                        }
+                        "status" => into_enumvalue(status, df.values.as_ref()),
+                        "thin" => Ok(Value::Boolean(self.is_thin_enabled)),
+                        "size_total" => information(self.size_total),
+                        "size_allocated" => information(self.size_allocated),
+                        "reads" => rate(OPERATIONS_PER_SECOND, |p| p.reads),
+                        "writes" => rate(OPERATIONS_PER_SECOND, |p| p.writes),
+                        "iops" => {
+                            rate(OPERATIONS_PER_SECOND, |p| p.reads + p.writes)
+                        }
+                        "read_bytes" => {
+                            rate(BYTES_PER_SECOND, |p| p.read_bytes)
+                        }
+                        "write_bytes" => {
+                            rate(BYTES_PER_SECOND, |p| p.write_bytes)
+                        }
+                        "throughput" => rate(BYTES_PER_SECOND, |p| {
+                            p.read_bytes + p.write_bytes
+                        }),
+                        "response_time" => perf
+                            .and_then(|p| p.response_time)
+                            .map(|v| {
+                                Value::Quantity(Quantity(
+                                    v,
+                                    MICROSECONDS_PER_OPERATION,
+                                ))
+                            })
+                            .ok_or(DataError::Missing),
+                        _ => Err(DataError::Missing),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl LunPerformance {
+    /// Aggregate the per-LUN metric values by LUN id.
+    pub fn from_metrics(
+        metrics: &[impl AsRef<MetricValue>],
+    ) -> HashMap<String, Self> {
+        /* Average the samples per metric, storage processor and LUN. */
+        let mut sums: HashMap<(&str, String, String), (f64, usize)> =
+            HashMap::new();
+        for mv in metrics {
+            let mv = mv.as_ref();
+            for (id, value) in mv.value_view() {
+                if let [sp, lun] = &id[..] {
+                    let sum = sums
+                        .entry((mv.path.as_str(), sp.clone(), lun.clone()))
+                        .or_default();
+                    sum.0 += value;
+                    sum.1 += 1;
+                }
+            }
+        }
+
+        let mut samples: HashMap<String, HashMap<String, SpSample>> =
+            HashMap::new();
+        for ((path, sp, lun), (sum, n)) in sums {
+            let avg = sum / n as f64;
+            let sample = samples.entry(lun).or_default().entry(sp).or_default();
+            match path {
+                LUN_READS => sample.reads = avg,
+                LUN_WRITES => sample.writes = avg,
+                LUN_READ_BYTES => sample.read_bytes = avg,
+                LUN_WRITE_BYTES => sample.write_bytes = avg,
+                LUN_RESPONSE_TIME => sample.response_time = Some(avg),
+                _ => {}
+            }
+        }
+
+        samples
+            .into_iter()
+            .map(|(lun, sps)| {
+                let mut perf = Self::default();
+                let (mut weighted, mut ops) = (0.0, 0.0);
+                for sample in sps.values() {
+                    perf.reads += sample.reads;
+                    perf.writes += sample.writes;
+                    perf.read_bytes += sample.read_bytes;
+                    perf.write_bytes += sample.write_bytes;
+                    if let Some(rt) = sample.response_time {
+                        weighted += rt * (sample.reads + sample.writes);
+                        ops += sample.reads + sample.writes;
+                    }
+                }
+                perf.response_time = (ops > 0.0).then(|| weighted / ops);
+                (lun, perf)
+            })
+            .collect()
+    }
+}
+
+fn information(bytes: u64) -> Data {
+    Ok(Value::Quantity(Quantity(bytes as f64, BYTES)))
+}
+
+fn into_enumvalue(value: impl Display, values: Option<&ValueTypes>) -> Data {
+    match values
+        .ok_or(DataError::TypeError("expected valuestypes".to_string()))?
+    {
+        ValueTypes::Integer(_) => {
+            Err(DataError::TypeError("expected stringenum".to_string()))
+        }
+        ValueTypes::String(s) => {
+            EnumValue::new(s.clone(), value.to_string()).map(Value::Enum)
+        }
+    }
+}
+
+impl Display for ObjectStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Ok => "ok",
+                Self::Degraded => "degraded",
+                Self::Faulted => "faulted",
+                Self::Offline => "offline",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use etc_base::ProtoDataFieldId;
+    use unit::Quantity;
+    use value::{DataError, EnumValue, Value};
+
+    use super::{
+        Lun, LunPerformance, ObjectStatus, Pool, BYTES, BYTES_PER_SECOND,
+        MICROSECONDS_PER_OPERATION, OPERATIONS_PER_SECOND,
+    };
+    use crate::input::{FieldSpec, ParameterType, ValueTypes};
+    use crate::unity::client::{MetricValue, ResourceResponse};
+
+    const POOLS: &str = r#"{
+        "@base": "https://unity/api/types/pool/instances",
+        "updated": "2024-05-01T10:00:00.000Z",
+        "links": [{"rel": "self", "href": "&page=1"}],
+        "entries": [
+            {"content": {"id": "pool_1", "name": "Pool 1",
+             "health": {"value": 5, "descriptionIds": ["ALRT_COMPONENT_OK"]},
+             "sizeTotal": 1000, "sizeUsed": 250, "sizeFree": 750,
+             "sizeSubscribed": 1500}},
+            {"content": {"id": "pool_2", "name": "Pool 2",
+             "health": {"value": 25,
+                        "descriptionIds": ["ALRT_POOL_DISK_FAULT"]},
+             "sizeTotal": 0, "sizeUsed": 0, "sizeFree": 0}}
+        ]
+    }"#;
+
+    const LUNS: &str = r#"{
+        "@base": "https://unity/api/types/lun/instances",
+        "updated": "2024-05-01T10:00:00.000Z",
+        "links": [],
+        "entries": [
+            {"content": {"id": "sv_1", "name": "LUN01",
+             "health": {"value": 5}, "pool": {"id": "pool_1"},
+             "sizeTotal": 107374182400, "sizeAllocated": 53687091200,
+             "isThinEnabled": true, "wwn": "60:06:01:60"}},
+            {"content": {"id": "sv_2", "name": "LUN02",
+             "health": {"value": 20,
+                        "descriptionIds": ["ALRT_VOL_OFFLINE"]},
+             "pool": {"id": "pool_1"}, "sizeTotal": 10737418240}}
+        ]
+    }"#;
+
+    const METRICS: &str = r#"[
+        {"path": "sp.*.storage.lun.*.readsRate",
+         "timestamp": "2024-05-01T10:00:00.000Z", "interval": 60,
+         "values": {"spa": {"sv_1": 100.0}, "spb": {"sv_1": 20.0}}},
+        {"path": "sp.*.storage.lun.*.readsRate",
+         "timestamp": "2024-05-01T09:59:00.000Z", "interval": 60,
+         "values": {"spa": {"sv_1": 80.0}, "spb": {"sv_1": 0.0}}},
+        {"path": "sp.*.storage.lun.*.writesRate",
+         "timestamp": "2024-05-01T10:00:00.000Z", "interval": 60,
+         "values": {"spa": {"sv_1": 10.0}, "spb": {"sv_1": 0.0}}},
+        {"path": "sp.*.storage.lun.*.readBytesRate",
+         "timestamp": "2024-05-01T10:00:00.000Z", "interval": 60,
+         "values": {"spa": {"sv_1": 4096.0}, "spb": {"sv_1": 1024.0}}},
+        {"path": "sp.*.storage.lun.*.responseTime",
+         "timestamp": "2024-05-01T10:00:00.000Z", "interval": 60,
+         "values": {"spa": {"sv_1": 500.0}, "spb": {"sv_1": 1600.0}}}
+    ]"#;
+
+    fn field(header: &str, parameter_type: ParameterType) -> FieldSpec {
+        let values = match parameter_type {
+            ParameterType::Enum => Some(ValueTypes::String(Arc::new(
+                ["ok", "degraded", "faulted", "offline", "unknown"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ))),
+            _ => None,
+        };
+        FieldSpec {
+            parameter_name: header.to_string(),
+            parameter_header: header.to_string(),
+            parameter_type,
+            values,
+            is_key: false,
+        }
+    }
+
+    fn enum_value(value: &str) -> value::Data {
+        let ValueTypes::String(values) =
+            field("", ParameterType::Enum).values.unwrap()
+        else {
+            unreachable!()
+        };
+        Ok(Value::Enum(
+            EnumValue::new(values, value.to_string()).unwrap(),
+        ))
+    }
+
+    fn fields(
+        fields: &[(&str, ParameterType)],
+    ) -> Vec<(ProtoDataFieldId, FieldSpec)> {
+        fields
+            .iter()
+            .map(|(name, typ)| {
+                (ProtoDataFieldId(name.to_string()), field(name, *typ))
+            })
+            .collect()
+    }
+
+    fn row(data: etc_base::ProtoRow) -> HashMap<String, value::Data> {
+        data.into_iter().map(|(k, v)| (k.0, v)).collect()
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(response: &str) -> Vec<T> {
+        serde_json::from_str::<ResourceResponse<T>>(response)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| e.content)
+            .collect()
+    }
+
+    #[test]
+    fn parse_pools() {
+        let pools = parse::<Pool>(POOLS);
+        assert_eq!(pools.len(), 2);
+        assert_eq!(pools[0].health.status(), ObjectStatus::Ok);
+        assert_eq!(pools[1].health.status(), ObjectStatus::Faulted);
+
+        let fields = fields(&[
+            ("name", ParameterType::String),
+            ("status", ParameterType::Enum),
+            ("size_used", ParameterType::Information),
+            ("size_free", ParameterType::Information),
+            ("utilization", ParameterType::Float),
+            ("subscription", ParameterType::Float),
+        ]);
+        let datafields = fields.iter().map(|(id, f)| (id, f)).collect();
+
+        let mut pools = pools.into_iter();
+        let pool = row(pools.next().unwrap().into_data(&datafields));
+        assert_eq!(pool["name"], Ok(Value::UnicodeString("Pool 1".into())));
+        assert_eq!(pool["status"], enum_value("ok"));
+        assert_eq!(
+            pool["size_used"],
+            Ok(Value::Quantity(Quantity(250.0, BYTES)))
+        );
+        assert_eq!(
+            pool["size_free"],
+            Ok(Value::Quantity(Quantity(750.0, BYTES)))
+        );
+        assert_eq!(pool["utilization"], Ok(Value::Float(25.0)));
+        assert_eq!(pool["subscription"], Ok(Value::Float(150.0)));
+
+        let pool = row(pools.next().unwrap().into_data(&datafields));
+        assert_eq!(pool["status"], enum_value("faulted"));
+        assert_eq!(pool["utilization"], Err(DataError::Missing));
+    }
+
+    #[test]
+    fn parse_luns() {
+        let metrics: Vec<MetricValue> = serde_json::from_str(METRICS).unwrap();
+        let mut performance = LunPerformance::from_metrics(&metrics);
+        assert_eq!(performance.len(), 1);
+
+        let mut luns = parse::<Lun>(LUNS);
+        assert_eq!(luns.len(), 2);
+        assert_eq!(luns[1].health.status(), ObjectStatus::Offline);
+        for lun in &mut luns {
+            lun.performance = performance.remove(&lun.id);
+        }
+
+        let fields = fields(&[
+            ("pool_id", ParameterType::String),
+            ("status", ParameterType::Enum),
+            ("thin", ParameterType::Boolean),
+            ("size_allocated", ParameterType::Information),
+            ("reads", ParameterType::IoPerformance),
+            ("iops", ParameterType::IoPerformance),
+            ("throughput", ParameterType::Bandwidth),
+            ("response_time", ParameterType::IoLatency),
+        ]);
+        let datafields = fields.iter().map(|(id, f)| (id, f)).collect();
+
+        let mut luns = luns.into_iter();
+        let lun = row(luns.next().unwrap().into_data(&datafields));
+        assert_eq!(lun["pool_id"], Ok(Value::UnicodeString("pool_1".into())));
+        assert_eq!(lun["status"], enum_value("ok"));
+        assert_eq!(lun["thin"], Ok(Value::Boolean(true)));
+        assert_eq!(
+            lun["size_allocated"],
+            Ok(Value::Quantity(Quantity(53687091200.0, BYTES)))
+        );
+        /* Averaged over the samples, summed over the SPs. */
+        assert_eq!(
+            lun["reads"],
+            Ok(Value::Quantity(Quantity(100.0, OPERATIONS_PER_SECOND)))
+        );
+        assert_eq!(
+            lun["iops"],
+            Ok(Value::Quantity(Quantity(110.0, OPERATIONS_PER_SECOND)))
+        );
+        assert_eq!(
+            lun["throughput"],
+            Ok(Value::Quantity(Quantity(5120.0, BYTES_PER_SECOND)))
+        );
+        /* Weighted by the operations on each SP: (100 * 500 + 10 *
+         * 1600) / 110. */
+        assert_eq!(
+            lun["response_time"],
+            Ok(Value::Quantity(Quantity(600.0, MICROSECONDS_PER_OPERATION)))
+        );
+
+        /* No performance data for the offline LUN. */
+        let lun = row(luns.next().unwrap().into_data(&datafields));
+        assert_eq!(lun["status"], enum_value("offline"));
+        assert_eq!(lun["iops"], Err(DataError::Missing));
+        assert_eq!(
+            lun["size_allocated"],
+            Ok(Value::Quantity(Quantity(0.0, BYTES)))
+        );
+    }
+}
@@ -0,0 +1,92 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+
+use etc_base::{Annotated, ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap};
+use serde_json::json;
+use value::Value;
+
+use api_protocol::plugin::DataMap;
+use api_protocol::FieldAllowlist;
+
+fn table(id: &str) -> ProtoDataTableId {
+    ProtoDataTableId(id.to_string())
+}
+
+fn field(id: &str) -> ProtoDataFieldId {
+    ProtoDataFieldId(id.to_string())
+}
+
+fn fields(ids: &[&str]) -> HashSet<ProtoDataFieldId> {
+    ids.iter().map(|id| field(id)).collect()
+}
+
+fn allowlist() -> FieldAllowlist {
+    serde_json::from_value(json!({
+        "users": ["id", "displayName"]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn restrict_query() {
+    let query: ProtoQueryMap = HashMap::from([
+        (
+            table("users"),
+            fields(&["id", "displayName", "mail", "phone"]),
+        ),
+        (table("groups"), fields(&["id", "description"])),
+    ]);
+    let query = allowlist().restrict_query(&query);
+    assert_eq!(query[&table("users")], fields(&["id", "displayName"]));
+    assert_eq!(query[&table("groups")], fields(&["id", "description"]));
+}
+
+#[test]
+fn drop_fields_from_rows() {
+    let row = |table_fields: &[&str]| {
+        table_fields
+            .iter()
+            .map(|id| (field(id), Ok(Value::UnicodeString(id.to_string()))))
+            .collect()
+    };
+    let mut data: DataMap = HashMap::from([
+        (
+            table("users"),
+            Ok(Annotated {
+                value: vec![row(&["id", "displayName", "mail"])],
+                warnings: Vec::new(),
+            }),
+        ),
+        (
+            table("groups"),
+            Ok(Annotated {
+                value: vec![row(&["id", "description"])],
+                warnings: Vec::new(),
+            }),
+        ),
+    ]);
+    allowlist().apply(&mut data);
+
+    let users = &data[&table("users")].as_ref().unwrap().value;
+    assert_eq!(
+        users[0].keys().cloned().collect::<HashSet<_>>(),
+        fields(&["id", "displayName"])
+    );
+    let groups = &data[&table("groups")].as_ref().unwrap().value;
+    assert_eq!(
+        groups[0].keys().cloned().collect::<HashSet<_>>(),
+        fields(&["id", "description"])
+    );
+}
+
+#[test]
+fn empty_by_default() {
+    let config: FieldAllowlist = Default::default();
+    assert!(config.is_empty());
+    let query: ProtoQueryMap =
+        HashMap::from([(table("users"), fields(&["id", "mail"]))]);
+    assert_eq!(config.restrict_query(&query), query);
+}
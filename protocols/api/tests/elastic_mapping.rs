@@ -0,0 +1,73 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use serde_json::json;
+
+use api_protocol::elastic::{FieldKind, IndexMappings};
+
+#[test]
+fn reconcile_keyword_and_text() {
+    let mappings = IndexMappings::from_response(&json!({
+        "logs-2024.01.01": {
+            "mappings": {
+                "properties": {
+                    "message": { "type": "keyword" },
+                    "host": { "properties": { "name": { "type": "keyword" } } }
+                }
+            }
+        },
+        "logs-2024.01.02": {
+            "mappings": {
+                "properties": {
+                    "message": {
+                        "type": "text",
+                        "fields": { "keyword": { "type": "keyword" } }
+                    },
+                    "host": { "properties": { "name": { "type": "keyword" } } }
+                }
+            }
+        }
+    }));
+
+    let reconciled = mappings.reconcile();
+    assert_eq!(reconciled.fields["message"], FieldKind::String);
+    assert_eq!(reconciled.fields["message.keyword"], FieldKind::String);
+    assert_eq!(reconciled.fields["host.name"], FieldKind::String);
+
+    assert_eq!(reconciled.conflicts.len(), 1);
+    let conflict = &reconciled.conflicts[0];
+    assert_eq!(conflict.field, "message");
+    assert_eq!(conflict.resolved, FieldKind::String);
+    assert_eq!(conflict.types["logs-2024.01.01"], "keyword");
+    assert_eq!(conflict.types["logs-2024.01.02"], "text");
+}
+
+#[test]
+fn reconcile_numeric_and_incompatible() {
+    let mappings = IndexMappings::from_response(&json!({
+        "metrics-a": {
+            "mappings": {
+                "_doc": {
+                    "properties": {
+                        "duration": { "type": "long" },
+                        "status": { "type": "long" }
+                    }
+                }
+            }
+        },
+        "metrics-b": {
+            "mappings": {
+                "properties": {
+                    "duration": { "type": "double" },
+                    "status": { "type": "keyword" }
+                }
+            }
+        }
+    }));
+
+    let reconciled = mappings.reconcile();
+    assert_eq!(reconciled.fields["duration"], FieldKind::Float);
+    assert_eq!(reconciled.fields["status"], FieldKind::String);
+    assert_eq!(reconciled.conflicts.len(), 2);
+}
@@ -0,0 +1,86 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use serde_json::json;
+
+use api_protocol::graphql::{DTError, DTWarning, Response};
+
+#[test]
+fn successful_query() {
+    let response = Response::from_slice(
+        json!({
+            "data": {
+                "repository": {
+                    "issues": {
+                        "nodes": [
+                            { "number": 1, "title": "first" },
+                            { "number": 2, "title": "second" }
+                        ]
+                    }
+                }
+            }
+        })
+        .to_string()
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let rows = response.rows("$.repository.issues.nodes[*]").unwrap();
+    assert!(rows.warnings.is_empty());
+    assert_eq!(
+        rows.rows,
+        vec![
+            json!({ "number": 1, "title": "first" }),
+            json!({ "number": 2, "title": "second" })
+        ]
+    );
+}
+
+#[test]
+fn query_errors() {
+    let response = Response::from_slice(
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "Field 'isues' doesn't exist on type 'Repository'",
+                "locations": [{ "line": 1, "column": 14 }],
+                "path": ["query", "repository", "isues"]
+            }]
+        })
+        .to_string()
+        .as_bytes(),
+    )
+    .unwrap();
+
+    match response.rows("$.repository.issues.nodes[*]") {
+        Err(DTError::Query(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                errors[0].to_string(),
+                "Field 'isues' doesn't exist on type 'Repository' \
+                 (at query.repository.isues)"
+            );
+        }
+        r => panic!("expected query errors, got {r:?}"),
+    }
+}
+
+#[test]
+fn partial_data() {
+    let response = Response::from_slice(
+        json!({
+            "data": { "hosts": [{ "name": "a" }, null] },
+            "errors": [{ "message": "not allowed", "path": ["hosts", 1] }]
+        })
+        .to_string()
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let rows = response.rows("$.hosts[*]").unwrap();
+    assert_eq!(rows.rows, vec![json!({ "name": "a" }), json!(null)]);
+    assert_eq!(rows.warnings.len(), 1);
+    let DTWarning::Query(error) = &rows.warnings[0];
+    assert_eq!(error.to_string(), "not allowed (at hosts.1)");
+}
@@ -0,0 +1,51 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use protocol::http::RequestStats;
+use reqwest::Client;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use api_protocol::ms_graph::request_with_retry;
+
+/// Serve the given canned responses, one per connection.
+async fn serve(responses: &'static [&'static str]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        for response in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn throttled_requests_are_recorded() {
+    let url = serve(&[
+        "HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\n\
+         Connection: close\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\
+         Connection: close\r\n\r\n{\"value\": []}",
+    ])
+    .await;
+    let stats = RequestStats::new();
+
+    let response =
+        request_with_retry(&Client::new(), &stats, &format!("{url}/users"), 3)
+            .await
+            .unwrap();
+    assert_eq!(response.status.as_u16(), 200);
+    assert_eq!(response.text(), "{\"value\": []}");
+
+    let users = stats.get("GET /users").unwrap();
+    assert_eq!(users.requests, 2);
+    assert_eq!(users.failures, 1);
+    assert_eq!(users.bytes, 13);
+    assert_eq!(users.statuses.get(&429), Some(&1));
+    assert_eq!(users.last_status, Some(200));
+}
@@ -0,0 +1,132 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::time::{Duration, SystemTime};
+
+use serde_json::json;
+
+use api_protocol::ms_graph::{
+    Config, Error, Scopes, Token, TokenCache, TokenError, GRAPH_DEFAULT_SCOPE,
+};
+
+const REPORTS_SCOPE: &str = "https://graph.microsoft.com/Reports.Read.All";
+
+fn config() -> Config {
+    serde_json::from_value(json!({
+        "credentials": null,
+        "scopes": {
+            "reports": [REPORTS_SCOPE],
+            "reports/getOffice365ActiveUserDetail": [
+                REPORTS_SCOPE,
+                "https://graph.microsoft.com/User.Read.All"
+            ]
+        }
+    }))
+    .unwrap()
+}
+
+fn token(name: &str, now: SystemTime) -> Token {
+    Token::new(name.to_string(), Duration::from_secs(3600), now)
+}
+
+#[test]
+fn scope_selection() {
+    let config = config();
+    assert_eq!(
+        config.scopes_for("reports/getOneDriveUsageAccountDetail(period='D7')"),
+        Scopes::new([REPORTS_SCOPE])
+    );
+    assert_eq!(
+        config.scopes_for("reports/getOffice365ActiveUserDetail(period='D7')"),
+        Scopes::new([
+            "https://graph.microsoft.com/User.Read.All",
+            REPORTS_SCOPE,
+        ])
+    );
+    assert_eq!(config.scopes_for("subscribedSkus"), Scopes::default());
+    assert_eq!(Scopes::default().to_param(), GRAPH_DEFAULT_SCOPE);
+}
+
+#[test]
+fn tokens_cached_per_scope_set() {
+    let now = SystemTime::now();
+    let cache = TokenCache::new();
+    let reports = Scopes::new([REPORTS_SCOPE]);
+
+    cache.insert(Scopes::default(), token("default", now));
+    cache.insert(reports.clone(), token("reports", now));
+
+    assert_eq!(
+        cache.get(&Scopes::default(), now).unwrap().access_token(),
+        "default"
+    );
+    assert_eq!(cache.get(&reports, now).unwrap().access_token(), "reports");
+    assert!(cache
+        .get(&Scopes::new([REPORTS_SCOPE, GRAPH_DEFAULT_SCOPE]), now)
+        .is_none());
+
+    /* Scope order does not matter. */
+    let a = Scopes::new(["a", "b"]);
+    cache.insert(a, token("ab", now));
+    assert_eq!(
+        cache
+            .get(&Scopes::new(["b", "a"]), now)
+            .unwrap()
+            .access_token(),
+        "ab"
+    );
+}
+
+#[test]
+fn expired_tokens_are_not_returned() {
+    let now = SystemTime::now();
+    let cache = TokenCache::new();
+    cache.insert(Scopes::default(), token("default", now));
+
+    let later = now + Duration::from_secs(3590);
+    assert!(cache.get(&Scopes::default(), later).is_none());
+
+    cache.insert(Scopes::default(), token("renewed", later));
+    assert_eq!(
+        cache.get(&Scopes::default(), later).unwrap().access_token(),
+        "renewed"
+    );
+}
+
+#[test]
+fn consent_errors() {
+    let error: TokenError = serde_json::from_value(json!({
+        "error": "invalid_client",
+        "error_description": "AADSTS65001: The user or administrator has \
+                              not consented to use the application.",
+        "error_codes": [65001]
+    }))
+    .unwrap();
+    assert!(matches!(
+        error.into_error(Scopes::default()),
+        Error::ConsentRequired(_, _)
+    ));
+
+    let error: TokenError = serde_json::from_value(json!({
+        "error": "invalid_scope",
+        "error_description": "AADSTS70011: The provided value for the \
+                              input parameter 'scope' is not valid."
+    }))
+    .unwrap();
+    assert!(matches!(
+        error.into_error(Scopes::default()),
+        Error::ConsentRequired(_, _)
+    ));
+
+    let error: TokenError = serde_json::from_value(json!({
+        "error": "invalid_client",
+        "error_description": "AADSTS7000215: Invalid client secret provided.",
+        "error_codes": [7000215]
+    }))
+    .unwrap();
+    assert!(matches!(
+        error.into_error(Scopes::default()),
+        Error::TokenRequest(_, _)
+    ));
+}
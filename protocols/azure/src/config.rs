@@ -11,7 +11,7 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use uritemplate::UriTemplate;
 
-use rest_protocol::{config::Application, http::*, Template};
+use rest_protocol::{config::Application, http::*, Pagination, Template};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -103,6 +103,7 @@ impl ClientInfo {
             login_method: HTTPMethod::POST,
             login_body_type: BodyType::FormUrlEncoded,
             login_data: data_template,
+            pagination: Pagination::default(),
         };
 
         rest_application
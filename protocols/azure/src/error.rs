@@ -31,6 +31,12 @@ pub enum AzureError {
     FmtError(#[from] std::fmt::Error),
     #[error("No credentials given")]
     NoLogin,
+    #[error("Invalid nextLink in response ({0}): {1}")]
+    InvalidNextLink(String, &'static str),
+    #[error("Listing exceeds the maximum of {0} pages")]
+    TooManyPages(usize),
+    #[error("Request to {0} is still throttled after retrying")]
+    Throttled(String),
     #[error("Recieved an error from azure: {}", .0.error.message)]
     Response(ErrorResponse),
 }
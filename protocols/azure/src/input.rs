@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use value::Type;
 
-use super::schema::MetricValue;
+use super::schema::{Interval, MetricValue};
 use agent_utils::TryAppend;
 use etc_base::{DataFieldId, DataTableId};
 
@@ -29,6 +29,29 @@ pub struct Input {
 pub struct ResourceSpec {
     pub(super) name_space: String,
     pub(super) dimension_name: Option<String>,
+    #[serde(default)]
+    pub(super) table_type: TableType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "PascalCase")]
+pub enum TableType {
+    /// One row per resource, with the metrics aggregated over the
+    /// values received since the previous run.
+    #[default]
+    Aggregated,
+    /// One row per resource and timestamp, as returned by Azure
+    /// Monitor.
+    TimeSeries(TimeSeriesSpec),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TimeSeriesSpec {
+    pub(super) time_grain: Interval,
+    /// Defaults to the resource type.
+    #[serde(default)]
+    pub(super) metric_namespace: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -44,6 +67,7 @@ impl MetricSpec {
     pub fn get_type(&self) -> Type {
         match self.metric_name.as_str() {
             "Resource" | "ResourceGroup" => Type::UnicodeString,
+            "Timestamp" => Type::Time,
             _ => Type::Float,
         }
     }
@@ -60,6 +84,18 @@ pub enum Aggregation {
 }
 
 impl Aggregation {
+    /// The value of this aggregation in a single timeseries element.
+    pub fn value(&self, value: &MetricValue) -> Option<f64> {
+        match self {
+            Self::Average => value.average,
+            Self::Minimum => value.minimum,
+            Self::Maximum => value.maximum,
+            Self::Total => value.total,
+            Self::Count => value.count,
+            Self::None => None,
+        }
+    }
+
     pub fn aggregate_time_series(
         &self,
         time_series: &Vec<MetricValue>,
@@ -18,4 +18,7 @@ pub use definitions::{
 pub use error::{AzureError, Result};
 pub use input::Input;
 pub use plugin::Plugin;
-pub use requests::{request_resource, request_resource_from_subscription};
+pub use rest_protocol::Session;
+pub use requests::{
+    request_metrics, request_resource, request_resource_from_subscription,
+};
@@ -9,9 +9,8 @@ use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use futures::{stream, StreamExt};
 use log::{debug, info, warn};
 use regex::Regex;
-use reqwest::Client;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Write,
     path::PathBuf,
 };
@@ -25,21 +24,24 @@ use etc_base::{
 };
 use etc_base::{DataFieldId, DataTableId, Protocol};
 use logger::Verbosity;
+use protocol::http::RequestStats;
 use protocol::LocalPlugin;
 use protocol::{DataFieldSpec, DataTableSpec};
 use rest_protocol::{
-    http::HTTPMethod, input::RESTRequest, RESTError, Template,
+    http::HTTPMethod, input::RESTRequest, RESTError, Session, Template,
 };
 use value::{DataError, Value};
 
 use super::config::Config;
 use super::error::{AzureDataError, AzureError, Result};
-use super::input::{Aggregation, Input, MetricSpec};
+use super::input::{Aggregation, Input, MetricSpec, TableType, TimeSeriesSpec};
+use super::requests::{self, request_resource_from_subscription};
 use super::schema::{MetricValue, Metrics, Response};
 
 type TableData = AnnotatedResult<Vec<ProtoRow>, AzureDataError, AzureError>;
 pub type DataMap = HashMap<ProtoDataTableId, TableData>;
 pub type DataResult = std::result::Result<AzureData, AzureDataError>;
+pub type SeriesResult = std::result::Result<AzureSeries, AzureDataError>;
 
 #[derive(Debug)]
 pub struct AzureData {
@@ -51,6 +53,15 @@ pub struct AzureData {
     aggregated_data: HashMap<String, HashMap<Aggregation, Option<f64>>>,
 }
 
+#[derive(Debug)]
+pub struct AzureSeries {
+    datatable_id: ProtoDataTableId,
+    resource_uri: String,
+    resource: String,
+    // {timestamp: {(metric_name, aggregation): value}}
+    values: BTreeMap<DateTime<Utc>, HashMap<(String, Aggregation), Value>>,
+}
+
 pub struct Plugin {
     key_vault: KeyVault,
     cache_dir: PathBuf,
@@ -64,58 +75,49 @@ impl Plugin {
         }
     }
 
+    async fn probe_subscriptions(&self, config: &Config) -> Result<()> {
+        let session = Session::from(config.login(Some(&self.key_vault)).await?);
+        let subscriptions = config.subscriptions.clone().unwrap_or_default();
+        if subscriptions.is_empty() {
+            return requests::head_resource(
+                &session,
+                "subscriptions",
+                "2020-01-01",
+            )
+            .await;
+        }
+        for subscription in subscriptions {
+            requests::head_resource(
+                &session,
+                &format!("subscriptions/{subscription}"),
+                "2020-01-01",
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
     // return {name_space: [(resource_name, resource_id)]}
     pub async fn request_resources(
         &self,
-        client: &Client,
+        session: &Session,
         subscriptions: Vec<String>,
     ) -> Result<HashMap<String, Vec<(String, String)>>> {
         let mut resources: HashMap<String, Vec<(String, String)>> =
             HashMap::new();
-        let mut request_data: HashMap<String, Template> = HashMap::new();
-        request_data.insert(
-            String::from("subscription"),
-            Template::parse("{{subscription}}")?,
-        );
-        let mut request: RESTRequest = RESTRequest {
-			url: UriTemplate::new("https://management.azure.com/subscriptions/{subscription}/resources?api-version=2019-10-01"),
-			data: request_data,
-			method: HTTPMethod::GET,
-			schema: super::schema::RESOURCES.clone(),
-			reference: None
-		};
         info!("subscriptions: {:?}", &subscriptions);
 
         for subscription in subscriptions {
-            let mut wato: HashMap<String, String> = HashMap::new();
-            wato.insert(String::from("subscription"), subscription);
-            request.url = UriTemplate::new("https://management.azure.com/subscriptions/{subscription}/resources?api-version=2019-10-01");
-            let mut request_data: HashMap<String, Template> = HashMap::new();
-            request_data.insert(
-                String::from("subscription"),
-                Template::parse("{{subscription}}")?,
-            );
-            request.data = request_data;
-
-            let response: String = request.execute(client, &wato).await?;
-            // validation::validate_json(&response, &request.schema)?;
-            let response: serde_json::Value = serde_json::from_str(&response)?;
-
-            // we already validated the respoinse with json schema, so we can unwrap without consequences
-            for resource_val in response
-                .as_object()
-                .ok_or(AzureError::RESTError(RESTError::ValidationError(
-                    vec![String::from("response is not an object")],
-                )))?
-                .get("value")
-                .ok_or(AzureError::RESTError(RESTError::ValidationError(
-                    vec![String::from("response has no value parameter")],
-                )))?
-                .as_array()
-                .ok_or(AzureError::RESTError(RESTError::ValidationError(
-                    vec![String::from("the value parameter is not an array")],
-                )))?
-            {
+            let response: Vec<serde_json::Value> =
+                request_resource_from_subscription(
+                    session,
+                    &subscription,
+                    "resources",
+                    "2019-10-01",
+                )
+                .await?;
+
+            for resource_val in &response {
                 let resource = resource_val.as_object().ok_or(
                     AzureError::RESTError(RESTError::ValidationError(vec![
                         String::from("resource is not an object"),
@@ -158,7 +160,7 @@ impl Plugin {
 
     pub async fn request_metrics(
         &self,
-        client: &Client,
+        session: &Session,
         datatable_id: ProtoDataTableId,
         resource: &String,
         resource_uri: &String,
@@ -285,7 +287,7 @@ impl Plugin {
 			};
 
             let response: String =
-                request.execute(client, &wato).await.map_err(|e| {
+                request.execute_in(session, &wato).await.map_err(|e| {
                     AzureDataError::RESTError(datatable_id.clone(), e)
                 })?;
             let response: Metrics = match serde_json::from_str(&response)
@@ -318,7 +320,7 @@ impl Plugin {
 
                     request.url = UriTemplate::new("https://management.azure.com/{+resourceUri}/providers/microsoft.insights/metrics?api-version=2018-01-01{&metricnames,aggregation,timespan,$filter}");
                     match serde_json::from_str(
-                        &request.execute(client, &new_wato).await.map_err(
+                        &request.execute_in(session, &new_wato).await.map_err(
                             |e| {
                                 AzureDataError::RESTError(
                                     datatable_id.clone(),
@@ -490,6 +492,108 @@ impl Plugin {
         Ok(azdata)
     }
 
+    /// Request the timeseries of a resource's metrics for the last
+    /// hour, at the table's time grain.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn request_time_series(
+        &self,
+        session: &Session,
+        datatable_id: ProtoDataTableId,
+        resource: &String,
+        resource_uri: &String,
+        name_space: &str,
+        spec: &TimeSeriesSpec,
+        metric_specs: &Vec<&MetricSpec>,
+    ) -> SeriesResult {
+        let namespace = spec.metric_namespace.as_deref().unwrap_or(name_space);
+        let now = Utc::now();
+        let timespan = (now - Duration::minutes(60), now);
+
+        let mut metrics: HashMap<&Aggregation, Vec<String>> = HashMap::new();
+        for metric in metric_specs {
+            let names = metrics.entry(&metric.aggregation).or_default();
+            if !names.contains(&metric.metric_name) {
+                names.push(metric.metric_name.clone());
+            }
+        }
+
+        let mut values = BTreeMap::new();
+        for (aggregation, names) in metrics {
+            for chunk in names.chunks(20) {
+                let series = requests::request_metrics(
+                    session,
+                    resource_uri,
+                    namespace,
+                    chunk,
+                    aggregation,
+                    &spec.time_grain,
+                    timespan,
+                )
+                .await
+                .map_err(|e| {
+                    AzureDataError::AzureData(datatable_id.clone(), e)
+                })?;
+                for (metric_name, series) in series {
+                    for (timestamp, value) in series {
+                        values
+                            .entry(timestamp)
+                            .or_insert_with(HashMap::new)
+                            .insert(
+                                (metric_name.clone(), aggregation.clone()),
+                                value,
+                            );
+                    }
+                }
+            }
+        }
+
+        Ok(AzureSeries {
+            datatable_id,
+            resource_uri: resource_uri.clone(),
+            resource: resource.clone(),
+            values,
+        })
+    }
+
+    fn series_rows(
+        &self,
+        input: &Input,
+        df_ids: &HashSet<ProtoDataFieldId>,
+        series: AzureSeries,
+    ) -> Result<Vec<ProtoRow>> {
+        let resource_group = self.get_resource_group(series.resource_uri);
+        let mut rows = Vec::with_capacity(series.values.len());
+        for (timestamp, values) in series.values {
+            let mut row = HashMap::new();
+            for df_id in df_ids {
+                let metric_spec = Self::get_datafield_id(df_id)
+                    .try_get_from(&input.data_fields)?;
+                let value = match metric_spec.metric_name.as_str() {
+                    "Resource" => Ok(Value::BinaryString(
+                        series.resource.as_bytes().to_vec(),
+                    )),
+                    "ResourceGroup" => match &resource_group {
+                        Some(group) => {
+                            Ok(Value::BinaryString(group.as_bytes().to_vec()))
+                        }
+                        None => Err(DataError::Missing),
+                    },
+                    "Timestamp" => Ok(Value::Time(timestamp)),
+                    name => values
+                        .get(&(
+                            name.to_string(),
+                            metric_spec.aggregation.clone(),
+                        ))
+                        .cloned()
+                        .ok_or(DataError::Missing),
+                };
+                row.insert(df_id.clone(), value);
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
     fn get_datatable_id(dt_id: &ProtoDataTableId) -> DataTableId {
         DataTableId(Protocol(Self::PROTOCOL.to_string()), dt_id.clone())
     }
@@ -514,6 +618,16 @@ impl protocol::LocalPlugin for Plugin {
     const PROTOCOL: &'static str = "Azure";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
+    /// Log in and send a HEAD request for each configured
+    /// subscription, or for the subscription list if none are.
+    async fn probe(
+        &self,
+        _input: &Input,
+        config: &Config,
+    ) -> Option<Result<()>> {
+        Some(self.probe_subscriptions(config).await)
+    }
+
     fn show_queries(
         &self,
         input: &Input,
@@ -521,6 +635,10 @@ impl protocol::LocalPlugin for Plugin {
     ) -> Result<String> {
         let mut out = String::new();
         for (resource_id, field_ids) in query {
+            if *resource_id == RequestStats::table_id() {
+                writeln!(out, "Azure: request stats")?;
+                continue;
+            }
             let resource = Self::get_datatable_id(resource_id)
                 .try_get_from(&input.data_tables)?;
             writeln!(
@@ -550,7 +668,7 @@ impl protocol::LocalPlugin for Plugin {
         &self,
         input: &Self::Input,
     ) -> Result<HashMap<ProtoDataTableId, DataTableSpec>> {
-        Ok(input
+        let mut tables: HashMap<_, _> = input
             .data_tables
             .keys()
             .map(|dt_id| {
@@ -583,14 +701,16 @@ impl protocol::LocalPlugin for Plugin {
                     },
                 )
             })
-            .collect())
+            .collect();
+        tables.extend([RequestStats::table_spec()]);
+        Ok(tables)
     }
 
     fn get_fields(
         &self,
         input: &Self::Input,
     ) -> Result<HashMap<ProtoDataFieldId, DataFieldSpec>> {
-        Ok(input
+        let mut fields: HashMap<_, _> = input
             .data_fields
             .iter()
             .map(|(df_id, metric_spec)| {
@@ -602,10 +722,13 @@ impl protocol::LocalPlugin for Plugin {
                             metric_spec.metric_name, metric_spec.aggregation
                         ),
                         input_type: metric_spec.get_type(),
+                        sensitive: false,
                     },
                 )
             })
-            .collect())
+            .collect();
+        fields.extend(RequestStats::field_specs());
+        Ok(fields)
     }
 
     async fn run_queries(
@@ -615,7 +738,16 @@ impl protocol::LocalPlugin for Plugin {
         query: &ProtoQueryMap,
     ) -> Result<DataMap> {
         // debug!("config: {:?}", &config);
-        let client: Client = config.login(Some(&self.key_vault)).await?;
+        let session = Session::from(config.login(Some(&self.key_vault)).await?);
+
+        /* The request stats are answered after the other queries. */
+        let stats_table = RequestStats::table_id();
+        let query_stats = query.contains_key(&stats_table);
+        let query: &ProtoQueryMap = &query
+            .iter()
+            .filter(|(dt_id, _)| **dt_id != stats_table)
+            .map(|(dt_id, df_ids)| (dt_id.clone(), df_ids.clone()))
+            .collect();
 
         let timestamp_file =
             self.cache_dir.join(String::from("azure_timstamps.json"));
@@ -631,7 +763,7 @@ impl protocol::LocalPlugin for Plugin {
         // {name_space: [(resource_name, resource_id)]}
         let name_spaces: HashMap<String, Vec<(String, String)>> = self
             .request_resources(
-                &client,
+                &session,
                 config.subscriptions.clone().unwrap_or_default(),
             )
             .await?;
@@ -651,6 +783,7 @@ impl protocol::LocalPlugin for Plugin {
                             if let Ok(m) = rm {
                                 if m.metric_name != "Resource"
                                     && m.metric_name != "ResourceGroup"
+                                    && m.metric_name != "Timestamp"
                                 {
                                     Some(m)
                                 } else {
@@ -667,6 +800,7 @@ impl protocol::LocalPlugin for Plugin {
 
         let empty_resourcelist = Vec::new();
         let mut futures: Vec<_> = Vec::new();
+        let mut series_futures: Vec<_> = Vec::new();
         for (dt_id, _) in query {
             let resource_spec = Self::get_datatable_id(dt_id)
                 .try_get_from(&input.data_tables)?;
@@ -690,6 +824,25 @@ impl protocol::LocalPlugin for Plugin {
                         }
                     }
 
+                    if let TableType::TimeSeries(spec) =
+                        &resource_spec.table_type
+                    {
+                        debug!(
+                            "scheduling timeseries request for resource: {:?}",
+                            &resource_spec
+                        );
+                        series_futures.push(self.request_time_series(
+                            &session,
+                            dt_id.clone(),
+                            resource,
+                            resource_uri,
+                            &resource_spec.name_space,
+                            spec,
+                            metrics,
+                        ));
+                        continue;
+                    }
+
                     let timestamps = timestamp_map
                         .remove(resource_uri)
                         .unwrap_or(HashMap::new());
@@ -698,7 +851,7 @@ impl protocol::LocalPlugin for Plugin {
                         &resource_spec
                     );
                     futures.push(self.request_metrics(
-                        &client,
+                        &session,
                         dt_id.clone(),
                         resource,
                         resource_uri,
@@ -725,6 +878,20 @@ impl protocol::LocalPlugin for Plugin {
             data.entry(dt).or_insert(Vec::new()).push(resp);
         }
 
+        let series_responses = stream::iter(series_futures)
+            .buffer_unordered(8)
+            .collect::<Vec<SeriesResult>>()
+            .await;
+        let mut series: HashMap<ProtoDataTableId, Vec<SeriesResult>> =
+            HashMap::new();
+        for resp in series_responses.into_iter() {
+            let dt = match &resp {
+                Ok(d) => d.datatable_id.clone(),
+                Err(e) => e.get_dt(),
+            };
+            series.entry(dt).or_insert(Vec::new()).push(resp);
+        }
+
         let mut datamap: DataMap = HashMap::new();
         let mut new_timestamps: HashMap<
             String,
@@ -821,6 +988,13 @@ impl protocol::LocalPlugin for Plugin {
                 }
             }
 
+            for azs in series.remove(dt_id).unwrap_or_default() {
+                match azs {
+                    Ok(s) => rows.extend(self.series_rows(input, df_ids, s)?),
+                    Err(e) => errors.push(e),
+                }
+            }
+
             debug!("Inserting rows for {}: {:?}", &dt_id, &rows);
             datamap.insert(
                 dt_id.clone(),
@@ -847,6 +1021,16 @@ impl protocol::LocalPlugin for Plugin {
             .write_all(&serde_json::to_vec(&timestamp_map)?)
             .await?;
 
+        if query_stats {
+            datamap.insert(
+                stats_table,
+                Ok(Annotated {
+                    value: session.stats().rows(),
+                    warnings: Vec::new(),
+                }),
+            );
+        }
+
         Ok(datamap)
     }
 }
@@ -2,20 +2,40 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use log::debug;
-use reqwest::Client;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{debug, info};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{StatusCode, Url};
+use tokio::time::sleep;
 use uritemplate::UriTemplate;
 
+use crate::input::Aggregation;
+use crate::schema::{Interval, Metrics, Response};
 use crate::AzureError;
-use rest_protocol::{http::*, input::RESTRequest, Template};
+use rest_protocol::{
+    http::*, input::RESTRequest, RESTError, Session, Template,
+};
 use serde::de::DeserializeOwned;
+use value::Value;
 
-use crate::{ResourceResponse, Result, SubscriptionId};
+use crate::{ResourceId, ResourceResponse, Result, SubscriptionId};
+
+const METRICS_API_VERSION: &str = "2018-01-01";
+/// Upper bound on the number of pages followed for a single listing.
+const MAX_PAGES: usize = 1000;
+/// Number of times a throttled request is retried.
+const THROTTLE_RETRIES: u16 = 5;
+/// Wait time for throttled responses without a Retry-After header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+/// Total time a request may wait on throttling, before giving up
+/// instead of stalling the whole poll.
+const MAX_THROTTLE_WAIT: Duration = Duration::from_secs(60);
 
 pub async fn paged_requests<T: DeserializeOwned>(
-    client: &Client,
+    session: &Session,
     url: UriTemplate,
     tmpvars: HashMap<String, Template>,
     data: HashMap<String, String>,
@@ -28,8 +48,8 @@ pub async fn paged_requests<T: DeserializeOwned>(
         schema: serde_json::Value::Null,
         reference: None,
     };
-    let response = request.execute(client, &data).await?;
-    debug!("response from azure: {}", &response);
+    let response = request.execute_in(session, &data).await?;
+    debug!("response from azure: {} bytes", response.len());
     let mut response = serde_json::from_str::<ResourceResponse<T>>(&response)?;
 
     match response {
@@ -37,16 +57,24 @@ pub async fn paged_requests<T: DeserializeOwned>(
         ResourceResponse::Success(resources) => {
             let mut results = resources.value;
             let mut next_link = resources.next_link;
-            while let Some(ref next) = next_link {
+            let mut followed = HashSet::new();
+            while let Some(next) = next_link.take() {
+                let next = check_next_link(next, &followed)?;
+                followed.insert(next.clone());
+                if followed.len() >= MAX_PAGES {
+                    return Err(AzureError::TooManyPages(MAX_PAGES));
+                }
+                debug!("following nextLink: {}", &next);
+
                 let mut request = RESTRequest {
-                    url: UriTemplate::new(next),
+                    url: UriTemplate::new(&next),
                     data: HashMap::new(),
                     method: HTTPMethod::GET,
                     schema: serde_json::Value::Null,
                     reference: None,
                 };
                 response = serde_json::from_str::<ResourceResponse<T>>(
-                    &request.execute(client, &HashMap::new()).await?,
+                    &request.execute_in(session, &HashMap::new()).await?,
                 )?;
                 match response {
                     ResourceResponse::Error(e) => {
@@ -64,13 +92,29 @@ pub async fn paged_requests<T: DeserializeOwned>(
     }
 }
 
+/// Verify that a nextLink is an absolute https url that was not
+/// followed before, to avoid looping forever on a bad response.
+fn check_next_link(next: String, followed: &HashSet<String>) -> Result<String> {
+    match Url::parse(&next) {
+        Err(_) => Err(AzureError::InvalidNextLink(next, "not a valid url")),
+        Ok(url) if url.scheme() != "https" => {
+            Err(AzureError::InvalidNextLink(next, "not an https url"))
+        }
+        Ok(_) if followed.contains(&next) => Err(AzureError::InvalidNextLink(
+            next,
+            "link was already followed",
+        )),
+        Ok(_) => Ok(next),
+    }
+}
+
 pub async fn request_resource<T: DeserializeOwned>(
-    client: &Client,
+    session: &Session,
     resource: &str,
     api_version: &str,
 ) -> Result<Vec<T>> {
     paged_requests(
-        client,
+        session,
         UriTemplate::new(
             "https://management.azure.com/{resource}?api-version={api_version}",
         ),
@@ -95,14 +139,38 @@ pub async fn request_resource<T: DeserializeOwned>(
     .await
 }
 
+/// Send a HEAD request for a resource, to check that it is reachable
+/// with the client's credentials without transferring it.
+pub async fn head_resource(
+    session: &Session,
+    resource: &str,
+    api_version: &str,
+) -> Result<()> {
+    let url = format!(
+        "https://management.azure.com/{resource}?api-version={api_version}"
+    );
+    let response = session
+        .stats()
+        .send(session.client().head(&url))
+        .await
+        .map_err(RESTError::from)?;
+    match response.status.is_success() {
+        true => Ok(()),
+        false => Err(AzureError::ResponseError(format!(
+            "HEAD {}: {}",
+            url, response.status
+        ))),
+    }
+}
+
 pub async fn request_resource_from_subscription<T: DeserializeOwned>(
-    client: &Client,
+    session: &Session,
     subscription: &SubscriptionId,
     resource: &str,
     api_version: &str,
 ) -> Result<Vec<T>> {
     paged_requests(
-        client,
+        session,
         UriTemplate::new("https://management.azure.com/subscriptions/{subscription}/{resource}?api-version={api_version}"),
         [(String::from("resource"), Template::parse("{{resource}}")?), 
             (String::from("api_version"), Template::parse("{{api_version}}")?),
@@ -114,3 +182,213 @@ pub async fn request_resource_from_subscription<T: DeserializeOwned>(
             .iter().cloned().collect()
     ).await
 }
+
+/// Request metric timeseries for a resource from Azure Monitor. The
+/// values of the requested aggregation are returned per metric name,
+/// with their timestamps. Elements without a value are skipped.
+pub async fn request_metrics(
+    session: &Session,
+    resource: &ResourceId,
+    namespace: &str,
+    metric_names: &[String],
+    aggregation: &Aggregation,
+    interval: &Interval,
+    timespan: (DateTime<Utc>, DateTime<Utc>),
+) -> Result<HashMap<String, Vec<(DateTime<Utc>, Value)>>> {
+    let url = format!(
+        "https://management.azure.com/{}/providers/microsoft.insights/metrics",
+        resource.trim_start_matches('/')
+    );
+    let query = [
+        ("api-version", METRICS_API_VERSION.to_string()),
+        ("metricnamespace", namespace.to_string()),
+        ("metricnames", metric_names.join(",")),
+        ("aggregation", aggregation.to_string()),
+        ("interval", interval.to_string()),
+        (
+            "timespan",
+            format!(
+                "{}/{}",
+                timespan.0.to_rfc3339_opts(SecondsFormat::Secs, true),
+                timespan.1.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+        ),
+    ];
+
+    let response = get_with_retry(session, &url, &query).await?;
+    debug!("metrics from azure: {} bytes", response.len());
+    let metrics = match serde_json::from_str::<Response<Metrics>>(&response)? {
+        Response::Ok(metrics) => metrics,
+        Response::Err(err) => {
+            return Err(AzureError::ResponseError(format!(
+                "{}: {}",
+                err.code, err.message
+            )))
+        }
+    };
+
+    metrics
+        .value
+        .into_iter()
+        .map(|metric| match metric.error_code.as_str() {
+            "Success" => Ok((
+                metric.name.value,
+                metric
+                    .timeseries
+                    .iter()
+                    .flat_map(|ts| &ts.data)
+                    .filter_map(|elem| {
+                        aggregation
+                            .value(elem)
+                            .map(|v| (elem.timestamp, Value::Float(v)))
+                    })
+                    .collect(),
+            )),
+            code => Err(AzureError::ResponseError(format!(
+                "{}: {}",
+                code,
+                metric.error_message.as_deref().unwrap_or("unknown error")
+            ))),
+        })
+        .collect()
+}
+
+/// Send a GET request, honoring the Retry-After header when the
+/// request is throttled, as long as the total wait stays within
+/// `MAX_THROTTLE_WAIT`.
+async fn get_with_retry(
+    session: &Session,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<String> {
+    let mut retries = THROTTLE_RETRIES;
+    let mut waited = Duration::ZERO;
+    loop {
+        let response = session
+            .stats()
+            .send(session.client().get(url).query(query))
+            .await
+            .map_err(RESTError::from)?;
+        if response.status != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response.text());
+        }
+        if retries == 0 {
+            return Err(AzureError::Throttled(url.to_string()));
+        }
+
+        let wait =
+            retry_after(&response.headers).unwrap_or(DEFAULT_RETRY_AFTER);
+        waited += wait;
+        if waited > MAX_THROTTLE_WAIT {
+            info!(
+                "request for '{}' was throttled for {}s; giving up",
+                url,
+                wait.as_secs()
+            );
+            return Err(AzureError::Throttled(url.to_string()));
+        }
+        info!(
+            "request for '{}' was throttled; retrying in {}s ({} retries left)",
+            url,
+            wait.as_secs(),
+            retries
+        );
+        sleep(wait).await;
+        retries -= 1;
+    }
+}
+
+/// Parse the Retry-After header, which holds either a number of
+/// seconds or an HTTP date. Dates in the past mean no wait.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?;
+            Some(
+                (date.with_timezone(&Utc) - Utc::now())
+                    .to_std()
+                    .unwrap_or(Duration::ZERO),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::Client;
+    use rest_protocol::Session;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{get_with_retry, retry_after};
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(
+            retry_after(&headers("120")),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(retry_after(&headers(" 5 ")), Some(Duration::from_secs(5)));
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("soon")), None);
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        let date = (Utc::now() + chrono::Duration::seconds(30))
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let wait = retry_after(&headers(&date)).unwrap();
+        assert!(wait <= Duration::from_secs(30));
+        assert!(wait >= Duration::from_secs(25));
+    }
+
+    #[test]
+    fn retry_after_past_date() {
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_are_recorded() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\
+                      Connection: close\r\n\r\n{}",
+                )
+                .await
+                .unwrap();
+        });
+
+        let session = Session::from(Client::new());
+        let query = [("api-version", String::from("2018-01-01"))];
+        let response = get_with_retry(&session, &url, &query).await.unwrap();
+        assert_eq!(response, "{}");
+
+        let stats = session.stats().get("GET /metrics").unwrap();
+        assert_eq!(stats.requests, 1);
+        assert_eq!(stats.failures, 0);
+        assert_eq!(stats.bytes, 2);
+        assert_eq!(stats.last_status, Some(200));
+    }
+}
@@ -7,6 +7,7 @@ use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashSet;
+use std::fmt;
 
 use crate::error::AzureError;
 /* Metrics */
@@ -77,11 +78,18 @@ pub enum Interval {
     PT15M,
     PT30M,
     PT1H,
-    PT6,
+    #[serde(alias = "PT6")]
+    PT6H,
     PT12H,
     PT1D,
 }
 
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum Unit {
     Count,
@@ -0,0 +1,31 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Config {
+    /// Command timeout in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Commands producing more output than this are aborted.
+    #[serde(default)]
+    pub max_output_bytes: Option<u64>,
+}
+
+impl Config {
+    const DEFAULT_TIMEOUT: u64 = 10;
+    const DEFAULT_MAX_OUTPUT_BYTES: u64 = 16 * 1024 * 1024;
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT))
+    }
+
+    pub fn max_output_bytes(&self) -> u64 {
+        self.max_output_bytes
+            .unwrap_or(Self::DEFAULT_MAX_OUTPUT_BYTES)
+    }
+}
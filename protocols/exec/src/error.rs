@@ -0,0 +1,49 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    AgentUtils(#[from] agent_utils::Error),
+    #[error("{0}")]
+    Format(#[from] std::fmt::Error),
+}
+
+pub type TypeResult<T> = std::result::Result<T, TypeError>;
+
+#[derive(Error, Debug)]
+pub enum TypeError {
+    #[error("{0}")]
+    AgentUtils(#[from] agent_utils::Error),
+}
+
+pub type DTResult<T> = std::result::Result<T, DTError>;
+
+#[derive(Error, Debug)]
+pub enum DTError {
+    #[error("Empty command line")]
+    EmptyCommand,
+    #[error("Command {0} is not in the allowlist")]
+    NotAllowed(String),
+    #[error("Failed to execute {0}: {1}")]
+    Spawn(String, #[source] std::io::Error),
+    #[error("Failed to read output of {0}: {1}")]
+    Read(String, #[source] std::io::Error),
+    #[error("Command {0} timed out after {1} seconds")]
+    Timeout(String, u64),
+    #[error("Output of command {0} exceeds {1} bytes")]
+    OutputTooLarge(String, u64),
+    #[error("Invalid regex for command {0}: {1}")]
+    Regex(String, #[source] regex::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum DTWarning {
+    #[error("Command {0} failed with exit status {1}: {2}")]
+    ExitStatus(String, i32, String),
+}
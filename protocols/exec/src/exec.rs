@@ -0,0 +1,111 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+use crate::error::{DTError, DTResult};
+
+/// The search path is never consulted, but commands may start other
+/// programs themselves.
+const PATH: &str =
+    "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Programs the plugin may execute, by absolute path. The allowlist
+/// is part of the agent's local configuration, so that a remote
+/// configuration cannot make the agent run arbitrary commands.
+#[derive(Clone, Debug, Default)]
+pub struct Allowlist(BTreeSet<PathBuf>);
+
+impl Allowlist {
+    pub fn new<I: IntoIterator<Item = PathBuf>>(programs: I) -> Self {
+        Self(programs.into_iter().collect())
+    }
+
+    pub fn allows(&self, program: &Path) -> bool {
+        program.is_absolute() && self.0.contains(program)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// None if the command was terminated by a signal.
+    pub exit_code: Option<i32>,
+}
+
+/// Run a command directly (not through a shell), with a cleared
+/// environment. The command is killed when it does not finish within
+/// `timeout` or produces more than `max_bytes` of output.
+pub async fn run_command(
+    command: &[String],
+    allowlist: &Allowlist,
+    timeout: Duration,
+    max_bytes: u64,
+) -> DTResult<CommandOutput> {
+    let (program, args) = command.split_first().ok_or(DTError::EmptyCommand)?;
+    if !allowlist.allows(Path::new(program)) {
+        return Err(DTError::NotAllowed(program.clone()));
+    }
+
+    log::debug!("executing {}", command.join(" "));
+    let mut child = Command::new(program)
+        .args(args)
+        .env_clear()
+        .env("PATH", PATH)
+        .env("LANG", "C")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| DTError::Spawn(program.clone(), e))?;
+
+    let stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+    let run = async {
+        let (stdout, stderr) = futures::try_join!(
+            read_limited(program, stdout, max_bytes),
+            read_limited(program, stderr, max_bytes),
+        )?;
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| DTError::Read(program.clone(), e))?;
+        Ok(CommandOutput {
+            stdout,
+            stderr,
+            exit_code: status.code(),
+        })
+    };
+
+    /* Dropping the child on timeout or error kills the command. */
+    match tokio::time::timeout(timeout, run).await {
+        Ok(res) => res,
+        Err(_) => Err(DTError::Timeout(program.clone(), timeout.as_secs())),
+    }
+}
+
+async fn read_limited<R: AsyncRead + Unpin>(
+    program: &str,
+    reader: R,
+    max_bytes: u64,
+) -> DTResult<String> {
+    let mut buf = Vec::new();
+    reader
+        .take(max_bytes + 1)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(|e| DTError::Read(program.to_string(), e))?;
+    match buf.len() as u64 > max_bytes {
+        true => Err(DTError::OutputTooLarge(program.to_string(), max_bytes)),
+        false => Ok(String::from_utf8_lossy(&buf).into_owned()),
+    }
+}
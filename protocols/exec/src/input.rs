@@ -0,0 +1,218 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use agent_utils::TryAppend;
+use etc_base::{ProtoDataFieldId, ProtoDataTableId, ProtoRow};
+use value::{Data, DataError, Type, Value};
+
+use crate::error::{DTError, DTResult};
+use crate::exec::CommandOutput;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Input {
+    pub data_tables: HashMap<ProtoDataTableId, TableSpec>,
+    pub data_fields: HashMap<ProtoDataFieldId, FieldSpec>,
+    pub data_table_fields: HashMap<ProtoDataTableId, HashSet<ProtoDataFieldId>>,
+}
+
+/// A table holds the parsed output of one command.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TableSpec {
+    pub command_name: String,
+    /// The program, by absolute path, and its arguments.
+    pub command_line: Vec<String>,
+    pub parser: Parser,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Parser {
+    /// One row per line, split into columns on the separator, or on
+    /// whitespace if no separator is given.
+    Columns {
+        #[serde(default)]
+        separator: Option<String>,
+        /// Header lines to skip.
+        #[serde(default)]
+        skip_lines: usize,
+    },
+    /// One row per line matching the regex; fields refer to named
+    /// capture groups.
+    Regex { pattern: String },
+    /// A single row from `key<separator>value` lines.
+    KeyValue { separator: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FieldSpec {
+    pub field_name: String,
+    pub source: FieldSource,
+    #[serde(default)]
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub is_key: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldSource {
+    /// A column (from zero) of the `columns` parser.
+    Column(usize),
+    /// A named capture group of the `regex` parser.
+    Group(String),
+    /// A key of the `key_value` parser.
+    Key(String),
+    /// The exit code of the command.
+    ExitCode,
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl TableSpec {
+    /// Map command output to rows.
+    pub fn parse_output(
+        &self,
+        output: &CommandOutput,
+        fields: &[(&ProtoDataFieldId, &FieldSpec)],
+    ) -> DTResult<Vec<ProtoRow>> {
+        let row = |get: &dyn Fn(&FieldSource) -> Option<String>| {
+            fields
+                .iter()
+                .map(|(id, field)| {
+                    let value = match &field.source {
+                        FieldSource::ExitCode => match output.exit_code {
+                            Some(code) => Ok(Value::Integer(code as i64)),
+                            None => Err(DataError::Missing),
+                        },
+                        source => match get(source) {
+                            Some(s) => field.field_type.parse(s.trim()),
+                            None => Err(DataError::Missing),
+                        },
+                    };
+                    ((*id).clone(), value)
+                })
+                .collect::<ProtoRow>()
+        };
+
+        let lines = output.stdout.lines().filter(|l| !l.trim().is_empty());
+        Ok(match &self.parser {
+            Parser::Columns {
+                separator,
+                skip_lines,
+            } => lines
+                .skip(*skip_lines)
+                .map(|line| {
+                    let columns: Vec<&str> = match separator {
+                        Some(sep) => line.split(sep.as_str()).collect(),
+                        None => line.split_whitespace().collect(),
+                    };
+                    row(&|source| match source {
+                        FieldSource::Column(i) => {
+                            columns.get(*i).map(|s| s.to_string())
+                        }
+                        _ => None,
+                    })
+                })
+                .collect(),
+            Parser::Regex { pattern } => {
+                let regex = Regex::new(pattern).map_err(|e| {
+                    DTError::Regex(self.command_name.clone(), e)
+                })?;
+                lines
+                    .filter_map(|line| regex.captures(line))
+                    .map(|captures| {
+                        row(&|source| match source {
+                            FieldSource::Group(name) => captures
+                                .name(name)
+                                .map(|m| m.as_str().to_string()),
+                            _ => None,
+                        })
+                    })
+                    .collect()
+            }
+            Parser::KeyValue { separator } => {
+                let values = lines
+                    .filter_map(|line| line.split_once(separator.as_str()))
+                    .map(|(k, v)| (k.trim(), v))
+                    .collect::<HashMap<_, _>>();
+                vec![row(&|source| match source {
+                    FieldSource::Key(key) => {
+                        values.get(key.as_str()).map(|v| v.to_string())
+                    }
+                    _ => None,
+                })]
+            }
+        })
+    }
+
+    /// Key-value output forms a single row.
+    pub fn is_singleton(&self) -> bool {
+        matches!(self.parser, Parser::KeyValue { .. })
+    }
+}
+
+impl FieldType {
+    pub fn get_type(&self) -> Type {
+        match self {
+            FieldType::String => Type::UnicodeString,
+            FieldType::Integer => Type::Integer,
+            FieldType::Float => Type::Float,
+            FieldType::Boolean => Type::Boolean,
+        }
+    }
+
+    fn parse(&self, s: &str) -> Data {
+        let invalid = || DataError::TypeError(format!("invalid {self:?}: {s}"));
+        match self {
+            FieldType::String => Ok(Value::UnicodeString(s.to_string())),
+            FieldType::Integer => {
+                s.parse().map(Value::Integer).map_err(|_| invalid())
+            }
+            FieldType::Float => {
+                s.parse().map(Value::Float).map_err(|_| invalid())
+            }
+            FieldType::Boolean => match s.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(Value::Boolean(true)),
+                "false" | "no" | "off" | "0" => Ok(Value::Boolean(false)),
+                _ => Err(invalid()),
+            },
+        }
+    }
+}
+
+impl FieldSpec {
+    pub fn get_type(&self) -> Type {
+        match self.source {
+            FieldSource::ExitCode => Type::Integer,
+            _ => self.field_type.get_type(),
+        }
+    }
+}
+
+impl TryAppend for Input {
+    fn try_append(&mut self, other: Self) -> agent_utils::Result<()> {
+        self.data_tables.try_append(other.data_tables)?;
+        self.data_fields.try_append(other.data_fields)?;
+        self.data_table_fields.try_append(other.data_table_fields)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+mod config;
+mod error;
+mod exec;
+mod input;
+mod plugin;
+
+pub use config::Config;
+pub use error::{DTError, DTResult, DTWarning, Error, Result, TypeError};
+pub use exec::{run_command, Allowlist, CommandOutput};
+pub use input::{FieldSource, FieldSpec, FieldType, Input, Parser, TableSpec};
+pub use plugin::Plugin;
@@ -0,0 +1,181 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use agent_utils::TryGet;
+use etc_base::{
+    Annotated, AnnotatedResult, ProtoDataFieldId, ProtoDataTableId,
+    ProtoQueryMap, ProtoRow, Warning,
+};
+use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin};
+
+use crate::{
+    error::{DTError, DTWarning, Result, TypeError, TypeResult},
+    exec::{run_command, Allowlist},
+    input::{FieldSpec, Input, TableSpec},
+    Config, Error,
+};
+
+type TableData = AnnotatedResult<Vec<ProtoRow>, DTWarning, DTError>;
+type DataMap = HashMap<ProtoDataTableId, TableData>;
+
+/// Runs commands on the agent's own host.
+pub struct Plugin {
+    allowlist: Allowlist,
+}
+
+impl Plugin {
+    pub fn new(allowlist: Allowlist) -> Self {
+        Self { allowlist }
+    }
+
+    async fn get_data(
+        &self,
+        table: &TableSpec,
+        fields: &[(&ProtoDataFieldId, &FieldSpec)],
+        config: &Config,
+    ) -> TableData {
+        let output = run_command(
+            &table.command_line,
+            &self.allowlist,
+            config.timeout(),
+            config.max_output_bytes(),
+        )
+        .await?;
+
+        let mut warnings = Vec::new();
+        if output.exit_code != Some(0) {
+            let warn = Warning::warn(DTWarning::ExitStatus(
+                table.command_name.clone(),
+                output.exit_code.unwrap_or(-1),
+                output.stderr.trim().to_string(),
+            ));
+            warn.log();
+            warnings.push(warn);
+        }
+
+        Ok(Annotated {
+            value: table.parse_output(&output, fields)?,
+            warnings,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl LocalPlugin for Plugin {
+    type Error = Error;
+    type TypeError = TypeError;
+    type DTError = DTError;
+    type DTWarning = DTWarning;
+
+    type Input = Input;
+    type Config = Config;
+
+    const PROTOCOL: &'static str = "Exec";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    fn show_queries(
+        &self,
+        input: &Input,
+        query: &ProtoQueryMap,
+    ) -> Result<String> {
+        let mut out = String::new();
+        for table in query.keys() {
+            let table = input.data_tables.try_get(table)?;
+            writeln!(
+                out,
+                "{}: {}, {}",
+                Self::PROTOCOL,
+                table.command_name,
+                table.command_line.join(" ")
+            )?;
+        }
+        Ok(out)
+    }
+
+    fn get_tables(
+        &self,
+        input: &Input,
+    ) -> TypeResult<HashMap<ProtoDataTableId, DataTableSpec>> {
+        input
+            .data_tables
+            .iter()
+            .map(|(dt_id, table)| {
+                let dfs = input
+                    .data_table_fields
+                    .try_get(dt_id)?
+                    .iter()
+                    .map(|df_id| {
+                        input.data_fields.try_get(df_id).map(|df| (df_id, df))
+                    })
+                    .collect::<agent_utils::Result<HashMap<_, _>>>()?;
+                Ok((
+                    dt_id.clone(),
+                    DataTableSpec {
+                        name: dt_id.0.clone(),
+                        keys: dfs
+                            .iter()
+                            .filter(|(_, v)| v.is_key)
+                            .map(|(&k, _)| k.clone())
+                            .collect(),
+                        singleton: table.is_singleton(),
+                        fields: dfs.into_keys().cloned().collect(),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn get_fields(
+        &self,
+        input: &Input,
+    ) -> TypeResult<HashMap<ProtoDataFieldId, DataFieldSpec>> {
+        Ok(input
+            .data_fields
+            .iter()
+            .map(|(df_id, df)| {
+                (
+                    df_id.clone(),
+                    DataFieldSpec {
+                        name: df.field_name.clone(),
+                        input_type: df.get_type(),
+                        sensitive: false,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn run_queries(
+        &self,
+        input: &Input,
+        config: &Config,
+        query: &ProtoQueryMap,
+    ) -> Result<DataMap> {
+        let tables = query
+            .iter()
+            .map(|(dt_id, df_ids)| {
+                let table = input.data_tables.try_get(dt_id)?;
+                let fields = df_ids
+                    .iter()
+                    .map(|df_id| {
+                        input.data_fields.try_get(df_id).map(|df| (df_id, df))
+                    })
+                    .collect::<agent_utils::Result<Vec<_>>>()?;
+                Ok((dt_id, table, fields))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(futures::future::join_all(tables.iter().map(
+            |(dt_id, table, fields)| async move {
+                ((*dt_id).clone(), self.get_data(table, fields, config).await)
+            },
+        ))
+        .await
+        .into_iter()
+        .collect())
+    }
+}
@@ -0,0 +1,166 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use etc_base::ProtoDataFieldId;
+use exec_protocol::{
+    run_command, Allowlist, CommandOutput, DTError, FieldSource, FieldSpec,
+    FieldType, Parser, TableSpec,
+};
+use value::{DataError, Value};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BYTES: u64 = 1024 * 1024;
+
+fn allowlist() -> Allowlist {
+    Allowlist::new([PathBuf::from("/bin/sh"), PathBuf::from("/bin/sleep")])
+}
+
+fn sh(script: &str) -> Vec<String> {
+    vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()]
+}
+
+fn field(name: &str, source: FieldSource, field_type: FieldType) -> FieldSpec {
+    FieldSpec {
+        field_name: name.to_string(),
+        source,
+        field_type,
+        is_key: false,
+    }
+}
+
+#[tokio::test]
+async fn run_and_map_columns() {
+    let command = sh("printf 'name state mtu\\neth0 up 1500\\neth1 down\\n'");
+    let output = run_command(&command, &allowlist(), TIMEOUT, MAX_BYTES)
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, Some(0));
+
+    let table = TableSpec {
+        command_name: "interfaces".to_string(),
+        command_line: command,
+        parser: Parser::Columns {
+            separator: None,
+            skip_lines: 1,
+        },
+    };
+    let (name, mtu) = (
+        ProtoDataFieldId("name".to_string()),
+        ProtoDataFieldId("mtu".to_string()),
+    );
+    let name_spec = field("name", FieldSource::Column(0), FieldType::String);
+    let mtu_spec = field("mtu", FieldSource::Column(2), FieldType::Integer);
+    let rows = table
+        .parse_output(&output, &[(&name, &name_spec), (&mtu, &mtu_spec)])
+        .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][&name], Ok(Value::UnicodeString("eth0".to_string())));
+    assert_eq!(rows[0][&mtu], Ok(Value::Integer(1500)));
+    assert_eq!(rows[1][&mtu], Err(DataError::Missing));
+}
+
+#[tokio::test]
+async fn exit_code() {
+    let output = run_command(
+        &sh("echo oops >&2; exit 3"),
+        &allowlist(),
+        TIMEOUT,
+        MAX_BYTES,
+    )
+    .await
+    .unwrap();
+    assert_eq!(output.exit_code, Some(3));
+    assert_eq!(output.stderr, "oops\n");
+}
+
+#[tokio::test]
+async fn not_allowed() {
+    let command = vec!["/bin/echo".to_string(), "hello".to_string()];
+    assert!(matches!(
+        run_command(&command, &allowlist(), TIMEOUT, MAX_BYTES).await,
+        Err(DTError::NotAllowed(_))
+    ));
+    let command = vec!["sh".to_string(), "-c".to_string(), "true".to_string()];
+    assert!(matches!(
+        run_command(&command, &allowlist(), TIMEOUT, MAX_BYTES).await,
+        Err(DTError::NotAllowed(_))
+    ));
+}
+
+#[tokio::test]
+async fn timeout() {
+    let command = vec!["/bin/sleep".to_string(), "5".to_string()];
+    assert!(matches!(
+        run_command(
+            &command,
+            &allowlist(),
+            Duration::from_millis(100),
+            MAX_BYTES
+        )
+        .await,
+        Err(DTError::Timeout(_, _))
+    ));
+}
+
+#[tokio::test]
+async fn output_limit() {
+    assert!(matches!(
+        run_command(&sh("yes | head -c 4096"), &allowlist(), TIMEOUT, 1024)
+            .await,
+        Err(DTError::OutputTooLarge(_, 1024))
+    ));
+}
+
+#[test]
+fn regex_and_key_value() {
+    let output = CommandOutput {
+        stdout: "MemTotal:  16310200 kB\nMemFree:   1203420 kB\n".to_string(),
+        stderr: String::new(),
+        exit_code: Some(0),
+    };
+    let total = ProtoDataFieldId("total".to_string());
+    let status = ProtoDataFieldId("status".to_string());
+
+    let table = TableSpec {
+        command_name: "meminfo".to_string(),
+        command_line: sh("cat /proc/meminfo"),
+        parser: Parser::KeyValue {
+            separator: ":".to_string(),
+        },
+    };
+    let spec = field(
+        "total",
+        FieldSource::Key("MemTotal".to_string()),
+        FieldType::String,
+    );
+    let exit = field("status", FieldSource::ExitCode, FieldType::Integer);
+    let rows = table
+        .parse_output(&output, &[(&total, &spec), (&status, &exit)])
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        rows[0][&total],
+        Ok(Value::UnicodeString("16310200 kB".to_string()))
+    );
+    assert_eq!(rows[0][&status], Ok(Value::Integer(0)));
+
+    let table = TableSpec {
+        parser: Parser::Regex {
+            pattern: r"^(?P<key>\w+):\s+(?P<kb>\d+) kB$".to_string(),
+        },
+        ..table
+    };
+    let spec = field(
+        "total",
+        FieldSource::Group("kb".to_string()),
+        FieldType::Integer,
+    );
+    let rows = table.parse_output(&output, &[(&total, &spec)]).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1][&total], Ok(Value::Integer(1203420)));
+}
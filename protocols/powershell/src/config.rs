@@ -2,7 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::{collections::HashMap, net::IpAddr, path::PathBuf};
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, time::Duration};
 
 use handlebars::Context;
 use log::{debug, info, warn};
@@ -26,6 +26,10 @@ use crate::{
 pub struct Config {
     connection: ConnectionConfig,
     script_context: HashMap<String, String>,
+    /// Discard stored counters older than this many seconds. By
+    /// default, stored counters are kept indefinitely.
+    #[serde(default)]
+    counter_ttl: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -228,6 +232,39 @@ impl Config {
     pub fn script_context(&self) -> Context {
         Context::wraps(&self.script_context).unwrap()
     }
+
+    pub fn hostname(&self) -> &str {
+        match &self.connection {
+            ConnectionConfig::WinRM(config) => &config.hostname,
+            ConnectionConfig::WindowsAgent(config) => &config.hostname,
+        }
+    }
+
+    pub fn counter_ttl(&self) -> Option<Duration> {
+        self.counter_ttl.map(Duration::from_secs)
+    }
+
+    /// The key (host and user) and time-to-live for caching sessions
+    /// with this config, if enabled.
+    pub fn session_cache(&self) -> Option<(String, Duration)> {
+        match &self.connection {
+            ConnectionConfig::WinRM(WinrmConfig {
+                hostname,
+                credentials: Some(Credentials::Kerberos(kauth)),
+                ..
+            }) => kauth.cache_ttl.map(|ttl| {
+                (
+                    format!(
+                        "{hostname}/{}@{}",
+                        kauth.ccache_name.as_deref().unwrap_or_default(),
+                        kauth.realm.to_uppercase()
+                    ),
+                    Duration::from_secs(ttl),
+                )
+            }),
+            _ => None,
+        }
+    }
 }
 
 impl ConnectionConfig {
@@ -337,6 +374,10 @@ pub struct KerberosCredentials {
     pub hostname: String,
     pub realm: String,
     pub ccache_name: Option<String>,
+    /// Keep the authenticated session for this many seconds, so that
+    /// subsequent runs do not log on again.
+    #[serde(default)]
+    pub cache_ttl: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -2,7 +2,13 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::{collections::HashMap, fmt::Write, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use agent_utils::{KeyVault, TryGet};
 use etc_base::{
@@ -16,7 +22,7 @@ use tap::TapFallible;
 use crate::{
     error::{DTError, DTWarning, Result, TypeError, TypeResult},
     input::Input,
-    Config, Error,
+    Config, Error, WindowsSession,
 };
 
 pub type Row = HashMap<String, String>;
@@ -27,6 +33,8 @@ type DataMap = HashMap<ProtoDataTableId, TableData>;
 pub struct Plugin {
     key_vault: KeyVault,
     cache_dir: PathBuf,
+    /// Authenticated sessions by host and user, with their login time.
+    sessions: Mutex<HashMap<String, (Instant, WindowsSession)>>,
 }
 
 impl Plugin {
@@ -34,6 +42,41 @@ impl Plugin {
         Self {
             key_vault,
             cache_dir,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reuse a cached session or log on. Cached sessions are taken out
+    /// of the cache while in use.
+    async fn get_session(
+        &self,
+        config: &Config,
+    ) -> Result<(Instant, WindowsSession)> {
+        if let Some((key, ttl)) = config.session_cache() {
+            let cached = self.sessions.lock().unwrap().remove(&key);
+            match cached {
+                Some((login, session)) if login.elapsed() < ttl => {
+                    debug!("reusing session for {key}");
+                    return Ok((login, session));
+                }
+                Some(_) => debug!("cached session for {key} expired"),
+                None => {}
+            }
+        }
+        let session = config.new_session(&self.key_vault).await?;
+        Ok((Instant::now(), session))
+    }
+
+    fn put_session(
+        &self,
+        config: &Config,
+        login: Instant,
+        session: WindowsSession,
+    ) {
+        if let Some((key, ttl)) = config.session_cache() {
+            if login.elapsed() < ttl {
+                self.sessions.lock().unwrap().insert(key, (login, session));
+            }
         }
     }
 }
@@ -130,6 +173,7 @@ impl LocalPlugin for Plugin {
                     DataFieldSpec {
                         name: df.parameter_name.clone(),
                         input_type: df.get_type()?,
+                        sensitive: false,
                     },
                 ))
             })
@@ -144,17 +188,22 @@ impl LocalPlugin for Plugin {
     ) -> Result<DataMap> {
         info!("Using the winrm protocol");
 
-        let mut session = config.new_session(&self.key_vault).await?;
-        debug!("created session");
-        debug!("created shell");
+        let (login, mut session) = self.get_session(config).await?;
         info!("successfully logged in");
+        let mut session_failed = false;
 
-        let counter_file = self.cache_dir.join("winrm_counters.json");
+        let counter_file = self
+            .cache_dir
+            .join(config.hostname())
+            .join("winrm_counters.json");
         debug!("loading counters: {}", counter_file.display());
-        let counter_db = CounterDb::load(counter_file.clone())
-            .await
-            .map_err(|e| Error::LoadCounters(counter_file.clone(), e))
-            .map(Arc::new)?;
+        let counter_db = CounterDb::load_with_ttl(
+            counter_file.clone(),
+            config.counter_ttl(),
+        )
+        .await
+        .map_err(|e| Error::LoadCounters(counter_file.clone(), e))
+        .map(Arc::new)?;
         debug!("loaded counters");
 
         let context = config.script_context();
@@ -202,6 +251,9 @@ impl LocalPlugin for Plugin {
                     )
                 })
                 .tap_err(|e| warn!("error while executing command: {e}"));
+            if let Err(DTError::Winrm(_)) = &output {
+                session_failed = true;
+            }
 
             let table = output
                 .map(|out| dt.output_type.parse_table(out))
@@ -216,6 +268,10 @@ impl LocalPlugin for Plugin {
         }
 
         info!("all commands executed");
+        // Do not keep sessions that may have expired on the server.
+        if !session_failed {
+            self.put_session(config, login, session);
+        }
         if let Err(e) = counter_db.save().await {
             warn!("unable to save counters to {}: {e}", counter_file.display());
         }
@@ -0,0 +1,115 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use agent_utils::KeyVault;
+
+use crate::error::Result;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Config {
+    pub hostname: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub scheme: Scheme,
+    /// The path of the metrics endpoint.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Request timeout in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub disable_certificate_verification: Option<bool>,
+    /// Discard stored counters older than this many seconds. By
+    /// default, stored counters are kept indefinitely.
+    #[serde(default)]
+    pub counter_ttl: Option<u64>,
+}
+
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    #[default]
+    Http,
+    Https,
+}
+
+impl Config {
+    /// The port of node_exporter, the most common target.
+    const DEFAULT_PORT: u16 = 9100;
+    const DEFAULT_PATH: &'static str = "/metrics";
+    const DEFAULT_TIMEOUT: u64 = 10;
+
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(Self::DEFAULT_PORT)
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(Self::DEFAULT_PATH)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT))
+    }
+
+    pub fn counter_ttl(&self) -> Option<Duration> {
+        self.counter_ttl.map(Duration::from_secs)
+    }
+
+    pub fn url(&self) -> String {
+        let host = match self.hostname.contains(':') {
+            true => format!("[{}]", self.hostname),
+            false => self.hostname.clone(),
+        };
+        let path = self.path();
+        let sep = if path.starts_with('/') { "" } else { "/" };
+        format!("{}://{}:{}{}{}", self.scheme, host, self.port(), sep, path)
+    }
+
+    /// The username and password, from the keyvault if available.
+    pub async fn credentials(
+        &self,
+        kvault: &KeyVault,
+    ) -> Result<(Option<String>, Option<String>)> {
+        Ok(
+            match kvault
+                .retrieve_creds(self.username.clone().unwrap_or_default())
+                .await?
+            {
+                None => (self.username.clone(), self.password.clone()),
+                Some(creds) => (creds.username, creds.password),
+            },
+        )
+    }
+
+    pub fn client(&self) -> Result<Client> {
+        Ok(Client::builder()
+            .timeout(self.timeout())
+            .danger_accept_invalid_certs(
+                self.disable_certificate_verification.unwrap_or(false),
+            )
+            .build()?)
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Http => write!(f, "http"),
+            Self::Https => write!(f, "https"),
+        }
+    }
+}
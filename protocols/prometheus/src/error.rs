@@ -0,0 +1,42 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    AgentUtils(#[from] agent_utils::Error),
+    #[error("Failed to create http client: {0}")]
+    Client(#[from] reqwest::Error),
+    #[error("Failed to scrape {0}: {1}")]
+    Scrape(String, #[source] reqwest::Error),
+    #[error("Invalid metrics exposition on line {0}: {1}")]
+    Parse(usize, String),
+    #[error("Failed to access counterfile ({0}): {1}")]
+    LoadCounters(PathBuf, #[source] std::io::Error),
+    #[error("{0}")]
+    Format(#[from] std::fmt::Error),
+}
+
+pub type TypeResult<T> = std::result::Result<T, TypeError>;
+
+#[derive(Error, Debug)]
+pub enum TypeError {
+    #[error("{0}")]
+    AgentUtils(#[from] agent_utils::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum DTWarning {}
+
+#[derive(Error, Debug)]
+pub enum DTError {
+    #[error("Metric family {0} was not exposed")]
+    MissingFamily(String),
+}
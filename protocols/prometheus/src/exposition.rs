@@ -0,0 +1,316 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, HashMap};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_till1, take_while},
+    character::complete::{char, digit1, satisfy, space0, space1},
+    combinator::{all_consuming, map, map_res, opt, recognize, value},
+    multi::separated_list0,
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
+    IResult,
+};
+
+use crate::error::{Error, Result};
+
+/// Label names and values identifying a series within a family.
+pub type Labels = BTreeMap<String, String>;
+
+/// A scrape result in the Prometheus text exposition format.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Exposition {
+    pub families: HashMap<String, MetricFamily>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct MetricFamily {
+    pub metric_type: MetricType,
+    pub help: Option<String>,
+    pub series: BTreeMap<Labels, Series>,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    #[default]
+    Untyped,
+}
+
+/// The samples of a series. Histogram and summary series consist of
+/// several samples (`_bucket`, `_sum`, `_count`), the others only
+/// have a value. The `le` and `quantile` labels are not part of the
+/// series' labels.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Series {
+    pub value: Option<f64>,
+    pub sum: Option<f64>,
+    pub count: Option<f64>,
+    /// Cumulative counts by upper bound.
+    pub buckets: Vec<(f64, f64)>,
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+enum Part {
+    Value,
+    Sum,
+    Count,
+    Bucket,
+}
+
+impl Exposition {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut exposition = Self::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            let parse_err = |msg: String| Error::Parse(i + 1, msg);
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(comment) = line.strip_prefix('#') {
+                let comment = comment.trim_start();
+                if let Some(help) = comment.strip_prefix("HELP ") {
+                    let help = help.trim_start();
+                    let (name, help) =
+                        help.split_once(' ').unwrap_or((help, ""));
+                    exposition.family(name).help = Some(unescape_help(help));
+                } else if let Some(typ) = comment.strip_prefix("TYPE ") {
+                    let (name, typ) = typ
+                        .trim_start()
+                        .split_once(' ')
+                        .ok_or_else(|| parse_err("missing type".to_string()))?;
+                    exposition.family(name).metric_type =
+                        MetricType::parse(typ.trim()).ok_or_else(|| {
+                            parse_err(format!("unknown metric type '{typ}'"))
+                        })?;
+                }
+                continue;
+            }
+
+            let (name, mut labels, sample) = match sample_line(line) {
+                Ok((_, sample)) => sample,
+                Err(e) => return Err(parse_err(e.to_string())),
+            };
+            let (family, part) = exposition.resolve(name);
+            let metric_type = exposition.family(&family).metric_type;
+
+            let bound = match (&part, metric_type) {
+                (Part::Bucket, _) => Some("le"),
+                (Part::Value, MetricType::Summary) => Some("quantile"),
+                _ => None,
+            };
+            let bound = match bound {
+                None => None,
+                Some(label) => {
+                    let bound = labels.remove(label).ok_or_else(|| {
+                        parse_err(format!("missing label '{label}'"))
+                    })?;
+                    Some(parse_float(&bound).map_err(|_| {
+                        parse_err(format!("invalid {label} '{bound}'"))
+                    })?)
+                }
+            };
+
+            let series =
+                exposition.family(&family).series.entry(labels).or_default();
+            match (part, bound) {
+                (Part::Value, None) => series.value = Some(sample),
+                (Part::Value, Some(q)) => series.quantiles.push((q, sample)),
+                (Part::Bucket, Some(le)) => series.buckets.push((le, sample)),
+                (Part::Bucket, None) => unreachable!(),
+                (Part::Sum, _) => series.sum = Some(sample),
+                (Part::Count, _) => series.count = Some(sample),
+            }
+        }
+        Ok(exposition)
+    }
+
+    fn family(&mut self, name: &str) -> &mut MetricFamily {
+        self.families
+            .entry(name.to_string())
+            .or_insert_with(|| MetricFamily {
+                metric_type: MetricType::Untyped,
+                help: None,
+                series: BTreeMap::new(),
+            })
+    }
+
+    /// Find the family a sample belongs to. Counters may be declared
+    /// without their `_total` suffix, as in OpenMetrics. Samples
+    /// without a declared family form an untyped family of their own.
+    fn resolve(&self, name: &str) -> (String, Part) {
+        if self.families.contains_key(name) {
+            return (name.to_string(), Part::Value);
+        }
+
+        [
+            ("_bucket", Part::Bucket),
+            ("_sum", Part::Sum),
+            ("_count", Part::Count),
+            ("_total", Part::Value),
+        ]
+        .into_iter()
+        .find_map(|(suffix, part)| {
+            let base = name.strip_suffix(suffix)?;
+            let family = self.families.get(base)?;
+            match (&part, family.metric_type) {
+                (Part::Bucket, MetricType::Histogram)
+                | (
+                    Part::Sum | Part::Count,
+                    MetricType::Histogram | MetricType::Summary,
+                )
+                | (Part::Value, MetricType::Counter) => {
+                    Some((base.to_string(), part))
+                }
+                _ => None,
+            }
+        })
+        .unwrap_or_else(|| (name.to_string(), Part::Value))
+    }
+}
+
+impl Series {
+    /// The cumulative count of the bucket with the given upper bound.
+    pub fn bucket(&self, le: f64) -> Option<f64> {
+        self.buckets
+            .iter()
+            .find(|(bound, _)| *bound == le)
+            .map(|(_, count)| *count)
+    }
+
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        self.quantiles
+            .iter()
+            .find(|(q, _)| *q == quantile)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl MetricType {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "counter" => Some(Self::Counter),
+            "gauge" => Some(Self::Gauge),
+            "histogram" => Some(Self::Histogram),
+            "summary" => Some(Self::Summary),
+            "untyped" => Some(Self::Untyped),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a sample value or bound. Besides regular numbers, the format
+/// allows "+Inf", "-Inf" and "NaN".
+pub(crate) fn parse_float(
+    s: &str,
+) -> std::result::Result<f64, std::num::ParseFloatError> {
+    match s {
+        "+Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => s.parse(),
+    }
+}
+
+fn unescape_help(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('\\')) => {
+                output.push('\\');
+                chars.next();
+            }
+            ('\\', Some('n')) => {
+                output.push('\n');
+                chars.next();
+            }
+            (c, _) => output.push(c),
+        }
+    }
+    output
+}
+
+/* Sample parser. */
+
+fn sample_line(input: &str) -> IResult<&str, (&str, Labels, f64)> {
+    all_consuming(terminated(
+        map(
+            pair(
+                pair(metric_name, opt(labels)),
+                preceded(space1, sample_value),
+            ),
+            |((name, labels), value)| (name, labels.unwrap_or_default(), value),
+        ),
+        /* The timestamp is ignored. */
+        pair(
+            opt(preceded(space1, recognize(pair(opt(char('-')), digit1)))),
+            space0,
+        ),
+    ))(input)
+}
+
+fn metric_name(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_' || c == ':'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == ':'),
+    ))(input)
+}
+
+fn labels(input: &str) -> IResult<&str, Labels> {
+    map(
+        delimited(
+            pair(char('{'), space0),
+            terminated(
+                separated_list0(delimited(space0, char(','), space0), label),
+                opt(pair(space0, char(','))),
+            ),
+            pair(space0, char('}')),
+        ),
+        |labels| labels.into_iter().collect(),
+    )(input)
+}
+
+fn label(input: &str) -> IResult<&str, (String, String)> {
+    separated_pair(
+        map(label_name, String::from),
+        delimited(space0, char('='), space0),
+        label_value,
+    )(input)
+}
+
+fn label_name(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+    ))(input)
+}
+
+fn label_value(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(
+                is_not("\\\""),
+                '\\',
+                alt((
+                    value("\\", tag("\\")),
+                    value("\"", tag("\"")),
+                    value("\n", tag("n")),
+                )),
+            )),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(input)
+}
+
+fn sample_value(input: &str) -> IResult<&str, f64> {
+    map_res(take_till1(|c| c == ' ' || c == '\t'), parse_float)(input)
+}
@@ -0,0 +1,153 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use agent_utils::TryAppend;
+use etc_base::{ProtoDataFieldId, ProtoDataTableId};
+use protocol::CounterDb;
+use value::{Data, DataError, Type, Value};
+
+use crate::exposition::{parse_float, Labels, Series};
+
+/// Counters are stored in the counter db as integers. Prometheus
+/// counters are floats (eg. cpu seconds), so they are stored in
+/// thousandths to keep rates accurate.
+const COUNTER_SCALE: f64 = 1000.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Input {
+    pub data_tables: HashMap<ProtoDataTableId, TableSpec>,
+    pub data_fields: HashMap<ProtoDataFieldId, FieldSpec>,
+    pub data_table_fields: HashMap<ProtoDataTableId, HashSet<ProtoDataFieldId>>,
+}
+
+/// A table holds one row per series (label set) of a metric family.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TableSpec {
+    pub metric_name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct FieldSpec {
+    pub field_name: String,
+    pub source: FieldSource,
+    #[serde(default)]
+    pub mode: SampleMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldSource {
+    /// The value of a label. Label fields are the table's keys.
+    Label(String),
+    /// The sample of a counter, gauge or untyped metric.
+    Value,
+    /// The sum of a histogram or summary.
+    Sum,
+    /// The observation count of a histogram or summary.
+    Count,
+    /// The cumulative count of a histogram bucket, by upper bound.
+    Bucket(String),
+    /// A quantile of a summary.
+    Quantile(String),
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleMode {
+    /// The sample as-is.
+    #[default]
+    Gauge,
+    /// The per-second rate of a counter.
+    Counter,
+    /// The increase of a counter since the previous run.
+    Difference,
+}
+
+impl FieldSpec {
+    pub fn is_key(&self) -> bool {
+        matches!(self.source, FieldSource::Label(_))
+    }
+
+    pub fn get_type(&self) -> Type {
+        match (&self.source, self.mode) {
+            (FieldSource::Label(_), _) => Type::UnicodeString,
+            (_, SampleMode::Gauge | SampleMode::Counter) => Type::Float,
+            (_, SampleMode::Difference) => Type::Integer,
+        }
+    }
+
+    /// Extract the field from a series. The series key identifies
+    /// the series in the counter db.
+    pub(crate) fn get_value(
+        &self,
+        labels: &Labels,
+        series: &Series,
+        counter_db: &CounterDb,
+        series_key: &str,
+        now: SystemTime,
+    ) -> Data {
+        let sample = match &self.source {
+            FieldSource::Label(label) => {
+                return labels
+                    .get(label)
+                    .map(|v| Value::UnicodeString(v.clone()))
+                    .ok_or(DataError::Missing)
+            }
+            FieldSource::Value => series.value,
+            FieldSource::Sum => series.sum,
+            FieldSource::Count => series.count,
+            FieldSource::Bucket(le) => series.bucket(parse_bound(le)?),
+            FieldSource::Quantile(q) => series.quantile(parse_bound(q)?),
+        }
+        .ok_or(DataError::Missing)?;
+
+        let key = format!("{}.{}", series_key, self.field_name);
+        match self.mode {
+            SampleMode::Gauge => Ok(Value::Float(sample)),
+            SampleMode::Counter => match counter_value(sample, COUNTER_SCALE) {
+                Some(v) => match counter_db.counter(key, v, now)? {
+                    Value::Float(rate) => {
+                        Ok(Value::Float(rate / COUNTER_SCALE))
+                    }
+                    v => Ok(v),
+                },
+                None => Err(DataError::CounterOverflow),
+            },
+            SampleMode::Difference => match counter_value(sample, 1.0) {
+                Some(v) => counter_db.difference(key, v, now),
+                None => Err(DataError::CounterOverflow),
+            },
+        }
+    }
+}
+
+fn parse_bound(bound: &str) -> Result<f64, DataError> {
+    parse_float(bound)
+        .map_err(|_| DataError::TypeError(format!("invalid bound '{bound}'")))
+}
+
+fn counter_value(sample: f64, scale: f64) -> Option<u64> {
+    let value = (sample * scale).round();
+    (value.is_finite() && value >= 0.0 && value < u64::MAX as f64)
+        .then_some(value as u64)
+}
+
+impl TryAppend for Input {
+    fn try_append(&mut self, other: Self) -> agent_utils::Result<()> {
+        self.data_tables.try_append(other.data_tables)?;
+        self.data_fields.try_append(other.data_fields)?;
+        self.data_table_fields.try_append(other.data_table_fields)?;
+        Ok(())
+    }
+}
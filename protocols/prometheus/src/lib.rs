@@ -0,0 +1,15 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+mod config;
+mod error;
+mod exposition;
+mod input;
+mod plugin;
+
+pub use config::{Config, Scheme};
+pub use error::{DTError, DTWarning, Error, Result, TypeError};
+pub use exposition::{Exposition, Labels, MetricFamily, MetricType, Series};
+pub use input::{FieldSource, FieldSpec, Input, SampleMode, TableSpec};
+pub use plugin::Plugin;
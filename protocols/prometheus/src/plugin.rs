@@ -0,0 +1,274 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::{collections::HashMap, fmt::Write, path::PathBuf, time::SystemTime};
+
+use log::{debug, info, warn};
+use reqwest::header::ACCEPT;
+
+use agent_utils::{KeyVault, TryGet};
+use etc_base::{
+    Annotated, AnnotatedResult, ProtoDataFieldId, ProtoDataTableId,
+    ProtoQueryMap, ProtoRow,
+};
+use protocol::{
+    CounterDb, DataFieldSpec, DataTableSpec, Idempotency, LocalPlugin,
+};
+
+use crate::{
+    error::{DTError, DTWarning, Result, TypeError, TypeResult},
+    exposition::{Exposition, Labels},
+    input::Input,
+    Config, Error,
+};
+
+type TableData = AnnotatedResult<Vec<ProtoRow>, DTWarning, DTError>;
+type DataMap = HashMap<ProtoDataTableId, TableData>;
+
+/// The text exposition format; OpenMetrics is not supported.
+const ACCEPT_HEADER: &str = "text/plain;version=0.0.4;q=1,*/*;q=0.1";
+
+pub struct Plugin {
+    key_vault: KeyVault,
+    cache_dir: PathBuf,
+}
+
+impl Plugin {
+    pub fn new(cache_dir: PathBuf, key_vault: KeyVault) -> Self {
+        Self {
+            key_vault,
+            cache_dir,
+        }
+    }
+
+    async fn scrape(&self, config: &Config) -> Result<Exposition> {
+        let url = config.url();
+        let (username, password) = config.credentials(&self.key_vault).await?;
+
+        let mut request =
+            config.client()?.get(&url).header(ACCEPT, ACCEPT_HEADER);
+        if let Some(username) = username {
+            request = request.basic_auth(username, password);
+        }
+
+        info!("scraping {url}");
+        let text = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| Error::Scrape(url.clone(), e))?
+            .text()
+            .await
+            .map_err(|e| Error::Scrape(url.clone(), e))?;
+
+        let exposition = Exposition::parse(&text)?;
+        debug!("received {} metric families", exposition.families.len());
+        Ok(exposition)
+    }
+}
+
+#[async_trait::async_trait]
+impl LocalPlugin for Plugin {
+    type Error = Error;
+    type TypeError = TypeError;
+    type DTError = DTError;
+    type DTWarning = DTWarning;
+
+    type Input = Input;
+    type Config = Config;
+
+    const PROTOCOL: &'static str = "Prometheus";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    fn show_queries(
+        &self,
+        input: &Input,
+        query: &ProtoQueryMap,
+    ) -> Result<String> {
+        let mut out = String::new();
+
+        for (table, fields) in query.iter() {
+            let table = input.data_tables.try_get(table)?;
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    input
+                        .data_fields
+                        .try_get(field)
+                        .map(|f| f.field_name.as_str())
+                })
+                .collect::<agent_utils::Result<Vec<_>>>()?;
+            writeln!(
+                out,
+                "{}: {} with fields {}",
+                Self::PROTOCOL,
+                &table.metric_name,
+                fields.join(", ")
+            )?;
+        }
+
+        Ok(out)
+    }
+
+    fn get_tables(
+        &self,
+        input: &Input,
+    ) -> TypeResult<HashMap<ProtoDataTableId, DataTableSpec>> {
+        input
+            .data_tables
+            .keys()
+            .map(|dt_id| {
+                let dfs = input
+                    .data_table_fields
+                    .try_get(dt_id)?
+                    .iter()
+                    .map(|df_id| {
+                        input.data_fields.try_get(df_id).map(|df| (df_id, df))
+                    })
+                    .collect::<agent_utils::Result<HashMap<_, _>>>()?;
+                Ok((
+                    dt_id.clone(),
+                    DataTableSpec {
+                        name: dt_id.0.clone(),
+                        keys: dfs
+                            .iter()
+                            .filter(|(_, v)| v.is_key())
+                            .map(|(&k, _)| k.clone())
+                            .collect(),
+                        singleton: false,
+                        fields: dfs.into_keys().cloned().collect(),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn get_fields(
+        &self,
+        input: &Input,
+    ) -> TypeResult<HashMap<ProtoDataFieldId, DataFieldSpec>> {
+        Ok(input
+            .data_fields
+            .iter()
+            .map(|(df_id, df)| {
+                (
+                    df_id.clone(),
+                    DataFieldSpec {
+                        name: df.field_name.clone(),
+                        input_type: df.get_type(),
+                        sensitive: false,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Scraping an exporter only reads.
+    fn idempotency(
+        &self,
+        _input: &Input,
+        _query: &ProtoQueryMap,
+    ) -> Idempotency {
+        Idempotency::Idempotent
+    }
+
+    async fn run_queries(
+        &self,
+        input: &Input,
+        config: &Config,
+        query: &ProtoQueryMap,
+    ) -> Result<DataMap> {
+        let exposition = self.scrape(config).await?;
+        let now = SystemTime::now();
+
+        let counter_file = self
+            .cache_dir
+            .join(&config.hostname)
+            .join("prometheus_counters.json");
+        let counter_db = CounterDb::load_with_ttl(
+            counter_file.clone(),
+            config.counter_ttl(),
+        )
+        .await
+        .map_err(|e| Error::LoadCounters(counter_file.clone(), e))?;
+
+        let url = config.url();
+        let mut data = HashMap::with_capacity(query.len());
+        for (dt_id, df_ids) in query {
+            let table = input.data_tables.try_get(dt_id)?;
+            let dfs = df_ids
+                .iter()
+                .map(|df_id| {
+                    input.data_fields.try_get(df_id).map(|df| (df_id, df))
+                })
+                .collect::<agent_utils::Result<Vec<_>>>()?;
+
+            let family = match exposition.families.get(&table.metric_name) {
+                Some(family) => family,
+                None => {
+                    data.insert(
+                        dt_id.clone(),
+                        Err(DTError::MissingFamily(table.metric_name.clone())),
+                    );
+                    continue;
+                }
+            };
+
+            let rows = family
+                .series
+                .iter()
+                .map(|(labels, series)| {
+                    let series_key = format!(
+                        "{}/{}{}",
+                        url,
+                        table.metric_name,
+                        format_labels(labels)
+                    );
+                    dfs.iter()
+                        .map(|(df_id, df)| {
+                            (
+                                (*df_id).clone(),
+                                df.get_value(
+                                    labels,
+                                    series,
+                                    &counter_db,
+                                    &series_key,
+                                    now,
+                                ),
+                            )
+                        })
+                        .collect()
+                })
+                .collect();
+
+            data.insert(
+                dt_id.clone(),
+                Ok(Annotated {
+                    value: rows,
+                    warnings: Vec::new(),
+                }),
+            );
+        }
+
+        if let Err(e) = counter_db.save().await {
+            warn!("unable to save counters to {}: {e}", counter_file.display());
+        }
+
+        Ok(data)
+    }
+}
+
+fn format_labels(labels: &Labels) -> String {
+    match labels.is_empty() {
+        true => String::new(),
+        false => format!(
+            "{{{}}}",
+            labels
+                .iter()
+                .map(|(k, v)| format!("{k}={v:?}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
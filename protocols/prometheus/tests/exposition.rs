@@ -0,0 +1,104 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use prometheus_protocol::{Exposition, Labels, MetricType};
+
+const METRICS: &str = r#"
+# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method="post",code="200"} 1027 1395066363000
+http_requests_total{method="post",code="400"}    3 1395066363000
+
+# A comment.
+# TYPE node_load1 gauge
+node_load1 0.12
+
+# TYPE http_request_duration_seconds histogram
+http_request_duration_seconds_bucket{le="0.05"} 24054
+http_request_duration_seconds_bucket{le="0.1"} 33444
+http_request_duration_seconds_bucket{le="+Inf"} 144320
+http_request_duration_seconds_sum 53423
+http_request_duration_seconds_count 144320
+
+# TYPE rpc_duration_seconds summary
+rpc_duration_seconds{quantile="0.5"} 4773
+rpc_duration_seconds{quantile="0.99"} 76656
+rpc_duration_seconds_sum 1.7560473e+07
+rpc_duration_seconds_count 2693
+
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\"",} 1.458255915e9
+"#;
+
+fn labels(labels: &[(&str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn counters_and_gauges() {
+    let exposition = Exposition::parse(METRICS).unwrap();
+
+    let requests = &exposition.families["http_requests_total"];
+    assert_eq!(requests.metric_type, MetricType::Counter);
+    assert_eq!(
+        requests.help.as_deref(),
+        Some("The total number of HTTP requests.")
+    );
+    assert_eq!(requests.series.len(), 2);
+    assert_eq!(
+        requests.series[&labels(&[("method", "post"), ("code", "400")])].value,
+        Some(3.0)
+    );
+
+    let load = &exposition.families["node_load1"];
+    assert_eq!(load.metric_type, MetricType::Gauge);
+    assert_eq!(load.series[&Labels::new()].value, Some(0.12));
+}
+
+#[test]
+fn histograms_and_summaries() {
+    let exposition = Exposition::parse(METRICS).unwrap();
+
+    let duration = &exposition.families["http_request_duration_seconds"];
+    let series = &duration.series[&Labels::new()];
+    assert_eq!(duration.series.len(), 1);
+    assert_eq!(series.bucket(0.1), Some(33444.0));
+    assert_eq!(series.bucket(f64::INFINITY), Some(144320.0));
+    assert_eq!(series.sum, Some(53423.0));
+    assert_eq!(series.count, Some(144320.0));
+
+    let rpc = &exposition.families["rpc_duration_seconds"];
+    let series = &rpc.series[&Labels::new()];
+    assert_eq!(rpc.metric_type, MetricType::Summary);
+    assert_eq!(series.quantile(0.99), Some(76656.0));
+    assert_eq!(series.sum, Some(1.7560473e7));
+}
+
+#[test]
+fn untyped_and_escapes() {
+    let exposition = Exposition::parse(METRICS).unwrap();
+    let access = &exposition.families["msdos_file_access_time_seconds"];
+    assert_eq!(access.metric_type, MetricType::Untyped);
+    assert_eq!(
+        access.series[&labels(&[
+            ("path", "C:\\DIR\\FILE.TXT"),
+            ("error", "Cannot find file:\n\"FILE.TXT\"")
+        ])]
+            .value,
+        Some(1.458255915e9)
+    );
+}
+
+#[test]
+fn invalid_lines() {
+    assert!(Exposition::parse("metric{label=\"x} 1").is_err());
+    assert!(Exposition::parse("metric one").is_err());
+    assert!(Exposition::parse("# TYPE metric kind").is_err());
+    assert!(
+        Exposition::parse("# TYPE h histogram\nh_bucket{code=\"1\"} 2")
+            .is_err()
+    );
+}
@@ -11,6 +11,7 @@ use serde_json::Value;
 
 use super::error::RESTError;
 use super::http::*;
+use super::session::Session;
 use super::template::Template;
 use uritemplate::UriTemplate;
 
@@ -40,13 +41,74 @@ pub struct Application {
     pub login_data: HashMap<String, Template>,
     #[serde(rename = "LoginBodyType")]
     pub login_body_type: BodyType,
+    #[serde(rename = "Pagination", default)]
+    pub pagination: Pagination,
     /*
     #[serde(rename = "RestConfigVar")]
     pub(super) rest_config_var: Vec<Config>,
     */
 }
 
+/// How the pages of a paginated response are retrieved. Pages are
+/// fetched until no next page is indicated or `max_pages` pages
+/// have been fetched.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct Pagination {
+    #[serde(rename = "Mode", default)]
+    pub mode: PaginationMode,
+    /// JSON pointer to the items array in each page. If unset, the
+    /// page itself is expected to be the array of items.
+    #[serde(rename = "Items", default)]
+    pub items: Option<String>,
+    #[serde(rename = "MaxPages", default)]
+    pub max_pages: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub enum PaginationMode {
+    /// Only fetch the first page.
+    #[default]
+    None,
+    /// Follow the `rel="next"` link in the Link header.
+    LinkHeader,
+    /// Pass the cursor found in the response body as a query
+    /// parameter in the request for the next page.
+    Cursor {
+        /// JSON pointer to the next cursor in the page.
+        #[serde(rename = "CursorField")]
+        field: String,
+        #[serde(rename = "CursorParameter")]
+        parameter: String,
+    },
+}
+
+impl Pagination {
+    const DEFAULT_MAX_PAGES: usize = 100;
+
+    pub fn max_pages(&self) -> usize {
+        match self.mode {
+            PaginationMode::None => 1,
+            _ => self.max_pages.unwrap_or(Self::DEFAULT_MAX_PAGES),
+        }
+    }
+}
+
 impl Application {
+    /// Set up an authenticated session. Unlike `login`, this supports
+    /// OAuth2 authentication.
+    pub async fn session(
+        &mut self,
+        creds: &HashMap<String, String>,
+    ) -> Result<Session, RESTError> {
+        match &self.auth_type {
+            AuthType::OAuth2(oauth2) => Ok(Session::oauth2(
+                Client::builder().build()?,
+                oauth2.fill_in(creds)?,
+            )),
+            _ => Ok(Session::from(self.login(creds).await?)),
+        }
+    }
+
     pub async fn login(
         &mut self,
         creds: &HashMap<String, String>,
@@ -81,6 +143,7 @@ impl Application {
 
                 match &self.auth_type {
                     AuthType::Cookie => Ok(client),
+                    AuthType::OAuth2(_) => Err(RESTError::SessionRequired),
                     AuthType::Token(template) => {
                         let mut headers = HeaderMap::new();
                         let response_body =
@@ -24,6 +24,16 @@ pub enum RESTError {
     ValidationError(Vec<String>),
     #[error("Tried sending a request with an invalid header: {0}")]
     InvalidHeaderValue(#[from] InvalidHeaderValue),
+    #[error("OAuth2 authentication requires a session, not a login")]
+    SessionRequired,
+    #[error("Error transforming response: {0}")]
+    Transform(#[from] TransformError),
+    #[error("Invalid url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("Page does not contain items at {0}")]
+    MissingPageItems(String),
+    #[error("Pagination failed after {} pages: {1}", .0.len())]
+    Pagination(Vec<Value>, #[source] Box<RESTError>),
 }
 
 #[derive(Error, Debug)]
@@ -35,3 +45,15 @@ pub enum TemplateError {
     #[error("Value {0} ({1}) is not a string")]
     NotAString(String, Value),
 }
+
+#[derive(Error, Debug)]
+pub enum TransformError {
+    #[error("Invalid transform: {0}")]
+    Parse(String),
+    #[error("Cannot get field {0} of {1}: not an object")]
+    NotAnObject(String, Value),
+    #[error("Cannot get index {0} of {1}: not an array")]
+    NotAnArray(i64, Value),
+    #[error("Cannot iterate over {0}")]
+    NotIterable(Value),
+}
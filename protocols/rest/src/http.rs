@@ -2,9 +2,13 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::session::OAuth2Session;
 use super::template::Template;
+use crate::TemplateError;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub enum BodyType {
@@ -42,6 +46,43 @@ impl ContentType {
 pub enum AuthType {
     Token(Template),
     Cookie,
+    OAuth2(OAuth2Config),
+}
+
+/// OAuth2 client-credentials grant. The templates are filled in with
+/// the credentials, so the client secret can come from the vault.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct OAuth2Config {
+    #[serde(rename = "TokenURL")]
+    pub token_url: Template,
+    #[serde(rename = "ClientId")]
+    pub client_id: Template,
+    #[serde(rename = "ClientSecret")]
+    pub client_secret: Template,
+    #[serde(rename = "Scope", default)]
+    pub scope: Option<Template>,
+}
+
+impl OAuth2Config {
+    pub(crate) fn fill_in(
+        &self,
+        creds: &HashMap<String, String>,
+    ) -> Result<OAuth2Session, TemplateError> {
+        let mut form = HashMap::new();
+        form.insert(
+            String::from("grant_type"),
+            String::from("client_credentials"),
+        );
+        form.insert(String::from("client_id"), self.client_id.fill_in(creds)?);
+        form.insert(
+            String::from("client_secret"),
+            self.client_secret.fill_in(creds)?,
+        );
+        if let Some(scope) = &self.scope {
+            form.insert(String::from("scope"), scope.fill_in(creds)?);
+        }
+        Ok(OAuth2Session::new(self.token_url.fill_in(creds)?, form))
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
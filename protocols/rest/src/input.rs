@@ -3,15 +3,19 @@
  ******************************************************************************/
 
 use std::collections::HashMap;
+use std::time::Instant;
 
-use crate::RESTError;
-use reqwest::{Client, Request};
+use crate::config::{Pagination, PaginationMode};
+use crate::validation::validate_json;
+use crate::{RESTError, Session, Transform};
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::{Client, Request, Url};
 use serde::{Deserialize, Serialize};
 use uritemplate::UriTemplate;
 
 use super::http::*;
 use super::template::Template;
-use log::{debug, info};
+use log::{debug, info, warn};
 use serde_json::Value;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -19,7 +23,34 @@ pub struct DataTable {
     #[serde(rename = "request")]
     pub(super) request: Box<RESTRequest>,
     #[serde(rename = "JQProgram")]
-    pub(super) json_path: String,
+    pub(super) transform: Transform,
+}
+
+impl DataTable {
+    /// Retrieve the response and transform it into the rows of the
+    /// table.
+    pub async fn fetch(
+        &mut self,
+        session: &Session,
+        wato: &HashMap<String, String>,
+        pagination: &Pagination,
+    ) -> Result<Vec<Value>, RESTError> {
+        let response = match pagination.mode {
+            PaginationMode::None => {
+                let text = self.request.execute_in(session, wato).await?;
+                match &self.request.schema {
+                    Value::Null => serde_json::from_str(&text)?,
+                    schema => validate_json(&text, schema)?,
+                }
+            }
+            _ => {
+                self.request
+                    .execute_paginated(session, wato, pagination)
+                    .await?
+            }
+        };
+        Ok(self.transform.apply(&response)?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -42,6 +73,100 @@ impl RESTRequest {
         client: &Client,
         wato: &HashMap<String, String>,
     ) -> Result<String, RESTError> {
+        self.execute_in(&Session::from(client.clone()), wato).await
+    }
+
+    pub async fn execute_in(
+        &mut self,
+        session: &Session,
+        wato: &HashMap<String, String>,
+    ) -> Result<String, RESTError> {
+        let url = self.build_url(wato);
+        let (_headers, text) = self.send(session, &url).await?;
+        Ok(text)
+    }
+
+    /// Fetch all pages of a paginated response and concatenate their
+    /// items into one array. If fetching a page fails, the error is
+    /// returned along with the pages fetched so far.
+    pub async fn execute_paginated(
+        &mut self,
+        session: &Session,
+        wato: &HashMap<String, String>,
+        pagination: &Pagination,
+    ) -> Result<Value, RESTError> {
+        let mut url = Some(self.build_url(wato));
+        let mut pages = Vec::new();
+
+        while let Some(page_url) = url.take() {
+            if pages.len() == pagination.max_pages() {
+                warn!("stopping pagination after {} pages", pages.len());
+                break;
+            }
+
+            let (page, next) =
+                match self.fetch_page(session, &page_url, pagination).await {
+                    Ok(res) => res,
+                    Err(e) => {
+                        return Err(RESTError::Pagination(pages, Box::new(e)))
+                    }
+                };
+            pages.push(page);
+            url = next;
+        }
+
+        let mut items = Vec::new();
+        for page in pages {
+            let page_items = match &pagination.items {
+                None => page,
+                Some(pointer) => {
+                    page.pointer(pointer).cloned().ok_or_else(|| {
+                        RESTError::MissingPageItems(pointer.clone())
+                    })?
+                }
+            };
+            match page_items {
+                Value::Array(page_items) => items.extend(page_items),
+                item => items.push(item),
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// Fetch one page, returning it with the url of the next page.
+    async fn fetch_page(
+        &self,
+        session: &Session,
+        url: &str,
+        pagination: &Pagination,
+    ) -> Result<(Value, Option<String>), RESTError> {
+        let (headers, text) = self.send(session, url).await?;
+        let page: Value = serde_json::from_str(&text)?;
+        let next = match &pagination.mode {
+            PaginationMode::None => None,
+            PaginationMode::LinkHeader => next_link(&headers)
+                .map(|link| Url::parse(url)?.join(&link))
+                .transpose()?
+                .map(String::from),
+            PaginationMode::Cursor { field, parameter } => {
+                match page.pointer(field) {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(cursor)) if cursor.is_empty() => None,
+                    Some(Value::String(cursor)) => {
+                        Some(with_query_param(url, parameter, cursor)?)
+                    }
+                    Some(cursor) => Some(with_query_param(
+                        url,
+                        parameter,
+                        &cursor.to_string(),
+                    )?),
+                }
+            }
+        };
+        Ok((page, next))
+    }
+
+    fn build_url(&mut self, wato: &HashMap<String, String>) -> String {
         let filledin_data = self
             .data
             .iter()
@@ -54,18 +179,86 @@ impl RESTRequest {
             debug!("setting variable: {}", &k);
             self.url.set(k, v.clone());
         }
-        let url = &self.url.build();
+        let url = self.url.build();
         debug!("actual url: {}", &url);
+        url
+    }
 
+    async fn send(
+        &self,
+        session: &Session,
+        url: &str,
+    ) -> Result<(HeaderMap, String), RESTError> {
         let request: Request = match self.method {
-            HTTPMethod::GET => client.get(url.clone()).build()?,
+            HTTPMethod::GET => session.client().get(url).build()?,
             HTTPMethod::POST => panic!("{}", "Not yet implemented"),
         };
 
-        let response = client.execute(request).await?;
-        info!("request to {:?} returned {}", &url, response.status());
-        let text = response.text().await?;
+        let endpoint = format!("{} {}", request.method(), request.url().path());
+        let start = Instant::now();
+        let response = match session.execute(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                session.stats().record(&endpoint, None, start.elapsed(), 0);
+                return Err(e);
+            }
+        };
+        let status = response.status();
+        info!("request to {:?} returned {}", &url, status);
+        let headers = response.headers().clone();
+        let text = response.text().await;
+        session.stats().record(
+            &endpoint,
+            Some(status),
+            start.elapsed(),
+            text.as_ref().map_or(0, |text| text.len() as u64),
+        );
+        let text = text?;
         debug!("with data: {}", &text);
-        Ok(text)
+        Ok((headers, text))
     }
 }
+
+/// Find the `rel="next"` link in the Link headers.
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';').map(str::trim);
+            let target = parts.next()?.strip_prefix('<')?.strip_suffix('>')?;
+            parts
+                .any(|param| match param.split_once('=') {
+                    Some((key, rels)) => {
+                        key.trim().eq_ignore_ascii_case("rel")
+                            && rels
+                                .trim()
+                                .trim_matches('"')
+                                .split_whitespace()
+                                .any(|rel| rel.eq_ignore_ascii_case("next"))
+                    }
+                    None => false,
+                })
+                .then(|| target.to_string())
+        })
+}
+
+fn with_query_param(
+    url: &str,
+    key: &str,
+    value: &str,
+) -> Result<String, RESTError> {
+    let mut url = Url::parse(url)?;
+    let pairs = url
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+    Ok(url.into())
+}
@@ -6,9 +6,13 @@ pub mod config;
 mod error;
 pub mod http;
 pub mod input;
+mod session;
 mod template;
+mod transform;
 pub mod validation;
 
-pub use config::Application;
-pub use error::{RESTError, TemplateError};
+pub use config::{Application, Pagination, PaginationMode};
+pub use error::{RESTError, TemplateError, TransformError};
+pub use session::Session;
 pub use template::Template;
+pub use transform::Transform;
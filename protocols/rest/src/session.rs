@@ -0,0 +1,170 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::debug;
+use protocol::http::RequestStats;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, Request, Response, StatusCode};
+use serde::Deserialize;
+
+use super::error::RESTError;
+
+/// Tokens are refreshed this long before they expire, to account for
+/// the time requests take to reach the server.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// An HTTP client along with its authentication, and the metrics of
+/// the requests made with it.
+pub struct Session {
+    client: Client,
+    oauth2: Option<OAuth2Session>,
+    stats: Arc<RequestStats>,
+}
+
+/// OAuth2 client-credentials grant. The bearer token is cached until
+/// it expires, or until the server rejects it.
+pub(crate) struct OAuth2Session {
+    token_url: String,
+    form: HashMap<String, String>,
+    token: Mutex<Option<Token>>,
+}
+
+struct Token {
+    access_token: String,
+    expires: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl From<Client> for Session {
+    fn from(client: Client) -> Self {
+        Self {
+            client,
+            oauth2: None,
+            stats: Arc::default(),
+        }
+    }
+}
+
+impl Session {
+    pub(crate) fn oauth2(client: Client, oauth2: OAuth2Session) -> Self {
+        Self {
+            client,
+            oauth2: Some(oauth2),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Record request metrics in the given stats, shared with other
+    /// sessions.
+    pub fn with_stats(self, stats: Arc<RequestStats>) -> Self {
+        Self { stats, ..self }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn stats(&self) -> &RequestStats {
+        &self.stats
+    }
+
+    /// Execute a request. With OAuth2, the bearer token is added and
+    /// the request is retried once with a new token on a 401 response.
+    pub async fn execute(
+        &self,
+        request: Request,
+    ) -> Result<Response, RESTError> {
+        let oauth2 = match &self.oauth2 {
+            None => return Ok(self.client.execute(request).await?),
+            Some(oauth2) => oauth2,
+        };
+
+        let retry = request.try_clone();
+        let response = self
+            .client
+            .execute(oauth2.authorize(&self.client, request).await?)
+            .await?;
+        match (response.status(), retry) {
+            (StatusCode::UNAUTHORIZED, Some(request)) => {
+                debug!("bearer token was rejected; requesting a new one");
+                oauth2.invalidate();
+                Ok(self
+                    .client
+                    .execute(oauth2.authorize(&self.client, request).await?)
+                    .await?)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+impl OAuth2Session {
+    pub(crate) fn new(
+        token_url: String,
+        form: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            token_url,
+            form,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn authorize(
+        &self,
+        client: &Client,
+        mut request: Request,
+    ) -> Result<Request, RESTError> {
+        let token = self.token(client).await?;
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {token}").parse()?);
+        Ok(request)
+    }
+
+    async fn token(&self, client: &Client) -> Result<String, RESTError> {
+        let cached = self
+            .token
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|token| token.expires.map_or(true, |t| Instant::now() < t))
+            .map(|token| token.access_token.clone());
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+
+        debug!("requesting bearer token from {}", &self.token_url);
+        let response: TokenResponse = client
+            .post(&self.token_url)
+            .form(&self.form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let expires = response.expires_in.map(|secs| {
+            Instant::now()
+                + Duration::from_secs(secs).saturating_sub(EXPIRY_MARGIN)
+        });
+        *self.token.lock().unwrap() = Some(Token {
+            access_token: response.access_token.clone(),
+            expires,
+        });
+        Ok(response.access_token)
+    }
+
+    fn invalidate(&self) {
+        *self.token.lock().unwrap() = None;
+    }
+}
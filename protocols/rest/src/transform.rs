@@ -0,0 +1,291 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_while1},
+    character::complete::{char, digit1, multispace0},
+    combinator::{all_consuming, map, map_res, opt, recognize, value},
+    multi::{many0, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair},
+    IResult,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::TransformError;
+
+/// A jq-like program reshaping a parsed response before field
+/// extraction. The supported subset: `.`, `.field`, `."field"`,
+/// `.[n]`, `.[]`, pipes (`|`), commas, parentheses, array
+/// construction (`[...]`) and object construction (`{a: .x, b}`).
+/// Like jq, a program yields a stream of values; these become the
+/// rows of the table.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Transform {
+    source: String,
+    filter: Filter,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Filter {
+    Identity,
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, i64),
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(String, Filter)>),
+}
+
+impl Transform {
+    pub fn parse(source: &str) -> Result<Self, TransformError> {
+        match all_consuming(delimited(multispace0, pipe, multispace0))(source) {
+            Ok((_, filter)) => Ok(Self {
+                source: source.to_string(),
+                filter,
+            }),
+            Err(e) => Err(TransformError::Parse(e.to_string())),
+        }
+    }
+
+    /// Run the program on a value, returning its outputs.
+    pub fn apply(&self, input: &Value) -> Result<Vec<Value>, TransformError> {
+        self.filter.apply(input)
+    }
+}
+
+impl Filter {
+    fn apply(&self, input: &Value) -> Result<Vec<Value>, TransformError> {
+        match self {
+            Self::Identity => Ok(vec![input.clone()]),
+            Self::Field(base, name) => base
+                .apply(input)?
+                .into_iter()
+                .map(|v| match v {
+                    Value::Object(mut obj) => {
+                        Ok(obj.remove(name).unwrap_or(Value::Null))
+                    }
+                    Value::Null => Ok(Value::Null),
+                    v => Err(TransformError::NotAnObject(name.clone(), v)),
+                })
+                .collect(),
+            Self::Index(base, i) => base
+                .apply(input)?
+                .into_iter()
+                .map(|v| match v {
+                    Value::Array(mut arr) => {
+                        let n = arr.len() as i64;
+                        let i = if *i < 0 { n + i } else { *i };
+                        Ok(match (0..n).contains(&i) {
+                            true => arr.swap_remove(i as usize),
+                            false => Value::Null,
+                        })
+                    }
+                    Value::Null => Ok(Value::Null),
+                    v => Err(TransformError::NotAnArray(*i, v)),
+                })
+                .collect(),
+            Self::Iterate(base) => {
+                let mut outputs = Vec::new();
+                for v in base.apply(input)? {
+                    match v {
+                        Value::Array(arr) => outputs.extend(arr),
+                        Value::Object(obj) => {
+                            outputs.extend(obj.into_iter().map(|(_, v)| v))
+                        }
+                        v => return Err(TransformError::NotIterable(v)),
+                    }
+                }
+                Ok(outputs)
+            }
+            Self::Pipe(f, g) => {
+                let mut outputs = Vec::new();
+                for v in f.apply(input)? {
+                    outputs.extend(g.apply(&v)?);
+                }
+                Ok(outputs)
+            }
+            Self::Comma(f, g) => {
+                let mut outputs = f.apply(input)?;
+                outputs.extend(g.apply(input)?);
+                Ok(outputs)
+            }
+            Self::Array(None) => Ok(vec![Value::Array(Vec::new())]),
+            Self::Array(Some(f)) => Ok(vec![Value::Array(f.apply(input)?)]),
+            Self::Object(entries) => {
+                /* Every combination of the entries' outputs yields
+                 * an object, as in jq. */
+                let mut objects = vec![serde_json::Map::new()];
+                for (key, f) in entries {
+                    let values = f.apply(input)?;
+                    objects = objects
+                        .into_iter()
+                        .flat_map(|obj| {
+                            values.iter().map(move |v| {
+                                let mut obj = obj.clone();
+                                obj.insert(key.clone(), v.clone());
+                                obj
+                            })
+                        })
+                        .collect();
+                }
+                Ok(objects.into_iter().map(Value::Object).collect())
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Transform {
+    type Error = TransformError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Transform> for String {
+    fn from(value: Transform) -> Self {
+        value.source
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/* Parser. */
+
+fn ws<'a, O>(
+    f: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    delimited(multispace0, f, multispace0)
+}
+
+fn pipe(input: &str) -> IResult<&str, Filter> {
+    map(separated_list1(char('|'), comma), |filters| {
+        fold(filters, Filter::Pipe)
+    })(input)
+}
+
+fn comma(input: &str) -> IResult<&str, Filter> {
+    map(separated_list1(char(','), ws(postfix)), |filters| {
+        fold(filters, Filter::Comma)
+    })(input)
+}
+
+fn fold(
+    filters: Vec<Filter>,
+    constr: fn(Box<Filter>, Box<Filter>) -> Filter,
+) -> Filter {
+    filters
+        .into_iter()
+        .reduce(|f, g| constr(Box::new(f), Box::new(g)))
+        .unwrap()
+}
+
+enum Suffix {
+    Field(String),
+    Index(i64),
+    Iterate,
+}
+
+fn postfix(input: &str) -> IResult<&str, Filter> {
+    let (input, base) = primary(input)?;
+    let (input, suffixes) = many0(suffix)(input)?;
+    Ok((
+        input,
+        suffixes.into_iter().fold(base, |f, s| match s {
+            Suffix::Field(name) => Filter::Field(Box::new(f), name),
+            Suffix::Index(i) => Filter::Index(Box::new(f), i),
+            Suffix::Iterate => Filter::Iterate(Box::new(f)),
+        }),
+    ))
+}
+
+fn primary(input: &str) -> IResult<&str, Filter> {
+    alt((
+        map(preceded(char('.'), field_name), |name| {
+            Filter::Field(Box::new(Filter::Identity), name)
+        }),
+        value(Filter::Identity, char('.')),
+        delimited(char('('), ws(pipe), char(')')),
+        map(delimited(char('['), ws(opt(pipe)), char(']')), |f| {
+            Filter::Array(f.map(Box::new))
+        }),
+        map(
+            delimited(
+                char('{'),
+                ws(separated_list0(ws(char(',')), object_entry)),
+                char('}'),
+            ),
+            Filter::Object,
+        ),
+    ))(input)
+}
+
+fn suffix(input: &str) -> IResult<&str, Suffix> {
+    alt((
+        map(preceded(char('.'), field_name), Suffix::Field),
+        map(
+            preceded(
+                opt(char('.')),
+                delimited(char('['), ws(opt(integer)), char(']')),
+            ),
+            |i| match i {
+                Some(i) => Suffix::Index(i),
+                None => Suffix::Iterate,
+            },
+        ),
+    ))(input)
+}
+
+fn object_entry(input: &str) -> IResult<&str, (String, Filter)> {
+    alt((
+        separated_pair(field_name, ws(char(':')), postfix),
+        map(identifier, |name| {
+            (
+                name.clone(),
+                Filter::Field(Box::new(Filter::Identity), name),
+            )
+        }),
+    ))(input)
+}
+
+fn field_name(input: &str) -> IResult<&str, String> {
+    alt((identifier, string))(input)
+}
+
+fn identifier(input: &str) -> IResult<&str, String> {
+    map(
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+        String::from,
+    )(input)
+}
+
+fn string(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(
+                is_not("\\\""),
+                '\\',
+                alt((value("\\", tag("\\")), value("\"", tag("\"")))),
+            )),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(input)
+}
+
+fn integer(input: &str) -> IResult<&str, i64> {
+    map_res(recognize(pair(opt(char('-')), digit1)), str::parse)(input)
+}
@@ -0,0 +1,69 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use reqwest::Client;
+use rest_protocol::{http::HTTPMethod, input::RESTRequest, Session};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use uritemplate::UriTemplate;
+
+/// Serve a single canned response on a local port.
+async fn serve_once(status: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn request(url: &str) -> RESTRequest {
+    RESTRequest {
+        url: UriTemplate::new(url),
+        data: HashMap::new(),
+        method: HTTPMethod::GET,
+        schema: serde_json::Value::Null,
+        reference: None,
+    }
+}
+
+#[tokio::test]
+async fn requests_are_recorded() {
+    let url = serve_once("404 Not Found", "[]").await;
+    let session = Session::from(Client::new());
+
+    let text = request(&format!("{url}/items"))
+        .execute_in(&session, &HashMap::new())
+        .await
+        .unwrap();
+    assert_eq!(text, "[]");
+    /* Nothing listens on the port anymore. */
+    assert!(request(&format!("{url}/items"))
+        .execute_in(&session, &HashMap::new())
+        .await
+        .is_err());
+
+    let stats = session.stats().get("GET /items").unwrap();
+    assert_eq!(stats.requests, 2);
+    assert_eq!(stats.failures, 2);
+    assert_eq!(stats.bytes, 2);
+    assert_eq!(stats.statuses.get(&404), Some(&1));
+}
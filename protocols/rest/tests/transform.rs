@@ -0,0 +1,79 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use rest_protocol::Transform;
+use serde_json::{json, Value};
+
+fn apply(program: &str, input: Value) -> Vec<Value> {
+    Transform::parse(program).unwrap().apply(&input).unwrap()
+}
+
+#[test]
+fn flatten_nested_response() {
+    let response = json!({
+        "data": {
+            "clusters": [
+                {
+                    "name": "east",
+                    "nodes": [
+                        { "id": 1, "status": { "state": "up" } },
+                        { "id": 2, "status": { "state": "down" } }
+                    ]
+                },
+                {
+                    "name": "west",
+                    "nodes": [{ "id": 3, "status": { "state": "up" } }]
+                }
+            ]
+        }
+    });
+
+    assert_eq!(
+        apply(
+            ".data.clusters[] | {cluster: .name, node: .nodes[]} \
+             | {cluster, id: .node.id, state: .node.status.state}",
+            response
+        ),
+        vec![
+            json!({ "cluster": "east", "id": 1, "state": "up" }),
+            json!({ "cluster": "east", "id": 2, "state": "down" }),
+            json!({ "cluster": "west", "id": 3, "state": "up" }),
+        ]
+    );
+}
+
+#[test]
+fn paths() {
+    let input = json!({ "a": [10, 20, 30], "b c": null });
+    assert_eq!(apply(".", input.clone()), vec![input.clone()]);
+    assert_eq!(apply(".a[1]", input.clone()), vec![json!(20)]);
+    assert_eq!(apply(".a[-1]", input.clone()), vec![json!(30)]);
+    assert_eq!(apply(".a[5]", input.clone()), vec![Value::Null]);
+    assert_eq!(apply(".\"b c\".d", input.clone()), vec![Value::Null]);
+    assert_eq!(apply(".missing", input.clone()), vec![Value::Null]);
+    assert_eq!(
+        apply("[.a[] ], (.a[0], .a[2])", input),
+        vec![json!([10, 20, 30]), json!(10), json!(30)]
+    );
+}
+
+#[test]
+fn type_errors() {
+    let transform = Transform::parse(".a.b").unwrap();
+    assert!(transform.apply(&json!({ "a": [1] })).is_err());
+    let transform = Transform::parse(".a[]").unwrap();
+    assert!(transform.apply(&json!({ "a": 1 })).is_err());
+}
+
+#[test]
+fn invalid_transforms() {
+    for program in ["", ".a |", "{a: }", ".[", ".a ] "] {
+        assert!(Transform::parse(program).is_err(), "{program}");
+    }
+    assert!(serde_json::from_value::<Transform>(json!(".a[")).is_err());
+    assert_eq!(
+        serde_json::to_value(Transform::parse(".a[] | {b}").unwrap()).unwrap(),
+        json!(".a[] | {b}")
+    );
+}
@@ -2,7 +2,11 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::{collections::HashSet, net::IpAddr};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    net::IpAddr,
+};
 
 use netsnmp::Oid;
 use serde::{Deserialize, Serialize};
@@ -32,7 +36,131 @@ pub struct HostConfig {
     pub workers: u16,
     pub port: Option<u16>,
     #[serde(default)]
+    pub transport: Transport,
+    /// Certificate configuration for the TLS and DTLS transports.
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Context name for all requests to the host, overriding
+    /// `context` in the v3 auth of all credentials. Unset keeps the
+    /// context of the auth, or the empty default context. Only used
+    /// with SNMPv3.
+    #[serde(default)]
+    pub context_name: Option<String>,
+    /// Context engine id for all requests to the host, overriding
+    /// `context_engine` in the v3 auth of all credentials. Only used
+    /// with SNMPv3.
+    #[serde(default)]
+    pub context_engine_id: Option<String>,
+    /// Contexts to query, per OID, context group or for all objects.
+    /// The first matching rule applies; `None` in the set stands for
+    /// the session context (`context_name`, `context` in the v3 auth,
+    /// or the empty default context). Only used with SNMPv3.
+    #[serde(default)]
     pub snmpv3_contexts: Vec<(ContextSelector, HashSet<Option<String>>)>,
+    /// Credentials to use instead of `auth` for specific tables, OID
+    /// subtrees or context groups. The first matching rule applies;
+    /// objects not matched by any rule use `auth`.
+    #[serde(default)]
+    pub credentials: Vec<CredentialRule>,
+}
+
+impl HostConfig {
+    /// The transports to try, in order: the configured transport,
+    /// followed by plain UDP if fallback is enabled for a secure
+    /// transport.
+    pub fn transports(&self) -> Vec<Transport> {
+        match self.transport.is_secure() && self.tls.fallback {
+            true => vec![self.transport, Transport::Udp],
+            false => vec![self.transport],
+        }
+    }
+
+    /// The net-snmp peer specification for `transport`. Plain UDP
+    /// without a configured port leaves the port to net-snmp.
+    pub fn peer(&self, ip_addr: IpAddr, transport: Transport) -> String {
+        let port = self.port.filter(|_| transport == self.transport);
+        match (transport, port) {
+            (Transport::Udp, None) => ip_addr.to_string(),
+            (Transport::Udp, Some(port)) => format!("{}:{}", ip_addr, port),
+            (_, port) => {
+                let port = port.unwrap_or_else(|| transport.default_port());
+                match ip_addr {
+                    IpAddr::V4(ip) => {
+                        format!("{}:{}:{}", transport.domain(), ip, port)
+                    }
+                    IpAddr::V6(ip) => {
+                        format!("{}:[{}]:{}", transport.domain(), ip, port)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Select the credentials for a table (or, for scalars outside a
+    /// table, the object) by its id, OID and context group. Returns
+    /// the index of the matching rule in `credentials`, or `None` if
+    /// the default `auth` applies.
+    pub fn select_credentials(
+        &self,
+        table_id: &str,
+        oid: &Oid,
+        group: Option<&str>,
+    ) -> Option<usize> {
+        self.credentials
+            .iter()
+            .position(|rule| rule.selector.matches(table_id, oid, group))
+    }
+
+    /// The credentials selected by `select_credentials`.
+    pub fn credentials_auth(
+        &self,
+        selected: Option<usize>,
+    ) -> Option<&netsnmp::Auth> {
+        match selected {
+            Some(i) => self.credentials.get(i).map(|rule| &rule.auth),
+            None => self.auth.as_ref(),
+        }
+    }
+
+    /// The credentials to open a session with: the credentials
+    /// selected by `select_credentials`, with the configured context
+    /// name and engine id applied.
+    pub fn session_auth(
+        &self,
+        selected: Option<usize>,
+    ) -> Option<netsnmp::Auth> {
+        let mut auth = self.credentials_auth(selected)?.clone();
+        if let netsnmp::Auth::V3(auth) = &mut auth {
+            if let Some(context) = &self.context_name {
+                auth.context = Some(context.clone());
+            }
+            if let Some(engine_id) = &self.context_engine_id {
+                auth.context_engine = Some(engine_id.clone());
+            }
+        }
+        Some(auth)
+    }
+
+    /// The context settings that are ignored for some of the
+    /// credentials, because these are not SNMPv3.
+    pub fn ignored_context_settings(&self) -> Vec<&'static str> {
+        let is_v3 = |auth: Option<&netsnmp::Auth>| {
+            matches!(auth, Some(netsnmp::Auth::V3(_)))
+        };
+        if is_v3(self.auth.as_ref())
+            && self.credentials.iter().all(|rule| is_v3(Some(&rule.auth)))
+        {
+            return Vec::new();
+        }
+        [
+            ("context_name", self.context_name.is_some()),
+            ("context_engine_id", self.context_engine_id.is_some()),
+            ("snmpv3_contexts", !self.snmpv3_contexts.is_empty()),
+        ]
+        .into_iter()
+        .filter_map(|(setting, set)| set.then_some(setting))
+        .collect()
+    }
 }
 
 const fn default_true() -> bool {
@@ -52,6 +180,13 @@ pub struct BulkConfig {
     pub max_length: usize,
     pub max_size: usize,
     pub max_len_diff: usize,
+    /// Adapt max-repetitions per agent: halve it on tooBig responses
+    /// and grow it again after `grow_after` successful requests that
+    /// were limited by it. The value it settles on is kept in the
+    /// walk statistics and used as the starting point for the next
+    /// run.
+    pub adaptive: bool,
+    pub grow_after: usize,
 }
 
 impl Default for BulkConfig {
@@ -63,6 +198,8 @@ impl Default for BulkConfig {
             max_length: 100,
             max_size: 1000,
             max_len_diff: 5,
+            adaptive: false,
+            grow_after: 10,
         }
     }
 }
@@ -85,6 +222,87 @@ impl BulkConfig {
     }
 }
 
+/// The transport used to reach the agent. TLS and DTLS (RFC 5953)
+/// require SNMPv3 and authenticate the agent by its certificate.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    #[default]
+    Udp,
+    Tcp,
+    Tls,
+    Dtls,
+}
+
+impl Transport {
+    pub fn is_secure(self) -> bool {
+        matches!(self, Transport::Tls | Transport::Dtls)
+    }
+
+    /// The net-snmp transport domain.
+    pub fn domain(self) -> &'static str {
+        match self {
+            Transport::Udp => "udp",
+            Transport::Tcp => "tcp",
+            Transport::Tls => "tlstcp",
+            Transport::Dtls => "dtlsudp",
+        }
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            Transport::Udp | Transport::Tcp => 161,
+            Transport::Tls | Transport::Dtls => 10161,
+        }
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Udp => write!(f, "UDP"),
+            Transport::Tcp => write!(f, "TCP"),
+            Transport::Tls => write!(f, "TLS"),
+            Transport::Dtls => write!(f, "DTLS"),
+        }
+    }
+}
+
+/// Certificates for the TLS and DTLS transports. Certificates are
+/// given as a fingerprint or as a file name in net-snmp's certificate
+/// directories.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Our own (client) certificate.
+    pub local_cert: Option<String>,
+    /// The agent's certificate, if it is to be pinned.
+    pub peer_cert: Option<String>,
+    /// A CA certificate trusted to sign the agent's certificate.
+    pub trust_cert: Option<String>,
+    /// The host name to verify the agent's certificate against.
+    pub their_hostname: Option<String>,
+    /// Retry over plain UDP if the secure session cannot be set up.
+    pub fallback: bool,
+}
+
+impl TlsConfig {
+    /// The net-snmp transport configuration tokens.
+    pub fn transport_config(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("localCert", &self.local_cert),
+            ("peerCert", &self.peer_cert),
+            ("trustCert", &self.trust_cert),
+            ("their_hostname", &self.their_hostname),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+        .collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct TimingConfig {
@@ -109,6 +327,44 @@ pub enum ContextSelector {
     Oid(Oid),
 }
 
+/// Credentials (community or v3 user and context) for part of the
+/// objects on a host.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CredentialRule {
+    pub selector: CredentialSelector,
+    pub auth: netsnmp::Auth,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSelector {
+    /// A table (or scalar object) by its id in the MIB input.
+    Table(String),
+    /// All objects in a context group.
+    Group(String),
+    /// All objects in the subtree below this OID.
+    Subtree(Oid),
+}
+
+impl CredentialSelector {
+    pub fn matches(
+        &self,
+        table_id: &str,
+        oid: &Oid,
+        group: Option<&str>,
+    ) -> bool {
+        match self {
+            CredentialSelector::Table(id) => id == table_id,
+            CredentialSelector::Group(sel_group) => {
+                group.map_or(false, |group| group == sel_group)
+            }
+            CredentialSelector::Subtree(prefix) => {
+                oid.as_slice().starts_with(prefix.as_slice())
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(default)]
 pub struct Quirks {
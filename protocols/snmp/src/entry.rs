@@ -2,13 +2,15 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use agent_utils::DBObj;
 use agent_utils::TryGetFrom;
 
 use super::error::TypeResult;
-use super::index::Index;
+use super::index::{Index, IndexType};
 use super::input::{Input, ObjectId};
 
 #[derive(DBObj, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -18,6 +20,14 @@ pub struct EntrySpec {
     pub implied_index: Option<ObjectId>,
     pub augments: Option<ObjectId>,
     pub fold: Option<u64>,
+    /// Textual conventions of index columns that need more than their
+    /// syntax to be decoded (e.g. InetAddress).
+    #[serde(default)]
+    pub index_types: BTreeMap<ObjectId, IndexType>,
+    /// INET-ADDRESS-MIB InetAddress columns outside the index, with
+    /// the InetAddressType column that determines their decoding.
+    #[serde(default)]
+    pub address_types: BTreeMap<ObjectId, ObjectId>,
 }
 
 impl EntrySpec {
@@ -37,6 +47,28 @@ impl EntrySpec {
             index.implied = Some(implied.clone());
         }
 
+        index.types.extend(
+            self.index_types
+                .iter()
+                .map(|(object_id, typ)| (object_id.clone(), *typ)),
+        );
+
         Ok(index)
     }
+
+    /// The tables augmented by this table, directly or through
+    /// another augmenting table. These share the index of this table,
+    /// so their columns can be retrieved as part of it.
+    pub fn augmented_tables<'a>(
+        &'a self,
+        input: &'a Input,
+    ) -> TypeResult<Vec<&'a ObjectId>> {
+        let mut tables = Vec::new();
+        let mut entry = self;
+        while let Some(object_id) = &entry.augments {
+            tables.push(object_id);
+            entry = object_id.try_get_from(&input.tables)?;
+        }
+        Ok(tables)
+    }
 }
@@ -8,6 +8,8 @@ use std::fmt;
 use netsnmp::Oid;
 use thiserror::Error;
 
+use crate::config::Transport;
+
 pub type Result<T> = std::result::Result<T, Error>;
 pub type TypeResult<T> = std::result::Result<T, TypeError>;
 
@@ -23,12 +25,23 @@ pub enum Error {
     Authentication(netsnmp::Error),
     #[error("Failed to connect: {0}")]
     Connection(netsnmp::Error),
+    #[error(
+        "Failed to set up an SNMP session over {0} \
+         (does the device support SNMP over (D)TLS?): {1}"
+    )]
+    SecureTransport(Transport, netsnmp::Error),
+    #[error("SNMP over {0} requires SNMPv3")]
+    SecureTransportRequiresV3(Transport),
+    #[error("SNMP over {0} requires a local certificate")]
+    MissingLocalCert(Transport),
     #[error("Query failed: {0}")]
     Query(netsnmp::Error),
     #[error("SNMP bulk optimization yielded empty query!")]
     EmptyQuery,
     #[error("Empty response!")]
     EmptyResponse,
+    #[error("Probe of sysDescr.0 failed: {0}")]
+    Probe(WalkError),
     #[error("SNMP bulk optimization yielded invalid query!")]
     InvalidQuery,
     #[error("OID {0} was not requested (SNMP plugin error!)")]
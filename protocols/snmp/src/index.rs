@@ -2,12 +2,14 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 use agent_utils::TryGetFrom;
 use etc_base::ProtoDataFieldId;
 use netsnmp::Oid;
-use value::{Data, DataError};
+use serde::{Deserialize, Serialize};
+use value::{Data, DataError, Type, Value};
 
 use super::error::{Result, TypeResult};
 use super::input::{Input, ObjectId};
@@ -17,6 +19,23 @@ use super::scalar::ScalarSpec;
 pub struct Index {
     pub vars: Vec<ObjectId>,
     pub implied: Option<ObjectId>,
+    pub types: BTreeMap<ObjectId, IndexType>,
+}
+
+/// Textual conventions for index columns, which determine how the
+/// column is decoded from the row OID beyond its base syntax.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum IndexType {
+    /// INET-ADDRESS-MIB InetAddressType. Decoded according to its
+    /// syntax; determines the decoding of the next InetAddress column.
+    InetAddressType,
+    /// INET-ADDRESS-MIB InetAddress (length-prefixed or implied
+    /// octets), decoded to its textual representation.
+    InetAddress,
+    /// INET-ADDRESS-MIB InetAddressIPv4 (four octets).
+    InetAddressIPv4,
+    /// INET-ADDRESS-MIB InetAddressIPv6 (sixteen octets).
+    InetAddressIPv6,
 }
 
 impl Index {
@@ -24,6 +43,7 @@ impl Index {
         Index {
             vars: Vec::new(),
             implied: None,
+            types: BTreeMap::new(),
         }
     }
 
@@ -43,34 +63,26 @@ impl Index {
                 Ok((object_id, object_id.try_get_from(&input.scalars)?))
             })
             .collect::<Result<Vec<(&ObjectId, &ScalarSpec)>>>()?;
+
         let mut vals = HashMap::new();
-        let _next = (0..scalars.len()).fold(Some(oid.as_slice()), |idx, i| {
-            let (object_id, scalar) = scalars[i];
+        let mut idx = Some(oid.as_slice());
+        let mut addr_type = None;
+
+        for (i, (object_id, scalar)) in scalars.iter().enumerate() {
+            let typ = self.types.get(*object_id);
             let (val, next) = match idx {
                 Some(idx) => {
-                    let implied = self
-                        .implied
-                        .as_ref()
-                        .map_or(false, |implied| object_id == implied);
-                    let res = match implied {
-                        true => {
-                            let fixed_length: Option<usize> = scalars[(i + 1)..]
-                                .iter()
-                                .map(|(_, scalar)| scalar.fixed_index_length())
-                                .sum();
-                            match fixed_length {
-                                Some(fixed_length) if fixed_length <= idx.len() => scalar.get_value_from_index(idx, Some(idx.len() - fixed_length)),
-                                Some(_) => Err(DataError::TypeError(String::from("calculated implied length is longer than remaining index length"))),
-                                None => Err(DataError::TypeError(String::from("unable to calculate implied length for implied index")))
-                            }
-                        }
-                        false => scalar.get_value_from_index(idx, None),
-                    };
-                    match res {
+                    match self.implied_length(i, &scalars, idx).and_then(
+                        |implied| {
+                            get_value_from_index(
+                                typ, scalar, idx, implied, addr_type,
+                            )
+                        },
+                    ) {
                         Ok((val, next)) => (val, Some(next)),
                         Err(e) => (Err(e), None),
-					}
-				}
+                    }
+                }
                 None => (
                     Err(DataError::TypeError(String::from(
                         "previous index field failed to parse",
@@ -78,13 +90,52 @@ impl Index {
                     None,
                 ),
             };
-            vals.insert(object_id.clone(), val);
-            next
-        });
+
+            if typ == Some(&IndexType::InetAddressType) {
+                addr_type = address_type(&val);
+            }
+
+            vals.insert((*object_id).clone(), val);
+            idx = next;
+        }
 
         Ok(vals)
     }
 
+    /// Calculate the length of the implied index field at position
+    /// `i`, from the fixed length of the fields following it.
+    fn implied_length(
+        &self,
+        i: usize,
+        scalars: &[(&ObjectId, &ScalarSpec)],
+        idx: &[u64],
+    ) -> std::result::Result<Option<usize>, DataError> {
+        if self.implied.as_ref() != Some(scalars[i].0) {
+            return Ok(None);
+        }
+
+        let fixed_length: Option<usize> = scalars[(i + 1)..]
+            .iter()
+            .map(|(object_id, scalar)| match self.types.get(*object_id) {
+                Some(typ) => typ.fixed_index_length(),
+                None => scalar.fixed_index_length(),
+            })
+            .sum();
+
+        match fixed_length {
+            Some(fixed_length) if fixed_length <= idx.len() => {
+                Ok(Some(idx.len() - fixed_length))
+            }
+            Some(_) => Err(DataError::TypeError(String::from(
+                "calculated implied length is longer than remaining \
+                 index length",
+            ))),
+            None => Err(DataError::TypeError(String::from(
+                "unable to calculate implied length for implied index",
+            ))),
+        }
+    }
+
     pub fn to_field_id_set(
         &self,
         input: &Input,
@@ -96,3 +147,148 @@ impl Index {
         Ok(key_set)
     }
 }
+
+impl IndexType {
+    /// The value type, if it differs from the column syntax.
+    pub fn get_type(&self) -> Option<Type> {
+        match self {
+            Self::InetAddressType => None,
+            Self::InetAddress => Some(Type::UnicodeString),
+            Self::InetAddressIPv4 => Some(Type::Ipv4Address),
+            Self::InetAddressIPv6 => Some(Type::Ipv6Address),
+        }
+    }
+
+    fn fixed_index_length(&self) -> Option<usize> {
+        match self {
+            Self::InetAddressType => Some(1),
+            Self::InetAddress => None,
+            Self::InetAddressIPv4 => Some(4),
+            Self::InetAddressIPv6 => Some(16),
+        }
+    }
+}
+
+/// The InetAddressType column for an InetAddress column outside the
+/// index, if the column is declared as such.
+pub(super) fn address_type_column<'a>(
+    object_id: &ObjectId,
+    input: &'a Input,
+) -> TypeResult<Option<&'a ObjectId>> {
+    match &object_id.try_get_from(&input.scalars)?.table {
+        Some(table) => Ok(table
+            .try_get_from(&input.tables)?
+            .address_types
+            .get(object_id)),
+        None => Ok(None),
+    }
+}
+
+/// Read the address type from the value of an InetAddressType column.
+pub(super) fn address_type(val: &Data) -> Option<i64> {
+    match val {
+        Ok(Value::Integer(n)) => Some(*n),
+        Ok(Value::IntEnum(v)) => Some(v.get_value_int()),
+        _ => None,
+    }
+}
+
+/// Decode the value of an InetAddress column outside the index,
+/// according to the value of its InetAddressType column.
+pub(super) fn decode_inet_address(val: Data, addr_type: Option<i64>) -> Data {
+    match val? {
+        Value::BinaryString(octets) => inet_address(addr_type, &octets),
+        _ => Err(DataError::TypeError(String::from(
+            "expected an octet string for InetAddress",
+        ))),
+    }
+}
+
+fn get_value_from_index<'a>(
+    typ: Option<&IndexType>,
+    scalar: &ScalarSpec,
+    index: &'a [u64],
+    implied_length: Option<usize>,
+    addr_type: Option<i64>,
+) -> std::result::Result<(Data, &'a [u64]), DataError> {
+    match typ {
+        None | Some(IndexType::InetAddressType) => {
+            scalar.get_value_from_index(index, implied_length)
+        }
+        Some(IndexType::InetAddressIPv4) => {
+            let (octets, next) = get_octets(index, 4)?;
+            let octets: [u8; 4] = octets.try_into().unwrap();
+            Ok((Ok(Value::Ipv4Address(octets)), next))
+        }
+        Some(IndexType::InetAddressIPv6) => {
+            let (octets, next) = get_octets(index, 16)?;
+            let octets: [u8; 16] = octets.try_into().unwrap();
+            Ok((
+                Ok(Value::Ipv6Address(Ipv6Addr::from(octets).segments())),
+                next,
+            ))
+        }
+        Some(IndexType::InetAddress) => {
+            let (len, index) =
+                scalar.variable_index_length(index, implied_length)?;
+            let (octets, next) = get_octets(index, len)?;
+            Ok((inet_address(addr_type, &octets), next))
+        }
+    }
+}
+
+fn get_octets(
+    index: &[u64],
+    len: usize,
+) -> std::result::Result<(Vec<u8>, &[u64]), DataError> {
+    match index.len() >= len {
+        true => {
+            let (octets, next) = index.split_at(len);
+            let octets = octets
+                .iter()
+                .map(|v| u8::try_from(*v))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| {
+                    DataError::TypeError(String::from("invalid octet in index"))
+                })?;
+            Ok((octets, next))
+        }
+        false => Err(DataError::TypeError(String::from(
+            "index too short for address",
+        ))),
+    }
+}
+
+/// Format an InetAddress according to its InetAddressType, or its
+/// length if the type is unknown.
+fn inet_address(addr_type: Option<i64>, octets: &[u8]) -> Data {
+    let zone = |octets: &[u8]| {
+        u32::from_be_bytes(octets.try_into().unwrap()).to_string()
+    };
+    match (addr_type, octets.len()) {
+        (Some(0) | None, 0) => Ok(Value::UnicodeString(String::new())),
+        (Some(1) | None, 4) => Ok(Value::UnicodeString(
+            Ipv4Addr::from(<[u8; 4]>::try_from(octets).unwrap()).to_string(),
+        )),
+        (Some(2) | None, 16) => Ok(Value::UnicodeString(
+            Ipv6Addr::from(<[u8; 16]>::try_from(octets).unwrap()).to_string(),
+        )),
+        (Some(3) | None, 8) => Ok(Value::UnicodeString(format!(
+            "{}%{}",
+            Ipv4Addr::from(<[u8; 4]>::try_from(&octets[..4]).unwrap()),
+            zone(&octets[4..])
+        ))),
+        (Some(4) | None, 20) => Ok(Value::UnicodeString(format!(
+            "{}%{}",
+            Ipv6Addr::from(<[u8; 16]>::try_from(&octets[..16]).unwrap()),
+            zone(&octets[16..])
+        ))),
+        (Some(16), _) => Ok(Value::UnicodeString(
+            String::from_utf8_lossy(octets).to_string(),
+        )),
+        (addr_type, len) => Err(DataError::TypeError(format!(
+            "invalid InetAddress of length {} for type {:?}",
+            len, addr_type
+        ))),
+    }
+}
@@ -16,7 +16,10 @@ mod scalar;
 mod stats;
 mod walk;
 
-pub use config::{BulkConfig, Config, HostConfig};
+pub use config::{
+    BulkConfig, Config, CredentialRule, CredentialSelector, HostConfig,
+    TlsConfig, Transport,
+};
 pub use get::Gets;
 pub use input::Input;
 pub use plugin::Plugin;
@@ -10,10 +10,10 @@ use std::path::PathBuf;
 use async_trait::async_trait;
 use netsnmp::Oid;
 
-use agent_utils::{KeyVault, TryGetFrom};
+use agent_utils::{FileLock, KeyVault, TryGetFrom};
 use etc_base::{ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap};
 use parking_lot::Mutex;
-use protocol::{DataFieldSpec, DataTableSpec};
+use protocol::{DataFieldSpec, DataTableSpec, Idempotency};
 //use etc::{...};
 //use vault::VaultSock;
 
@@ -21,16 +21,21 @@ use super::config::Config;
 use super::counters::Counters;
 use super::error::{DTError, DTWarning, Error, Result, TypeError, TypeResult};
 use super::get::Gets;
-use super::index::Index;
+use super::index::{Index, IndexType};
 use super::input::{Input, ObjectId};
 use super::query::{self, DataMap, WalkMap};
 use super::stats::Stats;
 use super::walk::Walks;
 
+/// SNMPv2-MIB::sysDescr.0
+const SYS_DESCR: &[u64] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+
 pub struct Plugin {
     cache_dir: PathBuf,
     snmp: netsnmp::NetSNMP,
     key_vault: KeyVault,
+    /// Ignored context settings last reported per host.
+    ignored_settings: Mutex<HashMap<String, Vec<&'static str>>>,
 }
 
 #[async_trait]
@@ -70,16 +75,44 @@ impl protocol::LocalPlugin for Plugin {
         Ok(out)
     }
 
+    async fn probe(
+        &self,
+        _input: &Input,
+        config: &Config,
+    ) -> Option<Result<()>> {
+        self.check_config(config);
+        Some(self.probe_sys_descr(config).await)
+    }
+
+    /// SNMP gets and walks only read.
+    fn idempotency(
+        &self,
+        _input: &Input,
+        _query: &ProtoQueryMap,
+    ) -> Idempotency {
+        Idempotency::Idempotent
+    }
+
     async fn run_queries(
         &self,
         input: &Input,
         config: &Config,
         query: &ProtoQueryMap,
     ) -> Result<DataMap> {
+        self.check_config(config);
         let host_cache_dir = self.cache_dir.join(&config.host_name);
         let stats_file = host_cache_dir.join("snmp_table_length.json");
         let counters_file = host_cache_dir.join("snmp_counters.json");
 
+        /* Hold the locks for the whole run, so that concurrent runs
+         * for the same host do not overwrite each other's updates. */
+        let _stats_lock =
+            FileLock::acquire_async(&stats_file, FileLock::DEFAULT_TIMEOUT)
+                .await?;
+        let _counters_lock =
+            FileLock::acquire_async(&counters_file, FileLock::DEFAULT_TIMEOUT)
+                .await?;
+
         let stats = Mutex::new(Stats::load(&stats_file).await?);
         let mut counters = Counters::load(&counters_file).await?;
 
@@ -109,7 +142,7 @@ impl protocol::LocalPlugin for Plugin {
         }
 
         let mut tables = HashMap::new();
-        for (obj_id, fields) in table_fields {
+        for (obj_id, fields) in &table_fields {
             match obj_id {
                 None => {
                     tables.insert(
@@ -118,25 +151,33 @@ impl protocol::LocalPlugin for Plugin {
                             name: String::from("noIndex"),
                             singleton: true,
                             keys: HashSet::new(),
-                            fields,
+                            fields: fields.clone(),
                         },
                     );
                 }
                 Some(obj_id) => {
                     let object = obj_id.try_get_from(&input.objects)?;
                     let entry = obj_id.try_get_from(&input.tables)?;
+                    let keys =
+                        entry.get_index(input)?.to_field_id_set(input)?;
+                    let mut fields = fields | &keys;
+
+                    /* Augmented tables join on the shared index. */
+                    for base in entry.augmented_tables(input)? {
+                        if let Some(base_fields) =
+                            table_fields.get(&Some(base.clone()))
+                        {
+                            fields.extend(base_fields.iter().cloned());
+                        }
+                    }
+
                     tables.insert(
                         obj_id.to_table_id(input)?,
                         DataTableSpec {
                             name: object.name.to_string(),
                             singleton: false,
-                            keys: entry
-                                .get_index(input)?
-                                .to_field_id_set(input)?,
-                            fields: &fields
-                                | &entry
-                                    .get_index(input)?
-                                    .to_field_id_set(input)?,
+                            keys,
+                            fields,
                         },
                     );
                 }
@@ -152,11 +193,28 @@ impl protocol::LocalPlugin for Plugin {
         let mut fields = HashMap::new();
         for (obj_id, field) in &input.scalars {
             let object = obj_id.try_get_from(&input.objects)?;
+            let convention_type = match &field.table {
+                Some(table) => {
+                    let entry = table.try_get_from(&input.tables)?;
+                    match entry.address_types.contains_key(obj_id) {
+                        true => IndexType::InetAddress.get_type(),
+                        false => entry
+                            .index_types
+                            .get(obj_id)
+                            .and_then(IndexType::get_type),
+                    }
+                }
+                None => None,
+            };
             fields.insert(
                 obj_id.to_field_id(input)?,
                 DataFieldSpec {
                     name: object.name.to_string(),
-                    input_type: field.get_type()?,
+                    input_type: match convention_type {
+                        Some(typ) => typ,
+                        None => field.get_type()?,
+                    },
+                    sensitive: false,
                 },
             );
         }
@@ -181,6 +239,25 @@ impl Plugin {
             cache_dir,
             snmp: netsnmp::init("SmartM SNMP Agent"),
             key_vault,
+            ignored_settings: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Warn about context settings that are ignored for the host. The
+    /// config is passed in on every run, so this is only logged when
+    /// it changes, rather than on every poll.
+    fn check_config(&self, config: &Config) {
+        let ignored = config.host_config.ignored_context_settings();
+        let mut reported = self.ignored_settings.lock();
+        if reported.get(&config.host_name) != Some(&ignored) {
+            if !ignored.is_empty() {
+                log::warn!(
+                    "SNMP: ignoring {} for non-v3 credentials of {}",
+                    ignored.join(", "),
+                    config.host_name
+                );
+            }
+            reported.insert(config.host_name.clone(), ignored);
         }
     }
 
@@ -228,6 +305,31 @@ impl Plugin {
         Ok(query_list)
     }
 
+    /// Get sysDescr.0, to check that the host responds to the
+    /// configured credentials.
+    async fn probe_sys_descr(&self, config: &Config) -> Result<()> {
+        let oid = Oid::from_slice(SYS_DESCR);
+        let context = match config.host_config.session_auth(None) {
+            Some(netsnmp::Auth::V3(auth)) => auth.context.unwrap_or_default(),
+            _ => String::new(),
+        };
+
+        let mut gets = Gets::new();
+        gets.push(oid.clone());
+        let queries = HashMap::from([(context, (Walks::new(), gets))]);
+        let stats = Mutex::new(Stats::new());
+
+        match self
+            .get_raw_table(config, queries, &stats)
+            .await?
+            .remove(&oid)
+        {
+            Some(Ok(_)) => Ok(()),
+            Some(Err(e)) => Err(Error::Probe(e)),
+            None => Err(Error::EmptyResponse),
+        }
+    }
+
     async fn get_auth_from_vault(
         &self,
         mut auth: netsnmp::Auth,
@@ -283,7 +385,11 @@ impl Plugin {
     ) -> Result<DataMap> {
         let queries =
             query::get_queries(input, config, query_map, &mut stats.lock())?;
-        let data = self.get_raw_table(config, queries, stats).await?;
+        let mut data = HashMap::new();
+        for (selected, queries) in queries {
+            let auth = config.host_config.session_auth(selected);
+            data.extend(self.get_walks(config, auth, queries, stats).await?);
+        }
         query::build_tables(input, query_map, data, counters)
     }
 
@@ -292,6 +398,17 @@ impl Plugin {
         config: &Config,
         queries: HashMap<String, (Walks, Gets)>,
         stats: &Mutex<Stats>,
+    ) -> Result<WalkMap> {
+        let auth = config.host_config.session_auth(None);
+        self.get_walks(config, auth, queries, stats).await
+    }
+
+    async fn get_walks(
+        &self,
+        config: &Config,
+        auth: Option<netsnmp::Auth>,
+        queries: HashMap<String, (Walks, Gets)>,
+        stats: &Mutex<Stats>,
     ) -> Result<WalkMap> {
         match config.host_config.use_walk {
             true => query::retrieve_data_from_walk(queries, stats).await,
@@ -306,9 +423,9 @@ impl Plugin {
                     None => None
                 }*/
                 let auth = match self.key_vault {
-                    KeyVault::Identity => config.host_config.auth.clone(),
+                    KeyVault::Identity => auth,
                     _ => {
-                        if let Some(auth) = config.host_config.auth.clone() {
+                        if let Some(auth) = auth {
                             Some(self.get_auth_from_vault(auth).await?)
                         } else {
                             None
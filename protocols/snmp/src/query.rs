@@ -5,6 +5,7 @@
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
 
 use futures::{StreamExt, TryStreamExt};
 use netsnmp::{Oid, SingleSession};
@@ -14,21 +15,21 @@ use etc_base::{
     Annotated, AnnotatedResult, ProtoDataTableId, ProtoQueryMap, ProtoRow,
     Warning,
 };
-use log::debug;
+use log::{debug, warn};
 use logger::Verbosity;
 use parking_lot::Mutex;
 use value::DataError;
 
-use crate::config::ContextSelector;
+use crate::config::{ContextSelector, Transport};
 use crate::Config;
 
 use super::counters::Counters;
 use super::error::{DTError, DTWarning, Error, Result, WalkError, WalkWarning};
 use super::get::Gets;
-use super::index::Index;
+use super::index::{self, Index};
 use super::input::{Input, ObjectId};
 use super::stats::Stats;
-use super::walk::{WalkTable, WalkVar, Walks};
+use super::walk::{BulkTuner, WalkTable, WalkVar, Walks};
 
 pub(super) type Data = std::result::Result<netsnmp::Value, netsnmp::ErrType>;
 pub type WalkData = AnnotatedResult<HashMap<Oid, Data>, WalkWarning, WalkError>;
@@ -45,18 +46,51 @@ async fn init_snmp_session(
         Some(ip) => ip,
         None => ip_lookup_one(&config.host_name).await?,
     };
+
+    let mut transports = config.host_config.transports().into_iter();
+    loop {
+        let transport = transports.next().unwrap();
+        match open_snmp_session(snmp, auth, config, ip_addr, transport) {
+            Err(e @ Error::SecureTransport(..)) if transports.len() > 0 => {
+                warn!("SNMP: {e}; falling back to UDP");
+            }
+            res => return res,
+        }
+    }
+}
+
+fn open_snmp_session(
+    snmp: &netsnmp::NetSNMP,
+    auth: &Option<netsnmp::Auth>,
+    config: &Config,
+    ip_addr: IpAddr,
+    transport: Transport,
+) -> Result<SingleSession> {
     let mut session_builder = snmp.session().set_async_probe(true);
+    let peer = config.host_config.peer(ip_addr, transport);
 
-    let peer = match &config.host_config.port {
-        Some(port) => format!("{}:{}", ip_addr, port),
-        None => ip_addr.to_string(),
-    };
+    if transport.is_secure() {
+        if !matches!(auth, Some(netsnmp::Auth::V3(_))) {
+            return Err(Error::SecureTransportRequiresV3(transport));
+        }
+        if config.host_config.tls.local_cert.is_none() {
+            return Err(Error::MissingLocalCert(transport));
+        }
+    }
 
     debug!("SNMP: connecting to {}", peer);
     session_builder = session_builder
         .set_peer(peer.as_bytes())
         .map_err(Error::Connection)?;
 
+    if transport.is_secure() {
+        for (key, value) in config.host_config.tls.transport_config() {
+            session_builder = session_builder
+                .set_transport_config(key, value)
+                .map_err(|e| Error::SecureTransport(transport, e))?;
+        }
+    }
+
     if let Some(auth) = auth.as_ref() {
         /* Make sure to leave this disabled for final versions! */
         //debug!("SNMP authentication config: {:?}", &auth);
@@ -72,7 +106,12 @@ async fn init_snmp_session(
             .set_timeout(timing.timeout);
     }
 
-    session_builder.open_single().map_err(Error::Connection)
+    session_builder
+        .open_single()
+        .map_err(|e| match transport.is_secure() {
+            true => Error::SecureTransport(transport, e),
+            false => Error::Connection(e),
+        })
 }
 
 /// Retrieve SNMP data from a stored walk.
@@ -217,6 +256,7 @@ pub(super) async fn retrieve_data_bulk(
     debug!("SNMP: using bulk requests with options: {:?}", opts);
 
     let data = Mutex::new(HashMap::new());
+    let tuner = Mutex::new(BulkTuner::new(opts, &stats.lock()));
 
     for (context, (mut walks, gets)) in queries {
         debug!("SNMP: retrieving data for context '{}'", context);
@@ -236,13 +276,22 @@ pub(super) async fn retrieve_data_bulk(
                 // need to borrow all the other variables before
                 // entering the async block, to avoid moving them...
                 |i| {
-                    let (context, walks, gets, data, failed_gets, failed_walks) = (
-                    	&context,
-                    	&walks,
-                    	&gets,
-                    	&data,
-                    	&failed_gets,
-                    	&failed_walks,
+                    let (
+                        context,
+                        walks,
+                        gets,
+                        data,
+                        failed_gets,
+                        failed_walks,
+                        tuner,
+                    ) = (
+                        &context,
+                        &walks,
+                        &gets,
+                        &data,
+                        &failed_gets,
+                        &failed_walks,
+                        &tuner,
                     );
                     async move {
                         retrieve_data_bulk_worker(
@@ -257,6 +306,7 @@ pub(super) async fn retrieve_data_bulk(
                             data,
                             failed_gets,
                             failed_walks,
+                            tuner,
                         )
                         .await
                     }
@@ -281,6 +331,7 @@ pub(super) async fn retrieve_data_bulk(
         }
     }
 
+    tuner.lock().save_stats(&mut stats.lock());
     Ok(data.into_inner())
 }
 
@@ -296,6 +347,7 @@ async fn retrieve_data_bulk_worker(
     data: &Mutex<WalkMap>,
     failed_gets: &Mutex<Gets>,
     failed_walks: &Mutex<Walks>,
+    tuner: &Mutex<BulkTuner>,
 ) -> Result<()> {
     debug!("SNMP: starting worker {}", workern);
 
@@ -321,11 +373,13 @@ async fn retrieve_data_bulk_worker(
 
             let current_walks =
                 walks.take(opts.max_width, opts.max_size, opts, quirks);
-            let max_repetitions = opts.max_repetitions(
-                current_walks.max_expected(),
-                opts.max_size,
-                current_walks.width(),
-            );
+            let max_repetitions = opts
+                .max_repetitions(
+                    current_walks.max_expected(),
+                    opts.max_size,
+                    current_walks.width(),
+                )
+                .min(tuner.lock().limit());
             let current_gets = match quirks.invalid_packets_at_end
                 && !current_walks.is_empty()
             {
@@ -384,6 +438,9 @@ async fn retrieve_data_bulk_worker(
             .await
         {
             Ok(pdu) => {
+                if !current_walks.is_empty() {
+                    tuner.lock().success(max_repetitions, opts);
+                }
                 let mut data = data.lock();
                 let mut vars = pdu.variables().peekable();
                 let mut i = 0;
@@ -431,6 +488,33 @@ async fn retrieve_data_bulk_worker(
                 }
             }
 
+            Err(netsnmp::Error::Response(err))
+                if !current_walks.is_empty()
+                    && is_error_status(&err, "tooBig")
+                    && tuner.lock().too_big(max_repetitions) =>
+            {
+                debug!(
+                    "SNMP: worker {}: Received error: {}; retrying with \
+                     fewer repetitions",
+                    workern, err
+                );
+            }
+
+            Err(netsnmp::Error::Response(err))
+                if is_error_status(&err, "tooBig")
+                    || is_error_status(&err, "genErr") =>
+            {
+                debug!(
+                    "SNMP: worker {}: Received error: {}; saving current \
+                     get/walks to be retrieved using non-bulk requests",
+                    workern, err
+                );
+                failed_gets.lock().extend(current_gets);
+                failed_walks.lock().extend(current_walks);
+                current_gets = Gets::new();
+                current_walks = Walks::new();
+            }
+
             Err(err @ netsnmp::Error::Response(_))
                 if quirks.invalid_packets_at_end =>
             {
@@ -597,21 +681,19 @@ async fn retrieve_data_bulk_worker(
 //     }
 // }
 
+/// Queries per credential (see `HostConfig::select_credentials`)
+/// and context.
+pub(super) type Queries =
+    HashMap<Option<usize>, HashMap<String, (Walks, Gets)>>;
+
 /// Calculate the list of queries to be made.
 pub(super) fn get_queries(
     input: &Input,
     config: &Config,
     query_map: &ProtoQueryMap,
     stats: &mut Stats,
-) -> Result<HashMap<String, (Walks, Gets)>> {
-    let (session_context, context_rules) = match &config.host_config.auth {
-        Some(netsnmp::Auth::V3(netsnmp::V3Auth { context, .. })) => (
-            context.as_deref().unwrap_or(DEFAULT_CONTEXT).to_string(),
-            config.host_config.snmpv3_contexts.as_slice(),
-        ),
-        _ => (String::from(DEFAULT_CONTEXT), &[][..]),
-    };
-
+) -> Result<Queries> {
+    let mut sessions = HashMap::new();
     let mut walks = HashMap::new();
     let mut gets = HashMap::new();
 
@@ -621,13 +703,22 @@ pub(super) fn get_queries(
                 for field_id in field_ids {
                     let obj = ObjectId::from_field_id(field_id, input)?
                         .try_get_from(&input.objects)?;
+                    let cred = select_credentials(
+                        config,
+                        &field_id.0,
+                        &obj.oid,
+                        &obj.context_group,
+                    );
+                    let (session_context, context_rules) = sessions
+                        .entry(cred)
+                        .or_insert_with(|| session_contexts(config, cred));
                     for context in get_contexts(
                         &obj.oid,
                         &obj.context_group,
-                        &session_context,
+                        session_context,
                         context_rules,
                     ) {
-                        gets.entry(context)
+                        gets.entry((cred, context))
                             .or_insert_with(Gets::new)
                             .push(obj.oid.clone());
                     }
@@ -637,10 +728,19 @@ pub(super) fn get_queries(
             Some(obj_id) => {
                 let obj = obj_id.try_get_from(&input.objects)?;
                 let entry = obj_id.try_get_from(&input.tables)?;
+                let cred = select_credentials(
+                    config,
+                    &table_id.0,
+                    &obj.oid,
+                    &obj.context_group,
+                );
+                let (session_context, context_rules) = sessions
+                    .entry(cred)
+                    .or_insert_with(|| session_contexts(config, cred));
                 let contexts = get_contexts(
                     &obj.oid,
                     &obj.context_group,
-                    &session_context,
+                    session_context,
                     context_rules,
                 );
                 let index = entry.get_index(input)?;
@@ -651,7 +751,7 @@ pub(super) fn get_queries(
                         for context in &contexts {
                             add_walk(
                                 walks
-                                    .entry(context.to_string())
+                                    .entry((cred, context.to_string()))
                                     .or_insert_with(HashMap::new),
                                 table_oid,
                                 &index,
@@ -660,16 +760,26 @@ pub(super) fn get_queries(
                         }
                     }
                     false => {
+                        let mut obj_ids = HashSet::new();
                         for field_id in field_ids {
                             let obj_id =
                                 ObjectId::from_field_id(field_id, input)?;
+                            /* InetAddress columns need their type. */
+                            if let Some(type_id) =
+                                index::address_type_column(&obj_id, input)?
+                            {
+                                obj_ids.insert(type_id.clone());
+                            }
+                            obj_ids.insert(obj_id);
+                        }
+                        for obj_id in obj_ids {
                             if !index.contains(&obj_id) {
                                 let field_oid =
                                     &obj_id.try_get_from(&input.objects)?.oid;
                                 for context in &contexts {
                                     add_walk(
                                         walks
-                                            .entry(context.to_string())
+                                            .entry((cred, context.to_string()))
                                             .or_insert_with(HashMap::new),
                                         field_oid,
                                         &index,
@@ -684,27 +794,70 @@ pub(super) fn get_queries(
         }
     }
 
-    let contexts: HashSet<_> =
-        gets.keys().chain(walks.keys()).cloned().collect();
+    let keys: HashSet<_> = gets.keys().chain(walks.keys()).cloned().collect();
+    let mut queries: Queries = HashMap::new();
 
-    Ok(contexts
-        .into_iter()
-        .map(|context| {
-            (
-                context.clone(),
-                (
-                    walks.remove(&context).map_or_else(Walks::new, |walks| {
-                        Walks::from(walks.into_values().collect())
-                    }),
-                    gets.remove(&context).unwrap_or_else(Gets::new),
-                ),
-            )
-        })
-        .collect())
+    for key in keys {
+        let query = (
+            walks.remove(&key).map_or_else(Walks::new, |walks| {
+                Walks::from(walks.into_values().collect())
+            }),
+            gets.remove(&key).unwrap_or_else(Gets::new),
+        );
+        let (cred, context) = key;
+        queries.entry(cred).or_default().insert(context, query);
+    }
+
+    Ok(queries)
+}
+
+/// Select the credentials for an object, warning if credential rules
+/// are configured but none of them covers the object and there is no
+/// default auth to fall back to.
+fn select_credentials(
+    config: &Config,
+    table_id: &str,
+    oid: &Oid,
+    group: &Option<String>,
+) -> Option<usize> {
+    let host = &config.host_config;
+    let selected = host.select_credentials(table_id, oid, group.as_deref());
+    if selected.is_none() && host.auth.is_none() && !host.credentials.is_empty()
+    {
+        warn!(
+            "SNMP: {} ({}) on {} is not covered by any credential rule \
+             and no default auth is configured",
+            table_id, oid, config.host_name
+        );
+    }
+    selected
+}
+
+/// The session context and context rules for the selected credentials.
+fn session_contexts(
+    config: &Config,
+    selected: Option<usize>,
+) -> (String, &[(ContextSelector, HashSet<Option<String>>)]) {
+    match config.host_config.session_auth(selected) {
+        Some(netsnmp::Auth::V3(netsnmp::V3Auth { context, .. })) => (
+            context.unwrap_or_else(|| String::from(DEFAULT_CONTEXT)),
+            config.host_config.snmpv3_contexts.as_slice(),
+        ),
+        _ => (String::from(DEFAULT_CONTEXT), &[][..]),
+    }
 }
 
 const DEFAULT_CONTEXT: &str = "";
 
+/// Check the error status in an error response. Net-snmp only gives
+/// us its error string, which starts with the status name, e.g.
+/// "(tooBig) Response message would have been too large.".
+fn is_error_status(err: &str, status: &str) -> bool {
+    err.strip_prefix('(')
+        .and_then(|err| err.split_once(')'))
+        .map_or(false, |(name, _)| name.starts_with(status))
+}
+
 /// Find a list of contexts to use for the OID, based on the configured rules.
 fn get_contexts(
     oid: &Oid,
@@ -762,6 +915,33 @@ fn add_walk(
         .push(WalkVar::new(oid.clone()), expected);
 }
 
+/// The value of an InetAddressType column for a row, either from the
+/// index or from the walked column.
+fn row_address_type(
+    type_id: &ObjectId,
+    row_id: &Oid,
+    idx_vals: &HashMap<ObjectId, value::Data>,
+    data: &WalkMap,
+    input: &Input,
+    counters: &mut Counters,
+) -> Result<Option<i64>> {
+    if let Some(val) = idx_vals.get(type_id) {
+        return Ok(index::address_type(val));
+    }
+
+    let scalar = type_id.try_get_from(&input.scalars)?;
+    let object = type_id.try_get_from(&input.objects)?;
+    Ok(match data.get(&object.oid) {
+        Some(Ok(Annotated { value: rows, .. })) => rows
+            .get(row_id)
+            .and_then(|val| {
+                scalar.get_value(val.as_ref(), type_id, row_id, counters)
+            })
+            .and_then(|val| index::address_type(&val)),
+        _ => None,
+    })
+}
+
 /// Build a DataMap from an SNMPDataMap, converting field data, warnings and errors.
 pub(super) fn build_tables(
     input: &Input,
@@ -951,13 +1131,13 @@ pub(super) fn build_tables(
 
                 for row_id in &sorted_row_ids {
                     let mut row = HashMap::new();
-                    let mut idx_vals = index.get_values(row_id, input)?;
+                    let idx_vals = index.get_values(row_id, input)?;
 
                     for (data_field_id, object_id) in &index_cols {
                         row.insert(
                             data_field_id.clone(),
-                            match idx_vals.remove(object_id) {
-                                Some(val) => val,
+                            match idx_vals.get(object_id) {
+                                Some(val) => val.clone(),
                                 None => Err(DataError::Missing),
                             },
                         );
@@ -968,18 +1148,29 @@ pub(super) fn build_tables(
                             ObjectId::from_field_id(data_field_id, input)?;
                         let scalar = obj_id.try_get_from(&input.scalars)?;
 
+                        let val = match rows.get(row_id).and_then(|val| {
+                            scalar.get_value(
+                                val.as_ref(),
+                                &obj_id,
+                                row_id,
+                                counters,
+                            )
+                        }) {
+                            Some(val) => val,
+                            None => Err(DataError::Missing),
+                        };
+
                         row.insert(
                             data_field_id.clone(),
-                            match rows.get(row_id).and_then(|val| {
-                                scalar.get_value(
-                                    val.as_ref(),
-                                    &obj_id,
-                                    row_id,
-                                    counters,
-                                )
-                            }) {
-                                Some(val) => val,
-                                None => Err(DataError::Missing),
+                            match index::address_type_column(&obj_id, input)? {
+                                Some(type_id) => index::decode_inet_address(
+                                    val,
+                                    row_address_type(
+                                        type_id, row_id, &idx_vals, &data,
+                                        input, counters,
+                                    )?,
+                                ),
+                                None => val,
                             },
                         );
                     }
@@ -1013,3 +1204,228 @@ pub(super) fn build_tables(
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use netsnmp::Oid;
+    use serde_json::json;
+
+    use etc_base::{Annotated, ProtoDataFieldId, ProtoDataTableId, ProtoRow};
+    use value::Value;
+
+    use super::{build_tables, Data, WalkMap};
+    use crate::counters::Counters;
+    use crate::input::Input;
+
+    fn object(oid: &str, typ: &str) -> serde_json::Value {
+        json!({ "OID": oid, "Name": "", "Type": typ })
+    }
+
+    fn input() -> Input {
+        serde_json::from_value(json!({
+            "Objects": {
+                "ifEntry": object(".1.3.6.1.2.1.2.2.1", "Table"),
+                "ifIndex": object(".1.3.6.1.2.1.2.2.1.1", "Scalar"),
+                "ifDescr": object(".1.3.6.1.2.1.2.2.1.2", "Scalar"),
+                "ifXEntry": object(".1.3.6.1.2.1.31.1.1.1", "Table"),
+                "ifName": object(".1.3.6.1.2.1.31.1.1.1.1", "Scalar"),
+                "ipAddressEntry": object(".1.3.6.1.2.1.4.34.1", "Table"),
+                "ipAddressAddrType":
+                    object(".1.3.6.1.2.1.4.34.1.1", "Scalar"),
+                "ipAddressAddr": object(".1.3.6.1.2.1.4.34.1.2", "Scalar"),
+                "ipAddressIfIndex":
+                    object(".1.3.6.1.2.1.4.34.1.3", "Scalar"),
+                "routeEntry": object(".1.3.6.1.4.1.1.1", "Table"),
+                "routeIndex": object(".1.3.6.1.4.1.1.1.1", "Scalar"),
+                "routeNextHopType": object(".1.3.6.1.4.1.1.1.2", "Scalar"),
+                "routeNextHop": object(".1.3.6.1.4.1.1.1.3", "Scalar")
+            },
+            "Modules": {},
+            "Tables": {
+                "ifEntry": { "Index": ["ifIndex"] },
+                "ifXEntry": { "Index": [], "Augments": "ifEntry" },
+                "ipAddressEntry": {
+                    "Index": ["ipAddressAddrType", "ipAddressAddr"],
+                    "IndexTypes": {
+                        "ipAddressAddrType": "InetAddressType",
+                        "ipAddressAddr": "InetAddress"
+                    }
+                },
+                "routeEntry": {
+                    "Index": ["routeIndex"],
+                    "AddressTypes": { "routeNextHop": "routeNextHopType" }
+                }
+            },
+            "Scalars": {
+                "ifIndex": { "Table": "ifEntry", "Syntax": "Integer32" },
+                "ifDescr": { "Table": "ifEntry", "Syntax": "OCTET STRING" },
+                "ifName": { "Table": "ifXEntry", "Syntax": "OCTET STRING" },
+                "ipAddressAddrType": {
+                    "Table": "ipAddressEntry",
+                    "Syntax": "INTEGER"
+                },
+                "ipAddressAddr": {
+                    "Table": "ipAddressEntry",
+                    "Syntax": "OCTET STRING"
+                },
+                "ipAddressIfIndex": {
+                    "Table": "ipAddressEntry",
+                    "Syntax": "Integer32"
+                },
+                "routeIndex": { "Table": "routeEntry", "Syntax": "Integer32" },
+                "routeNextHopType": {
+                    "Table": "routeEntry",
+                    "Syntax": "INTEGER"
+                },
+                "routeNextHop": {
+                    "Table": "routeEntry",
+                    "Syntax": "OCTET STRING"
+                }
+            },
+            "Events": {}
+        }))
+        .unwrap()
+    }
+
+    fn oid(oid: &[u64]) -> Oid {
+        Oid::from_slice(oid)
+    }
+
+    fn walk(data: &mut WalkMap, column: &[u64], rows: Vec<(&[u64], Data)>) {
+        data.insert(
+            oid(column),
+            Ok(Annotated {
+                value: rows.into_iter().map(|(i, v)| (oid(i), v)).collect(),
+                warnings: Vec::new(),
+            }),
+        );
+    }
+
+    fn run(table: &str, fields: &[&str], data: WalkMap) -> Vec<ProtoRow> {
+        let query = HashMap::from([(
+            ProtoDataTableId(table.to_string()),
+            fields
+                .iter()
+                .map(|f| ProtoDataFieldId(f.to_string()))
+                .collect::<HashSet<_>>(),
+        )]);
+        let mut counters: Counters = serde_json::from_value(json!({})).unwrap();
+        let mut tables =
+            build_tables(&input(), &query, data, &mut counters).unwrap();
+        tables
+            .remove(&ProtoDataTableId(table.to_string()))
+            .unwrap()
+            .unwrap()
+            .value
+    }
+
+    fn field(row: &ProtoRow, name: &str) -> value::Data {
+        row[&ProtoDataFieldId(name.to_string())].clone()
+    }
+
+    #[test]
+    fn inet_address_index() {
+        let mut data = HashMap::new();
+        walk(
+            &mut data,
+            &[1, 3, 6, 1, 2, 1, 4, 34, 1, 3],
+            vec![(&[1, 4, 10, 0, 0, 1], Ok(netsnmp::Value::Integer(3)))],
+        );
+
+        let rows = run(
+            "ipAddressEntry",
+            &["ipAddressAddrType", "ipAddressAddr", "ipAddressIfIndex"],
+            data,
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(field(&rows[0], "ipAddressAddrType"), Ok(Value::Integer(1)));
+        assert_eq!(
+            field(&rows[0], "ipAddressAddr"),
+            Ok(Value::UnicodeString(String::from("10.0.0.1")))
+        );
+        assert_eq!(field(&rows[0], "ipAddressIfIndex"), Ok(Value::Integer(3)));
+    }
+
+    #[test]
+    fn inet_address_column() {
+        let mut data = HashMap::new();
+        walk(
+            &mut data,
+            &[1, 3, 6, 1, 4, 1, 1, 1, 2],
+            vec![
+                (&[1], Ok(netsnmp::Value::Integer(1))),
+                (&[2], Ok(netsnmp::Value::Integer(2))),
+            ],
+        );
+        walk(
+            &mut data,
+            &[1, 3, 6, 1, 4, 1, 1, 1, 3],
+            vec![
+                (&[1], Ok(netsnmp::Value::OctetStr(vec![192, 168, 0, 1]))),
+                (
+                    &[2],
+                    Ok(netsnmp::Value::OctetStr(vec![
+                        0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+                    ])),
+                ),
+            ],
+        );
+
+        /* The type column is used, even if not requested. */
+        let rows = run("routeEntry", &["routeIndex", "routeNextHop"], data);
+        assert_eq!(rows.len(), 2);
+        for row in &rows {
+            let hop = match field(row, "routeIndex") {
+                Ok(Value::Integer(1)) => "192.168.0.1",
+                Ok(Value::Integer(2)) => "fe80::1",
+                idx => panic!("unexpected index: {idx:?}"),
+            };
+            assert_eq!(
+                field(row, "routeNextHop"),
+                Ok(Value::UnicodeString(hop.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn augmented_table_join() {
+        let mut data = HashMap::new();
+        walk(
+            &mut data,
+            &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2],
+            vec![
+                (&[1], Ok(netsnmp::Value::OctetStr(b"lo".to_vec()))),
+                (&[2], Ok(netsnmp::Value::OctetStr(b"eth0".to_vec()))),
+            ],
+        );
+        walk(
+            &mut data,
+            &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 1],
+            vec![
+                (&[1], Ok(netsnmp::Value::OctetStr(b"lo0".to_vec()))),
+                (&[2], Ok(netsnmp::Value::OctetStr(b"ge-0/0/0".to_vec()))),
+            ],
+        );
+
+        /* Columns of ifEntry are available in ifXEntry. */
+        let rows = run("ifXEntry", &["ifIndex", "ifDescr", "ifName"], data);
+        assert_eq!(rows.len(), 2);
+        for row in &rows {
+            let (descr, name) = match field(row, "ifIndex") {
+                Ok(Value::Integer(1)) => ("lo", "lo0"),
+                Ok(Value::Integer(2)) => ("eth0", "ge-0/0/0"),
+                idx => panic!("unexpected index: {idx:?}"),
+            };
+            assert_eq!(
+                field(row, "ifDescr"),
+                Ok(Value::BinaryString(descr.as_bytes().to_vec()))
+            );
+            assert_eq!(
+                field(row, "ifName"),
+                Ok(Value::BinaryString(name.as_bytes().to_vec()))
+            );
+        }
+    }
+}
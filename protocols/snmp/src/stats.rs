@@ -17,12 +17,48 @@ use super::error::Result;
 use super::walk::WalkStats;
 
 #[derive(Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct Stats(HashMap<Oid, WalkStats>);
+#[serde(from = "StatsCompat")]
+pub struct Stats {
+    walks: HashMap<Oid, WalkStats>,
+    /// The max-repetitions value the adaptive bulk mode settled on.
+    max_repetitions: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StatsCompat {
+    V2 {
+        walks: HashMap<Oid, WalkStats>,
+        #[serde(default)]
+        max_repetitions: Option<usize>,
+    },
+    V1(HashMap<Oid, WalkStats>),
+}
+
+impl From<StatsCompat> for Stats {
+    fn from(stats: StatsCompat) -> Self {
+        match stats {
+            StatsCompat::V2 {
+                walks,
+                max_repetitions,
+            } => Self {
+                walks,
+                max_repetitions,
+            },
+            StatsCompat::V1(walks) => Self {
+                walks,
+                max_repetitions: None,
+            },
+        }
+    }
+}
 
 impl Stats {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            walks: HashMap::new(),
+            max_repetitions: None,
+        }
     }
 
     pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -36,7 +72,7 @@ impl Stats {
         }
 
         debug!("SNMP: using empty statistics");
-        Ok(Stats(HashMap::new()))
+        Ok(Stats::new())
     }
 
     pub async fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
@@ -58,10 +94,18 @@ impl Stats {
         &'a mut self,
         oid: Oid,
     ) -> hash_map::Entry<'b, Oid, WalkStats> {
-        self.0.entry(oid)
+        self.walks.entry(oid)
     }
 
     pub fn get_walk(&self, oid: &Oid) -> Option<&WalkStats> {
-        self.0.get(oid)
+        self.walks.get(oid)
+    }
+
+    pub fn max_repetitions(&self) -> Option<usize> {
+        self.max_repetitions
+    }
+
+    pub fn set_max_repetitions(&mut self, max_repetitions: usize) {
+        self.max_repetitions = Some(max_repetitions);
     }
 }
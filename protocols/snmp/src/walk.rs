@@ -39,6 +39,14 @@ pub struct WalkVar {
     pub retrieved: usize,
 }
 
+/// Adaptive GETBULK max-repetitions for one agent, shared by all
+/// workers querying it.
+pub struct BulkTuner {
+    adaptive: bool,
+    limit: usize,
+    successes: usize,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct WalkStats {
     pub length: TDigest,
@@ -356,6 +364,62 @@ impl WalkVar {
     }
 }
 
+impl BulkTuner {
+    pub fn new(opts: &BulkConfig, stats: &Stats) -> Self {
+        let limit = match opts.adaptive {
+            true => stats.max_repetitions().unwrap_or(opts.max_length),
+            false => opts.max_length,
+        };
+        Self {
+            adaptive: opts.adaptive,
+            limit: limit.clamp(1, opts.max_length.max(1)),
+            successes: 0,
+        }
+    }
+
+    /// The current upper bound for max-repetitions.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Register a tooBig response to a request with the given
+    /// max-repetitions. Returns false if the request cannot be
+    /// made smaller.
+    pub fn too_big(&mut self, max_repetitions: usize) -> bool {
+        self.successes = 0;
+        match self.adaptive && max_repetitions > 1 {
+            true => {
+                self.limit = self.limit.min(max_repetitions / 2);
+                debug!("SNMP: decreased max-repetitions to {}", self.limit);
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Register a successful request with the given max-repetitions.
+    pub fn success(&mut self, max_repetitions: usize, opts: &BulkConfig) {
+        if self.adaptive && max_repetitions >= self.limit {
+            self.successes += 1;
+            if self.successes >= opts.grow_after && self.limit < opts.max_length
+            {
+                self.successes = 0;
+                self.limit = (self.limit + self.limit / 4)
+                    .max(self.limit + 1)
+                    .min(opts.max_length);
+                debug!("SNMP: increased max-repetitions to {}", self.limit);
+            }
+        }
+    }
+
+    /// Save the settled max-repetitions value.
+    pub(crate) fn save_stats(&self, stats: &mut Stats) {
+        if self.adaptive {
+            stats.set_max_repetitions(self.limit);
+        }
+    }
+}
+
 impl WalkStats {
     fn from(length: usize, index: u64) -> Self {
         Self {
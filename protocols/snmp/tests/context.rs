@@ -0,0 +1,65 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use snmp_protocol::HostConfig;
+
+fn host_config(json: serde_json::Value) -> HostConfig {
+    serde_json::from_value(json).unwrap()
+}
+
+fn v3_config(context: serde_json::Value) -> HostConfig {
+    host_config(serde_json::json!({
+        "auth": {
+            "version": "3",
+            "level": "noAuthNoPriv",
+            "context": "auth-context",
+            "context_engine": null
+        },
+        "timing": null,
+        "port": null,
+        "context_name": context,
+        "context_engine_id": "80000009030000c1b1129980"
+    }))
+}
+
+fn session_context(config: &HostConfig) -> (Option<String>, Option<String>) {
+    match config.session_auth(None) {
+        Some(netsnmp::Auth::V3(auth)) => (auth.context, auth.context_engine),
+        auth => panic!("unexpected auth: {auth:?}"),
+    }
+}
+
+#[test]
+fn context_overrides_auth() {
+    let config = v3_config(serde_json::json!("vlan-10"));
+    assert_eq!(
+        session_context(&config),
+        (
+            Some("vlan-10".to_string()),
+            Some("80000009030000c1b1129980".to_string())
+        )
+    );
+    assert!(config.ignored_context_settings().is_empty());
+}
+
+#[test]
+fn context_defaults_to_auth() {
+    let config = v3_config(serde_json::Value::Null);
+    assert_eq!(session_context(&config).0, Some("auth-context".to_string()));
+}
+
+#[test]
+fn context_ignored_without_v3() {
+    let config = host_config(serde_json::json!({
+        "auth": {"version": "2c", "community": "public"},
+        "timing": null,
+        "port": null,
+        "context_name": "vlan-10"
+    }));
+    assert!(matches!(
+        config.session_auth(None),
+        Some(netsnmp::Auth::V2c(_))
+    ));
+    assert_eq!(config.ignored_context_settings(), vec!["context_name"]);
+}
@@ -0,0 +1,120 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use netsnmp::Oid;
+use snmp_protocol::{CredentialRule, CredentialSelector, HostConfig};
+
+fn host_config(json: serde_json::Value) -> HostConfig {
+    serde_json::from_value(json).unwrap()
+}
+
+fn community(community: &str) -> netsnmp::Auth {
+    serde_json::from_value(serde_json::json!({
+        "version": "2c",
+        "community": community
+    }))
+    .unwrap()
+}
+
+fn oid(oid: &[u64]) -> Oid {
+    Oid::from_vec(oid.to_vec())
+}
+
+fn config() -> HostConfig {
+    host_config(serde_json::json!({
+        "auth": {"version": "2c", "community": "public"},
+        "timing": null,
+        "port": null,
+        "credentials": [
+            {
+                "selector": {"table": "vlanTable"},
+                "auth": {"version": "2c", "community": "public@10"}
+            },
+            {
+                "selector": {"group": "bridge"},
+                "auth": {"version": "2c", "community": "public@20"}
+            }
+        ]
+    }))
+}
+
+fn selected_community(config: &HostConfig, selected: Option<usize>) -> String {
+    match config.credentials_auth(selected) {
+        Some(netsnmp::Auth::V2c(params)) => params.community.to_string(),
+        auth => panic!("unexpected auth: {auth:?}"),
+    }
+}
+
+#[test]
+fn select_by_table() {
+    let config = config();
+    let selected =
+        config.select_credentials("vlanTable", &oid(&[1, 3, 6, 1]), None);
+    assert_eq!(selected, Some(0));
+    assert_eq!(selected_community(&config, selected), "public@10");
+}
+
+#[test]
+fn select_by_group() {
+    let config = config();
+    let selected = config.select_credentials(
+        "dot1dTpFdbTable",
+        &oid(&[1, 3, 6, 1, 2, 1, 17, 4, 3]),
+        Some("bridge"),
+    );
+    assert_eq!(selected, Some(1));
+    assert_eq!(selected_community(&config, selected), "public@20");
+}
+
+#[test]
+fn first_rule_wins() {
+    let config = config();
+    let selected =
+        config.select_credentials("vlanTable", &oid(&[1]), Some("bridge"));
+    assert_eq!(selected, Some(0));
+}
+
+#[test]
+fn fall_back_to_default_auth() {
+    let config = config();
+    let selected =
+        config.select_credentials("ifTable", &oid(&[1, 3, 6, 1]), None);
+    assert_eq!(selected, None);
+    assert_eq!(selected_community(&config, selected), "public");
+}
+
+#[test]
+fn select_by_subtree() {
+    let mut config = host_config(serde_json::json!({
+        "auth": null,
+        "timing": null,
+        "port": null
+    }));
+    config.credentials.push(CredentialRule {
+        selector: CredentialSelector::Subtree(oid(&[1, 3, 6, 1, 2, 1, 17])),
+        auth: community("bridge"),
+    });
+
+    let bridge_oid = oid(&[1, 3, 6, 1, 2, 1, 17, 4, 3, 1]);
+    let selected =
+        config.select_credentials("dot1dTpFdbTable", &bridge_oid, None);
+    assert_eq!(selected, Some(0));
+    assert_eq!(selected_community(&config, selected), "bridge");
+
+    let if_oid = oid(&[1, 3, 6, 1, 2, 1, 2, 2]);
+    let selected = config.select_credentials("ifTable", &if_oid, None);
+    assert_eq!(selected, None);
+    assert!(config.credentials_auth(selected).is_none());
+}
+
+#[test]
+fn no_credential_rules() {
+    let config = host_config(serde_json::json!({
+        "auth": {"version": "2c", "community": "public"},
+        "timing": null,
+        "port": null
+    }));
+    assert!(config.credentials.is_empty());
+    assert_eq!(config.select_credentials("ifTable", &oid(&[1]), None), None);
+}
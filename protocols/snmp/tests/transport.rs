@@ -0,0 +1,94 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::net::IpAddr;
+
+use snmp_protocol::{HostConfig, Transport};
+
+fn host_config(json: serde_json::Value) -> HostConfig {
+    serde_json::from_value(json).unwrap()
+}
+
+fn ip(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+}
+
+#[test]
+fn parse_transport_config() {
+    let config = host_config(serde_json::json!({
+        "auth": null,
+        "timing": null,
+        "port": null,
+        "transport": "dtls",
+        "tls": {
+            "local_cert": "agent",
+            "trust_cert": "ca",
+            "their_hostname": "switch.example.com"
+        }
+    }));
+    assert_eq!(config.transport, Transport::Dtls);
+    assert!(!config.tls.fallback);
+    assert_eq!(
+        config.tls.transport_config(),
+        vec![
+            ("localCert", "agent"),
+            ("trustCert", "ca"),
+            ("their_hostname", "switch.example.com")
+        ]
+    );
+}
+
+#[test]
+fn default_transport() {
+    let config = host_config(serde_json::json!({
+        "auth": null,
+        "timing": null,
+        "port": null
+    }));
+    assert_eq!(config.transport, Transport::Udp);
+    assert_eq!(config.transports(), vec![Transport::Udp]);
+    assert_eq!(config.peer(ip("10.0.0.1"), Transport::Udp), "10.0.0.1");
+}
+
+#[test]
+fn secure_peers() {
+    let mut config = host_config(serde_json::json!({
+        "auth": null,
+        "timing": null,
+        "port": null,
+        "transport": "tls"
+    }));
+    assert_eq!(
+        config.peer(ip("10.0.0.1"), Transport::Tls),
+        "tlstcp:10.0.0.1:10161"
+    );
+    assert_eq!(
+        config.peer(ip("fe80::1"), Transport::Tls),
+        "tlstcp:[fe80::1]:10161"
+    );
+
+    config.port = Some(11161);
+    config.transport = Transport::Dtls;
+    assert_eq!(
+        config.peer(ip("10.0.0.1"), Transport::Dtls),
+        "dtlsudp:10.0.0.1:11161"
+    );
+}
+
+#[test]
+fn fallback_to_udp() {
+    let mut config = host_config(serde_json::json!({
+        "auth": null,
+        "timing": null,
+        "port": 11161,
+        "transport": "tls",
+        "tls": { "local_cert": "agent", "fallback": true }
+    }));
+    assert_eq!(config.transports(), vec![Transport::Tls, Transport::Udp]);
+    /* The configured port belongs to the secure transport. */
+    assert_eq!(config.peer(ip("10.0.0.1"), Transport::Udp), "10.0.0.1");
+
+    config.transport = Transport::Tcp;
+    assert_eq!(config.transports(), vec![Transport::Tcp]);
+}
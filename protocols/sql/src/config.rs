@@ -4,6 +4,7 @@
 
 use std::{
     collections::HashMap, fmt::Display, net::IpAddr, path::PathBuf, sync::Arc,
+    time::Duration,
 };
 
 use agent_utils::KeyVault;
@@ -12,6 +13,8 @@ use tap::Pipe;
 
 use crate::{error::Result, sqlplugin::SqlPlugin, ConnectionString};
 
+const DEFAULT_MAX_POOL_SIZE: usize = 8;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct Config {
@@ -26,8 +29,10 @@ pub struct Config {
     pub username: Option<String>,
     #[serde(default)]
     pub password: Option<String>,
+    /// The ODBC driver name, or "postgres" to use the native
+    /// PostgreSQL driver instead of ODBC.
     #[serde(default)]
-    pub driver: Option<String>,
+    pub driver: Option<Driver>,
     #[serde(default)]
     pub database: Option<String>,
     #[serde(default)]
@@ -50,9 +55,44 @@ pub struct Config {
     pub custom_args: Vec<(String, String)>,
     #[serde(default)]
     pub connection_string: Option<String>,
+    /// Maximum number of idle connections kept for reuse within a run.
+    #[serde(default)]
+    pub max_pool_size: Option<usize>,
+    /// Discard stored counters older than this many seconds. By
+    /// default, stored counters are kept indefinitely.
+    #[serde(default)]
+    pub counter_ttl: Option<u64>,
 }
 
 impl Config {
+    pub fn max_pool_size(&self) -> usize {
+        self.max_pool_size.unwrap_or(DEFAULT_MAX_POOL_SIZE)
+    }
+
+    pub fn counter_ttl(&self) -> Option<Duration> {
+        self.counter_ttl.map(Duration::from_secs)
+    }
+
+    pub fn driver(&self) -> &Driver {
+        self.driver.as_ref().unwrap_or(&Driver::Odbc(None))
+    }
+
+    /// The username and password, from the keyvault if available.
+    pub async fn credentials(
+        &self,
+        kvault: &KeyVault,
+    ) -> Result<(Option<String>, Option<String>)> {
+        Ok(
+            match kvault
+                .retrieve_creds(self.username.clone().unwrap_or_default())
+                .await?
+            {
+                None => (self.username.clone(), self.password.clone()),
+                Some(creds) => (creds.username, creds.password),
+            },
+        )
+    }
+
     pub async fn generic_connectionstring(
         self: Arc<Self>,
         sql_plugin: Arc<dyn SqlPlugin>,
@@ -79,6 +119,43 @@ impl Config {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum Driver {
+    /// Connect over ODBC, using the named driver if set.
+    Odbc(Option<String>),
+    /// Connect natively to a PostgreSQL server.
+    Postgres,
+}
+
+impl From<String> for Driver {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Self::Postgres,
+            _ => Self::Odbc(Some(value)),
+        }
+    }
+}
+
+impl From<Driver> for String {
+    fn from(value: Driver) -> Self {
+        match value {
+            Driver::Odbc(name) => name.unwrap_or_default(),
+            Driver::Postgres => "postgres".to_string(),
+        }
+    }
+}
+
+impl Driver {
+    /// The driver name to put in ODBC connection strings.
+    pub fn odbc_name(&self) -> Option<&str> {
+        match self {
+            Self::Odbc(name) => name.as_deref(),
+            Self::Postgres => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SslMode {
     Require,
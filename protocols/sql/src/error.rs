@@ -26,6 +26,10 @@ pub enum Error {
     Format(#[from] std::fmt::Error),
     #[error("Cannot connect to database instance {0}: {1}")]
     Connection(InstanceType, #[source] odbc_api::Error),
+    #[error("Cannot connect to postgres instance {0}: {1}")]
+    PgConnection(InstanceType, #[source] tokio_postgres::Error),
+    #[error("Cannot set up tls: {0}")]
+    Tls(#[source] native_tls::Error),
     #[error("Cannot retrieve the databases from instance {0}: {1}")]
     DatabaseQuery(InstanceType, #[source] Box<DTError>),
     #[error("Cannot create a counter database: {0}")]
@@ -45,6 +49,8 @@ pub type DTEResult<T> = std::result::Result<T, DTError>;
 pub enum DTError {
     #[error("Query failed to execute: {0}")]
     FailedQuery(#[source] odbc_api::Error),
+    #[error("Postgres query failed to execute: {0}")]
+    Postgres(#[source] tokio_postgres::Error),
     #[error("Query returned an empty result")]
     EmptyResult,
     #[error(
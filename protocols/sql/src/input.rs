@@ -76,6 +76,9 @@ pub struct FieldSpec {
     pub is_key: bool,
     /// possible values of the type if this field is an enum
     pub values: Option<ValueTypes>,
+    /// the value is hidden in logs and output
+    #[serde(default)]
+    pub sensitive: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -253,23 +256,27 @@ impl SqlPlugin {
     pub async fn get_plugin(
         &self,
         prot_plugin: &crate::Plugin,
+        config: &crate::Config,
     ) -> Result<Arc<dyn crate::sqlplugin::SqlPlugin>> {
         Ok(match self {
             SqlPlugin::Odbc => Arc::new(
                 crate::sqlplugin::odbc::Plugin::from_protocol_plugin(
                     prot_plugin,
+                    config,
                 )
                 .await?,
             ),
             SqlPlugin::Mssql => Arc::new(
                 crate::sqlplugin::mssql::Plugin::from_protocol_plugin(
                     prot_plugin,
+                    config,
                 )
                 .await?,
             ),
             SqlPlugin::Oracle => Arc::new(
                 crate::sqlplugin::oracle::Plugin::from_protocol_plugin(
                     prot_plugin,
+                    config,
                 )
                 .await?,
             ),
@@ -353,7 +360,7 @@ impl ConnectionString {
         let mut cs = ConnectionString::new()
             .with_arg("Server", config.hostname.clone())
             .with_args(config.custom_args.clone())
-            .with_arg_if_set("Driver", &config.driver)
+            .with_arg_if_set("Driver", &config.driver().odbc_name())
             .with_arg_if_set("SSL", &config.ssl)
             .with_arg_if_set("DSN", &config.dsn)
             .with_arg_if_set("Database", &config.database)
@@ -379,13 +386,7 @@ impl ConnectionString {
             std::env::set_var(key, value)
         }
 
-        let (username, password) = match kvault
-            .retrieve_creds(config.username.clone().unwrap_or_default())
-            .await?
-        {
-            None => (config.username.clone(), config.password.clone()),
-            Some(creds) => (creds.username, creds.password),
-        };
+        let (username, password) = config.credentials(kvault).await?;
 
         cs.add_if_set("Uid", &username);
         cs.add_if_set("Pwd", &password);
@@ -6,6 +6,8 @@ pub mod config;
 pub mod error;
 pub mod input;
 pub mod plugin;
+mod pool;
+mod postgres;
 mod sqlplugin;
 
 pub use config::*;
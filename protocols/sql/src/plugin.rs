@@ -26,15 +26,18 @@ use etc_base::{
     ProtoQueryMap, ProtoRow, Warning,
 };
 use logger::Verbosity;
-use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin};
+use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin, REDACTED};
 use tap::{Pipe, Tap, TapFallible};
+use tokio_postgres::Client;
 use value::Data;
 use wmi_protocol::CounterDB;
 
 use crate::{
-    config::{Config, InstanceType},
+    config::{Config, Driver, InstanceType},
     error::{DTEResult, DTError, DTWResult, DTWarning, Error, Result},
     input::{FieldSpec, Input, TableSpec},
+    pool::ConnectionPool,
+    postgres,
     sqlplugin::SqlPlugin,
 };
 
@@ -42,7 +45,8 @@ pub type Table = Vec<HashMap<String, String>>;
 type DataTable = DTWResult<Vec<HashMap<ProtoDataFieldId, Data>>>;
 type TableData = AnnotatedResult<Vec<ProtoRow>, DTWarning, DTError>;
 type DataMap = HashMap<ProtoDataTableId, TableData>;
-type SConnection<'a> = force_send_sync::Send<odbc_api::Connection<'a>>;
+pub(crate) type SConnection<'a> =
+    force_send_sync::Send<odbc_api::Connection<'a>>;
 
 lazy_static::lazy_static! {
     pub static ref ENV: Environment = Environment::new().unwrap();
@@ -61,6 +65,73 @@ impl Plugin {
             cache_dir,
         }
     }
+
+    /// Counters are stored per host, so that hosts polled in
+    /// separate runs do not overwrite each other's counters.
+    pub(crate) fn counter_file(&self, config: &Config) -> PathBuf {
+        self.cache_dir
+            .join(&config.hostname)
+            .join("sql_counters.json")
+    }
+
+    /// Log on to every instance of the host, without running queries.
+    async fn probe_connect(
+        &self,
+        input: &Input,
+        config: &Config,
+    ) -> Result<()> {
+        let config = Arc::new(config.clone());
+        let sql_plugin = input
+            .data_tables
+            .values()
+            .find_map(|dt| dt.plugin)
+            .unwrap_or_default()
+            .get_plugin(self, &config)
+            .await?;
+        let connection_strings = match config.driver() {
+            Driver::Odbc(_) => {
+                config
+                    .clone()
+                    .generic_connectionstring(sql_plugin, &self.key_vault)
+                    .await?
+            }
+            Driver::Postgres => {
+                postgres::connection_strings(&config, &self.key_vault).await?
+            }
+        };
+
+        let timeout = *config.timeout.as_ref().unwrap_or(&20) as u64;
+        for (instance, connection_string) in connection_strings {
+            let connect = async {
+                match config.driver() {
+                    Driver::Odbc(_) => tokio::task::spawn_blocking(move || {
+                        ENV.connect_with_connection_string(
+                            &connection_string,
+                            ConnectionOptions::default(),
+                        )
+                        .map(drop)
+                        .map_err(|e| Error::Connection(instance, e))
+                    })
+                    .await
+                    .unwrap(),
+                    Driver::Postgres => postgres::connect(
+                        &instance,
+                        &connection_string,
+                        None,
+                        config
+                            .disable_certificate_verification
+                            .unwrap_or(false),
+                    )
+                    .await
+                    .map(drop),
+                }
+            };
+            tokio::time::timeout(Duration::from_secs(timeout), connect)
+                .await
+                .map_err(|_| Error::Timeout(timeout))??;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -74,16 +145,26 @@ struct SqlRequest {
     pub sql_plugin: Arc<dyn SqlPlugin>,
     pub instance: InstanceType,
     pub connection_string: String,
+    pub pool: Arc<ConnectionPool>,
+    pub accept_invalid_certs: bool,
 }
 
 impl SqlRequest {
-    fn connect(&self, database: Option<&str>) -> Result<SConnection<'_>> {
-        let connection_string = match database {
+    fn database_connection_string(&self, database: Option<&str>) -> String {
+        match database {
             None => self.connection_string.to_string(),
             Some(database) => {
                 format!("{};Database={}", self.connection_string, database)
             }
-        };
+        }
+    }
+
+    fn connect(&self, database: Option<&str>) -> Result<SConnection<'static>> {
+        let connection_string = self.database_connection_string(database);
+        if let Some(conn) = self.pool.take(&connection_string) {
+            debug!("[{}] reusing pooled connection", self.instance);
+            return Ok(conn);
+        }
         ENV.connect_with_connection_string(
             &connection_string,
             ConnectionOptions::default(),
@@ -94,6 +175,20 @@ impl SqlRequest {
         .map_err(|e| Error::Connection(self.instance.clone(), e))
     }
 
+    /// Return the connection to the pool, unless it was used by a
+    /// failed query, in which case it is closed.
+    fn release(
+        &self,
+        database: Option<&str>,
+        connection: SConnection<'static>,
+        failed: bool,
+    ) {
+        if !failed {
+            self.pool
+                .put(self.database_connection_string(database), connection);
+        }
+    }
+
     fn query(
         &self,
         connection: &SConnection<'_>,
@@ -152,17 +247,7 @@ impl SqlRequest {
 
         let query = (*self.database_query).as_ref().unwrap();
         let results = self.query(connection, query.as_str())?;
-
-        results
-            .into_iter()
-            .map(|row| {
-                row.into_iter()
-                    .next()
-                    .map(|(_, v)| v)
-                    .ok_or(DTError::NoDatabaseColumn)
-            })
-            .collect::<DTEResult<Vec<String>>>()
-            .map(Some)
+        database_names(results).map(Some)
     }
 
     fn query_datatable(
@@ -171,6 +256,22 @@ impl SqlRequest {
         tablespec: &TableSpec,
         datafields: &HashSet<ProtoDataFieldId>,
     ) -> DataTable {
+        let (fieldspecs, query) =
+            self.datatable_query(tablespec, datafields)?;
+        let datatable = self
+            .query(connection, &query)
+            .tap_err(|e| warn!("query failed: {e}"))
+            .map_err(DTWarning::DTError)?;
+        self.parse_datatable(tablespec, &fieldspecs, datatable)
+    }
+
+    /// Construct the query for a data table, along with the specs
+    /// of the requested fields.
+    fn datatable_query<'a>(
+        &'a self,
+        tablespec: &TableSpec,
+        datafields: &'a HashSet<ProtoDataFieldId>,
+    ) -> DTWResult<(HashMap<&'a ProtoDataFieldId, &'a FieldSpec>, String)> {
         info!(
             "[{}]: Querying {}",
             self.instance,
@@ -187,12 +288,21 @@ impl SqlRequest {
             tablespec,
             fieldspecs.values().cloned().collect(),
         )?;
+        Ok((fieldspecs, query))
+    }
 
-        let datatable = self
-            .query(connection, &query)
-            .tap_err(|e| warn!("query failed: {e}"))
-            .map_err(DTWarning::DTError)?;
-        trace!("[{}] received table: {datatable:#?}", self.instance);
+    /// Transform the textual query result into data table rows.
+    fn parse_datatable(
+        &self,
+        tablespec: &TableSpec,
+        fieldspecs: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+        datatable: Table,
+    ) -> DataTable {
+        trace!(
+            "[{}] received table: {:#?}",
+            self.instance,
+            redact_table(&datatable, fieldspecs)
+        );
         let transformed = self
             .sql_plugin
             .transform_table(tablespec, &datatable)
@@ -205,7 +315,11 @@ impl SqlRequest {
         let datatable = match transformed {
             Cow::Borrowed(_) => datatable,
             Cow::Owned(dt) => dt.tap(|datatable| {
-                trace!("[{}] transformed table: {datatable:#?}", self.instance)
+                trace!(
+                    "[{}] transformed table: {:#?}",
+                    self.instance,
+                    redact_table(datatable, fieldspecs)
+                )
             }),
         };
 
@@ -267,13 +381,15 @@ impl SqlRequest {
             )
         })?;
 
-        Ok(self
+        let data: HashMap<_, _> = self
             .database_queries
             .iter()
             .map(|(df_id, (dt, dfs))| {
                 (df_id.clone(), self.query_datatable(&connection, dt, dfs))
             })
-            .collect())
+            .collect();
+        self.release(Some(&database), connection, has_failed(&data));
+        Ok(data)
     }
 
     fn query_instance(self) -> Result<HashMap<ProtoDataTableId, DataTable>> {
@@ -327,6 +443,7 @@ impl SqlRequest {
             })
             .collect();
         debug!("[{}]: Generic Queries done", &self.instance);
+        self.release(None, connection, has_failed(&data));
         data.reserve(self.database_queries.len());
 
         for handle in db_handles {
@@ -340,25 +457,116 @@ impl SqlRequest {
                 Ok(d) => d,
             };
 
-            for (dt_id, dt_res) in db_data {
-                match data.entry(dt_id) {
-                    Entry::Vacant(entry) => {
-                        entry.insert(dt_res);
-                    }
-                    Entry::Occupied(entry) => {
-                        let (dt_id, mut datatable) = entry.remove_entry();
-                        datatable = match (datatable, dt_res) {
-                            (Err(e), _) => Err(e),
-                            (_, Err(e)) => Err(e),
-                            (Ok(mut table1), Ok(table2)) => {
-                                table1.extend(table2);
-                                Ok(table1)
-                            }
-                        };
-                        data.insert(dt_id, datatable);
-                    }
-                };
-            }
+            merge_tables(&mut data, db_data);
+        }
+        debug!("[{}]: Database Queries done", &self.instance);
+
+        info!("[{}]: All queries completed", &self.instance);
+        Ok(data)
+    }
+}
+
+impl SqlRequest {
+    async fn pg_connect(&self, database: Option<&str>) -> Result<Client> {
+        postgres::connect(
+            &self.instance,
+            &self.connection_string,
+            database,
+            self.accept_invalid_certs,
+        )
+        .await
+    }
+
+    async fn pg_query_datatable(
+        &self,
+        client: &Client,
+        tablespec: &TableSpec,
+        datafields: &HashSet<ProtoDataFieldId>,
+    ) -> DataTable {
+        let (fieldspecs, query) =
+            self.datatable_query(tablespec, datafields)?;
+        let datatable = postgres::query(&self.instance, client, &query)
+            .await
+            .tap_err(|e| warn!("query failed: {e}"))
+            .map_err(DTWarning::DTError)?;
+        self.parse_datatable(tablespec, &fieldspecs, datatable)
+    }
+
+    async fn pg_query_tables(
+        &self,
+        client: &Client,
+        tables: &HashMap<
+            ProtoDataTableId,
+            (TableSpec, HashSet<ProtoDataFieldId>),
+        >,
+    ) -> HashMap<ProtoDataTableId, DataTable> {
+        let mut data = HashMap::with_capacity(tables.len());
+        for (dt_id, (dt, dfs)) in tables {
+            data.insert(
+                dt_id.clone(),
+                self.pg_query_datatable(client, dt, dfs).await,
+            );
+        }
+        data
+    }
+
+    async fn pg_query_dbspecific(
+        &self,
+        database: String,
+    ) -> Result<HashMap<ProtoDataTableId, DataTable>> {
+        debug!("[{}]: Switching to database: {database}", &self.instance);
+        let client = self.pg_connect(Some(&database)).await.tap_err(|e| {
+            error!(
+                "Cannot connect to database {database} in instance {}: {e}",
+                &self.instance
+            )
+        })?;
+        Ok(self.pg_query_tables(&client, &self.database_queries).await)
+    }
+
+    /// Query an instance using the native PostgreSQL driver.
+    async fn pg_query_instance(
+        self,
+    ) -> Result<HashMap<ProtoDataTableId, DataTable>> {
+        info!("[{}] starting postgres connection", &self.instance);
+        let client = self.pg_connect(None).await.tap_err(|e| {
+            error!("[{}] Cannot connect to instance: {e}", &self.instance)
+        })?;
+
+        let databases = match self.database_query.as_ref() {
+            None => Vec::new(),
+            Some(query) => postgres::query(&self.instance, &client, query)
+                .await
+                .and_then(database_names)
+                .map_err(|e| {
+                    warn!(
+                        "[{}]: Unable to retrieve databases on instance: {e}",
+                        &self.instance
+                    );
+                    Error::DatabaseQuery(self.instance.clone(), Box::new(e))
+                })?,
+        };
+
+        if !databases.is_empty() {
+            info!(
+                "[{}]: Found {} databases on instance: {:?}",
+                &self.instance,
+                databases.len(),
+                &databases
+            );
+        }
+
+        let mut data =
+            self.pg_query_tables(&client, &self.general_queries).await;
+        debug!("[{}]: Generic Queries done", &self.instance);
+        data.reserve(self.database_queries.len());
+
+        let db_data = futures::future::join_all(
+            databases.into_iter().map(|db| self.pg_query_dbspecific(db)),
+        )
+        .await;
+        for db_data in db_data.into_iter().flatten() {
+            merge_tables(&mut data, db_data);
         }
         debug!("[{}]: Database Queries done", &self.instance);
 
@@ -367,6 +575,71 @@ impl SqlRequest {
     }
 }
 
+fn has_failed(data: &HashMap<ProtoDataTableId, DataTable>) -> bool {
+    data.values().any(|table| table.is_err())
+}
+
+fn database_names(results: Table) -> DTEResult<Vec<String>> {
+    results
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .next()
+                .map(|(_, v)| v)
+                .ok_or(DTError::NoDatabaseColumn)
+        })
+        .collect()
+}
+
+/// The table with the values of sensitive columns hidden, for logging.
+fn redact_table(
+    datatable: &Table,
+    fieldspecs: &HashMap<&ProtoDataFieldId, &FieldSpec>,
+) -> Table {
+    let sensitive: HashSet<&str> = fieldspecs
+        .values()
+        .filter(|df| df.sensitive)
+        .map(|df| df.column_name.as_str())
+        .collect();
+    datatable
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|(col, val)| match sensitive.contains(col.as_str()) {
+                    true => (col.clone(), REDACTED.to_string()),
+                    false => (col.clone(), val.clone()),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Add the rows of the per-database tables to the instance's tables.
+fn merge_tables(
+    data: &mut HashMap<ProtoDataTableId, DataTable>,
+    db_data: HashMap<ProtoDataTableId, DataTable>,
+) {
+    for (dt_id, dt_res) in db_data {
+        match data.entry(dt_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(dt_res);
+            }
+            Entry::Occupied(entry) => {
+                let (dt_id, mut datatable) = entry.remove_entry();
+                datatable = match (datatable, dt_res) {
+                    (Err(e), _) => Err(e),
+                    (_, Err(e)) => Err(e),
+                    (Ok(mut table1), Ok(table2)) => {
+                        table1.extend(table2);
+                        Ok(table1)
+                    }
+                };
+                data.insert(dt_id, datatable);
+            }
+        };
+    }
+}
+
 #[async_trait::async_trait]
 impl LocalPlugin for Plugin {
     type Error = Error;
@@ -380,6 +653,14 @@ impl LocalPlugin for Plugin {
     const PROTOCOL: &'static str = "SQL";
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
+    async fn probe(
+        &self,
+        input: &Input,
+        config: &Config,
+    ) -> Option<Result<()>> {
+        Some(self.probe_connect(input, config).await)
+    }
+
     fn show_queries(
         &self,
         input: &Input,
@@ -456,6 +737,7 @@ impl LocalPlugin for Plugin {
                     DataFieldSpec {
                         name: df.column_name.clone(),
                         input_type: df.get_type()?,
+                        sensitive: df.sensitive,
                     },
                 ))
             })
@@ -471,8 +753,8 @@ impl LocalPlugin for Plugin {
         let config = Arc::new(config.clone());
         debug!("config: {config:#?}");
         debug!(
-            "loading wmi counters: {}",
-            self.cache_dir.join("sql_counters.json").display()
+            "loading sql counters: {}",
+            self.counter_file(&config).display()
         );
 
         let datatables: HashMap<
@@ -511,7 +793,7 @@ impl LocalPlugin for Plugin {
             .next()
             .and_then(|dt| dt.0.plugin)
             .unwrap_or_default()
-            .get_plugin(self)
+            .get_plugin(self, &config)
             .await?;
 
         // general queries: do not care about the connected database. will be executed right away
@@ -531,10 +813,20 @@ impl LocalPlugin for Plugin {
                 .next()
                 .map(|dt| dt.0.database_query.as_ref().unwrap().clone()),
         );
-        let connection_strings = config
-            .clone()
-            .generic_connectionstring(sql_plugin.clone(), &self.key_vault)
-            .await?;
+        let connection_strings = match config.driver() {
+            Driver::Odbc(_) => {
+                config
+                    .clone()
+                    .generic_connectionstring(
+                        sql_plugin.clone(),
+                        &self.key_vault,
+                    )
+                    .await?
+            }
+            Driver::Postgres => {
+                postgres::connection_strings(&config, &self.key_vault).await?
+            }
+        };
 
         info!(
             "{} queries ({} generic, {} database) to be executed on {} instances using the {} plugin",
@@ -543,6 +835,9 @@ impl LocalPlugin for Plugin {
             connection_strings.len(), sql_plugin
         );
 
+        /* Scoped to this run: the idle connections are closed when
+         * the last request releases the pool. */
+        let pool = Arc::new(ConnectionPool::new(config.max_pool_size()));
         let handles = connection_strings
             .into_iter()
             .map(|(instance, connection_string)| {
@@ -554,8 +849,19 @@ impl LocalPlugin for Plugin {
                     sql_plugin: sql_plugin.clone(),
                     instance,
                     connection_string,
+                    pool: pool.clone(),
+                    accept_invalid_certs: config
+                        .disable_certificate_verification
+                        .unwrap_or(false),
                 };
-                tokio::task::spawn_blocking(move || request.query_instance())
+                match config.driver() {
+                    Driver::Odbc(_) => tokio::task::spawn_blocking(move || {
+                        request.query_instance()
+                    }),
+                    Driver::Postgres => {
+                        tokio::spawn(request.pg_query_instance())
+                    }
+                }
             })
             .collect::<Vec<_>>();
 
@@ -0,0 +1,142 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+use log::debug;
+
+use crate::plugin::SConnection;
+
+/// A connection that can be checked before it is reused.
+pub(crate) trait Pooled {
+    fn is_alive(&self) -> bool;
+}
+
+impl Pooled for SConnection<'static> {
+    fn is_alive(&self) -> bool {
+        match self.is_dead() {
+            Ok(dead) => !dead,
+            Err(e) => {
+                debug!("pooled connection check failed: {e}");
+                false
+            }
+        }
+    }
+}
+
+/// Idle connections of a host, keyed on the connection string, so
+/// that the tables of a database share a logon. The pool lives for
+/// one run of the plugin: dropping it closes the idle connections,
+/// so no logons are left open on the monitored server between
+/// polls.
+pub(crate) struct ConnectionPool<C = SConnection<'static>> {
+    max_size: usize,
+    idle: Mutex<HashMap<String, Vec<C>>>,
+}
+
+impl<C: Pooled> ConnectionPool<C> {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a live idle connection for the connection string, if
+    /// any. Dead connections are discarded.
+    pub fn take(&self, connection_string: &str) -> Option<C> {
+        let mut idle = self.idle.lock().unwrap();
+        let conns = idle.get_mut(connection_string)?;
+        while let Some(conn) = conns.pop() {
+            match conn.is_alive() {
+                true => return Some(conn),
+                false => debug!("discarding dead pooled connection"),
+            }
+        }
+        None
+    }
+
+    /// Return a connection for reuse. It is closed instead if the
+    /// pool is full.
+    pub fn put(&self, connection_string: String, conn: C) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.values().map(Vec::len).sum::<usize>() < self.max_size {
+            idle.entry(connection_string).or_default().push(conn);
+        }
+    }
+}
+
+impl<C> fmt::Debug for ConnectionPool<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{ConnectionPool, Pooled};
+
+    #[derive(PartialEq, Eq, Debug)]
+    struct Conn(u32, bool);
+
+    impl Pooled for Conn {
+        fn is_alive(&self) -> bool {
+            self.1
+        }
+    }
+
+    #[test]
+    fn reuse() {
+        let pool = ConnectionPool::new(2);
+        pool.put("db1".to_string(), Conn(1, true));
+        assert_eq!(pool.take("db2"), None);
+        assert_eq!(pool.take("db1"), Some(Conn(1, true)));
+        assert_eq!(pool.take("db1"), None);
+    }
+
+    #[test]
+    fn discard_dead() {
+        let pool = ConnectionPool::new(2);
+        pool.put("db1".to_string(), Conn(1, true));
+        pool.put("db1".to_string(), Conn(2, false));
+        assert_eq!(pool.take("db1"), Some(Conn(1, true)));
+    }
+
+    #[test]
+    fn close_on_drop() {
+        struct Counted(Arc<AtomicUsize>);
+        impl Pooled for Counted {
+            fn is_alive(&self) -> bool {
+                true
+            }
+        }
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let closed = Arc::new(AtomicUsize::new(0));
+        let pool = ConnectionPool::new(2);
+        pool.put("db1".to_string(), Counted(closed.clone()));
+        pool.put("db2".to_string(), Counted(closed.clone()));
+        assert_eq!(closed.load(Ordering::SeqCst), 0);
+        drop(pool);
+        assert_eq!(closed.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn max_size() {
+        let pool = ConnectionPool::new(1);
+        pool.put("db1".to_string(), Conn(1, true));
+        pool.put("db2".to_string(), Conn(2, true));
+        assert_eq!(pool.take("db2"), None);
+        assert_eq!(pool.take("db1"), Some(Conn(1, true)));
+    }
+}
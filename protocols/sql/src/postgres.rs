@@ -0,0 +1,227 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::{collections::HashMap, fmt::Write};
+
+use log::{debug, trace, warn};
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::{types::Type, Client, SimpleQueryMessage};
+
+use agent_utils::KeyVault;
+
+use crate::{
+    config::{Config, InstanceType, SslMode},
+    error::{DTEResult, DTError, Error, Result},
+    plugin::Table,
+};
+
+const DEFAULT_PORT: u16 = 5432;
+
+/// Build a libpq-style connection string for every configured
+/// instance. Instances are selected by port.
+pub(crate) async fn connection_strings(
+    config: &Config,
+    kvault: &KeyVault,
+) -> Result<HashMap<InstanceType, String>> {
+    if let Some(cs) = config.connection_string.as_ref() {
+        return Ok(config
+            .instances
+            .iter()
+            .map(|inst| (inst.clone(), cs.clone()))
+            .collect());
+    }
+
+    let (username, password) = config.credentials(kvault).await?;
+    config
+        .instances
+        .iter()
+        .map(|inst| {
+            let port = match inst {
+                InstanceType::Port(port) => *port,
+                InstanceType::Default => DEFAULT_PORT,
+                InstanceType::String(_) => {
+                    return Err(Error::InvalidInstance(
+                        inst.clone(),
+                        "PostgreSQL",
+                    ))
+                }
+            };
+
+            let mut cs = String::new();
+            add_arg(&mut cs, "host", &config.hostname)?;
+            add_arg(&mut cs, "port", &port.to_string())?;
+            if let Some(username) = &username {
+                add_arg(&mut cs, "user", username)?;
+            }
+            if let Some(password) = &password {
+                add_arg(&mut cs, "password", password)?;
+            }
+            if let Some(database) = &config.database {
+                add_arg(&mut cs, "dbname", database)?;
+            }
+            if let Some(timeout) = config.timeout {
+                add_arg(&mut cs, "connect_timeout", &timeout.to_string())?;
+            }
+            if let Some(ssl) = &config.ssl {
+                add_arg(&mut cs, "sslmode", sslmode(ssl))?;
+            }
+            Ok((inst.clone(), cs))
+        })
+        .collect()
+}
+
+/// tokio-postgres only supports "disable", "prefer" and "require".
+/// "allow" (try without TLS first) is mapped to "prefer": both accept
+/// either kind of connection, only the order of attempts differs.
+fn sslmode(ssl: &SslMode) -> &'static str {
+    match ssl {
+        SslMode::Require => "require",
+        SslMode::Prefer | SslMode::Allow => "prefer",
+        SslMode::Disable => "disable",
+    }
+}
+
+fn add_arg(cs: &mut String, key: &str, value: &str) -> Result<()> {
+    if !cs.is_empty() {
+        cs.push(' ');
+    }
+    write!(
+        cs,
+        "{key}='{}'",
+        value.replace('\\', "\\\\").replace('\'', "\\'")
+    )?;
+    Ok(())
+}
+
+/// Connect to the server, optionally overriding the database.
+pub(crate) async fn connect(
+    instance: &InstanceType,
+    connection_string: &str,
+    database: Option<&str>,
+    accept_invalid_certs: bool,
+) -> Result<Client> {
+    let mut config: tokio_postgres::Config = connection_string
+        .parse()
+        .map_err(|e| Error::PgConnection(instance.clone(), e))?;
+    if let Some(database) = database {
+        config.dbname(database);
+    }
+
+    let tls = TlsConnector::builder()
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .map_err(Error::Tls)?;
+    let (client, connection) = config
+        .connect(MakeTlsConnector::new(tls))
+        .await
+        .map_err(|e| Error::PgConnection(instance.clone(), e))?;
+
+    let instance = instance.clone();
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            warn!("[{instance}] postgres connection failed: {e}");
+        }
+    });
+
+    Ok(client)
+}
+
+/// Execute a query, returning the values as ODBC would: in their
+/// textual representation, with NULL as an empty string.
+pub(crate) async fn query(
+    instance: &InstanceType,
+    client: &Client,
+    query: &str,
+) -> DTEResult<Table> {
+    debug!("[{instance}] executing query: {query}");
+    let statement = client.prepare(query).await.map_err(DTError::Postgres)?;
+    let columns = statement
+        .columns()
+        .iter()
+        .map(|col| (col.name().to_string(), col.type_().clone()))
+        .collect::<Vec<_>>();
+    trace!("[{instance}] received columns: {columns:?}");
+
+    let table = client
+        .simple_query(query)
+        .await
+        .map_err(DTError::Postgres)?
+        .into_iter()
+        .filter_map(|msg| match msg {
+            SimpleQueryMessage::Row(row) => Some(row),
+            _ => None,
+        })
+        .map(|row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, (name, typ))| {
+                    (name.clone(), odbc_text(typ, row.get(i)))
+                })
+                .collect()
+        })
+        .collect::<Table>();
+
+    trace!("[{instance}] recieved {} rows", table.len());
+    Ok(table)
+}
+
+/* Numeric and timestamp values are returned in the server's text
+ * format by both drivers. Booleans and bytea differ: psqlODBC
+ * returns "1"/"0" and plain hex digits. */
+fn odbc_text(typ: &Type, value: Option<&str>) -> String {
+    match (typ, value) {
+        (_, None) => String::new(),
+        (&Type::BOOL, Some("t")) => "1".to_string(),
+        (&Type::BOOL, Some("f")) => "0".to_string(),
+        (&Type::BYTEA, Some(value)) => {
+            value.strip_prefix("\\x").unwrap_or(value).to_string()
+        }
+        (_, Some(value)) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_postgres::types::Type;
+
+    use super::{odbc_text, sslmode};
+    use crate::config::SslMode;
+
+    #[test]
+    fn booleans_as_odbc() {
+        assert_eq!(odbc_text(&Type::BOOL, Some("t")), "1");
+        assert_eq!(odbc_text(&Type::BOOL, Some("f")), "0");
+        assert_eq!(odbc_text(&Type::BOOL, None), "");
+    }
+
+    #[test]
+    fn bytea_as_odbc() {
+        assert_eq!(odbc_text(&Type::BYTEA, Some("\\xdeadbeef")), "deadbeef");
+        assert_eq!(odbc_text(&Type::BYTEA, Some("")), "");
+        assert_eq!(odbc_text(&Type::BYTEA, None), "");
+    }
+
+    #[test]
+    fn other_types_unchanged() {
+        assert_eq!(odbc_text(&Type::INT4, Some("42")), "42");
+        assert_eq!(odbc_text(&Type::TEXT, Some("t")), "t");
+        assert_eq!(odbc_text(&Type::TEXT, None), "");
+    }
+
+    #[test]
+    fn supported_sslmodes() {
+        for (ssl, mode) in [
+            (SslMode::Require, "require"),
+            (SslMode::Prefer, "prefer"),
+            (SslMode::Allow, "prefer"),
+            (SslMode::Disable, "disable"),
+        ] {
+            assert_eq!(sslmode(&ssl), mode);
+            let cs = format!("host=localhost sslmode={mode}");
+            assert!(cs.parse::<tokio_postgres::Config>().is_ok());
+        }
+    }
+}
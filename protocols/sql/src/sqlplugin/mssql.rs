@@ -30,8 +30,9 @@ pub struct Plugin(Arc<CounterDB>);
 impl Plugin {
     pub async fn from_protocol_plugin(
         prot_plugin: &crate::Plugin,
+        config: &Config,
     ) -> Result<Self> {
-        CounterDB::new(prot_plugin.cache_dir.join("sql_counters.json"))
+        CounterDB::new(prot_plugin.counter_file(config))
             .await
             .map(Arc::new)
             .map(Self)
@@ -26,11 +26,15 @@ pub struct Plugin(CounterDb);
 impl Plugin {
     pub async fn from_protocol_plugin(
         prot_plugin: &crate::Plugin,
+        config: &Config,
     ) -> Result<Self> {
-        CounterDb::load(prot_plugin.cache_dir.join("sql_counters.json"))
-            .await
-            .map(Self)
-            .map_err(Error::CounterDbCreation)
+        CounterDb::load_with_ttl(
+            prot_plugin.counter_file(config),
+            config.counter_ttl(),
+        )
+        .await
+        .map(Self)
+        .map_err(Error::CounterDbCreation)
     }
 }
 
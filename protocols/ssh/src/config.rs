@@ -3,9 +3,11 @@
  ******************************************************************************/
 
 use std::{
+    collections::BTreeMap,
     net::IpAddr,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use agent_utils::{vault::Creds, KeyVault};
@@ -43,6 +45,24 @@ pub struct Options {
     pub allow_sudo: bool,
     #[serde(default = "default_timeout")]
     pub timeout: u32,
+    /// Commands of streamed tables producing more output than this
+    /// are aborted. The parser still holds the complete output in
+    /// memory, so this also bounds the parser's memory use.
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: u64,
+    /// Commands (by command name) whose output is cached in the
+    /// cache directory and reused for the given number of seconds.
+    #[serde(default)]
+    pub cache: BTreeMap<String, u64>,
+}
+
+impl Options {
+    /// How long the command's output may be reused, if it is cached.
+    pub fn cache_ttl(&self, command_name: &str) -> Option<Duration> {
+        self.cache
+            .get(command_name)
+            .map(|s| Duration::from_secs(*s))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +79,10 @@ fn default_timeout() -> u32 {
     10
 }
 
+fn default_max_output_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
 fn default_sudo() -> bool {
     false
 }
@@ -84,4 +84,6 @@ pub enum DTWarning {
     SudoNotAllowed(),
     #[error("Failed to set env variable {0}: {1}")]
     SetEnvVariable(&'static str, #[source] async_ssh2_lite::Error),
+    #[error("Output of command {0} exceeds {1} bytes; aborted")]
+    OutputTooLarge(String, u64),
 }
@@ -0,0 +1,50 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+
+use agent_utils::TryAppend;
+use etc_base::{ProtoDataFieldId, ProtoDataTableId};
+use serde::{Deserialize, Serialize};
+use sshparser_lib::FieldSpec;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Input {
+    pub data_tables: HashMap<ProtoDataTableId, TableSpec>,
+    pub data_fields: HashMap<ProtoDataFieldId, FieldSpec>,
+    pub data_table_fields: HashMap<ProtoDataTableId, HashSet<ProtoDataFieldId>>,
+}
+
+impl TryAppend for Input {
+    fn try_append(&mut self, other: Self) -> agent_utils::Result<()> {
+        self.data_tables.try_append(other.data_tables)?;
+        self.data_fields.try_append(other.data_fields)?;
+        self.data_table_fields.try_append(other.data_table_fields)?;
+        Ok(())
+    }
+}
+
+/// The parser's table spec, with agent-side options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TableSpec {
+    #[serde(flatten)]
+    pub command: sshparser_lib::TableSpec,
+    /// Read the command's output line by line as it arrives, and
+    /// abort when it exceeds the configured maximum size, instead of
+    /// buffering it first. This only bounds memory use in the agent:
+    /// the parser still receives the complete output in a single
+    /// parse request.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl Deref for TableSpec {
+    type Target = sshparser_lib::TableSpec;
+    fn deref(&self) -> &Self::Target {
+        &self.command
+    }
+}
@@ -4,8 +4,11 @@
 
 mod config;
 mod errors;
+mod input;
+mod output_cache;
 mod plugin;
 
 pub use config::Config;
 pub use errors::{DTError, DTResult, DTWResult, DTWarning, Error, Result};
+pub use input::{Input, TableSpec};
 pub use plugin::Plugin;
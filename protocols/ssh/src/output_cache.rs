@@ -0,0 +1,202 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Command output cached per host and command in the cache directory,
+/// for commands whose output changes rarely.
+pub struct OutputCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// The command line that produced the output. A changed command
+    /// line invalidates the entry.
+    command_line: String,
+    timestamp: SystemTime,
+    output: String,
+}
+
+impl OutputCache {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn path(&self, host: &str, command_name: &str) -> PathBuf {
+        const ILLEGAL_CHARS: [char; 1] = ['/'];
+        self.dir.join(format!(
+            "ssh_output_{}_{}.json",
+            host.replace(ILLEGAL_CHARS, "_"),
+            command_name.replace(ILLEGAL_CHARS, "_")
+        ))
+    }
+
+    /// The cached output, if it was produced by the same command line
+    /// less than `ttl` ago.
+    pub fn get(
+        &self,
+        host: &str,
+        command_name: &str,
+        command_line: &str,
+        ttl: Duration,
+        now: SystemTime,
+    ) -> Option<String> {
+        let path = self.path(host, command_name);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("Could not read {}: {e}", path.display());
+                return None;
+            }
+        };
+        let entry = match serde_json::from_slice::<CacheEntry>(&data) {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::warn!("Ignoring invalid {}: {e}", path.display());
+                return None;
+            }
+        };
+        if entry.command_line != command_line {
+            log::debug!("Command {command_name} changed; ignoring cache");
+            return None;
+        }
+        match now.duration_since(entry.timestamp) {
+            Ok(age) if age < ttl => Some(entry.output),
+            _ => None,
+        }
+    }
+
+    /// Store command output. Failures are logged, since caching is
+    /// only an optimization.
+    pub fn put(
+        &self,
+        host: &str,
+        command_name: &str,
+        command_line: &str,
+        output: &str,
+        now: SystemTime,
+    ) {
+        let path = self.path(host, command_name);
+        let entry = CacheEntry {
+            command_line: command_line.to_string(),
+            timestamp: now,
+            output: output.to_string(),
+        };
+        let res = serde_json::to_vec(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|data| {
+                std::fs::create_dir_all(&self.dir)?;
+                /* Write to a temporary file first, so concurrent runs
+                 * never read a partial entry. The name is unique, so
+                 * concurrent writers do not clobber each other's
+                 * temporary file; the last rename wins. */
+                let tmp = path.with_extension(format!(
+                    "json.{}.{}.tmp",
+                    std::process::id(),
+                    TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+                ));
+                std::fs::write(&tmp, data)?;
+                std::fs::rename(&tmp, &path)
+            });
+        if let Err(e) = res {
+            log::warn!("Could not write {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    use super::OutputCache;
+
+    const TTL: Duration = Duration::from_secs(3600);
+
+    fn cache_dir(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ssh_output_cache_{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn hit() {
+        let cache = OutputCache::new(&cache_dir("hit"));
+        let now = SystemTime::now();
+        cache.put("host1", "dmidecode", "sudo dmidecode", "output", now);
+        assert_eq!(
+            cache.get("host1", "dmidecode", "sudo dmidecode", TTL, now),
+            Some(String::from("output"))
+        );
+        assert_eq!(
+            cache.get("host2", "dmidecode", "sudo dmidecode", TTL, now),
+            None
+        );
+    }
+
+    #[test]
+    fn expiry() {
+        let cache = OutputCache::new(&cache_dir("expiry"));
+        let now = SystemTime::now();
+        cache.put("host1", "lsblk", "lsblk -J", "output", now);
+        assert_eq!(
+            cache.get(
+                "host1",
+                "lsblk",
+                "lsblk -J",
+                TTL,
+                now + TTL - Duration::from_secs(1)
+            ),
+            Some(String::from("output"))
+        );
+        assert_eq!(
+            cache.get("host1", "lsblk", "lsblk -J", TTL, now + TTL),
+            None
+        );
+    }
+
+    #[test]
+    fn concurrent_puts() {
+        let dir = cache_dir("concurrent_puts");
+        let now = SystemTime::now();
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let cache = OutputCache::new(&dir);
+                s.spawn(move || {
+                    cache.put("host1", "lsblk", "lsblk -J", &i.to_string(), now)
+                });
+            }
+        });
+        let output = OutputCache::new(&dir)
+            .get("host1", "lsblk", "lsblk -J", TTL, now)
+            .unwrap();
+        assert!((0..8).any(|i| output == i.to_string()));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn command_change() {
+        let cache = OutputCache::new(&cache_dir("command_change"));
+        let now = SystemTime::now();
+        cache.put("host1", "lsblk", "lsblk -J", "output", now);
+        assert_eq!(cache.get("host1", "lsblk", "lsblk -JO", TTL, now), None);
+        cache.put("host1", "lsblk", "lsblk -JO", "new output", now);
+        assert_eq!(
+            cache.get("host1", "lsblk", "lsblk -JO", TTL, now),
+            Some(String::from("new output"))
+        );
+    }
+}
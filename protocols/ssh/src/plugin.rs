@@ -6,12 +6,21 @@
 
 use agent_utils::{KeyVault, TryGet, TryGetFrom};
 use async_ssh2_lite::{
-    tokio::{self, io::AsyncReadExt},
-    AsyncSession, TokioTcpStream,
+    tokio::{
+        self,
+        io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    },
+    AsyncChannel, AsyncSession, TokioTcpStream,
 };
-use futures::{stream, StreamExt};
+use futures::{stream, Future, StreamExt};
 use log::info;
-use std::{collections::HashMap, path::PathBuf, process::Stdio, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tap::Pipe;
 
 use async_trait::async_trait;
@@ -20,14 +29,17 @@ use etc_base::{
     ProtoQueryMap, ProtoRow, Warning,
 };
 use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin};
-use tokio::process::Command;
+use tokio::process::{Child, ChildStdin, Command};
 
-use crate::{Config, DTError, DTWarning, Error, Result};
+use crate::output_cache::OutputCache;
+use crate::{
+    Config, DTError, DTResult, DTWarning, Error, Input, Result, TableSpec,
+};
 
 type TableData = AnnotatedResult<Vec<ProtoRow>, DTWarning, DTError>;
 pub type DataMap = HashMap<ProtoDataTableId, TableData>;
 
-use sshparser_lib::{FieldSpec, Input, ParseRequest, TableSpec};
+use sshparser_lib::{FieldSpec, ParseRequest};
 use std::fmt::Write;
 
 use tokio::io::AsyncWriteExt;
@@ -35,6 +47,7 @@ use tokio::io::AsyncWriteExt;
 pub struct Plugin {
     key_vault: KeyVault,
     cache_dir: PathBuf,
+    output_cache: OutputCache,
     parser_dir: PathBuf,
     log_level: u8,
 }
@@ -48,6 +61,7 @@ impl Plugin {
     ) -> Self {
         Self {
             key_vault,
+            output_cache: OutputCache::new(&cache_dir),
             cache_dir,
             parser_dir,
             log_level,
@@ -87,8 +101,222 @@ impl Plugin {
         table_spec: &TableSpec,
         session: Arc<AsyncSession<TokioTcpStream>>,
         field_specs: HashMap<ProtoDataFieldId, FieldSpec>,
+        stream_limit: Option<u64>,
+        cache: Option<(&str, Duration)>,
+    ) -> TableData {
+        if let Some((host, ttl)) = cache {
+            return self
+                .get_data_cached(table_spec, session, field_specs, host, ttl)
+                .await;
+        }
+        if let Some(max_bytes) = stream_limit {
+            return self
+                .get_data_streamed(table_spec, session, field_specs, max_bytes)
+                .await;
+        }
+        let (command_output, warnings) =
+            self.exec_command(table_spec, session).await?;
+        self.parse_output(table_spec, command_output, field_specs, warnings)
+            .await
+    }
+
+    /// Reuse the command output from a previous run on the same host
+    /// if it is younger than `ttl`, otherwise execute the command and
+    /// store its output.
+    async fn get_data_cached(
+        &self,
+        table_spec: &TableSpec,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+        field_specs: HashMap<ProtoDataFieldId, FieldSpec>,
+        host: &str,
+        ttl: Duration,
     ) -> TableData {
+        let command_name = &table_spec.command_name;
+        let command_line = &table_spec.command_line;
+        let now = SystemTime::now();
+        let (command_output, warnings) = match self.output_cache.get(
+            host,
+            command_name,
+            command_line,
+            ttl,
+            now,
+        ) {
+            Some(output) => {
+                log::info!("Using cached output for command {command_name}");
+                (output, Vec::new())
+            }
+            None => {
+                let (output, warnings) =
+                    self.exec_command(table_spec, session).await?;
+                self.output_cache.put(
+                    host,
+                    command_name,
+                    command_line,
+                    &output,
+                    now,
+                );
+                (output, warnings)
+            }
+        };
+        self.parse_output(table_spec, command_output, field_specs, warnings)
+            .await
+    }
+
+    /// Copy the command output into the parse request line by line as
+    /// it arrives, instead of buffering it in the agent. The command is
+    /// aborted with a warning when its output exceeds `max_bytes`.
+    /// The parser still reads the complete request before parsing.
+    async fn get_data_streamed(
+        &self,
+        table_spec: &TableSpec,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+        field_specs: HashMap<ProtoDataFieldId, FieldSpec>,
+        max_bytes: u64,
+    ) -> TableData {
+        let subprocess_err =
+            |e| DTError::SubProcess(table_spec.parser_bin.clone(), e);
+
+        /* The output is written into the command_output string of the
+         * serialized parse request. */
+        let request = serde_json::to_string(&ParseRequest {
+            parser_name: table_spec.parser_name.clone(),
+            command_output: String::from(OUTPUT_PLACEHOLDER),
+            field_specs,
+            counter_db: self.counter_db(table_spec),
+            log_level: self.log_level,
+        })
+        .map_err(DTError::Json)?;
+        let placeholder =
+            serde_json::to_string(OUTPUT_PLACEHOLDER).map_err(DTError::Json)?;
+        let (prefix, suffix) =
+            request.split_once(placeholder.as_str()).ok_or_else(|| {
+                DTError::Parser(String::from("failed to serialize request"))
+            })?;
+
+        let mut child = self.spawn_parser(table_spec)?;
+        let mut stdin = child.stdin.take().unwrap();
+        stdin
+            .write_all(format!("{prefix}\"").as_bytes())
+            .await
+            .map_err(subprocess_err)?;
+
+        let mut warnings = Vec::new();
+        let complete = self
+            .stream_command(
+                table_spec,
+                session,
+                &mut stdin,
+                max_bytes,
+                &mut warnings,
+            )
+            .await;
+        match complete {
+            Ok(true) => {}
+            Ok(false) | Err(_) => {
+                if let Err(e) = child.kill().await {
+                    log::warn!("Could not kill parser: {e}");
+                }
+                return complete.map(|_| Annotated {
+                    value: Vec::new(),
+                    warnings,
+                });
+            }
+        }
+
+        stdin
+            .write_all(format!("\"{suffix}").as_bytes())
+            .await
+            .map_err(subprocess_err)?;
+        drop(stdin);
+        self.parser_result(table_spec, child, warnings).await
+    }
+
+    async fn stream_command(
+        &self,
+        table_spec: &TableSpec,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+        parser: &mut ChildStdin,
+        max_bytes: u64,
+        warnings: &mut Vec<Warning<DTWarning>>,
+    ) -> DTResult<bool> {
+        let mut ssh_channel =
+            self.start_command(table_spec, session, warnings).await?;
+
+        let mut total = 0;
+        let mut line = String::new();
+        let mut stdout = BufReader::new(&mut ssh_channel);
+        loop {
+            line.clear();
+            /* Limit the read, in case of a very long line. */
+            let n = (&mut stdout)
+                .take(max_bytes - total + 1)
+                .read_line(&mut line)
+                .await
+                .map_err(|e| {
+                    DTError::ReadChannel(e, table_spec.command_line.clone())
+                })?;
+            if n == 0 {
+                break;
+            }
+            total += n as u64;
+            if total > max_bytes {
+                let warn = Warning::warn(DTWarning::OutputTooLarge(
+                    table_spec.command_name.clone(),
+                    max_bytes,
+                ));
+                warn.log();
+                warnings.push(warn);
+                drop(stdout);
+                if let Err(e) = ssh_channel.close().await {
+                    log::warn!("Could not close channel: {e}");
+                }
+                return Ok(false);
+            }
+            let escaped =
+                serde_json::to_string(&line).map_err(DTError::Json)?;
+            parser
+                .write_all(escaped[1..escaped.len() - 1].as_bytes())
+                .await
+                .map_err(|e| {
+                    DTError::SubProcess(table_spec.parser_bin.clone(), e)
+                })?;
+        }
+        drop(stdout);
+
+        log::trace!("streamed {total} bytes from command");
+        self.finish_command(table_spec, ssh_channel).await?;
+        Ok(true)
+    }
+
+    async fn exec_command(
+        &self,
+        table_spec: &TableSpec,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+    ) -> DTResult<(String, Vec<Warning<DTWarning>>)> {
         let mut warnings = Vec::new();
+        let mut ssh_channel = self
+            .start_command(table_spec, session, &mut warnings)
+            .await?;
+
+        let mut command_output = String::new();
+        ssh_channel
+            .read_to_string(&mut command_output)
+            .await
+            .map_err(|e: std::io::Error| {
+                DTError::ReadChannel(e, table_spec.command_line.clone())
+            })?;
+        log::trace!("stdout from command: {}", &command_output);
+
+        self.finish_command(table_spec, ssh_channel).await?;
+        Ok((command_output, warnings))
+    }
+
+    async fn start_command(
+        &self,
+        table_spec: &TableSpec,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+        warnings: &mut Vec<Warning<DTWarning>>,
+    ) -> DTResult<AsyncChannel<TokioTcpStream>> {
         // Create SSH channel per request
         let mut ssh_channel = session
             .channel_session()
@@ -109,15 +337,15 @@ impl Plugin {
             .exec(command_line)
             .await
             .map_err(|e| DTError::Command(e, command_line.clone()))?;
+        Ok(ssh_channel)
+    }
 
-        let mut command_output = String::new();
-        ssh_channel
-            .read_to_string(&mut command_output)
-            .await
-            .map_err(|e: std::io::Error| {
-                DTError::ReadChannel(e, table_spec.command_line.clone())
-            })?;
-
+    /// Close the channel after reading stdout, and check the exit status.
+    async fn finish_command(
+        &self,
+        table_spec: &TableSpec,
+        mut ssh_channel: AsyncChannel<TokioTcpStream>,
+    ) -> DTResult<()> {
         let mut stderr = String::new();
         ssh_channel
             .stderr()
@@ -126,7 +354,6 @@ impl Plugin {
             .map_err(|e: std::io::Error| {
                 DTError::ReadChannel(e, table_spec.command_line.clone())
             })?;
-        log::trace!("stdout from command: {}", &command_output);
         log::trace!("stderr from command: {}", &stderr);
         if let Err(e) = ssh_channel.close().await {
             log::warn!("Could not close channel: {e}");
@@ -138,8 +365,16 @@ impl Plugin {
         if exit_status != 0 {
             return Err(DTError::CommandFailed(exit_status, stderr));
         }
-        drop(ssh_channel);
+        Ok(())
+    }
 
+    async fn parse_output(
+        &self,
+        table_spec: &TableSpec,
+        command_output: String,
+        field_specs: HashMap<ProtoDataFieldId, FieldSpec>,
+        warnings: Vec<Warning<DTWarning>>,
+    ) -> TableData {
         // Prepare data to pass to the parser
         let par = ParseRequest {
             parser_name: table_spec.parser_name.clone(),
@@ -150,15 +385,7 @@ impl Plugin {
         };
 
         // Create the subprocess for the parser
-        let mut child =
-            Command::new(self.parser_dir.join(&table_spec.parser_bin))
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .map_err(|e| {
-                    DTError::SubProcess(table_spec.parser_bin.clone(), e)
-                })?;
+        let mut child = self.spawn_parser(table_spec)?;
 
         // Pass commandline output to the parser
         let mut stdin = child.stdin.take().unwrap();
@@ -173,6 +400,24 @@ impl Plugin {
                 DTError::SubProcess(table_spec.parser_bin.clone(), e)
             })?;
         drop(stdin);
+        self.parser_result(table_spec, child, warnings).await
+    }
+
+    fn spawn_parser(&self, table_spec: &TableSpec) -> DTResult<Child> {
+        Command::new(self.parser_dir.join(&table_spec.parser_bin))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| DTError::SubProcess(table_spec.parser_bin.clone(), e))
+    }
+
+    async fn parser_result(
+        &self,
+        table_spec: &TableSpec,
+        child: Child,
+        warnings: Vec<Warning<DTWarning>>,
+    ) -> TableData {
         let output = child.wait_with_output().await.map_err(|e| {
             DTError::SubProcess(table_spec.parser_bin.clone(), e)
         })?;
@@ -196,6 +441,9 @@ impl Plugin {
     }
 }
 
+/// Stands in for the command output in a serialized parse request.
+const OUTPUT_PLACEHOLDER: &str = "\u{0}command_output\u{0}";
+
 #[async_trait]
 impl LocalPlugin for Plugin {
     type Error = Error;
@@ -275,6 +523,7 @@ impl LocalPlugin for Plugin {
                     DataFieldSpec {
                         name: metric_spec.parameter_name.to_string(),
                         input_type: metric_spec.get_type(),
+                        sensitive: false,
                     },
                 )
             })
@@ -282,6 +531,15 @@ impl LocalPlugin for Plugin {
             .pipe(Ok)
     }
 
+    async fn probe(
+        &self,
+        _input: &Input,
+        config: &Config,
+    ) -> Option<Result<()>> {
+        // Dropping the session closes the connection.
+        Some(config.get_session(&self.key_vault).await.map(|_session| ()))
+    }
+
     async fn run_queries(
         &self,
         input: &Input,
@@ -289,7 +547,35 @@ impl LocalPlugin for Plugin {
         query: &ProtoQueryMap,
     ) -> Result<DataMap> {
         let session = config.get_session(&self.key_vault).await?;
+        self.query_session(session, input, config, query).await
+    }
+
+    fn supports_collect_all(&self, _input: &Input) -> bool {
+        true
+    }
+
+    /// Connect and authenticate once, then run every table's command
+    /// over its own channel on the shared session.
+    async fn collect_all(
+        &self,
+        input: &Input,
+        config: &Config,
+        query: &ProtoQueryMap,
+    ) -> Result<DataMap> {
+        let session = config.get_session(&self.key_vault).await?;
+        self.query_session(session, input, config, query).await
+    }
+}
 
+impl Plugin {
+    /// Run the queried tables' commands on an authenticated session.
+    async fn query_session(
+        &self,
+        session: Arc<AsyncSession<TokioTcpStream>>,
+        input: &Input,
+        config: &Config,
+        query: &ProtoQueryMap,
+    ) -> Result<DataMap> {
         // Create empty vec to put our async requests in
         let mut requests = Vec::with_capacity(input.data_tables.len());
 
@@ -327,13 +613,20 @@ impl LocalPlugin for Plugin {
                 .collect::<Result<HashMap<ProtoDataFieldId, FieldSpec>>>()?;
 
             // Put a pair of table_id and get_data call for our table into the requests vector as an async block
-            requests.push(async {
-                (
-                    table_id.clone(),
-                    self.get_data(table_spec, session.clone(), field_specs)
-                        .await,
-                )
-            })
+            requests.push((
+                table_id.clone(),
+                self.get_data(
+                    table_spec,
+                    session.clone(),
+                    field_specs,
+                    table_spec
+                        .stream
+                        .then_some(config.options.max_output_bytes),
+                    config.options.cache_ttl(&table_spec.command_name).map(
+                        |ttl| (config.connectivity.hostname.as_str(), ttl),
+                    ),
+                ),
+            ))
         }
         log::trace!(
             "All data requests succesfully prepared, start executing..."
@@ -343,10 +636,9 @@ impl LocalPlugin for Plugin {
             "requesting data with max {} channels",
             config.connectivity.max_sessions
         );
-        let mut data = stream::iter(requests)
-            .buffer_unordered(config.connectivity.max_sessions as usize)
-            .collect::<HashMap<ProtoDataTableId, TableData>>()
-            .await;
+        let mut data =
+            run_tables(requests, config.connectivity.max_sessions as usize)
+                .await;
         log::trace!("Data requests executed");
         // add the sudo-related warnings to the data we created
         data.extend(sudo_warnings);
@@ -355,3 +647,162 @@ impl LocalPlugin for Plugin {
         Ok(data)
     }
 }
+
+/// Run the table requests over separate channels, at most
+/// `max_channels` at a time, keeping each result attributed to the
+/// table it was requested for.
+async fn run_tables<F>(
+    requests: Vec<(ProtoDataTableId, F)>,
+    max_channels: usize,
+) -> DataMap
+where
+    F: Future<Output = TableData>,
+{
+    stream::iter(
+        requests
+            .into_iter()
+            .map(|(table_id, fut)| async move { (table_id, fut.await) }),
+    )
+    .buffer_unordered(max_channels.max(1))
+    .collect()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use agent_utils::KeyVault;
+    use etc_base::{
+        Annotated, ProtoDataFieldId, ProtoDataTableId, ProtoQueryMap,
+    };
+    use protocol::LocalPlugin;
+    use tokio::net::TcpListener;
+    use value::Value;
+
+    use super::{run_tables, Plugin};
+    use crate::config::{
+        Config, Connectivity, Credential, CredentialType, Options,
+    };
+
+    /// Accept connections on a local port, counting and dropping
+    /// them, so the ssh handshake fails right after connecting.
+    async fn counting_listener() -> (u16, Arc<AtomicUsize>) {
+        let listener =
+            TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                drop(stream);
+            }
+        });
+        (port, connections)
+    }
+
+    fn local_config(port: u16) -> Config {
+        Config {
+            connectivity: Connectivity {
+                hostname: String::from("localhost"),
+                ipaddress: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                port,
+                max_sessions: 10,
+            },
+            credentials: Credential {
+                username: String::from("user"),
+                credential_type: Some(CredentialType::Password {
+                    password: String::from("secret"),
+                }),
+            },
+            options: Options {
+                timeout: 1,
+                ..Options::default()
+            },
+            jumphosts: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn collect_all_connects_once() {
+        let plugin = Plugin::new(
+            std::env::temp_dir(),
+            KeyVault::Identity,
+            std::env::temp_dir(),
+            0,
+        );
+        let input = Default::default();
+        let query: ProtoQueryMap = (0..3)
+            .map(|i| (ProtoDataTableId(format!("table_{i}")), HashSet::new()))
+            .collect();
+
+        let (port, connections) = counting_listener().await;
+        let config = local_config(port);
+        for (table_id, fields) in &query {
+            let table_query =
+                HashMap::from([(table_id.clone(), fields.clone())]);
+            assert!(plugin
+                .run_queries(&input, &config, &table_query)
+                .await
+                .is_err());
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+
+        let (port, connections) = counting_listener().await;
+        let config = local_config(port);
+        assert!(plugin.supports_collect_all(&input));
+        assert!(plugin.collect_all(&input, &config, &query).await.is_err());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn commands_run_concurrently_per_table() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+        let field = ProtoDataFieldId(String::from("output"));
+
+        let requests = (0..10)
+            .map(|i| {
+                let (running, max_running, field) =
+                    (&running, &max_running, &field);
+                (ProtoDataTableId(format!("table_{i}")), async move {
+                    let n = running.fetch_add(1, Ordering::SeqCst);
+                    max_running.fetch_max(n + 1, Ordering::SeqCst);
+                    /* Finish in reverse order of submission. */
+                    tokio::time::sleep(Duration::from_millis((10 - i) * 10))
+                        .await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    let output = format!("output of command {i}");
+                    Ok(Annotated {
+                        value: vec![[(
+                            field.clone(),
+                            Ok(Value::UnicodeString(output)),
+                        )]
+                        .into_iter()
+                        .collect()],
+                        warnings: Vec::new(),
+                    })
+                })
+            })
+            .collect();
+
+        let data = run_tables(requests, 3).await;
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(data.len(), 10);
+        for i in 0..10 {
+            let rows = data[&ProtoDataTableId(format!("table_{i}"))]
+                .as_ref()
+                .unwrap();
+            assert_eq!(
+                rows.value[0][&field],
+                Ok(Value::UnicodeString(format!("output of command {i}")))
+            );
+        }
+    }
+}
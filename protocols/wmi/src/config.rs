@@ -3,6 +3,7 @@
  ******************************************************************************/
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use powershell_protocol as ps;
 
@@ -13,6 +14,7 @@ use agent_utils::KeyVault;
 use crate::dcom;
 use crate::error::DTResult;
 use crate::error::WMIDTError;
+use crate::input::{AssociationSpec, TableSpec};
 use crate::Result;
 use crate::WMIError;
 
@@ -25,6 +27,10 @@ pub struct Config {
     pub wmi_method: Option<WmiMethod>,
     pub retries: Option<u8>,
     pub quircks: WmiQuircks,
+    /// Maximum age (in seconds) of a session before it is
+    /// re-established.
+    #[serde(default)]
+    pub session_ttl: Option<u64>,
 }
 
 impl Config {
@@ -47,6 +53,17 @@ impl Config {
     pub fn get_method(&self) -> WmiMethod {
         self.wmi_method.unwrap_or(WmiMethod::GetWmiObject)
     }
+
+    pub fn session_ttl(&self) -> Duration {
+        Duration::from_secs(self.session_ttl.unwrap_or(300))
+    }
+
+    /// Identifies the host and credentials sessions are established
+    /// with.
+    pub(crate) fn connection_key(&self) -> String {
+        serde_json::to_string(&(&self.powershell, &self.dcom))
+            .unwrap_or_default()
+    }
 }
 
 pub enum WmiSession {
@@ -89,6 +106,28 @@ impl WmiSession {
         }
     }
 
+    /// Association queries return all properties of the related
+    /// instances; WQL does not allow selecting properties here.
+    pub async fn get_associations(
+        &mut self,
+        association: &AssociationSpec,
+        namespace: &str,
+    ) -> DTResult<Vec<HashMap<String, String>>> {
+        match self {
+            Self::Powershell(_) => {
+                log::warn!(
+                    "association query {} not supported over PowerShell; \
+                     use the DCOM method for this host",
+                    association
+                );
+                Err(WMIDTError::AssociationUnsupported)
+            }
+            Self::Dcom(dcom) => {
+                dcom.exec_wql(namespace, &association.to_wql()).await
+            }
+        }
+    }
+
     pub async fn enumerate_ciminstance(
         &mut self,
         class: &str,
@@ -145,6 +184,32 @@ impl WmiMethod {
         }
         .map_err(|e| WMIDTError::Request(e.to_string()))
     }
+
+    /// Query the instances for a data table: either all instances of
+    /// its class, or the instances related to its association's object.
+    pub async fn exec_table_query(
+        &self,
+        session: &mut WmiSession,
+        table: &TableSpec,
+        properties: &[String],
+    ) -> DTResult<Vec<HashMap<String, String>>> {
+        match &table.association {
+            None => {
+                self.exec_query(
+                    session,
+                    &table.classname,
+                    properties,
+                    &table.namespace,
+                )
+                .await
+            }
+            Some(association) => session
+                .get_associations(association, &table.namespace)
+                .await
+                .map_err(|e| WMIDTError::Request(e.to_string())),
+        }
+        .map_err(|e| e.for_namespace(&table.namespace))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -190,8 +190,20 @@ impl DcomSession {
         attributes: &[String],
     ) -> DTResult<Vec<HashMap<String, String>>> {
         debug!("requesting class {class} with attributes: {attributes:?}");
+        self.exec_wql(
+            namespace,
+            &format!("select {} from {class}", attributes.join(",")),
+        )
+        .await
+    }
 
-        let output = self.execute_wmic(class, namespace, attributes).await?;
+    /// Execute a raw WQL query, e.g. an `ASSOCIATORS OF` query.
+    pub async fn exec_wql(
+        &mut self,
+        namespace: &str,
+        query: &str,
+    ) -> DTResult<Vec<HashMap<String, String>>> {
+        let output = self.execute_wmic(namespace, query).await?;
         if !output.status.success() {
             let stderr = String::from_utf8(output.stderr)
                 .map_err(WMIDTError::ParseUTF8)?;
@@ -206,9 +218,8 @@ impl DcomSession {
 
     async fn execute_wmic(
         &self,
-        class: &str,
         namespace: &str,
-        attributes: &[String],
+        query: &str,
     ) -> DTResult<Output> {
         let (rx, mut tx) =
             UnixStream::pair().map_err(WMIDTError::SocketCreation)?;
@@ -234,7 +245,7 @@ impl DcomSession {
             .arg("--delimiter")
             .arg(DECOM_DELIMITER)
             .arg(format!("//{}[sign]", self.address))
-            .arg(format!("select {} from {class}", attributes.join(",")))
+            .arg(query)
             .env("PASSWD_FD", rx_fd.to_string());
 
         let std_cmd = command.as_std();
@@ -68,12 +68,34 @@ pub enum WMIDTError {
     SpawnWmic(#[source] std::io::Error),
     #[error("output from wmic is not valid utf-8: {0}")]
     ParseUTF8(#[from] std::string::FromUtf8Error),
+    #[error("Association queries are only supported over DCOM")]
+    AssociationUnsupported,
+    #[error("WMI namespace {0} does not exist on this host")]
+    InvalidNamespace(String),
+}
+
+impl WMIDTError {
+    /// Replace the generic COM failure (WBEM_E_INVALID_NAMESPACE,
+    /// 0x8004100E) for queries against a non-existent namespace.
+    pub(crate) fn for_namespace(self, namespace: &str) -> Self {
+        let msg = self.to_string().to_lowercase();
+        if msg.contains("invalid_namespace")
+            || msg.contains("invalid namespace")
+            || msg.contains("0x8004100e")
+        {
+            Self::InvalidNamespace(namespace.to_string())
+        } else {
+            self
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug, Clone)]
 pub enum TypeError {
     #[error("Unable to parse {0:?}: {1}")]
     ParseError(WmiType, String),
+    #[error("Invalid association query: {0}")]
+    Association(String),
     #[error("(0)")]
     Format(#[from] std::fmt::Error),
 }
@@ -4,6 +4,7 @@
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use chrono::{DateTime, FixedOffset, TimeZone, Utc};
@@ -17,7 +18,7 @@ use value::{DataError, EnumValue, IntEnumValue, Type, Value};
 
 use crate::config::WmiQuircks;
 use crate::counters::{CounterDB, WmiCounter};
-use crate::error::TypeResult;
+use crate::error::{TypeError, TypeResult};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "PascalCase")]
@@ -40,12 +41,224 @@ impl TryAppend for Input {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct TableSpec {
-    #[serde(rename = "NameSpace")]
+    /// The WMI namespace, e.g. `root\MSCluster`. Forward slashes are
+    /// accepted as separator.
+    #[serde(
+        rename = "NameSpace",
+        alias = "namespace",
+        default = "default_namespace",
+        deserialize_with = "deserialize_namespace"
+    )]
     pub namespace: String,
     #[serde(rename = "ClassName")]
     pub classname: String,
     #[serde(rename = "InstancePlugin")]
     pub instance_plugin: Option<InstancePlugin>,
+    /// Query the instances related to an object instead of all
+    /// instances of the class. Association queries are only
+    /// supported over DCOM: on hosts using the PowerShell method,
+    /// these tables fail with an "only supported over DCOM" error.
+    #[serde(rename = "Association", default)]
+    pub association: Option<AssociationSpec>,
+}
+
+pub const DEFAULT_NAMESPACE: &str = "root\\cimv2";
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+fn deserialize_namespace<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(String::deserialize(deserializer)?.replace('/', "\\"))
+}
+
+/// A WQL `ASSOCIATORS OF` or `REFERENCES OF` query. The values are
+/// interpolated into the query, so they are validated on
+/// deserialization: the object path may not contain braces or
+/// whitespace outside of quoted key values, and class and role names
+/// must be identifiers.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssociationSpec {
+    pub kind: AssociationKind,
+    /// Path of the source instance, e.g. `Win32_DiskDrive.DeviceID="X"`.
+    #[serde(deserialize_with = "deserialize_object_path")]
+    pub object_path: String,
+    /// Only follow this association class (associators only).
+    #[serde(default, deserialize_with = "deserialize_name")]
+    pub assoc_class: Option<String>,
+    /// Only return instances of this class. For references, this is
+    /// the association class.
+    #[serde(default, deserialize_with = "deserialize_name")]
+    pub result_class: Option<String>,
+    /// Only follow associations in which the source plays this role.
+    #[serde(default, deserialize_with = "deserialize_name")]
+    pub role: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssociationKind {
+    Associators,
+    References,
+}
+
+impl AssociationSpec {
+    pub fn to_wql(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn deserialize_object_path<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = String::deserialize(deserializer)?;
+    check_object_path(&path).map_err(serde::de::Error::custom)?;
+    Ok(path)
+}
+
+fn deserialize_name<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = Option::<String>::deserialize(deserializer)?;
+    if let Some(name) = &name {
+        check_name(name).map_err(serde::de::Error::custom)?;
+    }
+    Ok(name)
+}
+
+/// Braces would end the object path early and whitespace would end
+/// it in the parser; both are only allowed (escaped, for quotes)
+/// within quoted key values.
+fn check_object_path(path: &str) -> Result<(), String> {
+    let mut quoted = false;
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {
+                return Err(format!("invalid character '{c}' in {path}"))
+            }
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                chars.next();
+            }
+            c if c.is_whitespace() && !quoted => {
+                return Err(format!("unquoted whitespace in {path}"))
+            }
+            _ => {}
+        }
+    }
+    match (path.is_empty(), quoted) {
+        (true, _) => Err(String::from("empty object path")),
+        (_, true) => Err(format!("unterminated string in {path}")),
+        (false, false) => Ok(()),
+    }
+}
+
+/// Class and role names are identifiers.
+fn check_name(name: &str) -> Result<(), String> {
+    match !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        true => Ok(()),
+        false => Err(format!("invalid class or role name: {name:?}")),
+    }
+}
+
+impl fmt::Display for AssociationSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            AssociationKind::Associators => write!(f, "ASSOCIATORS OF")?,
+            AssociationKind::References => write!(f, "REFERENCES OF")?,
+        }
+        write!(f, " {{{}}}", self.object_path)?;
+
+        let conditions = [
+            ("AssocClass", &self.assoc_class),
+            ("ResultClass", &self.result_class),
+            ("Role", &self.role),
+        ];
+        let mut conditions = conditions
+            .iter()
+            .filter_map(|(key, val)| Some((key, val.as_ref()?)))
+            .peekable();
+        if conditions.peek().is_some() {
+            write!(f, " WHERE")?;
+            for (key, val) in conditions {
+                write!(f, " {} = {}", key, val)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AssociationSpec {
+    type Err = TypeError;
+
+    fn from_str(s: &str) -> TypeResult<Self> {
+        let err = |msg: &str| TypeError::Association(format!("{msg}: {s}"));
+
+        let (open, close) = match (s.find('{'), s.rfind('}')) {
+            (Some(open), Some(close)) if open < close => (open, close),
+            _ => return Err(err("missing object path")),
+        };
+
+        let kind = match s[..open]
+            .split_whitespace()
+            .map(str::to_uppercase)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [kind, of] if kind == "ASSOCIATORS" && of == "OF" => {
+                AssociationKind::Associators
+            }
+            [kind, of] if kind == "REFERENCES" && of == "OF" => {
+                AssociationKind::References
+            }
+            _ => return Err(err("expected ASSOCIATORS OF or REFERENCES OF")),
+        };
+
+        let object_path = s[open + 1..close].trim().to_string();
+        check_object_path(&object_path).map_err(|e| err(&e))?;
+        let mut spec = AssociationSpec {
+            kind,
+            object_path,
+            assoc_class: None,
+            result_class: None,
+            role: None,
+        };
+
+        let conditions = s[close + 1..].replace('=', " = ");
+        let mut words = conditions.split_whitespace();
+        match words.next() {
+            None => return Ok(spec),
+            Some(word) if word.eq_ignore_ascii_case("WHERE") => {}
+            Some(_) => return Err(err("expected WHERE")),
+        }
+
+        let words = words.collect::<Vec<_>>();
+        for condition in words.chunks(3) {
+            let (key, val) = match condition {
+                [key, "=", val] => (key.to_lowercase(), val.to_string()),
+                _ => return Err(err("invalid condition")),
+            };
+            check_name(&val).map_err(|e| err(&e))?;
+            match (key.as_str(), kind) {
+                ("assocclass", AssociationKind::Associators) => {
+                    spec.assoc_class = Some(val)
+                }
+                ("resultclass", _) => spec.result_class = Some(val),
+                ("role", _) => spec.role = Some(val),
+                _ => return Err(err("unsupported condition")),
+            }
+        }
+
+        Ok(spec)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
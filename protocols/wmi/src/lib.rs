@@ -8,9 +8,10 @@ mod dcom;
 mod error;
 mod input;
 mod plugin;
+mod session;
 
 pub use config::{Config, WmiMethod};
 pub use counters::{CounterDB, WmiCounter};
-pub use error::{Result, WMIError};
-pub use input::Input;
+pub use error::{Result, TypeError, WMIError};
+pub use input::{AssociationKind, AssociationSpec, Input};
 pub use plugin::Plugin;
@@ -17,11 +17,12 @@ use etc_base::{
     Annotated, AnnotatedResult, DataFieldId, DataTableId, ProtoDataFieldId,
     ProtoDataTableId, ProtoQueryMap, ProtoRow, Protocol,
 };
-use protocol::{DataFieldSpec, DataTableSpec, LocalPlugin};
+use protocol::{DataFieldSpec, DataTableSpec, Idempotency, LocalPlugin};
 
 use crate::counters::{CounterDB, COUNTER_VARIABLES, REQUIRES_BASE};
 use crate::error::{TypeError, TypeResult, WMIDTError};
 use crate::input::FieldSpec;
+use crate::session::{CachedSession, SessionCache, SessionKey};
 use crate::{Config, Input, Result, WMIError};
 
 type TableData = AnnotatedResult<Vec<ProtoRow>, WMIDTError, WMIDTError>;
@@ -30,6 +31,7 @@ pub type DataMap = HashMap<ProtoDataTableId, TableData>;
 pub struct Plugin {
     key_vault: KeyVault,
     cache_dir: PathBuf,
+    sessions: SessionCache,
 }
 
 impl Plugin {
@@ -37,6 +39,7 @@ impl Plugin {
         Self {
             key_vault,
             cache_dir,
+            sessions: SessionCache::default(),
         }
     }
 
@@ -71,13 +74,20 @@ impl protocol::LocalPlugin for Plugin {
             let command = input
                 .data_tables
                 .try_get(&Self::get_datatable_id(table_id))?;
-            writeln!(
-                out,
-                "WMI (Plugin: {:?}): {}/{}",
-                &command.instance_plugin,
-                &command.namespace,
-                &command.classname
-            )?;
+            match &command.association {
+                None => writeln!(
+                    out,
+                    "WMI (Plugin: {:?}): {}/{}",
+                    &command.instance_plugin,
+                    &command.namespace,
+                    &command.classname
+                )?,
+                Some(association) => writeln!(
+                    out,
+                    "WMI (Plugin: {:?}): {}: {}",
+                    &command.instance_plugin, &command.namespace, association
+                )?,
+            }
         }
         Ok(out)
     }
@@ -136,6 +146,7 @@ impl protocol::LocalPlugin for Plugin {
                     DataFieldSpec {
                         name: field_spec.property_name.clone(),
                         input_type: field_spec.get_type()?,
+                        sensitive: false,
                     },
                 ))
             })
@@ -151,15 +162,34 @@ impl protocol::LocalPlugin for Plugin {
         info!("Using the wmi protocol");
         // info!("config from wato: {:?}", &config);
 
+        self.run_queries_with_sessions(input, config, query).await
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.sessions.close_all();
+        Ok(())
+    }
+
+    /// WQL queries only read.
+    fn idempotency(
+        &self,
+        _input: &Input,
+        _query: &ProtoQueryMap,
+    ) -> Idempotency {
+        Idempotency::Idempotent
+    }
+}
+
+impl Plugin {
+    async fn run_queries_with_sessions(
+        &self,
+        input: &Input,
+        config: &Config,
+        query: &ProtoQueryMap,
+    ) -> Result<DataMap> {
         let counter_file = self.cache_dir.join("wmi_counter_timestamps.json");
         let counterdb = Arc::new(CounterDB::new(counter_file).await?);
 
-        let mut session = config.get_session(&self.key_vault).await?;
-        info!("winrm session created");
-        // fake login
-        // let shell = session.shell().await?;
-        // info!("login successfull");
-
         let mut data: DataMap = HashMap::new();
         for (dt_id, df_ids) in query {
             info!(
@@ -185,15 +215,32 @@ impl protocol::LocalPlugin for Plugin {
                     .try_get_from(&input.data_tables)?,
             );
 
+            let session_key = SessionKey::new(config);
+            let mut session = self
+                .sessions
+                .take(&session_key, config, &self.key_vault)
+                .await?;
+
             let method = config.get_method();
             let mut wmi_res = method
-                .exec_query(
-                    &mut session,
-                    &class.1.classname,
-                    &fieldnames,
-                    &class.1.namespace,
-                )
+                .exec_table_query(&mut session.session, class.1, &fieldnames)
                 .await;
+
+            /* The server may have closed a cached session since the
+             * last run: reconnect once, regardless of retries. */
+            if wmi_res.is_err() && session.reused {
+                debug!("cached session failed; reconnecting");
+                session =
+                    CachedSession::connect(config, &self.key_vault).await?;
+                wmi_res = method
+                    .exec_table_query(
+                        &mut session.session,
+                        class.1,
+                        &fieldnames,
+                    )
+                    .await;
+            }
+
             let mut retries = config.retries.unwrap_or(0);
 
             while wmi_res.is_err() {
@@ -201,14 +248,14 @@ impl protocol::LocalPlugin for Plugin {
                     break;
                 }
 
-                session = config.get_session(&self.key_vault).await?;
+                session =
+                    CachedSession::connect(config, &self.key_vault).await?;
                 time::sleep(Duration::from_secs(1)).await;
                 wmi_res = method
-                    .exec_query(
-                        &mut session,
-                        &class.1.classname,
+                    .exec_table_query(
+                        &mut session.session,
+                        class.1,
                         &fieldnames,
-                        &class.1.namespace,
                     )
                     .await;
                 retries -= 1;
@@ -224,18 +271,27 @@ impl protocol::LocalPlugin for Plugin {
                             "An error occured while requesting {}: {:?}",
                             &class.0, &e
                         );
-                        session = config.get_session(&self.key_vault).await?;
                         Err(e)
                     }
                     Ok(wmi_res) => {
+                        self.sessions.put(session_key, session);
                         let mut idx: u32 = 0;
                         let mut data = Vec::new();
+                        /* Instances related to different objects must
+                         * not share counters. */
+                        let key_prefix = match &class.1.association {
+                            None => class.1.classname.clone(),
+                            Some(association) => format!(
+                                "{}_{}",
+                                association.object_path, &class.1.classname
+                            ),
+                        };
 
                         for wmi_obj in wmi_res {
                             let mut row = HashMap::new();
                             let base_key = format!(
                                 "{}_{}",
-                                &class.1.classname,
+                                &key_prefix,
                                 df_ids
                                     .iter()
                                     .filter(|(_, f)| f.is_key)
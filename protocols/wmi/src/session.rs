@@ -0,0 +1,94 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use log::debug;
+
+use agent_utils::KeyVault;
+
+use crate::config::{Config, WmiSession};
+use crate::Result;
+
+/// Established sessions, keyed on the connection config (host and
+/// credentials), kept across runs until they expire. A session is
+/// taken out of the cache while in use, so concurrent runs never share
+/// one, and is only put back after a successful query: sessions that
+/// failed (e.g. on an authentication error) are dropped.
+#[derive(Default)]
+pub(crate) struct SessionCache {
+    sessions: Mutex<HashMap<SessionKey, CachedSession>>,
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub(crate) struct SessionKey(String);
+
+pub(crate) struct CachedSession {
+    pub session: WmiSession,
+    /// Whether the session was taken from the cache, rather than
+    /// freshly established. The server may have dropped it meanwhile.
+    pub reused: bool,
+    expires: Instant,
+}
+
+impl SessionKey {
+    pub fn new(config: &Config) -> Self {
+        Self(config.connection_key())
+    }
+}
+
+impl CachedSession {
+    pub async fn connect(
+        config: &Config,
+        key_vault: &KeyVault,
+    ) -> Result<Self> {
+        Ok(Self {
+            session: config.get_session(key_vault).await?,
+            reused: false,
+            expires: Instant::now() + config.session_ttl(),
+        })
+    }
+
+    fn expired(&self) -> bool {
+        Instant::now() >= self.expires
+    }
+}
+
+impl SessionCache {
+    /// Take a cached session for `key` if one is available and has
+    /// not expired, or establish a new one.
+    pub async fn take(
+        &self,
+        key: &SessionKey,
+        config: &Config,
+        key_vault: &KeyVault,
+    ) -> Result<CachedSession> {
+        let cached = self.sessions.lock().unwrap().remove(key);
+        match cached {
+            Some(session) if !session.expired() => {
+                debug!("reusing cached session");
+                Ok(CachedSession {
+                    reused: true,
+                    ..session
+                })
+            }
+            _ => CachedSession::connect(config, key_vault).await,
+        }
+    }
+
+    /// Return a session to the cache, dropping sessions that have
+    /// expired in the meantime (e.g. for hosts no longer polled).
+    pub fn put(&self, key: SessionKey, session: CachedSession) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| !session.expired());
+        sessions.insert(key, session);
+    }
+
+    /// Drop all cached sessions.
+    pub fn close_all(&self) {
+        self.sessions.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,114 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use wmi_protocol::{AssociationKind, AssociationSpec};
+
+fn spec(
+    kind: AssociationKind,
+    assoc_class: Option<&str>,
+    result_class: Option<&str>,
+) -> AssociationSpec {
+    AssociationSpec {
+        kind,
+        object_path: String::from(r#"Win32_DiskDrive.DeviceID="DISK0""#),
+        assoc_class: assoc_class.map(String::from),
+        result_class: result_class.map(String::from),
+        role: None,
+    }
+}
+
+#[test]
+fn construct_query() {
+    assert_eq!(
+        spec(AssociationKind::Associators, None, None).to_wql(),
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0"}"#
+    );
+    assert_eq!(
+        spec(
+            AssociationKind::Associators,
+            Some("Win32_DiskDriveToDiskPartition"),
+            Some("Win32_DiskPartition")
+        )
+        .to_wql(),
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0"} WHERE AssocClass = Win32_DiskDriveToDiskPartition ResultClass = Win32_DiskPartition"#
+    );
+    assert_eq!(
+        spec(
+            AssociationKind::References,
+            None,
+            Some("Win32_DiskDriveToDiskPartition")
+        )
+        .to_wql(),
+        r#"REFERENCES OF {Win32_DiskDrive.DeviceID="DISK0"} WHERE ResultClass = Win32_DiskDriveToDiskPartition"#
+    );
+}
+
+#[test]
+fn parse_query() {
+    assert_eq!(
+        r#"associators of {Win32_DiskDrive.DeviceID="DISK0"} where ResultClass=Win32_DiskPartition"#
+            .parse::<AssociationSpec>()
+            .unwrap(),
+        spec(AssociationKind::Associators, None, Some("Win32_DiskPartition"))
+    );
+
+    for kind in [AssociationKind::Associators, AssociationKind::References] {
+        let assoc_class = (kind == AssociationKind::Associators)
+            .then_some("Win32_DiskDriveToDiskPartition");
+        let spec = spec(kind, assoc_class, Some("Win32_DiskPartition"));
+        assert_eq!(spec.to_wql().parse::<AssociationSpec>().unwrap(), spec);
+    }
+}
+
+#[test]
+fn reject_invalid_query() {
+    for query in [
+        "SELECT * FROM Win32_DiskDrive",
+        "ASSOCIATORS OF Win32_DiskDrive",
+        r#"ASSOCIATORS {Win32_DiskDrive.DeviceID="DISK0"}"#,
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0"} ResultClass = X"#,
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0"} WHERE ResultClass"#,
+        r#"REFERENCES OF {Win32_DiskDrive.DeviceID="DISK0"} WHERE AssocClass = X"#,
+    ] {
+        assert!(query.parse::<AssociationSpec>().is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn reject_injection() {
+    for query in [
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0"} WHERE ResultClass = X}"#,
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="}"} WHERE Role = X"#,
+        r#"ASSOCIATORS OF {Win32_DiskDrive.DeviceID="DISK0" OR 1} "#,
+    ] {
+        assert!(query.parse::<AssociationSpec>().is_err(), "{}", query);
+    }
+
+    let deserialize = |object_path: &str, role: &str| {
+        serde_json::from_value::<AssociationSpec>(serde_json::json!({
+            "Kind": "Associators",
+            "ObjectPath": object_path,
+            "Role": role
+        }))
+    };
+    assert!(deserialize(
+        r#"Win32_Service.Name="My \"Service\"""#,
+        "Antecedent"
+    )
+    .is_ok());
+    for (object_path, role) in [
+        (
+            r#"Win32_DiskDrive.DeviceID="DISK0"} WHERE Role = X"#,
+            "Antecedent",
+        ),
+        (r#"Win32_DiskDrive.DeviceID="DISK0"#, "Antecedent"),
+        (
+            r#"Win32_DiskDrive.DeviceID="DISK0""#,
+            "Antecedent ResultClass",
+        ),
+        (r#"Win32_DiskDrive.DeviceID="DISK0""#, "X}"),
+    ] {
+        assert!(deserialize(object_path, role).is_err(), "{object_path}");
+    }
+}
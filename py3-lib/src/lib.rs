@@ -6,9 +6,10 @@ use pyo3::{pyclass, pymethods, pymodule, types::PyModule, PyResult, Python};
 use serde::Serialize;
 
 #[pymodule]
-/// Parse and evaluate smart agent expressions.
+/// Parse and evaluate smart agent expressions and units.
 fn smart_agent(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<Expr>()?;
+    m.add_class::<Unit>()?;
     Ok(())
 }
 
@@ -48,3 +49,41 @@ impl Serialize for Expr {
         self.0.serialize(serializer)
     }
 }
+
+#[pyclass]
+#[derive(Debug, Clone, Copy)]
+/// A unit, parsed from a string such as "kB", "MB/s" or "m^2".
+struct Unit(unit::Unit);
+
+#[pymethods]
+impl Unit {
+    #[new]
+    fn new(s: &str) -> PyResult<Self> {
+        parse_unit(s).map(Self)
+    }
+
+    /// Name of the unit's dimension, eg. "information".
+    fn dimension(&self) -> String {
+        self.0.dimension().to_string()
+    }
+
+    /// Convert a value in this unit to the unit given as a string.
+    fn convert_to(&self, other: &str, value: f64) -> PyResult<f64> {
+        self.0
+            .convert(&parse_unit(other)?, value)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Unit({:?})", self.0.to_string())
+    }
+}
+
+fn parse_unit(s: &str) -> PyResult<unit::Unit> {
+    unit::parser::parse_composite_unit(s)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
@@ -0,0 +1,112 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use etc_base::{DataFieldId, Row};
+use expression::{EvalCell, EvalError, Expr};
+use value::{DataError, Type, Value};
+
+use super::error::{QueryCheckResult, QueryResult, QueryTypeError};
+use super::query::QueryType;
+
+/// A column calculated from the other columns in the row, e.g. a
+/// normalized join key. Data fields are referenced in the expression
+/// as `${<protocol>.<field>}`, e.g. `${SNMP.ifDescr}`; computed
+/// columns can reference the columns computed before them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComputedField {
+    pub field: DataFieldId,
+    pub expr: Expr,
+}
+
+impl ComputedField {
+    /// Whether the expression references the data field.
+    pub fn uses_field(&self, field: &DataFieldId) -> bool {
+        self.expr
+            .to_string()
+            .contains(&format!("${{{}}}", variable_name(field)))
+    }
+}
+
+/// The name by which a field can be referenced in expressions.
+pub fn variable_name(field_id: &DataFieldId) -> String {
+    format!("{}.{}", field_id.0 .0, field_id.1 .0)
+}
+
+pub(super) fn run(
+    fields: &[ComputedField],
+    data: Vec<Row>,
+) -> QueryResult<Vec<Row>> {
+    Ok(data
+        .into_iter()
+        .map(|mut row| {
+            for computed in fields {
+                let value = eval_field(&computed.expr, &row);
+                row.insert(computed.field.clone(), value);
+            }
+            row
+        })
+        .collect())
+}
+
+pub(super) fn check(
+    fields: &[ComputedField],
+    mut table: QueryType,
+) -> QueryCheckResult<QueryType> {
+    for computed in fields {
+        if table.fields.contains_key(&computed.field) {
+            return Err(QueryTypeError::DuplicateField(computed.field.clone()));
+        }
+        let typ = check_field(&computed.expr, &table.fields).map_err(|e| {
+            QueryTypeError::ComputedField(computed.field.clone(), e)
+        })?;
+        table.fields.insert(computed.field.clone(), typ);
+    }
+    Ok(table)
+}
+
+fn eval_field(expr: &Expr, row: &Row) -> Result<Value, DataError> {
+    let names = row
+        .keys()
+        .map(|field_id| (field_id, variable_name(field_id)))
+        .collect::<HashMap<_, _>>();
+    let vars = row
+        .iter()
+        .map(|(field_id, value)| {
+            (
+                names[field_id].as_str(),
+                EvalCell::new_evaluated(
+                    value.clone().map_err(EvalError::DataError),
+                ),
+            )
+        })
+        .collect();
+    expr.eval_in_row(Some(&vars), None).map_err(|e| match e {
+        EvalError::DataError(e) => e,
+        e => DataError::External(e.to_string()),
+    })
+}
+
+fn check_field(
+    expr: &Expr,
+    fields: &HashMap<DataFieldId, Type>,
+) -> Result<Type, EvalError> {
+    let names = fields
+        .keys()
+        .map(|field_id| (field_id, variable_name(field_id)))
+        .collect::<HashMap<_, _>>();
+    let vars = fields
+        .iter()
+        .map(|(field_id, typ)| {
+            (
+                names[field_id].as_str(),
+                EvalCell::new_evaluated(Ok(typ.clone())),
+            )
+        })
+        .collect();
+    expr.check_in_row(Some(&vars), None)
+}
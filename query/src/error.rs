@@ -8,6 +8,7 @@ use std::sync::Arc;
 
 use etc_base::{AnnotatedResult, Warning};
 use etc_base::{DataFieldId, DataTableId};
+use expression::EvalError;
 use protocol::DataTableError;
 use protocol::ErrorCategory;
 use thiserror::Error;
@@ -54,6 +55,12 @@ pub enum QueryTypeError {
     MissingField(DataFieldId),
     #[error("Missing key {0}")]
     MissingKey(DataFieldId),
+    #[error("Computed field {0} already exists")]
+    DuplicateField(DataFieldId),
+    #[error("Invalid expression for computed field {0}: {1}")]
+    ComputedField(DataFieldId, EvalError),
+    #[error("Pivot name field {0} should be a string, got {1}")]
+    PivotName(DataFieldId, Type),
 }
 
 #[derive(Clone, Debug)]
@@ -3,16 +3,22 @@
  ******************************************************************************/
 
 mod compat;
+mod compute;
 mod error;
 mod join;
 mod key_set;
+mod pivot;
 mod prefilter;
 mod query;
 mod reindex;
 
-pub use crate::query::{Query, QueryType, TypeMap};
-pub use error::{AnnotatedQueryResult, QueryError, QueryResult, QueryWarning};
+pub use crate::query::{ErrorAction, Query, QueryType, TypeMap};
+pub use compute::{variable_name, ComputedField};
+pub use error::{
+    AnnotatedQueryResult, QueryError, QueryResult, QueryTypeError, QueryWarning,
+};
 pub use join::{JoinOperand, JoinType};
 pub use key_set::KeySet;
+pub use pivot::{Pivot, PivotColumn};
 pub use prefilter::PreFilter;
 pub use reindex::Select;
@@ -0,0 +1,148 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use etc_base::{DataFieldId, Row};
+use value::{Data, DataError, Type, Value};
+
+use super::error::{QueryCheckResult, QueryResult, QueryTypeError};
+use super::join;
+use super::key_set::KeySet;
+use super::query::QueryType;
+
+/// Turn a long-format table, with a row per item and metric, into a
+/// wide table with a row per item and a column per metric. Metrics
+/// missing for an item result in missing values.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Pivot {
+    /// The fields identifying an item; these form the key of the
+    /// resulting table.
+    pub key: Vec<DataFieldId>,
+    /// The field holding the metric name.
+    pub name: DataFieldId,
+    /// The field holding the metric value.
+    pub value: DataFieldId,
+    pub columns: Vec<PivotColumn>,
+}
+
+/// A column of the pivoted table, holding the values of the metric
+/// with the given name.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PivotColumn {
+    pub name: String,
+    pub field: DataFieldId,
+}
+
+pub(super) fn run(pivot: &Pivot, data: Vec<Row>) -> QueryResult<Vec<Row>> {
+    let (index, _) = join::index(&pivot.key, data)?;
+    Ok(index.into_iter().map(|(_, rows)| pivot.row(rows)).collect())
+}
+
+pub(super) fn check(
+    pivot: &Pivot,
+    table: QueryType,
+) -> QueryCheckResult<QueryType> {
+    let field_type = |field_id: &DataFieldId| {
+        table
+            .fields
+            .get(field_id)
+            .ok_or_else(|| QueryTypeError::MissingField(field_id.clone()))
+    };
+
+    let mut fields = HashMap::new();
+    for field_id in &pivot.key {
+        let typ = field_type(field_id)
+            .map_err(|_| QueryTypeError::MissingKey(field_id.clone()))?;
+        if !typ.is_hashable() {
+            return Err(QueryTypeError::UnhashableKey(
+                field_id.clone(),
+                typ.clone(),
+            ));
+        }
+        fields.insert(field_id.clone(), typ.clone());
+    }
+
+    match field_type(&pivot.name)? {
+        Type::UnicodeString | Type::BinaryString => {}
+        typ => {
+            return Err(QueryTypeError::PivotName(
+                pivot.name.clone(),
+                typ.clone(),
+            ))
+        }
+    }
+
+    let value_type = field_type(&pivot.value)?;
+    for column in &pivot.columns {
+        if fields
+            .insert(column.field.clone(), value_type.clone())
+            .is_some()
+        {
+            return Err(QueryTypeError::DuplicateField(column.field.clone()));
+        }
+    }
+
+    Ok(QueryType {
+        singleton: pivot.key.is_empty(),
+        fields,
+        keys: KeySet::from_simple(
+            pivot.key.iter().cloned().collect::<BTreeSet<_>>(),
+        ),
+    })
+}
+
+impl Pivot {
+    /// Whether the pivot reads the data field.
+    pub fn uses_field(&self, field: &DataFieldId) -> bool {
+        self.key.contains(field) || &self.name == field || &self.value == field
+    }
+
+    /// Whether the data field is one of the pivoted columns.
+    pub fn computes_field(&self, field: &DataFieldId) -> bool {
+        self.columns.iter().any(|column| &column.field == field)
+    }
+
+    /// Combine the rows of an item. If a metric occurs more than
+    /// once, the last value is used.
+    fn row(&self, rows: Vec<Row>) -> Row {
+        let mut row = Row::new();
+        let mut values: HashMap<String, Data> = HashMap::new();
+
+        for mut metric in rows {
+            if row.is_empty() {
+                for field_id in &self.key {
+                    if let Some(value) = metric.get(field_id) {
+                        row.insert(field_id.clone(), value.clone());
+                    }
+                }
+            }
+
+            let name = match metric.get(&self.name) {
+                Some(Ok(Value::UnicodeString(s))) => s.clone(),
+                Some(Ok(Value::BinaryString(s))) => {
+                    String::from_utf8_lossy(s).into_owned()
+                }
+                _ => continue,
+            };
+            let value = metric
+                .remove(&self.value)
+                .unwrap_or(Err(DataError::Missing));
+            values.insert(name, value);
+        }
+
+        for column in &self.columns {
+            row.insert(
+                column.field.clone(),
+                values
+                    .remove(&column.name)
+                    .unwrap_or(Err(DataError::Missing)),
+            );
+        }
+
+        row
+    }
+}
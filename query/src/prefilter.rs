@@ -34,6 +34,19 @@ pub enum PreFilter {
 }
 
 impl PreFilter {
+    /// Whether the filter reads the data field.
+    pub fn uses_field(&self, field: &DataFieldId) -> bool {
+        match self {
+            PreFilter::All(cs) | PreFilter::Any(cs) => {
+                cs.iter().any(|c| c.uses_field(field))
+            }
+            PreFilter::Is { field: f, .. }
+            | PreFilter::IsNot { field: f, .. }
+            | PreFilter::In { field: f, .. }
+            | PreFilter::NotIn { field: f, .. } => f == field,
+        }
+    }
+
     pub fn run(&self, row: &Row) -> QueryResult<bool> {
         match self {
             PreFilter::All(cs) => {
@@ -12,12 +12,14 @@ use logger::Verbosity;
 use protocol::{DataMap, ErrorOrigin};
 
 use super::compat::{self, TableQuery};
+use super::compute::{self, ComputedField};
 use super::error::{
     AnnotatedQueryResult, QueryCheckResult, QueryError, QueryResult,
     QueryTypeError, QueryWarning,
 };
 use super::join::{self, JoinOperand};
 use super::key_set::KeySet;
+use super::pivot::{self, Pivot};
 use super::prefilter::PreFilter;
 use super::reindex::{self, Select};
 
@@ -27,6 +29,10 @@ pub enum Query {
     Filter(PreFilter, Box<Query>),
     Join(JoinOperand, JoinOperand),
     Reindex(Vec<DataFieldId>, Select, Box<Query>),
+    /// Add computed columns, e.g. to use as join key.
+    Compute(Vec<ComputedField>, Box<Query>),
+    /// Turn rows per metric into columns.
+    Pivot(Pivot, Box<Query>),
     // Compat with excel-ETCs
     TableQueries(Vec<TableQuery>),
 }
@@ -165,6 +171,24 @@ impl Query {
                     warnings,
                 }),
             },
+            Query::Compute(fields, query) => match query.eval(data)? {
+                Annotated {
+                    value: (table, exists),
+                    warnings,
+                } => Ok(Annotated {
+                    value: (compute::run(fields, table)?, exists),
+                    warnings,
+                }),
+            },
+            Query::Pivot(pivot, query) => match query.eval(data)? {
+                Annotated {
+                    value: (table, exists),
+                    warnings,
+                } => Ok(Annotated {
+                    value: (pivot::run(pivot, table)?, exists),
+                    warnings,
+                }),
+            },
             // Support TableQueries for backward compatibility:
             Query::TableQueries(queries) => {
                 compat::transform_table_queries(queries.to_vec())?.eval(data)
@@ -196,6 +220,14 @@ impl Query {
                 let table = query.check(data)?;
                 reindex::check(fields, table)
             }
+            Query::Compute(fields, query) => {
+                let table = query.check(data)?;
+                compute::check(fields, table)
+            }
+            Query::Pivot(pivot, query) => {
+                let table = query.check(data)?;
+                pivot::check(pivot, table)
+            }
             // Support TableQueries for backward compatibility:
             Query::TableQueries(queries) => {
                 let table = compat::transform_table_queries(queries.to_vec())?;
@@ -216,12 +248,66 @@ impl Query {
                 .chain(right.query.required_data_tables())
                 .collect(),
             Query::Reindex(_, _, query) => query.required_data_tables(),
+            Query::Compute(_, query) => query.required_data_tables(),
+            Query::Pivot(_, query) => query.required_data_tables(),
             Query::TableQueries(qs) => {
                 qs.iter().map(|q| q.data_table.clone()).collect()
             }
         }
     }
 
+    /// Whether the query itself reads the data field, to filter,
+    /// join, reindex, pivot or compute other fields.
+    pub fn uses_data_field(&self, field: &DataFieldId) -> bool {
+        match self {
+            Query::Data(_, _, _) => false,
+            Query::Filter(filter, query) => {
+                filter.uses_field(field) || query.uses_data_field(field)
+            }
+            Query::Join(left, right) => [left, right].iter().any(|operand| {
+                operand.join_key.contains(field)
+                    || operand.query.uses_data_field(field)
+            }),
+            Query::Reindex(keys, _, query) => {
+                keys.contains(field) || query.uses_data_field(field)
+            }
+            Query::Compute(fields, query) => {
+                fields.iter().any(|computed| computed.uses_field(field))
+                    || query.uses_data_field(field)
+            }
+            Query::Pivot(pivot, query) => {
+                pivot.uses_field(field) || query.uses_data_field(field)
+            }
+            Query::TableQueries(qs) => qs.iter().any(|q| {
+                q.pre_filter.uses_field(field)
+                    || q.join_key.values().any(|key| key == field)
+                    || q.reindex_keys.iter().flatten().any(|key| key == field)
+            }),
+        }
+    }
+
+    /// Whether the data field is added by the query, rather than
+    /// collected from a data table.
+    pub fn computes_data_field(&self, field: &DataFieldId) -> bool {
+        match self {
+            Query::Data(_, _, _) | Query::TableQueries(_) => false,
+            Query::Filter(_, query) | Query::Reindex(_, _, query) => {
+                query.computes_data_field(field)
+            }
+            Query::Join(left, right) => {
+                left.query.computes_data_field(field)
+                    || right.query.computes_data_field(field)
+            }
+            Query::Compute(fields, query) => {
+                fields.iter().any(|computed| &computed.field == field)
+                    || query.computes_data_field(field)
+            }
+            Query::Pivot(pivot, query) => {
+                pivot.computes_field(field) || query.computes_data_field(field)
+            }
+        }
+    }
+
     /*pub fn required_data(&self) -> HashMap<DataTableId,HashSet<DataFieldId>> {
     match self {
         Query::Data(data_table,_) => once((data_table.clone(),HashSet::new())).collect(),
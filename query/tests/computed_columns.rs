@@ -0,0 +1,207 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+
+use etc_base::{
+    Annotated, DataFieldId, DataTableId, ProtoDataFieldId, ProtoDataTableId,
+    Protocol, Row,
+};
+use expression::Expr;
+use protocol::DataMap;
+use query::{
+    ComputedField, ErrorAction, JoinOperand, JoinType, KeySet, Query,
+    QueryType, QueryTypeError, Select, TypeMap,
+};
+use value::{Type, Value};
+
+fn table_id(protocol: &str, table: &str) -> DataTableId {
+    DataTableId(
+        Protocol(protocol.to_string()),
+        ProtoDataTableId(table.to_string()),
+    )
+}
+
+fn field_id(protocol: &str, field: &str) -> DataFieldId {
+    DataFieldId(
+        Protocol(protocol.to_string()),
+        ProtoDataFieldId(field.to_string()),
+    )
+}
+
+fn table_type(key: DataFieldId, fields: &[(DataFieldId, Type)]) -> QueryType {
+    QueryType {
+        keys: KeySet::from_simple(BTreeSet::from([key])),
+        fields: fields.iter().cloned().collect(),
+        singleton: false,
+    }
+}
+
+fn row(cells: &[(DataFieldId, Value)]) -> Row {
+    cells
+        .iter()
+        .map(|(field, value)| (field.clone(), Ok(value.clone())))
+        .collect()
+}
+
+fn string(s: &str) -> Value {
+    Value::UnicodeString(s.to_string())
+}
+
+/// Interfaces (keyed by index) joined with ports (keyed by name) on
+/// the interface description, stripped of its "port-" prefix.
+fn query() -> Query {
+    let interfaces =
+        Query::Data(table_id("SNMP", "ifTable"), ErrorAction::Fail, false);
+    let ports = Query::Data(table_id("API", "ports"), ErrorAction::Fail, false);
+    Query::Join(
+        JoinOperand {
+            query: Box::new(Query::Reindex(
+                vec![field_id("Query", "port")],
+                Select::First,
+                Box::new(Query::Compute(
+                    vec![ComputedField {
+                        field: field_id("Query", "port"),
+                        expr: Expr::parse(
+                            "{substitute(${SNMP.ifDescr}, '^port-', '')}",
+                        )
+                        .unwrap(),
+                    }],
+                    Box::new(interfaces),
+                )),
+            )),
+            join_type: JoinType::Inner,
+            join_key: vec![field_id("Query", "port")],
+        },
+        JoinOperand {
+            query: Box::new(ports),
+            join_type: JoinType::Inner,
+            join_key: vec![field_id("API", "name")],
+        },
+    )
+}
+
+fn types() -> TypeMap {
+    HashMap::from([
+        (
+            table_id("SNMP", "ifTable"),
+            table_type(
+                field_id("SNMP", "ifIndex"),
+                &[
+                    (field_id("SNMP", "ifIndex"), Type::Integer),
+                    (field_id("SNMP", "ifDescr"), Type::UnicodeString),
+                ],
+            ),
+        ),
+        (
+            table_id("API", "ports"),
+            table_type(
+                field_id("API", "name"),
+                &[
+                    (field_id("API", "name"), Type::UnicodeString),
+                    (field_id("API", "speed"), Type::Integer),
+                ],
+            ),
+        ),
+    ])
+}
+
+#[test]
+fn check_computed_join_key() {
+    let typ = query().check(&types()).unwrap();
+    assert_eq!(
+        typ.fields.get(&field_id("Query", "port")),
+        Some(&Type::UnicodeString)
+    );
+    assert_eq!(typ.fields.len(), 5);
+}
+
+#[test]
+fn reject_invalid_computed_field() {
+    let query = Query::Compute(
+        vec![ComputedField {
+            field: field_id("Query", "port"),
+            expr: Expr::parse("{${SNMP.ifName}}").unwrap(),
+        }],
+        Box::new(Query::Data(
+            table_id("SNMP", "ifTable"),
+            ErrorAction::Fail,
+            false,
+        )),
+    );
+    assert!(matches!(
+        query.check(&types()),
+        Err(QueryTypeError::ComputedField(_, _))
+    ));
+
+    let query = Query::Compute(
+        vec![ComputedField {
+            field: field_id("SNMP", "ifDescr"),
+            expr: Expr::parse("{${SNMP.ifIndex}}").unwrap(),
+        }],
+        Box::new(Query::Data(
+            table_id("SNMP", "ifTable"),
+            ErrorAction::Fail,
+            false,
+        )),
+    );
+    assert!(matches!(
+        query.check(&types()),
+        Err(QueryTypeError::DuplicateField(_))
+    ));
+}
+
+#[test]
+fn join_on_computed_key() {
+    let data: DataMap = HashMap::from([
+        (
+            table_id("SNMP", "ifTable"),
+            Ok(Annotated {
+                value: vec![
+                    row(&[
+                        (field_id("SNMP", "ifIndex"), Value::Integer(1)),
+                        (field_id("SNMP", "ifDescr"), string("port-eth0")),
+                    ]),
+                    row(&[
+                        (field_id("SNMP", "ifIndex"), Value::Integer(2)),
+                        (field_id("SNMP", "ifDescr"), string("port-eth1")),
+                    ]),
+                ],
+                warnings: Vec::new(),
+            }),
+        ),
+        (
+            table_id("API", "ports"),
+            Ok(Annotated {
+                value: vec![
+                    row(&[
+                        (field_id("API", "name"), string("eth1")),
+                        (field_id("API", "speed"), Value::Integer(1000)),
+                    ]),
+                    row(&[
+                        (field_id("API", "name"), string("eth2")),
+                        (field_id("API", "speed"), Value::Integer(100)),
+                    ]),
+                ],
+                warnings: Vec::new(),
+            }),
+        ),
+    ]);
+
+    let rows = query().run(&data).unwrap().value;
+    assert_eq!(rows.len(), 1);
+    let row = &rows[0];
+    assert_eq!(
+        row[&field_id("SNMP", "ifIndex")].as_ref().unwrap(),
+        &Value::Integer(2)
+    );
+    assert_eq!(
+        row[&field_id("Query", "port")].as_ref().unwrap(),
+        &string("eth1")
+    );
+    assert_eq!(
+        row[&field_id("API", "speed")].as_ref().unwrap(),
+        &Value::Integer(1000)
+    );
+}
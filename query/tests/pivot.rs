@@ -0,0 +1,141 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+
+use etc_base::{
+    Annotated, DataFieldId, DataTableId, ProtoDataFieldId, ProtoDataTableId,
+    Protocol, Row,
+};
+use protocol::DataMap;
+use query::{
+    ErrorAction, KeySet, Pivot, PivotColumn, Query, QueryType, QueryTypeError,
+    TypeMap,
+};
+use value::{DataError, Type, Value};
+
+fn table_id() -> DataTableId {
+    DataTableId(
+        Protocol("API".to_string()),
+        ProtoDataTableId("metrics".to_string()),
+    )
+}
+
+fn field_id(field: &str) -> DataFieldId {
+    DataFieldId(
+        Protocol("API".to_string()),
+        ProtoDataFieldId(field.to_string()),
+    )
+}
+
+fn string(s: &str) -> Value {
+    Value::UnicodeString(s.to_string())
+}
+
+fn metric(item: &str, name: &str, value: f64) -> Row {
+    [
+        (field_id("item"), Ok(string(item))),
+        (field_id("name"), Ok(string(name))),
+        (field_id("value"), Ok(Value::Float(value))),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn query() -> Query {
+    Query::Pivot(
+        Pivot {
+            key: vec![field_id("item")],
+            name: field_id("name"),
+            value: field_id("value"),
+            columns: vec![
+                PivotColumn {
+                    name: "cpu".to_string(),
+                    field: field_id("cpu"),
+                },
+                PivotColumn {
+                    name: "memory".to_string(),
+                    field: field_id("memory"),
+                },
+            ],
+        },
+        Box::new(Query::Data(table_id(), ErrorAction::Fail, false)),
+    )
+}
+
+fn types(name_type: Type) -> TypeMap {
+    HashMap::from([(
+        table_id(),
+        QueryType {
+            keys: KeySet::from_simple(BTreeSet::from([
+                field_id("item"),
+                field_id("name"),
+            ])),
+            fields: HashMap::from([
+                (field_id("item"), Type::UnicodeString),
+                (field_id("name"), name_type),
+                (field_id("value"), Type::Float),
+            ]),
+            singleton: false,
+        },
+    )])
+}
+
+#[test]
+fn check_pivot() {
+    let typ = query().check(&types(Type::UnicodeString)).unwrap();
+    assert_eq!(
+        typ.keys,
+        KeySet::from_simple(BTreeSet::from([field_id("item")]))
+    );
+    assert_eq!(
+        typ.fields,
+        HashMap::from([
+            (field_id("item"), Type::UnicodeString),
+            (field_id("cpu"), Type::Float),
+            (field_id("memory"), Type::Float),
+        ])
+    );
+
+    assert!(matches!(
+        query().check(&types(Type::Integer)),
+        Err(QueryTypeError::PivotName(_, Type::Integer))
+    ));
+}
+
+#[test]
+fn pivot_long_to_wide() {
+    let data: DataMap = HashMap::from([(
+        table_id(),
+        Ok(Annotated {
+            value: vec![
+                metric("web01", "cpu", 12.5),
+                metric("web01", "memory", 61.0),
+                metric("web02", "cpu", 80.0),
+                metric("web02", "disk", 20.0),
+                metric("db01", "memory", 90.0),
+            ],
+            warnings: Vec::new(),
+        }),
+    )]);
+
+    let rows = query().run(&data).unwrap().value;
+    let rows = rows
+        .iter()
+        .map(|row| match &row[&field_id("item")] {
+            Ok(Value::UnicodeString(item)) => (item.as_str(), row),
+            v => panic!("unexpected item: {v:?}"),
+        })
+        .collect::<HashMap<_, _>>();
+    assert_eq!(rows.len(), 3);
+
+    let value = |item: &str, field: &str| rows[item][&field_id(field)].clone();
+    assert_eq!(value("web01", "cpu"), Ok(Value::Float(12.5)));
+    assert_eq!(value("web01", "memory"), Ok(Value::Float(61.0)));
+    assert_eq!(value("web02", "cpu"), Ok(Value::Float(80.0)));
+    assert_eq!(value("web02", "memory"), Err(DataError::Missing));
+    assert_eq!(value("db01", "cpu"), Err(DataError::Missing));
+    assert_eq!(value("db01", "memory"), Ok(Value::Float(90.0)));
+    assert!(!rows["web02"].contains_key(&field_id("disk")));
+}
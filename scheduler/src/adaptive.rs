@@ -0,0 +1,55 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Bounds for a task interval that adapts to how often the task's
+/// result changes.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Adaptive {
+    #[serde(with = "agent_serde::duration")]
+    pub min: Duration,
+    #[serde(with = "agent_serde::duration")]
+    pub max: Duration,
+}
+
+/// The current interval of an adaptive task. The interval doubles
+/// while the result stays the same and halves when it changes.
+#[derive(Clone, Debug)]
+pub struct AdaptiveInterval {
+    interval: Duration,
+    digest: Option<u64>,
+}
+
+impl AdaptiveInterval {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            digest: None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Register the digest of a run's result and return the new
+    /// interval. Results without a digest leave the interval as is.
+    pub fn update(
+        &mut self,
+        digest: Option<u64>,
+        bounds: &Adaptive,
+    ) -> Duration {
+        if let Some(digest) = digest {
+            self.interval = match self.digest.replace(digest) {
+                None => self.interval,
+                Some(last) if last == digest => self.interval * 2,
+                Some(_) => self.interval / 2,
+            };
+        }
+        self.interval = self.interval.max(bounds.min).min(bounds.max);
+        self.interval
+    }
+}
@@ -0,0 +1,66 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::Duration;
+
+use crate::{TaskKey, TaskSchedule};
+
+/// Failure streaks of the scheduled tasks. These are kept outside
+/// the task runners, so that they survive reschedules.
+#[derive(Debug, Default)]
+pub(crate) struct Backoffs(Mutex<HashMap<TaskKey, Backoff>>);
+
+#[derive(Clone, Copy, Debug)]
+struct Backoff {
+    failures: u32,
+    delay: Duration,
+}
+
+impl Backoffs {
+    /// The delay currently added to the interval of the task.
+    pub(crate) fn delay(&self, key: &TaskKey) -> Duration {
+        self.0
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_or_else(Duration::zero, |b| b.delay)
+    }
+
+    /// Record the outcome of a run and return the new delay.
+    pub(crate) fn update(&self, task: &TaskSchedule, failed: bool) -> Duration {
+        let mut backoffs = self.0.lock().unwrap();
+        match failed {
+            true => {
+                let failures = backoffs
+                    .get(&task.key())
+                    .map_or(1, |b| b.failures.saturating_add(1));
+                let delay = task.backoff(failures);
+                backoffs.insert(task.key(), Backoff { failures, delay });
+                delay
+            }
+            false => {
+                backoffs.remove(&task.key());
+                Duration::zero()
+            }
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &TaskKey) {
+        self.0.lock().unwrap().remove(key);
+    }
+
+    /// The tasks that are currently backing off, with their delay.
+    pub(crate) fn snapshot(&self) -> HashMap<TaskKey, Duration> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, b)| !b.delay.is_zero())
+            .map(|(k, b)| (k.clone(), b.delay))
+            .collect()
+    }
+}
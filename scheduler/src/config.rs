@@ -2,11 +2,40 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use etc_base::Protocol;
+
+use crate::limiter::ConcurrencyLimit;
 use crate::task_schedule::TaskSchedule;
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Default)]
 pub struct Config {
     pub(crate) tasks: Vec<TaskSchedule>,
+    /// The maximum delay added to each run of a task, as a fraction
+    /// of its interval. Tasks can override this.
+    #[serde(default)]
+    pub(crate) jitter: Option<f64>,
+    /// Limits on the number of tasks querying a protocol at once.
+    /// Tasks exceeding the limit wait for a permit.
+    #[serde(default)]
+    pub(crate) concurrency: HashMap<Protocol, ConcurrencyLimit>,
+}
+
+impl Config {
+    pub const DEFAULT_JITTER: f64 = 0.05;
+
+    pub fn jitter(&self) -> f64 {
+        self.jitter.unwrap_or(Self::DEFAULT_JITTER)
+    }
+
+    /// The tasks, with the default jitter applied.
+    pub(crate) fn tasks(&self) -> impl Iterator<Item = TaskSchedule> + '_ {
+        self.tasks.iter().map(|task| TaskSchedule {
+            jitter: Some(task.jitter.unwrap_or(self.jitter())),
+            ..task.clone()
+        })
+    }
 }
@@ -0,0 +1,270 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Number of days searched for the next fire time. Any expression
+/// that fires at all, fires at least once in this period.
+const SEARCH_DAYS: i64 = 28 * 366;
+
+/// A cron expression ("minute hour day-of-month month day-of-week"),
+/// evaluated in a timezone. Local times that are skipped by a DST
+/// transition do not fire; local times that occur twice fire once.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "CronRepr", into = "CronRepr")]
+pub struct Cron {
+    expr: String,
+    timezone: Tz,
+    minutes: Field,
+    hours: Field,
+    days: Field,
+    months: Field,
+    weekdays: Field,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CronRepr {
+    Expr(String),
+    WithTimezone {
+        expr: String,
+        #[serde(default)]
+        timezone: Option<String>,
+    },
+}
+
+/// The allowed values for a field, as a bitmask.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Field {
+    mask: u64,
+    any: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum CronError {
+    #[error("expected 5 fields, found {0}")]
+    FieldCount(usize),
+    #[error("invalid {0} field: {1}")]
+    InvalidField(&'static str, String),
+    #[error("unknown timezone: {0}")]
+    Timezone(String),
+    #[error("the expression never fires")]
+    NeverFires,
+}
+
+impl Cron {
+    pub fn new(expr: &str, timezone: Tz) -> Result<Self, CronError> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let (minutes, hours, days, months, weekdays) = match fields[..] {
+            [minutes, hours, days, months, weekdays] => {
+                (minutes, hours, days, months, weekdays)
+            }
+            _ => return Err(CronError::FieldCount(fields.len())),
+        };
+        let mut weekdays = Field::parse("day-of-week", weekdays, 0, 7)?;
+        /* Both 0 and 7 are Sunday. */
+        if weekdays.mask & (1 << 7) != 0 {
+            weekdays.mask = (weekdays.mask | 1) & !(1 << 7);
+        }
+        let cron = Self {
+            expr: fields.join(" "),
+            timezone,
+            minutes: Field::parse("minute", minutes, 0, 59)?,
+            hours: Field::parse("hour", hours, 0, 23)?,
+            days: Field::parse("day-of-month", days, 1, 31)?,
+            months: Field::parse("month", months, 1, 12)?,
+            weekdays,
+        };
+        /* 2000-01-01T00:00:00Z */
+        match cron.next_after(Utc.timestamp_opt(946684800, 0).unwrap()) {
+            Some(_) => Ok(cron),
+            None => Err(CronError::NeverFires),
+        }
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// The first fire time strictly after the given time.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_timezone(&self.timezone).naive_local().date();
+        (0..SEARCH_DAYS)
+            .map(|n| start + Duration::days(n))
+            .filter(|date| self.matches_date(date))
+            .find_map(|date| {
+                self.hours
+                    .values(0, 23)
+                    .flat_map(|h| {
+                        self.minutes.values(0, 59).map(move |m| (h, m))
+                    })
+                    .filter_map(|(h, m)| {
+                        let time = date.and_hms_opt(h, m, 0)?;
+                        match self.timezone.from_local_datetime(&time) {
+                            LocalResult::Single(t) => Some(t),
+                            LocalResult::Ambiguous(t, _) => Some(t),
+                            LocalResult::None => None,
+                        }
+                    })
+                    .map(|t| t.with_timezone(&Utc))
+                    .find(|t| *t > after)
+            })
+    }
+
+    /// Like in other cron implementations, if both the day of month
+    /// and the day of week are restricted, either may match.
+    fn matches_date(&self, date: &NaiveDate) -> bool {
+        let day = self.days.contains(date.day());
+        let weekday = self
+            .weekdays
+            .contains(date.weekday().num_days_from_sunday());
+        self.months.contains(date.month())
+            && match (self.days.any, self.weekdays.any) {
+                (false, false) => day || weekday,
+                _ => day && weekday,
+            }
+    }
+}
+
+impl Field {
+    fn parse(
+        name: &'static str,
+        spec: &str,
+        min: u32,
+        max: u32,
+    ) -> Result<Self, CronError> {
+        let err = || CronError::InvalidField(name, spec.to_string());
+        let mut mask = 0;
+        for part in spec.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    (range, step.parse::<u32>().map_err(|_| err())?)
+                }
+                None => (part, 1),
+            };
+            let (from, to) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((from, to)) => (
+                        from.parse().map_err(|_| err())?,
+                        to.parse().map_err(|_| err())?,
+                    ),
+                    None => {
+                        let value = range.parse().map_err(|_| err())?;
+                        match part.contains('/') {
+                            true => (value, max),
+                            false => (value, value),
+                        }
+                    }
+                },
+            };
+            if step == 0 || from < min || to > max || from > to {
+                return Err(err());
+            }
+            for value in (from..=to).step_by(step as usize) {
+                mask |= 1 << value;
+            }
+        }
+        Ok(Self {
+            mask,
+            any: spec == "*",
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.mask & (1 << value) != 0
+    }
+
+    fn values(self, min: u32, max: u32) -> impl Iterator<Item = u32> {
+        (min..=max).filter(move |v| self.contains(*v))
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s, Tz::UTC)
+    }
+}
+
+impl TryFrom<CronRepr> for Cron {
+    type Error = CronError;
+    fn try_from(repr: CronRepr) -> Result<Self, Self::Error> {
+        match repr {
+            CronRepr::Expr(expr)
+            | CronRepr::WithTimezone {
+                expr,
+                timezone: None,
+            } => expr.parse(),
+            CronRepr::WithTimezone {
+                expr,
+                timezone: Some(timezone),
+            } => Self::new(
+                &expr,
+                timezone
+                    .parse()
+                    .map_err(|_| CronError::Timezone(timezone))?,
+            ),
+        }
+    }
+}
+
+impl From<Cron> for CronRepr {
+    fn from(cron: Cron) -> Self {
+        match cron.timezone {
+            Tz::UTC => Self::Expr(cron.expr),
+            tz => Self::WithTimezone {
+                expr: cron.expr,
+                timezone: Some(tz.name().to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.timezone {
+            Tz::UTC => write!(f, "{}", self.expr),
+            tz => write!(f, "{} ({})", self.expr, tz.name()),
+        }
+    }
+}
+
+impl PartialEq for Cron {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cron {}
+
+impl PartialOrd for Cron {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cron {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.expr, self.timezone.name())
+            .cmp(&(&other.expr, other.timezone.name()))
+    }
+}
+
+impl Hash for Cron {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.timezone.name().hash(state);
+    }
+}
@@ -0,0 +1,43 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use chrono::Duration;
+
+/// Pseudo-random schedule offsets for a task. The sequence is seeded
+/// by the task's key, so that a task is scheduled identically every
+/// time the agent starts.
+#[derive(Clone, Debug)]
+pub struct Jitter {
+    state: u64,
+}
+
+impl Jitter {
+    pub fn new<K: Hash>(seed: &K) -> Self {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        Self {
+            state: hasher.finish(),
+        }
+    }
+
+    /// The next offset, between zero and the given fraction of the
+    /// period.
+    pub fn offset(&mut self, period: Duration, fraction: f64) -> Duration {
+        let max = period.num_milliseconds() as f64 * fraction.clamp(0.0, 1.0);
+        Duration::milliseconds((max * self.next_fraction()) as i64)
+    }
+
+    /// A number in [0, 1), using splitmix64.
+    fn next_fraction(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
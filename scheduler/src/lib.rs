@@ -2,17 +2,30 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+mod adaptive;
+mod backoff;
 mod config;
+mod cron;
 mod error;
+mod jitter;
+mod limiter;
 mod schedule;
 mod scheduler;
+mod state;
+mod stats;
 mod task;
 mod task_runner;
 mod task_schedule;
 
 pub use crate::scheduler::Scheduler;
+pub use adaptive::{Adaptive, AdaptiveInterval};
 pub use config::Config;
+pub use cron::{Cron, CronError};
 pub use error::{Error, Result};
+pub use jitter::Jitter;
+pub use limiter::ConcurrencyLimit;
 pub use schedule::Schedule;
-pub use task::Task;
+pub use state::{StateChange, StateTracker, TaskOutcome, TaskState};
+pub use stats::{Stats, StatsSnapshot};
+pub use task::{Task, TaskKey};
 pub use task_schedule::TaskSchedule;
@@ -0,0 +1,130 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use etc_base::Protocol;
+
+/// Limits on the number of tasks querying a protocol at once.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ConcurrencyLimit {
+    /// The maximum number of concurrent tasks over all hosts.
+    #[serde(default)]
+    pub total: Option<usize>,
+    /// The maximum number of concurrent tasks per host.
+    #[serde(default)]
+    pub per_host: Option<usize>,
+}
+
+/// Hands out permits to run tasks, according to the configured
+/// concurrency limits.
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    limits: HashMap<Protocol, ConcurrencyLimit>,
+    total: HashMap<Protocol, Arc<Semaphore>>,
+    per_host: HashMap<(Protocol, String), Arc<Semaphore>>,
+}
+
+/// Permits held while a task runs.
+pub(crate) struct Permits {
+    _permits: Vec<OwnedSemaphorePermit>,
+    /// Whether the task had to wait for any of the permits.
+    pub waited: bool,
+}
+
+impl Limiter {
+    pub(crate) fn new(limits: HashMap<Protocol, ConcurrencyLimit>) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                limits,
+                ..LimiterState::default()
+            }),
+        }
+    }
+
+    /// Replace the limits. Tasks that are currently running keep
+    /// their permits; new permits are taken from fresh semaphores.
+    pub(crate) fn update(&self, limits: &HashMap<Protocol, ConcurrencyLimit>) {
+        let mut state = self.state.lock().unwrap();
+        if &state.limits != limits {
+            *state = LimiterState {
+                limits: limits.clone(),
+                ..LimiterState::default()
+            };
+        }
+    }
+
+    /// Wait for permits for all given protocols on the host. Permits
+    /// are always taken in the same order, to avoid deadlocks between
+    /// tasks using multiple limited protocols.
+    pub(crate) async fn acquire<'a, I>(
+        &self,
+        host: &str,
+        protocols: I,
+    ) -> Permits
+    where
+        I: IntoIterator<Item = &'a Protocol>,
+    {
+        let protocols = protocols.into_iter().collect::<BTreeSet<_>>();
+        let semaphores = self.semaphores(host, protocols);
+        let mut permits = Vec::with_capacity(semaphores.len());
+        let mut waited = false;
+        for semaphore in semaphores {
+            let permit = match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    waited = true;
+                    /* Semaphores are never closed. */
+                    semaphore.acquire_owned().await.unwrap()
+                }
+            };
+            permits.push(permit);
+        }
+        Permits {
+            _permits: permits,
+            waited,
+        }
+    }
+
+    fn semaphores(
+        &self,
+        host: &str,
+        protocols: BTreeSet<&Protocol>,
+    ) -> Vec<Arc<Semaphore>> {
+        let mut state = self.state.lock().unwrap();
+        let LimiterState {
+            limits,
+            total,
+            per_host,
+        } = &mut *state;
+        protocols
+            .into_iter()
+            .filter_map(|protocol| Some((protocol, limits.get(protocol)?)))
+            .flat_map(|(protocol, limit)| {
+                let total = limit.total.map(|n| {
+                    total
+                        .entry(protocol.clone())
+                        .or_insert_with(|| Arc::new(Semaphore::new(n.max(1))))
+                        .clone()
+                });
+                let per_host = limit.per_host.map(|n| {
+                    per_host
+                        .entry((protocol.clone(), host.to_string()))
+                        .or_insert_with(|| Arc::new(Semaphore::new(n.max(1))))
+                        .clone()
+                });
+                total.into_iter().chain(per_host)
+            })
+            .collect()
+    }
+}
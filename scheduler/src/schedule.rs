@@ -5,19 +5,35 @@
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::Cron;
+
 #[derive(
     Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug,
 )]
 #[serde(rename_all = "lowercase")]
 pub enum Schedule {
     Period(#[serde(with = "agent_serde::duration")] Duration),
+    Cron(Cron),
 }
 
 impl Schedule {
-    /// Provide the ideal next scheduler target.
-    pub(crate) fn next_target(&self, last: DateTime<Utc>) -> DateTime<Utc> {
+    /// Provide the ideal next scheduler target. Returns `None` if
+    /// the schedule never fires.
+    pub(crate) fn next_target(
+        &self,
+        last: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Period(p) => Some(last + *p),
+            Self::Cron(cron) => cron.next_after(last),
+        }
+    }
+
+    /// The interval between runs, for periodic schedules.
+    pub(crate) fn period(&self) -> Option<Duration> {
         match self {
-            Self::Period(p) => last + *p,
+            Self::Period(p) => Some(*p),
+            Self::Cron(_) => None,
         }
     }
 
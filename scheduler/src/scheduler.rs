@@ -5,8 +5,9 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use chrono::Duration;
 use serde_json::Value;
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio::task::JoinHandle;
 
 use dbschema::Timestamped;
@@ -15,15 +16,26 @@ use metrics_types::{Data, MetricsTable};
 use etc::Spec;
 use protocol::PluginManager;
 
+use crate::backoff::Backoffs;
+use crate::limiter::Limiter;
+use crate::state::StateChange;
+use crate::stats::Stats;
+use crate::task::TaskKey;
 use crate::task_runner::TaskRunner;
 
 use super::config::Config;
 use super::error::Result;
 
+/// Number of state changes buffered for slow subscribers.
+const STATE_CHANGE_CAPACITY: usize = 256;
+
 #[derive(Debug)]
 pub struct Scheduler {
     config_sender: watch::Sender<Arc<Config>>,
     cmd_sender: mpsc::Sender<Cmd>,
+    stats: Arc<Stats>,
+    state_sender: broadcast::Sender<StateChange>,
+    backoffs: Arc<Backoffs>,
     worker: JoinHandle<Result<()>>,
 }
 
@@ -45,20 +57,44 @@ impl Scheduler {
         let (config_sender, config_receiver) =
             watch::channel(Arc::new(Config::default()));
         let (cmd_sender, cmd_receiver) = mpsc::channel(10);
+        let stats = Arc::new(Stats::new());
+        let (state_sender, _) = broadcast::channel(STATE_CHANGE_CAPACITY);
+        let backoffs = Arc::new(Backoffs::default());
         let worker = tokio::spawn(Self::worker(
             plugin_manager,
             config_receiver,
             etc_receiver,
             cmd_receiver,
             data_sender,
+            stats.clone(),
+            state_sender.clone(),
+            backoffs.clone(),
         ));
         Self {
             config_sender,
             cmd_sender,
+            stats,
+            state_sender,
+            backoffs,
             worker,
         }
     }
 
+    pub fn stats(&self) -> Arc<Stats> {
+        self.stats.clone()
+    }
+
+    /// The tasks that are backing off after consecutive failures,
+    /// with the delay currently added to their interval.
+    pub fn backoffs(&self) -> HashMap<TaskKey, Duration> {
+        self.backoffs.snapshot()
+    }
+
+    /// Subscribe to task state changes.
+    pub fn state_changes(&self) -> broadcast::Receiver<StateChange> {
+        self.state_sender.subscribe()
+    }
+
     pub async fn shutdown(self) -> Result<()> {
         self.cmd_sender.send(Cmd::Exit).await?;
         self.worker.await?
@@ -78,37 +114,43 @@ impl Scheduler {
             String,
             Timestamped<MetricsTable<Data<Value>>>,
         )>,
+        stats: Arc<Stats>,
+        state_sender: broadcast::Sender<StateChange>,
+        backoffs: Arc<Backoffs>,
     ) -> Result<()> {
         let config: Arc<Config> = config_receiver.borrow().clone();
         log::debug!("Scheduling {} task(s)", config.tasks.len());
+        let limiter = Arc::new(Limiter::new(config.concurrency.clone()));
 
-        let mut tasks = config.tasks.iter().cloned().fold(
-            HashMap::new(),
-            |mut map, task| {
-                let key = task.key();
-                map.entry(key)
-                    .or_insert_with(Vec::new)
-                    .push(TaskRunner::new(
-                        task,
-                        plugin_manager.clone(),
-                        etc_receiver.clone(),
-                        data_sender.clone(),
-                    ));
-                map
-            },
-        );
+        let mut tasks = config.tasks().fold(HashMap::new(), |mut map, task| {
+            let key = task.key();
+            map.entry(key)
+                .or_insert_with(Vec::new)
+                .push(TaskRunner::new(
+                    task,
+                    plugin_manager.clone(),
+                    etc_receiver.clone(),
+                    data_sender.clone(),
+                    stats.clone(),
+                    state_sender.clone(),
+                    limiter.clone(),
+                    backoffs.clone(),
+                ));
+            map
+        });
+        stats.set_tasks(config.tasks.len());
 
         loop {
             tokio::select! {
                 _ = config_receiver.changed() => {
                     log::debug!("Config changed; reloading tasks...");
 
+                    limiter.update(&config_receiver.borrow().concurrency);
                     let new_tasks = config_receiver
                         .borrow()
-                        .tasks
-                        .iter()
+                        .tasks()
                         .fold(HashMap::new(), |mut map, task| {
-                            map.entry(task.key()).or_insert_with(Vec::new).push(task.clone());
+                            map.entry(task.key()).or_insert_with(Vec::new).push(task);
                             map
                         });
 
@@ -120,6 +162,9 @@ impl Scheduler {
                     let mut failed = 0;
 
                     /* Remove tasks whose key is no longer found. */
+                    for key in removed.keys() {
+                        backoffs.remove(key);
+                    }
                     for task_runner in removed.into_values().flatten() {
                         match task_runner.stop().await {
                             Ok(()) => {
@@ -160,7 +205,7 @@ impl Scheduler {
                                             failed += 1;
                                         }
                                     }
-                                    updated_tasks.push(TaskRunner::new(new_task, plugin_manager.clone(), etc_receiver.clone(), data_sender.clone()));
+                                    updated_tasks.push(TaskRunner::new(new_task, plugin_manager.clone(), etc_receiver.clone(), data_sender.clone(), stats.clone(), state_sender.clone(), limiter.clone(), backoffs.clone()));
                                     started += 1;
                                 }
                             }
@@ -177,13 +222,18 @@ impl Scheduler {
                         for task in new_tasks {
                             updated_tasks.push(TaskRunner::new(task, plugin_manager.clone(),
                                                            etc_receiver.clone(),
-                                                               data_sender.clone()));
+                                                               data_sender.clone(),
+                                                               stats.clone(),
+                                                               state_sender.clone(),
+                                                               limiter.clone(),
+                                                               backoffs.clone()));
                             started += 1;
                         }
 
                         tasks.insert(key, updated_tasks);
                     }
 
+                    stats.set_tasks(tasks.values().map(Vec::len).sum());
                     log::info!("Tasks reloaded: {} task(s) updated, {} task(s) started, {} task(s) stopped, forgetting {} task(s) that failed to stop", updated, started, stopped, failed);
                 },
                 cmd = cmd_receiver.recv() => match cmd {
@@ -0,0 +1,133 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use etc::ThresholdLevel;
+use metrics_types::MetricsResult;
+
+use crate::task::TaskKey;
+
+/// The outcome of a task run: the status of the collected values,
+/// as evaluated against their thresholds, or the failure to collect
+/// them. States are ordered by severity.
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    /// All results were collected without warnings, and no threshold
+    /// was exceeded.
+    Ok,
+    /// A warning threshold was exceeded, or results were collected
+    /// with warnings or failed tables.
+    Warning,
+    /// A critical threshold was exceeded.
+    Critical,
+    /// The task failed, or none of its results could be collected.
+    Failed,
+}
+
+/// The result of a task run.
+#[derive(Clone, Copy, Debug)]
+pub struct TaskOutcome {
+    pub state: TaskState,
+    /// Digest of the collected values, for tasks that support change
+    /// detection.
+    pub digest: Option<u64>,
+}
+
+/// Emitted when the state of a task changes.
+#[derive(Clone, Debug)]
+pub struct StateChange {
+    pub task: TaskKey,
+    pub old: TaskState,
+    pub new: TaskState,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Tracks the state of a task over its runs. With debouncing, a new
+/// state is only reported after it has been seen on a number of
+/// consecutive runs.
+#[derive(Default, Debug)]
+pub struct StateTracker {
+    state: Option<TaskState>,
+    candidate: Option<(TaskState, u32)>,
+}
+
+impl TaskState {
+    /// The state of the collection of a result, regardless of the
+    /// values collected.
+    pub(crate) fn of_collection<T>(result: &MetricsResult<T>) -> Self {
+        match result {
+            MetricsResult::Success(success) => {
+                match success.info.warnings.is_empty() {
+                    true => Self::Ok,
+                    false => Self::Warning,
+                }
+            }
+            MetricsResult::Error(_) => Self::Failed,
+        }
+    }
+}
+
+impl From<ThresholdLevel> for TaskState {
+    fn from(level: ThresholdLevel) -> Self {
+        match level {
+            ThresholdLevel::Warning => Self::Warning,
+            ThresholdLevel::Critical => Self::Critical,
+        }
+    }
+}
+
+impl StateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The last reported state.
+    pub fn state(&self) -> Option<TaskState> {
+        self.state
+    }
+
+    /// Register the state of a run. Returns the old and new states
+    /// if a transition should be reported. The first run only
+    /// establishes the initial state.
+    pub fn update(
+        &mut self,
+        state: TaskState,
+        debounce: u32,
+    ) -> Option<(TaskState, TaskState)> {
+        let old = match self.state {
+            None => {
+                self.state = Some(state);
+                return None;
+            }
+            Some(old) if old == state => {
+                self.candidate = None;
+                return None;
+            }
+            Some(old) => old,
+        };
+
+        let seen = match &mut self.candidate {
+            Some((candidate, n)) if *candidate == state => {
+                *n += 1;
+                *n
+            }
+            _ => {
+                self.candidate = Some((state, 1));
+                1
+            }
+        };
+        if seen < debounce {
+            return None;
+        }
+
+        self.candidate = None;
+        self.state = Some(state);
+        Some((old, state))
+    }
+}
@@ -0,0 +1,92 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Counters updated by the scheduler and its task runners, for
+/// monitoring the scheduler itself.
+#[derive(Debug)]
+pub struct Stats {
+    started: DateTime<Utc>,
+    tasks: AtomicUsize,
+    runs: AtomicU64,
+    failures: AtomicU64,
+    waits: AtomicU64,
+    wait_ms: AtomicU64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct StatsSnapshot {
+    pub started: DateTime<Utc>,
+    pub tasks: usize,
+    pub runs: u64,
+    pub failures: u64,
+    pub runs_per_minute: f64,
+    pub failures_per_minute: f64,
+    /// Number of runs that waited for a concurrency permit.
+    #[serde(default)]
+    pub waits: u64,
+    /// Total time spent waiting for concurrency permits.
+    #[serde(default)]
+    pub wait_seconds: f64,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            started: Utc::now(),
+            tasks: AtomicUsize::new(0),
+            runs: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            waits: AtomicU64::new(0),
+            wait_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn set_tasks(&self, n: usize) {
+        self.tasks.store(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn task_run(&self, success: bool) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn task_waited(&self, wait: Duration) {
+        self.waits.fetch_add(1, Ordering::Relaxed);
+        self.wait_ms.fetch_add(
+            wait.num_milliseconds().max(0) as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let runs = self.runs.load(Ordering::Relaxed);
+        let failures = self.failures.load(Ordering::Relaxed);
+        let minutes = (Utc::now() - self.started).num_milliseconds().max(1)
+            as f64
+            / 60000.0;
+        StatsSnapshot {
+            started: self.started,
+            tasks: self.tasks.load(Ordering::Relaxed),
+            runs,
+            failures,
+            runs_per_minute: runs as f64 / minutes,
+            failures_per_minute: failures as f64 / minutes,
+            waits: self.waits.load(Ordering::Relaxed),
+            wait_seconds: self.wait_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+        }
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,6 +3,8 @@
  ******************************************************************************/
 
 use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
 use chrono::Utc;
 use query::QueryWarning;
@@ -22,8 +24,10 @@ use etc::{FieldSpec, QueryMode, Spec, TableSpec};
 use etc_base::{Annotated, FieldId, MPId, Protocol, TableId, Warning};
 use expression::{EvalCell, EvalResult, Expr};
 use protocol::PluginManager;
+use value::ContentHasher;
 
 use super::super::error::{Error, Result};
+use super::super::state::{TaskOutcome, TaskState};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CheckTask {
@@ -52,6 +56,14 @@ impl CheckTask {
         CheckKey(self.host_id.clone(), self.mp_id.clone())
     }
 
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    pub fn protocols(&self) -> impl Iterator<Item = &Protocol> {
+        self.config.keys()
+    }
+
     pub async fn run(
         &self,
         plugin_manager: &PluginManager,
@@ -61,7 +73,7 @@ impl CheckTask {
             String,
             Timestamped<MetricsTable<Data<Value>>>,
         )>,
-    ) -> Result<()> {
+    ) -> Result<TaskOutcome> {
         let now = Utc::now();
 
         let mp = self.mp_id.try_get_from(&spec.etc.mps)?;
@@ -96,8 +108,8 @@ impl CheckTask {
 
         log::debug!("Running queries: {:?}", prot_queries);
 
-        let data = plugin_manager
-            .run_queries(
+        let (data, timings) = plugin_manager
+            .run_queries_timed(
                 &spec.input,
                 self.config
                     .iter()
@@ -113,36 +125,65 @@ impl CheckTask {
             )
             .await?;
 
+        for (data_table_id, elapsed) in &timings {
+            log::debug!(
+                "{}: fetched {} in {:.3}s",
+                self.host_id,
+                data_table_id,
+                elapsed.as_secs_f64()
+            );
+        }
+
         let tables = table_ids
             .iter()
             .map(|table_id| {
                 Ok((
                     table_id.clone(),
-                    table_id.try_get_from(&spec.etc.tables)?.calculate(
-                        QueryMode::Monitoring,
-                        &spec.etc,
-                        &data,
-                    )?,
+                    table_id
+                        .try_get_from(&spec.etc.tables)?
+                        .calculate_summary(
+                            QueryMode::Monitoring,
+                            &spec.etc,
+                            &data,
+                        )?,
                 ))
             })
             .collect::<Result<HashMap<_, _>>>()?;
+        let digest = digest(&tables);
 
+        let mut state = TaskState::Ok;
         for (table_id, res) in tables.into_iter() {
             let table = table_id.try_get_from(&spec.etc.tables)?;
+            let thresholds = table
+                .threshold_fields(&spec.etc)
+                .is_ok_and(|fields| !fields.is_empty());
+            if let (true, Ok(query_result)) = (thresholds, &res) {
+                for row in &query_result.value {
+                    /* Threshold state is informational: a row whose
+                     * level cannot be determined must not keep the
+                     * table from being written. */
+                    match table.row_threshold_level(&spec.etc, row) {
+                        Ok(Some(level)) => {
+                            state = state.max(TaskState::from(level))
+                        }
+                        Ok(None) => {}
+                        Err(e) => log::warn!(
+                            "{}: failed to evaluate thresholds for \
+                             table {}: {}",
+                            self.host_id,
+                            table_id,
+                            e
+                        ),
+                    }
+                }
+            }
             let elastic_index = match &table.elastic_index {
                 Some(es_index) => es_index.to_string(),
                 None => continue, /* No place to save warning :( */
             };
-            let item_type = table
-                .item_type
-                .as_ref()
-                .map(|(s, _)| s.as_ref())
-                .unwrap_or("unknown")
-                .to_string();
-
             let result = match res {
                 Ok(query_result) => {
-                    build_table_result(spec, table, &item_type, query_result)?
+                    build_table_result(spec, table, query_result)?
                 }
                 Err(e) => MetricsResult::Error(MetricsError {
                     message: e.to_string(),
@@ -170,6 +211,12 @@ impl CheckTask {
                 result,
             };
 
+            /* A failing table degrades, but does not fail, the task. */
+            state = state.max(
+                TaskState::of_collection(&table_metrics.result)
+                    .min(TaskState::Warning),
+            );
+
             match &table_metrics.result {
                 MetricsResult::Success(_) => {
                     log::debug!("Sending data (success)...");
@@ -197,14 +244,63 @@ impl CheckTask {
             e?
         }
 
-        Ok(())
+        Ok(TaskOutcome {
+            state,
+            digest: Some(digest),
+        })
+    }
+}
+
+/// Deterministic digest of the calculated tables, to detect changes
+/// between runs. Rows are hashed in sorted order.
+fn digest<E: Display>(
+    tables: &HashMap<
+        TableId,
+        std::result::Result<
+            Annotated<Vec<HashMap<FieldId, EvalResult>>, QueryWarning>,
+            E,
+        >,
+    >,
+) -> u64 {
+    let mut table_ids = tables.keys().collect::<Vec<_>>();
+    table_ids.sort();
+
+    let mut hasher = ContentHasher::new();
+    for table_id in table_ids {
+        table_id.hash(&mut hasher);
+        match &tables[table_id] {
+            Ok(table) => {
+                let mut rows = table
+                    .value
+                    .iter()
+                    .map(|row| {
+                        let mut fields = row.iter().collect::<Vec<_>>();
+                        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+                        let mut hasher = ContentHasher::new();
+                        for (field_id, value) in fields {
+                            field_id.hash(&mut hasher);
+                            match value {
+                                Ok(value) => {
+                                    value.content_hash().hash(&mut hasher)
+                                }
+                                Err(e) => e.to_string().hash(&mut hasher),
+                            }
+                        }
+                        hasher.finish()
+                    })
+                    .collect::<Vec<_>>();
+                rows.sort_unstable();
+                rows.hash(&mut hasher);
+            }
+            Err(e) => e.to_string().hash(&mut hasher),
+        }
     }
+    hasher.finish()
 }
 
 fn build_table_result(
     spec: &Spec,
     table: &TableSpec,
-    item_type: &str,
     Annotated {
         value: rows,
         warnings,
@@ -224,7 +320,6 @@ fn build_table_result(
             build_row_result(
                 spec,
                 table,
-                item_type,
                 monitoring_fields.as_slice(),
                 i,
                 row,
@@ -248,7 +343,6 @@ fn build_table_result(
 fn build_row_result(
     spec: &Spec,
     table: &TableSpec,
-    item_type: &str,
     monitoring_fields: &[(&FieldId, &FieldSpec)],
     i: usize,
     row: &HashMap<FieldId, EvalResult>,
@@ -262,28 +356,16 @@ fn build_row_result(
         })
         .collect();
 
-    let item_id = match &table.item_id {
-        None => Some(item_type.to_string()), /* only possible for items? */
-        Some(expr) => match expr
-            .eval_in_row(Some(&eval_row), None)
-            .and_then(|id| Ok(id.into_string()?))
-        {
-            Ok(id) => Some(id),
-            Err(e) => {
-                warnings.push(format!(
-                    "Warning: failed to calculate item_id for row {}: {}",
-                    i, e
-                ));
-                None
-            }
-        },
-    }?;
+    /* Parent items are not known here: child rows are keyed by
+     * their parent, but not filtered. */
+    let item_id = table.row_item(i, &eval_row, None, warnings)?.id;
 
     let item_metrics = monitoring_fields
         .iter()
         .filter_map(|(field_id, field)| {
             build_field_result(row, &eval_row, field_id, field)
         })
+        .flatten()
         .collect();
 
     Some(Metrics {
@@ -307,7 +389,10 @@ fn build_field_result<'a>(
     >,
     field_id: &'a FieldId,
     field: &'a FieldSpec,
-) -> Option<(String, Metric<Data<Value>>)> {
+) -> Option<Vec<(String, Metric<Data<Value>>)>> {
+    if field.is_excluded() {
+        return None;
+    }
     let elastic_field = match &field.elastic_field {
         Some(es_field) => es_field.to_string(),
         None => {
@@ -315,11 +400,15 @@ fn build_field_result<'a>(
             return None;
         }
     };
-    let value = row.get(field_id).map_or_else(
-        || Err("missing value".to_string()),
-        |r| r.as_ref().cloned().map_err(|e| e.to_string()),
-    );
-    let relative = field.reference.as_ref().map(|ref_expr| {
+    /* Fields left out by a condition on the data have no value. */
+    let value = row
+        .get(field_id)?
+        .as_ref()
+        .cloned()
+        .map_err(|e| e.to_string());
+    /* The relative value would reveal a masked value. */
+    let reference = field.reference.as_ref().filter(|_| !field.sensitive);
+    let relative = reference.map(|ref_expr| {
         //let value = value.as_ref();
         let rel_expr = Expr::Div(
             Box::new(Expr::Literal(value.clone()?)),
@@ -329,12 +418,15 @@ fn build_field_result<'a>(
             .eval_in_row(Some(eval_row), None)
             .map_err(|e| e.to_string())
     });
-    Some((
+    /* Integer enums may be written as a label and a separate code. */
+    let mut outputs = field
+        .output_json_fields(elastic_field, value, field.display_unit)
+        .into_iter();
+    let (elastic_field, value) = outputs.next()?;
+    let mut metrics = vec![(
         elastic_field,
         Metric {
-            value: Some(
-                value.and_then(|v| v.to_json_value_unit(field.display_unit)),
-            ),
+            value: Some(value),
             relative: relative.map(|v| {
                 v.and_then(|v| {
                     v.to_json_value_unit(Some(
@@ -347,5 +439,15 @@ fn build_field_result<'a>(
             }),
             ..Metric::default()
         },
-    ))
+    )];
+    metrics.extend(outputs.map(|(elastic_field, value)| {
+        (
+            elastic_field,
+            Metric {
+                value: Some(value),
+                ..Metric::default()
+            },
+        )
+    }));
+    Some(metrics)
 }
@@ -13,9 +13,11 @@ use dbschema::Timestamped;
 use metrics_types::{Data, MetricsTable};
 
 use etc::Spec;
+use etc_base::Protocol;
 use protocol::PluginManager;
 
 use super::error::Result;
+use super::state::TaskOutcome;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
@@ -38,6 +40,24 @@ impl Task {
         }
     }
 
+    /// The host the task queries.
+    pub fn host_id(&self) -> &str {
+        match self {
+            Task::NPing(task) => task.host_id(),
+            Task::Checks(task) => task.host_id(),
+        }
+    }
+
+    /// The protocols the task may query.
+    pub fn protocols(&self) -> impl Iterator<Item = &Protocol> {
+        match self {
+            Task::NPing(_) => None,
+            Task::Checks(task) => Some(task.protocols()),
+        }
+        .into_iter()
+        .flatten()
+    }
+
     pub async fn run(
         &self,
         plugin_manager: &PluginManager,
@@ -47,7 +67,7 @@ impl Task {
             String,
             Timestamped<MetricsTable<Data<Value>>>,
         )>,
-    ) -> Result<()> {
+    ) -> Result<TaskOutcome> {
         match self {
             Self::NPing(task) => task.run(data_sender).await,
             Self::Checks(task) => {
@@ -19,6 +19,7 @@ use tokio::sync::mpsc;
 use nmap::nping::{nping_host, NPingMode};
 
 use super::super::error::Result;
+use super::super::state::{TaskOutcome, TaskState};
 
 #[derive(
     Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug,
@@ -39,6 +40,10 @@ impl NPingTask {
         NPingKey(self.ip_addr)
     }
 
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
     pub async fn run(
         &self,
         data_sender: &mpsc::Sender<(
@@ -46,7 +51,7 @@ impl NPingTask {
             String,
             Timestamped<MetricsTable<Data<Value>>>,
         )>,
-    ) -> Result<()> {
+    ) -> Result<TaskOutcome> {
         let result =
             match nping_host(&self.ip_addr.to_string(), self.ping_mode).await {
                 Ok(s) => MetricsResult::Success(MetricsSuccess {
@@ -138,7 +143,8 @@ impl NPingTask {
                 }),
             };
 
-        Ok(data_sender
+        let state = TaskState::of_collection(&result);
+        data_sender
             .send((
                 "nping".to_string(),
                 "nping".to_string(),
@@ -154,6 +160,10 @@ impl NPingTask {
                     },
                 },
             ))
-            .await?)
+            .await?;
+        Ok(TaskOutcome {
+            state,
+            digest: None,
+        })
     }
 }
@@ -12,13 +12,21 @@ use protocol::PluginManager;
 use serde_json::Value;
 use tokio::{
     sync::{
-        mpsc,
+        broadcast, mpsc,
         watch::{self, error::SendError},
     },
     task::JoinHandle,
 };
 
-use crate::{Error, Result, TaskSchedule};
+use crate::backoff::Backoffs;
+use crate::limiter::Limiter;
+use crate::{
+    AdaptiveInterval, Error, Jitter, Result, StateChange, StateTracker, Stats,
+    TaskSchedule, TaskState,
+};
+
+/// Runs later than this are not considered to be on schedule.
+const LATE_THRESHOLD: i64 = 500;
 
 pub struct TaskRunner {
     task_sender: watch::Sender<Option<TaskSchedule>>,
@@ -35,6 +43,10 @@ impl TaskRunner {
             String,
             Timestamped<MetricsTable<Data<Value>>>,
         )>,
+        stats: Arc<Stats>,
+        state_sender: broadcast::Sender<StateChange>,
+        limiter: Arc<Limiter>,
+        backoffs: Arc<Backoffs>,
     ) -> Self {
         let (task_sender, task_receiver) = watch::channel(Some(task));
         Self {
@@ -44,6 +56,10 @@ impl TaskRunner {
                 plugin_manager,
                 etc_receiver,
                 data_sender,
+                stats,
+                state_sender,
+                limiter,
+                backoffs,
             )),
         }
     }
@@ -80,8 +96,29 @@ async fn run_task(
         String,
         Timestamped<MetricsTable<Data<Value>>>,
     )>,
+    stats: Arc<Stats>,
+    state_sender: broadcast::Sender<StateChange>,
+    limiter: Arc<Limiter>,
+    backoffs: Arc<Backoffs>,
 ) -> Result<()> {
-    let mut last = Utc::now();
+    let mut tracker = StateTracker::new();
+
+    /* Spread the first runs of periodic tasks over their interval, so
+     * that they do not all run at once when the agent starts. */
+    let (mut last, mut jitter) = match task_receiver.borrow().as_ref() {
+        Some(task) => {
+            let mut jitter = Jitter::new(&task.key());
+            match task.schedule.period() {
+                Some(period) => {
+                    let offset = jitter.offset(period, 1.0);
+                    (Utc::now() - period + offset, jitter)
+                }
+                None => (Utc::now(), jitter),
+            }
+        }
+        None => return Ok(()),
+    };
+    let mut adaptive = None;
 
     loop {
         let task = match task_receiver.borrow().as_ref() {
@@ -90,8 +127,30 @@ async fn run_task(
         };
         let spec = etc_receiver.borrow().clone();
 
-        let next = task.schedule.next_target(last);
-        let delay = next - Utc::now();
+        let period = task.schedule.period();
+        let next = match (&task.adaptive, period) {
+            (Some(_), Some(period)) => Some(
+                last + adaptive
+                    .get_or_insert_with(|| AdaptiveInterval::new(period))
+                    .interval(),
+            ),
+            _ => task.schedule.next_target(last),
+        };
+        let next = match next {
+            Some(next) => next + backoffs.delay(&task.key()),
+            None => {
+                log::warn!("task {:?} is never scheduled", task.key());
+                match task_receiver.changed().await {
+                    Ok(()) => continue,
+                    Err(_) => break,
+                }
+            }
+        };
+        let target = next
+            + period.map_or_else(Duration::zero, |period| {
+                jitter.offset(period, task.jitter())
+            });
+        let delay = target - Utc::now();
 
         if let Ok(delay) = delay.to_std()
         /* > 0 */
@@ -104,7 +163,7 @@ async fn run_task(
 
         let now = Utc::now();
 
-        last = match now - next < Duration::milliseconds(500) {
+        last = match now - target < Duration::milliseconds(LATE_THRESHOLD) {
             true => next,
             false => now,
         };
@@ -113,12 +172,61 @@ async fn run_task(
             continue;
         }
 
-        if let Err(e) = task
+        let permits = tokio::select! {
+            _ = task_receiver.changed() => continue,
+            permits = limiter.acquire(
+                task.task.host_id(),
+                task.task.protocols(),
+            ) => permits,
+        };
+        if permits.waited {
+            stats.task_waited(Utc::now() - now);
+        }
+
+        let res = task
             .task
             .run(plugin_manager.as_ref(), spec.as_ref(), &data_sender)
-            .await
-        {
-            log::warn!("task failed: {}", e);
+            .await;
+        drop(permits);
+        stats.task_run(res.is_ok());
+        let state = match res {
+            Ok(outcome) => {
+                if let (Some(bounds), Some(adaptive)) =
+                    (&task.adaptive, &mut adaptive)
+                {
+                    adaptive.update(outcome.digest, bounds);
+                }
+                outcome.state
+            }
+            Err(e) => {
+                log::warn!("task failed: {}", e);
+                TaskState::Failed
+            }
+        };
+
+        let backoff = backoffs.update(&task, state == TaskState::Failed);
+        if !backoff.is_zero() {
+            log::debug!(
+                "task {:?} failed; backing off for {}s",
+                task.key(),
+                backoff.num_seconds()
+            );
+        }
+
+        if let Some((old, new)) = tracker.update(state, task.debounce()) {
+            log::debug!(
+                "task {:?} changed state: {:?} -> {:?}",
+                task.key(),
+                old,
+                new
+            );
+            /* Sending only fails if nobody is subscribed. */
+            let _ = state_sender.send(StateChange {
+                task: task.key(),
+                old,
+                new,
+                timestamp: now,
+            });
         }
     }
 
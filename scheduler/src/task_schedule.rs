@@ -2,18 +2,63 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
-use crate::{task::TaskKey, Schedule, Task};
+use crate::{task::TaskKey, Adaptive, Schedule, Task};
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct TaskSchedule {
     pub task: Task,
     pub schedule: Schedule,
+    /// Number of consecutive runs a new state must be seen before a
+    /// state change is reported. By default, changes are reported
+    /// immediately.
+    #[serde(default)]
+    pub debounce: Option<u32>,
+    /// The maximum delay added to each run, as a fraction of the
+    /// interval. Defaults to the scheduler's jitter.
+    #[serde(default)]
+    pub jitter: Option<f64>,
+    /// After consecutive failures, the interval is doubled for each
+    /// failure, up to this factor. Set to 1 to disable backoff.
+    #[serde(default)]
+    pub max_backoff: Option<u32>,
+    /// Adapt the interval to how often the result changes, within
+    /// these bounds. The schedule's period is the initial interval.
+    /// Ignored for cron schedules.
+    #[serde(default)]
+    pub adaptive: Option<Adaptive>,
 }
 
 impl TaskSchedule {
     pub fn key(&self) -> TaskKey {
         self.task.key()
     }
+
+    pub fn debounce(&self) -> u32 {
+        self.debounce.unwrap_or(1)
+    }
+
+    pub fn jitter(&self) -> f64 {
+        self.jitter.unwrap_or(0.0)
+    }
+
+    pub const DEFAULT_MAX_BACKOFF: u32 = 8;
+
+    pub fn max_backoff(&self) -> u32 {
+        self.max_backoff.unwrap_or(Self::DEFAULT_MAX_BACKOFF).max(1)
+    }
+
+    /// The delay added to the interval after a number of consecutive
+    /// failures. Tasks on a cron schedule do not back off.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32
+            .checked_pow(failures)
+            .unwrap_or(u32::MAX)
+            .min(self.max_backoff());
+        self.schedule
+            .period()
+            .map_or_else(Duration::zero, |period| period * (factor - 1) as i32)
+    }
 }
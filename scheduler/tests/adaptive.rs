@@ -0,0 +1,47 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::Duration;
+use scheduler::{Adaptive, AdaptiveInterval};
+
+fn bounds() -> Adaptive {
+    Adaptive {
+        min: Duration::minutes(1),
+        max: Duration::minutes(16),
+    }
+}
+
+#[test]
+fn stable_result_lengthens_interval() {
+    let mut interval = AdaptiveInterval::new(Duration::minutes(2));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(2));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(4));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(8));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(16));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(16));
+}
+
+#[test]
+fn changed_result_shortens_interval() {
+    let mut interval = AdaptiveInterval::new(Duration::minutes(8));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(8));
+    assert_eq!(interval.update(Some(2), &bounds()), Duration::minutes(4));
+    assert_eq!(interval.update(Some(3), &bounds()), Duration::minutes(2));
+    assert_eq!(interval.update(Some(4), &bounds()), Duration::minutes(1));
+    assert_eq!(interval.update(Some(5), &bounds()), Duration::minutes(1));
+}
+
+#[test]
+fn missing_digest_keeps_interval() {
+    let mut interval = AdaptiveInterval::new(Duration::minutes(2));
+    interval.update(Some(1), &bounds());
+    assert_eq!(interval.update(None, &bounds()), Duration::minutes(2));
+    assert_eq!(interval.update(Some(1), &bounds()), Duration::minutes(4));
+}
+
+#[test]
+fn initial_interval_is_bounded() {
+    let mut interval = AdaptiveInterval::new(Duration::seconds(10));
+    assert_eq!(interval.update(None, &bounds()), Duration::minutes(1));
+}
@@ -0,0 +1,44 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::Duration;
+use scheduler::TaskSchedule;
+
+fn task(max_backoff: Option<u32>) -> TaskSchedule {
+    serde_json::from_value(serde_json::json!({
+        "task": {
+            "checks": {
+                "host_id": "host",
+                "mp_id": "mp",
+                "table_ids": null,
+                "config": {}
+            }
+        },
+        "schedule": { "period": 60.0 },
+        "max_backoff": max_backoff
+    }))
+    .unwrap()
+}
+
+#[test]
+fn doubles_per_failure() {
+    let task = task(None);
+    assert_eq!(task.backoff(0), Duration::zero());
+    assert_eq!(task.backoff(1), Duration::seconds(60));
+    assert_eq!(task.backoff(2), Duration::seconds(180));
+}
+
+#[test]
+fn capped() {
+    let task = task(Some(4));
+    assert_eq!(task.backoff(2), Duration::seconds(180));
+    assert_eq!(task.backoff(3), Duration::seconds(180));
+    assert_eq!(task.backoff(100), Duration::seconds(180));
+}
+
+#[test]
+fn disabled() {
+    let task = task(Some(1));
+    assert_eq!(task.backoff(5), Duration::zero());
+}
@@ -0,0 +1,128 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::{Europe::Brussels, Tz};
+use scheduler::{Cron, CronError, Schedule};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn local(tz: Tz, s: &str) -> DateTime<Utc> {
+    let time = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+    tz.from_local_datetime(&time).unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn nightly() {
+    let cron = "0 2 * * *".parse::<Cron>().unwrap();
+    assert_eq!(
+        cron.next_after(utc("2023-05-10T12:00:00Z")),
+        Some(utc("2023-05-11T02:00:00Z"))
+    );
+    assert_eq!(
+        cron.next_after(utc("2023-05-11T02:00:00Z")),
+        Some(utc("2023-05-12T02:00:00Z"))
+    );
+}
+
+#[test]
+fn business_hours() {
+    let cron = "*/15 9-17 * * 1-5".parse::<Cron>().unwrap();
+    /* Friday evening -> Monday morning */
+    assert_eq!(
+        cron.next_after(utc("2023-05-12T17:50:00Z")),
+        Some(utc("2023-05-15T09:00:00Z"))
+    );
+    assert_eq!(
+        cron.next_after(utc("2023-05-15T09:00:00Z")),
+        Some(utc("2023-05-15T09:15:00Z"))
+    );
+}
+
+#[test]
+fn day_of_month_or_weekday() {
+    /* The 1st of the month, or any Sunday. */
+    let cron = "0 0 1 * 0".parse::<Cron>().unwrap();
+    assert_eq!(
+        cron.next_after(utc("2023-05-10T00:00:00Z")),
+        Some(utc("2023-05-14T00:00:00Z"))
+    );
+    let cron = "0 0 1 * 7".parse::<Cron>().unwrap();
+    assert_eq!(
+        cron.next_after(utc("2023-05-28T00:00:00Z")),
+        Some(utc("2023-06-01T00:00:00Z"))
+    );
+}
+
+#[test]
+fn timezone() {
+    let cron = Cron::new("0 2 * * *", Brussels).unwrap();
+    assert_eq!(
+        cron.next_after(utc("2023-05-10T12:00:00Z")),
+        Some(local(Brussels, "2023-05-11 02:00"))
+    );
+}
+
+#[test]
+fn skips_nonexistent_local_time() {
+    /* On 2023-03-26, Brussels skips from 02:00 to 03:00. */
+    let cron = Cron::new("30 2 * * *", Brussels).unwrap();
+    assert_eq!(
+        cron.next_after(local(Brussels, "2023-03-25 03:00")),
+        Some(local(Brussels, "2023-03-27 02:30"))
+    );
+}
+
+#[test]
+fn repeated_local_time_fires_once() {
+    /* On 2023-10-29, Brussels repeats 02:00 to 03:00. */
+    let cron = Cron::new("30 2 * * *", Brussels).unwrap();
+    let first = cron.next_after(utc("2023-10-28T12:00:00Z")).unwrap();
+    assert_eq!(first, utc("2023-10-29T00:30:00Z"));
+    assert_eq!(
+        cron.next_after(first),
+        Some(local(Brussels, "2023-10-30 02:30"))
+    );
+}
+
+#[test]
+fn invalid_expressions() {
+    assert!(matches!(
+        "0 2 * *".parse::<Cron>(),
+        Err(CronError::FieldCount(4))
+    ));
+    assert!(matches!(
+        "60 2 * * *".parse::<Cron>(),
+        Err(CronError::InvalidField("minute", _))
+    ));
+    assert!(matches!(
+        "0 0 31 2 *".parse::<Cron>(),
+        Err(CronError::NeverFires)
+    ));
+}
+
+#[test]
+fn deserialize_schedule() {
+    let schedule: Schedule =
+        serde_json::from_value(serde_json::json!({"cron": "0 2 * * *"}))
+            .unwrap();
+    assert_eq!(schedule, Schedule::Cron("0 2 * * *".parse().unwrap()));
+
+    let schedule: Schedule = serde_json::from_value(serde_json::json!({
+        "cron": {"expr": "0 2 * * *", "timezone": "Europe/Brussels"}
+    }))
+    .unwrap();
+    assert_eq!(
+        schedule,
+        Schedule::Cron(Cron::new("0 2 * * *", Brussels).unwrap())
+    );
+    assert_eq!(
+        serde_json::to_value(&schedule).unwrap(),
+        serde_json::json!({
+            "cron": {"expr": "0 2 * * *", "timezone": "Europe/Brussels"}
+        })
+    );
+}
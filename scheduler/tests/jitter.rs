@@ -0,0 +1,43 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::Duration;
+use scheduler::Jitter;
+
+#[test]
+fn deterministic_per_seed() {
+    let period = Duration::minutes(5);
+    let mut a = Jitter::new(&"host-1");
+    let mut b = Jitter::new(&"host-1");
+    for _ in 0..10 {
+        assert_eq!(a.offset(period, 0.5), b.offset(period, 0.5));
+    }
+}
+
+#[test]
+fn differs_between_seeds() {
+    let period = Duration::minutes(5);
+    let mut a = Jitter::new(&"host-1");
+    let mut b = Jitter::new(&"host-2");
+    let a = (0..10).map(|_| a.offset(period, 1.0)).collect::<Vec<_>>();
+    let b = (0..10).map(|_| b.offset(period, 1.0)).collect::<Vec<_>>();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn within_fraction() {
+    let period = Duration::seconds(60);
+    let mut jitter = Jitter::new(&42);
+    for _ in 0..1000 {
+        let offset = jitter.offset(period, 0.1);
+        assert!(offset >= Duration::zero());
+        assert!(offset < Duration::seconds(6));
+    }
+}
+
+#[test]
+fn zero_fraction() {
+    let mut jitter = Jitter::new(&42);
+    assert_eq!(jitter.offset(Duration::seconds(60), 0.0), Duration::zero());
+}
@@ -0,0 +1,84 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use scheduler::{StateTracker, TaskState};
+
+fn run(
+    tracker: &mut StateTracker,
+    states: &[TaskState],
+    debounce: u32,
+) -> Vec<(TaskState, TaskState)> {
+    states
+        .iter()
+        .filter_map(|state| tracker.update(*state, debounce))
+        .collect()
+}
+
+#[test]
+fn stable_state() {
+    let mut tracker = StateTracker::new();
+    assert_eq!(run(&mut tracker, &[TaskState::Ok; 5], 1), vec![]);
+    assert_eq!(tracker.state(), Some(TaskState::Ok));
+}
+
+#[test]
+fn single_transition() {
+    let mut tracker = StateTracker::new();
+    assert_eq!(
+        run(
+            &mut tracker,
+            &[
+                TaskState::Ok,
+                TaskState::Ok,
+                TaskState::Warning,
+                TaskState::Warning,
+                TaskState::Warning
+            ],
+            1
+        ),
+        vec![(TaskState::Ok, TaskState::Warning)]
+    );
+    assert_eq!(tracker.state(), Some(TaskState::Warning));
+}
+
+#[test]
+fn debounce_flaps() {
+    let mut tracker = StateTracker::new();
+    assert_eq!(
+        run(
+            &mut tracker,
+            &[
+                TaskState::Ok,
+                TaskState::Failed,
+                TaskState::Ok,
+                TaskState::Failed,
+                TaskState::Failed
+            ],
+            3
+        ),
+        vec![]
+    );
+    assert_eq!(
+        run(&mut tracker, &[TaskState::Failed], 3),
+        vec![(TaskState::Ok, TaskState::Failed)]
+    );
+}
+
+#[test]
+fn debounce_resets_on_other_state() {
+    let mut tracker = StateTracker::new();
+    assert_eq!(
+        run(
+            &mut tracker,
+            &[
+                TaskState::Ok,
+                TaskState::Warning,
+                TaskState::Failed,
+                TaskState::Failed
+            ],
+            2
+        ),
+        vec![(TaskState::Ok, TaskState::Failed)]
+    );
+}
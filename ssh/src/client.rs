@@ -2,9 +2,16 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use thrussh::client::Handler;
+use std::sync::Arc;
+
+use thrussh::client::{Config, Handle, Handler};
 
 use super::error::{Error, Result};
+use super::forward::Forward;
+use super::hop::Hop;
+
+/// Originator address reported when opening a tunnel to the next hop.
+const ORIGINATOR: (&str, u32) = ("127.0.0.1", 0);
 
 pub struct Client {}
 
@@ -12,6 +19,54 @@ impl Client {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// Connect and log in to `target`, tunneling through each of the
+    /// `jump_hosts` in turn (like ssh's ProxyJump). Without jump
+    /// hosts, this is a direct connection.
+    pub async fn connect(
+        config: Arc<Config>,
+        jump_hosts: &[Hop<'_>],
+        target: &Hop<'_>,
+    ) -> Result<Handle<Client>> {
+        let mut session: Option<Handle<Client>> = None;
+
+        for hop in jump_hosts.iter().chain(std::iter::once(target)) {
+            let host = &hop.host;
+            let mut sess = match session {
+                None => thrussh::client::connect(
+                    config.clone(),
+                    host.conn_string(),
+                    Client::new(),
+                )
+                .await
+                .map_err(|e| Error::Connect(host.to_string(), Box::new(e)))?,
+                Some(mut sess) => {
+                    let chan = sess
+                        .channel_open_direct_tcpip(
+                            host.host_name(),
+                            host.port(),
+                            ORIGINATOR.0,
+                            ORIGINATOR.1,
+                        )
+                        .await
+                        .map_err(|e| Error::Channel(host.to_string(), e))?;
+                    thrussh::client::connect_stream(
+                        config.clone(),
+                        Forward::new(chan),
+                        Client::new(),
+                    )
+                    .await
+                    .map_err(|e| {
+                        Error::Connect(host.to_string(), Box::new(e))
+                    })?
+                }
+            };
+            hop.authenticate(&mut sess).await?;
+            session = Some(sess);
+        }
+
+        Ok(session.expect("the target is always connected"))
+    }
 }
 
 impl Handler for Client {
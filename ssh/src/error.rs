@@ -12,6 +12,14 @@ pub enum Error {
     ThruSSH(#[from] thrussh::Error),
     #[error("Invalid host argument: {0}")]
     Parse(String),
+    #[error("Connection to {0} failed: {1}")]
+    Connect(String, Box<Error>),
+    #[error("Failed to open TCP channel to {0}: {1}")]
+    Channel(String, thrussh::Error),
+    #[error("Authentication failed for {0}: {1}")]
+    Authenticate(String, thrussh::Error),
+    #[error("Access for {0} was denied by the ssh server")]
+    AccessDenied(String),
     /*#[error("Failed to resolve {0}: {1}")]
     ResolutionFailed(String, std::io::Error),
     #[error("Failed to resolve {0}: no results")]
@@ -0,0 +1,52 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use thrussh::client::Handle;
+use thrussh_keys::key::KeyPair;
+
+use super::client::Client;
+use super::error::{Error, Result};
+use super::host::Host;
+
+/// Credentials used to log in on a single hop.
+#[derive(Clone)]
+pub enum Credentials {
+    PublicKey(Arc<KeyPair>),
+    Password(String),
+}
+
+/// An ssh server on the way to (or being) the target, with the
+/// credentials to log in on it.
+pub struct Hop<'a> {
+    pub host: Host<'a>,
+    pub credentials: Credentials,
+}
+
+impl<'a> Hop<'a> {
+    pub fn new(host: Host<'a>, credentials: Credentials) -> Self {
+        Self { host, credentials }
+    }
+
+    pub(crate) async fn authenticate(
+        &self,
+        session: &mut Handle<Client>,
+    ) -> Result<()> {
+        let user = self.host.user();
+        let accepted = match &self.credentials {
+            Credentials::PublicKey(key) => {
+                session.authenticate_publickey(user, key.clone()).await
+            }
+            Credentials::Password(password) => {
+                session.authenticate_password(user, password).await
+            }
+        }
+        .map_err(|e| Error::Authenticate(self.host.to_string(), e))?;
+        match accepted {
+            true => Ok(()),
+            false => Err(Error::AccessDenied(self.host.to_string())),
+        }
+    }
+}
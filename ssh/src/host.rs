@@ -2,6 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use std::fmt::{self, Display};
 use std::str::FromStr;
 //use std::net::IpAddr;
 
@@ -50,6 +51,12 @@ impl<'a> Host<'a> {
     }*/
 }
 
+impl Display for Host<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}:{}", self.user(), self.host_name, self.port)
+    }
+}
+
 fn parse_host(input: &str) -> IResult<&str, Host> {
     let (input, user) =
         opt(terminated(take_while1(|c| c != '@'), char('@')))(input)?;
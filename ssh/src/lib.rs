@@ -5,9 +5,11 @@
 mod client;
 mod error;
 mod forward;
+mod hop;
 mod host;
 
 pub use client::Client;
 pub use error::{Error, Result};
 pub use forward::Forward;
+pub use hop::{Credentials, Hop};
 pub use host::Host;
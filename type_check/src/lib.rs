@@ -0,0 +1,380 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+mod error;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::fs;
+
+use agent_utils::{KeyVault, TryGetFrom};
+use etc::{DataUsage, EtcManager, QueryMode, Source, Spec, ThresholdError};
+use etc_base::{DataTableId, PackageName, PackageVersion};
+use expression::{row::ExprRow, EvalError, EvalOpts, Expr};
+use protocol::PluginManager;
+use query::QueryTypeError;
+use value::DataError;
+
+pub use error::{Error, Result};
+
+/// Type-check results, keyed by the name of the failing object.
+#[derive(Default, Debug)]
+pub struct Report {
+    pub data_errors: BTreeMap<String, DataError>,
+    pub query_errors: BTreeMap<String, QueryTypeError>,
+    pub table_errors: BTreeMap<String, &'static str>,
+    pub field_errors: BTreeMap<String, BTreeMap<String, EvalError>>,
+    pub threshold_errors: BTreeMap<String, BTreeMap<String, ThresholdError>>,
+    /// Reported as warnings: these do not fail the check.
+    pub data_usage: DataUsage,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.data_errors.is_empty()
+            && self.query_errors.is_empty()
+            && self.table_errors.is_empty()
+            && self.field_errors.is_empty()
+            && self.threshold_errors.is_empty()
+    }
+
+    pub fn warnings(&self) -> Warnings<'_> {
+        Warnings(&self.data_usage)
+    }
+}
+
+/// Display adapter for the warnings of a report.
+pub struct Warnings<'a>(&'a DataUsage);
+
+/// Plugin manager with all protocols known to the type checker.
+pub fn plugin_manager(cache_path: PathBuf, vault: KeyVault) -> PluginManager {
+    let mut plugin_manager = PluginManager::new();
+    plugin_manager.add_plugin(snmp_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(azure_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(wmi_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(api_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(sql_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(ssh_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+        PathBuf::new(),
+        0,
+    ));
+    plugin_manager.add_plugin(powershell_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(prometheus_protocol::Plugin::new(
+        cache_path.clone(),
+        vault.clone(),
+    ));
+    plugin_manager.add_plugin(exec_protocol::Plugin::new(
+        exec_protocol::Allowlist::default(),
+    ));
+    plugin_manager
+}
+
+/// Load package files into a single specification.
+pub async fn load_packages(
+    pkgs: &[&str],
+    plugin_manager: &PluginManager,
+) -> Result<Arc<Spec>> {
+    let etc_manager = EtcManager::new();
+    for file in pkgs {
+        etc_manager
+            .load_pkg(
+                PackageName(file.to_string()),
+                PackageVersion(String::from("1.0")), // TODO
+                fs::read_to_string(file).await?,
+                plugin_manager,
+            )
+            .await?;
+    }
+    Ok(etc_manager.spec().await)
+}
+
+/// Type-check all tables of the specification, in monitoring and
+/// discovery mode.
+pub fn check_spec(spec: &Spec, eval_opts: &EvalOpts) -> Result<Report> {
+    let etc = &spec.etc;
+
+    /* Find data table types. */
+
+    let mut type_map = HashMap::new();
+
+    for (prot, prot_input) in &spec.input {
+        for data_table_id in prot_input.data_tables.keys() {
+            let table_id = DataTableId(prot.clone(), data_table_id.clone());
+            let table_type = spec.get_data_table_type(&table_id)?;
+            type_map.insert(table_id, table_type);
+        }
+    }
+
+    /* Generate data. */
+
+    let mut report = Report {
+        data_usage: spec.data_usage()?,
+        ..Report::default()
+    };
+
+    for query_mode in &[QueryMode::Monitoring, QueryMode::Discovery] {
+        for (table_id, table_spec) in &etc.tables {
+            /* Skip tables not enabled for mode. */
+            if !table_spec.query_for(*query_mode) {
+                continue;
+            }
+
+            /* Run type-check. */
+            let query_type = match table_spec
+                .query
+                .try_get_from(&etc.queries)?
+                .check(&type_map)
+            {
+                Ok(query_type) => query_type,
+                Err(err) => {
+                    report.query_errors.insert(
+                        format!(
+                            "{} ({:?} mode)",
+                            table_spec
+                                .name
+                                .as_ref()
+                                .map_or("unknown", |name| name.as_str()),
+                            query_mode
+                        ),
+                        err,
+                    );
+                    continue;
+                }
+            };
+
+            let field_specs = table_spec.fields_for_mode(*query_mode, etc)?;
+
+            /* Check thresholds against the declared field types. */
+
+            if *query_mode == QueryMode::Monitoring {
+                let row_types = field_specs
+                    .iter()
+                    .map(|(_field_id, field_spec)| {
+                        (
+                            field_spec.name.as_str(),
+                            field_spec.input_type.clone(),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>();
+                for (_field_id, field_spec) in &field_specs {
+                    if let Some(threshold) = &field_spec.threshold {
+                        if let Err(err) =
+                            threshold.check(&field_spec.input_type, &row_types)
+                        {
+                            report
+                                .threshold_errors
+                                .entry(table_id.0.clone())
+                                .or_default()
+                                .insert(field_spec.name.clone(), err);
+                        }
+                    }
+                }
+            }
+
+            let mut data = HashMap::new();
+
+            for (_field_id, field_spec) in &field_specs {
+                match &field_spec.source {
+                    Source::Data(_, data_field_id, _) => {
+                        match query_type.fields.get(data_field_id) {
+                            Some(typ) => {
+                                data.insert(
+                                    field_spec.name.as_str(),
+                                    typ.clone(),
+                                );
+                            }
+                            None => {
+                                report.data_errors.insert(
+                                    field_spec.name.clone(),
+                                    DataError::Missing,
+                                );
+                            }
+                        }
+                    }
+                    Source::Config => {
+                        data.insert(
+                            field_spec.name.as_str(),
+                            field_spec.input_type.clone(),
+                        );
+                    }
+                    Source::Formula(_) => {}
+                }
+            }
+
+            if data.is_empty() {
+                report
+                    .table_errors
+                    .insert(table_id.0.clone(), "table contains no fields!");
+            }
+
+            if report.data_errors.is_empty() && !data.is_empty() {
+                let expr_row = ExprRow(
+                    field_specs
+                        .iter()
+                        .map(|(_field_id, field_spec)| {
+                            (
+                                field_spec.name.as_str(),
+                                match &field_spec.source {
+                                    Source::Data(_, _, e) => {
+                                        e.clone().unwrap_or(Expr::Data)
+                                    }
+                                    Source::Formula(e) => e.clone(),
+                                    Source::Config => Expr::Data,
+                                },
+                            )
+                        })
+                        .collect(),
+                );
+
+                let row = expr_row.check_opts(data, eval_opts);
+
+                /* Save errors. */
+
+                for ((_field_id, field_spec), (field_name, field_type)) in
+                    field_specs.iter().zip(row.0)
+                {
+                    match field_type {
+                        Ok(field_type) => {
+                            if !field_type.castable_to_opts(
+                                &field_spec.input_type,
+                                &eval_opts.types,
+                            ) {
+                                report
+                                    .field_errors
+                                    .entry(format!(
+                                        "{} ({:?} mode)",
+                                        table_id.0, query_mode
+                                    ))
+                                    .or_default()
+                                    .insert(
+                                        field_name.to_string(),
+                                        EvalError::TypeError(
+                                            "InputType does not match \
+                                             calculated field type",
+                                        ),
+                                    );
+                            }
+                        }
+                        Err(err) => {
+                            report
+                                .field_errors
+                                .entry(table_id.0.to_string())
+                                .or_default()
+                                .insert(field_name.to_string(), err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("Data fields", display_errors(&self.data_errors)),
+            ("Queries", display_errors(&self.query_errors)),
+            ("Tables", display_errors(&self.table_errors)),
+        ];
+        for (title, errors) in sections {
+            if !errors.is_empty() {
+                writeln!(f, "{}\n{}", title, "-".repeat(title.len()))?;
+                write!(f, "{}", errors)?;
+                writeln!(f)?;
+            }
+        }
+
+        for (table_name, field_errors) in &self.field_errors {
+            writeln!(
+                f,
+                "Table: {}\n{}",
+                table_name,
+                "-".repeat(table_name.len())
+            )?;
+            write!(f, "{}", display_errors(field_errors))?;
+            writeln!(f)?;
+        }
+
+        for (table_name, threshold_errors) in &self.threshold_errors {
+            let title = format!("Thresholds: {}", table_name);
+            writeln!(f, "{}\n{}", title, "-".repeat(title.len()))?;
+            write!(f, "{}", display_errors(threshold_errors))?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Warnings<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            (
+                "Unused data tables",
+                display_ids(&self.0.unused_data_tables),
+            ),
+            (
+                "Unused data fields",
+                display_ids(&self.0.unused_data_fields),
+            ),
+            (
+                "Missing data tables",
+                display_ids(&self.0.missing_data_tables),
+            ),
+            (
+                "Missing data fields",
+                display_ids(&self.0.missing_data_fields),
+            ),
+        ];
+        for (title, ids) in sections {
+            if !ids.is_empty() {
+                writeln!(
+                    f,
+                    "Warning: {}\n{}",
+                    title,
+                    "-".repeat(title.len() + 9)
+                )?;
+                write!(f, "{}", ids)?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn display_ids<T: Display>(ids: &BTreeSet<T>) -> String {
+    ids.iter().map(|id| format!("- {}\n", id)).collect()
+}
+
+fn display_errors<E: Display>(errors: &BTreeMap<String, E>) -> String {
+    errors
+        .iter()
+        .map(|(name, error)| format!("- {}: {}\n", name, error))
+        .collect()
+}
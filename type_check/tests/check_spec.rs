@@ -0,0 +1,232 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use etc::{
+    FieldSpec, Source, Spec, TableSpec, ThresholdBound, ThresholdError,
+    ThresholdOp, ThresholdSpec,
+};
+use etc_base::{
+    DataFieldId, DataTableId, FieldId, ProtoDataFieldId, ProtoDataTableId,
+    Protocol, QueryId, TableId,
+};
+use expression::{EvalOpts, Expr};
+use protocol::{DataFieldSpec, DataTableSpec, Input};
+use query::{ErrorAction, Query};
+use value::Type;
+
+fn proto() -> Protocol {
+    Protocol(String::from("test"))
+}
+
+/// A "test" protocol with a single "interfaces" table of integers.
+fn input() -> Input {
+    Input {
+        handle: Arc::new(()),
+        data_tables: HashMap::from([(
+            ProtoDataTableId(String::from("interfaces")),
+            DataTableSpec {
+                name: String::from("interfaces"),
+                singleton: false,
+                keys: HashSet::from([ProtoDataFieldId(String::from("index"))]),
+                fields: HashSet::from([ProtoDataFieldId(String::from(
+                    "index",
+                ))]),
+            },
+        )]),
+        data_fields: HashMap::from([(
+            ProtoDataFieldId(String::from("index")),
+            DataFieldSpec {
+                name: String::from("index"),
+                input_type: Type::Integer,
+                sensitive: false,
+            },
+        )]),
+    }
+}
+
+fn field(name: &str, source: Source) -> FieldSpec {
+    let mut field: FieldSpec = serde_json::from_value(serde_json::json!({
+        "Name": name,
+        "Source": "Config",
+        "InputType": "integer"
+    }))
+    .unwrap();
+    field.source = source;
+    field
+}
+
+fn data_field(name: &str) -> FieldSpec {
+    field(
+        name,
+        Source::Data(
+            DataTableId(proto(), ProtoDataTableId(String::from("interfaces"))),
+            DataFieldId(proto(), ProtoDataFieldId(String::from("index"))),
+            None,
+        ),
+    )
+}
+
+fn formula_field(name: &str, formula: &str) -> FieldSpec {
+    field(name, Source::Formula(Expr::parse(formula).unwrap()))
+}
+
+fn spec(fields: Vec<FieldSpec>) -> Spec {
+    let mut spec = Spec::default();
+    spec.input.insert(proto(), input());
+    spec.etc.queries.insert(
+        QueryId::from("interfaces"),
+        Query::Data(
+            DataTableId(proto(), ProtoDataTableId(String::from("interfaces"))),
+            ErrorAction::Fail,
+            false,
+        ),
+    );
+    let table: TableSpec = serde_json::from_value(serde_json::json!({
+        "Query": "interfaces",
+        "Name": "interfaces",
+        "Fields": fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>()
+    }))
+    .unwrap();
+    spec.etc.tables.insert(TableId::from("interfaces"), table);
+    for field in fields {
+        spec.etc
+            .fields
+            .insert(FieldId::from(field.name.as_str()), field);
+    }
+    spec
+}
+
+#[test]
+fn good_package() {
+    let spec = spec(vec![
+        data_field("index"),
+        formula_field("next", "{${index} + 1}"),
+    ]);
+    let report = type_check::check_spec(&spec, &EvalOpts::default()).unwrap();
+    assert!(report.is_ok(), "{report}");
+}
+
+#[test]
+fn bad_package() {
+    let spec = spec(vec![
+        data_field("index"),
+        formula_field("label", "{${index} + 'x'}"),
+    ]);
+    let report = type_check::check_spec(&spec, &EvalOpts::default()).unwrap();
+    assert!(!report.is_ok());
+    assert!(report.data_errors.is_empty());
+    assert!(report.query_errors.is_empty());
+    assert_eq!(
+        report
+            .field_errors
+            .values()
+            .flat_map(|fields| fields.keys())
+            .collect::<Vec<_>>(),
+        vec!["label"]
+    );
+}
+
+fn dynamic_threshold(bound: &str) -> Option<ThresholdSpec> {
+    Some(ThresholdSpec::Dynamic {
+        warning: Some(ThresholdBound {
+            op: ThresholdOp::Ge,
+            value: Expr::parse(bound).unwrap(),
+        }),
+        critical: None,
+    })
+}
+
+#[test]
+fn thresholds() {
+    let mut index = data_field("index");
+    index.threshold = dynamic_threshold("{${next} - 1}");
+    let spec = spec(vec![index, formula_field("next", "{${index} + 1}")]);
+    let report = type_check::check_spec(&spec, &EvalOpts::default()).unwrap();
+    assert!(report.is_ok(), "{report}");
+
+    let mut index = data_field("index");
+    index.threshold = dynamic_threshold("{'high'}");
+    let spec = spec(vec![index]);
+    let report = type_check::check_spec(&spec, &EvalOpts::default()).unwrap();
+    assert!(!report.is_ok());
+    assert!(matches!(
+        report.threshold_errors["interfaces"]["index"],
+        ThresholdError::NotNumeric(_)
+    ));
+}
+
+#[test]
+fn data_usage() {
+    let mut spec = spec(vec![data_field("index")]);
+    let input = spec.input.get_mut(&proto()).unwrap();
+    input
+        .data_tables
+        .get_mut(&ProtoDataTableId(String::from("interfaces")))
+        .unwrap()
+        .fields
+        .insert(ProtoDataFieldId(String::from("speed")));
+    input.data_tables.insert(
+        ProtoDataTableId(String::from("routes")),
+        DataTableSpec {
+            name: String::from("routes"),
+            singleton: false,
+            keys: HashSet::new(),
+            fields: HashSet::new(),
+        },
+    );
+    let bogus = field(
+        "bogus",
+        Source::Data(
+            DataTableId(proto(), ProtoDataTableId(String::from("interfaces"))),
+            DataFieldId(proto(), ProtoDataFieldId(String::from("bogus"))),
+            None,
+        ),
+    );
+    spec.etc.fields.insert(FieldId::from("bogus"), bogus);
+    spec.etc
+        .tables
+        .get_mut(&TableId::from("interfaces"))
+        .unwrap()
+        .fields
+        .push(FieldId::from("bogus"));
+
+    let usage = spec.data_usage().unwrap();
+    assert_eq!(
+        usage.unused_data_tables,
+        BTreeSet::from([DataTableId(
+            proto(),
+            ProtoDataTableId(String::from("routes"))
+        )])
+    );
+    assert_eq!(
+        usage.unused_data_fields,
+        BTreeSet::from([DataFieldId(
+            proto(),
+            ProtoDataFieldId(String::from("speed"))
+        )])
+    );
+    assert!(usage.missing_data_tables.is_empty());
+    assert_eq!(
+        usage.missing_data_fields,
+        BTreeSet::from([DataFieldId(
+            proto(),
+            ProtoDataFieldId(String::from("bogus"))
+        )])
+    );
+
+    let report = type_check::check_spec(&spec, &EvalOpts::default()).unwrap();
+    assert_eq!(report.data_usage, usage);
+    let warnings = report.warnings().to_string();
+    assert!(warnings.contains("routes"), "{warnings}");
+    assert!(warnings.contains("speed"), "{warnings}");
+}
+
+#[test]
+fn no_unused_data_in_good_package() {
+    let spec = spec(vec![data_field("index")]);
+    assert!(spec.data_usage().unwrap().is_empty());
+}
@@ -9,7 +9,8 @@ use crate::{BaseUnit, BinPrefix, DecPrefix, FracPrefix, Prefix, SiPrefix};
 use super::{
     ConductivityUnit, CurrentUnit, DimensionlessUnit, FanSpeedUnit,
     FrequencyUnit, InformationUnit, LengthUnit, MassUnit, OperationUnit,
-    PotentialUnit, PowerUnit, ResistanceUnit, TemperatureUnit, TimeUnit,
+    PotentialUnit, PowerUnit, PressureUnit, ResistanceUnit, TemperatureUnit,
+    TimeUnit,
 };
 
 macro_rules! units_len {
@@ -81,6 +82,15 @@ define_units!(
 );
 define_units!(POTENTIAL_UNITS, PotentialUnit, Volt(SiPrefix));
 define_units!(POWER_UNITS, PowerUnit, Watt(SiPrefix));
+define_units!(
+    PRESSURE_UNITS,
+    PressureUnit,
+    Pascal(SiPrefix),
+    Bar(SiPrefix),
+    Psi,
+    Atmosphere,
+    MmHg
+);
 define_units!(RESISTANCE_UNITS, ResistanceUnit, Ohm(SiPrefix));
 define_units!(CONDUCTIVITY_UNITS, ConductivityUnit, Siemens(SiPrefix));
 define_units!(
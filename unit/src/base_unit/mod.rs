@@ -16,6 +16,7 @@ pub mod mass_unit;
 pub mod operation_unit;
 pub mod potential_unit;
 pub mod power_unit;
+pub mod pressure_unit;
 pub mod resistance_unit;
 pub mod temperature_unit;
 pub mod time_unit;
@@ -32,6 +33,7 @@ pub use mass_unit::MassUnit;
 pub use operation_unit::OperationUnit;
 pub use potential_unit::PotentialUnit;
 pub use power_unit::PowerUnit;
+pub use pressure_unit::PressureUnit;
 pub use resistance_unit::ResistanceUnit;
 pub use temperature_unit::TemperatureUnit;
 pub use time_unit::TimeUnit;
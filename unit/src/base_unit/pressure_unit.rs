@@ -0,0 +1,70 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use super::super::prefix::{Prefix, SiPrefix};
+use super::base_units::PRESSURE_UNITS;
+use super::BaseUnit;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    PartialOrd,
+    Eq,
+    Ord,
+    Hash,
+    Clone,
+    Copy,
+    Debug,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "tsify", derive(tsify::Tsify))]
+pub enum PressureUnit {
+    Pascal(SiPrefix),
+    Bar(SiPrefix),
+    Psi,
+    Atmosphere,
+    MmHg,
+}
+
+impl BaseUnit for PressureUnit {
+    const LIST: &[Self] = &PRESSURE_UNITS;
+    const REFERENCE: Self = PressureUnit::Pascal(SiPrefix::Unit);
+    fn multiplier(&self) -> f64 {
+        match self {
+            PressureUnit::Pascal(p) => p.multiplier(),
+            /* 1 bar = 10^5 Pa; keep the power integral so that
+             * Pa <-> bar conversions stay exact. */
+            PressureUnit::Bar(p) => 10f64.powi(p.power() as i32 + 5),
+            PressureUnit::Psi => 6894.757293168361,
+            PressureUnit::Atmosphere => 101325.0,
+            PressureUnit::MmHg => 133.322387415,
+        }
+    }
+    fn scale(&self) -> Vec<Self> {
+        match self {
+            PressureUnit::Pascal(_) => {
+                SiPrefix::SCALE.iter().map(|p| Self::Pascal(*p)).collect()
+            }
+            PressureUnit::Bar(_) => {
+                SiPrefix::SCALE.iter().map(|p| Self::Bar(*p)).collect()
+            }
+            _ => vec![*self],
+        }
+    }
+}
+
+impl Display for PressureUnit {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            PressureUnit::Pascal(m) => write!(f, "{}Pa", m),
+            PressureUnit::Bar(m) => write!(f, "{}bar", m),
+            PressureUnit::Psi => write!(f, "psi"),
+            PressureUnit::Atmosphere => write!(f, "atm"),
+            PressureUnit::MmHg => write!(f, "mmHg"),
+        }
+    }
+}
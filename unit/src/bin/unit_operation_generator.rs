@@ -37,6 +37,7 @@ fn main() {
         ('a', "CurrentUnit"),
         ('v', "PotentialUnit"),
         ('w', "PowerUnit"),
+        ('p', "PressureUnit"),
         ('c', "ConductivityUnit"),
         ('r', "ResistanceUnit"),
     ]
@@ -122,6 +123,17 @@ fn main() {
                 vec![("PowerUnit", 1)],
             ),
         ),
+        (
+            "Pressure",
+            Unit::from(
+                vec![
+                    (Dimension::Mass, 1),
+                    (Dimension::Length, -1),
+                    (Dimension::Time, -2),
+                ],
+                vec![("PressureUnit", 1)],
+            ),
+        ),
         (
             "Resistance",
             Unit::from(
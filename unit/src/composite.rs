@@ -2,82 +2,442 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use std::convert::From;
 use std::collections::BTreeMap;
-use std::iter::{once,IntoIterator};
-use std::fmt::{self,Display,Formatter};
-use std::ops::Mul;
+use std::convert::From;
+use std::fmt::{self, Display, Formatter};
+use std::iter::{once, IntoIterator};
+use std::ops::{Add, Div, Mul, Sub};
 
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::parser::parse_unit_product;
+use crate::{
+    Dimension, FrequencyUnit, Quantity, Unit, UnitError, NEUTRAL_UNIT,
+};
 
 /// Composite unit / quantity.
-#[derive(PartialEq,Eq,Hash,Clone,Debug)]
-pub struct Composite<T:Ord>(BTreeMap<T,i8>);
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug,
+)]
+#[serde(from = "BTreeMap<T, i8>")]
+pub struct Composite<T: Ord>(BTreeMap<T, i8>);
+
+/// Product of powers of simple units, for units that cannot be
+/// represented by the static `Unit` enum (eg. "W/m^2"). Derived
+/// units in `Unit` are decomposed on construction, so "B/s" and
+/// `Unit::Bandwidth` compare equal.
+#[derive(PartialEq, Eq, Hash, Clone, Default, Debug)]
+pub struct CompositeUnit(Composite<Unit>);
 
+/// Dimension of a composite unit.
+pub type CompositeDimension = Composite<Dimension>;
 
-impl<T:Ord> Composite<T> {
+/// A value in a composite unit, for quantities whose unit cannot be
+/// represented by `Unit` (eg. irradiance in W/m^2).
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct CompositeQuantity(pub f64, pub CompositeUnit);
 
+impl<T: Ord> Composite<T> {
     pub fn simple(val: T) -> Self {
-	Composite::from(once((val,1)))
+        Composite::from(once((val, 1)))
     }
 
-    pub fn from_map(map: BTreeMap<T,i8>) -> Self {
-	Composite(map)
+    pub fn from_map(map: BTreeMap<T, i8>) -> Self {
+        Composite(map)
     }
 
-    pub fn as_map(&self) -> &BTreeMap<T,i8> {
-	&self.0
+    pub fn as_map(&self) -> &BTreeMap<T, i8> {
+        &self.0
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&T,&i8)> {
-	self.as_map().iter()
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &i8)> {
+        self.as_map().iter()
     }
 
-    pub fn into_iter(self) -> impl Iterator<Item = (T,i8)> {
-	self.0.into_iter()
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter(self) -> impl Iterator<Item = (T, i8)> {
+        self.0.into_iter()
     }
 
+    pub fn powi(self, n: i8) -> Self {
+        Composite::from(self.into_iter().map(|(u, p)| (u, p * n)))
+    }
+
+    /// Like `powi`, but fails if an exponent does not fit.
+    pub fn checked_powi(self, n: i32) -> Option<Self> {
+        self.into_iter()
+            .map(|(u, p)| {
+                Some((u, i8::try_from(i32::from(p).checked_mul(n)?).ok()?))
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Composite::from)
+    }
+
+    /// Like `*`, but fails if an exponent does not fit.
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut map = self.0;
+        for (u, n) in other.into_iter() {
+            let p = map.entry(u).or_insert(0);
+            *p = p.checked_add(n)?;
+        }
+        map.retain(|_, n| *n != 0);
+        Some(Composite(map))
+    }
 }
 
-impl<S,T:Ord> From<S> for Composite<T>
-where S: IntoIterator<Item = (T,i8)> {
-    fn from(vals: S) -> Self {
-	Composite(vals.into_iter().collect())
+impl CompositeDimension {
+    pub fn from_dimension(dim: Dimension) -> Self {
+        CompositeUnit::from(dim.reference_unit()).dimension()
+    }
+
+    /// The product of powers of the dimensions' reference units.
+    pub fn reference_unit(&self) -> CompositeUnit {
+        self.iter().fold(CompositeUnit::default(), |unit, (d, n)| {
+            unit * CompositeUnit::from(d.reference_unit()).powi(*n)
+        })
+    }
+}
+
+impl<T: Ord> Default for Composite<T> {
+    fn default() -> Self {
+        Composite(BTreeMap::new())
     }
 }
 
+impl<S, T: Ord> From<S> for Composite<T>
+where
+    S: IntoIterator<Item = (T, i8)>,
+{
+    fn from(vals: S) -> Self {
+        let mut map =
+            vals.into_iter().fold(BTreeMap::new(), |mut m, (u, n)| {
+                *m.entry(u).or_insert(0) += n;
+                m
+            });
+        map.retain(|_, n| *n != 0);
+        Composite(map)
+    }
+}
 
-impl<T:Ord> Mul<Composite<T>> for Composite<T> {
+impl<T: Ord> Mul<Composite<T>> for Composite<T> {
     type Output = Self;
     fn mul(self, other: Self) -> Self {
-	Composite(other.into_iter().fold(self.0, |mut m,(u,n)| {
-	    *m.entry(u).or_insert(0) += n; m }))
+        let mut map = other.into_iter().fold(self.0, |mut m, (u, n)| {
+            *m.entry(u).or_insert(0) += n;
+            m
+        });
+        map.retain(|_, n| *n != 0);
+        Composite(map)
+    }
+}
+
+impl<T: Ord> Div<Composite<T>> for Composite<T> {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        self * other.powi(-1)
+    }
+}
+
+/// Formats as "a*b^2/c/d", which is accepted by the parser.
+impl<T: Display + Ord> Display for Composite<T> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .filter(|(_, &p)| p > 0)
+                .map(|(u, p)| format!("{}{}", u, power(*p)))
+                .collect::<Vec<String>>()
+                .join("*")
+        )?;
+        for (u, p) in self.0.iter().filter(|(_, &p)| p < 0) {
+            write!(f, "/{}{}", u, power(-*p))?;
+        }
+        Ok(())
+    }
+}
+
+fn power(val: i8) -> String {
+    match val {
+        1 => String::new(),
+        n => format!("^{}", n),
+    }
+}
+
+impl CompositeUnit {
+    pub fn parse(input: &str) -> Result<Self, UnitError> {
+        parse_unit_product(input)
+    }
+
+    pub fn as_composite(&self) -> &Composite<Unit> {
+        &self.0
+    }
+
+    /// The dimension, as a product of powers of the dimensions of
+    /// the constituent units. Frequency is reduced to time^-1;
+    /// other derived dimensions are kept as-is.
+    pub fn dimension(&self) -> CompositeDimension {
+        Composite::from(self.0.iter().filter_map(
+            |(u, n)| match u.dimension() {
+                Dimension::Dimensionless => None,
+                Dimension::Frequency => Some((Dimension::Time, -n)),
+                d => Some((d, *n)),
+            },
+        ))
+    }
+
+    /// Return the equivalent static unit, if there is one.
+    pub fn to_unit(&self) -> Option<Unit> {
+        let mut unit = NEUTRAL_UNIT;
+        for (u, n) in self.0.iter().filter(|(_, n)| **n > 0) {
+            unit = unit.mul_unwrapped(unit_power(*u, *n)?).ok()?;
+        }
+        for (u, n) in self.0.iter().filter(|(_, n)| **n < 0) {
+            unit = match unit {
+                NEUTRAL_UNIT => u.powi_unwrapped(*n as i32).ok()?,
+                _ => unit.div_unwrapped(unit_power(*u, -*n)?).ok()?,
+            };
+        }
+        (CompositeUnit::from(unit) == *self).then_some(unit)
+    }
+
+    pub fn powi(self, n: i8) -> Self {
+        CompositeUnit(self.0.powi(n))
+    }
+
+    /// Like `powi`, but fails if an exponent does not fit in an i8.
+    pub fn checked_powi(self, n: i32) -> Option<Self> {
+        self.0.checked_powi(n).map(CompositeUnit)
+    }
+
+    /// Like `*`, but fails if an exponent does not fit in an i8.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0).map(CompositeUnit)
+    }
+
+    /// Like `/`, but fails if an exponent does not fit in an i8.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs.checked_powi(-1)?)
+    }
+
+    /// Convert a value between units of the same dimension. Simple
+    /// units are converted by `Unit::convert`; for others, only
+    /// the multipliers are taken into account (offsets, as for
+    /// temperature, make no sense in a product).
+    pub fn convert(&self, other: &Self, val: f64) -> Result<f64, UnitError> {
+        if let (Some(from), Some(to)) = (self.to_unit(), other.to_unit()) {
+            if let Ok(val) = from.convert(&to, val) {
+                return Ok(val);
+            }
+        }
+        self.check_dimension(other)?;
+        Ok(val * self.multiplier() / other.multiplier())
+    }
+
+    fn check_dimension(&self, other: &Self) -> Result<(), UnitError> {
+        match self.dimension() == other.dimension() {
+            true => Ok(()),
+            false => Err(UnitError::CompositeConversion(
+                self.dimension().to_string(),
+                other.dimension().to_string(),
+            )),
+        }
+    }
+
+    fn multiplier(&self) -> f64 {
+        self.0
+            .iter()
+            .map(|(u, n)| u.multiplier().powi(*n as i32))
+            .product()
     }
 }
 
+impl CompositeQuantity {
+    pub fn new(val: f64, unit: CompositeUnit) -> Self {
+        CompositeQuantity(val, unit)
+    }
+
+    pub fn dimension(&self) -> CompositeDimension {
+        self.1.dimension()
+    }
+
+    pub fn convert(self, unit: &CompositeUnit) -> Result<Self, UnitError> {
+        Ok(CompositeQuantity(
+            self.1.convert(unit, self.0)?,
+            unit.clone(),
+        ))
+    }
+
+    /// Convert to the reference unit of the quantity's dimension.
+    pub fn normalize(self) -> Result<Self, UnitError> {
+        let unit = self.dimension().reference_unit();
+        self.convert(&unit)
+    }
+
+    /// Return the equivalent simple quantity, if there is one.
+    pub fn to_quantity(&self) -> Option<Quantity> {
+        Some(Quantity(self.0, self.1.to_unit()?))
+    }
+}
+
+fn unit_power(unit: Unit, n: i8) -> Option<Unit> {
+    unit.powi_unwrapped(n as i32)
+        .ok()
+        .or_else(|| (1..n).try_fold(unit, |u, _| u.mul_unwrapped(unit).ok()))
+}
+
+impl From<Unit> for CompositeUnit {
+    fn from(unit: Unit) -> Self {
+        CompositeUnit(match unit {
+            NEUTRAL_UNIT => Composite::default(),
+            Unit::Area(l) => Composite::from([(Unit::Length(l), 2)]),
+            Unit::Volume(l) => Composite::from([(Unit::Length(l), 3)]),
+            Unit::TimeSquare(t) => Composite::from([(Unit::Time(t), 2)]),
+            Unit::Speed(l, t) => {
+                Composite::from([(Unit::Length(l), 1), (Unit::Time(t), -1)])
+            }
+            Unit::Acceleration(l, t) => {
+                Composite::from([(Unit::Length(l), 1), (Unit::Time(t), -2)])
+            }
+            Unit::AbsoluteHumidity(m, l) => {
+                Composite::from([(Unit::Mass(m), 1), (Unit::Length(l), -3)])
+            }
+            Unit::Bandwidth(i, t) => Composite::from([
+                (Unit::Information(i), 1),
+                (Unit::Time(t), -1),
+            ]),
+            Unit::IOLatency(t, n) => {
+                Composite::from([(Unit::Time(t), 1), (Unit::Operations(n), -1)])
+            }
+            Unit::IOPerformance(n, t) => {
+                Composite::from([(Unit::Operations(n), 1), (Unit::Time(t), -1)])
+            }
+            Unit::AvgOpSize(i, n) => Composite::from([
+                (Unit::Information(i), 1),
+                (Unit::Operations(n), -1),
+            ]),
+            Unit::Frequency(FrequencyUnit::PerTime(t)) => {
+                Composite::from([(Unit::Time(t), -1)])
+            }
+            u => Composite::simple(u),
+        })
+    }
+}
+
+impl Mul for CompositeUnit {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        CompositeUnit(self.0 * rhs.0)
+    }
+}
+
+impl Div for CompositeUnit {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        CompositeUnit(self.0 / rhs.0)
+    }
+}
+
+/// Sums are only defined between units of the same dimension; the
+/// result is expressed in the unit of the left-hand side.
+impl Add for CompositeUnit {
+    type Output = Result<Self, UnitError>;
+    fn add(self, rhs: Self) -> Result<Self, UnitError> {
+        self.check_dimension(&rhs)?;
+        Ok(self)
+    }
+}
 
-impl<T:Display+Ord> Display for Composite<T> {
+impl Sub for CompositeUnit {
+    type Output = Result<Self, UnitError>;
+    fn sub(self, rhs: Self) -> Result<Self, UnitError> {
+        self.check_dimension(&rhs)?;
+        Ok(self)
+    }
+}
+
+impl Mul<Unit> for CompositeUnit {
+    type Output = Self;
+    fn mul(self, rhs: Unit) -> Self {
+        self * CompositeUnit::from(rhs)
+    }
+}
+
+impl Div<Unit> for CompositeUnit {
+    type Output = Self;
+    fn div(self, rhs: Unit) -> Self {
+        self / CompositeUnit::from(rhs)
+    }
+}
+
+impl From<Quantity> for CompositeQuantity {
+    fn from(q: Quantity) -> Self {
+        CompositeQuantity(q.0, CompositeUnit::from(q.1))
+    }
+}
+
+impl Mul for CompositeQuantity {
+    type Output = Result<Self, UnitError>;
+    fn mul(self, rhs: Self) -> Result<Self, UnitError> {
+        let unit = self.1.checked_mul(rhs.1).ok_or(UnitError::Exponent)?;
+        Ok(CompositeQuantity(self.0 * rhs.0, unit))
+    }
+}
+
+impl Div for CompositeQuantity {
+    type Output = Result<Self, UnitError>;
+    fn div(self, rhs: Self) -> Result<Self, UnitError> {
+        let unit = self.1.checked_div(rhs.1).ok_or(UnitError::Exponent)?;
+        Ok(CompositeQuantity(self.0 / rhs.0, unit))
+    }
+}
+
+impl Display for CompositeQuantity {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-	write!(f, "{}", self.0.iter().filter(|(_,&p)| p > 0).map(
-	    |(u,p)| format!("{}{}", u, superscript(*p)))
-	       .collect::<Vec<String>>().join("\u{22c5}"))?;
-	if self.0.iter().any(|(_,&p)| p < 0) {
-	    write!(f, "/{}", self.0.iter().filter(|(_,&p)| p < 0).map(
-		|(u,p)| format!("{}{}", u, superscript(-*p)))
-		   .collect::<Vec<String>>().join("\u{22c5}"))?;
-	}
-	Ok(())
+        write!(f, "{} {}", self.0, self.1)
     }
 }
 
+impl Display for CompositeUnit {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self.to_unit() {
+            Some(unit) => write!(f, "{}", unit),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Single units serialize exactly like `Unit`, so existing configs
+/// keep working. Other units are serialized as a string.
+impl Serialize for CompositeUnit {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match self.to_unit() {
+            Some(unit) => unit.serialize(serializer),
+            None => serializer.serialize_str(&self.0.to_string()),
+        }
+    }
+}
 
-static SS : [char;10] = ['\u{2070}','\u{00b9}','\u{00b2}','\u{00b3}','\u{2074}',
-			 '\u{2075}','\u{2076}','\u{2077}','\u{2078}','\u{2079}'];
+impl<'de> Deserialize<'de> for CompositeUnit {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Unit(Unit),
+            Composite(String),
+        }
 
-fn superscript(val: i8) -> String {
-    if val != 1 {
-	val.to_string().chars().map(|c| match c.to_digit(10) {
-	    Some(n) => SS[n as usize], None => c }).collect()
-    } else {
-	"".to_string()
+        match Repr::deserialize(deserializer)? {
+            Repr::Unit(unit) => Ok(CompositeUnit::from(unit)),
+            Repr::Composite(s) => {
+                CompositeUnit::parse(&s).map_err(de::Error::custom)
+            }
+        }
     }
 }
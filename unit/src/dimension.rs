@@ -5,7 +5,8 @@
 use super::base_unit::{
     BaseUnit, ConductivityUnit, CurrentUnit, DimensionlessUnit, FanSpeedUnit,
     FrequencyUnit, InformationUnit, LengthUnit, MassUnit, OperationUnit,
-    PotentialUnit, PowerUnit, ResistanceUnit, TemperatureUnit, TimeUnit,
+    PotentialUnit, PowerUnit, PressureUnit, ResistanceUnit, TemperatureUnit,
+    TimeUnit,
 };
 use super::error::UnitError;
 use super::prefix::DecPrefix;
@@ -73,15 +74,20 @@ pub enum Dimension {
 
     /* Dimensionless */
     Dimensionless,
+
+    /* Added later; kept at the end to preserve the ordering
+     * of the dimensions above. */
+    Pressure,
 }
 
-pub(crate) static DIMENSIONS: [Dimension; 24] = [
+pub(crate) static DIMENSIONS: [Dimension; 25] = [
     Dimension::Length,
     Dimension::Mass,
     Dimension::Time,
     Dimension::Current,
     Dimension::Potential,
     Dimension::Power,
+    Dimension::Pressure,
     Dimension::Resistance,
     Dimension::Conductivity,
     Dimension::Temperature,
@@ -146,6 +152,7 @@ define_units!(SPEED_UNITS, Speed, LengthUnit, TimeUnit);
 define_units!(ACCELERATION_UNITS, Acceleration, LengthUnit, TimeUnit);
 define_units!(POTENTIAL_UNITS, Potential, PotentialUnit);
 define_units!(POWER_UNITS, Power, PowerUnit);
+define_units!(PRESSURE_UNITS, Pressure, PressureUnit);
 define_units!(RESISTANCE_UNITS, Resistance, ResistanceUnit);
 define_units!(CONDUCTIVITY_UNITS, Conductivity, ConductivityUnit);
 define_units!(
@@ -176,6 +183,7 @@ impl Dimension {
             Dimension::Current => "I",
             Dimension::Potential => "U",
             Dimension::Power => "P",
+            Dimension::Pressure => "p",
             Dimension::Resistance => "R",
             Dimension::Conductivity => "C",
             Dimension::Temperature => "Θ",
@@ -205,6 +213,7 @@ impl Dimension {
             Dimension::Current => "current",
             Dimension::Potential => "potential",
             Dimension::Power => "power",
+            Dimension::Pressure => "pressure",
             Dimension::Resistance => "resistance",
             Dimension::Conductivity => "conductivity",
             Dimension::Temperature => "temperature",
@@ -234,6 +243,7 @@ impl Dimension {
             Dimension::Current => Unit::Current(CurrentUnit::REFERENCE),
             Dimension::Potential => Unit::Potential(PotentialUnit::REFERENCE),
             Dimension::Power => Unit::Power(PowerUnit::REFERENCE),
+            Dimension::Pressure => Unit::Pressure(PressureUnit::REFERENCE),
             Dimension::Resistance => {
                 Unit::Resistance(ResistanceUnit::REFERENCE)
             }
@@ -296,6 +306,7 @@ impl Dimension {
             Dimension::Acceleration => &ACCELERATION_UNITS,
             Dimension::Potential => &POTENTIAL_UNITS,
             Dimension::Power => &POWER_UNITS,
+            Dimension::Pressure => &PRESSURE_UNITS,
             Dimension::Resistance => &RESISTANCE_UNITS,
             Dimension::Conductivity => &CONDUCTIVITY_UNITS,
             Dimension::AbsoluteHumidity => &ABSOLUTE_HUMIDITY_UNITS,
@@ -614,6 +625,9 @@ impl Div<Dimension> for Dimension {
             (Dimension::Power, Dimension::Power) => {
                 Ok(Dimension::Dimensionless)
             }
+            (Dimension::Pressure, Dimension::Pressure) => {
+                Ok(Dimension::Dimensionless)
+            }
             (Dimension::Resistance, Dimension::Resistance) => {
                 Ok(Dimension::Dimensionless)
             }
@@ -24,6 +24,8 @@ pub enum UnitError {
     CPow(Unit, i32),
     #[error("Incompatible units: {0} <-> {1}")]
     Conversion(Dimension, Dimension),
+    #[error("Incompatible units: {0} <-> {1}")]
+    CompositeConversion(String, String),
     #[error("Unit parse error: {0}")]
     ParseError(String),
     #[error("invalid unit {1} for dimension {0}")]
@@ -32,4 +34,6 @@ pub enum UnitError {
     Unsupported(Dimension),
     #[error("JSON error: {0}")]
     Json(String),
+    #[error("Unit exponent out of range")]
+    Exponent,
 }
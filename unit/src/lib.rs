@@ -3,6 +3,7 @@
  ******************************************************************************/
 
 pub mod base_unit;
+pub mod composite;
 pub mod dimension;
 pub mod error;
 pub mod parser;
@@ -20,11 +21,13 @@ pub use crate::unit::{Unit, NEUTRAL_UNIT};
 pub use base_unit::{
     BaseUnit, ConductivityUnit, CurrentUnit, DimensionlessUnit, FanSpeedUnit,
     FrequencyUnit, InformationUnit, LengthUnit, MassUnit, OperationUnit,
-    PotentialUnit, PowerUnit, ResistanceUnit, TemperatureUnit, TimeUnit,
+    PotentialUnit, PowerUnit, PressureUnit, ResistanceUnit, TemperatureUnit,
+    TimeUnit,
 };
+pub use composite::{CompositeDimension, CompositeQuantity, CompositeUnit};
 pub use dimension::Dimension;
 pub use error::UnitError;
-pub use quantity::Quantity;
+pub use quantity::{HumanizeOpts, Quantity};
 pub use quantity_seed::QuantitySeed;
 pub use unit_seed::UnitSeed;
 pub use units::{FrequencyUnits, TimeUnits, Units};
@@ -6,7 +6,7 @@ use nom::{
     self,
     branch::alt,
     character::complete::{digit1, space0},
-    combinator::{opt, value},
+    combinator::{map_res, opt, value},
     error::ErrorKind,
     multi::{fold_many1, separated_list1},
     number::complete::double,
@@ -14,13 +14,14 @@ use nom::{
     IResult,
 };
 
-use crate::Quantity;
+use crate::{CompositeUnit, Quantity};
 
 use super::{BinPrefix, DecPrefix, FracPrefix, SiPrefix};
 use super::{
     ConductivityUnit, CurrentUnit, DimensionlessUnit, FanSpeedUnit,
     FrequencyUnit, InformationUnit, LengthUnit, MassUnit, OperationUnit,
-    PotentialUnit, PowerUnit, ResistanceUnit, TemperatureUnit, TimeUnit,
+    PotentialUnit, PowerUnit, PressureUnit, ResistanceUnit, TemperatureUnit,
+    TimeUnit,
 };
 use super::{Unit, UnitError, NEUTRAL_UNIT};
 
@@ -50,6 +51,22 @@ pub fn parse_composite_unit(input: &str) -> Result<Unit, UnitError> {
     }
 }
 
+/// Parse a string to a product of unit powers. Unlike
+/// `parse_composite_unit`, this accepts any combination of units.
+pub fn parse_unit_product(input: &str) -> Result<CompositeUnit, UnitError> {
+    if input.is_empty() {
+        return Ok(CompositeUnit::default());
+    }
+
+    match unit_product(input) {
+        Ok(("", u)) => u,
+        Ok((r, _)) => {
+            Err(UnitError::ParseError(format!("Leftover input: {}", r)))
+        }
+        Err(err) => Err(UnitError::ParseError(format!("{}", err))),
+    }
+}
+
 /// Parse a string to a simple unit (no multiplication,
 /// division or exponentiation).
 pub fn parse_unit(input: &str) -> Result<Unit, UnitError> {
@@ -97,6 +114,33 @@ pub fn composite_unit(input: &str) -> IResult<&str, Result<Unit, UnitError>> {
     }
 }
 
+/// Parser for products of unit powers. Fails if an exponent does
+/// not fit in an i8.
+pub fn unit_product(
+    input: &str,
+) -> IResult<&str, Result<CompositeUnit, UnitError>> {
+    let (input, (num, denom)) = tuple((
+        opt(unit_product_list('*')),
+        opt(preceded(char('/'), unit_product_list('/'))),
+    ))(input)?;
+
+    match (num, denom) {
+        (None, None) => Err(nom::Err::Error(nom::error::Error {
+            input,
+            code: ErrorKind::Alt,
+        })),
+        (num, denom) => Ok((
+            input,
+            num.unwrap_or(Some(CompositeUnit::default()))
+                .zip(denom.unwrap_or(Some(CompositeUnit::default())))
+                .and_then(|(num, denom)| num.checked_div(denom))
+                .ok_or_else(|| {
+                    UnitError::ParseError(String::from("Exponent out of range"))
+                }),
+        )),
+    }
+}
+
 /// Parser for quantities (number and unit).
 pub fn quantity(input: &str) -> IResult<&str, Result<Quantity, UnitError>> {
     let (input, (num, _, unit)) =
@@ -129,14 +173,36 @@ fn unit_list(
     }
 }
 
+fn unit_product_list(
+    sep: char,
+) -> impl Fn(&str) -> IResult<&str, Option<CompositeUnit>> {
+    move |input| {
+        let (input, units) =
+            separated_list1(char(sep), tuple((unit, opt(power))))(input)?;
+        Ok((
+            input,
+            units.into_iter().try_fold(
+                CompositeUnit::default(),
+                |q, (u, n)| {
+                    q.checked_mul(
+                        CompositeUnit::from(u).checked_powi(n.unwrap_or(1))?,
+                    )
+                },
+            ),
+        ))
+    }
+}
+
 fn power(input: &str) -> IResult<&str, i32> {
     alt((hat_power, superscript_power))(input)
 }
 
 fn hat_power(input: &str) -> IResult<&str, i32> {
-    let (input, (s, n)) =
-        preceded(char('^'), tuple((opt(sign), digit1)))(input)?;
-    Ok((input, s.unwrap_or(1) * n.parse::<i32>().unwrap()))
+    let (input, (s, n)) = preceded(
+        char('^'),
+        tuple((opt(sign), map_res(digit1, str::parse::<i32>))),
+    )(input)?;
+    Ok((input, s.unwrap_or(1) * n))
 }
 
 fn superscript_power(input: &str) -> IResult<&str, i32> {
@@ -150,7 +216,11 @@ fn superscript_sign(input: &str) -> IResult<&str, i32> {
 }
 
 fn superscript_digit1(input: &str) -> IResult<&str, i32> {
-    fold_many1(superscript_digit, || 0, |n, i| n * 10 + i)(input)
+    fold_many1(
+        superscript_digit,
+        || 0,
+        |n: i32, i| n.saturating_mul(10).saturating_add(i),
+    )(input)
 }
 
 fn superscript_digit(input: &str) -> IResult<&str, i32> {
@@ -233,6 +303,12 @@ fn nonprefixed_unit(input: &str) -> IResult<&str, Unit> {
             tag("rps"),
             Unit::Power(PowerUnit::DBmW),
             tuple((tag("dBm"), opt(char('W')))),
+            Unit::Pressure(PressureUnit::Psi),
+            tag("psi"),
+            Unit::Pressure(PressureUnit::Atmosphere),
+            tag("atm"),
+            Unit::Pressure(PressureUnit::MmHg),
+            tag("mmHg"),
             Unit::Dimensionless(DimensionlessUnit::Percent),
             char('%'),
             Unit::Dimensionless(DimensionlessUnit::Permille),
@@ -255,6 +331,10 @@ fn si_unit(input: &str, prefix: SiPrefix) -> IResult<&str, Unit> {
             char('V'),
             Unit::Power(PowerUnit::Watt(prefix)),
             char('W'),
+            Unit::Pressure(PressureUnit::Pascal(prefix)),
+            tag("Pa"),
+            Unit::Pressure(PressureUnit::Bar(prefix)),
+            tag("bar"),
             Unit::Resistance(ResistanceUnit::Ohm(prefix)),
             char('Ω'),
             Unit::Conductivity(ConductivityUnit::Siemens(prefix)),
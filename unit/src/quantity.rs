@@ -17,6 +17,22 @@ use super::{Dimension, Unit, NEUTRAL_UNIT};
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Quantity(pub f64, pub Unit);
 
+/// Options for human-readable quantity output.
+#[derive(Clone, Debug)]
+pub struct HumanizeOpts {
+    /// Number of significant digits in the mantissa. Digits before
+    /// the decimal point are never dropped.
+    pub significant_digits: u8,
+}
+
+impl Default for HumanizeOpts {
+    fn default() -> Self {
+        Self {
+            significant_digits: 3,
+        }
+    }
+}
+
 impl Quantity {
     pub fn new(val: f64, unit: Unit) -> Self {
         Quantity(val, unit)
@@ -45,7 +61,7 @@ impl Quantity {
         let scale = self.1.scale();
         for unit in scale[1..].iter().rev() {
             let val = self.1.convert(unit, self.0)?;
-            if val >= 1.0 {
+            if val.abs() >= 1.0 {
                 return Ok(Quantity(val, *unit));
             }
         }
@@ -54,6 +70,18 @@ impl Quantity {
         Ok(Quantity(val, unit))
     }
 
+    /// Format with the largest prefix (from the unit's own scale)
+    /// for which the mantissa is at least one, eg. "1.43 MB" for
+    /// 1500000 B. Zero is shown in the reference unit; values below
+    /// the smallest prefix use the smallest prefix.
+    pub fn humanize(&self, opts: &HumanizeOpts) -> String {
+        let Quantity(val, unit) = match self.0.is_finite() {
+            true => self.autoscale().unwrap_or(*self),
+            false => *self,
+        };
+        format!("{} {}", significant(val, opts.significant_digits), unit)
+    }
+
     pub fn convert(self, unit: &Unit) -> Result<Self, UnitError> {
         Ok(Quantity(self.1.convert(unit, self.0)?, *unit))
     }
@@ -148,3 +176,19 @@ impl Div<f64> for Quantity {
         Quantity(self.1.delinearize(self.1.linearize(self.0) / rhs), self.1)
     }
 }
+
+fn significant(val: f64, digits: u8) -> String {
+    if val == 0.0 {
+        return String::from("0");
+    }
+    if !val.is_finite() {
+        return val.to_string();
+    }
+    let magnitude = val.abs().log10().floor() as i32;
+    let decimals = (digits.max(1) as i32 - 1 - magnitude).max(0) as usize;
+    let s = format!("{:.*}", decimals, val);
+    match s.contains('.') {
+        true => s.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => s,
+    }
+}
@@ -15,7 +15,8 @@ use super::parser::{parse_composite_unit, parse_unit};
 use super::{
     ConductivityUnit, CurrentUnit, DimensionlessUnit, FanSpeedUnit,
     FrequencyUnit, InformationUnit, LengthUnit, MassUnit, OperationUnit,
-    PotentialUnit, PowerUnit, ResistanceUnit, TemperatureUnit, TimeUnit,
+    PotentialUnit, PowerUnit, PressureUnit, ResistanceUnit, TemperatureUnit,
+    TimeUnit,
 };
 
 /// Supported unit and prefix combinations, grouped by dimension.
@@ -70,6 +71,7 @@ pub enum Unit {
     Frequency(FrequencyUnit),
     FanSpeed(FanSpeedUnit),
     Dimensionless(DimensionlessUnit),
+    Pressure(PressureUnit),
 }
 
 pub const NEUTRAL_UNIT: Unit =
@@ -96,6 +98,7 @@ impl Unit {
             Unit::Current(_) => Dimension::Current,
             Unit::Potential(_) => Dimension::Potential,
             Unit::Power(_) => Dimension::Power,
+            Unit::Pressure(_) => Dimension::Pressure,
             Unit::Resistance(_) => Dimension::Resistance,
             Unit::Conductivity(_) => Dimension::Conductivity,
             Unit::Area(_) => Dimension::Area,
@@ -124,6 +127,7 @@ impl Unit {
             Unit::Current(u) => Unit::Current(u.normalize()),
             Unit::Potential(u) => Unit::Potential(u.normalize()),
             Unit::Power(u) => Unit::Power(u.normalize()),
+            Unit::Pressure(u) => Unit::Pressure(u.normalize()),
             Unit::Resistance(u) => Unit::Resistance(u.normalize()),
             Unit::Conductivity(u) => Unit::Conductivity(u.normalize()),
             Unit::Area(u) => Unit::Area(u.normalize()),
@@ -182,6 +186,9 @@ impl Unit {
                 u.scale().into_iter().map(Unit::Potential).collect()
             }
             Unit::Power(u) => u.scale().into_iter().map(Unit::Power).collect(),
+            Unit::Pressure(u) => {
+                u.scale().into_iter().map(Unit::Pressure).collect()
+            }
             Unit::Resistance(u) => {
                 u.scale().into_iter().map(Unit::Resistance).collect()
             }
@@ -248,7 +255,7 @@ impl Unit {
         }
     }
 
-    fn multiplier(&self) -> f64 {
+    pub(crate) fn multiplier(&self) -> f64 {
         match self {
             Unit::Information(u) => u.multiplier(),
             Unit::Operations(u) => u.multiplier(),
@@ -259,6 +266,7 @@ impl Unit {
             Unit::Current(u) => u.multiplier(),
             Unit::Potential(u) => u.multiplier(),
             Unit::Power(u) => u.multiplier(),
+            Unit::Pressure(u) => u.multiplier(),
             Unit::Resistance(u) => u.multiplier(),
             Unit::Conductivity(u) => u.multiplier(),
             Unit::Area(u) => u.multiplier().powi(2),
@@ -319,6 +327,7 @@ impl Display for Unit {
             Unit::Current(u) => u.fmt(f),
             Unit::Potential(u) => u.fmt(f),
             Unit::Power(u) => u.fmt(f),
+            Unit::Pressure(u) => u.fmt(f),
             Unit::Resistance(u) => u.fmt(f),
             Unit::Conductivity(u) => u.fmt(f),
             Unit::Frequency(u) => u.fmt(f),
@@ -1047,6 +1056,10 @@ impl Div<Unit> for Unit {
                 wl.multiplier() / wr.multiplier(),
                 Unit::Dimensionless(DimensionlessUnit::REFERENCE),
             )),
+            (Unit::Pressure(pl), Unit::Pressure(pr)) => Ok(Quantity(
+                pl.multiplier() / pr.multiplier(),
+                Unit::Dimensionless(DimensionlessUnit::REFERENCE),
+            )),
             (Unit::Resistance(rl), Unit::Resistance(rr)) => Ok(Quantity(
                 rl.multiplier() / rr.multiplier(),
                 Unit::Dimensionless(DimensionlessUnit::REFERENCE),
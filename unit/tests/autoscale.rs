@@ -2,7 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use unit::Quantity;
+use unit::{HumanizeOpts, Quantity};
 
 #[test]
 fn autoscale_info() {
@@ -54,3 +54,52 @@ fn autoscale_time() {
         Quantity::parse("0s").unwrap()
     );
 }
+
+fn humanize(input: &str) -> String {
+    Quantity::parse(input)
+        .unwrap()
+        .humanize(&HumanizeOpts::default())
+}
+
+#[test]
+fn humanize_info() {
+    assert_eq!(humanize("1500000B"), "1.43 MB");
+    assert_eq!(humanize("5120kB"), "5 MB");
+    assert_eq!(humanize("1023B"), "1023 B");
+    assert_eq!(humanize("0TB"), "0 B");
+    assert_eq!(humanize("-2048B"), "-2 kB");
+    assert_eq!(humanize("0.5B"), "0.5 B");
+}
+
+#[test]
+fn humanize_time() {
+    assert_eq!(humanize("90s"), "1.5 min");
+    assert_eq!(humanize("-0.0025s"), "-2.5 ms");
+    assert_eq!(humanize(".00000000000000000000000001s"), "0.01 ys");
+    assert_eq!(humanize("0h"), "0 s");
+}
+
+#[test]
+fn humanize_significant_digits() {
+    let q = Quantity::parse("1234567B").unwrap();
+    assert_eq!(
+        q.humanize(&HumanizeOpts {
+            significant_digits: 1
+        }),
+        "1 MB"
+    );
+    assert_eq!(
+        q.humanize(&HumanizeOpts {
+            significant_digits: 5
+        }),
+        "1.1774 MB"
+    );
+    assert_eq!(
+        Quantity::parse("123456789s")
+            .unwrap()
+            .humanize(&HumanizeOpts {
+                significant_digits: 2
+            }),
+        "204 week"
+    );
+}
@@ -0,0 +1,193 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use unit::{
+    BinPrefix, CompositeDimension, CompositeQuantity, CompositeUnit, Dimension,
+    FracPrefix, InformationUnit, LengthUnit, PowerUnit, Quantity, SiPrefix,
+    TimeUnit, Unit, UnitError,
+};
+
+const BYTE: Unit = Unit::Information(InformationUnit::Byte(BinPrefix::Unit));
+const SECOND: Unit = Unit::Time(TimeUnit::Second(FracPrefix::Unit));
+const METER: Unit = Unit::Length(LengthUnit::Meter(SiPrefix::Unit));
+const WATT: Unit = Unit::Power(PowerUnit::Watt(SiPrefix::Unit));
+
+#[test]
+fn bandwidth_as_product() {
+    let bps = CompositeUnit::from(BYTE) / CompositeUnit::from(SECOND);
+    assert_eq!(CompositeUnit::parse("B/s").unwrap(), bps);
+    assert_eq!(
+        CompositeUnit::from(Unit::Bandwidth(
+            InformationUnit::Byte(BinPrefix::Unit),
+            TimeUnit::Second(FracPrefix::Unit)
+        )),
+        bps
+    );
+    assert_eq!(
+        bps.as_composite().as_map().iter().collect::<Vec<_>>(),
+        vec![(&BYTE, &1), (&SECOND, &-1)]
+    );
+    assert_eq!(
+        bps.dimension().as_map().iter().collect::<Vec<_>>(),
+        vec![(&Dimension::Time, &-1), (&Dimension::Information, &1)]
+    );
+    assert_eq!(
+        bps.to_unit(),
+        Some(Unit::Bandwidth(
+            InformationUnit::Byte(BinPrefix::Unit),
+            TimeUnit::Second(FracPrefix::Unit)
+        ))
+    );
+}
+
+#[test]
+fn parse_products() {
+    let irradiance = CompositeUnit::parse("W/m^2").unwrap();
+    assert_eq!(
+        irradiance,
+        CompositeUnit::from(WATT) / CompositeUnit::from(METER).powi(2)
+    );
+    assert_eq!(irradiance.to_unit(), None);
+    assert_eq!(irradiance.to_string(), "W/m^2");
+    assert_eq!(
+        CompositeUnit::parse(&irradiance.to_string()),
+        Ok(irradiance)
+    );
+
+    let energy = CompositeUnit::parse("kW*h").unwrap();
+    assert_eq!(CompositeUnit::parse(&energy.to_string()), Ok(energy));
+    assert_eq!(
+        CompositeUnit::parse("op/min").unwrap().to_string(),
+        Unit::parse_composite("op/min").unwrap().to_string()
+    );
+    assert_eq!(
+        CompositeUnit::parse("m*s/s").unwrap(),
+        CompositeUnit::from(METER)
+    );
+    assert!(CompositeUnit::parse("m^").is_err());
+}
+
+#[test]
+fn reject_large_exponents() {
+    assert_eq!(
+        CompositeUnit::parse("m^127").unwrap(),
+        CompositeUnit::from(METER).powi(127)
+    );
+    for input in [
+        "m^200",
+        "m^-129",
+        "m^100*m^100",
+        "m^99999999999",
+        "1/m^-128",
+    ] {
+        assert!(
+            matches!(
+                CompositeUnit::parse(input),
+                Err(UnitError::ParseError(_))
+            ),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn convert_products() {
+    let from = CompositeUnit::parse("MB/s").unwrap();
+    let to = CompositeUnit::parse("B/s").unwrap();
+    assert_eq!(from.convert(&to, 1.0), Ok(1048576.0));
+
+    let from = CompositeUnit::parse("kW/m^2").unwrap();
+    let to = CompositeUnit::parse("W/cm^2").unwrap();
+    assert!((from.convert(&to, 1.0).unwrap() - 0.1).abs() < 1e-12);
+
+    let from = CompositeUnit::parse("kW*h").unwrap();
+    let to = CompositeUnit::parse("W*s").unwrap();
+    assert_eq!(from.convert(&to, 1.0), Ok(3600000.0));
+}
+
+#[test]
+fn reject_incompatible_dimensions() {
+    let time = CompositeUnit::from(SECOND);
+    let power = CompositeUnit::from(WATT);
+    assert!(matches!(
+        time.convert(&power, 1.0),
+        Err(UnitError::CompositeConversion(_, _))
+    ));
+    assert!(CompositeUnit::parse("W/m^2")
+        .unwrap()
+        .convert(&power, 1.0)
+        .is_err());
+}
+
+#[test]
+fn reject_incompatible_sums() {
+    let time = CompositeUnit::from(SECOND);
+    let power = CompositeUnit::from(WATT);
+    assert!(matches!(
+        time.clone() + power.clone(),
+        Err(UnitError::CompositeConversion(_, _))
+    ));
+    assert!(matches!(
+        power.clone() - time.clone(),
+        Err(UnitError::CompositeConversion(_, _))
+    ));
+    assert_eq!(time.clone() + time.clone(), Ok(time));
+
+    let mbps = CompositeUnit::parse("MB/s").unwrap();
+    let bps = CompositeUnit::parse("B/s").unwrap();
+    assert_eq!(mbps.clone() - bps, Ok(mbps));
+}
+
+#[test]
+fn single_unit_serialization() {
+    for unit in [BYTE, SECOND, WATT] {
+        let composite = CompositeUnit::from(unit);
+        assert_eq!(
+            serde_json::to_value(&composite).unwrap(),
+            serde_json::to_value(unit).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_value::<CompositeUnit>(
+                serde_json::to_value(unit).unwrap()
+            )
+            .unwrap(),
+            composite
+        );
+    }
+
+    let irradiance = CompositeUnit::parse("W/m^2").unwrap();
+    let json = serde_json::to_value(&irradiance).unwrap();
+    assert_eq!(json, serde_json::json!("W/m^2"));
+    assert_eq!(
+        serde_json::from_value::<CompositeUnit>(json).unwrap(),
+        irradiance
+    );
+}
+
+#[test]
+fn composite_quantities() {
+    let power = CompositeQuantity::from(
+        Quantity(2.0, WATT)
+            .convert(&Unit::Power(PowerUnit::Watt(SiPrefix::Kilo)))
+            .unwrap(),
+    );
+    let area = CompositeQuantity::from(Quantity(4.0, METER).powi(2).unwrap());
+    let irradiance = (power / area).unwrap();
+    assert_eq!(irradiance.1, CompositeUnit::parse("kW/m^2").unwrap());
+    assert_eq!(
+        irradiance.dimension(),
+        CompositeDimension::from_map(
+            [(Dimension::Power, 1), (Dimension::Length, -2)].into()
+        )
+    );
+    assert_eq!(irradiance.to_quantity(), None);
+    assert_eq!(irradiance.to_string(), "0.000125 kW/m^2");
+
+    let normalized = irradiance.normalize().unwrap();
+    assert_eq!(normalized.1, CompositeUnit::parse("W/m^2").unwrap());
+    assert!((normalized.0 - 0.125).abs() < 1e-12);
+
+    let big = CompositeQuantity(1.0, CompositeUnit::parse("m^100").unwrap());
+    assert_eq!(big.clone() * big, Err(UnitError::Exponent));
+}
@@ -0,0 +1,80 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use unit::{Dimension, PressureUnit, SiPrefix, Unit};
+
+#[test]
+fn parse_pressure_units() {
+    assert_eq!(
+        Unit::parse("Pa").unwrap(),
+        Unit::Pressure(PressureUnit::Pascal(SiPrefix::Unit))
+    );
+    assert_eq!(
+        Unit::parse("kPa").unwrap(),
+        Unit::Pressure(PressureUnit::Pascal(SiPrefix::Kilo))
+    );
+    assert_eq!(
+        Unit::parse("hPa").unwrap(),
+        Unit::Pressure(PressureUnit::Pascal(SiPrefix::Hecto))
+    );
+    assert_eq!(
+        Unit::parse("MPa").unwrap(),
+        Unit::Pressure(PressureUnit::Pascal(SiPrefix::Mega))
+    );
+    assert_eq!(
+        Unit::parse("mbar").unwrap(),
+        Unit::Pressure(PressureUnit::Bar(SiPrefix::Milli))
+    );
+    assert_eq!(
+        Unit::parse("psi").unwrap(),
+        Unit::Pressure(PressureUnit::Psi)
+    );
+    assert_eq!(
+        Unit::parse("atm").unwrap(),
+        Unit::Pressure(PressureUnit::Atmosphere)
+    );
+    assert_eq!(
+        Unit::parse("mmHg").unwrap(),
+        Unit::Pressure(PressureUnit::MmHg)
+    );
+    assert_eq!(Unit::parse("mm").unwrap().dimension(), Dimension::Length);
+}
+
+#[test]
+fn display_round_trips() {
+    for unit in Dimension::Pressure.units() {
+        assert_eq!(&Unit::parse(&unit.to_string()).unwrap(), unit);
+    }
+}
+
+#[test]
+fn convert_atmosphere() {
+    let atm = Unit::Pressure(PressureUnit::Atmosphere);
+    let pa = Unit::Pressure(PressureUnit::Pascal(SiPrefix::Unit));
+    let v = atm.convert(&pa, 1.0).unwrap();
+    assert_eq!(v, 101325.0);
+    assert_eq!(pa.convert(&atm, v).unwrap(), 1.0);
+}
+
+#[test]
+fn convert_pa_bar_psi() {
+    let pa = Unit::parse("Pa").unwrap();
+    let hpa = Unit::parse("hPa").unwrap();
+    let bar = Unit::parse("bar").unwrap();
+    let mbar = Unit::parse("mbar").unwrap();
+    let psi = Unit::parse("psi").unwrap();
+
+    assert_eq!(bar.convert(&pa, 1.0).unwrap(), 100000.0);
+    assert_eq!(mbar.convert(&hpa, 1013.25).unwrap(), 1013.25);
+    assert_eq!(pa.convert(&bar, 100000.0).unwrap(), 1.0);
+
+    /* Psi is not an integral power of ten: allow for rounding. */
+    for v in [0.5, 1.0, 14.7, 1013.25] {
+        let p = psi.convert(&pa, v).unwrap();
+        assert!((pa.convert(&psi, p).unwrap() - v).abs() <= v * 1e-12);
+        let b = psi.convert(&bar, v).unwrap();
+        assert!((bar.convert(&psi, b).unwrap() - v).abs() <= v * 1e-12);
+    }
+    assert!((psi.convert(&pa, 1.0).unwrap() - 6894.757293168361).abs() < 1e-9);
+}
@@ -0,0 +1,119 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Parsing and canonical formatting of network addresses, so that
+//! addresses from different sources compare equal regardless of the
+//! notation they were received in.
+
+use std::fmt;
+
+use super::error::DataError;
+
+/// Parse a mac address. Accepted notations are six groups of one or
+/// two hex digits separated by colons or dashes ("0:1b:2C:..."),
+/// three dot-separated groups of four digits ("001b.2c3d.4e5f") and
+/// twelve digits without separators.
+pub fn parse_mac_address(s: &str) -> Result<[u8; 6], DataError> {
+    let err = || DataError::InvalidMacAddress(s.to_string());
+    let t = s.trim();
+
+    let groups: Vec<&str> = if t.contains(':') {
+        t.split(':').collect()
+    } else if t.contains('-') {
+        t.split('-').collect()
+    } else if t.contains('.') {
+        let groups: Vec<&str> = t.split('.').collect();
+        if groups.len() != 3 || groups.iter().any(|g| g.len() != 4) {
+            return Err(err());
+        }
+        groups.iter().flat_map(|g| [&g[..2], &g[2..]]).collect()
+    } else if t.len() == 12 && t.is_ascii() {
+        (0..6).map(|i| &t[2 * i..2 * i + 2]).collect()
+    } else {
+        return Err(err());
+    };
+
+    if groups.len() != 6
+        || groups.iter().any(|g| {
+            g.is_empty()
+                || g.len() > 2
+                || !g.chars().all(|c| c.is_ascii_hexdigit())
+        })
+    {
+        return Err(err());
+    }
+
+    let mut addr = [0; 6];
+    for (byte, group) in addr.iter_mut().zip(groups) {
+        *byte = u8::from_str_radix(group, 16).map_err(|_| err())?;
+    }
+    Ok(addr)
+}
+
+/// Parse an ipv4 address in dotted decimal notation. Unlike the
+/// standard library, octets with leading zeros are accepted (and read
+/// as decimal), since some devices pad them.
+pub fn parse_ipv4_address(s: &str) -> Result<[u8; 4], DataError> {
+    let err = || DataError::InvalidIpv4Address(s.to_string());
+    let groups: Vec<&str> = s.trim().split('.').collect();
+
+    if groups.len() != 4
+        || groups.iter().any(|g| {
+            g.is_empty()
+                || g.len() > 3
+                || !g.chars().all(|c| c.is_ascii_digit())
+        })
+    {
+        return Err(err());
+    }
+
+    let mut addr = [0; 4];
+    for (byte, group) in addr.iter_mut().zip(groups) {
+        *byte = group.parse().map_err(|_| err())?;
+    }
+    Ok(addr)
+}
+
+/// Parse an ipv6 address in any of the notations of RFC 4291.
+pub fn parse_ipv6_address(s: &str) -> Result<[u16; 8], DataError> {
+    s.trim()
+        .parse::<std::net::Ipv6Addr>()
+        .map(|addr| addr.segments())
+        .map_err(|_| DataError::InvalidIpv6Address(s.to_string()))
+}
+
+/// Canonical mac address notation: lowercase, colon-separated,
+/// zero-padded ("00:1b:2c:3d:4e:5f").
+pub struct DisplayMac<'a>(pub &'a [u8; 6]);
+
+/// Dotted decimal ipv4 address notation, without leading zeros.
+pub struct DisplayIpv4<'a>(pub &'a [u8; 4]);
+
+/// Canonical ipv6 address notation (RFC 5952): lowercase, without
+/// leading zeros, with the longest run of zero groups compressed.
+pub struct DisplayIpv6<'a>(pub &'a [u16; 8]);
+
+impl fmt::Display for DisplayMac<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            v[0], v[1], v[2], v[3], v[4], v[5]
+        )
+    }
+}
+
+impl fmt::Display for DisplayIpv4<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+        write!(f, "{}.{}.{}.{}", v[0], v[1], v[2], v[3])
+    }
+}
+
+impl fmt::Display for DisplayIpv6<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", std::net::Ipv6Addr::from(*self.0))
+    }
+}
@@ -0,0 +1,366 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! Deterministic hashing of values, for use as a cache key across
+//! process runs. Unlike `std::collections::hash_map::DefaultHasher`,
+//! the digest does not depend on a random seed, and sets and maps are
+//! hashed in sorted order so that the iteration order of the
+//! underlying `HashSet` / `HashMap` does not matter.
+
+use std::hash::{Hash, Hasher};
+
+use unit::Unit;
+
+use crate::value::{EnumValue, IntEnumValue};
+use crate::{HashableValue, Value};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit FNV-1a hasher. Integers are written in little-endian byte
+/// order, so digests are stable across platforms.
+#[derive(Clone, Copy, Debug)]
+pub struct ContentHasher(u64);
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self(FNV_OFFSET)
+    }
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i])
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8)
+    }
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64)
+    }
+}
+
+impl HashableValue {
+    /// Deterministic digest of the value's content. This equals the
+    /// digest of the corresponding `Value`.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        self.content_hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    fn content_hash_into(&self, state: &mut ContentHasher) {
+        state.write_u8(self.content_tag());
+        match self {
+            HashableValue::BinaryString(v) => v.hash(state),
+            HashableValue::UnicodeString(v) => v.hash(state),
+            HashableValue::Integer(v) => v.hash(state),
+            HashableValue::Enum(v) => hash_enum(v, state),
+            HashableValue::IntEnum(v) => hash_int_enum(v, state),
+            HashableValue::Boolean(v) => v.hash(state),
+            HashableValue::MacAddress(v) => state.write(v),
+            HashableValue::Ipv4Address(v) => state.write(v),
+            HashableValue::Ipv6Address(v) => {
+                v.iter().for_each(|w| state.write_u16(*w))
+            }
+            HashableValue::Option(v) => match v.get_value() {
+                Some(v) => {
+                    true.hash(state);
+                    v.content_hash_into(state);
+                }
+                None => false.hash(state),
+            },
+            HashableValue::Result(v) => match v.get_value() {
+                Ok(v) => {
+                    true.hash(state);
+                    v.content_hash_into(state);
+                }
+                Err(v) => {
+                    false.hash(state);
+                    v.content_hash_into(state);
+                }
+            },
+            HashableValue::Tuple(vs) => hash_hashable_seq(vs, state),
+            HashableValue::List(v) => hash_hashable_seq(v.get_values(), state),
+        }
+    }
+
+    /// Fixed per-variant tag, shared with the corresponding `Value`
+    /// variant (see `Value::content_tag`). Never renumber.
+    fn content_tag(&self) -> u8 {
+        match self {
+            HashableValue::BinaryString(_) => 0,
+            HashableValue::UnicodeString(_) => 1,
+            HashableValue::Integer(_) => 2,
+            HashableValue::Enum(_) => 5,
+            HashableValue::IntEnum(_) => 6,
+            HashableValue::Boolean(_) => 7,
+            HashableValue::MacAddress(_) => 10,
+            HashableValue::Ipv4Address(_) => 11,
+            HashableValue::Ipv6Address(_) => 12,
+            HashableValue::Option(_) => 13,
+            HashableValue::Result(_) => 14,
+            HashableValue::Tuple(_) => 15,
+            HashableValue::List(_) => 16,
+        }
+    }
+}
+
+impl Value {
+    /// Deterministic digest of the value's content. Values that
+    /// compare equal hash identically, regardless of how sets and
+    /// maps were constructed.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        self.content_hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    fn content_hash_into(&self, state: &mut ContentHasher) {
+        state.write_u8(self.content_tag());
+        match self {
+            Value::BinaryString(v) => v.hash(state),
+            Value::UnicodeString(v) => v.hash(state),
+            Value::Integer(v) => v.hash(state),
+            Value::Float(v) => hash_float(*v, state),
+            Value::Quantity(v) => {
+                hash_float(v.0, state);
+                hash_unit(&v.1, state);
+            }
+            Value::Enum(v) => hash_enum(v, state),
+            Value::IntEnum(v) => hash_int_enum(v, state),
+            Value::Boolean(v) => v.hash(state),
+            Value::TriState(v) => v.to_bool().hash(state),
+            Value::Decimal(v) => v.hash(state),
+            Value::CompositeQuantity(v) => {
+                hash_float(v.0, state);
+                v.1.to_string().hash(state);
+            }
+            Value::Time(v) => {
+                v.timestamp().hash(state);
+                v.timestamp_subsec_nanos().hash(state);
+            }
+            Value::Age(v) => v.num_nanoseconds().hash(state),
+            Value::MacAddress(v) => state.write(v),
+            Value::Ipv4Address(v) => state.write(v),
+            Value::Ipv6Address(v) => v.iter().for_each(|w| state.write_u16(*w)),
+            Value::Option(v) => match v.get_value() {
+                Some(v) => {
+                    true.hash(state);
+                    v.content_hash_into(state);
+                }
+                None => false.hash(state),
+            },
+            Value::Result(v) => match v.get_value() {
+                Ok(v) => {
+                    true.hash(state);
+                    v.content_hash_into(state);
+                }
+                Err(v) => {
+                    false.hash(state);
+                    v.content_hash_into(state);
+                }
+            },
+            Value::Tuple(vs) => hash_seq(vs, state),
+            Value::List(v) => hash_seq(v.get_values(), state),
+            Value::Set(v) => {
+                let mut vs = v.get_values().iter().collect::<Vec<_>>();
+                vs.sort();
+                vs.len().hash(state);
+                vs.into_iter().for_each(|v| v.content_hash_into(state));
+            }
+            Value::Map(v) => {
+                let mut vs = v.get_values().iter().collect::<Vec<_>>();
+                vs.sort_by_key(|(k, _)| *k);
+                vs.len().hash(state);
+                vs.into_iter().for_each(|(k, v)| {
+                    k.content_hash_into(state);
+                    v.content_hash_into(state);
+                });
+            }
+            Value::Json(v) => hash_json(v, state),
+        }
+    }
+
+    /// Fixed per-variant tag. Unlike `std::mem::discriminant`, this
+    /// does not change when variants are added to the enum. Never
+    /// renumber existing tags; new variants get a new number.
+    fn content_tag(&self) -> u8 {
+        match self {
+            Value::BinaryString(_) => 0,
+            Value::UnicodeString(_) => 1,
+            Value::Integer(_) => 2,
+            Value::Float(_) => 3,
+            Value::Quantity(_) => 4,
+            Value::Enum(_) => 5,
+            Value::IntEnum(_) => 6,
+            Value::Boolean(_) => 7,
+            Value::Time(_) => 8,
+            Value::Age(_) => 9,
+            Value::MacAddress(_) => 10,
+            Value::Ipv4Address(_) => 11,
+            Value::Ipv6Address(_) => 12,
+            Value::Option(_) => 13,
+            Value::Result(_) => 14,
+            Value::Tuple(_) => 15,
+            Value::List(_) => 16,
+            Value::Set(_) => 17,
+            Value::Map(_) => 18,
+            Value::Json(_) => 19,
+            Value::TriState(_) => 20,
+            Value::Decimal(_) => 21,
+            Value::CompositeQuantity(_) => 22,
+        }
+    }
+}
+
+fn hash_seq(vs: &[Value], state: &mut ContentHasher) {
+    vs.len().hash(state);
+    vs.iter().for_each(|v| v.content_hash_into(state));
+}
+
+fn hash_hashable_seq(vs: &[HashableValue], state: &mut ContentHasher) {
+    vs.len().hash(state);
+    vs.iter().for_each(|v| v.content_hash_into(state));
+}
+
+fn hash_enum(v: &EnumValue, state: &mut ContentHasher) {
+    let choices = v.choices();
+    choices.len().hash(state);
+    choices.iter().for_each(|c| c.hash(state));
+    v.get_value().hash(state);
+}
+
+fn hash_int_enum(v: &IntEnumValue, state: &mut ContentHasher) {
+    let choices = v.choices();
+    choices.len().hash(state);
+    choices.iter().for_each(|(i, c)| {
+        i.hash(state);
+        c.hash(state);
+    });
+    v.get_value_int().hash(state);
+}
+
+/// Hash a unit by a fixed tag for its variant and its symbol, so
+/// that the digest does not depend on the declaration order of the
+/// unit enums.
+fn hash_unit(unit: &Unit, state: &mut ContentHasher) {
+    state.write_u8(match unit {
+        Unit::Information(_) => 0,
+        Unit::Operations(_) => 1,
+        Unit::Length(_) => 2,
+        Unit::Mass(_) => 3,
+        Unit::Time(_) => 4,
+        Unit::TimeSquare(_) => 5,
+        Unit::Temperature(_) => 6,
+        Unit::Current(_) => 7,
+        Unit::Potential(_) => 8,
+        Unit::Power(_) => 9,
+        Unit::Resistance(_) => 10,
+        Unit::Conductivity(_) => 11,
+        Unit::Area(_) => 12,
+        Unit::Volume(_) => 13,
+        Unit::Speed(_, _) => 14,
+        Unit::Acceleration(_, _) => 15,
+        Unit::AbsoluteHumidity(_, _) => 16,
+        Unit::Bandwidth(_, _) => 17,
+        Unit::IOLatency(_, _) => 18,
+        Unit::IOPerformance(_, _) => 19,
+        Unit::AvgOpSize(_, _) => 20,
+        Unit::Frequency(_) => 21,
+        Unit::FanSpeed(_) => 22,
+        Unit::Dimensionless(_) => 23,
+        Unit::Pressure(_) => 24,
+    });
+    unit.to_string().hash(state);
+}
+
+/// Hash floats so that `0.0 == -0.0` hash identically and all NaNs
+/// share one digest.
+fn hash_float(v: f64, state: &mut ContentHasher) {
+    if v.is_nan() {
+        f64::NAN.to_bits().hash(state)
+    } else if v == 0.0 {
+        0u64.hash(state)
+    } else {
+        v.to_bits().hash(state)
+    }
+}
+
+fn hash_json(v: &serde_json::Value, state: &mut ContentHasher) {
+    match v {
+        serde_json::Value::Null => state.write_u8(0),
+        serde_json::Value::Bool(b) => {
+            state.write_u8(1);
+            b.hash(state);
+        }
+        serde_json::Value::Number(n) => {
+            state.write_u8(2);
+            n.to_string().hash(state);
+        }
+        serde_json::Value::String(s) => {
+            state.write_u8(3);
+            s.hash(state);
+        }
+        serde_json::Value::Array(vs) => {
+            state.write_u8(4);
+            vs.len().hash(state);
+            vs.iter().for_each(|v| hash_json(v, state));
+        }
+        serde_json::Value::Object(vs) => {
+            state.write_u8(5);
+            let mut vs = vs.iter().collect::<Vec<_>>();
+            vs.sort_by_key(|(k, _)| *k);
+            vs.len().hash(state);
+            vs.into_iter().for_each(|(k, v)| {
+                k.hash(state);
+                hash_json(v, state);
+            });
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+//! CSV (RFC 4180) serialization of tables, for export to
+//! spreadsheets. Values are formatted with `Value::format`; missing
+//! values and values that fail to format are left empty.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+
+use crate::{Data, FormatOpts};
+
+/// Serialize a table to CSV, with a header row holding the field
+/// names of all rows, in sorted order.
+pub fn to_csv<K>(rows: &[HashMap<K, Data>], opts: &FormatOpts) -> String
+where
+    K: Display + Ord + Hash,
+{
+    let columns: BTreeSet<&K> =
+        rows.iter().flat_map(|row| row.keys()).collect();
+    to_csv_with_columns(columns, rows, opts)
+}
+
+/// Serialize a table to CSV, with the given columns in order.
+pub fn to_csv_with_columns<'a, K, I>(
+    columns: I,
+    rows: &[HashMap<K, Data>],
+    opts: &FormatOpts,
+) -> String
+where
+    K: Display + Eq + Hash + 'a,
+    I: IntoIterator<Item = &'a K>,
+{
+    let columns: Vec<&K> = columns.into_iter().collect();
+    let mut csv = String::new();
+    write_record(&mut csv, columns.iter().map(|col| col.to_string()));
+    for row in rows {
+        write_record(
+            &mut csv,
+            columns.iter().map(|col| match row.get(*col) {
+                Some(Ok(value)) => value.format(opts).unwrap_or_default(),
+                Some(Err(_)) | None => String::new(),
+            }),
+        );
+    }
+    csv
+}
+
+fn write_record<I: Iterator<Item = String>>(csv: &mut String, fields: I) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        write_field(csv, &field);
+    }
+    csv.push_str("\r\n");
+}
+
+/// Write a field, quoting it if it contains a separator, a quote or
+/// a line break. Quotes are escaped by doubling them.
+fn write_field(csv: &mut String, field: &str) {
+    match field.contains([',', '"', '\r', '\n']) {
+        true => {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        }
+        false => csv.push_str(field),
+    }
+}
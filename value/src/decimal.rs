@@ -0,0 +1,292 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::DataError;
+
+/// Exact decimal number, for metrics that should not suffer float
+/// rounding, such as costs. Stored as an integer mantissa scaled by
+/// a power of ten: addition, subtraction and multiplication are exact
+/// and fail on overflow instead of losing precision. Values compare
+/// numerically, regardless of scale (1.5 == 1.50), but are displayed
+/// with their own scale. Serialized as a string, e.g. "12.50".
+#[derive(Clone, Copy, Debug)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The value `mantissa` * 10^-`scale`.
+    pub const fn new(mantissa: i128, scale: u32) -> Self {
+        Self { mantissa, scale }
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Nearest float. Precision is lost, as with any conversion to
+    /// float.
+    pub fn to_f64(&self) -> f64 {
+        /* Parsing the exact representation rounds correctly. */
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// The same value, with the given number of decimal places.
+    /// Extra places are rounded half away from zero.
+    pub fn rescale(&self, scale: u32) -> Result<Self, DataError> {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Ok(*self),
+            Ordering::Greater => Ok(Self::new(
+                upscale(self.mantissa, scale - self.scale)
+                    .ok_or(DataError::DecimalOverflow)?,
+                scale,
+            )),
+            Ordering::Less => {
+                let mantissa = match pow10(self.scale - scale) {
+                    Some(div) => {
+                        let (q, r) = (self.mantissa / div, self.mantissa % div);
+                        match r.unsigned_abs() * 2 >= div.unsigned_abs() {
+                            true => q + self.mantissa.signum(),
+                            false => q,
+                        }
+                    }
+                    /* More places than the mantissa can hold. */
+                    None => 0,
+                };
+                Ok(Self::new(mantissa, scale))
+            }
+        }
+    }
+
+    /// The same value without trailing zeros.
+    pub fn normalize(&self) -> Self {
+        let mut d = *self;
+        while d.scale > 0 && d.mantissa % 10 == 0 {
+            d.mantissa /= 10;
+            d.scale -= 1;
+        }
+        d
+    }
+
+    /// Both values at the larger of their scales.
+    fn align(self, rhs: Self) -> Result<(i128, i128, u32), DataError> {
+        let scale = self.scale.max(rhs.scale);
+        Ok((
+            self.rescale(scale)?.mantissa,
+            rhs.rescale(scale)?.mantissa,
+            scale,
+        ))
+    }
+}
+
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+fn upscale(mantissa: i128, exp: u32) -> Option<i128> {
+    mantissa.checked_mul(pow10(exp)?)
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value as i128, 0)
+    }
+}
+
+impl Add for Decimal {
+    type Output = Result<Decimal, DataError>;
+    fn add(self, rhs: Self) -> Self::Output {
+        let (a, b, scale) = self.align(rhs)?;
+        Ok(Self::new(
+            a.checked_add(b).ok_or(DataError::DecimalOverflow)?,
+            scale,
+        ))
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Result<Decimal, DataError>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        let (a, b, scale) = self.align(rhs)?;
+        Ok(Self::new(
+            a.checked_sub(b).ok_or(DataError::DecimalOverflow)?,
+            scale,
+        ))
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Result<Decimal, DataError>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (a, b) = (self.normalize(), rhs.normalize());
+        Ok(Self::new(
+            a.mantissa
+                .checked_mul(b.mantissa)
+                .ok_or(DataError::DecimalOverflow)?,
+            a.scale
+                .checked_add(b.scale)
+                .ok_or(DataError::DecimalOverflow)?,
+        ))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.normalize(), other.normalize());
+        /* If the value with fewer places overflows when scaled up,
+         * its magnitude is larger than the other's. */
+        match a.scale.cmp(&b.scale) {
+            Ordering::Equal => a.mantissa.cmp(&b.mantissa),
+            Ordering::Less => match upscale(a.mantissa, b.scale - a.scale) {
+                Some(m) => m.cmp(&b.mantissa),
+                None => a.mantissa.cmp(&0),
+            },
+            Ordering::Greater => match upscale(b.mantissa, a.scale - b.scale) {
+                Some(m) => a.mantissa.cmp(&m),
+                None => 0.cmp(&b.mantissa),
+            },
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let d = self.normalize();
+        d.mantissa.hash(state);
+        d.scale.hash(state);
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DataError;
+    /// Parse decimal notation with an optional exponent, e.g.
+    /// "-12.50" or "1.25e3".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || DataError::Parse(s.to_string(), String::from("decimal"));
+        let (num, exp) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i32>().map_err(|_| err())?),
+            None => (s, 0),
+        };
+        let (neg, num) = match num.strip_prefix('-') {
+            Some(num) => (true, num),
+            None => (false, num.strip_prefix('+').unwrap_or(num)),
+        };
+        let (int, frac) = num.split_once('.').unwrap_or((num, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(err());
+        }
+
+        let mut mantissa: i128 = 0;
+        for c in int.chars().chain(frac.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(c as i128 - '0' as i128))
+                .ok_or(DataError::DecimalOverflow)?;
+        }
+        if neg {
+            mantissa = -mantissa;
+        }
+
+        let scale = frac.len() as i64 - exp as i64;
+        match scale < 0 {
+            true => Ok(Self::new(
+                upscale(mantissa, scale.unsigned_abs() as u32)
+                    .ok_or(DataError::DecimalOverflow)?,
+                0,
+            )),
+            false => Ok(Self::new(
+                mantissa,
+                u32::try_from(scale).map_err(|_| err())?,
+            )),
+        }
+    }
+}
+
+/// Displayed with all places, or rounded to the precision given in
+/// the format string (e.g. "{:.2}").
+impl Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = match f.precision() {
+            Some(p) => self.rescale(p as u32).unwrap_or(*self),
+            None => *self,
+        };
+        let digits = d.mantissa.unsigned_abs().to_string();
+        let scale = d.scale as usize;
+        let digits = match digits.len() <= scale {
+            true => {
+                format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+            }
+            false => digits,
+        };
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        if d.mantissa < 0 {
+            write!(f, "-")?;
+        }
+        match frac.is_empty() {
+            true => write!(f, "{int}"),
+            false => write!(f, "{int}.{frac}"),
+        }
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        match DecimalRepr::deserialize(deserializer)? {
+            DecimalRepr::String(s) => s.parse(),
+            DecimalRepr::Integer(n) => Ok(Self::from(n)),
+            /* The shortest representation that reads back as the
+             * same float, e.g. 0.1 rather than its binary value. */
+            DecimalRepr::Float(n) => n.to_string().parse(),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DecimalRepr {
+    String(String),
+    Integer(i64),
+    Float(f64),
+}
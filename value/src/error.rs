@@ -28,11 +28,11 @@ pub enum DataError {
     CounterPending,
     #[error("Counter undefined")]
     CounterUndefined,
-    #[error("Invalid mac address")]
+    #[error("Invalid mac address: {0}")]
     InvalidMacAddress(String),
-    #[error("Invalid ipv4 address")]
+    #[error("Invalid ipv4 address: {0}")]
     InvalidIpv4Address(String),
-    #[error("Invalid ipv6 address")]
+    #[error("Invalid ipv6 address: {0}")]
     InvalidIpv6Address(String),
     #[error("Invalid option value")]
     InvalidOptionValue,
@@ -50,4 +50,6 @@ pub enum DataError {
     External(String),
     #[error("Failed to parse value {0:?} to type {1}")]
     Parse(String, String),
+    #[error("Decimal overflow")]
+    DecimalOverflow,
 }
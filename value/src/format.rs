@@ -3,11 +3,12 @@
  ******************************************************************************/
 
 use thiserror::Error;
-use unit::{Quantity, UnitError};
+use unit::{CompositeQuantity, Quantity, UnitError};
 
+use crate::addr::{DisplayIpv4, DisplayIpv6, DisplayMac};
 use crate::HashableValue;
 
-use super::options::FormatOpts;
+use super::options::{EnumRepr, FormatOpts};
 use super::value::Value;
 
 pub struct Format<'a, T>(pub(crate) &'a T);
@@ -28,6 +29,10 @@ impl Format<'_, Value> {
                 Some(d) => write!(f, "{n:.0$}", *d as usize)?,
                 None => write!(f, "{n}")?,
             },
+            Value::Decimal(n) => match &opts.precision {
+                Some(d) => write!(f, "{n:.0$}", *d as usize)?,
+                None => write!(f, "{n}")?,
+            },
             Value::Quantity(q) => {
                 let q = match &opts.unit {
                     Some(unit) => q.convert(unit)?,
@@ -42,24 +47,27 @@ impl Format<'_, Value> {
                     None => write!(f, "{n} {u}")?,
                 }
             }
+            Value::CompositeQuantity(CompositeQuantity(n, u)) => {
+                match &opts.precision {
+                    Some(d) => write!(f, "{n:.0$} {u}", *d as usize)?,
+                    None => write!(f, "{n} {u}")?,
+                }
+            }
             Value::Enum(v) => write!(f, "{}", v.get_value())?,
-            Value::IntEnum(v) => write!(f, "{}", v.get_value_str())?,
+            Value::IntEnum(v) => match opts.enum_repr {
+                EnumRepr::Label => write!(f, "{}", v.get_value_str())?,
+                EnumRepr::Code => write!(f, "{}", v.get_value_int())?,
+                EnumRepr::Both => {
+                    write!(f, "{} ({})", v.get_value_str(), v.get_value_int())?
+                }
+            },
             Value::Boolean(v) => write!(f, "{v}")?,
+            Value::TriState(v) => write!(f, "{v}")?,
             Value::Time(t) => write!(f, "{}", t.to_rfc3339())?,
             Value::Age(d) => write!(f, "{}s", d.num_seconds())?, // TODO!
-            Value::MacAddress(v) => write!(
-                f,
-                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            )?,
-            Value::Ipv4Address(v) => {
-                write!(f, "{}.{}.{}.{}", v[0], v[1], v[2], v[3])?
-            }
-            Value::Ipv6Address(v) => write!(
-                f,
-                "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            )?,
+            Value::MacAddress(v) => write!(f, "{}", DisplayMac(v))?,
+            Value::Ipv4Address(v) => write!(f, "{}", DisplayIpv4(v))?,
+            Value::Ipv6Address(v) => write!(f, "{}", DisplayIpv6(v))?,
             Value::Option(v) => match v.get_value() {
                 Some(v) => Format(v).fmt(f, opts)?,
                 None => write!(f, "-")?,
@@ -143,19 +151,9 @@ impl Format<'_, HashableValue> {
             HashableValue::Enum(v) => write!(f, "{}", v.get_value())?,
             HashableValue::IntEnum(v) => write!(f, "{}", v.get_value_str())?,
             HashableValue::Boolean(v) => write!(f, "{v}")?,
-            HashableValue::MacAddress(v) => write!(
-                f,
-                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            )?,
-            HashableValue::Ipv4Address(v) => {
-                write!(f, "{}.{}.{}.{}", v[0], v[1], v[2], v[3])?
-            }
-            HashableValue::Ipv6Address(v) => write!(
-                f,
-                "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            )?,
+            HashableValue::MacAddress(v) => write!(f, "{}", DisplayMac(v))?,
+            HashableValue::Ipv4Address(v) => write!(f, "{}", DisplayIpv4(v))?,
+            HashableValue::Ipv6Address(v) => write!(f, "{}", DisplayIpv6(v))?,
             HashableValue::Option(v) => match v.get_value() {
                 Some(v) => Format(v).fmt(f, opts)?,
                 None => write!(f, "-")?,
@@ -3,9 +3,7 @@
  ******************************************************************************/
 
 use std::collections::{BTreeMap, BTreeSet};
-use std::convert::TryInto;
 use std::fmt::Display;
-use std::str::FromStr;
 use std::sync::Arc;
 
 use serde::de::DeserializeOwned;
@@ -18,6 +16,10 @@ use dbschema::{
     ListSchema, OptionSchema, StringSchema, StructSchema, UnitSchema,
 };
 
+use crate::addr::{
+    parse_ipv4_address, parse_ipv6_address, parse_mac_address, DisplayIpv4,
+    DisplayIpv6, DisplayMac,
+};
 use crate::value::{
     EnumValue, IntEnumValue, ListValue, OptionValue, ResultValue,
 };
@@ -199,37 +201,25 @@ impl HashableType {
                 IntEnumValue::new(cs.clone(), decode(value)?)?,
             )),
             HashableType::MacAddress => {
-                let s: String = decode(value)?;
-                Ok(HashableValue::MacAddress(
-                    s.split(':')
-                        .map(|n| u8::from_str_radix(n, 16))
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|_| {
-                            DataError::InvalidMacAddress(s.to_string())
-                        })?
-                        .try_into()
-                        .map_err(|_| {
-                            DataError::InvalidMacAddress(s.to_string())
-                        })?,
-                ))
+                Ok(HashableValue::MacAddress(parse_mac_address(&decode::<
+                    String,
+                >(
+                    value
+                )?)?))
             }
             HashableType::Ipv4Address => {
-                let s: String = decode(value)?;
-                Ok(HashableValue::Ipv4Address(
-                    std::net::Ipv4Addr::from_str(&s)
-                        .map_err(|_| DataError::InvalidIpv4Address(s))?
-                        .octets(),
-                ))
+                Ok(HashableValue::Ipv4Address(parse_ipv4_address(&decode::<
+                    String,
+                >(
+                    value
+                )?)?))
             }
             HashableType::Ipv6Address => {
-                let s: String = decode(value)?;
-                Ok(HashableValue::Ipv6Address(
-                    std::net::Ipv6Addr::from_str(&s)
-                        .map_err(|_| {
-                            DataError::InvalidIpv6Address(s.to_string())
-                        })?
-                        .segments(),
-                ))
+                Ok(HashableValue::Ipv6Address(parse_ipv6_address(&decode::<
+                    String,
+                >(
+                    value
+                )?)?))
             }
             HashableType::Option(t) => {
                 Ok(HashableValue::Option(HashableOptionValue(
@@ -493,17 +483,15 @@ impl HashableValue {
             HashableValue::Enum(v) => Ok(json!(v.get_value())),
             HashableValue::IntEnum(v) => Ok(json!(v.get_value_str())),
             HashableValue::Boolean(v) => Ok(json!(v)),
-            HashableValue::MacAddress(v) => Ok(json!(format!(
-                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            ))),
+            HashableValue::MacAddress(v) => {
+                Ok(json!(DisplayMac(v).to_string()))
+            }
             HashableValue::Ipv4Address(v) => {
-                Ok(json!(format!("{}.{}.{}.{}", v[0], v[1], v[2], v[3])))
+                Ok(json!(DisplayIpv4(v).to_string()))
+            }
+            HashableValue::Ipv6Address(v) => {
+                Ok(json!(DisplayIpv6(v).to_string()))
             }
-            HashableValue::Ipv6Address(v) => Ok(json!(format!(
-                "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            ))),
             HashableValue::Option(v) => match &v.1 {
                 Some(v) => v.to_json_value(),
                 None => Ok(json!(null)),
@@ -677,19 +665,11 @@ impl Display for HashableValue {
                 write!(f, "{}", v.get_value_str())
             }
             HashableValue::Boolean(v) => write!(f, "{v}"),
-            HashableValue::MacAddress(v) => write!(
-                f,
-                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            ),
+            HashableValue::MacAddress(v) => write!(f, "{}", DisplayMac(v)),
             HashableValue::Ipv4Address(v) => {
-                write!(f, "{},{}.{}.{}", v[0], v[1], v[2], v[3])
+                write!(f, "{}", DisplayIpv4(v))
             }
-            HashableValue::Ipv6Address(v) => write!(
-                f,
-                "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            ),
+            HashableValue::Ipv6Address(v) => write!(f, "{}", DisplayIpv6(v)),
             HashableValue::Option(v) => match v.get_value() {
                 None => write!(f, "None"),
                 Some(v) => write!(f, "{v}"),
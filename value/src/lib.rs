@@ -2,6 +2,10 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+pub mod addr;
+pub mod content_hash;
+pub mod csv;
+pub mod decimal;
 pub mod defaults;
 pub mod enums_type;
 pub mod error;
@@ -10,16 +14,25 @@ pub mod hashable;
 pub mod numeric_pair;
 pub mod options;
 pub mod pyrepr;
+pub mod tristate;
 pub mod types;
 pub mod value;
 
 pub use crate::value::{
-    EnumValue, IntEnumValue, OptionValue, ResultValue, SetValue, Value,
+    EnumValue, IntEnumValue, ListValue, OptionValue, ResultValue, SetValue,
+    Value,
 };
+pub use addr::{parse_ipv4_address, parse_ipv6_address, parse_mac_address};
+pub use content_hash::ContentHasher;
+pub use csv::{to_csv, to_csv_with_columns};
+pub use decimal::Decimal;
 pub use defaults::https_port;
 pub use enums_type::EnumType;
 pub use error::{Data, DataError};
 pub use hashable::{HashableOptionValue, HashableResultValue, HashableValue};
 pub use numeric_pair::{NumericTypePair, NumericValuePair};
-pub use options::{FormatOpts, TypeOpts};
+pub use options::{
+    EnumRepr, Epsilon, FormatOpts, TypeOpts, ENUM_CODE_SUFFIX,
+};
+pub use tristate::TriState;
 pub use types::Type;
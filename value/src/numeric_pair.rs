@@ -2,10 +2,20 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
-use super::{Type, Value};
+use super::{Decimal, Type, Value};
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
-use unit::{Dimension, Quantity};
+use unit::{Dimension, FracPrefix, Quantity, TimeUnit, Unit};
 
+/// The common type of two numeric operands. Decimals are promoted as
+/// follows:
+///  - decimal with decimal or integer: decimal (exact);
+///  - decimal with float: float (precision is lost);
+///  - decimal with quantity: quantity, the decimal being converted to
+///    a dimensionless float.
+///
+/// Operations that cannot be exact on decimals, such as division,
+/// yield a float.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum NumericTypePair {
     #[serde(rename = "integer")]
@@ -14,6 +24,8 @@ pub enum NumericTypePair {
     Float,
     #[serde(rename = "quantity")]
     Quantity(Dimension, Dimension),
+    #[serde(rename = "decimal")]
+    Decimal,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -24,22 +36,38 @@ pub enum NumericValuePair {
     Float(f64, f64),
     #[serde(rename = "quantity")]
     Quantity(Quantity, Quantity),
+    #[serde(rename = "decimal")]
+    Decimal(Decimal, Decimal),
 }
 
 impl NumericTypePair {
     pub fn from(left: Type, right: Type) -> Option<Self> {
         match (left, right) {
             (Type::Integer, Type::Integer) => Some(Self::Integer),
-            (Type::Float | Type::Integer, Type::Float | Type::Integer) => {
-                Some(Self::Float)
-            }
-            (Type::Quantity(d), Type::Integer | Type::Float)
-            | (Type::Integer | Type::Float, Type::Quantity(d)) => {
-                Some(Self::Quantity(d, Dimension::Dimensionless))
+            (Type::Decimal | Type::Integer, Type::Decimal | Type::Integer) => {
+                Some(Self::Decimal)
             }
+            (
+                Type::Float | Type::Integer | Type::Decimal,
+                Type::Float | Type::Integer | Type::Decimal,
+            ) => Some(Self::Float),
+            (
+                Type::Quantity(d),
+                Type::Integer | Type::Float | Type::Decimal,
+            )
+            | (
+                Type::Integer | Type::Float | Type::Decimal,
+                Type::Quantity(d),
+            ) => Some(Self::Quantity(d, Dimension::Dimensionless)),
             (Type::Quantity(l), Type::Quantity(r)) => {
                 Some(Self::Quantity(l, r))
             }
+            (Type::Quantity(d), Type::Age) => {
+                Some(Self::Quantity(d, Dimension::Time))
+            }
+            (Type::Age, Type::Quantity(d)) => {
+                Some(Self::Quantity(Dimension::Time, d))
+            }
             _ => None,
         }
     }
@@ -50,6 +78,7 @@ impl NumericValuePair {
         match self {
             Self::Integer(_, _) => NumericTypePair::Integer,
             Self::Float(_, _) => NumericTypePair::Float,
+            Self::Decimal(_, _) => NumericTypePair::Decimal,
             Self::Quantity(l, r) => {
                 NumericTypePair::Quantity(l.dimension(), r.dimension())
             }
@@ -66,6 +95,25 @@ impl NumericValuePair {
                 Some(Self::Float(l as f64, r))
             }
             (Value::Float(l), Value::Float(r)) => Some(Self::Float(l, r)),
+            (Value::Decimal(l), Value::Decimal(r)) => Some(Self::Decimal(l, r)),
+            (Value::Decimal(l), Value::Integer(r)) => {
+                Some(Self::Decimal(l, Decimal::from(r)))
+            }
+            (Value::Integer(l), Value::Decimal(r)) => {
+                Some(Self::Decimal(Decimal::from(l), r))
+            }
+            (Value::Decimal(l), Value::Float(r)) => {
+                Some(Self::Float(l.to_f64(), r))
+            }
+            (Value::Float(l), Value::Decimal(r)) => {
+                Some(Self::Float(l, r.to_f64()))
+            }
+            (Value::Quantity(l), Value::Decimal(r)) => {
+                Some(Self::Quantity(l, Quantity::from_value(r.to_f64())))
+            }
+            (Value::Decimal(l), Value::Quantity(r)) => {
+                Some(Self::Quantity(Quantity::from_value(l.to_f64()), r))
+            }
             (Value::Quantity(l), Value::Integer(r)) => {
                 Some(Self::Quantity(l, Quantity::from_value(r as f64)))
             }
@@ -81,7 +129,23 @@ impl NumericValuePair {
             (Value::Quantity(l), Value::Quantity(r)) => {
                 Some(Self::Quantity(l, r))
             }
+            (Value::Quantity(l), Value::Age(r)) => {
+                Some(Self::Quantity(l, age_quantity(r)))
+            }
+            (Value::Age(l), Value::Quantity(r)) => {
+                Some(Self::Quantity(age_quantity(l), r))
+            }
             _ => None,
         }
     }
 }
+
+/// Durations combine with quantities as a time in seconds, so that
+/// e.g. an amount of bytes divided by an age yields a bandwidth.
+fn age_quantity(age: Duration) -> Quantity {
+    let seconds = match age.num_nanoseconds() {
+        Some(ns) => ns as f64 / 1e9,
+        None => age.num_milliseconds() as f64 / 1e3,
+    };
+    Quantity(seconds, Unit::Time(TimeUnit::Second(FracPrefix::Unit)))
+}
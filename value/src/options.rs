@@ -2,6 +2,7 @@
  * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
  ******************************************************************************/
 
+use serde::{Deserialize, Serialize};
 use unit::Unit;
 
 #[derive(Default, Debug)]
@@ -15,4 +16,53 @@ pub struct FormatOpts {
     pub autoscale: bool,
     pub precision: Option<u8>,
     pub unit: Option<Unit>,
+    pub enum_repr: EnumRepr,
+}
+
+/// How integer enum values are represented in output.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EnumRepr {
+    /// The label, e.g. "up".
+    #[default]
+    Label,
+    /// The integer code, e.g. 1.
+    Code,
+    /// The label, with the code in a separate field.
+    Both,
+}
+
+/// Suffix of the separate field holding the code of an integer enum
+/// value, when represented as `EnumRepr::Both`.
+pub const ENUM_CODE_SUFFIX: &str = "_code";
+
+/// Tolerance for approximate comparison of floating point values.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Epsilon {
+    /// Maximum absolute difference.
+    Absolute(f64),
+    /// Maximum difference relative to the largest magnitude.
+    Relative(f64),
+}
+
+impl Epsilon {
+    /// Whether `a` and `b` are equal within the tolerance.
+    pub fn within(self, a: f64, b: f64) -> bool {
+        a == b
+            || match self {
+                Epsilon::Absolute(eps) => (a - b).abs() <= eps,
+                Epsilon::Relative(eps) => {
+                    (a - b).abs() <= eps * a.abs().max(b.abs())
+                }
+            }
+    }
+}
+
+impl Default for Epsilon {
+    fn default() -> Self {
+        Epsilon::Relative(1e-9)
+    }
 }
@@ -5,8 +5,9 @@
 use std::fmt::{self, Display};
 
 use agent_utils::pyrepr::{PyBytes, PyUnicode};
-use unit::Quantity;
+use unit::{CompositeQuantity, Quantity};
 
+use crate::addr::{DisplayIpv4, DisplayIpv6, DisplayMac};
 use crate::{HashableValue, Value};
 
 pub struct PyRepr<'a, T>(pub(crate) &'a T);
@@ -20,9 +21,13 @@ impl Display for PyRepr<'_, Value> {
             Value::UnicodeString(v) => write!(f, "{}", PyUnicode(v)),
             Value::Integer(v) => write!(f, "{v}"),
             Value::Float(v) => write!(f, "{v}"),
+            Value::Decimal(v) => write!(f, "Decimal('{v}')"),
             Value::Quantity(Quantity(v, u)) => {
                 write!(f, "Quantity({v}, {})", PyUnicode(&u.to_string()))
             }
+            Value::CompositeQuantity(CompositeQuantity(v, u)) => {
+                write!(f, "Quantity({v}, {})", PyUnicode(&u.to_string()))
+            }
             Value::Time(v) => write!(f, "{}", PyUnicode(&v.to_string())),
             Value::Age(v) => write!(f, "{}", PyUnicode(&v.to_string())),
             Value::Enum(v) => write!(f, "{}", PyUnicode(v.get_value())),
@@ -31,19 +36,16 @@ impl Display for PyRepr<'_, Value> {
                 true => write!(f, "True"),
                 false => write!(f, "False"),
             },
-            Value::MacAddress(v) => write!(
-                f,
-                "'{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}'",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            ),
+            Value::TriState(v) => match v.to_bool() {
+                Some(true) => write!(f, "True"),
+                Some(false) => write!(f, "False"),
+                None => write!(f, "None"),
+            },
+            Value::MacAddress(v) => write!(f, "'{}'", DisplayMac(v)),
             Value::Ipv4Address(v) => {
-                write!(f, "'{}.{}.{}.{}'", v[0], v[1], v[2], v[3])
+                write!(f, "'{}'", DisplayIpv4(v))
             }
-            Value::Ipv6Address(v) => write!(
-                f,
-                "'{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}'",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            ),
+            Value::Ipv6Address(v) => write!(f, "'{}'", DisplayIpv6(v)),
             Value::Option(v) => match v.get_value() {
                 Some(v) => write!(f, "{}", PyRepr(v)),
                 None => write!(f, "None"),
@@ -115,19 +117,11 @@ impl Display for PyRepr<'_, HashableValue> {
                 true => write!(f, "True"),
                 false => write!(f, "False"),
             },
-            HashableValue::MacAddress(v) => write!(
-                f,
-                "'{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}'",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            ),
+            HashableValue::MacAddress(v) => write!(f, "'{}'", DisplayMac(v)),
             HashableValue::Ipv4Address(v) => {
-                write!(f, "'{}.{}.{}.{}'", v[0], v[1], v[2], v[3])
+                write!(f, "'{}'", DisplayIpv4(v))
             }
-            HashableValue::Ipv6Address(v) => write!(
-                f,
-                "'{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}'",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            ),
+            HashableValue::Ipv6Address(v) => write!(f, "'{}'", DisplayIpv6(v)),
             HashableValue::Option(v) => match v.get_value() {
                 Some(v) => write!(f, "{}", PyRepr(v)),
                 None => write!(f, "None"),
@@ -0,0 +1,104 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::DataError;
+
+/// Boolean with an explicit unknown state, for status fields that
+/// can be unavailable. Logic operators follow Kleene's three-valued
+/// logic: the result is unknown only if it depends on an unknown
+/// operand. Serialized as true, false or null.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[serde(from = "Option<bool>", into = "Option<bool>")]
+pub enum TriState {
+    False,
+    True,
+    Unknown,
+}
+
+impl TriState {
+    pub fn is_unknown(self) -> bool {
+        self == TriState::Unknown
+    }
+
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            TriState::False => Some(false),
+            TriState::True => Some(true),
+            TriState::Unknown => None,
+        }
+    }
+
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (TriState::False, _) | (_, TriState::False) => TriState::False,
+            (TriState::True, TriState::True) => TriState::True,
+            _ => TriState::Unknown,
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (TriState::True, _) | (_, TriState::True) => TriState::True,
+            (TriState::False, TriState::False) => TriState::False,
+            _ => TriState::Unknown,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        match self {
+            TriState::False => TriState::True,
+            TriState::True => TriState::False,
+            TriState::Unknown => TriState::Unknown,
+        }
+    }
+}
+
+impl From<bool> for TriState {
+    fn from(value: bool) -> Self {
+        match value {
+            true => TriState::True,
+            false => TriState::False,
+        }
+    }
+}
+
+impl From<Option<bool>> for TriState {
+    fn from(value: Option<bool>) -> Self {
+        value.map_or(TriState::Unknown, TriState::from)
+    }
+}
+
+impl From<TriState> for Option<bool> {
+    fn from(value: TriState) -> Self {
+        value.to_bool()
+    }
+}
+
+impl FromStr for TriState {
+    type Err = DataError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "true" => Ok(TriState::True),
+            "false" => Ok(TriState::False),
+            "unknown" => Ok(TriState::Unknown),
+            _ => Err(DataError::InvalidChoice(s.to_string())),
+        }
+    }
+}
+
+impl Display for TriState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriState::False => write!(f, "false"),
+            TriState::True => write!(f, "true"),
+            TriState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
@@ -3,16 +3,14 @@
  ******************************************************************************/
 
 use std::collections::{BTreeMap, BTreeSet, HashMap};
-use std::convert::TryInto;
 use std::fmt::{self, Display};
-use std::str::FromStr;
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, Utc};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use unit::{Dimension, Quantity, Unit};
+use unit::{CompositeDimension, CompositeQuantity, Dimension, Quantity, Unit};
 
 #[cfg(feature = "dbschema")]
 use dbschema::{
@@ -21,6 +19,7 @@ use dbschema::{
     OptionSchema, SetSchema, StringSchema, StructSchema, UnitSchema,
 };
 
+use crate::addr::{parse_ipv4_address, parse_ipv6_address, parse_mac_address};
 use crate::hashable::HashableType;
 use crate::value::{
     EnumValue, IntEnumValue, ListValue, MapValue, OptionValue, ResultValue,
@@ -73,6 +72,12 @@ pub enum Type {
     Set(Arc<HashableType>),
     Map(Arc<HashableType>, Arc<Type>),
     Json,
+    /* Appended, so that existing content hashes remain stable. */
+    TriState,
+    Decimal,
+    #[serde(rename = "composite-quantity")]
+    #[cfg_attr(feature = "schemars", schemars(with = "BTreeMap<String, i8>"))]
+    CompositeQuantity(CompositeDimension),
 }
 
 impl Type {
@@ -98,6 +103,9 @@ impl Type {
             Type::Time => false,
             Type::Age => false,
             Type::Json => false,
+            Type::TriState => false,
+            Type::Decimal => false,
+            Type::CompositeQuantity(_) => false,
         }
     }
 
@@ -131,7 +139,10 @@ impl Type {
             | Type::Age
             | Type::Set(_)
             | Type::Map(_, _)
-            | Type::Json => None,
+            | Type::Json
+            | Type::TriState
+            | Type::Decimal
+            | Type::CompositeQuantity(_) => None,
         }
     }
 
@@ -152,6 +163,11 @@ impl Type {
                     Type::Integer | Type::Float,
                 ) => true,
                 (Type::Float, Type::Integer) => true,
+                (Type::TriState, Type::Boolean) => true,
+                (Type::Decimal, Type::Integer) => true,
+                (Type::CompositeQuantity(d), Type::Quantity(e)) => {
+                    CompositeDimension::from_dimension(*e) == *d
+                }
                 (Type::Option(s), Type::Option(t)) => {
                     t.castable_to_opts(s, opts)
                 }
@@ -208,6 +224,11 @@ impl Type {
                 decode(value)?,
             )?)),
             Type::Boolean => Ok(Value::Boolean(decode(value)?)),
+            Type::TriState => Ok(Value::TriState(decode(value)?)),
+            Type::Decimal => Ok(Value::Decimal(decode(value)?)),
+            Type::CompositeQuantity(dim) => Ok(Value::CompositeQuantity(
+                CompositeQuantity(decode(value)?, dim.reference_unit()),
+            )),
             Type::Time => {
                 let s: String = decode(value)?;
                 Ok(Value::Time(
@@ -222,39 +243,15 @@ impl Type {
                     seconds * 1000.0,
                 ) as i64)))
             }
-            Type::MacAddress => {
-                let s: String = decode(value)?;
-                Ok(Value::MacAddress(
-                    s.split(':')
-                        .map(|n| u8::from_str_radix(n, 16))
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|_| {
-                            DataError::InvalidMacAddress(s.to_string())
-                        })?
-                        .try_into()
-                        .map_err(|_| {
-                            DataError::InvalidMacAddress(s.to_string())
-                        })?,
-                ))
-            }
-            Type::Ipv4Address => {
-                let s: String = decode(value)?;
-                Ok(Value::Ipv4Address(
-                    std::net::Ipv4Addr::from_str(&s)
-                        .map_err(|_| DataError::InvalidIpv4Address(s))?
-                        .octets(),
-                ))
-            }
-            Type::Ipv6Address => {
-                let s: String = decode(value)?;
-                Ok(Value::Ipv6Address(
-                    std::net::Ipv6Addr::from_str(&s)
-                        .map_err(|_| {
-                            DataError::InvalidIpv6Address(s.to_string())
-                        })?
-                        .segments(),
-                ))
-            }
+            Type::MacAddress => Ok(Value::MacAddress(parse_mac_address(
+                &decode::<String>(value)?,
+            )?)),
+            Type::Ipv4Address => Ok(Value::Ipv4Address(parse_ipv4_address(
+                &decode::<String>(value)?,
+            )?)),
+            Type::Ipv6Address => Ok(Value::Ipv6Address(parse_ipv6_address(
+                &decode::<String>(value)?,
+            )?)),
             Type::Option(typ) => Ok(Value::Option(OptionValue::new_unchecked(
                 typ.clone(),
                 match value {
@@ -354,6 +351,9 @@ impl Type {
                 })
                 .into(),
             Type::Json => JsonSchema::new().into(),
+            Type::TriState => OptionSchema::new(BoolSchema::new()).into(),
+            Type::Decimal => StringSchema::new().into(),
+            Type::CompositeQuantity(_) => DoubleSchema::new().into(),
         }
     }
 }
@@ -388,6 +388,11 @@ impl Display for Type {
                     .join(",")
             ),
             Type::Json => write!(f, "json"),
+            Type::TriState => write!(f, "tristate"),
+            Type::Decimal => write!(f, "decimal"),
+            Type::CompositeQuantity(d) => {
+                write!(f, "composite_quantity({d})")
+            }
         }
     }
 }
@@ -11,15 +11,20 @@ use std::sync::Arc;
 use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use unit::{Dimension, Quantity, Unit};
+use unit::{CompositeDimension, CompositeQuantity, Dimension, Quantity, Unit};
 
+use crate::addr::{DisplayIpv4, DisplayIpv6, DisplayMac};
+use crate::decimal::Decimal;
 use crate::format::Format;
 use crate::hashable::HashableType;
 use crate::pyrepr::PyRepr;
 
 use super::error::{Data, DataError};
 use super::hashable::HashableValue;
-use super::options::{FormatOpts, TypeOpts};
+use super::options::{
+    EnumRepr, Epsilon, FormatOpts, TypeOpts, ENUM_CODE_SUFFIX,
+};
+use super::tristate::TriState;
 use super::types::Type;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -54,6 +59,14 @@ pub enum Value {
     Set(SetValue),
     Map(MapValue),
     Json(serde_json::Value),
+    /* Appended, so that existing content hashes remain stable. */
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<bool>"))]
+    TriState(TriState),
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    Decimal(Decimal),
+    #[serde(rename = "composite-quantity")]
+    #[cfg_attr(feature = "schemars", schemars(with = "(f64, String)"))]
+    CompositeQuantity(CompositeQuantity),
 }
 
 #[derive(
@@ -125,6 +138,11 @@ impl Value {
                 Type::Tuple(ts.iter().map(|t| t.get_type()).collect())
             }
             Value::Json(_) => Type::Json,
+            Value::TriState(_) => Type::TriState,
+            Value::Decimal(_) => Type::Decimal,
+            Value::CompositeQuantity(q) => {
+                Type::CompositeQuantity(q.dimension())
+            }
         }
     }
 
@@ -132,6 +150,7 @@ impl Value {
         match self {
             Value::Integer(v) => Some(v as f64),
             Value::Float(v) => Some(v),
+            Value::Decimal(v) => Some(v.to_f64()),
             _ => None,
         }
     }
@@ -144,6 +163,10 @@ impl Value {
                 Value::Quantity(Quantity(a, ua)),
                 Value::Quantity(Quantity(b, ub)),
             ) => ua == ub && literal_float_eq(*a, *b),
+            (
+                Value::CompositeQuantity(CompositeQuantity(a, ua)),
+                Value::CompositeQuantity(CompositeQuantity(b, ub)),
+            ) => ua == ub && literal_float_eq(*a, *b),
             (
                 Value::Option(OptionValue(s, a)),
                 Value::Option(OptionValue(t, b)),
@@ -164,6 +187,61 @@ impl Value {
         }
     }
 
+    /// Equality within a tolerance for floating point values. Integers,
+    /// decimals and floats compare numerically and quantities are
+    /// converted to the same unit first. Other values must be equal.
+    pub fn approx_eq(&self, rhs: &Self, epsilon: Epsilon) -> bool {
+        match (self, rhs) {
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (
+                Value::Integer(_) | Value::Float(_) | Value::Decimal(_),
+                Value::Integer(_) | Value::Float(_) | Value::Decimal(_),
+            ) => match (self.clone().as_float(), rhs.clone().as_float()) {
+                (Some(a), Some(b)) => epsilon.within(a, b),
+                _ => false,
+            },
+            (Value::Quantity(a), Value::Quantity(b)) => match b.convert(&a.1) {
+                Ok(b) => epsilon.within(a.0, b.0),
+                Err(_) => false,
+            },
+            (Value::Quantity(_), Value::Integer(_) | Value::Float(_)) => {
+                match rhs.clone().as_float() {
+                    Some(b) => self.approx_eq(
+                        &Value::Quantity(Quantity::from_value(b)),
+                        epsilon,
+                    ),
+                    None => false,
+                }
+            }
+            (Value::Integer(_) | Value::Float(_), Value::Quantity(_)) => {
+                rhs.approx_eq(self, epsilon)
+            }
+            (Value::CompositeQuantity(a), Value::CompositeQuantity(b)) => {
+                match b.clone().convert(&a.1) {
+                    Ok(b) => epsilon.within(a.0, b.0),
+                    Err(_) => false,
+                }
+            }
+            (
+                Value::Option(OptionValue(_, a)),
+                Value::Option(OptionValue(_, b)),
+            ) => match (a, b) {
+                (Some(a), Some(b)) => a.approx_eq(b, epsilon),
+                (None, None) => true,
+                _ => false,
+            },
+            (Value::Tuple(a), Value::Tuple(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (Value::List(ListValue(_, a)), Value::List(ListValue(_, b))) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, epsilon))
+            }
+            (a, b) => a == b,
+        }
+    }
+
     pub fn cast_to(self, target: &Type) -> Data {
         self.cast_to_opts(target, &TypeOpts::default())
     }
@@ -206,6 +284,18 @@ impl Value {
                     Ok(Value::Quantity(Quantity::from_value(v)))
                 }
                 (Type::Float, Value::Integer(v)) => Ok(Value::Float(v as f64)),
+                (Type::TriState, Value::Boolean(v)) => {
+                    Ok(Value::TriState(TriState::from(v)))
+                }
+                (Type::Decimal, Value::Integer(v)) => {
+                    Ok(Value::Decimal(Decimal::from(v)))
+                }
+                (Type::CompositeQuantity(d), Value::Quantity(q))
+                    if CompositeDimension::from_dimension(q.dimension())
+                        == *d =>
+                {
+                    Ok(Value::CompositeQuantity(CompositeQuantity::from(q)))
+                }
                 (Type::Option(t), Value::Option(OptionValue(_, v))) => {
                     match v {
                         Some(v) => Ok(Value::Option(OptionValue(
@@ -301,6 +391,46 @@ impl Value {
     pub fn to_json_value_unit(
         &self,
         display_unit: Option<Unit>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.to_json_value_repr(display_unit, EnumRepr::Label)
+    }
+
+    /// Serialize to one or more named JSON fields. Integer enums are
+    /// represented according to `repr`; with `EnumRepr::Both`, the
+    /// label is written to `name` and the code to a separate field
+    /// (`name` + `ENUM_CODE_SUFFIX`).
+    pub fn to_json_fields(
+        &self,
+        name: &str,
+        display_unit: Option<Unit>,
+        repr: EnumRepr,
+    ) -> std::result::Result<Vec<(String, serde_json::Value)>, String> {
+        let value = self.to_json_value_repr(display_unit, repr)?;
+        Ok(match (repr, self.enum_code()) {
+            (EnumRepr::Both, Some(code)) => vec![
+                (name.to_string(), value),
+                (format!("{name}{ENUM_CODE_SUFFIX}"), serde_json::json!(code)),
+            ],
+            _ => vec![(name.to_string(), value)],
+        })
+    }
+
+    /// The integer code of an (optional) integer enum value.
+    fn enum_code(&self) -> Option<i64> {
+        match self {
+            Value::IntEnum(v) => Some(v.get_value_int()),
+            Value::Option(OptionValue(_, Some(v))) => v.enum_code(),
+            _ => None,
+        }
+    }
+
+    /// Serialize to JSON, with integer enums represented according
+    /// to `repr`. Nested values only support a single representation:
+    /// `EnumRepr::Both` is written as the label.
+    pub fn to_json_value_repr(
+        &self,
+        display_unit: Option<Unit>,
+        repr: EnumRepr,
     ) -> std::result::Result<serde_json::Value, String> {
         Ok(match self {
             Value::BinaryString(v) => serde_json::Value::Array(
@@ -337,10 +467,25 @@ impl Value {
             Value::Enum(EnumValue(_, v)) => {
                 serde_json::Value::String(v.to_string())
             }
+            Value::IntEnum(IntEnumValue(_, v)) if repr == EnumRepr::Code => {
+                serde_json::json!(v)
+            }
             Value::IntEnum(IntEnumValue(cs, v)) => serde_json::json!(cs
                 .get(v)
                 .ok_or_else(|| format!("invalid choice: {}", v))?),
             Value::Boolean(v) => serde_json::Value::Bool(*v),
+            Value::TriState(v) => match v.to_bool() {
+                Some(v) => serde_json::Value::Bool(v),
+                None => serde_json::Value::Null,
+            },
+            /* A string, since JSON numbers are usually read as floats. */
+            Value::Decimal(v) => serde_json::Value::String(v.to_string()),
+            Value::CompositeQuantity(v) => serde_json::Value::Number(
+                serde_json::Number::from_f64(
+                    v.clone().normalize().map_err(|e| e.to_string())?.0,
+                )
+                .ok_or_else(|| "NaN".to_string())?,
+            ),
             Value::Time(v) => serde_json::Value::String(
                 v.to_rfc3339_opts(SecondsFormat::AutoSi, true),
             ),
@@ -350,39 +495,34 @@ impl Value {
                 )
                 .ok_or_else(|| "invalid age value".to_string())?,
             ),
-            Value::MacAddress(v) => serde_json::Value::String(
-                v.iter()
-                    .map(|i| format!("{:02x}", i))
-                    .collect::<Vec<_>>()
-                    .join(":"),
-            ),
-            Value::Ipv4Address(v) => serde_json::Value::String(
-                v.iter()
-                    .map(|i| format!("{}", i))
-                    .collect::<Vec<_>>()
-                    .join("."),
-            ),
-            Value::Ipv6Address(v) => serde_json::Value::String(
-                v.iter()
-                    .map(|i| format!("{:x}", i))
-                    .collect::<Vec<_>>()
-                    .join(":"),
-            ),
+            Value::MacAddress(v) => {
+                serde_json::Value::String(DisplayMac(v).to_string())
+            }
+            Value::Ipv4Address(v) => {
+                serde_json::Value::String(DisplayIpv4(v).to_string())
+            }
+            Value::Ipv6Address(v) => {
+                serde_json::Value::String(DisplayIpv6(v).to_string())
+            }
             Value::Option(OptionValue(_, v)) => match v {
-                Some(v) => v.to_json_value_unit(display_unit)?,
+                Some(v) => v.to_json_value_repr(display_unit, repr)?,
                 None => serde_json::Value::Null,
             },
             Value::Result(ResultValue(_, _, v)) => match v {
                 Ok(v) => {
-                    serde_json::json!({"ok": v.to_json_value_unit(display_unit)?})
+                    serde_json::json!({
+                        "ok": v.to_json_value_repr(display_unit, repr)?
+                    })
                 }
                 Err(e) => {
-                    serde_json::json!({"err": e.to_json_value_unit(display_unit)?})
+                    serde_json::json!({
+                        "err": e.to_json_value_repr(display_unit, repr)?
+                    })
                 }
             },
             Value::List(ListValue(_, vs)) => serde_json::Value::Array(
                 vs.iter()
-                    .map(|v| v.to_json_value_unit(display_unit))
+                    .map(|v| v.to_json_value_repr(display_unit, repr))
                     .collect::<Result<_, String>>()?,
             ),
             Value::Set(SetValue(_, vs)) => serde_json::Value::Array(
@@ -395,7 +535,7 @@ impl Value {
                     .map(|(k, v)| {
                         Ok((
                             k.to_json_key()?,
-                            v.to_json_value_unit(display_unit)?,
+                            v.to_json_value_repr(display_unit, repr)?,
                         ))
                     })
                     .collect::<Result<_, String>>()?,
@@ -588,6 +728,10 @@ impl ListValue {
     pub fn get_values(&self) -> &Vec<Value> {
         &self.1
     }
+
+    pub fn get_element_type(&self) -> &Type {
+        &self.0
+    }
 }
 
 impl SetValue {
@@ -673,19 +817,14 @@ impl Display for Value {
                 write!(f, "{}", v.get_value_str())
             }
             Value::Boolean(v) => write!(f, "{v}"),
-            Value::MacAddress(v) => write!(
-                f,
-                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                v[0], v[1], v[2], v[3], v[4], v[5]
-            ),
+            Value::TriState(v) => write!(f, "{v}"),
+            Value::Decimal(v) => write!(f, "{v}"),
+            Value::CompositeQuantity(v) => write!(f, "{v}"),
+            Value::MacAddress(v) => write!(f, "{}", DisplayMac(v)),
             Value::Ipv4Address(v) => {
-                write!(f, "{}.{}.{}.{}", v[0], v[1], v[2], v[3])
+                write!(f, "{}", DisplayIpv4(v))
             }
-            Value::Ipv6Address(v) => write!(
-                f,
-                "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-                v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]
-            ),
+            Value::Ipv6Address(v) => write!(f, "{}", DisplayIpv6(v)),
             Value::Option(v) => match v.get_value() {
                 None => write!(f, "None"),
                 Some(v) => write!(f, "{v}"),
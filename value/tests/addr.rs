@@ -0,0 +1,129 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use serde_json::json;
+use value::{
+    parse_ipv4_address, parse_ipv6_address, parse_mac_address, DataError,
+    HashableValue, Type, Value,
+};
+
+const MAC: [u8; 6] = [0x00, 0x1b, 0x2c, 0x3d, 0x4e, 0x5f];
+
+#[test]
+fn parse_mac_notations() {
+    for s in [
+        "00:1b:2c:3d:4e:5f",
+        "00:1B:2C:3D:4E:5F",
+        "0:1b:2c:3d:4e:5f",
+        "00-1b-2c-3d-4e-5f",
+        "001b.2c3d.4e5f",
+        "001B2C3D4E5F",
+        " 00:1b:2c:3d:4e:5f\n",
+    ] {
+        assert_eq!(parse_mac_address(s), Ok(MAC), "{s:?}");
+    }
+}
+
+#[test]
+fn reject_invalid_mac() {
+    for s in [
+        "",
+        "00:1b:2c:3d:4e",
+        "00:1b:2c:3d:4e:5f:60",
+        "00:1b:2c:3d:4e:5g",
+        "00:1b:2c:3d:4e:+f",
+        "000:1b:2c:3d:4e:5f",
+        "00:1b-2c:3d:4e:5f",
+        "001b.2c3d.4e5",
+        "001b2c3d4e5",
+    ] {
+        assert_eq!(
+            parse_mac_address(s),
+            Err(DataError::InvalidMacAddress(s.to_string())),
+            "{s:?}"
+        );
+    }
+}
+
+#[test]
+fn parse_ipv4() {
+    assert_eq!(parse_ipv4_address("10.0.0.1"), Ok([10, 0, 0, 1]));
+    assert_eq!(parse_ipv4_address("010.000.000.001"), Ok([10, 0, 0, 1]));
+    assert_eq!(parse_ipv4_address("255.255.255.255"), Ok([255; 4]));
+    for s in [
+        "",
+        "10.0.0",
+        "10.0.0.1.2",
+        "10.0.0.256",
+        "10..0.1",
+        "a.b.c.d",
+    ] {
+        assert_eq!(
+            parse_ipv4_address(s),
+            Err(DataError::InvalidIpv4Address(s.to_string())),
+            "{s:?}"
+        );
+    }
+}
+
+#[test]
+fn parse_ipv6() {
+    let addr = [0x2001, 0xdb8, 0, 0, 0, 0, 0, 1];
+    assert_eq!(parse_ipv6_address("2001:db8::1"), Ok(addr));
+    assert_eq!(parse_ipv6_address("2001:DB8:0:0:0:0:0:1"), Ok(addr));
+    assert_eq!(parse_ipv6_address("2001:0db8:0000::0001"), Ok(addr));
+    assert!(parse_ipv6_address("2001:db8::1::2").is_err());
+    assert!(parse_ipv6_address("10.0.0.1").is_err());
+}
+
+#[test]
+fn canonical_format() {
+    assert_eq!(Value::MacAddress(MAC).to_string(), "00:1b:2c:3d:4e:5f");
+    assert_eq!(Value::Ipv4Address([10, 0, 0, 1]).to_string(), "10.0.0.1");
+    assert_eq!(
+        Value::Ipv6Address([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]).to_string(),
+        "2001:db8::1"
+    );
+    assert_eq!(
+        HashableValue::Ipv4Address([10, 0, 0, 1]).to_string(),
+        "10.0.0.1"
+    );
+    assert_eq!(
+        Value::MacAddress(MAC).py_repr().to_string(),
+        "'00:1b:2c:3d:4e:5f'"
+    );
+}
+
+#[test]
+fn json_normalizes() {
+    assert_eq!(
+        Type::MacAddress.value_from_json(json!("00-1B-2C-3D-4E-5F")),
+        Ok(Value::MacAddress(MAC))
+    );
+    assert_eq!(
+        Type::Ipv4Address.value_from_json(json!("010.000.000.001")),
+        Ok(Value::Ipv4Address([10, 0, 0, 1]))
+    );
+    assert_eq!(
+        Type::Ipv6Address
+            .value_from_json(json!("2001:0DB8::0001"))
+            .unwrap()
+            .to_json_value(),
+        Some(json!("2001:db8::1"))
+    );
+    assert_eq!(
+        Type::MacAddress.value_from_json(json!("not a mac")),
+        Err(DataError::InvalidMacAddress(String::from("not a mac")))
+    );
+}
+
+#[test]
+fn differently_formatted_addresses_join() {
+    let snmp = Value::MacAddress(MAC);
+    let api = Type::MacAddress
+        .value_from_json(json!("001b.2c3d.4e5f"))
+        .unwrap();
+    assert_eq!(snmp, api);
+    assert_eq!(snmp.content_hash(), api.content_hash());
+}
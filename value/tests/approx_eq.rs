@@ -0,0 +1,62 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use unit::{BinPrefix, InformationUnit, Quantity, Unit};
+use value::{Epsilon, Value};
+
+const BYTE: Unit = Unit::Information(InformationUnit::Byte(BinPrefix::Unit));
+const KIB: Unit = Unit::Information(InformationUnit::Byte(BinPrefix::Kilo));
+
+#[test]
+fn absolute() {
+    let eps = Epsilon::Absolute(1e-6);
+    assert!(Value::Float(0.1 + 0.2).approx_eq(&Value::Float(0.3), eps));
+    assert!(Value::Float(1e6 + 1e-7).approx_eq(&Value::Float(1e6), eps));
+    assert!(Value::Float(3.0).approx_eq(&Value::Integer(3), eps));
+    assert!(!Value::Float(0.3).approx_eq(&Value::Float(0.31), eps));
+    assert!(!Value::Float(1e6 + 1.0).approx_eq(&Value::Float(1e6), eps));
+}
+
+#[test]
+fn relative() {
+    let eps = Epsilon::Relative(1e-3);
+    assert!(Value::Float(1e6 + 1.0).approx_eq(&Value::Float(1e6), eps));
+    assert!(Value::Float(0.1 + 0.2).approx_eq(&Value::Float(0.3), eps));
+    assert!(!Value::Float(1e-6).approx_eq(&Value::Float(2e-6), eps));
+    assert!(!Value::Float(100.0).approx_eq(&Value::Float(101.0), eps));
+    assert!(!Value::Float(0.0).approx_eq(&Value::Float(1e-12), eps));
+}
+
+#[test]
+fn special_values() {
+    let eps = Epsilon::Absolute(1e-6);
+    assert!(Value::Float(f64::INFINITY)
+        .approx_eq(&Value::Float(f64::INFINITY), eps));
+    assert!(!Value::Float(f64::NAN).approx_eq(&Value::Float(f64::NAN), eps));
+}
+
+#[test]
+fn quantities() {
+    let eps = Epsilon::Relative(1e-9);
+    assert!(Value::Quantity(Quantity(1.0, KIB))
+        .approx_eq(&Value::Quantity(Quantity(1024.0, BYTE)), eps));
+    assert!(!Value::Quantity(Quantity(1.0, KIB))
+        .approx_eq(&Value::Quantity(Quantity(1000.0, BYTE)), eps));
+}
+
+#[test]
+fn non_numeric() {
+    let eps = Epsilon::default();
+    assert!(Value::UnicodeString("a".to_string())
+        .approx_eq(&Value::UnicodeString("a".to_string()), eps));
+    assert!(!Value::UnicodeString("1".to_string())
+        .approx_eq(&Value::Integer(1), eps));
+    assert!(
+        Value::Tuple(vec![Value::Float(0.1 + 0.2), Value::Integer(1)])
+            .approx_eq(
+                &Value::Tuple(vec![Value::Float(0.3), Value::Integer(1)]),
+                eps
+            )
+    );
+}
@@ -0,0 +1,71 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use unit::{
+    CompositeDimension, CompositeQuantity, CompositeUnit, Dimension, PowerUnit,
+    Quantity, SiPrefix, Unit,
+};
+use value::{Type, Value};
+
+fn irradiance() -> Type {
+    Type::CompositeQuantity(CompositeDimension::from_map(
+        [(Dimension::Power, 1), (Dimension::Length, -2)].into(),
+    ))
+}
+
+#[test]
+fn composite_type_serialization() {
+    let typ = irradiance();
+    let json = serde_json::to_value(&typ).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"composite-quantity": {"Length": -2, "Power": 1}})
+    );
+    assert_eq!(serde_json::from_value::<Type>(json).unwrap(), typ);
+}
+
+#[test]
+fn composite_value_from_json() {
+    let value = irradiance()
+        .value_from_json(serde_json::json!(800.0))
+        .unwrap();
+    assert_eq!(
+        value,
+        Value::CompositeQuantity(CompositeQuantity(
+            800.0,
+            CompositeUnit::parse("W/m^2").unwrap()
+        ))
+    );
+    assert_eq!(value.get_type(), irradiance());
+    assert_eq!(value.to_string(), "800 W/m^2");
+}
+
+#[test]
+fn composite_value_to_json() {
+    let value = Value::CompositeQuantity(CompositeQuantity(
+        0.5,
+        CompositeUnit::parse("kW/m^2").unwrap(),
+    ));
+    assert_eq!(value.to_json_value(), Some(serde_json::json!(500.0)));
+}
+
+#[test]
+fn cast_quantity_to_composite() {
+    let kw = Unit::Power(PowerUnit::Watt(SiPrefix::Kilo));
+    let typ = Type::CompositeQuantity(CompositeDimension::from_dimension(
+        Dimension::Power,
+    ));
+    assert!(Type::Quantity(Dimension::Power).castable_to(&typ));
+    assert_eq!(
+        Value::Quantity(Quantity(2.0, kw)).cast_to(&typ).unwrap(),
+        Value::CompositeQuantity(CompositeQuantity(
+            2.0,
+            CompositeUnit::from(kw)
+        ))
+    );
+    assert!(!Type::Quantity(Dimension::Time).castable_to(&typ));
+    assert!(Value::Quantity(Quantity::from_value(1.0))
+        .cast_to(&typ)
+        .is_err());
+}
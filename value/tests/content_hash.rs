@@ -0,0 +1,126 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use unit::{Quantity, Unit};
+use value::hashable::HashableType;
+use value::value::MapValue;
+use value::{HashableValue, SetValue, Type, Value};
+
+fn set(vals: impl IntoIterator<Item = i64>) -> Value {
+    Value::Set(
+        SetValue::new(
+            Arc::new(HashableType::Integer),
+            vals.into_iter().map(HashableValue::Integer).collect(),
+        )
+        .unwrap(),
+    )
+}
+
+fn map(vals: impl IntoIterator<Item = (String, i64)>) -> Value {
+    Value::Map(
+        MapValue::new(
+            Arc::new(HashableType::UnicodeString),
+            Arc::new(Type::Integer),
+            vals.into_iter()
+                .map(|(k, v)| {
+                    (HashableValue::UnicodeString(k), Value::Integer(v))
+                })
+                .collect(),
+        )
+        .unwrap(),
+    )
+}
+
+#[test]
+fn content_hash_is_stable() {
+    /* FNV-1a is fixed; a changed digest invalidates persisted caches. */
+    assert_eq!(
+        HashableValue::Integer(42).content_hash(),
+        0x21fdd47119083f4f
+    );
+    /* Variant tags are fixed, so adding variants to `Value` must
+     * not change the digest of existing ones. */
+    assert_eq!(Value::Integer(42).content_hash(), 0x21fdd47119083f4f);
+    /* Likewise for set elements, map keys and units, which must not
+     * depend on the declaration order of `HashableValue` or `Unit`. */
+    assert_eq!(set([1, 2, 3]).content_hash(), 0x76db86d79292c527);
+    assert_eq!(
+        map([("a".to_string(), 1)]).content_hash(),
+        0x3f1abcd20dafda76
+    );
+    assert_eq!(
+        Value::Quantity(Quantity(1.5, Unit::parse("ms").unwrap()))
+            .content_hash(),
+        0x1786720f2c7af3cf
+    );
+    assert_eq!(
+        HashableValue::MacAddress([0, 1, 2, 3, 4, 5]).content_hash(),
+        Value::MacAddress([0, 1, 2, 3, 4, 5]).content_hash()
+    );
+    assert_eq!(
+        Value::UnicodeString("abc".to_string()).content_hash(),
+        Value::UnicodeString(String::from("abc")).content_hash()
+    );
+}
+
+#[test]
+fn content_hash_ignores_construction_order() {
+    assert_eq!(
+        set([1, 2, 3, 4, 5]).content_hash(),
+        set([5, 4, 3, 2, 1]).content_hash()
+    );
+    assert_eq!(
+        set((0..1000).rev()).content_hash(),
+        set(0..1000).content_hash()
+    );
+
+    assert_eq!(
+        map((0..100).map(|i| (i.to_string(), i * 2))).content_hash(),
+        map((0..100).rev().map(|i| (i.to_string(), i * 2))).content_hash()
+    );
+
+    assert_eq!(
+        Value::Json(serde_json::json!({"a": 1, "b": [1, 2]})).content_hash(),
+        Value::Json(serde_json::json!({"b": [1, 2], "a": 1})).content_hash()
+    );
+}
+
+#[test]
+fn content_hash_distinguishes_values() {
+    assert_ne!(set([1, 2, 3]).content_hash(), set([1, 2, 4]).content_hash());
+    assert_ne!(
+        map([("a".to_string(), 1), ("b".to_string(), 2)]).content_hash(),
+        map([("a".to_string(), 2), ("b".to_string(), 1)]).content_hash()
+    );
+    assert_ne!(
+        Value::Integer(1).content_hash(),
+        Value::Float(1.0).content_hash()
+    );
+    assert_ne!(
+        Value::UnicodeString("1".to_string()).content_hash(),
+        Value::BinaryString(b"1".to_vec()).content_hash()
+    );
+    assert_ne!(
+        Value::Tuple(vec![Value::Integer(1), Value::Integer(2)]).content_hash(),
+        Value::Tuple(vec![Value::Integer(2), Value::Integer(1)]).content_hash()
+    );
+    assert_ne!(
+        HashableValue::Integer(1).content_hash(),
+        HashableValue::Integer(2).content_hash()
+    );
+}
+
+#[test]
+fn content_hash_floats() {
+    assert_eq!(
+        Value::Float(0.0).content_hash(),
+        Value::Float(-0.0).content_hash()
+    );
+    assert_ne!(
+        Value::Float(0.1).content_hash(),
+        Value::Float(0.2).content_hash()
+    );
+}
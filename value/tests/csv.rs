@@ -0,0 +1,84 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use value::{to_csv, to_csv_with_columns, Data, DataError, FormatOpts, Value};
+
+fn row(fields: &[(&'static str, Data)]) -> HashMap<&'static str, Data> {
+    fields.iter().cloned().collect()
+}
+
+fn string(s: &str) -> Data {
+    Ok(Value::UnicodeString(s.to_string()))
+}
+
+#[test]
+fn header_from_field_names() {
+    let rows = vec![
+        row(&[("name", string("eth0")), ("mtu", Ok(Value::Integer(1500)))]),
+        row(&[("name", string("eth1")), ("speed", Ok(Value::Integer(10)))]),
+    ];
+    assert_eq!(
+        to_csv(&rows, &FormatOpts::default()),
+        "mtu,name,speed\r\n1500,eth0,\r\n,eth1,10\r\n"
+    );
+}
+
+#[test]
+fn explicit_columns() {
+    let rows = vec![row(&[
+        ("name", string("eth0")),
+        ("mtu", Ok(Value::Integer(1500))),
+    ])];
+    assert_eq!(
+        to_csv_with_columns(&["name", "mtu"], &rows, &FormatOpts::default()),
+        "name,mtu\r\neth0,1500\r\n"
+    );
+}
+
+#[test]
+fn empty_table() {
+    let rows: Vec<HashMap<&str, Data>> = Vec::new();
+    assert_eq!(to_csv(&rows, &FormatOpts::default()), "\r\n");
+}
+
+#[test]
+fn escaping() {
+    let rows = vec![
+        row(&[("descr", string("a, b"))]),
+        row(&[("descr", string("say \"hi\""))]),
+        row(&[("descr", string("two\nlines"))]),
+        row(&[("descr", string("plain"))]),
+    ];
+    assert_eq!(
+        to_csv(&rows, &FormatOpts::default()),
+        "descr\r\n\"a, b\"\r\n\"say \"\"hi\"\"\"\r\n\"two\nlines\"\r\nplain\r\n"
+    );
+}
+
+#[test]
+fn escaped_header() {
+    let rows = vec![row(&[("a,b", Ok(Value::Integer(1)))])];
+    assert_eq!(to_csv(&rows, &FormatOpts::default()), "\"a,b\"\r\n1\r\n");
+}
+
+#[test]
+fn missing_values_are_empty() {
+    let rows = vec![row(&[
+        ("a", Err(DataError::Missing)),
+        ("b", Ok(Value::Integer(2))),
+    ])];
+    assert_eq!(to_csv(&rows, &FormatOpts::default()), "a,b\r\n,2\r\n");
+}
+
+#[test]
+fn format_options() {
+    let rows = vec![row(&[("load", Ok(Value::Float(0.12345)))])];
+    let opts = FormatOpts {
+        precision: Some(2),
+        ..FormatOpts::default()
+    };
+    assert_eq!(to_csv(&rows, &opts), "load\r\n0.12\r\n");
+}
@@ -0,0 +1,95 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use value::{DataError, Decimal, FormatOpts, NumericValuePair, Type, Value};
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn exact_addition() {
+    /* 0.1 + 0.2 != 0.3 in floating point. */
+    let sum = (dec("0.1") + dec("0.2")).unwrap();
+    assert_eq!(sum, dec("0.3"));
+    assert_eq!(sum.to_string(), "0.3");
+
+    /* Scales are aligned to the larger one. */
+    assert_eq!((dec("1.50") + dec("2.005")).unwrap().to_string(), "3.505");
+    assert_eq!((dec("1.50") - dec("2")).unwrap().to_string(), "-0.50");
+    assert_eq!((dec("1.5") * dec("-0.25")).unwrap().to_string(), "-0.375");
+}
+
+#[test]
+fn overflow() {
+    let max = Decimal::new(i128::MAX, 0);
+    assert!(matches!(max + dec("1"), Err(DataError::DecimalOverflow)));
+}
+
+#[test]
+fn format() {
+    assert_eq!(dec("12.50").to_string(), "12.50");
+    assert_eq!(dec("-0.05").to_string(), "-0.05");
+    assert_eq!(dec("1.25e3").to_string(), "1250");
+    assert_eq!(format!("{:.1}", dec("0.25")), "0.3");
+    assert_eq!(format!("{:.1}", dec("-0.25")), "-0.3");
+    assert_eq!(format!("{:.3}", dec("0.25")), "0.250");
+    assert_eq!(
+        Value::Decimal(dec("2.675"))
+            .format(&FormatOpts {
+                precision: Some(2),
+                ..FormatOpts::default()
+            })
+            .unwrap(),
+        "2.68"
+    );
+    assert_eq!(
+        Value::Decimal(dec("2.675")).py_repr().to_string(),
+        "Decimal('2.675')"
+    );
+}
+
+#[test]
+fn parse() {
+    assert_eq!(dec("+1.5"), dec("1.50"));
+    assert!("1.2.3".parse::<Decimal>().is_err());
+    assert!("".parse::<Decimal>().is_err());
+    assert!("1e".parse::<Decimal>().is_err());
+}
+
+#[test]
+fn json() {
+    let value = Value::Decimal(dec("0.10"));
+    assert_eq!(value.to_json_value(), Some(serde_json::json!("0.10")));
+    assert_eq!(
+        Type::Decimal.value_from_json(serde_json::json!("0.10")),
+        Ok(value.clone())
+    );
+    assert_eq!(
+        Type::Decimal.value_from_json(serde_json::json!(0.1)),
+        Ok(value)
+    );
+}
+
+#[test]
+fn compare() {
+    assert_eq!(dec("1.5"), dec("1.500"));
+    assert!(dec("1.49") < dec("1.5"));
+    assert_eq!(
+        Value::Decimal(dec("1.5")).content_hash(),
+        Value::Decimal(dec("1.50")).content_hash()
+    );
+}
+
+#[test]
+fn promotion() {
+    assert_eq!(
+        NumericValuePair::from(Value::Decimal(dec("0.5")), Value::Integer(2)),
+        Some(NumericValuePair::Decimal(dec("0.5"), dec("2")))
+    );
+    assert_eq!(
+        NumericValuePair::from(Value::Decimal(dec("0.5")), Value::Float(2.0)),
+        Some(NumericValuePair::Float(0.5, 2.0))
+    );
+}
@@ -0,0 +1,99 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde_json::json;
+use value::{EnumRepr, FormatOpts, IntEnumValue, OptionValue, Type, Value};
+
+fn choices() -> Arc<BTreeMap<i64, String>> {
+    Arc::new(BTreeMap::from_iter([
+        (1, "up".to_string()),
+        (2, "down".to_string()),
+    ]))
+}
+
+fn oper_status(code: i64) -> Value {
+    Value::IntEnum(IntEnumValue::new(choices(), code).unwrap())
+}
+
+fn format(value: &Value, enum_repr: EnumRepr) -> String {
+    value
+        .format(&FormatOpts {
+            enum_repr,
+            ..FormatOpts::default()
+        })
+        .unwrap()
+}
+
+#[test]
+fn label_representation() {
+    assert_eq!(format(&oper_status(2), EnumRepr::Label), "down");
+    assert_eq!(
+        oper_status(2)
+            .to_json_fields("status", None, EnumRepr::Label)
+            .unwrap(),
+        vec![("status".to_string(), json!("down"))]
+    );
+    assert_eq!(oper_status(2).to_json_value(), Some(json!("down")));
+}
+
+#[test]
+fn code_representation() {
+    assert_eq!(format(&oper_status(2), EnumRepr::Code), "2");
+    assert_eq!(
+        oper_status(2)
+            .to_json_fields("status", None, EnumRepr::Code)
+            .unwrap(),
+        vec![("status".to_string(), json!(2))]
+    );
+}
+
+#[test]
+fn both_representations() {
+    assert_eq!(format(&oper_status(1), EnumRepr::Both), "up (1)");
+    assert_eq!(
+        oper_status(1)
+            .to_json_fields("status", None, EnumRepr::Both)
+            .unwrap(),
+        vec![
+            ("status".to_string(), json!("up")),
+            ("status_code".to_string(), json!(1)),
+        ]
+    );
+}
+
+#[test]
+fn optional_enum_and_other_values() {
+    let value = Value::Option(
+        OptionValue::new(
+            Arc::new(Type::IntEnum(choices())),
+            Some(oper_status(1)),
+        )
+        .unwrap(),
+    );
+    assert_eq!(
+        value
+            .to_json_fields("status", None, EnumRepr::Both)
+            .unwrap(),
+        vec![
+            ("status".to_string(), json!("up")),
+            ("status_code".to_string(), json!(1)),
+        ]
+    );
+    assert_eq!(
+        Value::Integer(5)
+            .to_json_fields("mtu", None, EnumRepr::Both)
+            .unwrap(),
+        vec![("mtu".to_string(), json!(5.0))]
+    );
+}
+
+#[test]
+fn deserialize_representation() {
+    let repr: EnumRepr = serde_json::from_value(json!("both")).unwrap();
+    assert_eq!(repr, EnumRepr::Both);
+    assert_eq!(EnumRepr::default(), EnumRepr::Label);
+}
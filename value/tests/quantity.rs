@@ -0,0 +1,81 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use chrono::Duration;
+use unit::{
+    BinPrefix, Dimension, FracPrefix, InformationUnit, Quantity, TimeUnit, Unit,
+};
+use value::{NumericTypePair, NumericValuePair, Type, Value};
+
+const BYTE: Unit = Unit::Information(InformationUnit::Byte(BinPrefix::Unit));
+const SECOND: Unit = Unit::Time(TimeUnit::Second(FracPrefix::Unit));
+
+fn divide(left: Value, right: Value) -> Quantity {
+    match NumericValuePair::from(left, right) {
+        Some(NumericValuePair::Quantity(l, r)) => (l / r).unwrap(),
+        pair => panic!("expected a quantity pair, got {pair:?}"),
+    }
+}
+
+#[test]
+fn divide_quantities() {
+    let q = divide(
+        Value::Quantity(Quantity(1000.0, BYTE)),
+        Value::Quantity(Quantity(10.0, SECOND)),
+    );
+    assert_eq!(q.dimension(), Dimension::Bandwidth);
+    assert_eq!(
+        q.convert(&Unit::Bandwidth(
+            InformationUnit::Byte(BinPrefix::Unit),
+            TimeUnit::Second(FracPrefix::Unit)
+        ))
+        .unwrap()
+        .0,
+        100.0
+    );
+    assert_eq!(
+        Value::Quantity(q).get_type(),
+        Type::Quantity(Dimension::Bandwidth)
+    );
+}
+
+#[test]
+fn divide_quantity_by_age() {
+    let q = divide(
+        Value::Quantity(Quantity(1000.0, BYTE)),
+        Value::Age(Duration::milliseconds(2500)),
+    );
+    assert_eq!(q.dimension(), Dimension::Bandwidth);
+    assert_eq!(
+        q.convert(&Unit::Bandwidth(
+            InformationUnit::Byte(BinPrefix::Unit),
+            TimeUnit::Second(FracPrefix::Unit)
+        ))
+        .unwrap()
+        .0,
+        400.0
+    );
+    assert_eq!(
+        NumericTypePair::from(
+            Type::Quantity(Dimension::Information),
+            Type::Age
+        ),
+        Some(NumericTypePair::Quantity(
+            Dimension::Information,
+            Dimension::Time
+        ))
+    );
+}
+
+#[test]
+fn ages_alone_are_not_numeric() {
+    assert_eq!(
+        NumericValuePair::from(
+            Value::Age(Duration::seconds(1)),
+            Value::Integer(2)
+        ),
+        None
+    );
+    assert_eq!(NumericTypePair::from(Type::Age, Type::Float), None);
+}
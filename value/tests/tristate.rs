@@ -0,0 +1,74 @@
+/******************************************************************************
+ * Copyright ContinuousC. Licensed under the "Elastic License 2.0".           *
+ ******************************************************************************/
+
+use value::{FormatOpts, TriState, Type, Value};
+
+#[test]
+fn format() {
+    assert_eq!(Value::TriState(TriState::True).to_string(), "true");
+    assert_eq!(Value::TriState(TriState::False).to_string(), "false");
+    assert_eq!(Value::TriState(TriState::Unknown).to_string(), "unknown");
+    assert_eq!(
+        Value::TriState(TriState::Unknown)
+            .format(&FormatOpts::default())
+            .unwrap(),
+        "unknown"
+    );
+    assert_eq!(
+        Value::TriState(TriState::Unknown).py_repr().to_string(),
+        "None"
+    );
+    assert_eq!("Unknown".parse::<TriState>().unwrap(), TriState::Unknown);
+    assert!("maybe".parse::<TriState>().is_err());
+}
+
+#[test]
+fn json() {
+    for (state, json) in [
+        (TriState::True, serde_json::json!(true)),
+        (TriState::False, serde_json::json!(false)),
+        (TriState::Unknown, serde_json::Value::Null),
+    ] {
+        assert_eq!(Value::TriState(state).to_json_value(), Some(json.clone()));
+        assert_eq!(
+            Type::TriState.value_from_json(json),
+            Ok(Value::TriState(state))
+        );
+    }
+}
+
+#[test]
+fn compare() {
+    assert_eq!(TriState::Unknown, TriState::Unknown);
+    assert_ne!(TriState::Unknown, TriState::False);
+    assert_ne!(
+        Value::TriState(TriState::False),
+        Value::TriState(TriState::Unknown)
+    );
+    assert_ne!(
+        Value::TriState(TriState::True).content_hash(),
+        Value::TriState(TriState::Unknown).content_hash()
+    );
+}
+
+#[test]
+fn kleene_logic() {
+    use TriState::{False, True, Unknown};
+    assert_eq!(Unknown.and(False), False);
+    assert_eq!(Unknown.and(True), Unknown);
+    assert_eq!(Unknown.or(True), True);
+    assert_eq!(Unknown.or(False), Unknown);
+    assert_eq!(Unknown.not(), Unknown);
+    assert_eq!(True.not(), False);
+}
+
+#[test]
+fn cast_from_boolean() {
+    assert!(Type::Boolean.castable_to(&Type::TriState));
+    assert!(!Type::TriState.castable_to(&Type::Boolean));
+    assert_eq!(
+        Value::Boolean(true).cast_to(&Type::TriState),
+        Ok(Value::TriState(TriState::True))
+    );
+}